@@ -216,6 +216,13 @@ pub fn do_router_based_random_reconfiguration_with_fail_magnitude(
             (ConfigExpr::BgpRouteMap { router, .. }, _, _) => {
                 router_modifiers.get_mut(router).unwrap().push(m.clone())
             }
+            (ConfigExpr::BgpNetwork { router, .. }, _, _)
+            | (ConfigExpr::BgpRedistribute { router, .. }, _, _)
+            | (ConfigExpr::BgpAggregate { router, .. }, _, _)
+            | (ConfigExpr::BgpDecision { router, .. }, _, _)
+            | (ConfigExpr::BgpClusterId { router, .. }, _, _) => {
+                router_modifiers.get_mut(router).unwrap().push(m.clone())
+            }
             (
                 ConfigExpr::BgpSession {
                     source,
@@ -264,6 +271,16 @@ pub fn do_router_based_random_reconfiguration_with_fail_magnitude(
             (ConfigExpr::AccessControl { .. }, _, _) => {
                 // TODO
             }
+            (ConfigExpr::IgpLinkArea { source, .. }, _, _) => {
+                router_modifiers
+                    .get_mut(source)
+                    .and_then(|v| Some(v.push(m.clone())));
+            }
+            (ConfigExpr::OspfStubArea { .. }, _, _) => {
+                // the area type is not bound to a single router. Apply it together with the
+                // first router.
+                router_modifiers.get_mut(&router_order[0]).unwrap().push(m.clone())
+            }
         }
     }
 
@@ -21,6 +21,7 @@
 
 pub(crate) mod optimizer;
 pub(crate) mod optimizer_trta;
+pub(crate) mod staged;
 pub(crate) mod strategy;
 pub(crate) mod strategy_trta;
 mod utils;
@@ -29,3 +30,5 @@ const TIME_FRACTION: u32 = 30;
 const DO_EXPANSION: bool = true;
 const EXPANSION_CHECK_ERRORS: bool = true;
 const REDUCTION_CHECK_ERRORS: bool = true;
+/// Default number of modifiers applied while explaining why no valid ordering exists.
+const EXPLAIN_MAX_STATES: usize = 1_000;
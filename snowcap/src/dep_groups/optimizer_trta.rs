@@ -21,7 +21,7 @@ use super::utils;
 use crate::hard_policies::{HardPolicy, PolicyError};
 use crate::modifier_ordering::RandomOrdering;
 use crate::netsim::config::ConfigModifier;
-use crate::netsim::Network;
+use crate::netsim::{ForwardingState, Network};
 use crate::optimizers::Optimizer;
 use crate::soft_policies::SoftPolicy;
 use crate::strategies::PushBackTreeStrategy;
@@ -62,6 +62,7 @@ where
     rng: ThreadRng,
     stop_time: Option<SystemTime>,
     max_group_solve_time: Option<Duration>,
+    explain_max_states: usize,
     #[cfg(feature = "count-states")]
     num_states: usize,
 }
@@ -106,6 +107,7 @@ where
             rng: rand::thread_rng(),
             stop_time,
             max_group_solve_time,
+            explain_max_states: super::EXPLAIN_MAX_STATES,
             #[cfg(feature = "count-states")]
             num_states: 0,
         }))
@@ -116,10 +118,14 @@ where
         let mut net = self.net.clone();
         let mut hard_policy = self.hard_policy.clone();
         let mut soft_policy = self.soft_policy.clone();
+        // forwarding state, updated incrementally after every change of the network
+        let mut fw_state = net.get_forwarding_state();
+        net.clear_forwarding_changes();
 
         // compute the initial options
         let (valid_groups, invalid_groups) = self.prepare_next_option(
             &mut net,
+            &mut fw_state,
             &mut hard_policy,
             &soft_policy,
             0.0,
@@ -150,7 +156,13 @@ where
                 Some(frame) => frame,
                 None => {
                     error!("Could not find any valid ordering!");
-                    return Err(Error::ProbablyNoSafeOrdering);
+                    return Err(utils::no_valid_ordering(
+                        &self.net,
+                        &self.groups,
+                        &self.hard_policy,
+                        self.stop_time,
+                        self.explain_max_states,
+                    ));
                 }
             };
 
@@ -181,14 +193,17 @@ where
                 // perform the step
                 for modifier in self.groups[next_group_idx].iter() {
                     net.apply_modifier(modifier).expect("Modifier should be ok!");
-                    let mut fw_state = net.get_forwarding_state();
-                    hard_policy.step(&mut net, &mut fw_state).expect("Modifier should be ok!");
-                    soft_policy.update(&mut fw_state, &net);
+                    let changes = fw_state.update(&mut net);
+                    hard_policy
+                        .step_incremental(&mut net, &mut fw_state, &changes)
+                        .expect("Modifier should be ok!");
+                    soft_policy.update_incremental(&mut fw_state, &net, &changes);
                 }
 
                 // compute the next options
                 let (valid_groups, invalid_groups) = self.prepare_next_option(
                     &mut net,
+                    &mut fw_state,
                     &mut hard_policy,
                     &soft_policy,
                     current_cost,
@@ -215,6 +230,7 @@ where
                 let random_group_pos = self.rng.gen_range(0, frame.invalid_groups.len());
                 match self.find_dependency(
                     &mut net,
+                    &mut fw_state,
                     &mut hard_policy,
                     &current_sequence,
                     frame.invalid_groups[random_group_pos],
@@ -237,8 +253,12 @@ where
                         info!("Could not find a new dependency group!");
                         // check the length of the probed sequence. If it is the same length as
                         // the groups, it means that we have already exhaustively checked every
-                        // possible permutation, and we can exit here!
-                        if current_sequence.len() + 1 == self.groups.len() {
+                        // possible permutation, and we can exit here! This does not hold for
+                        // ordering constraints, since no dependency is searched if the problem is
+                        // a violated constraint.
+                        if current_sequence.len() + 1 == self.groups.len()
+                            && self.hard_policy.constraints().is_empty()
+                        {
                             return Err(Error::NoSafeOrdering);
                         }
                         StackAction::Pop
@@ -262,6 +282,7 @@ where
                                 net.undo_action().expect("Cannot undo the action on the network");
                                 hard_policy.undo();
                             });
+                            fw_state.update(&mut net);
                             // pop the stack
                             stack.pop();
                         }
@@ -277,10 +298,13 @@ where
                     net = self.net.clone();
                     hard_policy = self.hard_policy.clone();
                     soft_policy = self.soft_policy.clone();
+                    fw_state = net.get_forwarding_state();
+                    net.clear_forwarding_changes();
 
                     // compute the initial options
                     let (valid_groups, invalid_groups) = self.prepare_next_option(
                         &mut net,
+                        &mut fw_state,
                         &mut hard_policy,
                         &soft_policy,
                         0.0,
@@ -311,16 +335,27 @@ impl<P> OptimizerTRTA<P>
 where
     P: SoftPolicy + Clone,
 {
+    /// Explain why no valid ordering exists, if the optimizer cannot find one. This works in the
+    /// same way as
+    /// [`StrategyTRTA::set_explain_unsat`](crate::strategies::StrategyTRTA::set_explain_unsat),
+    /// applying at most `max_states` modifiers (1000 by default). If set to 0, no explanation is
+    /// searched.
+    pub fn set_explain_unsat(&mut self, max_states: usize) {
+        self.explain_max_states = max_states;
+    }
+
     /// Check all remaining possible choices at the current position in the stack. For all options,
     /// we check if it is possible and what the cost is. Once finished, this function will return a
     /// tuple, where the first vector contains all the valid options, including the cost, already
     /// sorted such that the cheapest is the first, and the second vector contains all invalid
     /// options. Every option is an index into `self.groups`.
     ///
-    /// The network and the hard policy will remain in the same state as before!
+    /// The network and the hard policy will remain in the same state as before! `fw_state` must
+    /// match the network, and is kept up to date.
     fn prepare_next_option(
         &mut self,
         net: &mut Network,
+        fw_state: &mut ForwardingState,
         hard_policy: &mut HardPolicy,
         soft_policy: &P,
         current_cost: f64,
@@ -344,13 +379,15 @@ where
                 num_undo += 1;
                 if net.apply_modifier(modifier).is_ok() {
                     num_undo_policy += 1;
-                    let mut fw_state = net.get_forwarding_state();
-                    hard_policy.step(net, &mut fw_state).expect("cannot check policies!");
+                    let changes = fw_state.update(net);
+                    hard_policy
+                        .step_incremental(net, fw_state, &changes)
+                        .expect("cannot check policies!");
                     if !hard_policy.check() {
                         mod_ok = false;
                         break 'apply_group;
                     }
-                    sp.update(&mut fw_state, net);
+                    sp.update_incremental(fw_state, net, &changes);
                     cost += sp.cost();
                 } else {
                     mod_ok = false;
@@ -363,6 +400,7 @@ where
             (0..num_undo).for_each(|_| {
                 net.undo_action().expect("Cannot perform undo!");
             });
+            fw_state.update(net);
 
             // check if the modifier is ok
             if mod_ok {
@@ -385,6 +423,7 @@ where
     ///
     /// - `net`: Network at state of the good ordering. After returning, the net will have the exact
     ///   same state as before.
+    /// - `fw_state`: Forwarding state of `net`, which is kept up to date.
     /// - `hard_policy`: Hard Policy at state of the good ordering. After returning, the hard policy
     ///   will have the exact same state as before.
     /// - `good_ordering`: Ordering of groups, which work up to the point of the bad group
@@ -397,6 +436,7 @@ where
     fn find_dependency(
         &mut self,
         net: &mut Network,
+        fw_state: &mut ForwardingState,
         hard_policy: &mut HardPolicy,
         good_ordering: &[usize],
         bad_group: usize,
@@ -410,8 +450,10 @@ where
             num_undo += 1;
             if net.apply_modifier(modifier).is_ok() {
                 num_undo_policy += 1;
-                let mut fw_state = net.get_forwarding_state();
-                hard_policy.step(net, &mut fw_state).expect("cannot check policies!");
+                let changes = fw_state.update(net);
+                hard_policy
+                    .step_incremental(net, fw_state, &changes)
+                    .expect("cannot check policies!");
                 if !hard_policy.check() {
                     errors = Some(hard_policy.get_watch_errors());
                     break 'apply_group;
@@ -427,6 +469,7 @@ where
         (0..num_undo).for_each(|_| {
             net.undo_action().expect("Cannot perform undo!");
         });
+        fw_state.update(net);
 
         match errors {
             Some(errors) => {
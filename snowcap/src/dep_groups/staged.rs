@@ -0,0 +1,632 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! # Staged Strategy
+
+use crate::hard_policies::{Condition, HardPolicy, PolicyError};
+use crate::netsim::config::ConfigModifier;
+use crate::netsim::types::Destination;
+use crate::netsim::{ForwardingState, Network};
+use crate::strategies::{Strategy, StrategyTRTA};
+use crate::{Error, Stopper};
+
+use log::*;
+use rand::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime};
+
+/// # Staged Strategy
+///
+/// Strategy that synthesizes a migration plan made of *stages*. All modifiers of a stage can be
+/// pushed to the network at the same time, and the hard policy is satisfied for every interleaving
+/// in which the modifiers of the stage can be applied. Between two stages, the network must
+/// converge. The strategy searches for the plan with the fewest stages.
+///
+/// ## Description
+///
+/// First, [`StrategyTRTA`] is used to find a valid sequence of single modifiers, and to learn the
+/// dependency groups of the problem. Then, the stages are built greedily along this sequence. A
+/// stage always starts with the first remaining modifier of the sequence, and the remaining
+/// modifiers are added to the stage one after the other, as long as all interleavings of the stage
+/// satisfy the hard policy, and the remaining sequence is still valid afterwards.
+///
+/// The greedy solution is an upper bound on the number of stages. Afterwards, a branch and bound
+/// search looks for a plan with fewer stages. In every step, all valid stages that can be applied
+/// next are tried, starting with the largest one. A branch is pruned if the number of remaining
+/// modifiers (or the size of the largest dependency group among them) requires too many stages,
+/// or if the same set of modifiers was already applied in a branch that did not lead to a shorter
+/// plan. The search stops once it has applied a number of modifiers (see
+/// [`StagedStrategy::set_search_limit`]), or if the time budget is used up. In this case, the best
+/// plan found so far is returned, and the number of stages may not be minimal.
+///
+/// The modifiers of a stage are checked in two ways. First, the hard policy must be satisfied for
+/// every order in which the modifiers are applied one after the other, with the network converging
+/// in between. Second, the modifiers are applied concurrently, i.e., all at once without waiting
+/// for convergence, and the resulting events are processed in a random order (see
+/// [`Network::apply_modifiers_concurrently`]). This is repeated for a number of samples (see
+/// [`StagedStrategy::set_concurrent_samples`]). In every sample, the network must converge to the
+/// same forwarding state as when applying the modifiers in sequence, and the path conditions of all
+/// reachability conditions must hold in every transient state. As for the convergence after a
+/// single modifier, transient black holes and forwarding loops are not considered. Since the
+/// orderings of the events are sampled, this check is not exhaustive.
+///
+/// The dependency groups learned by [`StrategyTRTA`] are used to skip candidates: Two modifiers of
+/// the same dependency group must be applied in a specific order, and can therefore never be part
+/// of the same stage. Also, a modifier is never moved in front of a modifier of its own dependency
+/// group.
+///
+/// *Note*: The hard policy is checked for every interleaving of the current stage, but the history
+/// of the previous stages is always taken in their canonical order (i.e., the order of the sequence
+/// found by [`StrategyTRTA`]). Temporal properties spanning multiple stages are therefore only
+/// checked for this order. Also, the search assumes that the stages which can follow only depend on
+/// the set of modifiers already applied. Hence, the number of stages is only guaranteed to be
+/// minimal for policies without such temporal properties, and among the plans that respect the
+/// order of the learned dependency groups.
+pub struct StagedStrategy {
+    net: Network,
+    hard_policy: HardPolicy,
+    strategy: Box<StrategyTRTA>,
+    max_stage_size: usize,
+    concurrent_samples: usize,
+    search_limit: Option<usize>,
+    stop_time: Option<SystemTime>,
+}
+
+/// Default number of random event orderings used to check the concurrent application of a stage.
+const DEFAULT_CONCURRENT_SAMPLES: usize = 10;
+
+/// Default number of modifiers applied while searching for fewer stages than the greedy solution.
+const DEFAULT_SEARCH_LIMIT: usize = 2000;
+
+impl StagedStrategy {
+    /// Create a new staged strategy. Every stage contains at most `max_stage_size` modifiers. Since
+    /// all interleavings of a stage are checked, this limit should be kept small.
+    pub fn new(
+        mut net: Network,
+        modifiers: Vec<ConfigModifier>,
+        mut hard_policy: HardPolicy,
+        time_budget: Option<Duration>,
+        max_stage_size: usize,
+    ) -> Result<Box<Self>, Error> {
+        // the strategy checks the initial state
+        let strategy =
+            StrategyTRTA::new(net.clone(), modifiers.clone(), hard_policy.clone(), time_budget)?;
+
+        // prepare the network and the hard policy in the same way
+        net.clear_undo_stack();
+        hard_policy.set_num_mods_if_none(modifiers.len());
+        let mut fw_state = net.get_forwarding_state();
+        hard_policy.step(&mut net, &mut fw_state)?;
+
+        Ok(Box::new(Self {
+            net,
+            hard_policy,
+            strategy,
+            max_stage_size: max_stage_size.max(1),
+            concurrent_samples: DEFAULT_CONCURRENT_SAMPLES,
+            search_limit: Some(DEFAULT_SEARCH_LIMIT),
+            stop_time: time_budget.map(|dur| SystemTime::now() + dur),
+        }))
+    }
+
+    /// Set the number of random event orderings used to check the concurrent application of each
+    /// stage (10 by default). If set to 0, the concurrent application is not checked, and the
+    /// modifiers of a stage may only be applied one after the other (in any order), waiting for
+    /// the network to converge in between.
+    pub fn set_concurrent_samples(&mut self, samples: usize) {
+        self.concurrent_samples = samples;
+    }
+
+    /// Set the maximum number of modifiers applied while checking the stages during the search for
+    /// fewer stages than the greedy solution (2000 by default). If the limit is reached, the best
+    /// solution found so far is returned. If set to `None`, the search only stops once the number of stages is minimal, or
+    /// the time budget is used up.
+    pub fn set_search_limit(&mut self, limit: Option<usize>) {
+        self.search_limit = limit;
+    }
+
+    /// Synthesize the migration plan, returning the sequence of stages. Every modifier is part of
+    /// exactly one stage.
+    pub fn work(&mut self, abort: Stopper) -> Result<Vec<Vec<ConfigModifier>>, Error> {
+        let sequence = self.strategy.work(abort.clone())?;
+        let groups = self.strategy.groups();
+        // index of the (learned) dependency group of every modifier in the sequence
+        let group_of: Vec<Option<usize>> = sequence
+            .iter()
+            .map(|m| groups.iter().position(|g| g.len() > 1 && g.contains(m)))
+            .collect();
+
+        let mut search = StageSearch {
+            done: vec![false; sequence.len()],
+            failed: HashMap::new(),
+            sequence,
+            group_of,
+            net: self.net.clone(),
+            hard_policy: self.hard_policy.clone(),
+            max_stage_size: self.max_stage_size,
+            concurrent_samples: self.concurrent_samples,
+            search_limit: self.search_limit,
+            num_applied: 0,
+            stop_time: self.stop_time,
+            abort,
+        };
+
+        // the greedy solution is the upper bound for the search
+        let mut best = search.greedy()?;
+        debug!("Greedy solution has {} stages", best.len());
+        search.num_applied = 0;
+        match search.search(&mut Vec::new(), &mut best) {
+            Ok(()) if search.limit_reached() => {
+                info!("Search limit is reached! The number of stages may not be minimal.")
+            }
+            Ok(()) => {}
+            Err(Error::Timeout) => {
+                warn!("Time budget is used up! The number of stages may not be minimal.")
+            }
+            Err(e) => return Err(e),
+        }
+
+        info!("Valid staged solution was found with {} stages", best.len());
+        Ok(best.iter().map(|stage| search.modifiers(stage)).collect())
+    }
+}
+
+/// State of the search for the stages. Stages are given as the positions of their modifiers in the
+/// sequence found by [`StrategyTRTA`], sorted in ascending order (the canonical order).
+struct StageSearch {
+    sequence: Vec<ConfigModifier>,
+    group_of: Vec<Option<usize>>,
+    /// Modifiers that are part of the stages already applied to `net`.
+    done: Vec<bool>,
+    /// For each set of applied modifiers, the number of stages known to be insufficient to apply
+    /// all remaining modifiers.
+    failed: HashMap<Vec<bool>, usize>,
+    net: Network,
+    hard_policy: HardPolicy,
+    max_stage_size: usize,
+    concurrent_samples: usize,
+    search_limit: Option<usize>,
+    /// Number of modifiers applied while checking the interleavings of stages.
+    num_applied: usize,
+    stop_time: Option<SystemTime>,
+    abort: Stopper,
+}
+
+impl StageSearch {
+    /// Build the stages greedily along the sequence (see [`StagedStrategy`]). After returning, the
+    /// network and the hard policy are in the same state as before.
+    fn greedy(&mut self) -> Result<Vec<Vec<usize>>, Error> {
+        let mut stages: Vec<Vec<usize>> = Vec::new();
+        while let Some(first) = self.done.iter().position(|d| !d) {
+            // the first remaining modifier is always valid
+            let mut stage: Vec<usize> = vec![first];
+            for candidate in first + 1..self.sequence.len() {
+                if stage.len() >= self.max_stage_size {
+                    break;
+                }
+                if !self.is_eligible(candidate) {
+                    continue;
+                }
+
+                let mut probe = stage.clone();
+                probe.push(candidate);
+                if !self.check_stage(&probe)? || !self.check_concurrent(&probe)? {
+                    continue;
+                }
+
+                // if the stage is not a prefix of the sequence, the rest of the sequence must be
+                // checked again.
+                let rest: Vec<usize> = (first..self.sequence.len())
+                    .filter(|i| !self.done[*i] && !probe.contains(i))
+                    .collect();
+                if rest.first().map(|i| *i < candidate).unwrap_or(false) {
+                    let mut sequence = self.modifiers(&probe);
+                    sequence.extend(self.modifiers(&rest));
+                    if !check_sequence(&mut self.net, &mut self.hard_policy, &sequence)? {
+                        continue;
+                    }
+                }
+
+                stage = probe;
+            }
+
+            if !self.apply_stage(&stage)? {
+                return Err(Error::ProbablyNoSafeOrdering);
+            }
+            debug!("Found a stage with {} modifiers", stage.len());
+            stages.push(stage);
+        }
+
+        // go back to the initial state
+        for stage in stages.iter().rev() {
+            self.undo_stage(stage)?;
+        }
+        Ok(stages)
+    }
+
+    /// Search for a sequence of stages that is shorter than `best`, using branch and bound. The
+    /// `stages` are already applied to the network. If a shorter solution is found, `best` is
+    /// replaced. After returning successfully, the network and the hard policy are in the same
+    /// state as before.
+    fn search(
+        &mut self,
+        stages: &mut Vec<Vec<usize>>,
+        best: &mut Vec<Vec<usize>>,
+    ) -> Result<(), Error> {
+        if self.done.iter().all(|d| *d) {
+            if stages.len() < best.len() {
+                debug!("Found a solution with {} stages", stages.len());
+                *best = stages.clone();
+            }
+            return Ok(());
+        }
+        if self.limit_reached() || stages.len() + self.lower_bound() >= best.len() {
+            return Ok(());
+        }
+        // the remaining modifiers must be applied in at most `budget` stages to improve `best`.
+        let budget = best.len() - stages.len() - 1;
+        if self.failed.get(&self.done).map(|b| *b >= budget).unwrap_or(false) {
+            return Ok(());
+        }
+
+        // The next stage must leave at most `max_stage_size` modifiers for each of the remaining
+        // stages. Try the largest stages first, as they likely lead to a short solution.
+        let num_remaining = self.done.iter().filter(|d| !**d).count();
+        let min_size =
+            num_remaining.saturating_sub(self.max_stage_size * (best.len() - stages.len() - 2));
+        let mut candidates = self.valid_stages(min_size)?;
+        candidates.sort_by_key(|stage| Reverse(stage.len()));
+        for stage in candidates {
+            if self.limit_reached() {
+                break;
+            }
+            if stages.len() + 1 + self.lower_bound_without(&stage) >= best.len() {
+                continue;
+            }
+            if !self.check_concurrent(&stage)? || !self.apply_stage(&stage)? {
+                continue;
+            }
+            stages.push(stage);
+            let result = self.search(stages, best);
+            let stage = stages.pop().unwrap();
+            self.undo_stage(&stage)?;
+            result?;
+        }
+
+        if self.limit_reached() {
+            // the search is incomplete, so nothing can be learned.
+            return Ok(());
+        }
+        // no solution with fewer stages than `best` exists from the current state.
+        let budget = best.len() - stages.len() - 1;
+        let failed = self.failed.entry(self.done.clone()).or_default();
+        *failed = budget.max(*failed);
+        Ok(())
+    }
+
+    /// Returns all stages with at least `min_size` modifiers that can be applied next, such that
+    /// all their interleavings satisfy the hard policy. Their concurrent application is not yet
+    /// checked. Every prefix of an interleaving of a stage is also an interleaving of each of its
+    /// subsets. Hence, a stage is only checked if all pairs of its modifiers are valid, and it is
+    /// only extended if it is valid itself. If the search limit is reached, only some of the valid
+    /// stages are returned.
+    fn valid_stages(&mut self, min_size: usize) -> Result<Vec<Vec<usize>>, Error> {
+        let eligible: Vec<usize> =
+            (0..self.sequence.len()).filter(|i| self.is_eligible(*i)).collect();
+        let mut valid: Vec<bool> = Vec::with_capacity(eligible.len());
+        for i in eligible.iter() {
+            valid.push(self.check_candidate(&[*i])?);
+        }
+        let eligible: Vec<usize> =
+            eligible.into_iter().zip(valid).filter(|(_, ok)| *ok).map(|(i, _)| i).collect();
+        let mut pairs: HashSet<(usize, usize)> = HashSet::new();
+        if self.max_stage_size > 1 {
+            for (k, a) in eligible.iter().enumerate() {
+                for b in eligible[k + 1..].iter() {
+                    if self.check_candidate(&[*a, *b])? {
+                        pairs.insert((*a, *b));
+                    }
+                }
+            }
+        }
+
+        let mut result = Vec::new();
+        for (k, i) in eligible.iter().enumerate() {
+            if min_size <= 1 {
+                result.push(vec![*i]);
+            }
+            self.extend_stages(&eligible[k + 1..], &pairs, min_size, &mut vec![*i], &mut result)?;
+        }
+        Ok(result)
+    }
+
+    /// Extend the `stage` with the `candidates`, whose pairs with all modifiers of the stage are
+    /// valid. The stage is already known to be valid.
+    fn extend_stages(
+        &mut self,
+        candidates: &[usize],
+        pairs: &HashSet<(usize, usize)>,
+        min_size: usize,
+        stage: &mut Vec<usize>,
+        result: &mut Vec<Vec<usize>>,
+    ) -> Result<(), Error> {
+        if stage.len() >= self.max_stage_size {
+            return Ok(());
+        }
+        for (k, candidate) in candidates.iter().enumerate() {
+            if stage.len() + candidates.len() - k < min_size {
+                break;
+            }
+            if !stage.iter().all(|i| pairs.contains(&(*i, *candidate))) {
+                continue;
+            }
+            stage.push(*candidate);
+            if stage.len() == 2 || self.check_candidate(stage)? {
+                if stage.len() >= min_size {
+                    result.push(stage.clone());
+                }
+                self.extend_stages(&candidates[k + 1..], pairs, min_size, stage, result)?;
+            }
+            stage.pop();
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the search has applied as many modifiers as allowed.
+    fn limit_reached(&self) -> bool {
+        self.search_limit.map(|limit| self.num_applied >= limit).unwrap_or(false)
+    }
+
+    /// Returns `true` if the modifier is not yet applied, and it is not preceded by a remaining
+    /// modifier of the same dependency group. Two modifiers of the same dependency group must be
+    /// applied in a specific order, and can therefore never be part of the same stage.
+    fn is_eligible(&self, i: usize) -> bool {
+        !self.done[i]
+            && match self.group_of[i] {
+                Some(g) => (0..i).all(|j| self.done[j] || self.group_of[j] != Some(g)),
+                None => true,
+            }
+    }
+
+    /// Lower bound on the number of stages needed for all remaining modifiers. Every stage
+    /// contains at most `max_stage_size` modifiers, and at most one of each dependency group.
+    fn lower_bound(&self) -> usize {
+        self.lower_bound_without(&[])
+    }
+
+    /// Lower bound on the number of stages needed for all remaining modifiers, except `stage`.
+    fn lower_bound_without(&self, stage: &[usize]) -> usize {
+        let remaining: Vec<usize> =
+            (0..self.sequence.len()).filter(|i| !self.done[*i] && !stage.contains(i)).collect();
+        let mut group_size: HashMap<usize, usize> = HashMap::new();
+        for g in remaining.iter().filter_map(|i| self.group_of[*i]) {
+            *group_size.entry(g).or_default() += 1;
+        }
+        let by_size = (remaining.len() + self.max_stage_size - 1) / self.max_stage_size;
+        group_size.values().copied().max().unwrap_or(0).max(by_size)
+    }
+
+    /// Check that all interleavings of the stage satisfy the hard policy. Use
+    /// [`StageSearch::check_concurrent`] to check the concurrent application of the stage.
+    fn check_stage(&mut self, stage: &[usize]) -> Result<bool, Error> {
+        if self.stop_time.as_ref().map(|time| time.elapsed().is_ok()).unwrap_or(false) {
+            return Err(Error::Timeout);
+        }
+        if self.abort.try_is_stop().unwrap_or(false) {
+            info!("Operation was aborted!");
+            return Err(Error::Abort);
+        }
+        let mods = self.modifiers(stage);
+        let (net, hard_policy) = (&mut self.net, &mut self.hard_policy);
+        check_interleavings(net, hard_policy, &mods, &mut vec![], &mut self.num_applied)
+    }
+
+    /// Check a candidate for the next stage during the search. Once the search limit is reached,
+    /// no more candidates are checked, and `false` is returned.
+    fn check_candidate(&mut self, stage: &[usize]) -> Result<bool, Error> {
+        if self.limit_reached() {
+            return Ok(false);
+        }
+        self.check_stage(stage)
+    }
+
+    /// Check that the stage can be applied concurrently (see [`StagedStrategy`]).
+    fn check_concurrent(&mut self, stage: &[usize]) -> Result<bool, Error> {
+        let mods = self.modifiers(stage);
+        check_concurrent(&mut self.net, &self.hard_policy, &mods, self.concurrent_samples)
+    }
+
+    /// Apply the stage in its canonical order. If the hard policy is violated, the stage is undone,
+    /// and `false` is returned.
+    fn apply_stage(&mut self, stage: &[usize]) -> Result<bool, Error> {
+        for (num_applied, i) in stage.iter().enumerate() {
+            match apply(&mut self.net, &mut self.hard_policy, &self.sequence[*i])? {
+                Some(true) => {}
+                Some(false) => {
+                    (0..=num_applied)
+                        .try_for_each(|_| undo(&mut self.net, &mut self.hard_policy))?;
+                    return Ok(false);
+                }
+                None => {
+                    (0..num_applied)
+                        .try_for_each(|_| undo(&mut self.net, &mut self.hard_policy))?;
+                    return Ok(false);
+                }
+            }
+        }
+        stage.iter().for_each(|i| self.done[*i] = true);
+        Ok(true)
+    }
+
+    /// Undo a stage applied with [`StageSearch::apply_stage`].
+    fn undo_stage(&mut self, stage: &[usize]) -> Result<(), Error> {
+        stage.iter().try_for_each(|_| undo(&mut self.net, &mut self.hard_policy))?;
+        stage.iter().for_each(|i| self.done[*i] = false);
+        Ok(())
+    }
+
+    /// Returns the modifiers of the stage.
+    fn modifiers(&self, stage: &[usize]) -> Vec<ConfigModifier> {
+        stage.iter().map(|i| self.sequence[*i].clone()).collect()
+    }
+}
+
+/// Apply the modifier and check the hard policy. If the modifier cannot be applied, it is undone
+/// immediately, and `None` is returned. Otherwise, the result of the check is returned, and the
+/// modifier must be undone with [`undo`].
+fn apply(
+    net: &mut Network,
+    hard_policy: &mut HardPolicy,
+    modifier: &ConfigModifier,
+) -> Result<Option<bool>, Error> {
+    match net.apply_modifier(modifier) {
+        Ok(()) => {
+            let mut fw_state = net.get_forwarding_state();
+            hard_policy.step(net, &mut fw_state)?;
+            Ok(Some(hard_policy.check()))
+        }
+        Err(_) => {
+            net.undo_action()?;
+            Ok(None)
+        }
+    }
+}
+
+/// Undo a modifier applied with [`apply`].
+fn undo(net: &mut Network, hard_policy: &mut HardPolicy) -> Result<(), Error> {
+    net.undo_action()?;
+    hard_policy.undo();
+    Ok(())
+}
+
+/// Check that the hard policy is satisfied for every interleaving of the stage, starting at the
+/// current state. `used` contains the positions of the stage that are already applied, and
+/// `num_applied` is incremented for every modifier applied. After returning, the network and the
+/// hard policy are in the same state as before.
+fn check_interleavings(
+    net: &mut Network,
+    hard_policy: &mut HardPolicy,
+    stage: &[ConfigModifier],
+    used: &mut Vec<usize>,
+    num_applied: &mut usize,
+) -> Result<bool, Error> {
+    for i in 0..stage.len() {
+        if used.contains(&i) {
+            continue;
+        }
+        *num_applied += 1;
+        let ok = match apply(net, hard_policy, &stage[i])? {
+            Some(ok) => {
+                used.push(i);
+                let ok = ok && check_interleavings(net, hard_policy, stage, used, num_applied)?;
+                used.pop();
+                undo(net, hard_policy)?;
+                ok
+            }
+            None => false,
+        };
+        if !ok {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Check that the stage can be applied concurrently in `num_samples` random orderings of the events
+/// (see [`StagedStrategy`]). After returning, the network is in the same state as before.
+fn check_concurrent(
+    net: &mut Network,
+    hard_policy: &HardPolicy,
+    stage: &[ConfigModifier],
+    num_samples: usize,
+) -> Result<bool, Error> {
+    if num_samples == 0 {
+        return Ok(true);
+    }
+    // only the path conditions are checked in the transient states
+    let conditions: Vec<&Condition> = hard_policy
+        .prop_vars
+        .iter()
+        .filter(|c| matches!(c, Condition::Reachable(_, _, Some(_))))
+        .collect();
+    let transient_ok = |n: &Network| {
+        if conditions.is_empty() {
+            return true;
+        }
+        let mut fw_state = n.get_forwarding_state();
+        conditions
+            .iter()
+            .all(|c| !matches!(c.check(&mut fw_state), Err(PolicyError::PathCondition { .. })))
+    };
+
+    let snapshot = net.snapshot();
+    // converged state after applying the stage in its canonical order
+    let mut ok = stage.iter().try_for_each(|m| net.apply_modifier(m)).is_ok();
+    let expected = net.get_forwarding_state();
+    let mut rng = thread_rng();
+    for _ in 0..num_samples {
+        if !ok {
+            break;
+        }
+        net.restore(snapshot)?;
+        ok = matches!(net.apply_modifiers_concurrently(stage, &mut rng, &transient_ok), Ok(true))
+            && same_next_hops(net, &net.get_forwarding_state(), &expected);
+    }
+    net.restore(snapshot)?;
+    net.truncate_snapshots(snapshot);
+    Ok(ok)
+}
+
+/// Returns `true` if every router of the network uses the same next hops towards every known
+/// prefix in both forwarding states.
+pub(crate) fn same_next_hops(net: &Network, a: &ForwardingState, b: &ForwardingState) -> bool {
+    net.get_routers().into_iter().all(|r| {
+        net.get_known_prefixes().iter().all(|p| {
+            a.get_next_hops_new(r, Destination::BGP(*p)).ok()
+                == b.get_next_hops_new(r, Destination::BGP(*p)).ok()
+        })
+    })
+}
+
+/// Check that the hard policy is satisfied when applying the sequence, starting at the current
+/// state. After returning, the network and the hard policy are in the same state as before.
+fn check_sequence(
+    net: &mut Network,
+    hard_policy: &mut HardPolicy,
+    sequence: &[ConfigModifier],
+) -> Result<bool, Error> {
+    let mut num_undo = 0;
+    let mut ok = true;
+    for modifier in sequence {
+        match apply(net, hard_policy, modifier)? {
+            Some(step_ok) => {
+                num_undo += 1;
+                if !step_ok {
+                    ok = false;
+                    break;
+                }
+            }
+            None => {
+                ok = false;
+                break;
+            }
+        }
+    }
+    (0..num_undo).try_for_each(|_| undo(net, hard_policy))?;
+    Ok(ok)
+}
@@ -21,8 +21,8 @@ use super::utils;
 use crate::hard_policies::{HardPolicy, PolicyError};
 use crate::modifier_ordering::RandomOrdering;
 use crate::netsim::config::ConfigModifier;
-use crate::netsim::Network;
-use crate::strategies::{PushBackTreeStrategy, Strategy};
+use crate::netsim::{ForwardingState, Network};
+use crate::strategies::{GroupStrategy, PushBackTreeStrategy, Strategy};
 use crate::{Error, Stopper};
 
 use log::*;
@@ -179,6 +179,7 @@ pub struct StrategyTRTA {
     rng: ThreadRng,
     stop_time: Option<SystemTime>,
     max_group_solve_time: Option<Duration>,
+    explain_max_states: usize,
     #[cfg(feature = "count-states")]
     num_states: usize,
     #[cfg(feature = "count-states")]
@@ -187,47 +188,17 @@ pub struct StrategyTRTA {
 
 impl Strategy for StrategyTRTA {
     fn new(
-        mut net: Network,
+        net: Network,
         modifiers: Vec<ConfigModifier>,
-        mut hard_policy: HardPolicy,
+        hard_policy: HardPolicy,
         time_budget: Option<Duration>,
     ) -> Result<Box<Self>, Error> {
-        // clear the undo stack
-        net.clear_undo_stack();
         for i in &modifiers {
             println!("{:?}", i);
         }
-        // check the state
-        hard_policy.set_num_mods_if_none(modifiers.len());
-        let mut fw_state = net.get_forwarding_state();
-        hard_policy.step(&mut net, &mut fw_state)?;
-        if !hard_policy.check() {
-            error!("Initial state errors::\n{}", fmt_err(&hard_policy.get_watch_errors(), &net));
-            return Err(Error::InvalidInitialState);
-        }
-
         // prepare the groups
-        let mut groups: Vec<Vec<ConfigModifier>> = Vec::with_capacity(modifiers.len());
-        for modifier in modifiers {
-            groups.push(vec![modifier]);
-        }
-
-        // prepare the timings
-        let max_group_solve_time: Option<Duration> =
-            time_budget.as_ref().map(|dur| *dur / super::TIME_FRACTION);
-        let stop_time: Option<SystemTime> = time_budget.map(|dur| SystemTime::now() + dur);
-        Ok(Box::new(Self {
-            net,
-            groups,
-            hard_policy,
-            rng: rand::thread_rng(),
-            stop_time,
-            max_group_solve_time,
-            #[cfg(feature = "count-states")]
-            num_states: 0,
-            #[cfg(feature = "count-states")]
-            seen_difficult_dependency: false,
-        }))
+        let groups: Vec<Vec<ConfigModifier>> = modifiers.into_iter().map(|m| vec![m]).collect();
+        Self::from_groups(net, groups, hard_policy, time_budget)
     }
 
     fn work(&mut self, mut abort: Stopper) -> Result<Vec<ConfigModifier>, Error> {
@@ -245,6 +216,9 @@ impl Strategy for StrategyTRTA {
         // clone the network and the hard policies to work with them for the tree exploration
         let mut net = self.net.clone();
         let mut hard_policy = self.hard_policy.clone();
+        // forwarding state, updated incrementally after every change of the network
+        let mut fw_state = net.get_forwarding_state();
+        net.clear_forwarding_changes();
         
         println!("########## Invariances ##########");
         for i in hard_policy.prop_vars.iter() {
@@ -277,12 +251,18 @@ impl Strategy for StrategyTRTA {
                 Some(frame) => frame,
                 None => {
                     error!("Could not find any valid ordering!");
-                    return Err(Error::ProbablyNoSafeOrdering);
+                    return Err(utils::no_valid_ordering(
+                        &self.net,
+                        &self.groups,
+                        &self.hard_policy,
+                        self.stop_time,
+                        self.explain_max_states,
+                    ));
                 }
             };
             let get_option_start = Instant::now();
             opt_ctr += 1;
-            let result = self.get_next_option(&mut net, &mut hard_policy, frame);
+            let result = self.get_next_option(&mut net, &mut fw_state, &mut hard_policy, frame);
             let mut find_dependency_end: Option<Duration> = None;
             let get_option_end = get_option_start.elapsed();
             opt_duration += get_option_end;
@@ -345,6 +325,7 @@ impl Strategy for StrategyTRTA {
                     dep_ctr += 1;
                     let dep_result =self.find_dependency(
                         &mut net,
+                        &mut fw_state,
                         &mut hard_policy,
                         &current_sequence,
                         frame.rem_groups[check_idx],
@@ -393,6 +374,7 @@ impl Strategy for StrategyTRTA {
                                 net.undo_action().expect("Cannot undo the action on the network");
                                 hard_policy.undo();
                             });
+                            fw_state.update(&mut net);
                             // pop the stack
                             stack.pop();
                         }
@@ -408,6 +390,8 @@ impl Strategy for StrategyTRTA {
                     current_sequence.clear();
                     net = self.net.clone();
                     hard_policy = self.hard_policy.clone();
+                    fw_state = net.get_forwarding_state();
+                    net.clear_forwarding_changes();
                 }
             }
             println!();
@@ -421,20 +405,75 @@ impl Strategy for StrategyTRTA {
     }
 }
 
+impl GroupStrategy for StrategyTRTA {
+    fn from_groups(
+        mut net: Network,
+        groups: Vec<Vec<ConfigModifier>>,
+        mut hard_policy: HardPolicy,
+        time_budget: Option<Duration>,
+    ) -> Result<Box<Self>, Error> {
+        // clear the undo stack
+        net.clear_undo_stack();
+        // check the state
+        hard_policy.set_num_mods_if_none(groups.iter().map(|g| g.len()).sum());
+        let mut fw_state = net.get_forwarding_state();
+        hard_policy.step(&mut net, &mut fw_state)?;
+        if !hard_policy.check() {
+            error!("Initial state errors::\n{}", fmt_err(&hard_policy.get_watch_errors(), &net));
+            return Err(Error::InvalidInitialState);
+        }
+
+        // prepare the timings
+        let max_group_solve_time: Option<Duration> =
+            time_budget.as_ref().map(|dur| *dur / super::TIME_FRACTION);
+        let stop_time: Option<SystemTime> = time_budget.map(|dur| SystemTime::now() + dur);
+        Ok(Box::new(Self {
+            net,
+            groups,
+            hard_policy,
+            rng: rand::thread_rng(),
+            stop_time,
+            max_group_solve_time,
+            explain_max_states: super::EXPLAIN_MAX_STATES,
+            #[cfg(feature = "count-states")]
+            num_states: 0,
+            #[cfg(feature = "count-states")]
+            seen_difficult_dependency: false,
+        }))
+    }
+}
+
 impl StrategyTRTA {
+    /// Explain why no valid ordering exists, if the strategy cannot find one. The strategy then
+    /// searches a minimal set of modifiers, for which every ordering violates the hard policy, and
+    /// returns it as [`Error::Unsat`] (see [`UnsatExplanation`](crate::UnsatExplanation)). This
+    /// search explores all orderings exhaustively, applying at most `max_states` modifiers in
+    /// total (1000 by default). If this limit (or the time budget) is reached before any such set
+    /// is found, [`Error::ProbablyNoSafeOrdering`] is returned. If set to 0, no explanation is
+    /// searched.
+    pub fn set_explain_unsat(&mut self, max_states: usize) {
+        self.explain_max_states = max_states;
+    }
+
+    /// Returns the dependency groups, including all groups learned while calling `work`.
+    pub(crate) fn groups(&self) -> &[Vec<ConfigModifier>] {
+        &self.groups
+    }
+
     /// Check all remaining possible choices at the current position in the stack. The first option,
     /// that works is returned (with `Ok(idx)`). However, if none of them seem to work, then one of
     /// the checked and failed groups is returned at random, which should be used to find a
     /// dependency group. The returned index corresponds to the position in `frame.rem_groups`!
     ///
     /// In the OK case, the network and the hard policy will remain in the state of the modification
-    /// of which the index is returned
+    /// of which the index is returned. `fw_state` must match the network, and is kept up to date.
     
     // seems to be different from the algorithm described in the paper?
     // as it does not check dependency immediately after encountering a counter-example 
     fn get_next_option(
         &mut self,
         net: &mut Network,
+        fw_state: &mut ForwardingState,
         hard_policy: &mut HardPolicy,
         frame: &StackFrame,
     ) -> Result<usize, usize> {
@@ -469,12 +508,14 @@ impl StrategyTRTA {
                 match mod_result {
                     Ok(()) => {
                         num_undo_policy += 1;
-                        let mut fw_state = net.get_forwarding_state();
+                        let changes = fw_state.update(net);
                         // checks whether the order aligns with the policy
                         println!("\t\tChecking option {} against invariances", ctr);
                         // return Ok(()) or Err
                         let hard_start = Instant::now();
-                        hard_policy.step(net, &mut fw_state).expect("cannot check policies!");
+                        hard_policy
+                            .step_incremental(net, fw_state, &changes)
+                            .expect("cannot check policies!");
                         let hard_end = hard_start.elapsed();
                         println!("\t\tChecking invariances {:?}", hard_end);
                         // what does this check function do?
@@ -506,6 +547,7 @@ impl StrategyTRTA {
                 (0..num_undo).for_each(|_| {
                     net.undo_action().expect("Cannot perform undo!");
                 });
+                fw_state.update(net);
                 let undo_end = undo_start.elapsed();
                 println!("\tUndo actions {:?}", undo_end);
             }
@@ -520,6 +562,7 @@ impl StrategyTRTA {
     ///
     /// - `net`: Network at state of the good ordering. After returning, the net will have the exact
     ///   same state as before.
+    /// - `fw_state`: Forwarding state of `net`, which is kept up to date.
     /// - `hard_policy`: Hard Policy at state of the good ordering. After returning, the hard policy
     ///   will have the exact same state as before.
     /// - `good_ordering`: Ordering of groups, which work up to the point of the bad group
@@ -532,6 +575,7 @@ impl StrategyTRTA {
     fn find_dependency(
         &mut self,
         net: &mut Network,
+        fw_state: &mut ForwardingState,
         hard_policy: &mut HardPolicy,
         good_ordering: &[usize],
         bad_group: usize,
@@ -546,8 +590,10 @@ impl StrategyTRTA {
             num_undo += 1;
             if net.apply_modifier(modifier).is_ok() {
                 num_undo_policy += 1;
-                let mut fw_state = net.get_forwarding_state();
-                hard_policy.step(net, &mut fw_state).expect("cannot check policies!");
+                let changes = fw_state.update(net);
+                hard_policy
+                    .step_incremental(net, fw_state, &changes)
+                    .expect("cannot check policies!");
                 if !hard_policy.check() {
                     errors = Some(hard_policy.get_watch_errors());
                     break 'apply_group;
@@ -563,6 +609,7 @@ impl StrategyTRTA {
         (0..num_undo).for_each(|_| {
             net.undo_action().expect("Cannot perform undo!");
         });
+        fw_state.update(net);
 
         match errors {
             Some(errors) => {
@@ -21,11 +21,11 @@
 //! functions are necessary for reducing the dependency group, and expanding it. However, it is
 //! agnostic to wether we try to optimize for soft-policies, or only consider hard-policy.
 
-use crate::hard_policies::{HardPolicy, PolicyError, WatchErrors};
+use crate::hard_policies::{Condition, HardPolicy, PolicyError, WatchErrors};
 use crate::netsim::config::ConfigModifier;
 use crate::netsim::{printer, Network, NetworkError};
 use crate::strategies::{GroupStrategy, Strategy};
-use crate::{Error, Stopper};
+use crate::{Error, Stopper, UnsatExplanation};
 
 use log::*;
 use std::time::{Duration, SystemTime, Instant};
@@ -55,6 +55,9 @@ use std::time::{Duration, SystemTime, Instant};
 /// 3. Try to extend the problem by adding yet unexplored groups to the problem. If the problem
 ///    changes, then go back to step 2 to solve the problem. If the problem group cannot be
 ///    expanded, finding dependencies failed, and return `None`.
+///
+/// If the problem is caused by a violated ordering constraint of the hard policy, no dependency is
+/// searched, and `None` is returned.
 #[allow(clippy::too_many_arguments)]
 pub(super) fn find_dependency<S>(
     net: &Network,
@@ -70,6 +73,14 @@ pub(super) fn find_dependency<S>(
 where
     S: Strategy + GroupStrategy,
 {
+    // A violated ordering constraint cannot be solved by a dependency, but only by choosing a
+    // different ordering.
+    if errors.1.iter().flatten().any(|e| matches!(e, PolicyError::OrderingConstraint { .. })) {
+        return None;
+    }
+    // the smaller problems only contain a subset of the modifiers.
+    let hard_policy = &hard_policy.with_relative_constraints();
+
     // compute the remaining groups
     let mut remaining_groups =
         (0..groups.len()).filter(|x| !ordering.contains(x)).collect::<Vec<usize>>();
@@ -659,6 +670,217 @@ pub(super) fn fmt_group_ord(
         .join("\n")
 }
 
+/// Returns the error if no valid ordering could be found. If `max_states` is not 0, the problem is
+/// explained using [`explain_unsat`], and [`Error::Unsat`] is returned if an explanation is found.
+/// Otherwise, [`Error::ProbablyNoSafeOrdering`] is returned.
+pub(super) fn no_valid_ordering(
+    net: &Network,
+    groups: &[Vec<ConfigModifier>],
+    hard_policy: &HardPolicy,
+    stop_time: Option<SystemTime>,
+    max_states: usize,
+) -> Error {
+    if max_states == 0 {
+        return Error::ProbablyNoSafeOrdering;
+    }
+    match explain_unsat(net, groups, hard_policy, stop_time, max_states) {
+        Some(explanation) => {
+            error!("{}", explanation);
+            Error::Unsat(Box::new(explanation))
+        }
+        None => Error::ProbablyNoSafeOrdering,
+    }
+}
+
+/// # Explaining Unsolvable Problems
+///
+/// This function searches a set of modifiers, for which every ordering violates the hard policy.
+/// It starts with all modifiers, and first tries to remove entire dependency `groups`, before
+/// removing single modifiers, as long as the remaining set still has no valid ordering. Whether a
+/// set has a valid ordering is checked exhaustively, by exploring all orderings in a tree, which
+/// is pruned as soon as the hard policy is violated.
+///
+/// The arguments `net` and `hard_policy` must be in the initial state (with the initial state
+/// already checked by the `hard_policy`). The search applies at most `max_states` modifiers in
+/// total. If all modifiers together have a valid ordering, or if the time budget or `max_states`
+/// is used up before this could be determined, `None` is returned. If either of them is used up
+/// while removing modifiers, the explanation is not minimal.
+pub(super) fn explain_unsat(
+    net: &Network,
+    groups: &[Vec<ConfigModifier>],
+    hard_policy: &HardPolicy,
+    stop_time: Option<SystemTime>,
+    max_states: usize,
+) -> Option<UnsatExplanation> {
+    let mut net = net.clone();
+    let mut hard_policy = hard_policy.clone();
+    let mut limit = Limit { stop_time, states: max_states };
+
+    let mut groups = groups.to_vec();
+    let mut failure =
+        match check_all_orderings(&mut net, &mut hard_policy, &groups.concat(), &mut limit) {
+            Some(Some(failure)) => failure,
+            _ => return None,
+        };
+    let mut minimal = true;
+
+    // remove entire groups, and then single modifiers
+    let mut i = 0;
+    while minimal && i < groups.len() {
+        let mut probe = groups.clone();
+        probe.remove(i);
+        match check_all_orderings(&mut net, &mut hard_policy, &probe.concat(), &mut limit) {
+            Some(Some(f)) => {
+                groups = probe;
+                failure = f;
+            }
+            Some(None) => i += 1,
+            None => minimal = false,
+        }
+    }
+    let mut modifiers = groups.concat();
+    let mut i = 0;
+    while minimal && i < modifiers.len() {
+        let mut probe = modifiers.clone();
+        probe.remove(i);
+        match check_all_orderings(&mut net, &mut hard_policy, &probe, &mut limit) {
+            Some(Some(f)) => {
+                modifiers = probe;
+                failure = f;
+            }
+            Some(None) => i += 1,
+            None => minimal = false,
+        }
+    }
+
+    let (ordering, (watch, watch_errors)) = failure;
+    let sequence: Vec<ConfigModifier> = ordering.iter().map(|i| modifiers[*i].clone()).collect();
+    let conditions: Vec<Condition> = watch
+        .iter()
+        .zip(watch_errors.iter())
+        .filter(|(_, e)| e.is_some())
+        .map(|(i, _)| hard_policy.prop_vars[*i].clone())
+        .collect();
+    let mut errors: Vec<PolicyError> = Vec::new();
+    for e in watch_errors.into_iter().flatten() {
+        if !errors.contains(&e) {
+            errors.push(e);
+        }
+    }
+
+    let fmt_mods = |mods: &[ConfigModifier]| {
+        mods.iter()
+            .map(|m| printer::config_modifier(&net, m).unwrap_or_else(|_| format!("{:?}", m)))
+            .collect::<Vec<String>>()
+            .join("\n    ")
+    };
+    let description = format!(
+        "Every ordering of the following {}modifiers violates the hard policy:\n    {}\n\
+         The hard policy is violated after applying:\n    {}\n\
+         Violated conditions:\n    {}\n\
+         Errors:\n    {}",
+        if minimal { "" } else { "(not minimized) " },
+        fmt_mods(&modifiers),
+        fmt_mods(&sequence),
+        conditions.iter().map(|c| c.repr_with_name(&net)).collect::<Vec<String>>().join("\n    "),
+        errors.iter().map(|e| e.repr_with_name(&net)).collect::<Vec<String>>().join("\n    "),
+    );
+
+    Some(UnsatExplanation { modifiers, minimal, sequence, conditions, errors, description })
+}
+
+/// Failing ordering (indices into the modifiers), together with the errors after applying it.
+type Failure = (Vec<usize>, WatchErrors);
+
+/// Limits of the exhaustive search in [`explain_unsat`]: the time budget, and the number of
+/// modifiers which may still be applied.
+struct Limit {
+    stop_time: Option<SystemTime>,
+    states: usize,
+}
+
+impl Limit {
+    /// Returns `true` if the search may apply another modifier, and counts it.
+    fn next_state(&mut self) -> bool {
+        if self.states == 0 || self.stop_time.map(|time| time.elapsed().is_ok()).unwrap_or(false) {
+            return false;
+        }
+        self.states -= 1;
+        true
+    }
+}
+
+/// Check all orderings of the modifiers. If none of them is valid, return the ordering which can be
+/// applied the furthest (including the modifier which causes the problem), together with the
+/// errors in this state (`Some(Some(failure))`). If a valid ordering exists, `Some(None)` is
+/// returned, and if the `limit` is reached, `None` is returned. After returning, the network and
+/// the hard policy are in the same state as before.
+fn check_all_orderings(
+    net: &mut Network,
+    hard_policy: &mut HardPolicy,
+    modifiers: &[ConfigModifier],
+    limit: &mut Limit,
+) -> Option<Option<Failure>> {
+    let mut deepest: Option<Failure> = None;
+    match explore_orderings(net, hard_policy, modifiers, &mut Vec::new(), &mut deepest, limit) {
+        Some(true) => Some(deepest),
+        Some(false) => Some(None),
+        None => None,
+    }
+}
+
+/// Recursive tree search of [`check_all_orderings`], returning `Some(true)` if no ordering starting
+/// with `ordering` is valid.
+fn explore_orderings(
+    net: &mut Network,
+    hard_policy: &mut HardPolicy,
+    modifiers: &[ConfigModifier],
+    ordering: &mut Vec<usize>,
+    deepest: &mut Option<Failure>,
+    limit: &mut Limit,
+) -> Option<bool> {
+    if ordering.len() == modifiers.len() {
+        return Some(false);
+    }
+    for i in 0..modifiers.len() {
+        if ordering.contains(&i) {
+            continue;
+        }
+        if !limit.next_state() {
+            return None;
+        }
+        ordering.push(i);
+        let result = if net.apply_modifier(&modifiers[i]).is_ok() {
+            let mut fw_state = net.get_forwarding_state();
+            hard_policy.step(net, &mut fw_state).expect("cannot check policies!");
+            let result = if hard_policy.check() {
+                explore_orderings(net, hard_policy, modifiers, ordering, deepest, limit)
+            } else {
+                record_failure(deepest, ordering, hard_policy.get_watch_errors());
+                Some(true)
+            };
+            hard_policy.undo();
+            result
+        } else {
+            record_failure(deepest, ordering, (Vec::new(), vec![Some(PolicyError::NoConvergence)]));
+            Some(true)
+        };
+        net.undo_action().expect("Cannot perform undo!");
+        ordering.pop();
+        if result != Some(true) {
+            return result;
+        }
+    }
+    Some(true)
+}
+
+/// Remember the failing ordering, if it is longer than the previously longest one.
+fn record_failure(deepest: &mut Option<Failure>, ordering: &[usize], errors: WatchErrors) {
+    if deepest.as_ref().map(|(o, _)| o.len() < ordering.len()).unwrap_or(true) {
+        *deepest = Some((ordering.to_vec(), errors));
+    }
+}
+
 pub(super) fn fmt_err(errors: &WatchErrors, net: &Network) -> String {
     errors
         .1
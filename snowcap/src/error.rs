@@ -17,8 +17,10 @@
 
 //! Module containing all error types
 
+use crate::hard_policies::{Condition, PolicyError};
 use crate::netsim::{config::ConfigModifier, ConfigError, NetworkError};
 use crate::topology_zoo::ZooTopologyError;
+use std::fmt;
 use thiserror::Error;
 
 /// Main error type
@@ -34,6 +36,13 @@ pub enum Error {
     /// strategies that may find a solution.
     #[error("No safe ordering can be found using the chosen strategy!")]
     ProbablyNoSafeOrdering,
+    /// No safe ordering can be found, and the contained explanation shows a minimal set of
+    /// modifiers, for which every ordering violates the hard policy. This is only returned by the
+    /// [`StrategyTRTA`](crate::strategies::StrategyTRTA) and the
+    /// [`OptimizerTRTA`](crate::optimizers::OptimizerTRTA) (see
+    /// [`StrategyTRTA::set_explain_unsat`](crate::strategies::StrategyTRTA::set_explain_unsat)).
+    #[error("No safe ordering can be found!\n{0}")]
+    Unsat(Box<UnsatExplanation>),
     /// Global Optimum was not found using the GlobalOptimizer.
     #[error("Global optimum was not found: Best solution yet has cost {1}")]
     GlobalOptimumNotFound(Vec<ConfigModifier>, f64),
@@ -59,3 +68,37 @@ impl From<ConfigError> for Error {
         Self::NetworkError(NetworkError::ConfigError(cause))
     }
 }
+
+/// Explanation why no safe ordering exists. It contains a set of modifiers, for which every
+/// ordering (starting from the initial state) violates the hard policy, and one intermediate state
+/// in which the hard policy is violated.
+///
+/// Only the [`StrategyTRTA`](crate::strategies::StrategyTRTA) and the
+/// [`OptimizerTRTA`](crate::optimizers::OptimizerTRTA) search such an explanation, exploring a
+/// limited number of states (see
+/// [`StrategyTRTA::set_explain_unsat`](crate::strategies::StrategyTRTA::set_explain_unsat)). All
+/// other strategies and optimizers return [`Error::NoSafeOrdering`] or
+/// [`Error::ProbablyNoSafeOrdering`].
+#[derive(Debug, Clone)]
+pub struct UnsatExplanation {
+    /// Set of modifiers, for which every ordering violates the hard policy.
+    pub modifiers: Vec<ConfigModifier>,
+    /// `true` if no modifier can be removed from `modifiers` without the problem disappearing. If
+    /// the time budget did not suffice to minimize the set, this is `false`.
+    pub minimal: bool,
+    /// Ordering of (some of) the `modifiers`, after which the hard policy is violated. This is the
+    /// ordering which can be applied the furthest.
+    pub sequence: Vec<ConfigModifier>,
+    /// Conditions violated after applying the `sequence`.
+    pub conditions: Vec<Condition>,
+    /// Errors reported after applying the `sequence` (without duplicates).
+    pub errors: Vec<PolicyError>,
+    /// Description of the explanation, with all router names inserted.
+    pub description: String,
+}
+
+impl fmt::Display for UnsatExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
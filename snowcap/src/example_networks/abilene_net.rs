@@ -144,11 +144,11 @@ impl ExampleNetwork for AbileneNetwork {
             let cf = Self::initial_config(&net, initial_variant);
             net.set_config(&cf).unwrap();
 
-            net.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None)
+            net.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None)
                 .unwrap();
-            net.advertise_external_route(e2, Prefix(0), vec![AsId(65102), AsId(65200)], None, None)
+            net.advertise_external_route(e2, Prefix::from(0), vec![AsId(65102), AsId(65200)], None, None)
                 .unwrap();
-            net.advertise_external_route(e3, Prefix(0), vec![AsId(65103), AsId(65200)], None, None)
+            net.advertise_external_route(e3, Prefix::from(0), vec![AsId(65103), AsId(65200)], None, None)
                 .unwrap();
         } else if initial_variant == 2
             || initial_variant == 3
@@ -156,12 +156,12 @@ impl ExampleNetwork for AbileneNetwork {
             || initial_variant == 5
             || initial_variant == 6
         {
-            let p_a = Prefix(1);
-            let p_b = Prefix(2);
-            let p_c = Prefix(3);
-            let p_d = Prefix(4);
-            let p_e = Prefix(5);
-            let p_i = Prefix(0);
+            let p_a = Prefix::from(1);
+            let p_b = Prefix::from(2);
+            let p_c = Prefix::from(3);
+            let p_d = Prefix::from(4);
+            let p_e = Prefix::from(5);
+            let p_i = Prefix::from(0);
 
             let as_a = AsId(65101);
             let as_b = AsId(65102);
@@ -813,7 +813,7 @@ impl ExampleNetwork for AbileneNetwork {
         if variant == 0 || variant == 1 {
             let routers = vec![se, dv, hs, ks, ip, at, dc, ny, ch /*sv, la*/];
 
-            let p = Prefix(0);
+            let p = Prefix::from(0);
 
             let initial_prop_vars = routers
                 .iter()
@@ -870,7 +870,7 @@ impl ExampleNetwork for AbileneNetwork {
             HardPolicy::reachability(net.get_routers().iter(), net.get_known_prefixes().iter())
         } else if variant == 3 {
             // connectivity, for peer C, no traffic is allowed to shift
-            let p_a = Prefix(1);
+            let p_a = Prefix::from(1);
 
             let prop_vars = iproduct!(net.get_routers().iter(), net.get_known_prefixes().iter())
                 .map(|(&r, &p)| {
@@ -913,8 +913,8 @@ impl ExampleNetwork for AbileneNetwork {
             HardPolicy::globally(prop_vars)
         } else if variant == 4 {
             // connectivity, for peer C, no traffic is allowed to shift
-            let p_a = Prefix(1);
-            let p_b = Prefix(2);
+            let p_a = Prefix::from(1);
+            let p_b = Prefix::from(2);
 
             let prop_vars = iproduct!(net.get_routers().iter(), net.get_known_prefixes().iter())
                 .map(|(&r, &p)| {
@@ -983,9 +983,9 @@ impl ExampleNetwork for AbileneNetwork {
             HardPolicy::globally(prop_vars)
         } else if variant == 5 {
             // connectivity, for peer C, no traffic is allowed to shift
-            let p_a = Prefix(1);
-            let p_b = Prefix(2);
-            let p_c = Prefix(3);
+            let p_a = Prefix::from(1);
+            let p_b = Prefix::from(2);
+            let p_c = Prefix::from(3);
 
             let prop_vars = iproduct!(net.get_routers().iter(), net.get_known_prefixes().iter())
                 .map(|(&r, &p)| {
@@ -1079,12 +1079,12 @@ impl ExampleNetwork for AbileneNetwork {
 
             HardPolicy::globally(prop_vars)
         } else if variant == 6 {
-            let p_a = Prefix(1);
-            let p_b = Prefix(2);
-            let p_c = Prefix(3);
-            let p_d = Prefix(4);
-            let p_e = Prefix(5);
-            let p_i = Prefix(0);
+            let p_a = Prefix::from(1);
+            let p_b = Prefix::from(2);
+            let p_c = Prefix::from(3);
+            let p_d = Prefix::from(4);
+            let p_e = Prefix::from(5);
+            let p_i = Prefix::from(0);
 
             let se_ = Fix(se);
             let sv_ = Fix(sv);
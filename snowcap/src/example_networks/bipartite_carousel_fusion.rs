@@ -135,16 +135,16 @@ where
         net.set_config(&cf).unwrap();
 
         // advertise prefixes
-        net.advertise_external_route(ex, Prefix(0), vec![AsId(65100), AsId(65534)], None, None)
+        net.advertise_external_route(ex, Prefix::from(0), vec![AsId(65100), AsId(65534)], None, None)
             .unwrap();
-        net.advertise_external_route(ex, Prefix(1), vec![AsId(65100), AsId(65535)], None, None)
+        net.advertise_external_route(ex, Prefix::from(1), vec![AsId(65100), AsId(65535)], None, None)
             .unwrap();
 
         for e in all_e {
             let e_as = net.get_device(e).unwrap_external().as_id();
-            net.advertise_external_route(e, Prefix(0), vec![e_as, AsId(65534)], None, None)
+            net.advertise_external_route(e, Prefix::from(0), vec![e_as, AsId(65534)], None, None)
                 .unwrap();
-            net.advertise_external_route(e, Prefix(1), vec![e_as, AsId(65535)], None, None)
+            net.advertise_external_route(e, Prefix::from(1), vec![e_as, AsId(65535)], None, None)
                 .unwrap();
         }
 
@@ -73,21 +73,21 @@ impl ExampleNetwork for CarouselGadget {
         let ca = Self::initial_config(&net, initial_variant);
         net.set_config(&ca).unwrap();
 
-        net.advertise_external_route(er, Prefix(1), vec![AsId(65100), AsId(65201)], None, None)
+        net.advertise_external_route(er, Prefix::from(1), vec![AsId(65100), AsId(65201)], None, None)
             .unwrap();
-        net.advertise_external_route(er, Prefix(2), vec![AsId(65100), AsId(65202)], None, None)
+        net.advertise_external_route(er, Prefix::from(2), vec![AsId(65100), AsId(65202)], None, None)
             .unwrap();
-        net.advertise_external_route(e1, Prefix(1), vec![AsId(65101), AsId(65201)], None, None)
+        net.advertise_external_route(e1, Prefix::from(1), vec![AsId(65101), AsId(65201)], None, None)
             .unwrap();
-        net.advertise_external_route(e2, Prefix(1), vec![AsId(65102), AsId(65201)], None, None)
+        net.advertise_external_route(e2, Prefix::from(1), vec![AsId(65102), AsId(65201)], None, None)
             .unwrap();
-        net.advertise_external_route(e2, Prefix(2), vec![AsId(65102), AsId(65202)], None, None)
+        net.advertise_external_route(e2, Prefix::from(2), vec![AsId(65102), AsId(65202)], None, None)
             .unwrap(); //
-        net.advertise_external_route(e3, Prefix(1), vec![AsId(65103), AsId(65201)], None, None)
+        net.advertise_external_route(e3, Prefix::from(1), vec![AsId(65103), AsId(65201)], None, None)
             .unwrap();
-        net.advertise_external_route(e3, Prefix(2), vec![AsId(65103), AsId(65202)], None, None)
+        net.advertise_external_route(e3, Prefix::from(2), vec![AsId(65103), AsId(65202)], None, None)
             .unwrap();
-        net.advertise_external_route(e4, Prefix(2), vec![AsId(65104), AsId(65202)], None, None)
+        net.advertise_external_route(e4, Prefix::from(2), vec![AsId(65104), AsId(65202)], None, None)
             .unwrap();
 
         net
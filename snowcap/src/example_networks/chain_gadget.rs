@@ -79,9 +79,9 @@ where
         net.set_config(&cf).unwrap();
 
         // advertise prefixes
-        net.advertise_external_route(e0, Prefix(0), vec![AsId(65100), AsId(65200)], None, None)
+        net.advertise_external_route(e0, Prefix::from(0), vec![AsId(65100), AsId(65200)], None, None)
             .unwrap();
-        net.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None)
+        net.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None)
             .unwrap();
 
         net
@@ -251,9 +251,9 @@ where
         net.set_config(&cf).unwrap();
 
         // advertise prefixes
-        net.advertise_external_route(e0, Prefix(0), vec![AsId(65100), AsId(65200)], None, None)
+        net.advertise_external_route(e0, Prefix::from(0), vec![AsId(65100), AsId(65200)], None, None)
             .unwrap();
-        net.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None)
+        net.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None)
             .unwrap();
 
         net
@@ -398,6 +398,6 @@ mod test {
                 .build(),
         }))
         .unwrap();
-        assert!(net.get_route(r1, Prefix(0)).is_err());
+        assert!(net.get_route(r1, Prefix::from(0)).is_err());
     }
 }
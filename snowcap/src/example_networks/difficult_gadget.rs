@@ -107,11 +107,11 @@ impl ExampleNetwork for DifficultGadgetMinimal {
         net.set_config(&cf).unwrap();
 
         // advertise prefixes
-        net.advertise_external_route(ex, Prefix(0), vec![AsId(65100), AsId(65200)], None, None)
+        net.advertise_external_route(ex, Prefix::from(0), vec![AsId(65100), AsId(65200)], None, None)
             .unwrap();
-        net.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None)
+        net.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None)
             .unwrap();
-        net.advertise_external_route(e2, Prefix(0), vec![AsId(65102), AsId(65200)], None, None)
+        net.advertise_external_route(e2, Prefix::from(0), vec![AsId(65102), AsId(65200)], None, None)
             .unwrap();
 
         net
@@ -300,11 +300,11 @@ impl ExampleNetwork for DifficultGadgetComplete {
         net.set_config(&cf).unwrap();
 
         // advertise prefixes
-        net.advertise_external_route(ex, Prefix(0), vec![AsId(65100), AsId(65200)], None, None)
+        net.advertise_external_route(ex, Prefix::from(0), vec![AsId(65100), AsId(65200)], None, None)
             .unwrap();
-        net.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None)
+        net.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None)
             .unwrap();
-        net.advertise_external_route(e2, Prefix(0), vec![AsId(65102), AsId(65200)], None, None)
+        net.advertise_external_route(e2, Prefix::from(0), vec![AsId(65102), AsId(65200)], None, None)
             .unwrap();
 
         net
@@ -552,15 +552,15 @@ where
         net.set_config(&cf).unwrap();
 
         // advertise prefixes
-        net.advertise_external_route(ex, Prefix(0), vec![AsId(65100), AsId(65200)], None, None)
+        net.advertise_external_route(ex, Prefix::from(0), vec![AsId(65100), AsId(65200)], None, None)
             .unwrap();
 
         for i in 0..R::get_count() {
             let e1 = net.get_router_id(&format!("{:02}_e1", i)).unwrap();
             let e2 = net.get_router_id(&format!("{:02}_e2", i)).unwrap();
-            net.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None)
+            net.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None)
                 .unwrap();
-            net.advertise_external_route(e2, Prefix(0), vec![AsId(65102), AsId(65200)], None, None)
+            net.advertise_external_route(e2, Prefix::from(0), vec![AsId(65102), AsId(65200)], None, None)
                 .unwrap();
         }
 
@@ -85,9 +85,9 @@ impl ExampleNetwork for FirewallNet {
         net.set_config(&cf).unwrap();
 
         // advertise prefixes
-        net.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None)
+        net.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None)
             .unwrap();
-        net.advertise_external_route(e2, Prefix(0), vec![AsId(65102), AsId(65200)], None, None)
+        net.advertise_external_route(e2, Prefix::from(0), vec![AsId(65102), AsId(65200)], None, None)
             .unwrap();
 
         net
@@ -157,7 +157,7 @@ impl ExampleNetwork for FirewallNet {
         }
 
         if variant == 1 {
-            c.add(StaticRoute { router: rx, prefix: Prefix(0), target: r1 }).unwrap();
+            c.add(StaticRoute { router: rx, prefix: Prefix::from(0), target: r1 }).unwrap();
         }
 
         c
@@ -227,7 +227,7 @@ impl ExampleNetwork for FirewallNet {
         }
 
         if variant == 1 {
-            c.add(StaticRoute { router: rx, prefix: Prefix(0), target: r1 }).unwrap();
+            c.add(StaticRoute { router: rx, prefix: Prefix::from(0), target: r1 }).unwrap();
         }
 
         c
@@ -243,8 +243,8 @@ impl ExampleNetwork for FirewallNet {
             let r6 = net.get_router_id("r6").unwrap();
             HardPolicy::new(
                 vec![
-                    Condition::Reachable(rx, Prefix(0), Some(PathCondition::Edge(r2, r6))),
-                    Condition::Reachable(rx, Prefix(0), Some(PathCondition::Edge(r1, r4))),
+                    Condition::Reachable(rx, Prefix::from(0), Some(PathCondition::Edge(r2, r6))),
+                    Condition::Reachable(rx, Prefix::from(0), Some(PathCondition::Edge(r1, r4))),
                 ],
                 LTLModal::Until(Box::new(0), Box::new(LTLModal::Globally(Box::new(1)))),
             )
@@ -256,11 +256,11 @@ impl ExampleNetwork for FirewallNet {
             let r6 = net.get_router_id("r6").unwrap();
             HardPolicy::new(
                 vec![
-                    Condition::Reachable(rx, Prefix(0), Some(PathCondition::Edge(r2, r6))),
-                    Condition::Reachable(rx, Prefix(0), Some(PathCondition::Edge(r1, r4))),
+                    Condition::Reachable(rx, Prefix::from(0), Some(PathCondition::Edge(r2, r6))),
+                    Condition::Reachable(rx, Prefix::from(0), Some(PathCondition::Edge(r1, r4))),
                     Condition::TransientPath(
                         rx,
-                        Prefix(0),
+                        Prefix::from(0),
                         PathCondition::Or(vec![
                             PathCondition::Edge(r2, r6),
                             PathCondition::Edge(r1, r4),
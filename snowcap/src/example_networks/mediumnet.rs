@@ -118,29 +118,29 @@ impl ExampleNetwork for MediumNet {
         let cs = Self::initial_config(&net, initial_variant);
         net.set_config(&cs).unwrap();
 
-        net.advertise_external_route(e1, Prefix(1), vec![AsId(65101), AsId(65201)], None, None)
+        net.advertise_external_route(e1, Prefix::from(1), vec![AsId(65101), AsId(65201)], None, None)
             .unwrap();
-        net.advertise_external_route(e2, Prefix(1), vec![AsId(65102), AsId(65201)], None, None)
+        net.advertise_external_route(e2, Prefix::from(1), vec![AsId(65102), AsId(65201)], None, None)
             .unwrap();
-        net.advertise_external_route(e2, Prefix(2), vec![AsId(65102), AsId(65202)], None, None)
+        net.advertise_external_route(e2, Prefix::from(2), vec![AsId(65102), AsId(65202)], None, None)
             .unwrap();
-        net.advertise_external_route(e3, Prefix(2), vec![AsId(65103), AsId(65202)], None, None)
+        net.advertise_external_route(e3, Prefix::from(2), vec![AsId(65103), AsId(65202)], None, None)
             .unwrap();
-        net.advertise_external_route(e4, Prefix(2), vec![AsId(65104), AsId(65202)], None, None)
+        net.advertise_external_route(e4, Prefix::from(2), vec![AsId(65104), AsId(65202)], None, None)
             .unwrap();
-        net.advertise_external_route(e4, Prefix(3), vec![AsId(65104), AsId(65203)], None, None)
+        net.advertise_external_route(e4, Prefix::from(3), vec![AsId(65104), AsId(65203)], None, None)
             .unwrap();
-        net.advertise_external_route(e5, Prefix(3), vec![AsId(65105), AsId(65203)], None, None)
+        net.advertise_external_route(e5, Prefix::from(3), vec![AsId(65105), AsId(65203)], None, None)
             .unwrap();
-        net.advertise_external_route(e6, Prefix(3), vec![AsId(65106), AsId(65203)], None, None)
+        net.advertise_external_route(e6, Prefix::from(3), vec![AsId(65106), AsId(65203)], None, None)
             .unwrap();
-        net.advertise_external_route(e6, Prefix(4), vec![AsId(65106), AsId(65204)], None, None)
+        net.advertise_external_route(e6, Prefix::from(4), vec![AsId(65106), AsId(65204)], None, None)
             .unwrap();
-        net.advertise_external_route(e7, Prefix(4), vec![AsId(65107), AsId(65204)], None, None)
+        net.advertise_external_route(e7, Prefix::from(4), vec![AsId(65107), AsId(65204)], None, None)
             .unwrap();
-        net.advertise_external_route(e8, Prefix(4), vec![AsId(65108), AsId(65204)], None, None)
+        net.advertise_external_route(e8, Prefix::from(4), vec![AsId(65108), AsId(65204)], None, None)
             .unwrap();
-        net.advertise_external_route(e8, Prefix(1), vec![AsId(65108), AsId(65201)], None, None)
+        net.advertise_external_route(e8, Prefix::from(1), vec![AsId(65108), AsId(65201)], None, None)
             .unwrap();
 
         net
@@ -53,9 +53,9 @@ impl ExampleNetwork for SimpleNet {
         net.set_config(&cf).unwrap();
 
         // advertise prefixes
-        net.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None)
+        net.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None)
             .unwrap();
-        net.advertise_external_route(e4, Prefix(0), vec![AsId(65104), AsId(65200)], None, None)
+        net.advertise_external_route(e4, Prefix::from(0), vec![AsId(65104), AsId(65200)], None, None)
             .unwrap();
 
         net
@@ -63,13 +63,13 @@ impl ExampleNetwork for SmallNet {
         let cf = Self::initial_config(&net, initial_variant);
         net.set_config(&cf).unwrap();
 
-        net.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None)
+        net.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None)
             .unwrap();
-        net.advertise_external_route(e2, Prefix(0), vec![AsId(65102), AsId(65200)], None, None)
+        net.advertise_external_route(e2, Prefix::from(0), vec![AsId(65102), AsId(65200)], None, None)
             .unwrap();
-        net.advertise_external_route(e3, Prefix(0), vec![AsId(65103), AsId(65200)], None, None)
+        net.advertise_external_route(e3, Prefix::from(0), vec![AsId(65103), AsId(65200)], None, None)
             .unwrap();
-        net.advertise_external_route(e4, Prefix(0), vec![AsId(65104), AsId(65200)], None, None)
+        net.advertise_external_route(e4, Prefix::from(0), vec![AsId(65104), AsId(65200)], None, None)
             .unwrap();
 
         net
@@ -51,12 +51,12 @@ impl<R: Repetitions> ExampleNetwork for VariableAbileneNetwork<R> {
     fn net(initial_variant: usize) -> Network {
         let mut net = Network::new();
 
-        let p_a = Prefix(1);
-        let p_b = Prefix(2);
-        let p_c = Prefix(3);
-        let p_d = Prefix(4);
-        let p_e = Prefix(5);
-        let p_i = Prefix(0);
+        let p_a = Prefix::from(1);
+        let p_b = Prefix::from(2);
+        let p_c = Prefix::from(3);
+        let p_d = Prefix::from(4);
+        let p_e = Prefix::from(5);
+        let p_i = Prefix::from(0);
 
         let as_a = AsId(65101);
         let as_b = AsId(65102);
@@ -623,7 +623,7 @@ impl<R: Repetitions> ExampleNetwork for VariableAbileneNetwork<R> {
         let ny = net.get_router_id("NewYork").unwrap();
         let ch = net.get_router_id("Chicago").unwrap();
         let routers = vec![sv, se, dv, la, hs, ks, ip, at, dc, ny, ch];
-        let prefixes = vec![Prefix(1), Prefix(2), Prefix(3), Prefix(4), Prefix(5), Prefix(0)];
+        let prefixes = vec![Prefix::from(1), Prefix::from(2), Prefix::from(3), Prefix::from(4), Prefix::from(5), Prefix::from(0)];
 
         for &p in prefixes.iter() {
             for &r in routers.iter() {
@@ -21,15 +21,19 @@
 //! false.
 
 use super::{prepare_loop_path, PolicyError};
+use crate::netsim::acl::TrafficClass;
 use crate::netsim::{ForwardingState, Network, NetworkError, Prefix, RouterId};
 use crate::netsim::types::Destination;
 
 use itertools::iproduct;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt;
-use std::time::Instant;
 
 /// Condition that can be checked for either being true or false.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Condition {
     /// Condition that a router can reach a prefix, with optional conditions to the path that is
     /// taken.
@@ -41,6 +45,12 @@ pub enum Condition {
     NotReachable(RouterId, Prefix),
     /// Not reachable within IGP
     NotReachableIGP(RouterId, RouterId),
+    /// Condition that the traffic class sent by the router reaches the prefix, without being
+    /// dropped by any access list.
+    ReachableTraffic(RouterId, Prefix, TrafficClass),
+    /// Condition that the traffic class sent by the router does not reach the prefix, either
+    /// because it is dropped by an access list, or because there is no route.
+    NotReachableTraffic(RouterId, Prefix, TrafficClass),
     /// Condition that the router has a route towards the prefix, even if every possible link in
     /// the network fails. Optionally, you can pass in a path condition, requiring the path when
     /// one of the links fail.
@@ -55,10 +65,10 @@ impl fmt::Display for Condition {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Reachable(r, p, Some(c)) => {
-                write!(f, "Reachability(r{}, prefix {}, condition {})", r.index(), p.0, c)
+                write!(f, "Reachability(r{}, prefix {}, condition {})", r.index(), p, c)
             }
             Self::Reachable(r, p, None) => {
-                write!(f, "Reachability(r{}, prefix {})", r.index(), p.0)
+                write!(f, "Reachability(r{}, prefix {})", r.index(), p)
             }
             Self::ReachableIGP(r1, r2, Some(c)) => {
                 write!(f, "ReachabilityIGP(r{}, r{}, condition{})", r1.index(), r2.index(), c)
@@ -66,14 +76,20 @@ impl fmt::Display for Condition {
             Self::ReachableIGP(r1, r2, None) => {
                 write!(f, "ReachabilityIGP(r{}, r{})", r1.index(), r2.index())
             }
-            Self::NotReachable(r, p) => write!(f, "Isolation(r{}, prefix {})", r.index(), p.0),
+            Self::NotReachable(r, p) => write!(f, "Isolation(r{}, prefix {})", r.index(), p),
             Self::NotReachableIGP(r1, r2) => write!(f, "Isolation(r{}, r{})", r1.index(), r2.index()),
+            Self::ReachableTraffic(r, p, t) => {
+                write!(f, "Reachability(r{}, prefix {}, traffic {})", r.index(), p, t)
+            }
+            Self::NotReachableTraffic(r, p, t) => {
+                write!(f, "Isolation(r{}, prefix {}, traffic {})", r.index(), p, t)
+            }
             Self::Reliable(r, p, Some(c)) => {
-                write!(f, "Reliability(r{}, prefix {}, condition {})", r.index(), p.0, c)
+                write!(f, "Reliability(r{}, prefix {}, condition {})", r.index(), p, c)
             }
-            Self::Reliable(r, p, None) => write!(f, "Reliability(r{}, prefix {})", r.index(), p.0),
+            Self::Reliable(r, p, None) => write!(f, "Reliability(r{}, prefix {})", r.index(), p),
             Self::TransientPath(r, p, c) => {
-                write!(f, "Transient(r{}, prefix {}, condition {})", r.index(), p.0, c)
+                write!(f, "Transient(r{}, prefix {}, condition {})", r.index(), p, c)
             }
         }
     }
@@ -86,11 +102,11 @@ impl Condition {
             Self::Reachable(r, p, Some(c)) => format!(
                 "Reachability({}, prefix {}, condition {})",
                 net.get_router_name(*r).unwrap(),
-                p.0,
+                p,
                 c.repr_with_name(net)
             ),
             Self::Reachable(r, p, None) => {
-                format!("Reachability({}, prefix {})", net.get_router_name(*r).unwrap(), p.0)
+                format!("Reachability({}, prefix {})", net.get_router_name(*r).unwrap(), p)
             }
             Self::ReachableIGP(r1, r2, Some(c)) => format!(
                 "ReachabilityIGP(router1 {}, router2 {}, condition {})", 
@@ -104,30 +120,45 @@ impl Condition {
                 net.get_router_name(*r2).unwrap()
             ),
             Self::NotReachable(r, p) => {
-                format!("Isolation({}, prefix {})", net.get_router_name(*r).unwrap(), p.0)
+                format!("Isolation({}, prefix {})", net.get_router_name(*r).unwrap(), p)
             }
             Self::NotReachableIGP(r1, r2) => {
                 format!("Isolation(router1 {}, router2 {})", net.get_router_name(*r1).unwrap(), net.get_router_name(*r2).unwrap())
             }
+            Self::ReachableTraffic(r, p, t) => format!(
+                "Reachability({}, prefix {}, traffic {})",
+                net.get_router_name(*r).unwrap(),
+                p,
+                t
+            ),
+            Self::NotReachableTraffic(r, p, t) => format!(
+                "Isolation({}, prefix {}, traffic {})",
+                net.get_router_name(*r).unwrap(),
+                p,
+                t
+            ),
             Self::Reliable(r, p, Some(c)) => format!(
                 "Reliability({}, prefix {}, condition {})",
                 net.get_router_name(*r).unwrap(),
-                p.0,
+                p,
                 c.repr_with_name(net)
             ),
             Self::Reliable(r, p, None) => {
-                format!("Reliability({}, prefix {})", net.get_router_name(*r).unwrap(), p.0)
+                format!("Reliability({}, prefix {})", net.get_router_name(*r).unwrap(), p)
             }
             Self::TransientPath(r, p, c) => format!(
                 "Transient({}, prefix {}, condition {})",
                 net.get_router_name(*r).unwrap(),
-                p.0,
+                p,
                 c
             ),
         }
     }
 
-    /// Check the the condition, returning a policy error if it is violated.
+    /// Check the the condition, returning a policy error if it is violated. If routers forward
+    /// traffic over multiple equal-cost next hops, every path in the forwarding DAG is checked.
+    /// Reachability (and the path condition) must hold for every path, and isolation requires that
+    /// no path reaches the destination.
     ///
     /// **Warning**: reliability or transient condition is not checked here, but will just return
     /// `Ok`.
@@ -135,76 +166,74 @@ impl Condition {
         match self {
             // test between every pair of nodes
             Self::Reachable(r, p, c) => {
-                let get_route_start = Instant::now();
-                let r_result = fw_state.get_route_new(*r, Destination::BGP(*p));
-                let get_route_end = get_route_start.elapsed();
-                match r_result {
-                    // for every pair of routers in the network
-                    Ok(path) => match c {
-                        // if there is no condition, then just return
-                        None => Ok(()),
-                        // if there is some condition, then call the check function 
-                        // to check if the condition is violated
-                        Some(c) => c.check(&path, *p),
-                    },
-                    Err(NetworkError::ForwardingLoop(path)) => {
-                        Err(PolicyError::ForwardingLoop { path: prepare_loop_path(path), dest: Destination::BGP(*p) })
-                    }
-                    Err(NetworkError::ForwardingBlackHole(path)) => {
-                        Err(PolicyError::BlackHole { router: *path.last().unwrap(), dest: Destination::BGP(*p) })
-                    }
-                    Err(e) => panic!("Unrecoverable error detected: {}", e),
+                let dest = Destination::BGP(*p);
+                fw_state
+                    .check_all_routes(*r, dest.clone())
+                    .map_err(|e| route_error(e, *r, dest))?;
+                // every path is valid, so the path condition can be checked on the DAG
+                match c {
+                    Some(c) => c.check_all_paths(fw_state, *r, *p),
+                    None => Ok(()),
+                }
+            }
+            Self::ReachableTraffic(r, p, t) => {
+                let dest = Destination::BGP(*p);
+                fw_state
+                    .check_all_routes_of_class(*r, dest.clone(), t)
+                    .map_err(|e| route_error(e, *r, dest))
+            }
+            Self::NotReachableTraffic(r, p, t) => {
+                // every route must be dropped, by an access list or otherwise
+                match fw_state.find_valid_route_of_class(*r, Destination::BGP(*p), t) {
+                    Some(path) => Err(PolicyError::UnallowedPathExists {
+                        router: *r,
+                        dest: Destination::BGP(*p),
+                        path,
+                    }),
+                    None => Ok(()),
                 }
             }
-            Self::ReachableIGP(r1, r2, c) => {
+            Self::ReachableIGP(r1, r2, _c) => {
                 // TODO
                 // need to update the get_route function
                 // !!! What if instead of checking every invariance
                 // !!! we identify invariances that might be violated
                 // !!! and check those? Possibly reduce complexity
                 println!("Checking IGP reachability {:?} {:?}", r1, r2);
-                let r_result = fw_state.get_route_new(*r1, Destination::IGP(*r2));
-                match r_result {
-                    Ok(path) => match c {
-                        // no path condition
-                        None => Ok(()),
-                        // path condition exists
-                        // not yet implemented
-                        Some(cond) => Ok(())
-                    },
-                    // Err(NetworkError::ForwardingLoop(path)) => {
-                    //     Err(PolicyError::ForwardingLoop { path: prepare_loop_path(path), prefix: *p })
-                    // }
-                    // Err(NetworkError::ForwardingBlackHole(path)) => {
-                    //     Err(PolicyError::BlackHole { router: *path.last().unwrap(), prefix: *p })
-                    // }
+                match fw_state.check_all_routes(*r1, Destination::IGP(*r2)) {
+                    // path conditions are not yet implemented for IGP reachability
+                    Ok(()) => Ok(()),
                     Err(NetworkError::AccessDenied(router)) => {
-                        Err(PolicyError::AccessDenied {router1: *r1, router2: router})
+                        Err(PolicyError::AccessDenied { router1: router, router2: *r1 })
                     }
                     // not yet implemented other types of errors
                     Err(e) => panic!("Unrecoverable error detected: {}", e),
                 }
             }
-            Self::NotReachable(r, p) => match fw_state.get_route_new(*r, Destination::BGP(*p)) {
-                // no path available then return ok
-                Err(NetworkError::ForwardingBlackHole(_)) => Ok(()),
-                Err(NetworkError::ForwardingLoop(_)) => Ok(()),
-                Err(NetworkError::AccessDenied(_)) => Ok(()),
-                Err(e) => panic!("Unrecoverable error detected: {}", e),
-                Ok(path) => Err(PolicyError::UnallowedPathExists { router: *r, dest: Destination::BGP(*p), path }),
-            },
+            Self::NotReachable(r, p) => {
+                let class = TrafficClass::default();
+                match fw_state.find_valid_route_of_class(*r, Destination::BGP(*p), &class) {
+                    Some(path) => Err(PolicyError::UnallowedPathExists {
+                        router: *r,
+                        dest: Destination::BGP(*p),
+                        path,
+                    }),
+                    None => Ok(()),
+                }
+            }
             Self::NotReachableIGP(r1, r2) => {
                 // TODO
                 println!("Checking isolation {:?} {:?}", r1, r2);
-                let r_result = fw_state.get_route_new(*r1, Destination::IGP(*r2));
-                match r_result {
-                    Err(NetworkError::ForwardingBlackHole(_)) => Ok(()),
-                    Err(NetworkError::ForwardingLoop(_)) => Ok(()),
-                    Err(NetworkError::AccessDenied(_)) => Ok(()),
-                    Err(e) => panic!("Unrecoverable error detected: {}", e),
-                    Ok(path) => Err(PolicyError::UnallowedPathExists {router: *r1, dest: Destination::IGP(*r2), path}),
+                let class = TrafficClass::default();
+                match fw_state.find_valid_route_of_class(*r1, Destination::IGP(*r2), &class) {
+                    Some(path) => Err(PolicyError::UnallowedPathExists {
+                        router: *r1,
+                        dest: Destination::IGP(*r2),
+                        path,
+                    }),
+                    None => Ok(()),
                 }
-             }
+            }
             Self::Reliable(_, _, _) => Ok(()),
             Self::TransientPath(_, _, _) => Ok(()),
         }
@@ -227,6 +256,8 @@ impl Condition {
             Condition::ReachableIGP(r1, _, _) => *r1,
             Condition::NotReachable(r, _) => *r,
             Condition::NotReachableIGP(r1, _) => *r1,
+            Condition::ReachableTraffic(r, _, _) => *r,
+            Condition::NotReachableTraffic(r, _, _) => *r,
             Condition::Reliable(r, _, _) => *r,
             Condition::TransientPath(r, _, _) => *r,
         }
@@ -237,16 +268,33 @@ impl Condition {
         match self {
             Condition::Reachable(_, p, _) => *p,
             Condition::NotReachable(_, p) => *p,
+            Condition::ReachableTraffic(_, p, _) => *p,
+            Condition::NotReachableTraffic(_, p, _) => *p,
             Condition::Reliable(_, p, _) => *p,
             Condition::TransientPath(_, p, _) => *p,
-            _ => Prefix(0)
+            _ => Prefix::from(0)
         }
     }
 }
 
+/// Transform the error of a path from `router` towards `dest` into the policy error.
+fn route_error(e: NetworkError, router: RouterId, dest: Destination) -> PolicyError {
+    match e {
+        NetworkError::ForwardingLoop(path) => {
+            PolicyError::ForwardingLoop { path: prepare_loop_path(path), dest }
+        }
+        NetworkError::ForwardingBlackHole(path) => {
+            PolicyError::BlackHole { router: *path.last().unwrap(), dest }
+        }
+        NetworkError::AccessDenied(r) => PolicyError::AccessDenied { router1: r, router2: router },
+        e => panic!("Unrecoverable error detected: {}", e),
+    }
+}
+
 /// Condition on the path, which may be either to require that the path passes through a specific
 /// node, or that the path traverses a specific edge.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PathCondition {
     /// Condition that a specific node must be traversed by the path
     Node(RouterId),
@@ -459,6 +507,65 @@ impl PathCondition {
         }
     }
 
+    /// Check the path condition on every path from `router` towards `prefix` in the forwarding DAG,
+    /// and return the error of the first path that violates it. The forwarding state must already
+    /// be checked to reach the destination on every path. Instead of enumerating the paths, the
+    /// state of the condition is tracked while walking the DAG, and every pair of router and state
+    /// is visited at most once.
+    pub fn check_all_paths(
+        &self,
+        fw_state: &ForwardingState,
+        router: RouterId,
+        prefix: Prefix,
+    ) -> Result<(), PolicyError> {
+        let state = PathState::new(self).step(self, None, router);
+        let mut done = HashSet::new();
+        self.check_paths_from(fw_state, prefix, &mut vec![router], state, &mut done)
+    }
+
+    /// Depth-first search through the forwarding DAG, starting at the last router of `path`, where
+    /// `state` is the state of the condition after reading `path`. Pairs of router and state in
+    /// `done` were already visited, and every path starting at them satisfies the condition.
+    fn check_paths_from(
+        &self,
+        fw_state: &ForwardingState,
+        prefix: Prefix,
+        path: &mut Vec<RouterId>,
+        state: PathState,
+        done: &mut HashSet<(RouterId, PathState)>,
+    ) -> Result<(), PolicyError> {
+        let current_node = *path.last().unwrap();
+        let key = (current_node, state);
+        // forwarding loops are already reported when checking reachability
+        if done.contains(&key) || path[..path.len() - 1].contains(&current_node) {
+            return Ok(());
+        }
+        let next_hops = fw_state.get_next_hops_new(current_node, Destination::BGP(prefix));
+        match next_hops.unwrap_or(&[]) {
+            [] => {}
+            [next_hop, ..] if *next_hop == current_node => {
+                // has arrived at the destination
+                if !key.1.accepts(self) {
+                    return Err(PolicyError::PathCondition {
+                        path: path.clone(),
+                        condition: self.clone(),
+                        dest: Destination::BGP(prefix),
+                    });
+                }
+            }
+            next_hops => {
+                for next_hop in next_hops {
+                    let next_state = key.1.step(self, Some(current_node), *next_hop);
+                    path.push(*next_hop);
+                    self.check_paths_from(fw_state, prefix, path, next_state, done)?;
+                    path.pop();
+                }
+            }
+        }
+        done.insert(key);
+        Ok(())
+    }
+
     /// Private function for doing the recursive cnf conversion. The return has the following form:
     /// The first array represents the expressions combined with a logical AND. each of these
     /// elements represent a logical OR. The first array are regular elements, and the second array
@@ -524,6 +631,7 @@ impl Into<PathConditionCNF> for PathCondition {
 
 /// Part of the positional waypointing argument
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Waypoint {
     /// The next node is always allowed, no matter what it is. This is equivalent to the regular
     /// expression `.` (UNIX style)
@@ -556,6 +664,156 @@ impl Waypoint {
     }
 }
 
+/// State of a path condition after reading the beginning of a path, which has the same structure
+/// as the condition itself. It is used to check the condition on every path of the forwarding DAG
+/// at once, and it matches the result of [`PathCondition::check`]. Sub-conditions whose result
+/// no longer depends on the rest of the path are collapsed, such that paths which only differ in
+/// how a condition was satisfied end up in the same state.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum PathState {
+    /// The result is the same for every remaining path.
+    Done(bool),
+    /// The node or edge was not traversed yet.
+    Unseen,
+    /// Combination of the states of the sub-conditions.
+    Group(Vec<PathState>),
+    /// Sub-condition of an inverted condition
+    Not(Box<PathState>),
+    /// Position in the waypoints of a positional condition
+    Positional(PositionalState),
+}
+
+/// State of a positional condition, following the algorithm in [`PathCondition::check`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum PositionalState {
+    /// The next node must match the waypoint at the given position.
+    Next(usize),
+    /// After a star, skip nodes until the waypoint at the given position matches.
+    Star(usize),
+    /// The remaining path does not matter.
+    Accept,
+    /// The path does not match.
+    Reject,
+}
+
+impl PathState {
+    /// Returns the state of `c` before reading any node.
+    fn new(c: &PathCondition) -> Self {
+        match c {
+            PathCondition::Node(_) | PathCondition::Edge(_, _) => Self::Unseen,
+            PathCondition::And(v) | PathCondition::Or(v) => {
+                Self::group(c, v.iter().map(Self::new).collect())
+            }
+            PathCondition::Not(c) => Self::not(Self::new(c)),
+            PathCondition::Positional(v) => {
+                Self::positional(PositionalState::Next(0).skip_stars(v))
+            }
+        }
+    }
+
+    /// Returns the state of `c` after reading `node`, where `prev` is the previously read node.
+    fn step(&self, c: &PathCondition, prev: Option<RouterId>, node: RouterId) -> Self {
+        match (self, c) {
+            (Self::Done(_), _) => self.clone(),
+            (Self::Unseen, PathCondition::Node(v)) if node == *v => Self::Done(true),
+            (Self::Unseen, PathCondition::Edge(x, y)) if prev == Some(*x) && node == *y => {
+                Self::Done(true)
+            }
+            (Self::Unseen, _) => Self::Unseen,
+            (Self::Group(s), PathCondition::And(v)) | (Self::Group(s), PathCondition::Or(v)) => {
+                let s = s.iter().zip(v.iter()).map(|(s, c)| s.step(c, prev, node)).collect();
+                Self::group(c, s)
+            }
+            (Self::Not(s), PathCondition::Not(c)) => Self::not(s.step(c, prev, node)),
+            (Self::Positional(s), PathCondition::Positional(v)) => {
+                Self::positional(s.step(v, node).skip_stars(v))
+            }
+            _ => unreachable!("The state does not match the path condition"),
+        }
+    }
+
+    /// Returns wether the path read so far satisfies `c`.
+    fn accepts(&self, c: &PathCondition) -> bool {
+        match (self, c) {
+            (Self::Done(x), _) => *x,
+            (Self::Unseen, _) => false,
+            (Self::Group(s), PathCondition::And(v)) => {
+                s.iter().zip(v.iter()).all(|(s, c)| s.accepts(c))
+            }
+            (Self::Group(s), PathCondition::Or(v)) => {
+                s.iter().zip(v.iter()).any(|(s, c)| s.accepts(c))
+            }
+            (Self::Not(s), PathCondition::Not(c)) => !s.accepts(c),
+            (Self::Positional(PositionalState::Next(i)), PathCondition::Positional(v)) => {
+                *i == v.len()
+            }
+            (Self::Positional(_), PathCondition::Positional(_)) => false,
+            _ => unreachable!("The state does not match the path condition"),
+        }
+    }
+
+    /// Combine the states of the sub-conditions of `c`, which is either `And` or `Or`.
+    fn group(c: &PathCondition, s: Vec<Self>) -> Self {
+        // the value that decides the result of the group on its own
+        let dominant = matches!(c, PathCondition::Or(_));
+        if s.contains(&Self::Done(dominant)) {
+            Self::Done(dominant)
+        } else if s.iter().all(|s| s == &Self::Done(!dominant)) {
+            Self::Done(!dominant)
+        } else {
+            Self::Group(s)
+        }
+    }
+
+    /// Invert the state of the sub-condition.
+    fn not(s: Self) -> Self {
+        match s {
+            Self::Done(x) => Self::Done(!x),
+            s => Self::Not(Box::new(s)),
+        }
+    }
+
+    /// Wrap the state of a positional condition.
+    fn positional(s: PositionalState) -> Self {
+        match s {
+            PositionalState::Accept => Self::Done(true),
+            PositionalState::Reject => Self::Done(false),
+            s => Self::Positional(s),
+        }
+    }
+}
+
+impl PositionalState {
+    /// Advance over stars, which do not consume any node. `**` is equal to `*`, and a star at the
+    /// end accepts any remaining path.
+    fn skip_stars(self, v: &[Waypoint]) -> Self {
+        match self {
+            Self::Next(i) if v.get(i) == Some(&Waypoint::Star) => Self::Star(i + 1).skip_stars(v),
+            Self::Star(i) if i == v.len() => Self::Accept,
+            Self::Star(i) if v[i] == Waypoint::Star => Self::Star(i + 1).skip_stars(v),
+            s => s,
+        }
+    }
+
+    /// Returns the state after reading `node`, before skipping the following stars.
+    fn step(self, v: &[Waypoint], node: RouterId) -> Self {
+        match self {
+            Self::Next(i) => match v.get(i) {
+                Some(Waypoint::Any) => Self::Next(i + 1),
+                Some(Waypoint::Fix(n)) if *n == node => Self::Next(i + 1),
+                _ => Self::Reject,
+            },
+            // `*?` is equal to `?*`, so we remain in the star search
+            Self::Star(i) => match v[i] {
+                Waypoint::Fix(n) if n == node => Self::Next(i + 1),
+                Waypoint::Fix(_) => Self::Star(i),
+                _ => Self::Star(i + 1),
+            },
+            s => s,
+        }
+    }
+}
+
 /// Path Condition, expressed in Conjunctive Normal Form (CNF), which is a product of sums, or in
 /// other words, an AND of ORs.
 /// There might be cases, where the PathCondition cannot fully be expressed as a CNF. This is the
@@ -658,56 +916,56 @@ mod test {
     #[test]
     fn path_condition_node() {
         let c = Node(0.into());
-        assert!(c.check(&vec![1.into(), 0.into(), 2.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![2.into(), 1.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![], Prefix(0)).is_err());
+        assert!(c.check(&vec![1.into(), 0.into(), 2.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![2.into(), 1.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
     }
 
     #[test]
     fn path_condition_edge() {
         let c = Edge(0.into(), 1.into());
-        assert!(c.check(&vec![2.into(), 0.into(), 1.into(), 3.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![1.into(), 0.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into(), 2.into(), 1.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![1.into()], Prefix(0)).is_err());
+        assert!(c.check(&vec![2.into(), 0.into(), 1.into(), 3.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![1.into(), 0.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into(), 2.into(), 1.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![1.into()], Prefix::from(0)).is_err());
     }
 
     #[test]
     fn path_condition_not() {
         let c = Not(Box::new(Node(0.into())));
-        assert!(c.check(&vec![1.into(), 0.into(), 2.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![2.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![], Prefix(0)).is_ok());
+        assert!(c.check(&vec![1.into(), 0.into(), 2.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![2.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![], Prefix::from(0)).is_ok());
     }
 
     #[test]
     fn path_condition_or() {
         let c = Or(vec![Node(0.into()), Node(1.into())]);
-        assert!(c.check(&vec![0.into(), 2.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![2.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 2.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![3.into(), 2.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![], Prefix(0)).is_err());
+        assert!(c.check(&vec![0.into(), 2.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![2.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 2.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![3.into(), 2.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
         let c = Or(vec![]);
-        assert!(c.check(&vec![0.into(), 2.into(), 1.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![], Prefix(0)).is_err());
+        assert!(c.check(&vec![0.into(), 2.into(), 1.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
     }
 
     #[test]
     fn path_condition_and() {
         let c = And(vec![Node(0.into()), Node(1.into())]);
-        assert!(c.check(&vec![0.into(), 2.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![2.into(), 1.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into(), 2.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![3.into(), 2.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![], Prefix(0)).is_err());
+        assert!(c.check(&vec![0.into(), 2.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![2.into(), 1.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into(), 2.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![3.into(), 2.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
         let c = And(vec![]);
-        assert!(c.check(&vec![0.into(), 2.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![], Prefix(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 2.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![], Prefix::from(0)).is_ok());
     }
 
     fn test_cnf_equivalence(c: PathCondition, n: usize, num_devices: usize) {
@@ -718,8 +976,8 @@ mod test {
             let mut path: Vec<RouterId> = (0..num_devices).map(|x| (x as u32).into()).collect();
             path.shuffle(&mut rng);
             let path: Vec<RouterId> = path.into_iter().take(rng.next_u32() as usize).collect();
-            assert_eq!(c.check(&path, Prefix(0)).is_ok(), c_cnf.check(&path, Prefix(0)).is_ok());
-            assert_eq!(c.check(&path, Prefix(0)).is_ok(), c_rev.check(&path, Prefix(0)).is_ok());
+            assert_eq!(c.check(&path, Prefix::from(0)).is_ok(), c_cnf.check(&path, Prefix::from(0)).is_ok());
+            assert_eq!(c.check(&path, Prefix::from(0)).is_ok(), c_rev.check(&path, Prefix::from(0)).is_ok());
         }
     }
 
@@ -782,156 +1040,156 @@ mod test {
     #[test]
     fn path_positional_single_any() {
         let c = Positional(vec![Any]);
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_err());
     }
 
     #[test]
     fn path_positional_single_star() {
         let c = Positional(vec![Star]);
-        assert!(c.check(&vec![], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix(0)).is_ok());
+        assert!(c.check(&vec![], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix::from(0)).is_ok());
     }
 
     #[test]
     fn path_positional_single_fix() {
         let c = Positional(vec![Fix(0.into())]);
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![1.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![1.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_err());
     }
 
     #[test]
     fn path_positional_star_any() {
         let c = Positional(vec![Star, Any]);
-        assert!(c.check(&vec![], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix(0)).is_ok());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix::from(0)).is_ok());
         let c = Positional(vec![Any, Star]);
-        assert!(c.check(&vec![], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix(0)).is_ok());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix::from(0)).is_ok());
     }
 
     #[test]
     fn path_positional_star_star() {
         let c = Positional(vec![Star, Star]);
-        assert!(c.check(&vec![], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix(0)).is_ok());
+        assert!(c.check(&vec![], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix::from(0)).is_ok());
     }
 
     #[test]
     fn path_positional_any_any() {
         let c = Positional(vec![Any, Any]);
-        assert!(c.check(&vec![], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix(0)).is_err());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix::from(0)).is_err());
     }
 
     #[test]
     fn path_positional_star_fix() {
         let c = Positional(vec![Star, Fix(0.into())]);
-        assert!(c.check(&vec![], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![1.into(), 0.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![2.into(), 1.into(), 0.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![2.into(), 1.into(), 0.into(), 3.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![2.into(), 1.into(), 3.into()], Prefix(0)).is_err());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![1.into(), 0.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![2.into(), 1.into(), 0.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![2.into(), 1.into(), 0.into(), 3.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![2.into(), 1.into(), 3.into()], Prefix::from(0)).is_err());
     }
 
     #[test]
     fn path_positional_fix_star() {
         let c = Positional(vec![Fix(0.into()), Star]);
-        assert!(c.check(&vec![], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![3.into(), 0.into(), 1.into(), 2.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![3.into(), 1.into(), 2.into()], Prefix(0)).is_err());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![3.into(), 0.into(), 1.into(), 2.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![3.into(), 1.into(), 2.into()], Prefix::from(0)).is_err());
     }
 
     #[test]
     fn path_positional_star_fix_star() {
         let c = Positional(vec![Star, Fix(0.into()), Star]);
-        assert!(c.check(&vec![], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![3.into(), 0.into(), 1.into(), 2.into()], Prefix(0)).is_ok());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![3.into(), 0.into(), 1.into(), 2.into()], Prefix::from(0)).is_ok());
         assert!(c
-            .check(&vec![3.into(), 4.into(), 0.into(), 1.into(), 2.into()], Prefix(0))
+            .check(&vec![3.into(), 4.into(), 0.into(), 1.into(), 2.into()], Prefix::from(0))
             .is_ok());
-        assert!(c.check(&vec![3.into(), 1.into(), 2.into()], Prefix(0)).is_err());
+        assert!(c.check(&vec![3.into(), 1.into(), 2.into()], Prefix::from(0)).is_err());
     }
 
     #[test]
     fn path_positional_star_fix_fix_star() {
         let c = Positional(vec![Star, Fix(0.into()), Fix(1.into()), Star]);
-        assert!(c.check(&vec![], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![3.into(), 0.into(), 1.into(), 2.into()], Prefix(0)).is_ok());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![3.into(), 0.into(), 1.into(), 2.into()], Prefix::from(0)).is_ok());
         assert!(c
-            .check(&vec![3.into(), 4.into(), 0.into(), 1.into(), 2.into()], Prefix(0))
+            .check(&vec![3.into(), 4.into(), 0.into(), 1.into(), 2.into()], Prefix::from(0))
             .is_ok());
-        assert!(c.check(&vec![3.into(), 1.into(), 2.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![3.into(), 0.into(), 2.into(), 1.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![3.into(), 2.into(), 1.into()], Prefix(0)).is_err());
+        assert!(c.check(&vec![3.into(), 1.into(), 2.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![3.into(), 0.into(), 2.into(), 1.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![3.into(), 2.into(), 1.into()], Prefix::from(0)).is_err());
     }
 
     #[test]
     fn path_positional_star_fix_any_fix_star() {
         let c = Positional(vec![Star, Fix(0.into()), Any, Fix(1.into()), Star]);
-        assert!(c.check(&vec![], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![3.into(), 0.into(), 1.into(), 2.into()], Prefix(0)).is_err());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![3.into(), 0.into(), 1.into(), 2.into()], Prefix::from(0)).is_err());
         assert!(c
-            .check(&vec![3.into(), 4.into(), 0.into(), 1.into(), 2.into()], Prefix(0))
+            .check(&vec![3.into(), 4.into(), 0.into(), 1.into(), 2.into()], Prefix::from(0))
             .is_err());
-        assert!(c.check(&vec![3.into(), 1.into(), 2.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![3.into(), 0.into(), 2.into(), 1.into()], Prefix(0)).is_ok());
+        assert!(c.check(&vec![3.into(), 1.into(), 2.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![3.into(), 0.into(), 2.into(), 1.into()], Prefix::from(0)).is_ok());
         assert!(c
-            .check(&vec![3.into(), 0.into(), 2.into(), 1.into(), 3.into()], Prefix(0))
+            .check(&vec![3.into(), 0.into(), 2.into(), 1.into(), 3.into()], Prefix::from(0))
             .is_ok());
         assert!(c
-            .check(&vec![3.into(), 0.into(), 2.into(), 3.into(), 1.into()], Prefix(0))
+            .check(&vec![3.into(), 0.into(), 2.into(), 3.into(), 1.into()], Prefix::from(0))
             .is_err());
-        assert!(c.check(&vec![3.into(), 2.into(), 1.into()], Prefix(0)).is_err());
+        assert!(c.check(&vec![3.into(), 2.into(), 1.into()], Prefix::from(0)).is_err());
     }
 
     #[test]
     fn path_positional_star_fix_star_fix_star() {
         let c = Positional(vec![Star, Fix(0.into()), Star, Fix(1.into()), Star]);
-        assert!(c.check(&vec![], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![0.into(), 1.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix(0)).is_ok());
-        assert!(c.check(&vec![3.into(), 0.into(), 1.into(), 2.into()], Prefix(0)).is_ok());
+        assert!(c.check(&vec![], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![0.into(), 1.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![0.into(), 1.into(), 2.into()], Prefix::from(0)).is_ok());
+        assert!(c.check(&vec![3.into(), 0.into(), 1.into(), 2.into()], Prefix::from(0)).is_ok());
         assert!(c
-            .check(&vec![3.into(), 4.into(), 0.into(), 1.into(), 2.into()], Prefix(0))
+            .check(&vec![3.into(), 4.into(), 0.into(), 1.into(), 2.into()], Prefix::from(0))
             .is_ok());
-        assert!(c.check(&vec![3.into(), 1.into(), 2.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![3.into(), 0.into(), 2.into(), 1.into()], Prefix(0)).is_ok());
+        assert!(c.check(&vec![3.into(), 1.into(), 2.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![3.into(), 0.into(), 2.into(), 1.into()], Prefix::from(0)).is_ok());
         assert!(c
-            .check(&vec![3.into(), 0.into(), 2.into(), 1.into(), 3.into()], Prefix(0))
+            .check(&vec![3.into(), 0.into(), 2.into(), 1.into(), 3.into()], Prefix::from(0))
             .is_ok());
         assert!(c
-            .check(&vec![3.into(), 0.into(), 2.into(), 3.into(), 1.into()], Prefix(0))
+            .check(&vec![3.into(), 0.into(), 2.into(), 3.into(), 1.into()], Prefix::from(0))
             .is_ok());
-        assert!(c.check(&vec![3.into(), 2.into(), 1.into()], Prefix(0)).is_err());
-        assert!(c.check(&vec![3.into(), 2.into(), 1.into(), 0.into()], Prefix(0)).is_err());
+        assert!(c.check(&vec![3.into(), 2.into(), 1.into()], Prefix::from(0)).is_err());
+        assert!(c.check(&vec![3.into(), 2.into(), 1.into(), 0.into()], Prefix::from(0)).is_err());
     }
 }
@@ -0,0 +1,113 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! # Ordering Constraints
+
+use crate::netsim::config::{Config, ConfigModifier};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// # Ordering Constraint
+///
+/// Constraint on the ordering of the modifiers, imposed by the operator (e.g., by the change
+/// control process). Ordering constraints are part of the [`HardPolicy`](super::HardPolicy) (see
+/// [`HardPolicy::with_constraints`](super::HardPolicy::with_constraints)), and are therefore
+/// respected by every strategy and optimizer.
+///
+/// The constraints are checked on the configuration of each state. A modifier counts as applied,
+/// if the configuration contains the expression inserted or updated by the modifier, or if it no
+/// longer contains the expression removed by the modifier.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OrderingConstraint {
+    /// The first modifier must be applied before the second one.
+    Before(ConfigModifier, ConfigModifier),
+    /// The modifiers must be applied at the very end of the sequence, after all other modifiers.
+    AtEnd(Vec<ConfigModifier>),
+    /// The modifiers form an atomic group, which is applied in one single commit. No other
+    /// modifier may be applied in between, and the states while the group is only partially
+    /// applied are not observable. Hence, the hard policy is not checked in these states.
+    Atomic(Vec<ConfigModifier>),
+}
+
+impl OrderingConstraint {
+    /// Returns all modifiers of the constraint.
+    pub fn modifiers(&self) -> Vec<&ConfigModifier> {
+        match self {
+            Self::Before(a, b) => vec![a, b],
+            Self::AtEnd(mods) | Self::Atomic(mods) => mods.iter().collect(),
+        }
+    }
+}
+
+/// For every constraint, returns which of its modifiers are applied in the configuration.
+pub(super) fn applied(constraints: &[OrderingConstraint], config: &Config) -> Vec<Vec<bool>> {
+    constraints
+        .iter()
+        .map(|c| c.modifiers().into_iter().map(|m| is_applied(m, config)).collect())
+        .collect()
+}
+
+/// Returns the index of the first constraint that is violated in the current state. `prev` and
+/// `cur` are the applied modifiers (see [`applied`]) of the previous and the current state, and
+/// `num_applied` is the number of modifiers applied to reach the current state.
+pub(super) fn violated(
+    constraints: &[OrderingConstraint],
+    prev: Option<&Vec<Vec<bool>>>,
+    cur: &[Vec<bool>],
+    num_applied: usize,
+    num_mods: Option<usize>,
+) -> Option<usize> {
+    constraints.iter().enumerate().position(|(i, c)| {
+        let count = cur[i].iter().filter(|x| **x).count();
+        let prev_count = prev.map(|p| p[i].iter().filter(|x| **x).count()).unwrap_or_default();
+        match c {
+            OrderingConstraint::Before(_, _) => cur[i][1] && !cur[i][0],
+            // The modifiers must take the last positions of the sequence. If the number of
+            // modifiers is not known, only require that no other modifier follows them.
+            OrderingConstraint::AtEnd(mods) => match num_mods {
+                Some(n) => count > 0 && num_applied + mods.len() != n + count,
+                None => prev_count > 0 && count <= prev_count,
+            },
+            // if the group is partially applied, the next modifier must be part of it.
+            OrderingConstraint::Atomic(mods) => {
+                prev_count > 0 && prev_count < mods.len() && count <= prev_count
+            }
+        }
+    })
+}
+
+/// Returns `true` if any atomic group is only partially applied, which means that the state is not
+/// observable.
+pub(super) fn is_hidden(constraints: &[OrderingConstraint], cur: &[Vec<bool>]) -> bool {
+    constraints.iter().zip(cur.iter()).any(|(c, applied)| match c {
+        OrderingConstraint::Atomic(_) => applied.iter().any(|x| *x) && applied.iter().any(|x| !*x),
+        _ => false,
+    })
+}
+
+/// Returns `true` if the modifier is applied in the configuration.
+fn is_applied(modifier: &ConfigModifier, config: &Config) -> bool {
+    match modifier {
+        ConfigModifier::Insert(e) | ConfigModifier::Update { to: e, .. } => {
+            config.expr.get(&e.key()) == Some(e)
+        }
+        ConfigModifier::Remove(e) => !config.expr.contains_key(&e.key()),
+    }
+}
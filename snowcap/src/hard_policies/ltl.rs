@@ -18,14 +18,18 @@
 //! # Linear Temporal Logic
 
 use super::condition::Condition;
+use super::constraints::{self, OrderingConstraint};
 use super::{PolicyError, TransientStateAnalyzer};
 use crate::netsim::{
     config::{ConfigExpr, ConfigModifier},
-    ForwardingState, Network, NetworkError, Prefix, RouterId,
+    ForwardingChanges, ForwardingState, Network, NetworkError, Prefix, RouterId,
     types::Destination,
 };
 
 use itertools::iproduct;
+#[cfg(feature = "serde")]
+use serde::{ser::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::any::Any;
 use std::boxed::Box;
 use std::collections::HashSet;
 use std::fmt;
@@ -35,12 +39,36 @@ use std::time::Instant;
 /// Type alias for comfortable handling of the watch errors
 pub type WatchErrors = (Vec<usize>, Vec<Option<PolicyError>>);
 
+/// Returns `true` if the result of the condition might change due to `changes`. Reliability and
+/// transient conditions are always affected, since they do not only depend on the forwarding state.
+fn is_affected(condition: &Condition, changes: &ForwardingChanges) -> bool {
+    match condition {
+        Condition::Reachable(_, p, _)
+        | Condition::NotReachable(_, p)
+        | Condition::ReachableTraffic(_, p, _)
+        | Condition::NotReachableTraffic(_, p, _) => changes.affects(&Destination::BGP(*p)),
+        Condition::ReachableIGP(_, r, _) | Condition::NotReachableIGP(_, r) => {
+            changes.affects(&Destination::IGP(*r))
+        }
+        Condition::Reliable(..) | Condition::TransientPath(..) => true,
+    }
+}
+
 /// # Linear Temporal Logic
 ///
 /// This structure holds the entire LTL expression. It is stored as a vector of propositional
 /// a history of which constraints were satisfied, and an expression which can check the property
 /// based on the history.
+///
+/// Additionally, the policy may contain [`OrderingConstraint`]s on the modifiers (see
+/// [`HardPolicy::with_constraints`]), which are checked at every step.
+///
+/// If the `serde` feature is enabled, only the propositional variables, the LTL expression and the
+/// ordering constraints are (de)serialized. The history is dropped, and the policy is rebuilt using
+/// [`HardPolicy::new`].
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "HardPolicyRepr", from = "HardPolicyRepr"))]
 pub struct HardPolicy {
     /// Conditional variables of the hard poicy
     pub prop_vars: Vec<Condition>,
@@ -51,6 +79,9 @@ pub struct HardPolicy {
     pub expr: LTLModal,
     num_mods: Option<usize>,
     tsa: Option<TransientStateAnalyzer>,
+    constraints: Vec<OrderingConstraint>,
+    constraint_history: Vec<(Vec<Vec<bool>>, Option<usize>)>,
+    relative_constraints: bool,
 }
 
 impl HardPolicy {
@@ -126,9 +157,35 @@ impl HardPolicy {
             expr,
             num_mods: None,
             tsa,
+            constraints: Vec::new(),
+            constraint_history: Vec::new(),
+            relative_constraints: false,
         }
     }
 
+    /// Adds ordering constraints on the modifiers to the hard policy. Every strategy using this
+    /// policy will only find sequences satisfying these constraints. The history is cleared.
+    pub fn with_constraints(mut self, constraints: Vec<OrderingConstraint>) -> Self {
+        self.constraints = constraints;
+        self.reset();
+        self
+    }
+
+    /// Returns the ordering constraints of the hard policy.
+    pub fn constraints(&self) -> &[OrderingConstraint] {
+        &self.constraints
+    }
+
+    /// Returns a copy of the hard policy, where the ordering constraints only restrict the relative
+    /// order of the modifiers, but not their absolute position in the sequence. This is used to
+    /// check a subset of all modifiers, since the restriction of a valid sequence to a subset
+    /// always satisfies the relative constraints.
+    pub(crate) fn with_relative_constraints(&self) -> Self {
+        let mut policy = self.clone();
+        policy.relative_constraints = true;
+        policy
+    }
+
     /// Sets the total number of modifiers, if it was not yet set before. If it is already set, then
     /// nothing will change. This function returns `true` if there was no previous value.
     pub fn set_num_mods_if_none(&mut self, num_mods: usize) -> bool {
@@ -146,16 +203,81 @@ impl HardPolicy {
         net: &mut Network,
         state: &mut ForwardingState,
     ) -> Result<(), NetworkError> {
+        self.step_with_changes(net, state, None)
+    }
+
+    /// Applies a next step to the LTL model, only checking the conditions that might be affected by
+    /// `changes`. All other conditions keep their result from the last step. `changes` must contain
+    /// all entries that have changed since the forwarding state of the last step (see
+    /// [`ForwardingState::update`]). If the history is empty, all conditions are checked.
+    pub fn step_incremental(
+        &mut self,
+        net: &mut Network,
+        state: &mut ForwardingState,
+        changes: &ForwardingChanges,
+    ) -> Result<(), NetworkError> {
+        self.step_with_changes(net, state, Some(changes))
+    }
+
+    /// Applies the next step to the LTL model. If `changes` are given, only the affected conditions
+    /// are checked.
+    fn step_with_changes(
+        &mut self,
+        net: &mut Network,
+        state: &mut ForwardingState,
+        changes: Option<&ForwardingChanges>,
+    ) -> Result<(), NetworkError> {
+        // check the ordering constraints
+        let applied = constraints::applied(&self.constraints, net.current_config());
+        let last_applied = self.constraint_history.last().map(|(a, _)| a);
+        let violated = constraints::violated(
+            &self.constraints,
+            last_applied,
+            &applied,
+            self.history.len(),
+            self.num_mods.filter(|_| !self.relative_constraints),
+        );
+        let hidden = constraints::is_hidden(&self.constraints, &applied);
+        self.constraint_history.push((applied, violated));
+
+        // states where an atomic group is only partially applied are not observable. Hence, keep
+        // the result from the last step.
+        if hidden && !self.history.is_empty() {
+            if let Some(tsa) = self.tsa.as_mut() {
+                tsa.step(net);
+            }
+            self.history.push(self.history.last().unwrap().clone());
+            self.error_history.push(self.error_history.last().unwrap().clone());
+            return Ok(());
+        }
+
         // prepare new state
         let mut new_state = Vec::with_capacity(self.prop_vars.len());
         let mut new_error: Vec<Option<PolicyError>> = Vec::with_capacity(self.prop_vars.len());
+        // the last result might be from a hidden state, which is not related to `changes`.
+        let atomic = self.constraints.iter().any(|c| matches!(c, OrderingConstraint::Atomic(_)));
+        let last = match changes {
+            Some(changes) if !atomic => {
+                self.history.last().zip(self.error_history.last()).zip(Some(changes))
+            }
+            _ => None,
+        };
 
         // check invariance of the network (prop_vars)
         let start = Instant::now();
-        for v in self.prop_vars.iter() {
+        for (i, v) in self.prop_vars.iter().enumerate() {
             // iterate through all the policies
             // writes reachability condition from every node to the external routers
 
+            // keep the last result if the condition is not affected by the changes
+            if let Some(((last_state, last_error), changes)) = last {
+                if !is_affected(v, changes) {
+                    new_state.push(last_state[i]);
+                    new_error.push(last_error[i].clone());
+                    continue;
+                }
+            }
+
             match v.check(state) {
                 Ok(()) => {
                     new_state.push(true);
@@ -207,10 +329,12 @@ impl HardPolicy {
                     let check_result = if let Some(Condition::Reliable(r, p, c)) =
                         self.prop_vars.get(*c_id)
                     {
-                        match fw_state.get_route(*r, *p) {
-                            Ok(path) => match c {
+                        // every path of the forwarding DAG must be reliable
+                        match fw_state.check_all_routes(*r, Destination::BGP(*p)) {
+                            // every path is valid, so the path condition can be checked on the DAG
+                            Ok(()) => match c {
                                 None => Ok(()),
-                                Some(c) => match c.check(&path, *p) {
+                                Some(c) => match c.check_all_paths(&fw_state, *r, *p) {
                                     Ok(()) => Ok(()),
                                     Err(PolicyError::PathCondition { path, condition, dest }) => {
                                         Err(PolicyError::ReliabilityCondition {
@@ -221,12 +345,13 @@ impl HardPolicy {
                                             link_b: b,
                                         })
                                     }
-                                    // Condition::check can only return either Ok or Err(PolicyError::PathCondition)
+                                    // no other error is returned by check_all_paths
                                     Err(_) => unreachable!(),
                                 },
                             },
                             Err(NetworkError::ForwardingLoop(_))
-                            | Err(NetworkError::ForwardingBlackHole(_)) => {
+                            | Err(NetworkError::ForwardingBlackHole(_))
+                            | Err(NetworkError::AccessDenied(_)) => {
                                 Err(PolicyError::NotReliable {
                                     router: *r,
                                     dest: Destination::BGP(*p),
@@ -293,6 +418,7 @@ impl HardPolicy {
         // revert the change to the network
         self.history.pop();
         self.error_history.pop();
+        self.constraint_history.pop();
         if self.tsa.is_some() {
             self.tsa.as_mut().unwrap().undo();
         }
@@ -302,6 +428,7 @@ impl HardPolicy {
     pub fn reset(&mut self) {
         self.history.clear();
         self.error_history.clear();
+        self.constraint_history.clear();
         if self.tsa.is_some() {
             self.tsa.as_mut().unwrap().reset();
         }
@@ -314,17 +441,15 @@ impl HardPolicy {
     /// be set to `false` if the number of modifiers was not set yet.
     pub fn check(&self) -> bool {
         let finish = self.num_mods.map(|m| m + 1 == self.history.len()).unwrap_or(false);
-        if finish {
-            self.expr.check(&self.history[..])
-        } else {
-            !self.expr.partial(&self.history[..]).is_false()
-        }
+        self.check_overwrite_finish(finish)
     }
 
     /// Computes the condition using Linear Temporal Logic, and returns if the expression holds. The
     /// provided `finish` flag will be used to determine the method (`check` vs `partial`).
     pub fn check_overwrite_finish(&self, finish: bool) -> bool {
-        if finish {
+        if self.constraint_history.iter().any(|(_, v)| v.is_some()) {
+            false
+        } else if finish {
             self.expr.check(&self.history[..])
         } else {
             !self.expr.partial(&self.history[..]).is_false()
@@ -365,10 +490,13 @@ impl HardPolicy {
     /// Returns the set of all errors of all propositional variables. It might be the case that
     /// these errors don't necessarily contribute to the result of the last call to check.
     pub fn last_errors(&self) -> HashSet<PolicyError> {
-        self.error_history
+        let mut errors: HashSet<PolicyError> = self
+            .error_history
             .last()
             .map(|v| v.iter().filter_map(|e| e.clone()).collect())
-            .unwrap_or_default()
+            .unwrap_or_default();
+        errors.extend(self.last_constraint_error());
+        errors
     }
 
     /// Get the error associated with the watch provided to this method. The watch is an array
@@ -376,12 +504,27 @@ impl HardPolicy {
     /// resulting vector contains the error of the propositional variable, in the same order as
     /// provided. If a propositional variable is true, the error corresponding to this variable
     /// is None.
+    ///
+    /// If an ordering constraint was violated in the last step, the corresponding error is appended
+    /// at the end.
     pub fn last_errors_of_watch(&self, watch: &[usize]) -> Vec<Option<PolicyError>> {
-        if let Some(last_e) = self.error_history.last() {
+        let mut errors = if let Some(last_e) = self.error_history.last() {
             watch.iter().map(|&i| last_e[i].clone()).collect()
         } else {
             Vec::new()
+        };
+        if let Some(e) = self.last_constraint_error() {
+            errors.push(Some(e));
         }
+        errors
+    }
+
+    /// Returns the error of the ordering constraint violated in the last step (if any).
+    fn last_constraint_error(&self) -> Option<PolicyError> {
+        self.constraint_history
+            .last()
+            .and_then(|(_, v)| *v)
+            .map(|constraint| PolicyError::OrderingConstraint { constraint })
     }
 
     /// This method compares the current state of the checker with a previous state, which was
@@ -533,6 +676,7 @@ impl Not for LTLResult {
 
 pub trait LTLBoxClone {
     fn box_clone(&self) -> Box<dyn LTLOperator>;
+    fn as_any(&self) -> &dyn Any;
 }
 
 impl<T> LTLBoxClone for T
@@ -542,6 +686,10 @@ where
     fn box_clone(&self) -> Box<dyn LTLOperator> {
         Box::new(self.clone())
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 }
 
 impl Clone for Box<dyn LTLOperator> {
@@ -680,6 +828,7 @@ where
 
 /// # Boolean operator of LTL
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum LTLBoolean {
     /// Not: $\neg \phi$
     Not(Box<dyn LTLOperator>),
@@ -909,6 +1058,7 @@ impl LTLOperator for LTLBoolean {
 ///
 /// $$\phi_1\ \mathbf{U}\ \mathbf{G}\ \phi_2$$
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum LTLModal {
     /// $\phi$: $\phi$ holds at the current state.
     Now(Box<dyn LTLOperator>),
@@ -1361,6 +1511,84 @@ impl LTLOperator for LTLModal {
     }
 }
 
+/// Serializable representation of a [`HardPolicy`], without any history.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct HardPolicyRepr {
+    prop_vars: Vec<Condition>,
+    expr: LTLModal,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    constraints: Vec<OrderingConstraint>,
+}
+
+#[cfg(feature = "serde")]
+impl From<HardPolicy> for HardPolicyRepr {
+    fn from(policy: HardPolicy) -> Self {
+        Self { prop_vars: policy.prop_vars, expr: policy.expr, constraints: policy.constraints }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<HardPolicyRepr> for HardPolicy {
+    fn from(repr: HardPolicyRepr) -> Self {
+        Self::new(repr.prop_vars, repr.expr).with_constraints(repr.constraints)
+    }
+}
+
+/// Borrowed representation of a boxed [`LTLOperator`], used for serialization. Propositional
+/// variables and constants are serialized as plain numbers and booleans.
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+#[serde(untagged)]
+enum LTLOperatorRef<'a> {
+    Var(usize),
+    Const(bool),
+    Boolean(&'a LTLBoolean),
+    Modal(&'a LTLModal),
+}
+
+/// Owned representation of a boxed [`LTLOperator`], used for deserialization.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LTLOperatorRepr {
+    Var(usize),
+    Const(bool),
+    Boolean(LTLBoolean),
+    Modal(LTLModal),
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Box<dyn LTLOperator> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let op = (**self).as_any();
+        let repr = if let Some(x) = op.downcast_ref::<usize>() {
+            LTLOperatorRef::Var(*x)
+        } else if let Some(x) = op.downcast_ref::<bool>() {
+            LTLOperatorRef::Const(*x)
+        } else if let Some(x) = op.downcast_ref::<LTLBoolean>() {
+            LTLOperatorRef::Boolean(x)
+        } else if let Some(x) = op.downcast_ref::<LTLModal>() {
+            LTLOperatorRef::Modal(x)
+        } else {
+            return Err(S::Error::custom(format!("Cannot serialize LTL operator {:?}", self)));
+        };
+        repr.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Box<dyn LTLOperator> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match LTLOperatorRepr::deserialize(deserializer)? {
+            LTLOperatorRepr::Var(x) => Box::new(x),
+            LTLOperatorRepr::Const(x) => Box::new(x),
+            LTLOperatorRepr::Boolean(x) => Box::new(x),
+            LTLOperatorRepr::Modal(x) => Box::new(x),
+        })
+    }
+}
+
 #[cfg(test)]
 #[rustfmt::skip]
 mod test {
@@ -93,6 +93,16 @@
 //! other node, then $b$ must be traversed, immediately followed by $c$. This always matches on the
 //! entire path, and not just on a small part of it.
 //!
+//! # Ordering Constraints
+//!
+//! In addition to the conditions on the forwarding state, the hard policy may contain
+//! [`OrderingConstraint`]s (see [`HardPolicy::with_constraints`]), restricting the order in which
+//! the modifiers are applied. A modifier $m_a$ may be required to be applied before $m_b$, a set
+//! of modifiers may be required to be applied at the very end, or a set of modifiers may form an
+//! atomic group, which is applied in one single commit. Since the constraints are checked at every
+//! step of the hard policy, every strategy respects them. A violated constraint is reported as
+//! [`PolicyError::OrderingConstraint`].
+//!
 //! # Transient Behavior
 //!
 //! For transient behavior, we cannot guarantee the absence of black holes or forwarding loops. In
@@ -291,11 +301,15 @@
 //! that are better than the old known route (if this one is still known after reconfiguration).
 
 mod condition;
+mod constraints;
 mod ltl;
+mod parser;
 mod transient_behavior;
 
 pub use condition::{Condition, PathCondition, Waypoint};
+pub use constraints::OrderingConstraint;
 pub use ltl::{HardPolicy, LTLBoolean, LTLModal, LTLOperator, WatchErrors};
+pub use parser::{parse_policy, ParseError};
 use transient_behavior::TransientStateAnalyzer;
 
 use crate::netsim::{Network, Prefix, RouterId};
@@ -398,6 +412,13 @@ pub enum PolicyError {
         /// Path condition which may be violated in transient behavior
         condition: PathCondition,
     },
+
+    /// Ordering Constraint Violation
+    #[error("Ordering constraint {constraint} is violated")]
+    OrderingConstraint {
+        /// Index of the violated ordering constraint
+        constraint: usize,
+    },
 }
 
 impl PolicyError {
@@ -408,7 +429,7 @@ impl PolicyError {
                     Destination::BGP(p) => {
                         format!(
                             "Black hole for prefix {} at router {}",
-                            p.0,
+                            p,
                             net.get_router_name(*router).unwrap(),
                         )
                     },
@@ -430,7 +451,7 @@ impl PolicyError {
                 match dest {
                     Destination::BGP(p) => format!(
                         "Forwarding loop for prefix {}: {} -> {}",
-                        p.0,
+                        p,
                         path.iter()
                             .map(|r| net.get_router_name(*r).unwrap())
                             .collect::<Vec<&str>>()
@@ -451,7 +472,7 @@ impl PolicyError {
             PolicyError::PathCondition { path, condition, dest} => match dest {
                 Destination::BGP(p) => format!(
                     "Path condition invalidated for prefix {}: path: {}, condition: {}",
-                    p.0,
+                    p,
                     path.iter()
                         .map(|r| net.get_router_name(*r).unwrap())
                         .collect::<Vec<&str>>()
@@ -472,7 +493,7 @@ impl PolicyError {
                 Destination::BGP(p) => format!(
                     "Router {} can reach unallowed prefix {} via path [{}]",
                     net.get_router_name(*router).unwrap(),
-                    p.0,
+                    p,
                     path.iter()
                         .map(|r| net.get_router_name(*r).unwrap())
                         .collect::<Vec<_>>()
@@ -492,7 +513,7 @@ impl PolicyError {
                 Destination::BGP(p) => format!(
                     "Router {} cannot reach prefix {} when link [{} -> {}] fails",
                     net.get_router_name(*router).unwrap(),
-                    p.0,
+                    p,
                     net.get_router_name(*link_a).unwrap(),
                     net.get_router_name(*link_b).unwrap(),
                 ),
@@ -508,7 +529,7 @@ impl PolicyError {
                 Destination::BGP(p) => format!(
                         "Reliability condition {} violated for prefix {} with path {} when link [{} -> {}] fails",
                         condition.repr_with_name(net),
-                        p.0,
+                        p,
                         path.iter()
                             .map(|r| net.get_router_name(*r).unwrap())
                             .collect::<Vec<&str>>()
@@ -533,7 +554,7 @@ impl PolicyError {
                 Destination::BGP(p) => format!(
                     "Transient behavior of router {} for prefix {} may be violated! condition: {}",
                     net.get_router_name(*router).unwrap(),
-                    p.0,
+                    p,
                     condition.repr_with_name(net),
                 ),
                 Destination::IGP(r) => format!(
@@ -543,6 +564,9 @@ impl PolicyError {
                     condition.repr_with_name(net),
                 )
             }
+            PolicyError::OrderingConstraint { constraint } => {
+                format!("Ordering constraint {} is violated", constraint)
+            }
         }
     }
 }
@@ -599,7 +623,7 @@ mod test {
         let r3 = net.get_router_id("r3").unwrap();
         let r4 = net.get_router_id("r4").unwrap();
         let mut policy =
-            HardPolicy::reachability(vec![r1, r2, r3, r4].iter(), vec![Prefix(0)].iter());
+            HardPolicy::reachability(vec![r1, r2, r3, r4].iter(), vec![Prefix::from(0)].iter());
 
         let mut fw_state = net.get_forwarding_state();
         policy.step(&mut net, &mut fw_state).unwrap();
@@ -630,7 +654,7 @@ mod test {
         let e1 = net.get_router_id("e1").unwrap();
         let e4 = net.get_router_id("e4").unwrap();
 
-        let p = Prefix(0);
+        let p = Prefix::from(0);
 
         let mut policy = HardPolicy::globally(vec![
             Condition::Reachable(r1, p, Some(PathCondition::Edge(r1, e1))),
@@ -700,7 +724,7 @@ mod test {
         let r4 = net.get_router_id("r4").unwrap();
         let e4 = net.get_router_id("e4").unwrap();
 
-        let p = Prefix(0);
+        let p = Prefix::from(0);
 
         let mut policy = HardPolicy::globally(vec![
             Condition::Reachable(r1, p, None),
@@ -742,7 +766,7 @@ mod test {
         let e1 = net.get_router_id("e1").unwrap();
         let e4 = net.get_router_id("e4").unwrap();
 
-        let p = Prefix(0);
+        let p = Prefix::from(0);
 
         // the policy requires that all routers can always reach the prefix. After the
         // reconfiguration, we would like for all traffic to go via route r2 --> r1 --> e1.
@@ -0,0 +1,618 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! # Text Syntax for Hard Policies
+//!
+//! This module parses hard policies from strings at runtime, such that they can be stored in
+//! configuration files. In contrast to the `ltl!` macro of `snowcap_ltl_parser`, the conditions are
+//! written inline, and router names are resolved using [`Network::get_router_id`]. Equal
+//! conditions are mapped to the same propositional variable.
+//!
+//! ```
+//! use snowcap::hard_policies::parse_policy;
+//! use snowcap::netsim::Network;
+//!
+//! let mut net = Network::new();
+//! net.add_router("Zurich");
+//! net.add_router("Basel");
+//! net.add_router("Geneva");
+//!
+//! let policy = parse_policy(
+//!     &net,
+//!     "G(reach(Zurich, 10) & !via(Zurich, Basel, 10)) U reach(Geneva, 10)",
+//! )
+//! .unwrap();
+//! assert_eq!(policy.prop_vars.len(), 3);
+//! ```
+//!
+//! ## Operators
+//!
+//! Operators are listed from the lowest to the highest precedence. Binary operators are left
+//! associative, except `->`, `<->` and the temporal operators, which are right associative.
+//!
+//! - `a -> b`, `a <-> b`: `LTLBoolean::Implies` and `LTLBoolean::Iff`
+//! - `a | b`, `a || b`: `LTLBoolean::Or`
+//! - `a ^ b`: `LTLBoolean::Xor`
+//! - `a & b`, `a && b`: `LTLBoolean::And`
+//! - `a U b`, `a R b`, `a W b`, `a M b`: `LTLModal::Until`, `LTLModal::Release`,
+//!   `LTLModal::WeakUntil` and `LTLModal::StrongRelease`
+//! - `!a`, `X a`, `F a`, `G a`: `LTLBoolean::Not`, `LTLModal::Next`, `LTLModal::Finally` and
+//!   `LTLModal::Globally`
+//! - `true`, `false`, conditions and parentheses
+//!
+//! ## Conditions
+//!
+//! Routers are given by their name (which must be quoted if it contains special characters or is
+//! equal to an operator), and prefixes either as a number (see `Prefix::from(u32)`), or in the
+//! notation `10.0.0.0/8`.
+//!
+//! - `reach(r, p)`, `reach(r, p, path)`: `Condition::Reachable`
+//! - `via(r, w, p)`: `Condition::Reachable`, where the path must traverse `w`
+//! - `waypoint(r, p, [a, *, ?, b])`: `Condition::Reachable` with a positional path condition
+//! - `isolated(r, p)`: `Condition::NotReachable`
+//! - `reach_igp(r, t)`, `reach_igp(r, t, path)`: `Condition::ReachableIGP`
+//! - `isolated_igp(r, t)`: `Condition::NotReachableIGP`
+//! - `reliable(r, p)`, `reliable(r, p, path)`: `Condition::Reliable`
+//! - `transient(r, p, path)`: `Condition::TransientPath`
+//!
+//! The path condition is either a router, an edge `edge(a, b)`, a positional condition like
+//! `[a, *, ?, b]`, or a combination of those using `!`, `&`, `|` and parentheses.
+
+use super::{Condition, HardPolicy, LTLBoolean, LTLModal, LTLOperator, PathCondition, Waypoint};
+use crate::netsim::{Network, Prefix, RouterId};
+
+use thiserror::Error;
+
+/// # Policy Parse Error
+/// Error while parsing a hard policy from a string. All positions are byte offsets into the input.
+#[derive(Debug, Error, PartialEq, Eq, Clone)]
+pub enum ParseError {
+    /// The input contains a character that is not part of the syntax.
+    #[error("Invalid character {1:?} at position {0}")]
+    InvalidCharacter(usize, char),
+    /// A quoted name is not terminated.
+    #[error("Unterminated string starting at position {0}")]
+    UnterminatedString(usize),
+    /// The input ended while expecting more tokens.
+    #[error("Unexpected end of input, expected {0}")]
+    UnexpectedEnd(String),
+    /// An unexpected token was found
+    #[error("Expected {expected} at position {pos}, but found {found}")]
+    UnexpectedToken {
+        /// Position of the token
+        pos: usize,
+        /// Description of the expected token
+        expected: String,
+        /// The token that was found
+        found: String,
+    },
+    /// The condition is not known
+    #[error("Unknown condition {name} at position {pos}")]
+    UnknownCondition {
+        /// Position of the condition
+        pos: usize,
+        /// Name of the condition
+        name: String,
+    },
+    /// The condition was called with the wrong number of arguments
+    #[error("Invalid number of arguments for {name} at position {pos}")]
+    InvalidArguments {
+        /// Position of the condition
+        pos: usize,
+        /// Name of the condition
+        name: String,
+    },
+    /// The router name does not exist in the network
+    #[error("Unknown router {name} at position {pos}")]
+    UnknownRouter {
+        /// Position of the router name
+        pos: usize,
+        /// Name of the router
+        name: String,
+    },
+    /// The prefix cannot be parsed
+    #[error("Invalid prefix {name} at position {pos}")]
+    InvalidPrefix {
+        /// Position of the prefix
+        pos: usize,
+        /// The string that cannot be parsed as prefix
+        name: String,
+    },
+}
+
+/// Parse a hard policy from a string, resolving all router names in `net`. See the
+/// [module documentation](self) for the syntax. The resulting LTL expression is wrapped into
+/// `LTLModal::Now`, like the expressions generated by the `ltl!` macro.
+pub fn parse_policy(net: &Network, input: &str) -> Result<HardPolicy, ParseError> {
+    let mut parser = Parser { tokens: tokenize(input)?, pos: 0, net, prop_vars: Vec::new() };
+    let expr = parser.parse_implies()?;
+    if let Some((pos, token)) = parser.tokens.get(parser.pos) {
+        return Err(ParseError::UnexpectedToken {
+            pos: *pos,
+            expected: String::from("end of input"),
+            found: token.to_string(),
+        });
+    }
+    Ok(HardPolicy::new(parser.prop_vars, LTLModal::Now(expr)))
+}
+
+/// Token of the policy syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// Name, number or prefix
+    Word(String),
+    /// Quoted name, which is never interpreted as operator
+    Quoted(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Not,
+    And,
+    Or,
+    Xor,
+    Implies,
+    Iff,
+    Star,
+    Any,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "{:?}", w),
+            Token::Quoted(w) => write!(f, "\"{}\"", w),
+            Token::LParen => write!(f, "\"(\""),
+            Token::RParen => write!(f, "\")\""),
+            Token::LBracket => write!(f, "\"[\""),
+            Token::RBracket => write!(f, "\"]\""),
+            Token::Comma => write!(f, "\",\""),
+            Token::Not => write!(f, "\"!\""),
+            Token::And => write!(f, "\"&\""),
+            Token::Or => write!(f, "\"|\""),
+            Token::Xor => write!(f, "\"^\""),
+            Token::Implies => write!(f, "\"->\""),
+            Token::Iff => write!(f, "\"<->\""),
+            Token::Star => write!(f, "\"*\""),
+            Token::Any => write!(f, "\"?\""),
+        }
+    }
+}
+
+/// Split the input into tokens, together with their position.
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            ',' => Token::Comma,
+            '!' => Token::Not,
+            '^' => Token::Xor,
+            '*' => Token::Star,
+            '?' => Token::Any,
+            '&' | '|' => {
+                // `&&` and `||` are equal to `&` and `|`
+                if chars.peek().map(|(_, n)| *n) == Some(c) {
+                    chars.next();
+                }
+                if c == '&' {
+                    Token::And
+                } else {
+                    Token::Or
+                }
+            }
+            '-' if chars.peek().map(|(_, n)| *n) == Some('>') => {
+                chars.next();
+                Token::Implies
+            }
+            '<' if input[pos..].starts_with("<->") => {
+                chars.next();
+                chars.next();
+                Token::Iff
+            }
+            '"' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, c)) => name.push(c),
+                        None => return Err(ParseError::UnterminatedString(pos)),
+                    }
+                }
+                Token::Quoted(name)
+            }
+            c if is_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some((next_pos, n)) = chars.peek().copied() {
+                    // stop at the arrow of `->`
+                    if !is_word_char(n) || input[next_pos..].starts_with("->") {
+                        break;
+                    }
+                    word.push(n);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+            c => return Err(ParseError::InvalidCharacter(pos, c)),
+        };
+        tokens.push((pos, token));
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ':')
+}
+
+/// Kind of the arguments of a condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arg {
+    Router,
+    Prefix,
+    Path,
+    OptionalPath,
+}
+
+/// Parsed argument of a condition
+enum ArgValue {
+    Router(RouterId),
+    Prefix(Prefix),
+    Path(Option<PathCondition>),
+}
+
+/// Recursive descent parser for the policy syntax. The parser collects all conditions while
+/// parsing the expression.
+struct Parser<'a> {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    net: &'a Network,
+    prop_vars: Vec<Condition>,
+}
+
+impl<'a> Parser<'a> {
+    /// Returns the next token without consuming it.
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    /// Returns the next token if it is one of the given keywords (which must not be quoted).
+    fn peek_keyword(&self, keywords: &[&str]) -> Option<String> {
+        match self.peek() {
+            Some(Token::Word(w)) if keywords.contains(&w.as_str()) => Some(w.clone()),
+            _ => None,
+        }
+    }
+
+    /// Consume the next token, returning its position.
+    fn next(&mut self, expected: &str) -> Result<(usize, Token), ParseError> {
+        match self.tokens.get(self.pos) {
+            Some(t) => {
+                self.pos += 1;
+                Ok(t.clone())
+            }
+            None => Err(ParseError::UnexpectedEnd(expected.to_string())),
+        }
+    }
+
+    /// Consume the next token, which must be equal to `token`.
+    fn expect(&mut self, token: Token) -> Result<usize, ParseError> {
+        let expected = token.to_string();
+        match self.next(&expected)? {
+            (pos, t) if t == token => Ok(pos),
+            (pos, t) => Err(ParseError::UnexpectedToken { pos, expected, found: t.to_string() }),
+        }
+    }
+
+    fn parse_implies(&mut self) -> Result<Box<dyn LTLOperator>, ParseError> {
+        let lhs = self.parse_or()?;
+        match self.peek() {
+            Some(Token::Implies) => {
+                self.pos += 1;
+                Ok(Box::new(LTLBoolean::Implies(lhs, self.parse_implies()?)))
+            }
+            Some(Token::Iff) => {
+                self.pos += 1;
+                Ok(Box::new(LTLBoolean::Iff(lhs, self.parse_implies()?)))
+            }
+            _ => Ok(lhs),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Box<dyn LTLOperator>, ParseError> {
+        let mut operands = vec![self.parse_xor()?];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            operands.push(self.parse_xor()?);
+        }
+        Ok(if operands.len() == 1 {
+            operands.pop().unwrap()
+        } else {
+            Box::new(LTLBoolean::Or(operands))
+        })
+    }
+
+    fn parse_xor(&mut self) -> Result<Box<dyn LTLOperator>, ParseError> {
+        let mut lhs = self.parse_and()?;
+        while self.peek() == Some(&Token::Xor) {
+            self.pos += 1;
+            lhs = Box::new(LTLBoolean::Xor(lhs, self.parse_and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Box<dyn LTLOperator>, ParseError> {
+        let mut operands = vec![self.parse_temporal()?];
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            operands.push(self.parse_temporal()?);
+        }
+        Ok(if operands.len() == 1 {
+            operands.pop().unwrap()
+        } else {
+            Box::new(LTLBoolean::And(operands))
+        })
+    }
+
+    fn parse_temporal(&mut self) -> Result<Box<dyn LTLOperator>, ParseError> {
+        let lhs = self.parse_unary()?;
+        let op = match self.peek_keyword(&["U", "R", "W", "M"]) {
+            Some(op) => op,
+            None => return Ok(lhs),
+        };
+        self.pos += 1;
+        let rhs = self.parse_temporal()?;
+        Ok(Box::new(match op.as_str() {
+            "U" => LTLModal::Until(lhs, rhs),
+            "R" => LTLModal::Release(lhs, rhs),
+            "W" => LTLModal::WeakUntil(lhs, rhs),
+            _ => LTLModal::StrongRelease(lhs, rhs),
+        }))
+    }
+
+    fn parse_unary(&mut self) -> Result<Box<dyn LTLOperator>, ParseError> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(Box::new(LTLBoolean::Not(self.parse_unary()?)));
+        }
+        if let Some(op) = self.peek_keyword(&["X", "F", "G"]) {
+            self.pos += 1;
+            let operand = self.parse_unary()?;
+            return Ok(Box::new(match op.as_str() {
+                "X" => LTLModal::Next(operand),
+                "F" => LTLModal::Finally(operand),
+                _ => LTLModal::Globally(operand),
+            }));
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Box<dyn LTLOperator>, ParseError> {
+        match self.next("an expression")? {
+            (_, Token::LParen) => {
+                let expr = self.parse_implies()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            (_, Token::Word(w)) if w == "true" => Ok(Box::new(true)),
+            (_, Token::Word(w)) if w == "false" => Ok(Box::new(false)),
+            (pos, Token::Word(name)) => {
+                let condition = self.parse_condition(pos, name)?;
+                let idx = match self.prop_vars.iter().position(|c| *c == condition) {
+                    Some(idx) => idx,
+                    None => {
+                        self.prop_vars.push(condition);
+                        self.prop_vars.len() - 1
+                    }
+                };
+                Ok(Box::new(idx))
+            }
+            (pos, t) => Err(ParseError::UnexpectedToken {
+                pos,
+                expected: String::from("an expression"),
+                found: t.to_string(),
+            }),
+        }
+    }
+
+    /// Parse the arguments of the condition `name`, and build the condition.
+    fn parse_condition(&mut self, pos: usize, name: String) -> Result<Condition, ParseError> {
+        use Arg::*;
+        let signature: &[Arg] = match name.as_str() {
+            "reach" | "reliable" => &[Router, Prefix, OptionalPath],
+            "via" => &[Router, Router, Prefix],
+            "isolated" => &[Router, Prefix],
+            "reach_igp" => &[Router, Router, OptionalPath],
+            "isolated_igp" => &[Router, Router],
+            "transient" | "waypoint" => &[Router, Prefix, Path],
+            _ => return Err(ParseError::UnknownCondition { pos, name }),
+        };
+        let invalid_args = || ParseError::InvalidArguments { pos, name: name.clone() };
+
+        self.expect(Token::LParen)?;
+        let mut args = Vec::with_capacity(signature.len());
+        for (i, kind) in signature.iter().enumerate() {
+            if *kind == OptionalPath && self.peek() == Some(&Token::RParen) {
+                args.push(ArgValue::Path(None));
+                break;
+            }
+            if i > 0 {
+                match self.next("\",\"")? {
+                    (_, Token::Comma) => {}
+                    (_, Token::RParen) => return Err(invalid_args()),
+                    (pos, t) => {
+                        return Err(ParseError::UnexpectedToken {
+                            pos,
+                            expected: String::from("\",\""),
+                            found: t.to_string(),
+                        })
+                    }
+                }
+            }
+            args.push(match kind {
+                Router => ArgValue::Router(self.parse_router()?),
+                Prefix => ArgValue::Prefix(self.parse_prefix()?),
+                Path | OptionalPath => ArgValue::Path(Some(self.parse_path_or()?)),
+            });
+        }
+        if self.peek() == Some(&Token::Comma) {
+            return Err(invalid_args());
+        }
+        self.expect(Token::RParen)?;
+
+        Ok(match (name.as_str(), args.as_slice()) {
+            ("reach", [ArgValue::Router(r), ArgValue::Prefix(p), ArgValue::Path(c)]) => {
+                Condition::Reachable(*r, *p, c.clone())
+            }
+            ("reliable", [ArgValue::Router(r), ArgValue::Prefix(p), ArgValue::Path(c)]) => {
+                Condition::Reliable(*r, *p, c.clone())
+            }
+            ("via", [ArgValue::Router(r), ArgValue::Router(w), ArgValue::Prefix(p)]) => {
+                Condition::Reachable(*r, *p, Some(PathCondition::Node(*w)))
+            }
+            ("waypoint", [ArgValue::Router(r), ArgValue::Prefix(p), ArgValue::Path(c)]) => {
+                match c {
+                    Some(PathCondition::Positional(_)) => Condition::Reachable(*r, *p, c.clone()),
+                    _ => return Err(invalid_args()),
+                }
+            }
+            ("isolated", [ArgValue::Router(r), ArgValue::Prefix(p)]) => {
+                Condition::NotReachable(*r, *p)
+            }
+            ("reach_igp", [ArgValue::Router(r), ArgValue::Router(t), ArgValue::Path(c)]) => {
+                Condition::ReachableIGP(*r, *t, c.clone())
+            }
+            ("isolated_igp", [ArgValue::Router(r), ArgValue::Router(t)]) => {
+                Condition::NotReachableIGP(*r, *t)
+            }
+            ("transient", [ArgValue::Router(r), ArgValue::Prefix(p), ArgValue::Path(Some(c))]) => {
+                Condition::TransientPath(*r, *p, c.clone())
+            }
+            _ => unreachable!("arguments are parsed according to the signature"),
+        })
+    }
+
+    fn parse_router(&mut self) -> Result<RouterId, ParseError> {
+        match self.next("a router")? {
+            (pos, Token::Word(name)) | (pos, Token::Quoted(name)) => {
+                self.net.get_router_id(&name).map_err(|_| ParseError::UnknownRouter { pos, name })
+            }
+            (pos, t) => Err(ParseError::UnexpectedToken {
+                pos,
+                expected: String::from("a router"),
+                found: t.to_string(),
+            }),
+        }
+    }
+
+    fn parse_prefix(&mut self) -> Result<Prefix, ParseError> {
+        match self.next("a prefix")? {
+            (pos, Token::Word(name)) => {
+                let prefix = if name.contains('/') {
+                    name.parse().ok()
+                } else {
+                    name.parse::<u32>().ok().and_then(|x| Prefix::try_from_id(x).ok())
+                };
+                prefix.ok_or(ParseError::InvalidPrefix { pos, name })
+            }
+            (pos, t) => Err(ParseError::UnexpectedToken {
+                pos,
+                expected: String::from("a prefix"),
+                found: t.to_string(),
+            }),
+        }
+    }
+
+    fn parse_path_or(&mut self) -> Result<PathCondition, ParseError> {
+        let mut operands = vec![self.parse_path_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            operands.push(self.parse_path_and()?);
+        }
+        Ok(if operands.len() == 1 { operands.pop().unwrap() } else { PathCondition::Or(operands) })
+    }
+
+    fn parse_path_and(&mut self) -> Result<PathCondition, ParseError> {
+        let mut operands = vec![self.parse_path_atom()?];
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            operands.push(self.parse_path_atom()?);
+        }
+        Ok(if operands.len() == 1 { operands.pop().unwrap() } else { PathCondition::And(operands) })
+    }
+
+    fn parse_path_atom(&mut self) -> Result<PathCondition, ParseError> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(PathCondition::Not(Box::new(self.parse_path_atom()?)))
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let cond = self.parse_path_or()?;
+                self.expect(Token::RParen)?;
+                Ok(cond)
+            }
+            Some(Token::LBracket) => {
+                self.pos += 1;
+                let mut waypoints = Vec::new();
+                loop {
+                    let waypoint = match self.peek() {
+                        Some(Token::Star) => {
+                            self.pos += 1;
+                            Waypoint::Star
+                        }
+                        Some(Token::Any) => {
+                            self.pos += 1;
+                            Waypoint::Any
+                        }
+                        _ => Waypoint::Fix(self.parse_router()?),
+                    };
+                    waypoints.push(waypoint);
+                    match self.next("\"]\"")? {
+                        (_, Token::Comma) => {}
+                        (_, Token::RBracket) => break,
+                        (pos, t) => {
+                            return Err(ParseError::UnexpectedToken {
+                                pos,
+                                expected: String::from("\",\" or \"]\""),
+                                found: t.to_string(),
+                            })
+                        }
+                    }
+                }
+                Ok(PathCondition::Positional(waypoints))
+            }
+            _ if self.peek_keyword(&["edge"]).is_some()
+                && self.tokens.get(self.pos + 1).map(|(_, t)| t) == Some(&Token::LParen) =>
+            {
+                self.pos += 2;
+                let a = self.parse_router()?;
+                self.expect(Token::Comma)?;
+                let b = self.parse_router()?;
+                self.expect(Token::RParen)?;
+                Ok(PathCondition::Edge(a, b))
+            }
+            _ => Ok(PathCondition::Node(self.parse_router()?)),
+        }
+    }
+}
@@ -132,7 +132,7 @@ impl PrefixAnalyzer {
     fn repr_with_name(&self, net: &Network) -> String {
         format!(
             "\nPrefix {}\nconds:\n{}\nold graph:\n{}\nnew graph:\n{}\n",
-            self.prefix.0,
+            self.prefix,
             self.conds.iter().map(|c| c.repr_with_name(net)).collect::<Vec<_>>().join("\n"),
             self.fwsg
                 .iter()
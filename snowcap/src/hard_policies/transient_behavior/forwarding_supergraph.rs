@@ -37,27 +37,33 @@ pub struct ForwardingSupergraph {
 
 impl ForwardingSupergraph {
     /// Build the forwarding supergraph for a single prefix. The routes are filtered in this
-    /// funciton (i.e., only the routes with the given prefix are considered).
+    /// funciton (i.e., only the routes with a prefix containing the given prefix are considered,
+    /// since traffic may be forwarded using a less specific route, like an aggregate).
     pub fn new(net: &Network, prefix: Prefix, rri: &HashMap<BgpRoute, RouteReachability>) -> Self {
         // build the graph
         let mut g: Vec<Vec<RouterId>> = repeat(Vec::new()).take(net.num_devices()).collect();
 
         // go through each route and update the graph accordingly
         for (route, routers) in rri {
-            // skip all routes that are not of the given prefix
-            if route.prefix != prefix {
+            // skip all routes that do not cover the given prefix
+            if !route.prefix.contains(&prefix) {
                 continue;
             }
             let target = route.next_hop;
             for r_id in routers.iter() {
+                // the router originating the route (like an aggregate) does not forward the traffic
+                if *r_id == target {
+                    continue;
+                }
                 // compute the next hop for this potential route
                 if let NetworkDevice::InternalRouter(r) = net.get_device(*r_id) {
-                    if let Some(nh) =
-                        r.igp_forwarding_table.get(&target).cloned().flatten().map(|(nh, _)| nh)
-                    {
-                        // check if this next hop is already stored in the graph
-                        if !g[r_id.index()].contains(&nh) {
-                            g[r_id.index()].push(nh);
+                    // add every equal-cost next hop for this potential route
+                    if let Some(Some((next_hops, _))) = r.igp_forwarding_table.get(&target) {
+                        for nh in next_hops {
+                            // check if this next hop is already stored in the graph
+                            if !g[r_id.index()].contains(nh) {
+                                g[r_id.index()].push(*nh);
+                            }
                         }
                     }
                 }
@@ -89,7 +95,7 @@ impl ForwardingSupergraph {
             }
             TransientCondition::SlowMode { router_id, condition, .. } => {
                 for path in self.simple_paths(*router_id, old) {
-                    if condition.check(&path, Prefix(0)).is_err() {
+                    if condition.check(&path, Prefix::from(0)).is_err() {
                         return false;
                     }
                 }
@@ -140,7 +146,7 @@ impl ForwardingSupergraph {
     /// let command = &net.current_config().get_diff(&final_config).modifiers[0];
     ///
     /// // extract the routers for the condition
-    /// let p = Prefix(0);
+    /// let p = Prefix::from(0);
     /// let epfl = net.get_router_id("Lausanne_(EPFL)")?;
     /// let cern = net.get_router_id("CERN_1")?;
     /// let neuchatel = net.get_router_id("Neuchatel")?;
@@ -1177,4 +1183,47 @@ mod test {
             false
         );
     }
+
+    #[test]
+    fn supergraph_aggregate() {
+        use super::super::{get_all_route_reachability, BgpGraph};
+        use crate::netsim::config::{Config, ConfigExpr::*, ConfigModifier::Insert};
+        use crate::netsim::{AsId, BgpSessionType::*};
+
+        let p = |s: &str| s.parse::<Prefix>().unwrap();
+        let mut net = Network::new();
+        let r1 = net.add_router("r1");
+        let r2 = net.add_router("r2");
+        let e2 = net.add_external_router("e2", AsId(65102));
+        net.add_link(r1, r2);
+        net.add_link(r2, e2);
+        let mut c = Config::new();
+        for (a, b) in [(r1, r2), (r2, e2)] {
+            c.add(IgpLinkWeight { source: a, target: b, weight: 1.0 }).unwrap();
+            c.add(IgpLinkWeight { source: b, target: a, weight: 1.0 }).unwrap();
+        }
+        c.add(BgpSession { source: r1, target: r2, session_type: IBgpPeer }).unwrap();
+        c.add(BgpSession { source: r2, target: e2, session_type: EBgp }).unwrap();
+        net.set_config(&c).unwrap();
+        net.advertise_external_route(e2, p("10.2.1.0/24"), vec![AsId(65102)], None, None).unwrap();
+        let prefix = p("10.2.0.0/16");
+        let aggregate = BgpAggregate { router: r2, prefix, summary_only: true, as_set: false };
+        net.apply_modifier(&Insert(aggregate)).unwrap();
+
+        // the aggregate originated by r2 may reach r1
+        let rri = get_all_route_reachability(&net, &BgpGraph::new(&net));
+        let (_, routers) = rri.iter().find(|(r, _)| r.prefix == prefix).unwrap();
+        assert!(routers.contains(r1));
+
+        // traffic towards a more-specific prefix is forwarded using the contributing route
+        let fwsg = ForwardingSupergraph::new(&net, p("10.2.1.128/25"), &rri);
+        assert_eq!(fwsg.neighbors[r1.index()], vec![r2]);
+        assert_eq!(fwsg.neighbors[r2.index()], vec![e2]);
+
+        // traffic towards any other address is forwarded to r2 using the aggregate, where it is
+        // dropped
+        let fwsg = ForwardingSupergraph::new(&net, p("10.2.3.0/24"), &rri);
+        assert_eq!(fwsg.neighbors[r1.index()], vec![r2]);
+        assert!(fwsg.neighbors[r2.index()].is_empty());
+    }
 }
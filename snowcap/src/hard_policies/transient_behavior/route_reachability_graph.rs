@@ -45,28 +45,36 @@ pub fn get_all_route_reachability(
     // prepare result vector
     let mut result: HashMap<BgpRoute, RouteReachability> = HashMap::new();
 
-    for ext_router_id in net.get_external_routers() {
-        let ext_router = net.get_device(ext_router_id).unwrap_external();
-        for route in ext_router.get_advertised_routes() {
-            // initialize routes_todo
-            let mut routes_todo: HashMap<BgpRoute, Vec<(RouterId, BgpEdge, Option<RouterId>)>> =
-                hashmap!(route.clone() => vec![(ext_router_id, BgpEdge::UpExternal, None)]);
-
-            while let Some((next_route, next_start_stack)) = pop_hashmap(&mut routes_todo) {
-                let (rri, new_routes) =
-                    RouteReachability::new(net, bgp_graph, next_route.clone(), next_start_stack);
-                // push the information to the result. Check if the route was already created
-                match result.entry(next_route) {
-                    std::collections::hash_map::Entry::Occupied(mut e) => e.get_mut().union(rri),
-                    std::collections::hash_map::Entry::Vacant(e) => {
-                        e.insert(rri);
-                    }
+    // routes advertised by external routers, and routes originated by internal routers (network
+    // statements, aggregates and redistribution). Both are advertised to every BGP neighbor.
+    let external_routes = net.get_external_routers().into_iter().flat_map(|r| {
+        let routes = net.get_device(r).unwrap_external().get_advertised_routes();
+        routes.iter().cloned().map(move |route| (r, route))
+    });
+    let originated_routes = net.get_routers().into_iter().flat_map(|r| {
+        let routes = net.get_device(r).unwrap_internal().get_originated_routes();
+        routes.into_iter().map(move |route| (r, route))
+    });
+
+    for (origin, route) in external_routes.chain(originated_routes) {
+        // initialize routes_todo
+        let mut routes_todo: HashMap<BgpRoute, Vec<(RouterId, BgpEdge, Option<RouterId>)>> =
+            hashmap!(route => vec![(origin, BgpEdge::UpExternal, None)]);
+
+        while let Some((next_route, next_start_stack)) = pop_hashmap(&mut routes_todo) {
+            let (rri, new_routes) =
+                RouteReachability::new(net, bgp_graph, next_route.clone(), next_start_stack);
+            // push the information to the result. Check if the route was already created
+            match result.entry(next_route) {
+                std::collections::hash_map::Entry::Occupied(mut e) => e.get_mut().union(rri),
+                std::collections::hash_map::Entry::Vacant(e) => {
+                    e.insert(rri);
                 }
+            }
 
-                // extend the routes with new information
-                for (route, mut stack) in new_routes {
-                    routes_todo.entry(route).or_default().append(&mut stack);
-                }
+            // extend the routes with new information
+            for (route, mut stack) in new_routes {
+                routes_todo.entry(route).or_default().append(&mut stack);
             }
         }
     }
@@ -294,7 +302,8 @@ impl BgpGraph {
                         .igp_forwarding_table
                         .get(&next_hop)
                         .unwrap()
-                        .map(|(_, w)| w),
+                        .as_ref()
+                        .map(|(_, w)| *w),
                 };
 
                 // apply all outgoing route maps of the source
@@ -336,7 +345,8 @@ impl BgpGraph {
                     .igp_forwarding_table
                     .get(&next_hop)
                     .unwrap()
-                    .map(|(_, w)| w),
+                    .as_ref()
+                    .map(|(_, w)| *w),
             };
 
             // apply all outgoing route maps of the source
@@ -489,10 +499,12 @@ impl PartialOrd for BgpEdge {
 mod test {
     use super::*;
     use crate::example_networks::*;
+    use crate::netsim::bgp::Origin;
     use crate::netsim::config::ConfigModifier;
     use crate::netsim::route_map::{RouteMapBuilder, RouteMapDirection};
     use crate::netsim::{AsId, Prefix};
     use maplit::{hashmap, hashset};
+    use std::collections::BTreeSet;
 
     #[test]
     fn bgp_tree_simplenet() {
@@ -637,20 +649,30 @@ mod test {
         let bgp = BgpGraph::new(&net);
 
         let route1 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65101), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e1,
             local_pref: None,
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
         let route4 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65104), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e4,
             local_pref: None,
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
 
         let expected = hashmap![
@@ -689,28 +711,43 @@ mod test {
         let bgp = BgpGraph::new(&net);
 
         let route1 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65101), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e1,
             local_pref: None,
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
         let route2 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65101), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e1,
             local_pref: Some(200),
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
         let route4 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65104), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e4,
             local_pref: None,
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
 
         let expected = hashmap![
@@ -750,28 +787,43 @@ mod test {
         let bgp = BgpGraph::new(&net);
 
         let route1 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65101), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e1,
             local_pref: None,
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
         let route2 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65101), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e1,
             local_pref: Some(200),
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
         let route4 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65104), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e4,
             local_pref: None,
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
 
         let expected = hashmap![
@@ -802,7 +854,7 @@ mod test {
             map: RouteMapBuilder::new()
                 .order(10)
                 .allow()
-                .match_prefix(Prefix(0))
+                .match_prefix(Prefix::from(0))
                 .set_local_pref(200)
                 .build(),
         }))
@@ -822,36 +874,56 @@ mod test {
         let bgp = BgpGraph::new(&net);
 
         let route1 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65101), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e1,
             local_pref: None,
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
         let route2 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65101), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e1,
             local_pref: Some(200),
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
         let route3 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65101), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e1,
             local_pref: Some(300),
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
         let route4 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65104), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e4,
             local_pref: None,
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
 
         let expected = hashmap![
@@ -883,7 +955,7 @@ mod test {
             map: RouteMapBuilder::new()
                 .order(10)
                 .allow()
-                .match_prefix(Prefix(0))
+                .match_prefix(Prefix::from(0))
                 .set_local_pref(200)
                 .build(),
         }))
@@ -898,28 +970,43 @@ mod test {
         let bgp = BgpGraph::new(&net);
 
         let route1 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65101), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e1,
             local_pref: None,
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
         let route2 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65101), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e1,
             local_pref: Some(200),
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
         let route4 = BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(65104), AsId(65200)],
+            as_set: BTreeSet::new(),
             next_hop: e4,
             local_pref: None,
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         };
 
         let expected = hashmap![
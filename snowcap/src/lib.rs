@@ -95,6 +95,13 @@
 //!   [optimizers](optimizers::Optimizer) will contain the method `num_states`, to get the number
 //!   of network states that have been explored.
 //!
+//! - *`serde`*: If this feature is enabled, the [network](netsim::Network), the
+//!   [configuration](netsim::config::Config) (including patches and modifiers), and the
+//!   [hard policy](hard_policies::HardPolicy) implement `Serialize` and `Deserialize`. This allows
+//!   an entire problem instance to be stored (e.g., as JSON) and loaded again. The network is
+//!   stored as its topology, configuration and advertised routes, and it is rebuilt when it is
+//!   deserialized.
+//!
 //! ## Usage
 //!
 //! To use this module, you need to do first prepare your [network](netsim::Network) to
@@ -152,9 +159,11 @@ pub mod strategies;
 //pub mod transient_behavior;
 
 mod synthesize;
-pub use synthesize::{optimize, synthesize, synthesize_parallel};
+pub use synthesize::{
+    optimize, resynthesize, synthesize, synthesize_parallel, synthesize_staged, Resynthesis,
+};
 
-pub use error::Error;
+pub use error::{Error, UnsatExplanation};
 
 use std::sync::{Arc, RwLock};
 
@@ -19,7 +19,8 @@
 
 use super::{CompleteOrdering, ModifierOrdering};
 use crate::netsim::config::ConfigExpr::{
-    self, BgpRouteMap, BgpSession, IgpLinkWeight, StaticRoute, AccessControl
+    self, AccessControl, BgpAggregate, BgpClusterId, BgpDecision, BgpNetwork, BgpRedistribute,
+    BgpRouteMap, BgpSession, IgpLinkArea, IgpLinkWeight, OspfStubArea, StaticRoute,
 };
 use crate::netsim::config::ConfigModifier::{self, Insert, Remove, Update};
 use crate::netsim::BgpSessionType::*;
@@ -29,7 +30,8 @@ use std::cmp::Ordering;
 /// #Simple Ordering
 /// The following ordering is used:
 /// - Modification type: Insert < Update < Remove
-/// - Expression type: IgpLinkWeight < BgpSession < BgpLocalPref
+/// - Expression type: StaticRoute < IgpLinkWeight < IgpLinkArea < OspfStubArea < BgpSession <
+///   BgpRouteMap < AccessControl
 /// - Values of each expression.
 pub struct SimpleOrdering {}
 
@@ -54,7 +56,7 @@ fn order_expr(a: &ConfigExpr, b: &ConfigExpr) -> Ordering {
         (
             StaticRoute { router: ra, prefix: pa, target: ta },
             StaticRoute { router: rb, prefix: pb, target: tb },
-        ) => match pa.0.cmp(&pb.0) {
+        ) => match pa.cmp(pb) {
             Ordering::Equal => order_two_routers(ra, rb, ta, tb),
             o => o,
         },
@@ -134,6 +136,75 @@ fn order_expr(a: &ConfigExpr, b: &ConfigExpr) -> Ordering {
         // TODO
             Ordering::Equal
         } 
+        (
+            IgpLinkArea { source: sa, target: ta, area: aa },
+            IgpLinkArea { source: sb, target: tb, area: ab },
+        ) => match aa.cmp(ab) {
+            Ordering::Equal => order_two_routers(sa, sb, ta, tb),
+            o => o,
+        },
+        (OspfStubArea { area: aa }, OspfStubArea { area: ab }) => aa.cmp(ab),
+        (IgpLinkArea { .. }, StaticRoute { .. })
+        | (IgpLinkArea { .. }, IgpLinkWeight { .. })
+        | (OspfStubArea { .. }, StaticRoute { .. })
+        | (OspfStubArea { .. }, IgpLinkWeight { .. })
+        | (OspfStubArea { .. }, IgpLinkArea { .. })
+        | (BgpSession { .. }, IgpLinkArea { .. })
+        | (BgpSession { .. }, OspfStubArea { .. })
+        | (BgpRouteMap { .. }, IgpLinkArea { .. })
+        | (BgpRouteMap { .. }, OspfStubArea { .. })
+        | (AccessControl { .. }, IgpLinkArea { .. })
+        | (AccessControl { .. }, OspfStubArea { .. }) => Ordering::Greater,
+        (StaticRoute { .. }, IgpLinkArea { .. })
+        | (IgpLinkWeight { .. }, IgpLinkArea { .. })
+        | (StaticRoute { .. }, OspfStubArea { .. })
+        | (IgpLinkWeight { .. }, OspfStubArea { .. })
+        | (IgpLinkArea { .. }, OspfStubArea { .. })
+        | (IgpLinkArea { .. }, BgpSession { .. })
+        | (OspfStubArea { .. }, BgpSession { .. })
+        | (IgpLinkArea { .. }, BgpRouteMap { .. })
+        | (OspfStubArea { .. }, BgpRouteMap { .. })
+        | (IgpLinkArea { .. }, AccessControl { .. })
+        | (OspfStubArea { .. }, AccessControl { .. }) => Ordering::Less,
+        // options of the BGP process are changed after everything else
+        (BgpDecision { router: ra, .. }, BgpDecision { router: rb, .. })
+        | (BgpClusterId { router: ra, .. }, BgpClusterId { router: rb, .. }) => ra.cmp(rb),
+        (BgpDecision { .. }, BgpClusterId { .. }) => Ordering::Less,
+        (BgpClusterId { .. }, BgpDecision { .. }) => Ordering::Greater,
+        (BgpDecision { .. }, _) | (BgpClusterId { .. }, _) => Ordering::Greater,
+        (_, BgpDecision { .. }) | (_, BgpClusterId { .. }) => Ordering::Less,
+        (BgpNetwork { router: ra, prefix: pa }, BgpNetwork { router: rb, prefix: pb }) => {
+            match pa.cmp(pb) {
+                Ordering::Equal => ra.cmp(rb),
+                o => o,
+            }
+        }
+        (
+            BgpRedistribute { router: ra, source: sa, .. },
+            BgpRedistribute { router: rb, source: sb, .. },
+        ) => match sa.cmp(sb) {
+            Ordering::Equal => ra.cmp(rb),
+            o => o,
+        },
+        (
+            BgpAggregate { router: ra, prefix: pa, .. },
+            BgpAggregate { router: rb, prefix: pb, .. },
+        ) => match pa.cmp(pb) {
+            Ordering::Equal => ra.cmp(rb),
+            o => o,
+        },
+        (BgpNetwork { .. }, BgpRedistribute { .. })
+        | (BgpNetwork { .. }, BgpAggregate { .. })
+        | (BgpRedistribute { .. }, BgpAggregate { .. }) => Ordering::Less,
+        (BgpRedistribute { .. }, BgpNetwork { .. })
+        | (BgpAggregate { .. }, BgpNetwork { .. })
+        | (BgpAggregate { .. }, BgpRedistribute { .. }) => Ordering::Greater,
+        (BgpNetwork { .. }, _) | (BgpRedistribute { .. }, _) | (BgpAggregate { .. }, _) => {
+            Ordering::Greater
+        }
+        (_, BgpNetwork { .. }) | (_, BgpRedistribute { .. }) | (_, BgpAggregate { .. }) => {
+            Ordering::Less
+        }
     }
 }
 
@@ -0,0 +1,477 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! # Access Control Lists
+//!
+//! This module contains the packet filters of internal routers. An [`AccessList`] is applied on
+//! an interface of a router (identified by the neighbor on the other end of the link), either on
+//! incoming or on outgoing traffic. It consists of an ordered list of [`AclEntry`], each of which
+//! matches on the five-tuple (source and destination prefix, protocol, source and destination
+//! port), and either permits or denies the packets. The first matching entry decides, and packets
+//! that match no entry are denied.
+//!
+//! Traffic is described by a [`TrafficClass`], with `None` meaning any value of the field. An entry
+//! may match only some packets of a class, in which case the outcome depends on the packet. The
+//! access list is then evaluated conservatively: [`AccessList::permits`] requires every packet of
+//! the class to be permitted (used to check that traffic reaches its destination), while
+//! [`AccessList::permits_any`] holds as soon as some packet may be permitted (used to check that
+//! traffic is dropped).
+
+use crate::netsim::Prefix;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// # Access Control List
+/// Ordered list of filter entries, applied on one interface of a router in one direction. Use the
+/// [`AclEntryBuilder`] to conveniently build the entries:
+///
+/// ```
+/// # use snowcap::netsim::acl::*;
+/// # use snowcap::netsim::Prefix;
+/// let acl = AccessList::new(vec![
+///     AclEntryBuilder::new().order(10).deny().match_protocol(Protocol::Tcp).build(),
+///     AclEntryBuilder::new().order(20).permit().match_dst(Prefix::from(0)).build(),
+/// ]);
+/// let udp = TrafficClass { protocol: Some(Protocol::Udp), ..Default::default() };
+/// assert!(acl.permits(&TrafficClass { dst: Some(Prefix::from(0)), ..udp }));
+/// assert!(!acl.permits(&udp));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccessList {
+    /// Entries, sorted by their order.
+    entries: Vec<AclEntry>,
+}
+
+impl AccessList {
+    /// Create a new access list from the given entries. The entries are sorted by their order.
+    pub fn new(mut entries: Vec<AclEntry>) -> Self {
+        entries.sort_by_key(|e| e.order);
+        Self { entries }
+    }
+
+    /// Returns all entries, sorted by their order.
+    pub fn entries(&self) -> &[AclEntry] {
+        &self.entries
+    }
+
+    /// Returns the action applied to every packet of the traffic class, or `None` if packets of
+    /// the class are treated differently. The first entry that matches a packet decides, and
+    /// packets that match no entry are denied.
+    pub fn action(&self, class: &TrafficClass) -> Option<AclAction> {
+        let mut partial: Option<AclAction> = None;
+        for e in self.entries.iter().filter(|e| e.overlaps(class)) {
+            if e.matches(class) {
+                return Self::combine(partial, e.action);
+            }
+            match partial {
+                Some(a) if a != e.action => return None,
+                _ => partial = Some(e.action),
+            }
+        }
+        Self::combine(partial, AclAction::Deny)
+    }
+
+    /// Returns `true` if every packet of the traffic class is permitted by the access list.
+    pub fn permits(&self, class: &TrafficClass) -> bool {
+        self.action(class) == Some(AclAction::Permit)
+    }
+
+    /// Returns `true` if some packet of the traffic class may be permitted by the access list,
+    /// i.e., if not every packet of the class is denied.
+    pub fn permits_any(&self, class: &TrafficClass) -> bool {
+        self.action(class) != Some(AclAction::Deny)
+    }
+
+    /// Combine the action of the entries matching only some packets with the action of the first
+    /// entry matching all remaining ones.
+    fn combine(partial: Option<AclAction>, action: AclAction) -> Option<AclAction> {
+        match partial {
+            Some(a) if a != action => None,
+            _ => Some(action),
+        }
+    }
+}
+
+impl fmt::Display for AccessList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, e) in self.entries.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", e)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// # Entry of an Access Control List
+/// Entry matching on the five-tuple of a packet. Every field that is `None` matches any value. Use
+/// the [`AclEntryBuilder`] to create a new entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AclEntry {
+    /// In which order should the entries be checked. Lower values are checked earlier.
+    pub(crate) order: usize,
+    /// Either permit or deny the matching packets.
+    pub(crate) action: AclAction,
+    /// Source prefix
+    pub(crate) src: Option<Prefix>,
+    /// Destination prefix
+    pub(crate) dst: Option<Prefix>,
+    /// Protocol
+    pub(crate) protocol: Option<Protocol>,
+    /// Range of source ports
+    pub(crate) src_port: Option<PortRange>,
+    /// Range of destination ports
+    pub(crate) dst_port: Option<PortRange>,
+}
+
+impl AclEntry {
+    /// Returns the order of the entry.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Returns the action, either permit or deny.
+    pub fn action(&self) -> AclAction {
+        self.action
+    }
+
+    /// Returns the source prefix, or `None` if the entry matches any source.
+    pub fn src(&self) -> Option<Prefix> {
+        self.src
+    }
+
+    /// Returns the destination prefix, or `None` if the entry matches any destination.
+    pub fn dst(&self) -> Option<Prefix> {
+        self.dst
+    }
+
+    /// Returns the protocol, or `None` if the entry matches any protocol.
+    pub fn protocol(&self) -> Option<Protocol> {
+        self.protocol
+    }
+
+    /// Returns the range of source ports, or `None` if the entry matches any source port.
+    pub fn src_port(&self) -> Option<PortRange> {
+        self.src_port
+    }
+
+    /// Returns the range of destination ports, or `None` if the entry matches any destination port.
+    pub fn dst_port(&self) -> Option<PortRange> {
+        self.dst_port
+    }
+
+    /// Returns `true` if the entry matches every packet of the traffic class.
+    pub fn matches(&self, class: &TrafficClass) -> bool {
+        matches_field(&self.src, &class.src, |e, c| e.contains(c))
+            && matches_field(&self.dst, &class.dst, |e, c| e.contains(c))
+            && matches_field(&self.protocol, &class.protocol, |e, c| e == c)
+            && matches_field(&self.src_port, &class.src_port, |e, c| e.contains(*c))
+            && matches_field(&self.dst_port, &class.dst_port, |e, c| e.contains(*c))
+    }
+
+    /// Returns `true` if the entry matches at least one packet of the traffic class.
+    pub fn overlaps(&self, class: &TrafficClass) -> bool {
+        overlaps_field(&self.src, &class.src, |e, c| e.contains(c) || c.contains(e))
+            && overlaps_field(&self.dst, &class.dst, |e, c| e.contains(c) || c.contains(e))
+            && overlaps_field(&self.protocol, &class.protocol, |e, c| e == c)
+            && overlaps_field(&self.src_port, &class.src_port, |e, c| e.contains(*c))
+            && overlaps_field(&self.dst_port, &class.dst_port, |e, c| e.contains(*c))
+    }
+}
+
+/// Returns `true` if the field of the entry matches every value of the field of the class.
+fn matches_field<E, C>(entry: &Option<E>, class: &Option<C>, f: impl Fn(&E, &C) -> bool) -> bool {
+    match (entry, class) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(e), Some(c)) => f(e, c),
+    }
+}
+
+/// Returns `true` if the field of the entry matches some value of the field of the class.
+fn overlaps_field<E, C>(entry: &Option<E>, class: &Option<C>, f: impl Fn(&E, &C) -> bool) -> bool {
+    match (entry, class) {
+        (Some(e), Some(c)) => f(e, c),
+        _ => true,
+    }
+}
+
+impl fmt::Display for AclEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.order, self.action)?;
+        match self.protocol {
+            Some(p) => write!(f, " {}", p)?,
+            None => write!(f, " ip")?,
+        }
+        match self.src {
+            Some(p) => write!(f, " {}", p)?,
+            None => write!(f, " any")?,
+        }
+        if let Some(r) = self.src_port {
+            write!(f, " port {}", r)?;
+        }
+        match self.dst {
+            Some(p) => write!(f, " {}", p)?,
+            None => write!(f, " any")?,
+        }
+        if let Some(r) = self.dst_port {
+            write!(f, " port {}", r)?;
+        }
+        Ok(())
+    }
+}
+
+/// # Access Control List Entry Builder
+/// Builder type for an [`AclEntry`]. Fields that are not matched on match any value.
+///
+/// ```
+/// # use snowcap::netsim::acl::*;
+/// # use snowcap::netsim::Prefix;
+/// let entry = AclEntryBuilder::new()
+///     .order(10)
+///     .permit()
+///     .match_src("10.0.0.0/8".parse().unwrap())
+///     .match_dst(Prefix::from(0))
+///     .match_protocol(Protocol::Udp)
+///     .match_dst_port(53, 53)
+///     .build();
+/// ```
+#[derive(Debug, Default)]
+pub struct AclEntryBuilder {
+    order: Option<usize>,
+    action: Option<AclAction>,
+    src: Option<Prefix>,
+    dst: Option<Prefix>,
+    protocol: Option<Protocol>,
+    src_port: Option<PortRange>,
+    dst_port: Option<PortRange>,
+}
+
+impl AclEntryBuilder {
+    /// Create an empty AclEntryBuilder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the order of the entry.
+    pub fn order(&mut self, order: usize) -> &mut Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Set the action of the entry.
+    pub fn action(&mut self, action: AclAction) -> &mut Self {
+        self.action = Some(action);
+        self
+    }
+
+    /// Set the action of the entry to permit. This function is identical to calling
+    /// `action(AclAction::Permit)`.
+    pub fn permit(&mut self) -> &mut Self {
+        self.action = Some(AclAction::Permit);
+        self
+    }
+
+    /// Set the action of the entry to deny. This function is identical to calling
+    /// `action(AclAction::Deny)`.
+    pub fn deny(&mut self) -> &mut Self {
+        self.action = Some(AclAction::Deny);
+        self
+    }
+
+    /// Match on packets whose source address is within the prefix.
+    pub fn match_src(&mut self, prefix: Prefix) -> &mut Self {
+        self.src = Some(prefix);
+        self
+    }
+
+    /// Match on packets whose destination address is within the prefix.
+    pub fn match_dst(&mut self, prefix: Prefix) -> &mut Self {
+        self.dst = Some(prefix);
+        self
+    }
+
+    /// Match on packets of the protocol.
+    pub fn match_protocol(&mut self, protocol: Protocol) -> &mut Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Match on packets whose source port is within `from` and `to` (both inclusive).
+    pub fn match_src_port(&mut self, from: u16, to: u16) -> &mut Self {
+        self.src_port = Some(PortRange { from, to });
+        self
+    }
+
+    /// Match on packets whose destination port is within `from` and `to` (both inclusive).
+    pub fn match_dst_port(&mut self, from: u16, to: u16) -> &mut Self {
+        self.dst_port = Some(PortRange { from, to });
+        self
+    }
+
+    /// Build the entry.
+    ///
+    /// # Panics
+    /// The function panics in the following cases:
+    /// - The order is not set (`order` was not called),
+    /// - The action is not set (neither `action`, `permit` nor `deny` were called),
+    pub fn build(&self) -> AclEntry {
+        let order = match self.order {
+            Some(o) => o,
+            None => panic!("Order was not set for an ACL entry!"),
+        };
+        let action = match self.action {
+            Some(a) => a,
+            None => panic!("Action was not set for an ACL entry!"),
+        };
+        AclEntry {
+            order,
+            action,
+            src: self.src,
+            dst: self.dst,
+            protocol: self.protocol,
+            src_port: self.src_port,
+            dst_port: self.dst_port,
+        }
+    }
+}
+
+/// Action of an access list entry, which can either be permit or deny
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AclAction {
+    /// Forward the packet
+    Permit,
+    /// Drop the packet
+    Deny,
+}
+
+impl fmt::Display for AclAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Permit => write!(f, "permit"),
+            Self::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+/// Direction in which an access list is applied on an interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AclDirection {
+    /// Filter packets received on the interface
+    Incoming,
+    /// Filter packets sent over the interface
+    Outgoing,
+}
+
+/// Transport protocol of a packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Protocol {
+    /// Transmission Control Protocol
+    Tcp,
+    /// User Datagram Protocol
+    Udp,
+    /// Internet Control Message Protocol
+    Icmp,
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp => write!(f, "tcp"),
+            Self::Udp => write!(f, "udp"),
+            Self::Icmp => write!(f, "icmp"),
+        }
+    }
+}
+
+/// Range of ports, where both ends are inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PortRange {
+    /// First port of the range
+    pub from: u16,
+    /// Last port of the range
+    pub to: u16,
+}
+
+impl PortRange {
+    /// Returns `true` if the port is within the range.
+    pub fn contains(&self, port: u16) -> bool {
+        self.from <= port && port <= self.to
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.from == self.to {
+            write!(f, "{}", self.from)
+        } else {
+            write!(f, "{}-{}", self.from, self.to)
+        }
+    }
+}
+
+/// # Traffic Class
+/// Set of packets described by their five-tuple. Every field that is `None` stands for any value.
+/// If the destination is not set, it is replaced by the destination prefix when checking the path
+/// of the traffic towards a prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrafficClass {
+    /// Source prefix
+    pub src: Option<Prefix>,
+    /// Destination prefix
+    pub dst: Option<Prefix>,
+    /// Protocol
+    pub protocol: Option<Protocol>,
+    /// Source port
+    pub src_port: Option<u16>,
+    /// Destination port
+    pub dst_port: Option<u16>,
+}
+
+impl fmt::Display for TrafficClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.protocol {
+            Some(p) => write!(f, "{}", p)?,
+            None => write!(f, "ip")?,
+        }
+        match self.src {
+            Some(p) => write!(f, " from {}", p)?,
+            None => write!(f, " from any")?,
+        }
+        if let Some(port) = self.src_port {
+            write!(f, " port {}", port)?;
+        }
+        match self.dst {
+            Some(p) => write!(f, " to {}", p)?,
+            None => write!(f, " to any")?,
+        }
+        if let Some(port) = self.dst_port {
+            write!(f, " port {}", port)?;
+        }
+        Ok(())
+    }
+}
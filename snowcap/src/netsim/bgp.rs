@@ -18,30 +18,59 @@
 //! Module containing definitions for BGP
 
 use crate::netsim::{AsId, LinkWeight, Prefix, RouterId};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 
 /// Bgp Route
 /// The following attributes are omitted
-/// - ORIGIN: assumed to be always set to IGP
 /// - ATOMIC_AGGREGATE: not used
 /// - AGGREGATOR: not used
 #[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BgpRoute {
-    /// IP PREFIX (represented as a simple number)
+    /// IP PREFIX
     pub prefix: Prefix,
     /// AS-PATH, where the origin of the route is last, and the ID of a new AS is prepended.
     pub as_path: Vec<AsId>,
+    /// AS_SET path segment (RFC 4271), following the AS sequence in `as_path`. It is created by
+    /// aggregates, and contains the AS numbers of all contributing routes. The set counts as a
+    /// single AS when comparing the length of the AS path.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub as_set: BTreeSet<AsId>,
     /// NEXT-HOP for reaching the source of the route.
     pub next_hop: RouterId,
     /// LOCAL-PREF
     pub local_pref: Option<u32>,
     /// MED (Multi-Exit Discriminator)
     pub med: Option<u32>,
-    /// Community
-    pub community: Option<u32>,
+    /// ORIGIN
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub origin: Origin,
+    /// Set of standard communities
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub communities: BTreeSet<u32>,
+    /// Set of large communities (RFC 8092)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub large_communities: BTreeSet<LargeCommunity>,
+    /// ORIGINATOR_ID (RFC 4456), set by the first route reflector to the router which announced
+    /// the route into the AS.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub originator_id: Option<RouterId>,
+    /// CLUSTER_LIST (RFC 4456), where every route reflector prepends its cluster ID.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cluster_list: Vec<RouterId>,
 }
 
 impl BgpRoute {
+    /// Returns the length of the AS path, as used by the decision process. The AS_SET counts as a
+    /// single AS, independent of its size (RFC 4271, Section 9.1.2.2).
+    pub fn as_path_len(&self) -> usize {
+        self.as_path.len() + if self.as_set.is_empty() { 0 } else { 1 }
+    }
+
     /// Applies the default values for any non-mandatory field
     #[allow(dead_code)]
     pub(crate) fn apply_default(&mut self) {
@@ -54,10 +83,15 @@ impl BgpRoute {
         Self {
             prefix: self.prefix,
             as_path: self.as_path.clone(),
+            as_set: self.as_set.clone(),
             next_hop: self.next_hop,
             local_pref: Some(self.local_pref.unwrap_or(100)),
             med: Some(self.med.unwrap_or(0)),
-            community: Some(self.community.unwrap_or(0)),
+            origin: self.origin,
+            communities: self.communities.clone(),
+            large_communities: self.large_communities.clone(),
+            originator_id: self.originator_id,
+            cluster_list: self.cluster_list.clone(),
         }
     }
 }
@@ -68,10 +102,15 @@ impl PartialEq for BgpRoute {
         let o = other.clone_default();
         s.prefix == o.prefix
             && s.as_path == other.as_path
+            && s.as_set == o.as_set
             && s.next_hop == o.next_hop
             && s.local_pref == o.local_pref
             && s.med == o.med
-            && s.community == o.community
+            && s.origin == o.origin
+            && s.communities == o.communities
+            && s.large_communities == o.large_communities
+            && s.originator_id == o.originator_id
+            && s.cluster_list == o.cluster_list
     }
 }
 
@@ -80,15 +119,89 @@ impl std::hash::Hash for BgpRoute {
         let s = self.clone_default();
         s.prefix.hash(state);
         s.as_path.hash(state);
+        s.as_set.hash(state);
         s.next_hop.hash(state);
         s.local_pref.hash(state);
         s.med.hash(state);
-        s.community.hash(state);
+        s.origin.hash(state);
+        s.communities.hash(state);
+        s.large_communities.hash(state);
+        s.originator_id.hash(state);
+        s.cluster_list.hash(state);
+    }
+}
+
+/// ORIGIN attribute of a BGP route. During the decision process, `Igp` is preferred over `Egp`,
+/// which is preferred over `Incomplete`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Origin {
+    /// The route was learned from an interior gateway protocol
+    #[default]
+    Igp,
+    /// The route was learned via EGP
+    Egp,
+    /// The route was learned by some other means (e.g., redistribution)
+    Incomplete,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Igp => write!(f, "igp"),
+            Self::Egp => write!(f, "egp"),
+            Self::Incomplete => write!(f, "incomplete"),
+        }
+    }
+}
+
+/// Source of routes that are redistributed into BGP by an internal router.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RedistributionSource {
+    /// All static routes of the router that point to a neighbor.
+    Static,
+    /// All connected prefixes of the router, i.e., static routes that point to the router itself.
+    Connected,
+}
+
+impl fmt::Display for RedistributionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Static => write!(f, "static"),
+            Self::Connected => write!(f, "connected"),
+        }
+    }
+}
+
+/// Large BGP community (RFC 8092), written as `global:local1:local2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LargeCommunity {
+    /// Global administrator (usually the AS number)
+    pub global: u32,
+    /// First local data part
+    pub local1: u32,
+    /// Second local data part
+    pub local2: u32,
+}
+
+impl LargeCommunity {
+    /// Create a new large community
+    pub fn new(global: u32, local1: u32, local2: u32) -> Self {
+        Self { global, local1, local2 }
+    }
+}
+
+impl fmt::Display for LargeCommunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.global, self.local1, self.local2)
     }
 }
 
 /// Type of a BGP session
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BgpSessionType {
     /// iBGP session with a peer (or from a coient with a Route Reflector)
     IBgpPeer,
@@ -150,50 +263,160 @@ impl PartialEq for BgpRibEntry {
     }
 }
 
-impl PartialOrd for BgpRibEntry {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+impl BgpRibEntry {
+    /// Returns the neighboring AS from which the route was learned, i.e., the first AS on the AS
+    /// path. Routes originated inside the own AS have no neighboring AS.
+    pub fn neighbor_as(&self) -> Option<AsId> {
+        self.route.as_path.first().copied()
+    }
+
+    /// Compare two entries according to the BGP decision process, configured by `config`. The
+    /// result is `Ordering::Greater` if `self` is preferred over `other`. See
+    /// [`BgpDecisionConfig`] for the individual steps.
+    ///
+    /// MED is skipped if the two routes were learned from different neighboring ASes (and
+    /// `always_compare_med` is not set). Use [`BgpDecisionConfig::best_route`] to select the best
+    /// out of multiple routes, which takes care of this.
+    pub fn compare(&self, other: &Self, config: &BgpDecisionConfig) -> Ordering {
+        match self.compare_path_attributes(other, config) {
+            Ordering::Equal => {}
+            o => return o,
+        }
+
+        match self.router_id().cmp(&other.router_id()) {
+            Ordering::Equal => {}
+            o => return o.reverse(),
+        }
+
+        let s = self.route.clone_default();
+        let o = other.route.clone_default();
+        match s.cluster_list.len().cmp(&o.cluster_list.len()) {
+            Ordering::Equal => {}
+            o => return o.reverse(),
+        }
+
+        self.from_id.cmp(&other.from_id).reverse()
+    }
+
+    /// Compare the first six steps of the BGP decision process (up to, and including the IGP
+    /// cost). Routes for which this returns `Ordering::Equal` only differ in the tie-breakers, and
+    /// are candidates for multipath forwarding.
+    fn compare_path_attributes(&self, other: &Self, config: &BgpDecisionConfig) -> Ordering {
         let s = self.route.clone_default();
         let o = other.route.clone_default();
 
         match s.local_pref.unwrap().cmp(&o.local_pref.unwrap()) {
             Ordering::Equal => {}
-            o => return Some(o),
+            o => return o,
         }
 
-        match s.as_path.len().cmp(&o.as_path.len()) {
+        match s.as_path_len().cmp(&o.as_path_len()) {
             Ordering::Equal => {}
-            Ordering::Greater => return Some(Ordering::Less),
-            Ordering::Less => return Some(Ordering::Greater),
+            o => return o.reverse(),
         }
 
-        match s.med.unwrap().cmp(&o.med.unwrap()) {
+        match s.origin.cmp(&o.origin) {
             Ordering::Equal => {}
-            Ordering::Greater => return Some(Ordering::Less),
-            Ordering::Less => return Some(Ordering::Greater),
+            o => return o.reverse(),
+        }
+
+        if config.always_compare_med || self.neighbor_as() == other.neighbor_as() {
+            let missing_med = if config.med_missing_as_worst { u32::MAX } else { 0 };
+            let s_med = self.route.med.unwrap_or(missing_med);
+            let o_med = other.route.med.unwrap_or(missing_med);
+            match s_med.cmp(&o_med) {
+                Ordering::Equal => {}
+                o => return o.reverse(),
+            }
         }
 
         if self.from_type.is_ebgp() && other.from_type.is_ibgp() {
-            return Some(Ordering::Greater);
+            return Ordering::Greater;
         } else if self.from_type.is_ibgp() && other.from_type.is_ebgp() {
-            return Some(Ordering::Less);
+            return Ordering::Less;
         }
 
         match self.igp_cost.unwrap().partial_cmp(&other.igp_cost.unwrap()) {
-            Some(Ordering::Equal) | None => {}
-            Some(Ordering::Greater) => return Some(Ordering::Less),
-            Some(Ordering::Less) => return Some(Ordering::Greater),
+            Some(Ordering::Equal) | None => Ordering::Equal,
+            Some(o) => o.reverse(),
         }
+    }
 
-        match s.next_hop.cmp(&o.next_hop) {
-            Ordering::Equal => {}
-            Ordering::Greater => return Some(Ordering::Less),
-            Ordering::Less => return Some(Ordering::Greater),
-        }
+    /// Returns the router-id used in the decision process, which is the ORIGINATOR_ID if present,
+    /// and the neighbor from which the route was learned otherwise (RFC 4456, Section 9).
+    pub fn router_id(&self) -> RouterId {
+        self.route.originator_id.unwrap_or(self.from_id)
+    }
+}
+
+/// Configuration of the BGP decision process of a single router. The decision process selects the
+/// best route by comparing the following attributes, in this order (RFC 4271, Section 9.1.2.2):
+///
+/// 1. Highest LOCAL_PREF (missing values are treated as `100`)
+/// 2. Shortest AS_PATH
+/// 3. Lowest ORIGIN (`igp` < `egp` < `incomplete`)
+/// 4. Lowest MED. By default, MED is only compared between routes from the same neighboring AS,
+///    and a missing MED is treated as `0`. See `always_compare_med` and `med_missing_as_worst`.
+/// 5. Routes learned via eBGP over routes learned via iBGP
+/// 6. Lowest IGP cost to the next hop
+/// 7. Lowest router-id, which is the ORIGINATOR_ID if present, or the neighbor from which the
+///    route was learned otherwise
+/// 8. Shortest CLUSTER_LIST
+/// 9. Lowest neighbor from which the route was learned
+///
+/// MED is evaluated deterministically: routes are first grouped by their neighboring AS, the best
+/// route of each group is selected, and only then the best routes of all groups are compared with
+/// each other. This matches FRR with `bgp deterministic-med` and `bgp bestpath compare-routerid`
+/// enabled, which is how `snowcap_runtime` configures the routers.
+///
+/// If `multipath` is enabled, traffic is forwarded over all routes that are equal to the best route
+/// in steps 1 to 6, and that are learned from the same neighboring AS (see
+/// [`BgpDecisionConfig::is_multipath`]). Only the best route is advertised to other peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BgpDecisionConfig {
+    /// Compare MED of routes from different neighboring ASes (`bgp always-compare-med`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub always_compare_med: bool,
+    /// Treat a missing MED as the worst possible value instead of `0` (`bgp bestpath med
+    /// missing-as-worst`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub med_missing_as_worst: bool,
+    /// Forward traffic over all equally good routes instead of only the best one (`maximum-paths`
+    /// and `maximum-paths ibgp`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub multipath: bool,
+}
 
-        match self.from_id.cmp(&other.from_id) {
-            Ordering::Equal => Some(Ordering::Equal),
-            Ordering::Greater => Some(Ordering::Less),
-            Ordering::Less => Some(Ordering::Greater),
+impl BgpDecisionConfig {
+    /// Select the best route out of all `entries`. MED is compared deterministically, by first
+    /// selecting the best route for each neighboring AS, and then choosing the best of those.
+    pub fn best_route<I>(&self, entries: I) -> Option<BgpRibEntry>
+    where
+        I: IntoIterator<Item = BgpRibEntry>,
+    {
+        let mut groups: BTreeMap<Option<AsId>, BgpRibEntry> = BTreeMap::new();
+        for entry in entries {
+            let key = if self.always_compare_med { None } else { entry.neighbor_as() };
+            match groups.get(&key) {
+                Some(best) if entry.compare(best, self) != Ordering::Greater => {}
+                _ => {
+                    groups.insert(key, entry);
+                }
+            }
         }
+        groups.into_values().fold(None, |best, entry| match best {
+            Some(best) if entry.compare(&best, self) != Ordering::Greater => Some(best),
+            _ => Some(entry),
+        })
+    }
+
+    /// Returns `true` if traffic may be forwarded using `entry` in addition to the selected `best`
+    /// route. This requires `multipath` to be enabled, both routes to be learned from the same
+    /// neighboring AS, and both routes to be equal up to (and including) the IGP cost.
+    pub fn is_multipath(&self, entry: &BgpRibEntry, best: &BgpRibEntry) -> bool {
+        self.multipath
+            && entry.neighbor_as() == best.neighbor_as()
+            && entry.compare_path_attributes(best, self) == Ordering::Equal
     }
 }
@@ -71,11 +71,15 @@
 //! }
 //! ```
 
-use crate::netsim::bgp::BgpSessionType;
+use crate::netsim::acl::{AccessList, AclDirection};
+use crate::netsim::bgp::{BgpDecisionConfig, BgpSessionType, RedistributionSource};
 use crate::netsim::route_map::{RouteMap, RouteMapDirection};
-use crate::netsim::{ConfigError, LinkWeight, Prefix, RouterId};
+use crate::netsim::{ConfigError, LinkWeight, OspfArea, Prefix, RouterId};
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 
 /// # Network Configuration
 /// This struct represents the configuration of a network. It is made up of several *unordered*
@@ -88,7 +92,12 @@ use std::collections::{HashMap, HashSet};
 /// The `Config` struct contains only "unique" `ConfigExpr`. This means, that a config cannot have a
 /// expression to set a specific link weight to 1, and another expression setting the same link to
 /// 2.0.
+///
+/// If the `serde` feature is enabled, the configuration is (de)serialized as a list of all its
+/// expressions.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "Vec<ConfigExpr>", try_from = "Vec<ConfigExpr>"))]
 pub struct Config {
     /// All lines of configuration
     pub(crate) expr: HashMap<ConfigExprKey, ConfigExpr>,
@@ -228,6 +237,26 @@ impl Config {
     }
 }
 
+impl From<Config> for Vec<ConfigExpr> {
+    fn from(config: Config) -> Self {
+        config.expr.into_values().collect()
+    }
+}
+
+impl TryFrom<Vec<ConfigExpr>> for Config {
+    type Error = ConfigError;
+
+    /// Build a configuration from a list of expressions. This fails if two expressions have the
+    /// same key.
+    fn try_from(exprs: Vec<ConfigExpr>) -> Result<Self, Self::Error> {
+        let mut config = Self::new();
+        for expr in exprs {
+            config.add(expr)?;
+        }
+        Ok(config)
+    }
+}
+
 impl PartialEq for Config {
     fn eq(&self, other: &Self) -> bool {
         for (key, self_e) in self.expr.iter() {
@@ -246,6 +275,7 @@ impl PartialEq for Config {
 /// # Single configuration expression
 /// The expression sets a specific thing in the network.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ConfigExpr {
     /// Sets the link weight of a single link (directional)
     /// TODO make sure that the weight is strictly smaller than infinity.
@@ -280,7 +310,8 @@ pub enum ConfigExpr {
         /// Route Map
         map: RouteMap,
     },
-    /// Set a static route
+    /// Set a static route. If `target` is the router itself, the prefix is connected to the router,
+    /// and all packets are delivered locally.
     StaticRoute {
         /// On which router set the static route
         router: RouterId,
@@ -289,14 +320,88 @@ pub enum ConfigExpr {
         /// To which neighbor to forward packets to.
         target: RouterId,
     },
-    /// Access control functionality
+    /// Apply an access list on the interface of `router` towards `interface`, filtering either
+    /// the incoming or the outgoing packets.
     AccessControl {
         /// On which router the acl is applied
         router: RouterId,
-        /// Routers accepted (default *)
-        accept: Vec<RouterId>,
-        /// Routers are denied (default none)
-        deny: Vec<RouterId>,
+        /// Neighbor at the other end of the interface
+        interface: RouterId,
+        /// Direction (incoming or outgoing)
+        direction: AclDirection,
+        /// Access list
+        acl: AccessList,
+    },
+    /// Assign a link to an OSPF area. In contrast to the link weight, the area is configured on
+    /// both ends of the link at once, so `source` and `target` are interchangeable. Links without
+    /// an area are part of the backbone.
+    IgpLinkArea {
+        /// One end of the link
+        source: RouterId,
+        /// Other end of the link
+        target: RouterId,
+        /// Area to which the link belongs
+        area: OspfArea,
+    },
+    /// Configure an OSPF area as a stub area. Routers inside a stub area do not learn routes
+    /// towards external routers from other areas, but use the default route of the area border
+    /// routers instead.
+    OspfStubArea {
+        /// The area that is configured as a stub area (must not be the backbone)
+        area: OspfArea,
+    },
+    /// Announce a prefix into BGP from an internal router (BGP `network` statement). The route is
+    /// originated with an empty AS path and origin `IGP`. Packets towards the prefix are delivered
+    /// to the router itself, unless it has a static route for the prefix.
+    BgpNetwork {
+        /// Router which originates the prefix
+        router: RouterId,
+        /// Prefix that is announced
+        prefix: Prefix,
+    },
+    /// Redistribute routes of the router into BGP. The routes are originated with an empty AS path
+    /// and origin `incomplete`. They pass through the route-maps in the order of their `order`
+    /// field (like the BGP route-maps), and routes that are not matched by any route-map are
+    /// redistributed unchanged.
+    BgpRedistribute {
+        /// Router which redistributes the routes
+        router: RouterId,
+        /// Which routes are redistributed
+        source: RedistributionSource,
+        /// Route-maps to filter or modify the redistributed routes
+        route_maps: Vec<RouteMap>,
+    },
+    /// Create an aggregate route for `prefix` (`aggregate-address`), as long as the router has
+    /// selected a route for at least one more-specific prefix. The aggregate is originated with an
+    /// empty AS path and the worst origin of all contributing routes. At the router itself, traffic
+    /// that only matches the aggregate is dropped.
+    BgpAggregate {
+        /// Router which creates the aggregate
+        router: RouterId,
+        /// Prefix of the aggregate
+        prefix: Prefix,
+        /// If set, the more-specific prefixes are no longer advertised to any peer.
+        summary_only: bool,
+        /// If set, the aggregate carries an `AS_SET` path segment with all AS numbers of the
+        /// contributing routes (see [`BgpRoute::as_set`](crate::netsim::bgp::BgpRoute::as_set)).
+        as_set: bool,
+    },
+    /// Configure the BGP decision process of a router. Routers without this expression use the
+    /// default configuration (see [`BgpDecisionConfig`]).
+    BgpDecision {
+        /// Router to configure
+        router: RouterId,
+        /// Configuration of the decision process
+        config: BgpDecisionConfig,
+    },
+    /// Configure the cluster ID of a router, used for route reflection. The cluster ID is given as
+    /// the router ID of one of the routers in the cluster. Routers without this expression use
+    /// their own router ID as cluster ID.
+    BgpClusterId {
+        /// Router to configure
+        router: RouterId,
+        /// Cluster ID
+        cluster_id: RouterId,
     },
 }
 
@@ -324,11 +429,36 @@ impl ConfigExpr {
             ConfigExpr::StaticRoute { router, prefix, target: _ } => {
                 ConfigExprKey::StaticRoute { router: *router, prefix: *prefix }
             }
-            ConfigExpr::AccessControl { router, accept: _, deny: _ } => {
+            ConfigExpr::AccessControl { router, interface, direction, acl: _ } => {
                 ConfigExprKey::AccessControl {
                     router: *router,
+                    interface: *interface,
+                    direction: *direction,
                 }
             }
+            ConfigExpr::IgpLinkArea { source, target, area: _ } => {
+                if source < target {
+                    ConfigExprKey::IgpLinkArea { router_a: *source, router_b: *target }
+                } else {
+                    ConfigExprKey::IgpLinkArea { router_a: *target, router_b: *source }
+                }
+            }
+            ConfigExpr::OspfStubArea { area } => ConfigExprKey::OspfStubArea { area: *area },
+            ConfigExpr::BgpNetwork { router, prefix } => {
+                ConfigExprKey::BgpNetwork { router: *router, prefix: *prefix }
+            }
+            ConfigExpr::BgpRedistribute { router, source, route_maps: _ } => {
+                ConfigExprKey::BgpRedistribute { router: *router, source: *source }
+            }
+            ConfigExpr::BgpAggregate { router, prefix, .. } => {
+                ConfigExprKey::BgpAggregate { router: *router, prefix: *prefix }
+            }
+            ConfigExpr::BgpDecision { router, config: _ } => {
+                ConfigExprKey::BgpDecision { router: *router }
+            }
+            ConfigExpr::BgpClusterId { router, cluster_id: _ } => {
+                ConfigExprKey::BgpClusterId { router: *router }
+            }
         }
     }
 
@@ -340,6 +470,13 @@ impl ConfigExpr {
             ConfigExpr::BgpRouteMap { router, .. } => vec![*router],
             ConfigExpr::StaticRoute { router, .. } => vec![*router],
             ConfigExpr::AccessControl { router, .. } => vec![*router],
+            ConfigExpr::IgpLinkArea { source, target, .. } => vec![*source, *target],
+            ConfigExpr::OspfStubArea { .. } => vec![],
+            ConfigExpr::BgpNetwork { router, .. } => vec![*router],
+            ConfigExpr::BgpRedistribute { router, .. } => vec![*router],
+            ConfigExpr::BgpAggregate { router, .. } => vec![*router],
+            ConfigExpr::BgpDecision { router, .. } => vec![*router],
+            ConfigExpr::BgpClusterId { router, .. } => vec![*router],
         }
     }
 }
@@ -359,7 +496,7 @@ impl ConfigExpr {
 /// it would be used as a key-value store. By using a different struct, it is very clear how the
 /// `Config` is indexed, and which expressions represent the same key. In addition, it does not
 /// require us to reimplement `Eq` and `Hash`.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ConfigExprKey {
     /// Sets the link weight of a single link (directional)
     IgpLinkWeight {
@@ -391,17 +528,65 @@ pub enum ConfigExprKey {
         /// Prefix for which to configure the router
         prefix: Prefix,
     },
-    /// Key for setting access control on a given router
+    /// Key for the access list on an interface of a router
     AccessControl {
         /// The router that has this ACL field
         router: RouterId,
-    }
+        /// Neighbor at the other end of the interface
+        interface: RouterId,
+        /// Direction (incoming or outgoing)
+        direction: AclDirection,
+    },
+    /// Key for the OSPF area of a link (bidirectional)
+    IgpLinkArea {
+        /// One end of the link (with the smaller router ID)
+        router_a: RouterId,
+        /// Other end of the link (with the larger router ID)
+        router_b: RouterId,
+    },
+    /// Key for configuring an area as a stub area
+    OspfStubArea {
+        /// The area
+        area: OspfArea,
+    },
+    /// Key for a BGP network statement
+    BgpNetwork {
+        /// Router which originates the prefix
+        router: RouterId,
+        /// Prefix that is announced
+        prefix: Prefix,
+    },
+    /// Key for redistributing routes into BGP
+    BgpRedistribute {
+        /// Router which redistributes the routes
+        router: RouterId,
+        /// Which routes are redistributed
+        source: RedistributionSource,
+    },
+    /// Key for a BGP aggregate
+    BgpAggregate {
+        /// Router which creates the aggregate
+        router: RouterId,
+        /// Prefix of the aggregate
+        prefix: Prefix,
+    },
+    /// Key for the configuration of the BGP decision process
+    BgpDecision {
+        /// Router to configure
+        router: RouterId,
+    },
+    /// Key for the cluster ID of a router
+    BgpClusterId {
+        /// Router to configure
+        router: RouterId,
+    },
 }
 
 /// # Config Modifier
 /// A single patch to apply on a configuration. The modifier can either insert a new expression,
 /// update an existing expression or remove an old expression.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ConfigModifier {
     /// Insert a new expression
     Insert(ConfigExpr),
@@ -450,6 +635,7 @@ impl ConfigModifier {
 /// A series of `ConfigModifiers` which can be applied on a `Config` to get a new `Config`. The
 /// series is an ordered list, and the modifiers are applied in the order they were added.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ConfigPatch {
     /// List of all modifiers, in the order in which they are applied.
     pub modifiers: Vec<ConfigModifier>,
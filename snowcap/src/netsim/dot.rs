@@ -0,0 +1,309 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! # Graphviz export of the Network
+//! Module containing functions to render the network and its forwarding state in the DOT language,
+//! which can be drawn with [Graphviz](https://graphviz.org), e.g. `dot -Tpdf net.dot -o net.pdf`.
+//!
+//! - [`topology`] renders the physical links (with their IGP weights) and the BGP sessions.
+//! - [`forwarding_state`] renders the forwarding graph of a single prefix, where forwarding loops
+//!   and black holes are highlighted in red.
+//! - [`forwarding_diff`] renders the difference between two forwarding states, and
+//!   [`sequence_diffs`] the differences of all consecutive states while applying a sequence of
+//!   modifiers.
+
+use crate::netsim::config::ConfigModifier;
+use crate::netsim::network::Network;
+use crate::netsim::printer;
+use crate::netsim::types::Destination;
+use crate::netsim::{
+    BgpSessionType, ForwardingState, NetworkDevice, NetworkError, Prefix, RouterId,
+};
+
+use std::collections::HashSet;
+
+/// Render the topology of the network as an undirected DOT graph. Internal routers are drawn as
+/// ellipses and external routers as boxes. Every physical link is labelled with its IGP weights
+/// (in both directions, if they differ), and failed links are drawn dotted. iBGP sessions are
+/// drawn dashed, where sessions between a route reflector and its client point towards the client.
+/// eBGP sessions are drawn bold.
+pub fn topology(net: &Network) -> Result<String, NetworkError> {
+    let g = net.get_topology();
+    let weight = |a: RouterId, b: RouterId| g.find_edge(a, b).map(|e| g[e]);
+
+    let mut lines = vec![String::from("graph {")];
+    lines.extend(nodes(net)?);
+
+    // physical links
+    for (a, b) in net.links_symmetric() {
+        let label = match (weight(*a, *b), weight(*b, *a)) {
+            (Some(w_ab), Some(w_ba)) if w_ab == w_ba => format!("{}", w_ab),
+            (Some(w_ab), Some(w_ba)) => format!("{} / {}", w_ab, w_ba),
+            (Some(w), None) | (None, Some(w)) => format!("{}", w),
+            (None, None) => String::new(),
+        };
+        let style = if net.is_link_down(*a, *b) { ", style=dotted, color=gray" } else { "" };
+        lines.push(format!(
+            "    {} -- {} [label=\"{}\"{}];",
+            node_id(*a),
+            node_id(*b),
+            label,
+            style
+        ));
+    }
+
+    // BGP sessions
+    for router in sorted(net.get_routers()) {
+        let mut sessions: Vec<(RouterId, BgpSessionType)> = net
+            .get_device(router)
+            .unwrap_internal()
+            .get_bgp_sessions()
+            .map(|(n, t)| (*n, *t))
+            .collect();
+        sessions.sort_by_key(|(n, _)| *n);
+        for (neighbor, session_type) in sessions {
+            let attrs = match session_type {
+                BgpSessionType::EBgp => "style=bold, color=blue",
+                BgpSessionType::IBgpClient => "style=dashed, color=darkgreen, dir=forward",
+                BgpSessionType::IBgpPeer => {
+                    // the session is drawn by the route reflector, or by the smaller router id
+                    let reverse = match net.get_device(neighbor) {
+                        NetworkDevice::InternalRouter(r) => r.get_bgp_session_type(router),
+                        _ => None,
+                    };
+                    if reverse == Some(BgpSessionType::IBgpClient) || neighbor < router {
+                        continue;
+                    }
+                    "style=dashed, color=darkgreen"
+                }
+            };
+            lines.push(format!("    {} -- {} [{}];", node_id(router), node_id(neighbor), attrs));
+        }
+    }
+
+    lines.push(String::from("}"));
+    Ok(lines.join("\n"))
+}
+
+/// Render the forwarding graph of `prefix` as a directed DOT graph. Every router has an edge to
+/// each of its next hops towards the most specific known prefix containing `prefix`. Edges which
+/// are part of a forwarding loop, and routers at which traffic is dropped (black holes), are
+/// highlighted in red. Routers at which traffic leaves the network are drawn with a double border.
+pub fn forwarding_state(
+    net: &Network,
+    state: &ForwardingState,
+    prefix: Prefix,
+) -> Result<String, NetworkError> {
+    let edges = next_hop_edges(net, state, prefix)?;
+    let problems = Problems::new(net, state, prefix);
+
+    let mut lines = vec![format!("digraph {{\n    label=\"{}\";", prefix)];
+    lines.extend(forwarding_nodes(net, state, prefix, &problems)?);
+    for (a, b) in edges {
+        let attrs = if problems.loop_edges.contains(&(a, b)) { " [color=red]" } else { "" };
+        lines.push(format!("    {} -> {}{};", node_id(a), node_id(b), attrs));
+    }
+    lines.push(String::from("}"));
+    Ok(lines.join("\n"))
+}
+
+/// Render the difference between the forwarding graphs of `prefix` in `before` and `after` as a
+/// directed DOT graph. Next hops present in both states are drawn in black, new next hops in bold
+/// green, and removed next hops dashed in gray. Forwarding loops and black holes of `after` are
+/// highlighted like in [`forwarding_state`].
+pub fn forwarding_diff(
+    net: &Network,
+    before: &ForwardingState,
+    after: &ForwardingState,
+    prefix: Prefix,
+) -> Result<String, NetworkError> {
+    diff_graph(net, before, after, prefix, &prefix.to_string())
+}
+
+/// Apply the sequence of modifiers on a copy of the network, and render the difference of the
+/// forwarding graph of `prefix` for every step with [`forwarding_diff`]. The result contains one
+/// graph for each modifier, labelled with the applied modifier.
+pub fn sequence_diffs(
+    net: &Network,
+    sequence: &[ConfigModifier],
+    prefix: Prefix,
+) -> Result<Vec<String>, NetworkError> {
+    let mut net = net.clone();
+    let mut before = net.get_forwarding_state();
+    let mut graphs = Vec::with_capacity(sequence.len());
+    for modifier in sequence {
+        net.apply_modifier(modifier)?;
+        let after = net.get_forwarding_state();
+        let label = format!("{}: {}", prefix, printer::config_modifier(&net, modifier)?);
+        graphs.push(diff_graph(&net, &before, &after, prefix, &label)?);
+        before = after;
+    }
+    Ok(graphs)
+}
+
+/// Render the difference between both forwarding states, using the given graph label.
+fn diff_graph(
+    net: &Network,
+    before: &ForwardingState,
+    after: &ForwardingState,
+    prefix: Prefix,
+    label: &str,
+) -> Result<String, NetworkError> {
+    let old_edges = next_hop_edges(net, before, prefix)?;
+    let new_edges = next_hop_edges(net, after, prefix)?;
+    let problems = Problems::new(net, after, prefix);
+
+    let mut lines = vec![format!("digraph {{\n    label=\"{}\";", escape(label))];
+    lines.extend(forwarding_nodes(net, after, prefix, &problems)?);
+    for (a, b) in new_edges.iter() {
+        let in_loop = problems.loop_edges.contains(&(*a, *b));
+        let attrs = match (old_edges.contains(&(*a, *b)), in_loop) {
+            (true, false) => "",
+            (true, true) => " [color=red]",
+            (false, false) => " [color=darkgreen, style=bold]",
+            (false, true) => " [color=red, style=bold]",
+        };
+        lines.push(format!("    {} -> {}{};", node_id(*a), node_id(*b), attrs));
+    }
+    for (a, b) in old_edges.iter().filter(|e| !new_edges.contains(e)) {
+        lines.push(format!("    {} -> {} [color=gray, style=dashed];", node_id(*a), node_id(*b)));
+    }
+    lines.push(String::from("}"));
+    Ok(lines.join("\n"))
+}
+
+/// Forwarding loops and black holes in the forwarding graph of a single prefix, reached from any
+/// internal router. An edge is part of a forwarding loop if its target can reach its source.
+struct Problems {
+    loop_edges: HashSet<(RouterId, RouterId)>,
+    black_holes: HashSet<RouterId>,
+}
+
+impl Problems {
+    fn new(net: &Network, state: &ForwardingState, prefix: Prefix) -> Self {
+        let next_hops = |r: RouterId| {
+            state.get_next_hops_new(r, Destination::BGP(prefix)).unwrap_or(&[]).to_vec()
+        };
+        // all routers reachable from `r` (including `r` itself), visiting every router only once
+        let reachable = |r: RouterId| {
+            let mut visited = HashSet::new();
+            let mut stack = vec![r];
+            while let Some(r) = stack.pop() {
+                if visited.insert(r) {
+                    stack.extend(next_hops(r).into_iter().filter(|nh| *nh != r));
+                }
+            }
+            visited
+        };
+        let mut routers = HashSet::new();
+        for router in net.get_routers() {
+            if !routers.contains(&router) {
+                routers.extend(reachable(router));
+            }
+        }
+        let mut loop_edges = HashSet::new();
+        let mut black_holes = HashSet::new();
+        for router in routers {
+            let nhs = next_hops(router);
+            if nhs.is_empty() {
+                black_holes.insert(router);
+            }
+            for nh in nhs.into_iter().filter(|nh| *nh != router) {
+                if reachable(nh).contains(&router) {
+                    loop_edges.insert((router, nh));
+                }
+            }
+        }
+        Self { loop_edges, black_holes }
+    }
+}
+
+/// Returns all next hop edges (sorted) of the forwarding graph of the prefix. Routers at which
+/// traffic leaves the network (having themselves as next hop) have no edge.
+fn next_hop_edges(
+    net: &Network,
+    state: &ForwardingState,
+    prefix: Prefix,
+) -> Result<Vec<(RouterId, RouterId)>, NetworkError> {
+    let mut edges = Vec::new();
+    for router in all_devices(net) {
+        for next_hop in state.get_next_hops_new(router, Destination::BGP(prefix))? {
+            if *next_hop != router {
+                edges.push((router, *next_hop));
+            }
+        }
+    }
+    Ok(edges)
+}
+
+/// Returns the node statements of the forwarding graph, marking the black holes and egress
+/// routers.
+fn forwarding_nodes(
+    net: &Network,
+    state: &ForwardingState,
+    prefix: Prefix,
+    problems: &Problems,
+) -> Result<Vec<String>, NetworkError> {
+    let mut lines = Vec::new();
+    for router in all_devices(net) {
+        let next_hops = state.get_next_hops_new(router, Destination::BGP(prefix))?;
+        let mut attrs = node_attrs(net, router)?;
+        if problems.black_holes.contains(&router) {
+            attrs.push_str(", style=filled, fillcolor=red");
+        } else if next_hops.first() == Some(&router) {
+            attrs.push_str(", peripheries=2");
+        }
+        lines.push(format!("    {} [{}];", node_id(router), attrs));
+    }
+    Ok(lines)
+}
+
+/// Returns the node statements of all devices, labelled with their names.
+fn nodes(net: &Network) -> Result<Vec<String>, NetworkError> {
+    all_devices(net)
+        .into_iter()
+        .map(|r| Ok(format!("    {} [{}];", node_id(r), node_attrs(net, r)?)))
+        .collect()
+}
+
+/// Returns the label and shape of the node.
+fn node_attrs(net: &Network, router: RouterId) -> Result<String, NetworkError> {
+    let shape = if net.get_device(router).is_internal() { "ellipse" } else { "box" };
+    Ok(format!("label=\"{}\", shape={}", escape(net.get_router_name(router)?), shape))
+}
+
+/// Returns the identifier of the router in the DOT graph.
+fn node_id(router: RouterId) -> String {
+    format!("r{}", router.index())
+}
+
+/// Returns all internal and external routers, sorted by their id.
+fn all_devices(net: &Network) -> Vec<RouterId> {
+    let mut devices = net.get_routers();
+    devices.extend(net.get_external_routers());
+    sorted(devices)
+}
+
+fn sorted(mut routers: Vec<RouterId>) -> Vec<RouterId> {
+    routers.sort();
+    routers
+}
+
+/// Escape a string to be used inside a quoted DOT identifier.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
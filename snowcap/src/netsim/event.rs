@@ -33,6 +33,14 @@ pub enum Event {
     AdvertiseExternalRoute(RouterId, BgpRoute),
     /// Remove the advertisement of an external route
     WithdrawExternalRoute(RouterId, Prefix),
+    /// The link between `#0` and `#1` goes down.
+    LinkDown(RouterId, RouterId),
+    /// The link between `#0` and `#1` comes back up.
+    LinkUp(RouterId, RouterId),
+    /// The router fails, together with all of its BGP sessions.
+    RouterDown(RouterId),
+    /// The router comes back up.
+    RouterUp(RouterId),
 }
 
 impl Event {
@@ -44,6 +52,30 @@ impl Event {
             Event::Config(_) => None,
             Event::AdvertiseExternalRoute(_, route) => Some(route.prefix),
             Event::WithdrawExternalRoute(_, prefix) => Some(*prefix),
+            Event::LinkDown(_, _)
+            | Event::LinkUp(_, _)
+            | Event::RouterDown(_)
+            | Event::RouterUp(_) => None,
+        }
+    }
+
+    /// Returns true if the event is a link or router failure, or its recovery.
+    pub fn is_failure_event(&self) -> bool {
+        matches!(
+            self,
+            Event::LinkDown(_, _) | Event::LinkUp(_, _) | Event::RouterDown(_) | Event::RouterUp(_)
+        )
+    }
+
+    /// Returns the event that reverts the effect of a failure event, or `None` if the event is no
+    /// failure event.
+    pub fn reverse_failure(&self) -> Option<Event> {
+        match self {
+            Event::LinkDown(a, b) => Some(Event::LinkUp(*a, *b)),
+            Event::LinkUp(a, b) => Some(Event::LinkDown(*a, *b)),
+            Event::RouterDown(r) => Some(Event::RouterUp(*r)),
+            Event::RouterUp(r) => Some(Event::RouterDown(*r)),
+            _ => None,
         }
     }
 
@@ -22,6 +22,7 @@
 
 use crate::netsim::bgp::{BgpEvent, BgpRoute};
 use crate::netsim::event::{Event, EventQueue};
+use crate::netsim::snapshot::{SharedVec, SnapshotClone};
 use crate::netsim::{AsId, DeviceError, Prefix, RouterId};
 use std::collections::HashSet;
 
@@ -44,7 +45,7 @@ pub struct ExternalRouter {
     acl_accept: Vec<RouterId>,
     acl_deny: Vec<RouterId>,
     active_routes: Vec<BgpRoute>,
-    undo_stack: Vec<UndoAction>,
+    undo_stack: SharedVec<UndoAction>,
 }
 
 impl Clone for ExternalRouter {
@@ -57,11 +58,17 @@ impl Clone for ExternalRouter {
             acl_accept: self.acl_accept.clone(),
             acl_deny: self.acl_deny.clone(),
             active_routes: self.active_routes.clone(),
-            undo_stack: Vec::new(),
+            undo_stack: SharedVec::new(),
         }
     }
 }
 
+impl SnapshotClone for ExternalRouter {
+    fn snapshot_clone(&self) -> Self {
+        Self { undo_stack: self.undo_stack.clone(), ..self.clone() }
+    }
+}
+
 impl ExternalRouter {
     /// Create a new NetworkDevice instance
     pub(crate) fn new(name: String, router_id: RouterId, as_id: AsId) -> Self {
@@ -73,7 +80,7 @@ impl ExternalRouter {
             acl_accept: Vec::new(),
             acl_deny: Vec::new(),
             active_routes: Vec::new(),
-            undo_stack: Vec::new(),
+            undo_stack: SharedVec::new(),
         }
     }
 
@@ -158,25 +165,18 @@ impl ExternalRouter {
         self.active_routes.iter().map(|r| r.prefix).collect()
     }
 
-    /// Start advertizing a specific route. All neighbors (including future neighbors) will get an
-    /// update message with the route.
-    pub(crate) fn advertise_prefix(
+    /// Start advertizing a route with all its attributes. The next hop of the route is set to this
+    /// router, and the local preference is removed. All neighbors (including future neighbors) will
+    /// get an update message with the route.
+    pub(crate) fn advertise_route(
         &mut self,
-        prefix: Prefix,
-        as_path: Vec<AsId>,
-        med: Option<u32>,
-        community: Option<u32>,
+        mut route: BgpRoute,
         queue: &mut EventQueue,
         parent_event_id: usize,
     ) -> BgpRoute {
-        let route = BgpRoute {
-            prefix,
-            as_path,
-            next_hop: self.router_id,
-            local_pref: None,
-            med,
-            community,
-        };
+        route.next_hop = self.router_id;
+        route.local_pref = None;
+        let prefix = route.prefix;
 
         let mut new_route: bool = true;
         // check wether there was already a route present with the same prefix
@@ -21,10 +21,12 @@
 
 use crate::netsim::{Network, NetworkDevice, NetworkError, Prefix, RouterId};
 use crate::netsim::config::{Config, ConfigExpr};
-use crate::netsim::types::{Destination, ACL};
+use crate::netsim::acl::{AccessList, AclDirection, TrafficClass};
+use crate::netsim::snapshot::SharedVec;
+use crate::netsim::types::Destination;
 use log::*;
 use std::collections::{HashMap, HashSet};
-use std::iter::{repeat, Peekable, FromIterator};
+use std::iter::{Peekable, FromIterator};
 use std::vec::IntoIter;
 
 use super::router::Router;
@@ -47,12 +49,11 @@ pub struct ForwardingState {
     /// Number of routers, needed to check if the router exists
     num_devices: usize,
     /// Flattened 2-dimensional vector for the routers, the prefixes, and the rest of the routers. 
-    /// The value is None if the router knows no route ot the prefix, and the value is Some(usize)
-    /// with usize being the index
-    /// to the `RouterId`.
-    state: Vec<Option<RouterId>>,
-    /// Storing ACL rules of all routers
-    acl: Vec<Option<(ACL, HashSet<RouterId>)>>,
+    /// The value is the set of all (equal-cost) next hops, which is empty if the router knows no
+    /// route to the destination. The first next hop is the one used by `get_route`.
+    state: SharedVec<Vec<RouterId>>,
+    /// Access lists of all devices, indexed by the neighbor of the interface and the direction
+    acl: Vec<HashMap<(RouterId, AclDirection), AccessList>>,
     /// Lookup for the Prefix
     pub(self) prefixes: HashMap<Prefix, usize>,
     /// Lookup for IGP routers
@@ -64,6 +65,10 @@ pub struct ForwardingState {
     /// we have not yet computed the result there, But if cache[r * num_prefixes + p] is true, then
     /// it will store the result which was computed last time.
     cache: Vec<Option<(CacheResult, Vec<RouterId>)>>,
+    /// Cache storing, for every entry, whether every path in the forwarding DAG starting at the
+    /// router reaches the destination (ignoring access lists). Only `true` results are stored,
+    /// because they do not depend on the path that leads to the router.
+    valid_cache: Vec<bool>,
 }
 
 impl PartialEq for ForwardingState {
@@ -75,7 +80,7 @@ impl PartialEq for ForwardingState {
         for prefix in self.prefixes.keys() {
             for rid in 0..self.num_devices {
                 let router = (rid as u32).into();
-                if self.get_next_hop(router, *prefix) != other.get_next_hop(router, *prefix) {
+                if self.get_next_hops(router, *prefix) != other.get_next_hops(router, *prefix) {
                     return false;
                 }
             }
@@ -105,12 +110,12 @@ impl ForwardingState {
 
         // initialize state
         // need to account for igp as well
-        let mut state: Vec<Option<RouterId>> =
-            repeat(None).take(num_prefixes * num_devices).collect();
+        let mut state: Vec<Vec<RouterId>> = vec![Vec::new(); num_prefixes * num_devices];
         for rid in 0..num_devices as u32 {
             if let NetworkDevice::InternalRouter(r) = net.get_device(rid.into()) {
                 for (p, pid) in prefixes.iter() {
-                    state[get_idx(rid as usize, *pid, num_prefixes)] = r.get_next_hop(Destination::BGP(*p));
+                    state[get_idx(rid as usize, *pid, num_prefixes)] =
+                        r.get_next_hops(Destination::BGP(*p));
                 }
             }
         }
@@ -119,15 +124,28 @@ impl ForwardingState {
         // prefix they know a route to.
         let external_routers: HashSet<RouterId> = net.get_external_routers().into_iter().collect();
         for r in external_routers.iter() {
-            for p in net.get_device(*r).unwrap_external().advertised_prefixes() {
-                state[get_idx(r.index(), *prefixes.get(&p).unwrap(), num_prefixes)] = Some(*r);
+            let advertised = net.get_device(*r).unwrap_external().advertised_prefixes();
+            let covered = prefixes.iter().filter(|(p, _)| advertised.iter().any(|a| a.contains(p)));
+            for (_, pid) in covered {
+                state[get_idx(r.index(), *pid, num_prefixes)] = vec![*r];
             }
         }
 
         // prepare the cache
-        let cache = repeat(None).take(num_prefixes * num_devices).collect();
-        let acl: Vec<Option<(ACL, HashSet<RouterId>)>> = Vec::new();
-        Self { num_prefixes, num_devices, state, acl, prefixes, routers, external_routers, cache }
+        let cache = vec![None; num_prefixes * num_devices];
+        let valid_cache = vec![false; num_prefixes * num_devices];
+        let acl = Vec::new();
+        Self {
+            num_prefixes,
+            num_devices,
+            state: state.into(),
+            acl,
+            prefixes,
+            routers,
+            external_routers,
+            cache,
+            valid_cache,
+        }
     }
 
     /// New function that returns a forwarding state object indexing IGP communication
@@ -149,8 +167,8 @@ impl ForwardingState {
 
         // initialize state
         // need to account for igp as well
-        let mut state: Vec<Option<RouterId>> =
-            repeat(None).take((num_devices + num_prefixes) * num_devices).collect();
+        let mut state: Vec<Vec<RouterId>> =
+            vec![Vec::new(); (num_devices + num_prefixes) * num_devices];
         for rid in 0..num_devices as u32 {
             if let NetworkDevice::InternalRouter(r) = net.get_device(rid.into()) {
                 for (p, pid) in prefixes.iter() {
@@ -160,7 +178,7 @@ impl ForwardingState {
                         &prefixes,
                         &routers
                     );
-                    state[idx] = r.get_next_hop(Destination::BGP(*p));
+                    state[idx] = r.get_next_hops(Destination::BGP(*p));
                 }
                 for r_other in &routers {
                     let idx: usize = get_idx_new(
@@ -171,10 +189,10 @@ impl ForwardingState {
                     );
                     if *r_other != r.router_id() {
                         // when self is not the destination
-                        state[idx] = r.get_next_hop(Destination::IGP(*r_other));
+                        state[idx] = r.get_next_hops(Destination::IGP(*r_other));
                     } else {
                         // when self is the destination
-                        state[idx] = Some(*r_other);
+                        state[idx] = vec![*r_other];
                     }
                 }
             }
@@ -184,32 +202,189 @@ impl ForwardingState {
         // prefix they know a route to.
         let external_routers: HashSet<RouterId> = net.get_external_routers().into_iter().collect();
         for r in external_routers.iter() {
-            for p in net.get_device(*r).unwrap_external().advertised_prefixes() {
+            let advertised = net.get_device(*r).unwrap_external().advertised_prefixes();
+            for p in prefixes.keys().filter(|p| advertised.iter().any(|a| a.contains(p))) {
                 let idx: usize = get_idx_new(
                     r.index(),
-                    &Destination::BGP(p),
+                    &Destination::BGP(*p),
                     &prefixes,
                     &routers
                 );
-                state[idx] = Some(*r);
+                state[idx] = vec![*r];
             }
         }
 
-        // initialize ACL
-        let mut acl: Vec<Option<(ACL, HashSet<RouterId>)>> = 
-            repeat(None).take(num_devices).collect();
-        // assign values to ACL
-        for rid in 0..num_devices as u32 {
-            let router = net.get_device(rid.into());
-            if let NetworkDevice::InternalRouter(r) = router {
-                let (mode, acl_router) = r.get_acl().unwrap();
-                // let idx = routers.iter().position(|rid| *rid == r.router_id()).unwrap();
-                acl[rid as usize] = Some((mode, acl_router));
+        // collect the access lists of all routers
+        let acl = (0..num_devices as u32)
+            .map(|rid| match net.get_device(rid.into()) {
+                NetworkDevice::InternalRouter(r) => r.get_acls().clone(),
+                _ => HashMap::new(),
+            })
+            .collect();
+        // prepare the cache
+        let cache = vec![None; (num_prefixes + num_devices) * num_devices];
+        let valid_cache = vec![false; (num_prefixes + num_devices) * num_devices];
+        Self {
+            num_prefixes,
+            num_devices,
+            state: state.into(),
+            acl,
+            prefixes,
+            routers,
+            external_routers,
+            cache,
+            valid_cache,
+        }
+    }
+
+    /// Update the forwarding state (extracted with [`ForwardingState::from_net_new`]) in place,
+    /// such that it matches the current state of the network, and return the set of entries whose
+    /// next hops have changed. Only the entries that the network has marked as possibly changed
+    /// since the last update (see [`Network::clear_forwarding_changes`]) are recomputed, and only
+    /// cached paths that traverse a changed entry are invalidated.
+    ///
+    /// If the devices or the known prefixes have changed, or if a link or a router has failed,
+    /// the state is extracted again from the network. A change of the IGP only recomputes the
+    /// routers whose IGP table has changed.
+    pub fn update(&mut self, net: &mut Network) -> ForwardingChanges {
+        let candidates = net.take_forwarding_candidates();
+        self.update_with(net, &candidates)
+    }
+
+    /// Update the forwarding state like [`ForwardingState::update`], but only recompute the given
+    /// `candidates`, without forgetting the changes tracked by the network.
+    pub(crate) fn update_with(
+        &mut self,
+        net: &Network,
+        candidates: &ForwardingCandidates,
+    ) -> ForwardingChanges {
+        let routers = net.get_routers();
+        let same_layout = self.num_devices == net.num_devices()
+            && self.prefixes.len() == net.get_known_prefixes().len()
+            && net.get_known_prefixes().iter().all(|p| self.prefixes.contains_key(p));
+
+        if !same_layout || candidates.all || self.routers != routers {
+            let new_state = Self::from_net_new(net);
+            let changes = if same_layout && self.acl == new_state.acl {
+                self.diff(&new_state)
+            } else {
+                ForwardingChanges { all: true, entries: HashSet::new() }
+            };
+            *self = new_state;
+            return changes;
+        }
+
+        let mut changes = ForwardingChanges::default();
+        // recompute all entries of the marked routers
+        for router in candidates.routers.iter() {
+            if let NetworkDevice::InternalRouter(r) = net.get_device(*router) {
+                if self.acl[router.index()] != *r.get_acls() {
+                    self.acl[router.index()] = r.get_acls().clone();
+                    changes.all = true;
+                }
+            }
+            let dests = self
+                .routers
+                .iter()
+                .map(|r| Destination::IGP(*r))
+                .chain(self.prefixes.keys().map(|p| Destination::BGP(*p)))
+                .collect::<Vec<_>>();
+            for dest in dests {
+                self.update_entry(net, *router, dest, &mut changes);
             }
         }
-        // prepare the cache
-        let cache = repeat(None).take((num_prefixes + num_devices) * num_devices).collect();
-        Self { num_prefixes, num_devices, state, acl, prefixes, routers, external_routers, cache }
+        // recompute all marked entries, together with the entries of all prefixes that are more or
+        // less specific (which might be forwarded using the same route).
+        for (router, prefix) in candidates.entries.iter() {
+            if candidates.routers.contains(router) {
+                continue;
+            }
+            let related = self
+                .prefixes
+                .keys()
+                .filter(|p| p.contains(prefix) || prefix.contains(p))
+                .copied()
+                .collect::<Vec<_>>();
+            for p in related {
+                self.update_entry(net, *router, Destination::BGP(p), &mut changes);
+            }
+        }
+        changes
+    }
+
+    /// Clone the forwarding state without the cached paths. The next hops are shared with `self`,
+    /// and they are only copied in parts when either of both states is updated. This is much
+    /// cheaper than cloning the entire forwarding state.
+    pub(crate) fn clone_state(&self) -> Self {
+        let size = self.cache.len();
+        Self {
+            num_prefixes: self.num_prefixes,
+            num_devices: self.num_devices,
+            state: self.state.clone(),
+            acl: self.acl.clone(),
+            prefixes: self.prefixes.clone(),
+            routers: self.routers.clone(),
+            external_routers: self.external_routers.clone(),
+            cache: vec![None; size],
+            valid_cache: vec![false; size],
+        }
+    }
+
+    /// Recompute a single entry of the forwarding state. If it has changed, add it to `changes` and
+    /// invalidate all cached paths that traverse the router.
+    fn update_entry(
+        &mut self,
+        net: &Network,
+        router: RouterId,
+        dest: Destination,
+        changes: &mut ForwardingChanges,
+    ) {
+        let next_hops = match (net.get_device(router), &dest) {
+            (NetworkDevice::InternalRouter(r), Destination::IGP(target)) if *target == router => {
+                vec![r.router_id()]
+            }
+            (NetworkDevice::InternalRouter(r), dest) => r.get_next_hops(dest.clone()),
+            (NetworkDevice::ExternalRouter(r), Destination::BGP(p))
+                if r.advertised_prefixes().iter().any(|a| a.contains(p)) =>
+            {
+                vec![router]
+            }
+            _ => Vec::new(),
+        };
+        let idx = get_idx_new(router.index(), &dest, &self.prefixes, &self.routers);
+        if self.state[idx] == next_hops {
+            return;
+        }
+        self.state[idx] = next_hops;
+        for rid in 0..self.num_devices {
+            let idx = get_idx_new(rid, &dest, &self.prefixes, &self.routers);
+            if matches!(&self.cache[idx], Some((_, path)) if path.contains(&router)) {
+                self.cache[idx] = None;
+            }
+            self.valid_cache[idx] = false;
+        }
+        changes.entries.insert((router, dest));
+    }
+
+    /// Returns all entries which differ between `self` and `other`. Both states must contain the
+    /// same prefixes and devices.
+    fn diff(&self, other: &Self) -> ForwardingChanges {
+        let mut changes = ForwardingChanges::default();
+        let dests = self
+            .routers
+            .iter()
+            .map(|r| Destination::IGP(*r))
+            .chain(self.prefixes.keys().map(|p| Destination::BGP(*p)));
+        for dest in dests {
+            for rid in 0..self.num_devices {
+                let a = get_idx_new(rid, &dest, &self.prefixes, &self.routers);
+                let b = get_idx_new(rid, &dest, &other.prefixes, &other.routers);
+                if self.state[a] != other.state[b] {
+                    changes.entries.insert(((rid as u32).into(), dest.clone()));
+                }
+            }
+        }
+        changes
     }
 
     /// Returns the route from the source router to a specific prefix. This function uses the cached
@@ -227,9 +402,9 @@ impl ForwardingState {
         // what does the pid refer to here?
         // perhaps the id of the prefix?
         let pid = self
-            .prefixes
-            .get(&prefix)
+            .get_prefix_idx(prefix)
             .ok_or_else(|| NetworkError::ForwardingBlackHole(vec![source]))?;
+        let pid = &pid;
         let mut visited_routers: HashSet<RouterId> = HashSet::new();
         let mut path: Vec<RouterId> = Vec::new();
         let mut current_node = source;
@@ -256,7 +431,9 @@ impl ForwardingState {
             let is_external = self.external_routers.contains(&current_node);
 
             // get the next node and handle the errors
-            current_node = match self.state.get(current_idx).unwrap() {
+            current_node = match self.state.get(current_idx).unwrap().first() {
+                // the router is the destination itself
+                Some(nh) if *nh == current_node => break (CacheResult::ValidPath, path.len()),
                 Some(nh) => *nh,
                 None => {
                     break (CacheResult::BlackHole, path.len());
@@ -308,57 +485,56 @@ impl ForwardingState {
                 trace!("Forwarding loop detected: {:?}", path);
                 Err(NetworkError::ForwardingLoop(path))
             }
-            CacheResult::AccessDenied => {
-                trace!("Access denied");
-                Err(NetworkError::AccessDenied(current_node))
-            }
         }
     }
 
-    /// New get_route function that supports IGP
+    /// New get_route function that supports IGP. The access lists along the path are checked for
+    /// any traffic towards the destination (see [`ForwardingState::get_route_of_class`]).
     pub fn get_route_new(
         &mut self,
         src: RouterId,
         dest: Destination,
+    ) -> Result<Vec<RouterId>, NetworkError> {
+        self.get_route_of_class(src, dest, &TrafficClass::default())
+    }
+
+    /// Returns the route of the traffic class from `src` towards `dest`. The forwarding path is
+    /// cached independently of the traffic class, while the access lists along the path are
+    /// checked for every call. If some packet of the class is dropped by an access list,
+    /// `AccessDenied` is returned, containing the router that drops it.
+    pub fn get_route_of_class(
+        &mut self,
+        src: RouterId,
+        dest: Destination,
+        class: &TrafficClass,
     ) -> Result<Vec<RouterId>, NetworkError> {
         if src.index() >= self.num_devices {
             return Err(NetworkError::DeviceNotFound(src));
         }
         let mut current_node = src;
-        let mut current_idx: usize;
         let mut visited_routers: HashSet<RouterId> = HashSet::new();
         let mut path: Vec<RouterId> = Vec::new();
-                
-        match dest {
-            Destination::IGP(r) => {
-                if !self.routers.contains(&r) {
-                    return Err(NetworkError::DeviceNotFound(r));
-                } else {
-                    // everything is fine
-                }
-            }
-            Destination::BGP(p) => {
-                if let None = self.prefixes.get(&p){
-                    return Err(NetworkError::ForwardingBlackHole(vec![src]));
-                } else {
-                    // everything is fine
-                }
+
+        if let Destination::IGP(r) = dest {
+            if !self.routers.contains(&r) {
+                return Err(NetworkError::DeviceNotFound(r));
             }
         }
+        let class = packet_class(class, &dest);
+        // use the most specific known prefix that contains the destination
+        let dest = match dest {
+            Destination::BGP(p) => match self.get_longest_known_prefix(p) {
+                Some(p) => Destination::BGP(p),
+                None => return Err(NetworkError::ForwardingBlackHole(vec![src])),
+            },
+            dest => dest,
+        };
         let (result, mut update_cache_upto) = loop {
-            current_idx = get_idx_new(current_node.index(), &dest, &self.prefixes, &self.routers);
-            
             // check if the route already exists in cache
-            if let Some((mut r, c)) = self.get_cache(current_node, &dest) {
-            // test if access is accepted along the path
-                for router in c {
-                    path.push(router);
-                    if !self.check_access(src, router) {
-                        r = CacheResult::AccessDenied;
-                        break;
-                    } 
-                }
-                break (r, path.len());
+            if let Some((result, cache_path)) = self.get_cache(current_node, &dest) {
+                let cache_upto = path.len();
+                path.extend(cache_path);
+                break (result, cache_upto);
             }
 
             path.push(current_node);
@@ -368,68 +544,51 @@ impl ForwardingState {
                 break (CacheResult::ForwardingLoop, path.len());
             }
 
-            // println!("At router {:?}", current_node);
-            match &self.state[current_idx] {
-                Some(r) => {
-                    // check if access is denied here
-                    if self.check_access(src, current_node) {
-                        if *r == current_node {
-                            // has arrived at the destination
-                            break (CacheResult::ValidPath, path.len());
-                        } else {
-                            current_node = *r;
-                        }
-                    } else {
-                        break (CacheResult::AccessDenied, path.len());
-                    }    
-                    // not intercepted by acl rules
-                }
+            let current_idx =
+                get_idx_new(current_node.index(), &dest, &self.prefixes, &self.routers);
+            match self.state[current_idx].first() {
+                // has arrived at the destination
+                Some(r) if *r == current_node => break (CacheResult::ValidPath, path.len()),
+                Some(r) => current_node = *r,
                 None => break (CacheResult::BlackHole, path.len()),
-            }   
+            }
         };
-        println!("{:?}", path);
-        match result {
-            CacheResult::AccessDenied => {
-                // only update the src
-                self.cache[get_idx_new(src.index(), &dest, &self.prefixes, &self.routers)] =
-                    Some((result, path.clone()));
-            }
-            _ => {
-                // records the looped part for each node along the loop
-                if result == CacheResult::ForwardingLoop && update_cache_upto == path.len() {
-                    // find the first position of the last element, which must occur twice
-                    let loop_rid = path.last().unwrap();
-                    let loop_pos = path.iter().position(|x| x == loop_rid).unwrap();
-                    let mut tmp_loop_path = path.iter().skip(loop_pos).cloned().collect::<Vec<_>>();
-                    for (update_id, router) in
-                        path.iter().enumerate().take(update_cache_upto - 1).skip(loop_pos)
-                    {
-                        self.cache[get_idx_new(router.index(), &dest, &self.prefixes, &self.routers)] =
-                            Some((result, tmp_loop_path.clone()));
-                        if update_id < update_cache_upto - 1 {
-                            tmp_loop_path.remove(0);
-                            tmp_loop_path.push(tmp_loop_path[0]);
-                        }
-                    }
-                    update_cache_upto = loop_pos;
-                }
-        
-                // insert the newest path into cache
-                for idx in 0..update_cache_upto {
-                    self.cache[get_idx_new(path[idx].index(), &dest, &self.prefixes, &self.routers)] = 
-                        Some((result, path.iter().skip(idx).cloned().collect()));
+
+        // records the looped part for each node along the loop
+        if result == CacheResult::ForwardingLoop && update_cache_upto == path.len() {
+            // find the first position of the last element, which must occur twice
+            let loop_rid = path.last().unwrap();
+            let loop_pos = path.iter().position(|x| x == loop_rid).unwrap();
+            let mut tmp_loop_path = path.iter().skip(loop_pos).cloned().collect::<Vec<_>>();
+            for (update_id, router) in
+                path.iter().enumerate().take(update_cache_upto - 1).skip(loop_pos)
+            {
+                self.cache[get_idx_new(router.index(), &dest, &self.prefixes, &self.routers)] =
+                    Some((result, tmp_loop_path.clone()));
+                if update_id < update_cache_upto - 1 {
+                    tmp_loop_path.remove(0);
+                    tmp_loop_path.push(tmp_loop_path[0]);
                 }
             }
+            update_cache_upto = loop_pos;
+        }
+
+        // insert the newest path into cache
+        for idx in 0..update_cache_upto {
+            self.cache[get_idx_new(path[idx].index(), &dest, &self.prefixes, &self.routers)] =
+                Some((result, path.iter().skip(idx).cloned().collect()));
+        }
+
+        // check the access lists along the path
+        let denied = path.windows(2).find_map(|w| self.check_access(w[0], w[1], &class, false));
+        if let Some(router) = denied {
+            return Err(NetworkError::AccessDenied(router));
         }
 
         match result {
-            CacheResult::ValidPath => {
-                println!("{:?}", path);
-                Ok(path)
-            },
+            CacheResult::ValidPath => Ok(path),
             CacheResult::BlackHole => Err(NetworkError::ForwardingBlackHole(path)),
             CacheResult::ForwardingLoop => Err(NetworkError::ForwardingLoop(path)),
-            CacheResult::AccessDenied => Err(NetworkError::AccessDenied(*path.last().unwrap())),
         }
     }
 
@@ -443,42 +602,319 @@ impl ForwardingState {
         if router.index() >= self.num_devices {
             return Err(NetworkError::DeviceNotFound(router));
         }
-        let pid = self.prefixes.get(&prefix);
+        let pid = self.get_prefix_idx(prefix);
         if let Some(pid) = pid {
-            let data_idx = get_idx(router.index(), *pid, self.num_prefixes);
-            Ok(*self.state.get(data_idx).unwrap())
+            let data_idx = get_idx(router.index(), pid, self.num_prefixes);
+            Ok(self.state.get(data_idx).unwrap().first().copied())
         } else {
             Ok(None)
         }
     }
 
-    fn get_cache(&self, src: RouterId, dest: &Destination) -> Option<(CacheResult, Vec<RouterId>)>{
-        let idx = get_idx_new(src.index(), &dest, &self.prefixes, &self.routers);
-        self.cache[idx].clone()
+    /// Get all (equal-cost) next hops of a router for a specific prefix. If that router does not
+    /// know any route, an empty slice is returned.
+    pub fn get_next_hops(
+        &self,
+        router: RouterId,
+        prefix: Prefix,
+    ) -> Result<&[RouterId], NetworkError> {
+        if router.index() >= self.num_devices {
+            return Err(NetworkError::DeviceNotFound(router));
+        }
+        match self.get_prefix_idx(prefix) {
+            Some(pid) => Ok(&self.state[get_idx(router.index(), pid, self.num_prefixes)]),
+            None => Ok(&[]),
+        }
     }
 
-    fn check_access(&self, src: RouterId, dest: RouterId) -> bool {
-        if self.acl.len() == 0 {
-            return true;
+    /// Get all (equal-cost) next hops of a router towards a destination, in a forwarding state
+    /// extracted with [`ForwardingState::from_net_new`]. If the router does not know any route, or
+    /// if no known prefix contains the destination, an empty slice is returned.
+    pub fn get_next_hops_new(
+        &self,
+        router: RouterId,
+        dest: Destination,
+    ) -> Result<&[RouterId], NetworkError> {
+        if router.index() >= self.num_devices {
+            return Err(NetworkError::DeviceNotFound(router));
         }
-        let idx = dest.index();
-        if let Some((mode, acl)) = &self.acl[idx] {
-            // println!("Checking ACL on {:?}", current_node);
-            // println!("ACL: {:?}", acl);
-            match mode {
-                ACL::Accept => {
-                    if !acl.contains(&src) {
-                        return false;
-                    }
+        let dest = match dest {
+            Destination::IGP(r) if !self.routers.contains(&r) => {
+                return Err(NetworkError::DeviceNotFound(r))
+            }
+            Destination::BGP(p) => match self.get_longest_known_prefix(p) {
+                Some(p) => Destination::BGP(p),
+                None => return Ok(&[]),
+            },
+            dest => dest,
+        };
+        Ok(&self.state[get_idx_new(router.index(), &dest, &self.prefixes, &self.routers)])
+    }
+
+    /// Returns every path from `src` towards `dest` in the forwarding DAG, following all
+    /// equal-cost next hops of every router. Each path is either `Ok` if it reaches the
+    /// destination, or the error describing why it does not (black hole, forwarding loop or
+    /// denied access). If every router has a single next hop, the result contains exactly one
+    /// route, equal to the one of `get_route_new`. In contrast to `get_route_new`, the result is
+    /// not cached. The access lists are checked for any traffic towards the destination.
+    ///
+    /// **Warning**: The number of paths can grow exponentially with the number of routers that use
+    /// multiple next hops. Use [`ForwardingState::check_all_routes`] if you only need to know
+    /// whether every path reaches the destination, and
+    /// [`PathCondition::check_all_paths`](crate::hard_policies::PathCondition::check_all_paths) to
+    /// check a condition on every path.
+    pub fn get_all_routes(
+        &self,
+        src: RouterId,
+        dest: Destination,
+    ) -> Vec<Result<Vec<RouterId>, NetworkError>> {
+        self.get_all_routes_of_class(src, dest, &TrafficClass::default())
+    }
+
+    /// Returns every path of the traffic class from `src` towards `dest` in the forwarding DAG,
+    /// like [`ForwardingState::get_all_routes`]. A path is `AccessDenied` if the traffic class is
+    /// dropped by an access list along the path.
+    pub fn get_all_routes_of_class(
+        &self,
+        src: RouterId,
+        dest: Destination,
+        class: &TrafficClass,
+    ) -> Vec<Result<Vec<RouterId>, NetworkError>> {
+        // use the most specific known prefix that contains the destination
+        let dest = match self.resolve_dest(src, dest) {
+            Ok(dest) => dest,
+            Err(e) => return vec![Err(e)],
+        };
+        let class = packet_class(class, &dest);
+        let mut routes = Vec::new();
+        self.collect_routes(&class, &dest, &mut vec![src], &mut routes);
+        routes
+    }
+
+    /// Recursively extend `path` (ending at the current router) along every next hop towards
+    /// `dest`, and push the resulting routes into `routes`.
+    fn collect_routes(
+        &self,
+        class: &TrafficClass,
+        dest: &Destination,
+        path: &mut Vec<RouterId>,
+        routes: &mut Vec<Result<Vec<RouterId>, NetworkError>>,
+    ) {
+        let current_node = *path.last().unwrap();
+        if path[..path.len() - 1].contains(&current_node) {
+            routes.push(Err(NetworkError::ForwardingLoop(path.clone())));
+            return;
+        }
+        let current_idx = get_idx_new(current_node.index(), dest, &self.prefixes, &self.routers);
+        let next_hops = &self.state[current_idx];
+        if next_hops.is_empty() {
+            routes.push(Err(NetworkError::ForwardingBlackHole(path.clone())));
+        } else if next_hops[0] == current_node {
+            // has arrived at the destination
+            routes.push(Ok(path.clone()));
+        } else {
+            for next_hop in next_hops {
+                if let Some(router) = self.check_access(current_node, *next_hop, class, false) {
+                    routes.push(Err(NetworkError::AccessDenied(router)));
+                    continue;
                 }
-                ACL::Deny => {
-                    if acl.contains(&src) {
-                        return false;
-                    }
+                path.push(*next_hop);
+                self.collect_routes(class, dest, path, routes);
+                path.pop();
+            }
+        }
+    }
+
+    /// Check that every path from `src` towards `dest` in the forwarding DAG reaches the
+    /// destination, and return the first path that does not otherwise (black hole, forwarding loop
+    /// or denied access), like it would be returned by [`ForwardingState::get_all_routes`]. In
+    /// contrast to `get_all_routes`, the paths are not enumerated. Instead, every router is visited
+    /// at most once, and the routers from which every path is valid are cached. The access lists
+    /// are checked for any traffic towards the destination.
+    pub fn check_all_routes(
+        &mut self,
+        src: RouterId,
+        dest: Destination,
+    ) -> Result<(), NetworkError> {
+        self.check_all_routes_of_class(src, dest, &TrafficClass::default())
+    }
+
+    /// Check that every path of the traffic class from `src` towards `dest` in the forwarding DAG
+    /// reaches the destination, like [`ForwardingState::check_all_routes`]. A path is
+    /// `AccessDenied` if some packet of the class is dropped by an access list along the path.
+    pub fn check_all_routes_of_class(
+        &mut self,
+        src: RouterId,
+        dest: Destination,
+        class: &TrafficClass,
+    ) -> Result<(), NetworkError> {
+        let dest = self.resolve_dest(src, dest)?;
+        let class = packet_class(class, &dest);
+        // without any access list, the result does not depend on the traffic class.
+        let use_cache = self.acl.iter().all(HashMap::is_empty);
+        let mut done = HashSet::new();
+        self.check_routes_from(&class, &dest, &mut vec![src], &mut done, use_cache)
+    }
+
+    /// Depth-first search through the forwarding DAG, starting at the last router of `path`.
+    /// Routers in `done` were already visited, and every path starting at them is valid.
+    fn check_routes_from(
+        &mut self,
+        class: &TrafficClass,
+        dest: &Destination,
+        path: &mut Vec<RouterId>,
+        done: &mut HashSet<RouterId>,
+        use_cache: bool,
+    ) -> Result<(), NetworkError> {
+        let current_node = *path.last().unwrap();
+        if path[..path.len() - 1].contains(&current_node) {
+            return Err(NetworkError::ForwardingLoop(path.clone()));
+        }
+        let current_idx = get_idx_new(current_node.index(), dest, &self.prefixes, &self.routers);
+        if done.contains(&current_node) || (use_cache && self.valid_cache[current_idx]) {
+            return Ok(());
+        }
+        let next_hops = self.state[current_idx].clone();
+        if next_hops.is_empty() {
+            return Err(NetworkError::ForwardingBlackHole(path.clone()));
+        }
+        if next_hops[0] != current_node {
+            for next_hop in next_hops {
+                if let Some(router) = self.check_access(current_node, next_hop, class, false) {
+                    return Err(NetworkError::AccessDenied(router));
                 }
+                path.push(next_hop);
+                self.check_routes_from(class, dest, path, done, use_cache)?;
+                path.pop();
             }
         }
-        true
+        // every path is valid, which also holds if the access lists are ignored.
+        done.insert(current_node);
+        self.valid_cache[current_idx] = true;
+        Ok(())
+    }
+
+    /// Returns any path of the traffic class from `src` towards `dest` in the forwarding DAG that
+    /// reaches the destination with some of its packets, or `None` if every path is either a black
+    /// hole, a forwarding loop, or drops every packet of the class by an access list. Every router
+    /// is visited at most once.
+    pub fn find_valid_route_of_class(
+        &self,
+        src: RouterId,
+        dest: Destination,
+        class: &TrafficClass,
+    ) -> Option<Vec<RouterId>> {
+        let dest = self.resolve_dest(src, dest).ok()?;
+        let class = packet_class(class, &dest);
+        let mut path = vec![src];
+        let mut failed = HashSet::new();
+        if self.find_valid_route_from(&class, &dest, &mut path, &mut failed) {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Depth-first search through the forwarding DAG, starting at the last router of `path`, and
+    /// extending `path` until it reaches the destination. Routers in `failed` were already
+    /// visited, and no valid path starts at them. This also holds for routers whose search was cut
+    /// by a router on the current path, since that router is still being searched.
+    fn find_valid_route_from(
+        &self,
+        class: &TrafficClass,
+        dest: &Destination,
+        path: &mut Vec<RouterId>,
+        failed: &mut HashSet<RouterId>,
+    ) -> bool {
+        let current_node = *path.last().unwrap();
+        if path[..path.len() - 1].contains(&current_node) || failed.contains(&current_node) {
+            return false;
+        }
+        let current_idx = get_idx_new(current_node.index(), dest, &self.prefixes, &self.routers);
+        let next_hops = &self.state[current_idx];
+        if next_hops.first() == Some(&current_node) {
+            // has arrived at the destination
+            return true;
+        }
+        for next_hop in next_hops {
+            if self.check_access(current_node, *next_hop, class, true).is_some() {
+                continue;
+            }
+            path.push(*next_hop);
+            if self.find_valid_route_from(class, dest, path, failed) {
+                return true;
+            }
+            path.pop();
+        }
+        failed.insert(current_node);
+        false
+    }
+
+    /// Check that `src` and the destination exist, and replace a BGP destination by the most
+    /// specific known prefix that contains it.
+    fn resolve_dest(&self, src: RouterId, dest: Destination) -> Result<Destination, NetworkError> {
+        if src.index() >= self.num_devices {
+            return Err(NetworkError::DeviceNotFound(src));
+        }
+        match dest {
+            Destination::IGP(r) if !self.routers.contains(&r) => {
+                Err(NetworkError::DeviceNotFound(r))
+            }
+            Destination::BGP(p) => match self.get_longest_known_prefix(p) {
+                Some(p) => Ok(Destination::BGP(p)),
+                None => Err(NetworkError::ForwardingBlackHole(vec![src])),
+            },
+            dest => Ok(dest),
+        }
+    }
+
+    /// Returns the most specific known prefix that contains `prefix` (longest prefix match), or
+    /// `None` if no known prefix contains it.
+    pub fn get_longest_known_prefix(&self, prefix: Prefix) -> Option<Prefix> {
+        if self.prefixes.contains_key(&prefix) {
+            return Some(prefix);
+        }
+        self.prefixes.keys().filter(|p| p.contains(&prefix)).max_by_key(|p| p.prefix_len()).copied()
+    }
+
+    /// Returns the index of the most specific known prefix that contains `prefix`.
+    fn get_prefix_idx(&self, prefix: Prefix) -> Option<usize> {
+        self.get_longest_known_prefix(prefix).and_then(|p| self.prefixes.get(&p)).copied()
+    }
+
+    fn get_cache(&self, src: RouterId, dest: &Destination) -> Option<(CacheResult, Vec<RouterId>)>{
+        let idx = get_idx_new(src.index(), &dest, &self.prefixes, &self.routers);
+        self.cache[idx].clone()
+    }
+
+    /// Check the access lists on the link from `from` to `to` for the traffic class, first the
+    /// outgoing access list of `from`, and then the incoming one of `to`. Returns the router that
+    /// drops the traffic, or `None` if it is permitted. If `any_packet` is set, the traffic is only
+    /// dropped if every packet of the class is denied. Otherwise, it is dropped as soon as some
+    /// packet of the class may be denied.
+    fn check_access(
+        &self,
+        from: RouterId,
+        to: RouterId,
+        class: &TrafficClass,
+        any_packet: bool,
+    ) -> Option<RouterId> {
+        let permits = |router: RouterId, neighbor: RouterId, direction: AclDirection| {
+            self.acl
+                .get(router.index())
+                .and_then(|acls| acls.get(&(neighbor, direction)))
+                .map(|acl| if any_packet { acl.permits_any(class) } else { acl.permits(class) })
+                .unwrap_or(true)
+        };
+        if from == to {
+            None
+        } else if !permits(from, to, AclDirection::Outgoing) {
+            Some(from)
+        } else if !permits(to, from, AclDirection::Incoming) {
+            Some(to)
+        } else {
+            None
+        }
     }
 }
 
@@ -487,7 +923,15 @@ enum CacheResult {
     ValidPath,
     BlackHole,
     ForwardingLoop,
-    AccessDenied
+}
+
+/// Returns the traffic class, where the destination is set to the prefix of `dest` if it is not
+/// set yet.
+fn packet_class(class: &TrafficClass, dest: &Destination) -> TrafficClass {
+    match (class.dst, dest) {
+        (None, Destination::BGP(p)) => TrafficClass { dst: Some(*p), ..*class },
+        _ => *class,
+    }
 }
 
 fn get_idx(rid: usize, pid: usize, num_prefixes: usize) -> usize {
@@ -513,6 +957,100 @@ fn get_idx_new(rid: usize, dest: &Destination, prefixes: &HashMap<Prefix, usize>
     }
 }
 
+/// # Forwarding Changes
+///
+/// Set of entries of the forwarding state whose next hops have changed, returned by
+/// [`ForwardingState::update`]. Each entry is given by the router and the destination.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardingChanges {
+    /// Every entry must be considered as changed
+    all: bool,
+    /// Changed entries
+    entries: HashSet<(RouterId, Destination)>,
+}
+
+impl ForwardingChanges {
+    /// Returns `true` if every entry must be considered as changed. This is the case if the devices
+    /// or the prefixes of the forwarding state, or any access control list have changed.
+    pub fn is_all(&self) -> bool {
+        self.all
+    }
+
+    /// Returns `true` if nothing has changed.
+    pub fn is_empty(&self) -> bool {
+        !self.all && self.entries.is_empty()
+    }
+
+    /// Iterate over all changed entries. If [`ForwardingChanges::is_all`] is `true`, this does not
+    /// include every entry.
+    pub fn iter(&self) -> impl Iterator<Item = &(RouterId, Destination)> {
+        self.entries.iter()
+    }
+
+    /// Returns `true` if the next hops of `router` towards `dest` have changed.
+    pub fn contains(&self, router: RouterId, dest: &Destination) -> bool {
+        self.all || self.entries.contains(&(router, dest.clone()))
+    }
+
+    /// Returns `true` if the forwarding towards `dest` might have changed on any router. For a
+    /// BGP destination, this is the case if the entry of any prefix containing it has changed.
+    pub fn affects(&self, dest: &Destination) -> bool {
+        self.all
+            || self.entries.iter().any(|(_, d)| match (d, dest) {
+                (Destination::BGP(changed), Destination::BGP(p)) => changed.contains(p),
+                (d, dest) => d == dest,
+            })
+    }
+}
+
+/// Entries of the forwarding state which might have changed since the last call to
+/// [`ForwardingState::update`]. They are collected by the network while processing events and
+/// modifiers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ForwardingCandidates {
+    /// Every entry might have changed
+    all: bool,
+    /// Routers where every entry might have changed
+    routers: HashSet<RouterId>,
+    /// Single entries that might have changed
+    entries: HashSet<(RouterId, Prefix)>,
+}
+
+impl ForwardingCandidates {
+    /// Add all entries marked in `other`.
+    pub(crate) fn extend(&mut self, other: ForwardingCandidates) {
+        if other.all {
+            self.mark_all();
+        } else if !self.all {
+            self.routers.extend(other.routers);
+            self.entries.extend(other.entries);
+        }
+    }
+
+    /// Mark every entry of the forwarding state
+    pub(crate) fn mark_all(&mut self) {
+        if !self.all {
+            self.all = true;
+            self.routers.clear();
+            self.entries.clear();
+        }
+    }
+
+    /// Mark every entry of a router
+    pub(crate) fn mark_router(&mut self, router: RouterId) {
+        if !self.all {
+            self.routers.insert(router);
+        }
+    }
+
+    /// Mark the entry of a router for a prefix
+    pub(crate) fn mark(&mut self, router: RouterId, prefix: Prefix) {
+        if !self.all {
+            self.entries.insert((router, prefix));
+        }
+    }
+}
+
 impl IntoIterator for ForwardingState {
     type Item = (RouterId, Prefix, Vec<RouterId>);
     type IntoIter = ForwardingStateIterator;
@@ -586,23 +1124,25 @@ mod test {
             target: r0,
             weight: 1.0,
         }).unwrap();
+        // r1 drops all traffic received from r0
         config.add(ConfigExpr::AccessControl {
-            router: r2,
-            accept: vec![],
-            deny: vec![r0],
+            router: r1,
+            interface: r0,
+            direction: AclDirection::Incoming,
+            acl: AccessList::default(),
         }).unwrap();
 
         net.set_config(&config).unwrap();
         let mut fw = net.get_forwarding_state_new();
 
-        // let route1 = fw.get_route_new(r1, Destination::IGP(r2));
-        let route2 = fw.get_route_new(r0, Destination::IGP(r2));
-        let (r, p) = fw.get_cache(r0, &Destination::IGP(r2)).unwrap();
-        let cache = fw.get_cache(r1, &Destination::IGP(r2));
-        // assert_eq!(route1, Ok(vec![r1, r2]));
-        assert_eq!(route2, Err(NetworkError::AccessDenied(r2)));
-        assert_eq!(cache.is_none(), true);
+        let route0 = fw.get_route_new(r0, Destination::IGP(r2));
+        let route1 = fw.get_route_new(r1, Destination::IGP(r2));
+        assert_eq!(route0, Err(NetworkError::AccessDenied(r1)));
+        assert_eq!(route1, Ok(vec![r1, r2]));
+        // the forwarding path is cached independently of the access lists
+        assert_eq!(fw.get_cache(r0, &Destination::IGP(r2)), Some((ValidPath, vec![r0, r1, r2])));
     }
+
     #[test]
     fn test_route() {
         // let r0 = 0.into();
@@ -618,18 +1158,18 @@ mod test {
         //     num_devices: 6,
         //     state: vec![Some(r0), Some(r0), Some(r1), Some(r1), Some(r2), None],
         //     acl: acl,
-        //     prefixes: maplit::hashmap![Prefix(0) => 0, ],
+        //     prefixes: maplit::hashmap![Prefix::from(0) => 0, ],
         //     routers,
         //     external_routers: maplit::hashset![r0, r5],
         //     cache: vec![None],
         // };
-        // assert_eq!(state.get_route_new(r0, Destination::BGP(Prefix(0))), Ok(vec![r0]));
-        // assert_eq!(state.get_route_new(r1, Destination::BGP(Prefix(0))), Ok(vec![r1, r0]));
-        // assert_eq!(state.get_route(r2, Prefix(0)), Ok(vec![r2, r1, r0]));
-        // assert_eq!(state.get_route(r3, Prefix(0)), Ok(vec![r3, r1, r0]));
-        // assert_eq!(state.get_route(r4, Prefix(0)), Ok(vec![r4, r2, r1, r0]));
+        // assert_eq!(state.get_route_new(r0, Destination::BGP(Prefix::from(0))), Ok(vec![r0]));
+        // assert_eq!(state.get_route_new(r1, Destination::BGP(Prefix::from(0))), Ok(vec![r1, r0]));
+        // assert_eq!(state.get_route(r2, Prefix::from(0)), Ok(vec![r2, r1, r0]));
+        // assert_eq!(state.get_route(r3, Prefix::from(0)), Ok(vec![r3, r1, r0]));
+        // assert_eq!(state.get_route(r4, Prefix::from(0)), Ok(vec![r4, r2, r1, r0]));
         // assert_eq!(
-        //     state.get_route(r5, Prefix(0)),
+        //     state.get_route(r5, Prefix::from(0)),
         //     Err(NetworkError::ForwardingBlackHole(vec![r5]))
         // );
     }
@@ -645,11 +1185,11 @@ mod test {
         //     num_prefixes: 1,
         //     num_devices: 6,
         //     state: vec![Some(r0), Some(r0), Some(r1), Some(r1), Some(r2), None],
-        //     prefixes: maplit::hashmap![Prefix(0) => 0, ],
+        //     prefixes: maplit::hashmap![Prefix::from(0) => 0, ],
         //     external_routers: maplit::hashset![r0, r5],
         //     cache: vec![None, None, None, None, None, None],
         // };
-        // assert_eq!(state.get_route(r4, Prefix(0)), Ok(vec![r4, r2, r1, r0]));
+        // assert_eq!(state.get_route(r4, Prefix::from(0)), Ok(vec![r4, r2, r1, r0]));
         // assert_eq!(state.cache[5], None);
         // assert_eq!(state.cache[4], Some((ValidPath, vec![r4, r2, r1, r0])));
         // assert_eq!(state.cache[3], None);
@@ -670,12 +1210,12 @@ mod test {
         //     num_prefixes: 1,
         //     num_devices: 6,
         //     state: vec![Some(r0), Some(r0), Some(r3), Some(r4), Some(r3), None],
-        //     prefixes: maplit::hashmap![Prefix(0) => 0, ],
+        //     prefixes: maplit::hashmap![Prefix::from(0) => 0, ],
         //     external_routers: maplit::hashset![r0, r5],
         //     cache: vec![None, None, None, None, None, None],
         // };
         // assert_eq!(
-        //     state.get_route(r2, Prefix(0)),
+        //     state.get_route(r2, Prefix::from(0)),
         //     Err(NetworkError::ForwardingLoop(vec![r2, r3, r4, r3]))
         // );
         // assert_eq!(state.cache[0], None);
@@ -685,7 +1225,7 @@ mod test {
         // assert_eq!(state.cache[4], Some((ForwardingLoop, vec![r4, r3, r4])));
         // assert_eq!(state.cache[5], None);
         // assert_eq!(
-        //     state.get_route(r3, Prefix(0)),
+        //     state.get_route(r3, Prefix::from(0)),
         //     Err(NetworkError::ForwardingLoop(vec![r3, r4, r3]))
         // );
         // assert_eq!(state.cache[0], None);
@@ -695,7 +1235,7 @@ mod test {
         // assert_eq!(state.cache[4], Some((ForwardingLoop, vec![r4, r3, r4])));
         // assert_eq!(state.cache[5], None);
         // assert_eq!(
-        //     state.get_route(r4, Prefix(0)),
+        //     state.get_route(r4, Prefix::from(0)),
         //     Err(NetworkError::ForwardingLoop(vec![r4, r3, r4]))
         // );
         // assert_eq!(state.cache[0], None);
@@ -718,12 +1258,12 @@ mod test {
         //     num_prefixes: 1,
         //     num_devices: 6,
         //     state: vec![Some(r0), Some(r2), Some(r3), Some(r4), Some(r2), None],
-        //     prefixes: maplit::hashmap![Prefix(0) => 0, ],
+        //     prefixes: maplit::hashmap![Prefix::from(0) => 0, ],
         //     external_routers: maplit::hashset![r0, r5],
         //     cache: vec![None, None, None, None, None, None],
         // };
         // assert_eq!(
-        //     state.get_route(r1, Prefix(0)),
+        //     state.get_route(r1, Prefix::from(0)),
         //     Err(NetworkError::ForwardingLoop(vec![r1, r2, r3, r4, r2]))
         // );
         // assert_eq!(state.cache[0], None);
@@ -733,7 +1273,7 @@ mod test {
         // assert_eq!(state.cache[4], Some((ForwardingLoop, vec![r4, r2, r3, r4])));
         // assert_eq!(state.cache[5], None);
         // assert_eq!(
-        //     state.get_route(r2, Prefix(0)),
+        //     state.get_route(r2, Prefix::from(0)),
         //     Err(NetworkError::ForwardingLoop(vec![r2, r3, r4, r2]))
         // );
         // assert_eq!(state.cache[0], None);
@@ -743,7 +1283,7 @@ mod test {
         // assert_eq!(state.cache[4], Some((ForwardingLoop, vec![r4, r2, r3, r4])));
         // assert_eq!(state.cache[5], None);
         // assert_eq!(
-        //     state.get_route(r3, Prefix(0)),
+        //     state.get_route(r3, Prefix::from(0)),
         //     Err(NetworkError::ForwardingLoop(vec![r3, r4, r2, r3]))
         // );
         // assert_eq!(state.cache[0], None);
@@ -753,7 +1293,7 @@ mod test {
         // assert_eq!(state.cache[4], Some((ForwardingLoop, vec![r4, r2, r3, r4])));
         // assert_eq!(state.cache[5], None);
         // assert_eq!(
-        //     state.get_route(r4, Prefix(0)),
+        //     state.get_route(r4, Prefix::from(0)),
         //     Err(NetworkError::ForwardingLoop(vec![r4, r2, r3, r4]))
         // );
         // assert_eq!(state.cache[0], None);
@@ -28,7 +28,7 @@
 //!
 //! The following example generates a network with two border routers `B0` and `B1`, two route
 //! reflectors `R0` and `R1`, and two external routers `E0` and `E1`. Both routers advertise the
-//! same prefix `Prefix(0)`, and all links have the same weight `1.0`.
+//! same prefix `10.0.0.0/8`, and all links have the same weight `1.0`.
 //!
 //! ```rust
 //! use snowcap::netsim::{Network, Prefix, AsId, BgpSessionType::*};
@@ -38,7 +38,7 @@
 //!
 //!     let mut t = Network::new();
 //!
-//!     let prefix = Prefix(0);
+//!     let prefix: Prefix = "10.0.0.0/8".parse()?;
 //!
 //!     let e0 = t.add_external_router("E0", AsId(1));
 //!     let b0 = t.add_router("B0");
@@ -91,27 +91,30 @@
 //! - Currently, messages will magically be sent from the source to the destination. change the
 //!   network such that messages are sent over actual links, and that links with infinite weight
 //!   are not allowed to be used. Also, make the routers route the actual messages.
-//! - Allow links to go down.
-//! - MED should only be compared for the same AS
 
+pub mod acl;
 pub mod bgp;
+pub mod dot;
 pub(crate) mod event;
 pub mod external_router;
 pub(crate) mod forwarding_state;
 pub mod route_map;
 pub mod router;
+pub(crate) mod snapshot;
+pub mod timing;
 pub mod types;
 
 pub(crate) use event::{Event, EventQueue};
 
 pub mod config;
 pub(crate) mod network;
+pub(crate) mod ospf;
 pub mod printer;
 
 pub use bgp::BgpSessionType;
-pub use forwarding_state::ForwardingState;
+pub use forwarding_state::{ForwardingChanges, ForwardingState};
 pub use network::Network;
 pub use types::{
-    AsId, ConfigError, DeviceError, IgpNetwork, LinkWeight, NetworkDevice, NetworkError, Prefix,
-    RouterId,
+    AsId, ConfigError, DeviceError, IgpNetwork, LinkWeight, NetworkDevice, NetworkError, OspfArea,
+    Prefix, PrefixError, RouterId, SnapshotId,
 };
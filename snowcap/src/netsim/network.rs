@@ -20,30 +20,32 @@
 //! This module represents the network topology, applies the configuration, and simulates the
 //! network.
 
-// ACL
-// IP_addr: allow/deny
-// IP_addr&&port: allow/deny
-// IP_addr&&port&&protocol: allow/deny 
-
 #[cfg(feature = "transient-violation")]
 use crate::hard_policies::{Condition, PolicyError};
-use crate::netsim::bgp::{BgpEvent, BgpSessionType};
+use crate::netsim::bgp::{BgpDecisionConfig, BgpEvent, BgpRoute, BgpSessionType, Origin};
 use crate::netsim::config::{Config, ConfigExpr, ConfigModifier, ConfigPatch};
 use crate::netsim::event::{Event, EventQueue};
 use crate::netsim::external_router::ExternalRouter;
+use crate::netsim::forwarding_state::ForwardingCandidates;
+use crate::netsim::ospf::{link_key, OspfAreas};
 use crate::netsim::printer;
 use crate::netsim::route_map::RouteMapDirection;
 use crate::netsim::router::Router;
+use crate::netsim::snapshot::{CowMap, SharedVec};
+use crate::netsim::timing::{EventScheduler, TimedTrace, TimingModel};
 use crate::netsim::types::{IgpNetwork, NetworkDevice, Destination};
 use crate::netsim::{
-    AsId, ConfigError, ForwardingState, LinkWeight, NetworkError, Prefix, RouterId,
+    AsId, ConfigError, ForwardingState, LinkWeight, NetworkError, Prefix, RouterId, SnapshotId,
 };
 
 use log::*;
-use petgraph::algo::FloatMeasure;
-#[cfg(feature = "transient-violation")]
+use petgraph::algo::{has_path_connecting, FloatMeasure};
+use petgraph::visit::EdgeFiltered;
 use rand::prelude::*;
-use std::collections::{HashMap, HashSet};
+#[cfg(feature = "serde")]
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeSet, HashSet};
+use std::sync::Arc;
 
 static DEFAULT_STOP_AFTER: usize = 10_000;
 static MAXIMUM_ALLOWED_LOOP_LEN: usize = 500;
@@ -143,21 +145,29 @@ static MAXIMUM_ALLOWED_LOOP_LEN: usize = 500;
 ///   When not decoupling the prefixes, we need to check $3! \cdot 3! = 36$ orderings. However, when
 ///   decoupling the two prefixes, we only need to check $3! + 3! = 12$ orderings.
 pub struct Network {
-    net: IgpNetwork,
-    links: Vec<(RouterId, RouterId)>,
-    routers: HashMap<RouterId, Router>,
-    external_routers: HashMap<RouterId, ExternalRouter>,
-    known_prefixes: HashSet<Prefix>,
+    net: Arc<IgpNetwork>,
+    links: Arc<Vec<(RouterId, RouterId)>>,
+    routers: CowMap<RouterId, Router>,
+    external_routers: CowMap<RouterId, ExternalRouter>,
+    known_prefixes: Arc<HashSet<Prefix>>,
     stop_after: Option<usize>,
-    config: Config,
+    config: Arc<Config>,
     queue: EventQueue,
-    event_history: Vec<(Event, Option<usize>)>,
+    event_history: SharedVec<(Event, Option<usize>)>,
     skip_queue: bool,
+    failed_links: Arc<HashSet<(RouterId, RouterId)>>,
+    failed_routers: Arc<HashSet<RouterId>>,
+    timing: Option<TimingModel>,
+    timed_trace: TimedTrace,
+    igp_changed: bool,
+    snapshots: Vec<NetworkSnapshot>,
+    fw_candidates: ForwardingCandidates,
 }
 
 // implements the public trait clone
 impl Clone for Network {
-    /// Cloning the network does not clone the event history, and any of the undo traces.
+    /// Cloning the network does not clone the event history, and any of the snapshots. All devices
+    /// are shared between both networks, until they are modified.
     fn clone(&self) -> Self {
         // for the new queue, remove the history of all enqueued events
         Self {
@@ -170,8 +180,17 @@ impl Clone for Network {
             config: self.config.clone(),
             queue: self.queue.clone(),
             // does not clone the event history
-            event_history: Vec::new(),
+            event_history: SharedVec::new(),
             skip_queue: false,
+            failed_links: self.failed_links.clone(),
+            failed_routers: self.failed_routers.clone(),
+            timing: self.timing.clone(),
+            // does not clone the timed trace
+            timed_trace: TimedTrace::default(),
+            igp_changed: false,
+            // does not clone the snapshots
+            snapshots: Vec::new(),
+            fw_candidates: self.fw_candidates.clone(),
         }
     }
 }
@@ -188,16 +207,23 @@ impl Network {
     pub fn new() -> Self {
         Self {
             // for routing?
-            net: IgpNetwork::new(),
-            links: Vec::new(),
-            routers: HashMap::new(),
-            known_prefixes: HashSet::new(),
-            external_routers: HashMap::new(),
+            net: Arc::new(IgpNetwork::new()),
+            links: Arc::new(Vec::new()),
+            routers: CowMap::new(),
+            known_prefixes: Arc::new(HashSet::new()),
+            external_routers: CowMap::new(),
             stop_after: Some(DEFAULT_STOP_AFTER),
-            config: Config::new(),
+            config: Arc::new(Config::new()),
             queue: EventQueue::new(),
-            event_history: Vec::new(),
+            event_history: SharedVec::new(),
             skip_queue: false,
+            failed_links: Arc::new(HashSet::new()),
+            failed_routers: Arc::new(HashSet::new()),
+            timing: None,
+            timed_trace: TimedTrace::default(),
+            igp_changed: false,
+            snapshots: Vec::new(),
+            fw_candidates: ForwardingCandidates::default(),
         }
     }
 
@@ -208,7 +234,8 @@ impl Network {
         // constructs a new router object
         // router id is the second argument
         // NodeIndex object from petgraph
-        let new_router = Router::new(name.into(), self.net.add_node(()), AsId(65001));
+        let new_router =
+            Router::new(name.into(), Arc::make_mut(&mut self.net).add_node(()), AsId(65001));
         let router_id = new_router.router_id();
         self.routers.insert(router_id, new_router);
         router_id
@@ -218,7 +245,8 @@ impl Network {
     /// messages, it just advertises routes from outside of the network. This function returns
     /// the ID of the router, which can be used to reference it while configuring the network.
     pub fn add_external_router<S: Into<String>>(&mut self, name: S, as_id: AsId) -> RouterId {
-        let new_router = ExternalRouter::new(name.into(), self.net.add_node(()), as_id);
+        let new_router =
+            ExternalRouter::new(name.into(), Arc::make_mut(&mut self.net).add_node(()), as_id);
         let router_id = new_router.router_id();
         self.external_routers.insert(router_id, new_router);
         router_id
@@ -249,9 +277,9 @@ impl Network {
     /// # }
     /// ```
     pub fn add_link(&mut self, source: RouterId, target: RouterId) {
-        self.links.push((source, target));
-        self.net.add_edge(source, target, LinkWeight::infinite());
-        self.net.add_edge(target, source, LinkWeight::infinite());
+        Arc::make_mut(&mut self.links).push((source, target));
+        Arc::make_mut(&mut self.net).add_edge(source, target, LinkWeight::infinite());
+        Arc::make_mut(&mut self.net).add_edge(target, source, LinkWeight::infinite());
     }
 
     /// Set the provided network-wide configuration. The network first computes the patch from the
@@ -344,23 +372,7 @@ impl Network {
                 }
 
                 // shuffle the first element of the queue
-                let mut pos = (rng.next_u64() as usize) % self.queue.len();
-                // get the source and target from the selected message and set pos to the first
-                // message from this source to this target. This guarantees TCP message ordering to
-                // be considered.
-                if let Event::Bgp(from, to, _) = self.queue.get(pos).unwrap().0 {
-                    pos = self
-                        .queue
-                        .iter()
-                        .take(pos + 1)
-                        .filter_map(|m| match m.0 {
-                            Event::Bgp(a, b, _) => Some((a, b)),
-                            _ => None,
-                        })
-                        .position(|(a, b)| a == from && b == to)
-                        .unwrap_or(pos);
-                }
-                self.queue.swap(0, pos);
+                self.shuffle_queue_front(&mut rng);
                 // perform the step
                 self.do_queue_step()?;
                 // check the transient state
@@ -393,6 +405,66 @@ impl Network {
         Ok(num_success)
     }
 
+    /// # Concurrent application
+    ///
+    /// Apply all modifiers at once, without letting the network converge in between, and process
+    /// the resulting events in a random order. As in the transient condition verification, the
+    /// messages sent from one router to another are never reordered. After every event,
+    /// `check` is called with the network in its transient state, and the process stops as soon as
+    /// it returns `false`. Returns `Ok(true)` if the check holds in every transient state, and the
+    /// network has converged.
+    ///
+    /// The modifiers are not undone afterwards, not even on error. Use [`Network::snapshot`] to
+    /// bring the network back to its previous state.
+    pub fn apply_modifiers_concurrently<R: RngCore>(
+        &mut self,
+        modifiers: &[ConfigModifier],
+        rng: &mut R,
+        mut check: impl FnMut(&Network) -> bool,
+    ) -> Result<bool, NetworkError> {
+        // prohibit the network from executing the queue right away!
+        self.skip_queue = true;
+        let result = modifiers.iter().try_for_each(|m| self.apply_modifier(m));
+        self.skip_queue = false;
+        result?;
+
+        let mut remaining_iter = self.stop_after;
+        while !self.queue.is_empty() {
+            if let Some(rem) = remaining_iter {
+                if rem == 0 {
+                    return Err(NetworkError::NoConvergence);
+                }
+                remaining_iter = Some(rem - 1);
+            }
+            self.shuffle_queue_front(rng);
+            self.do_queue_step()?;
+            if !check(self) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Move a random event of the queue to the front. If the selected event is a BGP message, the
+    /// first message from the same source to the same target is moved instead. This guarantees TCP
+    /// message ordering to be considered.
+    fn shuffle_queue_front<R: RngCore>(&mut self, rng: &mut R) {
+        let mut pos = (rng.next_u64() as usize) % self.queue.len();
+        if let Event::Bgp(from, to, _) = self.queue.get(pos).unwrap().0 {
+            pos = self
+                .queue
+                .iter()
+                .take(pos + 1)
+                .filter_map(|m| match m.0 {
+                    Event::Bgp(a, b, _) => Some((a, b)),
+                    _ => None,
+                })
+                .position(|(a, b)| a == from && b == to)
+                .unwrap_or(pos);
+        }
+        self.queue.swap(0, pos);
+    }
+
     /*
      * The following part is legacy code for executing the queue qhile checking hard policies. This
      * however does not work due to several reasons. Also, the hard policies are legacy code, and
@@ -477,9 +549,35 @@ impl Network {
         med: Option<u32>,
         community: Option<u32>,
     ) -> Result<(), NetworkError> {
-        debug!("Advertise prefix {} on {}", prefix.0, self.get_router_name(source)?);
+        let route = BgpRoute {
+            prefix,
+            as_path,
+            as_set: BTreeSet::new(),
+            next_hop: source,
+            local_pref: None,
+            med,
+            origin: Origin::Igp,
+            communities: community.into_iter().collect(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
+        };
+        self.advertise_external_bgp_route(source, route)
+    }
+
+    /// Advertise an external route with all its BGP attributes (like the origin, or multiple
+    /// communities) and let the network converge. The next hop of the route is set to `source`,
+    /// and the local preference is ignored. Apart from that, this function behaves exactly like
+    /// [`Network::advertise_external_route`].
+    pub fn advertise_external_bgp_route(
+        &mut self,
+        source: RouterId,
+        route: BgpRoute,
+    ) -> Result<(), NetworkError> {
+        debug!("Advertise prefix {} on {}", route.prefix, self.get_router_name(source)?);
         // insert the prefix into the hashset
-        self.known_prefixes.insert(prefix);
+        Arc::make_mut(&mut self.known_prefixes).insert(route.prefix);
+        self.fw_candidates.mark(source, route.prefix);
         // get the event id this event will get
         let parent_event_id = self.event_history.len();
 
@@ -488,7 +586,7 @@ impl Network {
             .external_routers
             .get_mut(&source)
             .ok_or(NetworkError::DeviceNotFound(source))?
-            .advertise_prefix(prefix, as_path, med, community, &mut self.queue, parent_event_id);
+            .advertise_route(route, &mut self.queue, parent_event_id);
 
         // add the event to the history
         self.event_history.push((Event::AdvertiseExternalRoute(source, route), None));
@@ -503,7 +601,7 @@ impl Network {
         source: RouterId,
         prefix: Prefix,
     ) -> Result<(), NetworkError> {
-        debug!("Retract prefix {} on {}", prefix.0, self.get_router_name(source)?);
+        debug!("Retract prefix {} on {}", prefix, self.get_router_name(source)?);
         // initiate the advertisement
         let parent_event_id = self.event_history.len();
 
@@ -511,6 +609,7 @@ impl Network {
             .get_mut(&source)
             .ok_or(NetworkError::DeviceNotFound(source))?
             .widthdraw_prefix(prefix, &mut self.queue, parent_event_id);
+        self.fw_candidates.mark(source, prefix);
 
         self.event_history.push((Event::WithdrawExternalRoute(source, prefix), None));
 
@@ -518,6 +617,57 @@ impl Network {
         self.do_queue()
     }
 
+    /// Fail the link between `a` and `b` (in both directions) and let the network converge. While
+    /// the link is down, it is not used by the IGP, and an eBGP session between `a` and `b` is
+    /// closed. iBGP sessions may use any other path through the network. They are only closed if
+    /// the failure partitions the IGP, such that there is no path left between both routers. An
+    /// error is returned if the link does not exist, or if it is already down.
+    pub fn fail_link(&mut self, a: RouterId, b: RouterId) -> Result<(), NetworkError> {
+        if !self.net.contains_edge(a, b) {
+            return Err(NetworkError::RoutersNotConnected(a, b));
+        }
+        if self.failed_links.contains(&link_key(a, b)) {
+            return Err(NetworkError::InvalidEvent(Event::LinkDown(a, b)));
+        }
+        debug!("Fail link {} -- {}", self.get_router_name(a)?, self.get_router_name(b)?);
+        self.apply_failure(Event::LinkDown(a, b))
+    }
+
+    /// Restore the link between `a` and `b`, which was failed with [`Network::fail_link`], and let
+    /// the network converge. All BGP sessions that were closed due to the failure are established
+    /// again. An error is returned if the link is not down.
+    pub fn restore_link(&mut self, a: RouterId, b: RouterId) -> Result<(), NetworkError> {
+        if !self.failed_links.contains(&link_key(a, b)) {
+            return Err(NetworkError::InvalidEvent(Event::LinkUp(a, b)));
+        }
+        debug!("Restore link {} -- {}", self.get_router_name(a)?, self.get_router_name(b)?);
+        self.apply_failure(Event::LinkUp(a, b))
+    }
+
+    /// Fail a router (internal or external) and let the network converge. All links of the router
+    /// are removed from the IGP, and all of its BGP sessions are closed. iBGP sessions between
+    /// other routers are closed as well if the failure partitions the IGP between them. An error
+    /// is returned if the router does not exist, or if it is already down.
+    pub fn fail_router(&mut self, router: RouterId) -> Result<(), NetworkError> {
+        debug!("Fail router {}", self.get_router_name(router)?);
+        if self.failed_routers.contains(&router) {
+            return Err(NetworkError::InvalidEvent(Event::RouterDown(router)));
+        }
+        self.apply_failure(Event::RouterDown(router))
+    }
+
+    /// Restore a router, which was failed with [`Network::fail_router`], and let the network
+    /// converge. All links of the router are used again by the IGP (unless they are failed
+    /// themselves), and its BGP sessions are established again. An error is returned if the router
+    /// is not down.
+    pub fn restore_router(&mut self, router: RouterId) -> Result<(), NetworkError> {
+        debug!("Restore router {}", self.get_router_name(router)?);
+        if !self.failed_routers.contains(&router) {
+            return Err(NetworkError::InvalidEvent(Event::RouterUp(router)));
+        }
+        self.apply_failure(Event::RouterUp(router))
+    }
+
     /// Undo the last action of the network, causing the network to be in the earlier state. If
     /// there was no action to be undone, then Ok(false) is returned. If something has changed,
     /// then Ok(true) is returned.
@@ -526,6 +676,8 @@ impl Network {
     /// - `apply_modifier`
     /// - `advertise_external_route`
     /// - `retract_external_route`
+    /// - `fail_link` and `restore_link`
+    /// - `fail_router` and `restore_router`
     ///
     /// After undo, the event queue will be empty.
     ///
@@ -542,6 +694,90 @@ impl Network {
         }
     }
 
+    /// Take a snapshot of the current state of the network, and return its ID. The network can be
+    /// brought back into this state with [`Network::restore`], independent of what happened in the
+    /// meantime. This includes the topology, the configuration, all routing tables, and the
+    /// history of events, such that actions performed before taking the snapshot can still be
+    /// undone after restoring it.
+    ///
+    /// Taking a snapshot does not copy any device. Instead, the devices, the topology, the
+    /// configuration and the event history are shared between the network and all of its
+    /// snapshots, and they are only copied once they are modified. Only the event queue is copied,
+    /// which is empty as long as the network has converged.
+    pub fn snapshot(&mut self) -> SnapshotId {
+        self.snapshots.push(NetworkSnapshot {
+            net: self.net.clone(),
+            links: self.links.clone(),
+            routers: self.routers.clone(),
+            external_routers: self.external_routers.clone(),
+            known_prefixes: self.known_prefixes.clone(),
+            config: self.config.clone(),
+            queue: self.queue.clone(),
+            event_history: self.event_history.clone(),
+            failed_links: self.failed_links.clone(),
+            failed_routers: self.failed_routers.clone(),
+        });
+        self.snapshots.len() - 1
+    }
+
+    /// Restore the state of the network stored in the snapshot `id`, created by
+    /// [`Network::snapshot`]. The snapshot is kept, and can be restored again later.
+    pub fn restore(&mut self, id: SnapshotId) -> Result<(), NetworkError> {
+        let snapshot = self.snapshots.get(id).ok_or(NetworkError::SnapshotNotFound(id))?;
+        // only the devices that differ from the snapshot can change their forwarding state
+        if Arc::ptr_eq(&self.net, &snapshot.net)
+            && Arc::ptr_eq(&self.known_prefixes, &snapshot.known_prefixes)
+            && Arc::ptr_eq(&self.failed_links, &snapshot.failed_links)
+            && Arc::ptr_eq(&self.failed_routers, &snapshot.failed_routers)
+        {
+            let routers = self.routers.changed_keys(&snapshot.routers);
+            let external_routers = self.external_routers.changed_keys(&snapshot.external_routers);
+            for r in routers.into_iter().chain(external_routers) {
+                self.fw_candidates.mark_router(r);
+            }
+        } else {
+            self.fw_candidates.mark_all();
+        }
+        self.net = snapshot.net.clone();
+        self.links = snapshot.links.clone();
+        self.routers = snapshot.routers.clone();
+        self.external_routers = snapshot.external_routers.clone();
+        self.known_prefixes = snapshot.known_prefixes.clone();
+        self.config = snapshot.config.clone();
+        self.queue = snapshot.queue.clone();
+        self.event_history = snapshot.event_history.clone();
+        self.failed_links = snapshot.failed_links.clone();
+        self.failed_routers = snapshot.failed_routers.clone();
+        Ok(())
+    }
+
+    /// Remove all snapshots with an ID larger or equal to `id`, releasing all devices that are no
+    /// longer used by the network. The IDs of new snapshots start again at `id`. This allows the
+    /// snapshots to be used as a stack while exploring a tree of network states.
+    pub fn truncate_snapshots(&mut self, id: SnapshotId) {
+        self.snapshots.truncate(id);
+    }
+
+    /// Remove all snapshots, releasing all devices that are no longer used by the network. The IDs
+    /// of new snapshots start again at 0.
+    pub fn clear_snapshots(&mut self) {
+        self.snapshots.clear();
+    }
+
+    /// Forget all changes of the forwarding state that were tracked so far. Call this function
+    /// after extracting the forwarding state with [`Network::get_forwarding_state`], which already
+    /// contains all changes, to make the next call to [`ForwardingState::update`] as cheap as
+    /// possible.
+    pub fn clear_forwarding_changes(&mut self) {
+        self.fw_candidates = ForwardingCandidates::default();
+    }
+
+    /// Returns all entries of the forwarding state that might have changed since the last call, and
+    /// forgets them.
+    pub(crate) fn take_forwarding_candidates(&mut self) -> ForwardingCandidates {
+        std::mem::take(&mut self.fw_candidates)
+    }
+
     /// Compute and return the current forwarding state.
     pub fn get_forwarding_state(&self) -> ForwardingState {
         // faster access of the forwarding table
@@ -627,6 +863,16 @@ impl Network {
         self.links.iter()
     }
 
+    /// Returns `true` if the link between `a` and `b` is failed.
+    pub fn is_link_down(&self, a: RouterId, b: RouterId) -> bool {
+        self.failed_links.contains(&link_key(a, b))
+    }
+
+    /// Returns `true` if the router is failed.
+    pub fn is_router_down(&self, router: RouterId) -> bool {
+        self.failed_routers.contains(&router)
+    }
+
     /// Configure the topology to pause the queue and return after a certain number of queue have
     /// been executed. The job queue will remain active. If set to None, the queue will continue
     /// running until converged.
@@ -634,6 +880,32 @@ impl Network {
         self.stop_after = stop_after;
     }
 
+    /// Enable the timed simulation with the given timing model, or disable it by passing `None`.
+    /// In the timed simulation, events are processed in the order of the time at which they are
+    /// received, and the network records a timestamped trace of all forwarding state changes
+    /// while converging. See the [`timing`](crate::netsim::timing) module for more details. If any
+    /// delay of the timing model is negative or not finite, `NetworkError::InvalidDelay` is returned
+    /// and the timing model is not changed.
+    pub fn set_timing_model(&mut self, timing: Option<TimingModel>) -> Result<(), NetworkError> {
+        if let Some(timing) = timing.as_ref() {
+            timing.validate()?;
+        }
+        self.timing = timing;
+        Ok(())
+    }
+
+    /// Returns the timing model, if the timed simulation is enabled.
+    pub fn get_timing_model(&self) -> Option<&TimingModel> {
+        self.timing.as_ref()
+    }
+
+    /// Returns the trace of forwarding states recorded during the last operation (like
+    /// `apply_modifier`, `advertise_external_route`, ...). The trace is only recorded if the timed
+    /// simulation is enabled, and it is empty if the network has been cloned.
+    pub fn get_timed_trace(&self) -> &TimedTrace {
+        &self.timed_trace
+    }
+
     /// Returns the name of the router, if the ID was found.
     pub fn get_router_name(&self, router_id: RouterId) -> Result<&str, NetworkError> {
         if let Some(r) = self.routers.get(&router_id) {
@@ -709,6 +981,8 @@ impl Network {
                 }
                 // gets the id of the next hop to visit the destination
                 current_node = match r.get_next_hop(Destination::BGP(prefix)) {
+                    // the router is the destination itself
+                    Some(router_id) if router_id == current_node => break,
                     // unwrap option value
                     Some(router_id) => router_id,
                     None => {
@@ -779,11 +1053,15 @@ impl Network {
             .cloned()
             .collect::<HashSet<RouterId>>();
         for target in routers_set {
-            if let Some(Some((next_hop, cost))) = r.get_igp_fw_table().get(&target) {
+            if let Some(Some((next_hops, cost))) = r.get_igp_fw_table().get(&target) {
                 println!(
                     "  {} via {} (IGP cost: {})",
                     self.get_router_name(target)?,
-                    self.get_router_name(*next_hop)?,
+                    next_hops
+                        .iter()
+                        .map(|nh| self.get_router_name(*nh))
+                        .collect::<Result<Vec<_>, _>>()?
+                        .join(", "),
                     cost
                 );
             } else {
@@ -835,7 +1113,8 @@ impl Network {
         // self.config is the current configurations on the network
         // checks if the config can be applied 
         // if so, appended to the config struct of the network
-        self.config.apply_modifier(modifier)?;
+        Arc::make_mut(&mut self.config).apply_modifier(modifier)?;
+        modifier.routers().into_iter().for_each(|r| self.fw_candidates.mark_router(r));
         if undo {
             println!("Undoing modifier {:?}", modifier);
         }
@@ -849,11 +1128,15 @@ impl Network {
                         return Err(NetworkError::RoutersNotConnected(*source, *target));
                     }
                     // update the link in in dynamic graph
-                    self.net.update_edge(*source, *target, *weight);
+                    Arc::make_mut(&mut self.net).update_edge(*source, *target, *weight);
                     // call this function to update forwarding table?
                     self.write_igp_fw_tables(parent_event_id, undo)
                 }
                 ConfigExpr::BgpSession { source, target, session_type } => {
+                    if self.is_bgp_session_down(*source, *target, *session_type) {
+                        // the session is established once the failure is repaired.
+                        return Ok(());
+                    }
                     self.add_bgp_session(*source, *target, *session_type, parent_event_id, undo)
                 }
                 ConfigExpr::BgpRouteMap { router, direction, map } => {
@@ -888,24 +1171,90 @@ impl Network {
                     }
                 }
                 ConfigExpr::StaticRoute { router, prefix, target } => {
-                    // check if router has a link to target
-                    if !self.net.contains_edge(*router, *target) {
+                    // check if router has a link to target (or if the prefix is connected)
+                    if router != target && !self.net.contains_edge(*router, *target) {
                         return Err(NetworkError::RoutersNotConnected(*router, *target));
                     }
                     self.routers
                         .get_mut(router)
                         .ok_or(NetworkError::DeviceNotFound(*router))?
-                        .add_static_route(*prefix, *target)?;
-                    Ok(())
+                        .add_static_route(
+                            *prefix,
+                            *target,
+                            &mut self.queue,
+                            parent_event_id,
+                            undo,
+                        )?;
+                    self.update_originated_prefixes(&[*prefix], undo)
                 }
-                ConfigExpr::AccessControl { router, accept, deny } => {
-                    // TODO
+                ConfigExpr::AccessControl { router, interface, direction, acl } => {
+                    // check if router has a link to the interface
+                    if !self.net.contains_edge(*router, *interface) {
+                        return Err(NetworkError::RoutersNotConnected(*router, *interface));
+                    }
                     self.routers
                         .get_mut(router)
                         .ok_or(NetworkError::DeviceNotFound(*router))?
-                        .add_acl_rules(accept, deny)?;
+                        .add_acl(*interface, *direction, acl.clone())?;
                     Ok(())
                 }
+                ConfigExpr::IgpLinkArea { source, target, area: _ } => {
+                    // check if router has a link to target
+                    if !self.net.contains_edge(*source, *target) {
+                        return Err(NetworkError::RoutersNotConnected(*source, *target));
+                    }
+                    self.write_igp_fw_tables(parent_event_id, undo)
+                }
+                ConfigExpr::OspfStubArea { area } => {
+                    if area.is_backbone() {
+                        return Err(NetworkError::ConfigError(ConfigError::ConfigModifierError(
+                            modifier.clone(),
+                        )));
+                    }
+                    self.write_igp_fw_tables(parent_event_id, undo)
+                }
+                ConfigExpr::BgpNetwork { router, prefix } => {
+                    self.routers
+                        .get_mut(router)
+                        .ok_or(NetworkError::DeviceNotFound(*router))?
+                        .add_bgp_network(*prefix, &mut self.queue, parent_event_id, undo)?;
+                    self.update_originated_prefixes(&[*prefix], undo)
+                }
+                ConfigExpr::BgpRedistribute { router, source, route_maps } => {
+                    let r = self
+                        .routers
+                        .get_mut(router)
+                        .ok_or(NetworkError::DeviceNotFound(*router))?;
+                    r.set_bgp_redistribution(
+                        *source,
+                        Some(route_maps.clone()),
+                        &mut self.queue,
+                        parent_event_id,
+                        undo,
+                    )?;
+                    let prefixes: Vec<Prefix> = r.static_routes.keys().copied().collect();
+                    self.update_originated_prefixes(&prefixes, undo)
+                }
+                ConfigExpr::BgpAggregate { router, prefix, summary_only, as_set } => {
+                    self.routers
+                        .get_mut(router)
+                        .ok_or(NetworkError::DeviceNotFound(*router))?
+                        .add_bgp_aggregate(
+                            *prefix,
+                            *summary_only,
+                            *as_set,
+                            &mut self.queue,
+                            parent_event_id,
+                            undo,
+                        )?;
+                    self.update_originated_prefixes(&[*prefix], undo)
+                }
+                ConfigExpr::BgpDecision { router, config } => {
+                    self.set_bgp_decision_config(*router, *config, parent_event_id, undo)
+                }
+                ConfigExpr::BgpClusterId { router, cluster_id } => {
+                    self.set_bgp_cluster_id(*router, Some(*cluster_id), parent_event_id, undo)
+                }
             },
             ConfigModifier::Remove(expr) => match expr {
                 ConfigExpr::IgpLinkWeight { source, target, weight: _ } => {
@@ -913,10 +1262,17 @@ impl Network {
                     if !self.net.contains_edge(*source, *target) {
                         return Err(NetworkError::RoutersNotConnected(*source, *target));
                     }
-                    self.net.update_edge(*source, *target, LinkWeight::infinite());
+                    Arc::make_mut(&mut self.net).update_edge(
+                        *source,
+                        *target,
+                        LinkWeight::infinite(),
+                    );
                     self.write_igp_fw_tables(parent_event_id, undo)
                 }
-                ConfigExpr::BgpSession { source, target, session_type: _ } => {
+                ConfigExpr::BgpSession { source, target, session_type } => {
+                    if self.is_bgp_session_down(*source, *target, *session_type) {
+                        return Ok(());
+                    }
                     self.remove_bgp_session(*source, *target, parent_event_id, undo)
                 }
                 ConfigExpr::BgpRouteMap { router, direction, map } => {
@@ -952,24 +1308,69 @@ impl Network {
                 }
 
                 ConfigExpr::StaticRoute { router, prefix, target } => {
-                    // check if router has a link to target
-                    if !self.net.contains_edge(*router, *target) {
+                    // check if router has a link to target (or if the prefix is connected)
+                    if router != target && !self.net.contains_edge(*router, *target) {
                         return Err(NetworkError::RoutersNotConnected(*router, *target));
                     }
                     self.routers
                         .get_mut(router)
                         .ok_or(NetworkError::DeviceNotFound(*router))?
-                        .remove_static_route(*prefix)?;
-                    Ok(())
+                        .remove_static_route(*prefix, &mut self.queue, parent_event_id, undo)?;
+                    self.update_originated_prefixes(&[*prefix], undo)
                 }
-                ConfigExpr::AccessControl { router, accept, deny } => {
-                    // TODO
+                ConfigExpr::AccessControl { router, interface, direction, .. } => {
                     self.routers
                         .get_mut(router)
                         .ok_or(NetworkError::DeviceNotFound(*router))?
-                        .remove_acl_rules(accept, deny)?;
+                        .remove_acl(*interface, *direction)?;
                     Ok(())
                 }
+                ConfigExpr::IgpLinkArea { source, target, area: _ } => {
+                    // check if router has a link to target
+                    if !self.net.contains_edge(*source, *target) {
+                        return Err(NetworkError::RoutersNotConnected(*source, *target));
+                    }
+                    self.write_igp_fw_tables(parent_event_id, undo)
+                }
+                ConfigExpr::OspfStubArea { .. } => self.write_igp_fw_tables(parent_event_id, undo),
+                ConfigExpr::BgpNetwork { router, prefix } => {
+                    self.routers
+                        .get_mut(router)
+                        .ok_or(NetworkError::DeviceNotFound(*router))?
+                        .remove_bgp_network(*prefix, &mut self.queue, parent_event_id, undo)?;
+                    self.update_originated_prefixes(&[*prefix], undo)
+                }
+                ConfigExpr::BgpRedistribute { router, source, route_maps: _ } => {
+                    let r = self
+                        .routers
+                        .get_mut(router)
+                        .ok_or(NetworkError::DeviceNotFound(*router))?;
+                    r.set_bgp_redistribution(
+                        *source,
+                        None,
+                        &mut self.queue,
+                        parent_event_id,
+                        undo,
+                    )?;
+                    let prefixes: Vec<Prefix> = r.static_routes.keys().copied().collect();
+                    self.update_originated_prefixes(&prefixes, undo)
+                }
+                ConfigExpr::BgpAggregate { router, prefix, .. } => {
+                    self.routers
+                        .get_mut(router)
+                        .ok_or(NetworkError::DeviceNotFound(*router))?
+                        .remove_bgp_aggregate(*prefix, &mut self.queue, parent_event_id, undo)?;
+                    self.update_originated_prefixes(&[*prefix], undo)
+                }
+                ConfigExpr::BgpDecision { router, .. } => self.set_bgp_decision_config(
+                    *router,
+                    BgpDecisionConfig::default(),
+                    parent_event_id,
+                    undo,
+                ),
+                ConfigExpr::BgpClusterId { router, .. } => {
+                    self.set_bgp_cluster_id(*router, None, parent_event_id, undo)
+                }
             },
             ConfigModifier::Update { from, to } => match (from, to) {
                 (
@@ -980,13 +1381,16 @@ impl Network {
                     if !self.net.contains_edge(*s1, *t1) {
                         return Err(NetworkError::RoutersNotConnected(*s1, *t1));
                     }
-                    self.net.update_edge(*s1, *t1, *w);
+                    Arc::make_mut(&mut self.net).update_edge(*s1, *t1, *w);
                     self.write_igp_fw_tables(parent_event_id, undo)
                 }
                 (
                     ConfigExpr::BgpSession { source: s1, target: t1, session_type: _ },
                     ConfigExpr::BgpSession { source: s2, target: t2, session_type: x },
                 ) if (s1 == s2 && t1 == t2) || (s1 == t2 && t1 == s2) => {
+                    if self.is_bgp_session_down(*s2, *t2, *x) {
+                        return Ok(());
+                    }
                     self.modify_bgp_session(*s2, *t2, *x, parent_event_id, undo)
                 }
                 (
@@ -1029,27 +1433,83 @@ impl Network {
                     ConfigExpr::StaticRoute { router: r1, prefix: p1, target: _ },
                     ConfigExpr::StaticRoute { router: r2, prefix: p2, target: t },
                 ) if r1 == r2 && p1 == p2 => {
-                    // check if router has a link to target
-                    if !self.net.contains_edge(*r1, *t) {
+                    // check if router has a link to target (or if the prefix is connected)
+                    if r1 != t && !self.net.contains_edge(*r1, *t) {
                         return Err(NetworkError::RoutersNotConnected(*r1, *t));
                     }
                     self.routers
                         .get_mut(r1)
                         .ok_or(NetworkError::DeviceNotFound(*r1))?
-                        .modify_static_route(*p1, *t)?;
-                    Ok(())
+                        .modify_static_route(*p1, *t, &mut self.queue, parent_event_id, undo)?;
+                    self.update_originated_prefixes(&[*p1], undo)
                 }
                 (
-                    ConfigExpr::AccessControl { router: router1, accept: accept1, deny: deny1 },
-                    ConfigExpr::AccessControl { router: router2, accept: accept2, deny: deny2 }
-                ) if router1 == router2 => {
-                    // check if the router before and after are the same
+                    ConfigExpr::AccessControl { router: r1, interface: i1, direction: d1, .. },
+                    ConfigExpr::AccessControl { router: r2, interface: i2, direction: d2, acl },
+                ) if r1 == r2 && i1 == i2 && d1 == d2 => {
                     self.routers
-                        .get_mut(router1)
-                        .ok_or(NetworkError::DeviceNotFound(*router1))?
-                        .modify_acl_rules(accept1, deny1, accept2, deny2)?;
+                        .get_mut(r1)
+                        .ok_or(NetworkError::DeviceNotFound(*r1))?
+                        .modify_acl(*i2, *d2, acl.clone())?;
                     Ok(())
                 }
+                (
+                    ConfigExpr::IgpLinkArea { source: s1, target: t1, area: _ },
+                    ConfigExpr::IgpLinkArea { source: s2, target: t2, area: _ },
+                ) if (s1 == s2 && t1 == t2) || (s1 == t2 && t1 == s2) => {
+                    // check if router has a link to target
+                    if !self.net.contains_edge(*s2, *t2) {
+                        return Err(NetworkError::RoutersNotConnected(*s2, *t2));
+                    }
+                    self.write_igp_fw_tables(parent_event_id, undo)
+                }
+                (ConfigExpr::OspfStubArea { area: a1 }, ConfigExpr::OspfStubArea { area: a2 })
+                    if a1 == a2 && !a2.is_backbone() =>
+                {
+                    self.write_igp_fw_tables(parent_event_id, undo)
+                }
+                (
+                    ConfigExpr::BgpRedistribute { router: r1, source: s1, route_maps: _ },
+                    ConfigExpr::BgpRedistribute { router: r2, source: s2, route_maps: m },
+                ) if r1 == r2 && s1 == s2 => {
+                    let r = self.routers.get_mut(r1).ok_or(NetworkError::DeviceNotFound(*r1))?;
+                    r.set_bgp_redistribution(
+                        *s1,
+                        Some(m.clone()),
+                        &mut self.queue,
+                        parent_event_id,
+                        undo,
+                    )?;
+                    let prefixes: Vec<Prefix> = r.static_routes.keys().copied().collect();
+                    self.update_originated_prefixes(&prefixes, undo)
+                }
+                (
+                    ConfigExpr::BgpAggregate { router: r1, prefix: p1, .. },
+                    ConfigExpr::BgpAggregate { router: r2, prefix: p2, summary_only, as_set },
+                ) if r1 == r2 && p1 == p2 => {
+                    self.routers
+                        .get_mut(r1)
+                        .ok_or(NetworkError::DeviceNotFound(*r1))?
+                        .modify_bgp_aggregate(
+                            *p1,
+                            *summary_only,
+                            *as_set,
+                            &mut self.queue,
+                            parent_event_id,
+                            undo,
+                        )?;
+                    self.update_originated_prefixes(&[*p1], undo)
+                }
+                (
+                    ConfigExpr::BgpDecision { router: r1, .. },
+                    ConfigExpr::BgpDecision { router: r2, config },
+                ) if r1 == r2 => self.set_bgp_decision_config(*r1, *config, parent_event_id, undo),
+                (
+                    ConfigExpr::BgpClusterId { router: r1, .. },
+                    ConfigExpr::BgpClusterId { router: r2, cluster_id },
+                ) if r1 == r2 => {
+                    self.set_bgp_cluster_id(*r1, Some(*cluster_id), parent_event_id, undo)
+                }
                 _ => {
                     Err(NetworkError::ConfigError(ConfigError::ConfigModifierError(
                         modifier.clone()
@@ -1059,6 +1519,78 @@ impl Network {
         }
     }
 
+    /// Configure the BGP decision process of an internal router. If `undo` is set, the BGP tables
+    /// are restored from the undo stack of the router.
+    fn set_bgp_decision_config(
+        &mut self,
+        router: RouterId,
+        config: BgpDecisionConfig,
+        parent_event_id: usize,
+        undo: bool,
+    ) -> Result<(), NetworkError> {
+        self.routers
+            .get_mut(&router)
+            .ok_or(NetworkError::DeviceNotFound(router))?
+            .set_bgp_decision_config(config, &mut self.queue, parent_event_id, undo)?;
+        if undo {
+            Ok(())
+        } else {
+            self.do_queue()
+        }
+    }
+
+    /// Configure the cluster ID of an internal router. If `cluster_id` is `None`, the router's own
+    /// ID is used as cluster ID. If `undo` is set, the BGP tables are restored from the undo stack
+    /// of the router.
+    fn set_bgp_cluster_id(
+        &mut self,
+        router: RouterId,
+        cluster_id: Option<RouterId>,
+        parent_event_id: usize,
+        undo: bool,
+    ) -> Result<(), NetworkError> {
+        if let Some(cluster_id) = cluster_id {
+            self.get_router_name(cluster_id)?;
+        }
+        self.routers
+            .get_mut(&router)
+            .ok_or(NetworkError::DeviceNotFound(router))?
+            .set_bgp_cluster_id(cluster_id, &mut self.queue, parent_event_id, undo)?;
+        if undo {
+            Ok(())
+        } else {
+            self.do_queue()
+        }
+    }
+
+    /// Update the known prefixes after an internal router has changed which of the `prefixes` it
+    /// originates, and let the network converge (if `undo` is not set). Prefixes that are now
+    /// originated by a router are added to the known prefixes. In the undo case, all of the
+    /// `prefixes` that are neither originated by any router, nor advertised by an external router
+    /// are removed again.
+    fn update_originated_prefixes(
+        &mut self,
+        prefixes: &[Prefix],
+        undo: bool,
+    ) -> Result<(), NetworkError> {
+        let originated: HashSet<Prefix> =
+            self.routers.values().flat_map(|r| r.get_originated_prefixes()).collect();
+        if undo {
+            for prefix in prefixes {
+                if !originated.contains(prefix)
+                    && !self.external_routers.values().any(|r| r.has_active_route(*prefix))
+                {
+                    Arc::make_mut(&mut self.known_prefixes).remove(prefix);
+                }
+            }
+            Ok(())
+        } else {
+            Arc::make_mut(&mut self.known_prefixes)
+                .extend(prefixes.iter().filter(|p| originated.contains(p)));
+            self.do_queue()
+        }
+    }
+
     /// # Add an BGP session
     ///
     /// Adds an BGP session between source and target. If the session type is set to IBGpClient,
@@ -1236,9 +1768,144 @@ impl Network {
         }
     }
 
+    /// Add the failure event to the history, apply it and let the network converge.
+    fn apply_failure(&mut self, event: Event) -> Result<(), NetworkError> {
+        let parent_event_id = self.event_history.len();
+        self.event_history.push((event.clone(), None));
+
+        // only run the queue once all sessions are updated.
+        let skip_queue = self.skip_queue;
+        self.skip_queue = true;
+        let result = self.apply_or_undo_failure(&event, false, parent_event_id);
+        self.skip_queue = skip_queue;
+        result?;
+        self.do_queue()
+    }
+
+    /// Apply or undo a link or router failure (or its recovery). The IGP tables are recomputed, and
+    /// all BGP sessions whose state changes are closed or established. In the undo case, make sure
+    /// that the event is reversed (see [`Event::reverse_failure`])! The changes are then undone
+    /// in the reverse order, since the undo stack of each router is a stack.
+    fn apply_or_undo_failure(
+        &mut self,
+        event: &Event,
+        undo: bool,
+        parent_event_id: usize,
+    ) -> Result<(), NetworkError> {
+        self.fw_candidates.mark_all();
+        let down_before = self.bgp_sessions_down();
+        match event {
+            Event::LinkDown(a, b) => Arc::make_mut(&mut self.failed_links).insert(link_key(*a, *b)),
+            Event::LinkUp(a, b) => Arc::make_mut(&mut self.failed_links).remove(&link_key(*a, *b)),
+            Event::RouterDown(r) => Arc::make_mut(&mut self.failed_routers).insert(*r),
+            Event::RouterUp(r) => Arc::make_mut(&mut self.failed_routers).remove(r),
+            _ => return Err(NetworkError::InvalidEvent(event.clone())),
+        };
+        let down_after = self.bgp_sessions_down();
+        let closed: Vec<_> = down_after.iter().filter(|s| !down_before.contains(s)).collect();
+        let opened: Vec<_> = down_before.iter().filter(|s| !down_after.contains(s)).collect();
+
+        if undo {
+            for (source, target, _) in closed.into_iter().rev() {
+                self.remove_bgp_session(*source, *target, parent_event_id, true)?;
+            }
+            for (source, target, session_type) in opened.into_iter().rev() {
+                self.add_bgp_session(*source, *target, *session_type, parent_event_id, true)?;
+            }
+            self.write_igp_fw_tables(parent_event_id, true)
+        } else {
+            self.write_igp_fw_tables(parent_event_id, false)?;
+            for (source, target, _) in closed {
+                self.remove_bgp_session(*source, *target, parent_event_id, false)?;
+            }
+            for (source, target, session_type) in opened {
+                self.add_bgp_session(*source, *target, *session_type, parent_event_id, false)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Returns `true` if the BGP session cannot be established due to a failure. This is the case
+    /// if one of the routers is down, or if it is an eBGP session and the link between both
+    /// routers is down. An iBGP session is also down if the failures partition the IGP, such that
+    /// there is no longer a path between both routers (in both directions).
+    fn is_bgp_session_down(
+        &self,
+        source: RouterId,
+        target: RouterId,
+        session_type: BgpSessionType,
+    ) -> bool {
+        self.is_bgp_session_down_in(&self.failed_igp_net(), source, target, session_type)
+    }
+
+    /// Same as [`Network::is_bgp_session_down`], but reusing the IGP topology `failed_net`
+    /// without the failed links, as returned by [`Network::failed_igp_net`].
+    fn is_bgp_session_down_in(
+        &self,
+        failed_net: &IgpNetwork,
+        source: RouterId,
+        target: RouterId,
+        session_type: BgpSessionType,
+    ) -> bool {
+        if self.failed_routers.contains(&source) || self.failed_routers.contains(&target) {
+            return true;
+        }
+        if session_type.is_ebgp() {
+            return self.failed_links.contains(&link_key(source, target));
+        }
+        if self.failed_links.is_empty() && self.failed_routers.is_empty() {
+            return false;
+        }
+        // only consider sessions whose routers were disconnected by the failures, not by the
+        // configuration of the link weights.
+        igp_connected(&self.net, source, target) && !igp_connected(failed_net, source, target)
+    }
+
+    /// Returns all configured BGP sessions that are down due to a failure, sorted by the routers.
+    fn bgp_sessions_down(&self) -> Vec<(RouterId, RouterId, BgpSessionType)> {
+        let failed_net = self.failed_igp_net();
+        let mut sessions: Vec<(RouterId, RouterId, BgpSessionType)> = self
+            .config
+            .iter()
+            .filter_map(|expr| match expr {
+                ConfigExpr::BgpSession { source, target, session_type } => {
+                    Some((*source, *target, *session_type))
+                }
+                _ => None,
+            })
+            .filter(|(s, t, x)| self.is_bgp_session_down_in(&failed_net, *s, *t, *x))
+            .collect();
+        sessions.sort_by_key(|(s, t, _)| (*s, *t));
+        sessions
+    }
+
+    /// Returns the IGP topology without all failed links and without the links of all failed
+    /// routers (their weight is set to infinity).
+    fn failed_igp_net(&self) -> Arc<IgpNetwork> {
+        if self.failed_links.is_empty() && self.failed_routers.is_empty() {
+            return self.net.clone();
+        }
+        Arc::new(self.net.map(
+            |_, n| *n,
+            |e, w| {
+                let (a, b) = self.net.edge_endpoints(e).unwrap();
+                if self.failed_links.contains(&link_key(a, b))
+                    || self.failed_routers.contains(&a)
+                    || self.failed_routers.contains(&b)
+                {
+                    LinkWeight::infinite()
+                } else {
+                    *w
+                }
+            },
+        ))
+    }
+
     /// Write the igp forwarding tables for all internal routers. As soon as this is done, recompute
     /// the BGP table. and run the algorithm. This will happen all at once, in a very unpredictable
-    /// manner. If you want to do this more predictable, use `write_ibgp_fw_table`.
+    /// manner. If you want to do this more predictable, use `write_ibgp_fw_table`. As soon as any
+    /// link is assigned to an OSPF area, the tables of all routers are computed at once, taking
+    /// the areas into account.
     ///
     /// The function returns Ok(true) if all events caused by the igp fw table write are handled
     /// correctly. Returns Ok(false) if the max number of iterations is exceeded, and returns an
@@ -1248,13 +1915,31 @@ impl Network {
         parent_event_id: usize,
         undo: bool,
     ) -> Result<(), NetworkError> {
-        // update igp table
-        for r in self.routers.values_mut() {
-            r.write_igp_forwarding_table(&self.net, &mut self.queue, parent_event_id, undo)?;
+        let areas = OspfAreas::from_config(&self.config);
+        let failed_net = self.failed_igp_net();
+        let net: &IgpNetwork = &failed_net;
+        // update igp table. Only the forwarding state of routers whose table has changed can be
+        // affected directly, all others will receive BGP updates if anything changes for them.
+        if areas.is_flat() {
+            for r in self.routers.values_mut() {
+                if r.write_igp_forwarding_table(net, &mut self.queue, parent_event_id, undo)? {
+                    self.fw_candidates.mark_router(r.router_id());
+                }
+            }
+        } else {
+            let routers: Vec<RouterId> = self.routers.keys().copied().collect();
+            let mut tables = areas.igp_forwarding_tables(net, &routers);
+            for (id, r) in self.routers.iter_mut() {
+                let table = tables.remove(id).unwrap_or_default();
+                if r.set_igp_forwarding_table(table, &mut self.queue, parent_event_id, undo)? {
+                    self.fw_candidates.mark_router(*id);
+                }
+            }
         }
         if undo {
             Ok(())
         } else {
+            self.igp_changed = true;
             self.do_queue()
         }
     }
@@ -1336,6 +2021,7 @@ impl Network {
                 let fw_state_change = match next_event {
                     Event::Bgp(from, to, bgp_event) => {
                         //self.bgp_race_checker(to, &bgp_event, &history);
+                        self.fw_candidates.mark(to, bgp_event.prefix());
                         if let Some(r) = self.routers.get_mut(&to) {
                             r.handle_event(
                                 Event::Bgp(from, to, bgp_event),
@@ -1435,6 +2121,11 @@ impl Network {
         if self.skip_queue {
             return Ok(());
         }
+        if self.timing.is_some() {
+            return self.do_queue_timed();
+        }
+        // the SPF delay is only relevant for the timed simulation
+        self.igp_changed = false;
         let mut remaining_iter = self.stop_after;
         while !self.queue.is_empty() {
             if let Some(rem) = remaining_iter {
@@ -1463,34 +2154,146 @@ impl Network {
         Ok(())
     }
 
+    /// Execute the queue in the timed simulation, until all events are processed. All events are
+    /// handled in the order of the time at which they are received. Every time the forwarding state
+    /// changes, it is recorded in the timed trace. The recorded state is updated incrementally
+    /// after each event, while the changes are still tracked for [`ForwardingState::update`].
+    fn do_queue_timed(&mut self) -> Result<(), NetworkError> {
+        let timing = self.timing.clone().unwrap();
+        let igp_changed = std::mem::replace(&mut self.igp_changed, false);
+        let mut scheduler = EventScheduler::default();
+        let mut state = self.get_forwarding_state();
+        let mut tracked = self.take_forwarding_candidates();
+        self.timed_trace = TimedTrace::default();
+        self.timed_trace.push(0.0, state.clone_state());
+
+        // schedule all initial events, which are sent after the SPF delay if the IGP has changed.
+        for (event, parent_event_id) in std::mem::take(&mut self.queue) {
+            let now = match (&event, igp_changed) {
+                (Event::Bgp(from, _, _), true) => timing.spf_delay(*from),
+                _ => 0.0,
+            };
+            let delay = self.session_delay(&timing, &event);
+            scheduler.schedule(&timing, now, event, parent_event_id, delay);
+        }
+
+        let result = self.do_queue_timed_events(&timing, &mut scheduler, &mut state, &mut tracked);
+        tracked.extend(self.take_forwarding_candidates());
+        self.fw_candidates = tracked;
+        result
+    }
+
+    /// Process all events of the `scheduler`, and record the forwarding state after every change.
+    /// All changes of the forwarding state are added to `tracked`.
+    fn do_queue_timed_events(
+        &mut self,
+        timing: &TimingModel,
+        scheduler: &mut EventScheduler,
+        state: &mut ForwardingState,
+        tracked: &mut ForwardingCandidates,
+    ) -> Result<(), NetworkError> {
+        let mut remaining_iter = self.stop_after;
+        while let Some((time, event, parent_event_id)) = scheduler.pop() {
+            if let Some(rem) = remaining_iter {
+                if rem == 0 {
+                    debug!("Network cannot converge!");
+                    return Err(NetworkError::NoConvergence);
+                }
+                remaining_iter = Some(rem - 1);
+            }
+            if self.execute_event(event, parent_event_id)? {
+                let candidates = self.take_forwarding_candidates();
+                state.update_with(self, &candidates);
+                tracked.extend(candidates);
+                self.timed_trace.push(time, state.clone_state());
+            }
+            for (event, parent_event_id) in std::mem::take(&mut self.queue) {
+                let delay = self.session_delay(timing, &event);
+                scheduler.schedule(timing, time, event, parent_event_id, delay);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the propagation delay of a BGP message, which is the sum of all link delays along
+    /// the IGP path from the sender to the receiver. If there is no such path, the message is sent
+    /// over the direct link (or with the default link delay if there is no direct link).
+    fn session_delay(&self, timing: &TimingModel, event: &Event) -> f64 {
+        let (from, to) = match event {
+            Event::Bgp(from, to, _) => (*from, *to),
+            _ => return 0.0,
+        };
+        let mut delay = 0.0;
+        let mut current = from;
+        for _ in 0..self.num_devices() {
+            if current == to {
+                return delay;
+            }
+            let next = match self.routers.get(&current) {
+                Some(r) => r.get_next_hop(Destination::IGP(to)),
+                None => None,
+            };
+            let next = match next {
+                Some(next) => next,
+                None if self.net.contains_edge(current, to) => to,
+                None => break,
+            };
+            delay += timing.link_delay(current, next);
+            current = next;
+        }
+        if self.net.contains_edge(from, to) {
+            timing.link_delay(from, to)
+        } else {
+            timing.default_link_delay
+        }
+    }
+
     /// Executes one single step. If the result is Ok(true), then a step is successfully executed.
     /// If the result is Ok(false), then there was no event present in the queue.
     fn do_queue_step(&mut self) -> Result<bool, NetworkError> {
         if let Some((event, parent_event_id)) = self.queue.pop_front() {
-            // log the job
-            self.log_event(&event)?;
-            // execute the event
-            let event_id = self.event_history.len();
-            let _fw_state_change = match event.clone() {
-                Event::Bgp(from, to, bgp_event) => {
-                    //self.bgp_race_checker(to, &bgp_event, &history);
-                    if let Some(r) = self.routers.get_mut(&to) {
-                        r.handle_event(Event::Bgp(from, to, bgp_event), &mut self.queue, event_id)?
-                    } else if let Some(r) = self.external_routers.get_mut(&to) {
-                        r.handle_event(Event::Bgp(from, to, bgp_event), &mut self.queue, event_id)?
-                    } else {
-                        return Err(NetworkError::DeviceNotFound(to));
-                    }
-                }
-                e => return Err(NetworkError::InvalidEvent(e)),
-            };
-            self.event_history.push((event, Some(parent_event_id)));
+            self.execute_event(event, parent_event_id)?;
             Ok(true)
         } else {
             Ok(false)
         }
     }
 
+    /// Execute a single event (already removed from the queue), and push it to the event history.
+    /// Returns Ok(true) if the forwarding state has changed.
+    fn execute_event(
+        &mut self,
+        event: Event,
+        parent_event_id: usize,
+    ) -> Result<bool, NetworkError> {
+        // log the job
+        self.log_event(&event)?;
+        // execute the event
+        let event_id = self.event_history.len();
+        let fw_state_change = match event.clone() {
+            Event::Bgp(from, to, bgp_event) => {
+                //self.bgp_race_checker(to, &bgp_event, &history);
+                if let Some(r) = self.routers.get_mut(&to) {
+                    let prefix = bgp_event.prefix();
+                    let event = Event::Bgp(from, to, bgp_event);
+                    let change = r.handle_event(event, &mut self.queue, event_id)?;
+                    if change {
+                        self.fw_candidates.mark(to, prefix);
+                    }
+                    change
+                } else if let Some(r) = self.external_routers.get_mut(&to) {
+                    r.handle_event(Event::Bgp(from, to, bgp_event), &mut self.queue, event_id)?
+                } else {
+                    return Err(NetworkError::DeviceNotFound(to));
+                }
+            }
+            e => return Err(NetworkError::InvalidEvent(e)),
+        };
+        self.event_history.push((event, Some(parent_event_id)));
+        Ok(fw_state_change)
+    }
+
     /// Undo the last action of a router
     fn undo_router(&mut self, router: RouterId) -> Result<(), NetworkError> {
         if let Some(r) = self.routers.get_mut(&router) {
@@ -1514,6 +2317,7 @@ impl Network {
                 // Undo the event and push it back to the queue
                 // the event was executed on the to router.
                 self.undo_router(to)?;
+                self.fw_candidates.mark(to, bgp_event.prefix());
                 if update_queue {
                     // get the event id
                     let event_id = self.event_history.len();
@@ -1530,15 +2334,21 @@ impl Network {
             }
             Some((Event::AdvertiseExternalRoute(router, route), None)) => {
                 self.undo_router(router)?;
+                self.fw_candidates.mark(router, route.prefix);
                 // fix known prefixes
                 if !self.external_routers.values().any(|r| r.has_active_route(route.prefix)) {
-                    self.known_prefixes.remove(&route.prefix);
+                    Arc::make_mut(&mut self.known_prefixes).remove(&route.prefix);
                 }
                 Ok(false)
             }
             Some((Event::WithdrawExternalRoute(router, prefix), None)) => {
                 self.undo_router(router)?;
-                self.known_prefixes.insert(prefix);
+                self.fw_candidates.mark(router, prefix);
+                Arc::make_mut(&mut self.known_prefixes).insert(prefix);
+                Ok(false)
+            }
+            Some((event, None)) if event.is_failure_event() => {
+                self.apply_or_undo_failure(&event.reverse_failure().unwrap(), true, 0)?;
                 Ok(false)
             }
             Some(_) => Err(NetworkError::HistoryError("Parent event id is invalid!")),
@@ -1694,13 +2504,13 @@ impl Network {
                 "{} -> {}: BGP Update prefix {}",
                 self.get_router_name(*from)?,
                 self.get_router_name(*to)?,
-                route.prefix.0
+                route.prefix
             ),
             Event::Bgp(from, to, BgpEvent::Withdraw(prefix)) => trace!(
                 "{} -> {}: BGP withdraw prefix {}",
                 self.get_router_name(*from)?,
                 self.get_router_name(*to)?,
-                prefix.0
+                prefix
             ),
             Event::Config(modifier) => trace!("{}", printer::config_modifier(self, modifier)?),
             Event::AdvertiseExternalRoute(source, route) => trace!(
@@ -1711,8 +2521,20 @@ impl Network {
             Event::WithdrawExternalRoute(source, prefix) => trace!(
                 "Router {} withdraws advertisement for prefix {}",
                 self.get_router_name(*source)?,
-                prefix.0
+                prefix
+            ),
+            Event::LinkDown(a, b) => trace!(
+                "Link {} -- {} goes down",
+                self.get_router_name(*a)?,
+                self.get_router_name(*b)?
+            ),
+            Event::LinkUp(a, b) => trace!(
+                "Link {} -- {} comes up",
+                self.get_router_name(*a)?,
+                self.get_router_name(*b)?
             ),
+            Event::RouterDown(r) => trace!("Router {} goes down", self.get_router_name(*r)?),
+            Event::RouterUp(r) => trace!("Router {} comes up", self.get_router_name(*r)?),
         }
         Ok(())
     }
@@ -1790,6 +2612,13 @@ impl Network {
 
 type ConvergenceRepetition = (Vec<Event>, Vec<Network>);
 
+/// Returns `true` if there exists a path from `a` to `b` and back in the IGP topology, only using
+/// links with a finite weight.
+fn igp_connected(net: &IgpNetwork, a: RouterId, b: RouterId) -> bool {
+    let g = EdgeFiltered::from_fn(net, |e| e.weight().is_finite());
+    has_path_connecting(&g, a, b, None) && has_path_connecting(&g, b, a, None)
+}
+
 /// The `PartialEq` implementation checks if two networks are identical. The implementation first
 /// checks "simple" conditions, like the configuration, before checking the state of each individual
 /// router. Use the `Network::weak_eq` function to skip some checks, which can be known beforehand.
@@ -1813,6 +2642,11 @@ impl PartialEq for Network {
             return false;
         }
 
+        // check if the same links and routers are failed
+        if self.failed_links != other.failed_links || self.failed_routers != other.failed_routers {
+            return false;
+        }
+
         // check if the external routers advertise the same prefix
         let external_routers_same_prefixes = self.external_routers.keys().all(|rid| {
             self.external_routers
@@ -1827,3 +2661,134 @@ impl PartialEq for Network {
         self.weak_eq(other)
     }
 }
+
+/// State of the network stored by [`Network::snapshot`]. The devices are shared with the network
+/// and all other snapshots.
+#[derive(Debug)]
+struct NetworkSnapshot {
+    net: Arc<IgpNetwork>,
+    links: Arc<Vec<(RouterId, RouterId)>>,
+    routers: CowMap<RouterId, Router>,
+    external_routers: CowMap<RouterId, ExternalRouter>,
+    known_prefixes: Arc<HashSet<Prefix>>,
+    config: Arc<Config>,
+    queue: EventQueue,
+    event_history: SharedVec<(Event, Option<usize>)>,
+    failed_links: Arc<HashSet<(RouterId, RouterId)>>,
+    failed_routers: Arc<HashSet<RouterId>>,
+}
+
+/// Serializable representation of a [`Network`]. It only contains the information needed to
+/// rebuild the network: all devices (ordered by their `RouterId`), the links, the current
+/// configuration, the failed links and routers, and the routes advertised by external routers.
+/// The state of the routers is not stored, but recomputed while the network is rebuilt.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct NetworkRepr {
+    devices: Vec<DeviceRepr>,
+    links: Vec<(RouterId, RouterId)>,
+    config: Config,
+    stop_after: Option<usize>,
+    #[serde(default)]
+    failed_links: Vec<(RouterId, RouterId)>,
+    #[serde(default)]
+    failed_routers: Vec<RouterId>,
+}
+
+/// Serializable representation of a single device in the network.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+enum DeviceRepr {
+    Internal { id: RouterId, name: String },
+    External { id: RouterId, name: String, as_id: AsId, routes: Vec<BgpRoute> },
+}
+
+/// The network is serialized as its topology, configuration and advertised routes. Neither the
+/// event history, nor the undo stacks are serialized.
+#[cfg(feature = "serde")]
+impl Serialize for Network {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut ids: Vec<RouterId> = self.net.node_indices().collect();
+        ids.sort();
+        let devices = ids
+            .into_iter()
+            .map(|id| match self.get_device(id) {
+                NetworkDevice::InternalRouter(r) => {
+                    DeviceRepr::Internal { id, name: r.name().to_string() }
+                }
+                NetworkDevice::ExternalRouter(r) => DeviceRepr::External {
+                    id,
+                    name: r.name().to_string(),
+                    as_id: r.as_id(),
+                    routes: r.get_advertised_routes().clone(),
+                },
+                NetworkDevice::None => unreachable!(),
+            })
+            .collect();
+        let mut failed_links: Vec<(RouterId, RouterId)> =
+            self.failed_links.iter().copied().collect();
+        failed_links.sort();
+        let mut failed_routers: Vec<RouterId> = self.failed_routers.iter().copied().collect();
+        failed_routers.sort();
+        NetworkRepr {
+            devices,
+            links: self.links.as_ref().clone(),
+            config: self.config.as_ref().clone(),
+            stop_after: self.stop_after,
+            failed_links,
+            failed_routers,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// The network is rebuilt by adding all devices and links, applying the configuration, failing all
+/// links and routers that were down, and finally advertising all routes of the external routers.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Network {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = NetworkRepr::deserialize(deserializer)?;
+        let mut net = Network::new();
+        net.set_msg_limit(repr.stop_after);
+
+        let mut routes: Vec<(RouterId, BgpRoute)> = Vec::new();
+        for device in repr.devices {
+            let (id, new_id) = match device {
+                DeviceRepr::Internal { id, name } => (id, net.add_router(name)),
+                DeviceRepr::External { id, name, as_id, routes: r } => {
+                    routes.extend(r.into_iter().map(|r| (id, r)));
+                    (id, net.add_external_router(name, as_id))
+                }
+            };
+            if id != new_id {
+                return Err(D::Error::custom(format!(
+                    "Routers must have consecutive ids: expected {}, got {}",
+                    new_id.index(),
+                    id.index()
+                )));
+            }
+        }
+
+        for (a, b) in repr.links {
+            if let Some(r) = [a, b].iter().find(|r| net.net.node_weight(**r).is_none()) {
+                return Err(D::Error::custom(NetworkError::DeviceNotFound(*r)));
+            }
+            net.add_link(a, b);
+        }
+
+        net.set_config(&repr.config).map_err(D::Error::custom)?;
+
+        for (a, b) in repr.failed_links {
+            net.fail_link(a, b).map_err(D::Error::custom)?;
+        }
+        for router in repr.failed_routers {
+            net.fail_router(router).map_err(D::Error::custom)?;
+        }
+
+        for (id, route) in routes {
+            net.advertise_external_bgp_route(id, route).map_err(D::Error::custom)?;
+        }
+
+        Ok(net)
+    }
+}
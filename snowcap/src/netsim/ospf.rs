@@ -0,0 +1,306 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! # OSPF Areas
+//!
+//! This module computes the IGP forwarding tables of all routers. If no link is assigned to an
+//! area, the IGP is a single shortest-path computation over the link weights. Otherwise, the
+//! routes are computed like in multi-area OSPF (or IS-IS with level-1 areas and a level-2
+//! backbone):
+//!
+//! 1. Each area computes its shortest paths only over links inside the area. Intra-area routes are
+//!    always preferred over inter-area routes, independent of their cost.
+//! 2. Area border routers (ABRs, routers in the backbone and in at least one other area) summarize
+//!    the routes of their non-backbone areas into the backbone. Backbone routers reach those
+//!    destinations via the ABR with the smallest total cost.
+//! 3. ABRs summarize all routes they know into their non-backbone areas. Routers in those areas
+//!    reach destinations outside of the area via the ABR with the smallest total cost.
+//! 4. In a stub area, routers do not learn the routes towards external routers outside of the area.
+//!    Instead, they use the default route announced by the ABRs with a cost of `1`.
+//!
+//! In all cases, all paths with minimal cost are used (ECMP).
+
+use crate::netsim::config::{Config, ConfigExpr};
+use crate::netsim::{IgpNetwork, LinkWeight, OspfArea, RouterId};
+
+use petgraph::algo::bellman_ford;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// IGP forwarding table of a single router. For each destination, it stores all next hops (the
+/// first one is the primary next hop), and the cost. `None` means the destination is unreachable.
+pub(crate) type IgpForwardingTable = HashMap<RouterId, Option<(Vec<RouterId>, LinkWeight)>>;
+
+/// Cost of the default route announced by an ABR into a stub area.
+const STUB_DEFAULT_COST: LinkWeight = 1.0;
+
+/// Compute the shortest paths from `source` to all other nodes in the graph, keeping all next hops
+/// of equal-cost paths.
+pub(crate) fn shortest_paths(graph: &IgpNetwork, source: RouterId) -> IgpForwardingTable {
+    let mut table = IgpForwardingTable::new();
+    let (path_weights, predecessors) = bellman_ford(graph, source).unwrap();
+    let mut paths: Vec<(RouterId, LinkWeight, Option<RouterId>)> = path_weights
+        .into_iter()
+        .zip(predecessors)
+        .enumerate()
+        .map(|(i, (w, p))| ((i as u32).into(), w, p))
+        .collect();
+    paths.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    for (router, cost, predecessor) in paths {
+        if cost.is_infinite() {
+            table.insert(router, None);
+            continue;
+        }
+        let next_hop = if let Some(predecessor) = predecessor {
+            // the predecessor must already be inserted into the forwarding table, because we sorted the table
+            if predecessor == source {
+                router
+            } else {
+                table
+                    .get(&predecessor)
+                    .unwrap() // first unwrap for get, which returns an option
+                    .as_ref()
+                    .unwrap() // second unwrap to unwrap wether the route exists (it must!)
+                    .0[0]
+            }
+        } else {
+            router
+        };
+        // collect the next hops of all other predecessors on an equal-cost path. Again, they
+        // are already inserted into the forwarding table, because we sorted the table.
+        let mut ecmp_next_hops: Vec<RouterId> = Vec::new();
+        if router != source {
+            for edge in graph.edges_directed(router, Direction::Incoming) {
+                let neighbor = edge.source();
+                if neighbor == source {
+                    if *edge.weight() == cost {
+                        ecmp_next_hops.push(router);
+                    }
+                } else if let Some(Some((hops, c))) = table.get(&neighbor) {
+                    if *c + *edge.weight() == cost {
+                        ecmp_next_hops.extend(hops.iter().copied());
+                    }
+                }
+            }
+        }
+        ecmp_next_hops.retain(|nh| *nh != next_hop);
+        ecmp_next_hops.sort();
+        ecmp_next_hops.dedup();
+        let mut next_hops = vec![next_hop];
+        next_hops.append(&mut ecmp_next_hops);
+        table.insert(router, Some((next_hops, cost)));
+    }
+    table
+}
+
+/// # OSPF Area Configuration
+///
+/// Area assignment of all links and the set of stub areas, as extracted from the network
+/// configuration.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OspfAreas {
+    /// Area of each link, where the key is normalized such that the first router ID is smaller.
+    links: HashMap<(RouterId, RouterId), OspfArea>,
+    /// Set of all stub areas
+    stubs: HashSet<OspfArea>,
+}
+
+impl OspfAreas {
+    /// Extract the area configuration from the network configuration.
+    pub fn from_config(config: &Config) -> Self {
+        let mut areas = Self::default();
+        for expr in config.iter() {
+            match expr {
+                ConfigExpr::IgpLinkArea { source, target, area } => {
+                    areas.links.insert(link_key(*source, *target), *area);
+                }
+                ConfigExpr::OspfStubArea { area } => {
+                    areas.stubs.insert(*area);
+                }
+                _ => {}
+            }
+        }
+        areas
+    }
+
+    /// Returns `true` if all links are in the backbone, in which case the IGP is a single flat
+    /// shortest-path computation.
+    pub fn is_flat(&self) -> bool {
+        self.links.values().all(|a| a.is_backbone())
+    }
+
+    /// Returns the area of the link between `a` and `b`.
+    pub fn link_area(&self, a: RouterId, b: RouterId) -> OspfArea {
+        self.links.get(&link_key(a, b)).copied().unwrap_or(OspfArea::BACKBONE)
+    }
+
+    /// Returns all areas in which the router has at least one link.
+    fn router_areas(&self, graph: &IgpNetwork, router: RouterId) -> BTreeSet<OspfArea> {
+        graph
+            .edges_directed(router, Direction::Outgoing)
+            .chain(graph.edges_directed(router, Direction::Incoming))
+            .map(|e| self.link_area(e.source(), e.target()))
+            .collect()
+    }
+
+    /// Compute the IGP forwarding tables of all `routers`. The graph must contain all routers,
+    /// including the external ones, which are only considered as destinations.
+    pub fn igp_forwarding_tables(
+        &self,
+        graph: &IgpNetwork,
+        routers: &[RouterId],
+    ) -> HashMap<RouterId, IgpForwardingTable> {
+        if self.is_flat() {
+            return routers.iter().map(|r| (*r, shortest_paths(graph, *r))).collect();
+        }
+
+        let destinations: Vec<RouterId> = graph.node_indices().collect();
+        let internal: HashSet<RouterId> = routers.iter().copied().collect();
+        let areas_of: HashMap<RouterId, BTreeSet<OspfArea>> =
+            routers.iter().map(|r| (*r, self.router_areas(graph, *r))).collect();
+        let all_areas: BTreeSet<OspfArea> = areas_of.values().flatten().copied().collect();
+
+        // intra-area shortest paths of all routers in each area
+        let mut intra: HashMap<OspfArea, HashMap<RouterId, IgpForwardingTable>> = HashMap::new();
+        for area in all_areas.iter() {
+            let area_graph = graph.map(
+                |_, n| *n,
+                |e, w| {
+                    let (a, b) = graph.edge_endpoints(e).unwrap();
+                    if self.link_area(a, b) == *area {
+                        *w
+                    } else {
+                        LinkWeight::INFINITY
+                    }
+                },
+            );
+            intra.insert(
+                *area,
+                routers
+                    .iter()
+                    .filter(|r| areas_of[*r].contains(area))
+                    .map(|r| (*r, shortest_paths(&area_graph, *r)))
+                    .collect(),
+            );
+        }
+        let intra_route = |area: &OspfArea, r: RouterId, dst: RouterId| -> Option<Route> {
+            intra.get(area)?.get(&r)?.get(&dst)?.clone()
+        };
+
+        // area border routers, sorted by their router ID
+        let abrs: Vec<RouterId> = {
+            let mut abrs: Vec<RouterId> = routers
+                .iter()
+                .filter(|r| areas_of[*r].contains(&OspfArea::BACKBONE) && areas_of[*r].len() > 1)
+                .copied()
+                .collect();
+            abrs.sort();
+            abrs
+        };
+
+        // compute the backbone routers first, since the other areas depend on their tables.
+        let mut order: Vec<RouterId> = routers.to_vec();
+        order.sort_by_key(|r| (!areas_of[r].contains(&OspfArea::BACKBONE), *r));
+
+        let mut tables: HashMap<RouterId, IgpForwardingTable> = HashMap::new();
+        for r in order {
+            let areas = &areas_of[&r];
+            if areas.is_empty() {
+                tables.insert(r, shortest_paths(graph, r));
+                continue;
+            }
+            let mut table = IgpForwardingTable::new();
+            for dst in destinations.iter().copied() {
+                // intra-area routes are always preferred
+                let mut candidates: Vec<Route> =
+                    areas.iter().filter_map(|a| intra_route(a, r, dst)).collect();
+
+                if candidates.is_empty() {
+                    // ABRs only consider the summaries received over the backbone.
+                    let inter_areas: Vec<OspfArea> = if areas.contains(&OspfArea::BACKBONE) {
+                        vec![OspfArea::BACKBONE]
+                    } else {
+                        areas.iter().copied().collect()
+                    };
+                    for area in inter_areas.iter() {
+                        for abr in abrs.iter().copied().filter(|b| *b != r) {
+                            let (hops, cost) = match intra_route(area, r, abr) {
+                                Some(x) => x,
+                                None => continue,
+                            };
+                            let summary_cost = if area.is_backbone() {
+                                // summary of the intra-area routes in the ABR's other areas
+                                areas_of[&abr]
+                                    .iter()
+                                    .filter(|a| !a.is_backbone())
+                                    .filter_map(|a| intra_route(a, abr, dst))
+                                    .map(|(_, c)| c)
+                                    .fold(LinkWeight::INFINITY, LinkWeight::min)
+                            } else if self.stubs.contains(area) && !internal.contains(&dst) {
+                                // default route of the stub area
+                                STUB_DEFAULT_COST
+                            } else {
+                                // summary of all routes known by the ABR
+                                match tables.get(&abr).and_then(|t| t.get(&dst)) {
+                                    Some(Some((_, c))) => *c,
+                                    _ => continue,
+                                }
+                            };
+                            if summary_cost.is_finite() {
+                                candidates.push((hops, cost + summary_cost));
+                            }
+                        }
+                    }
+                }
+                table.insert(dst, best_route(candidates));
+            }
+            tables.insert(r, table);
+        }
+        tables
+    }
+}
+
+/// Next hops and cost of a single route
+type Route = (Vec<RouterId>, LinkWeight);
+
+/// Select the route with the smallest cost, and combine the next hops of all routes with equal
+/// cost. The primary next hop of the first best route stays in front.
+fn best_route(candidates: Vec<Route>) -> Option<Route> {
+    let cost = candidates.iter().map(|(_, c)| *c).fold(LinkWeight::INFINITY, LinkWeight::min);
+    if cost.is_infinite() {
+        return None;
+    }
+    let mut best = candidates.into_iter().filter(|(_, c)| *c == cost);
+    let (mut next_hops, _) = best.next()?;
+    let primary = next_hops[0];
+    next_hops.extend(best.flat_map(|(hops, _)| hops));
+    next_hops.retain(|nh| *nh != primary);
+    next_hops.sort();
+    next_hops.dedup();
+    next_hops.insert(0, primary);
+    Some((next_hops, cost))
+}
+
+/// Normalize the key of a link, such that the first router ID is smaller.
+pub(crate) fn link_key(a: RouterId, b: RouterId) -> (RouterId, RouterId) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
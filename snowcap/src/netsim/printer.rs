@@ -19,7 +19,7 @@
 //! Module containing helper functions to get formatted strings and print information about the
 //! network.
 
-use crate::netsim::bgp::{BgpEvent, BgpRibEntry, BgpRoute};
+use crate::netsim::bgp::{BgpEvent, BgpRibEntry, BgpRoute, Origin};
 use crate::netsim::config::{Config, ConfigExpr, ConfigModifier, ConfigPatch};
 use crate::netsim::event::Event;
 use crate::netsim::network::Network;
@@ -60,9 +60,9 @@ pub fn bgp_table(
 /// that all router names are inserted.
 pub fn bgp_entry(net: &Network, entry: &BgpRibEntry) -> Result<String, NetworkError> {
     Ok(format!(
-        "prefix: {p}, as_path: {path:?}, local_pref: {lp}, MED: {med}, IGP Cost: {cost}, next_hop: {nh}, from: {next}",
-        p = entry.route.prefix.0,
-        path = entry.route.as_path.iter().map(|x| x.0).collect::<Vec<u32>>(),
+        "prefix: {p}, as_path: {path}, local_pref: {lp}, MED: {med}, IGP Cost: {cost}, next_hop: {nh}, from: {next}",
+        p = entry.route.prefix,
+        path = as_path(&entry.route),
         lp = entry.route.local_pref.unwrap_or(100),
         med = entry.route.med.unwrap_or(0),
         cost = entry.igp_cost.unwrap_or(0.0),
@@ -74,9 +74,9 @@ pub fn bgp_entry(net: &Network, entry: &BgpRibEntry) -> Result<String, NetworkEr
 /// Returns a formatted string for a given BGP route.
 pub fn bgp_route(net: &Network, route: &BgpRoute) -> Result<String, NetworkError> {
     let mut result = format!(
-        "prefix: {}, AsPath: {:?}, next hop: {}",
-        route.prefix.0,
-        route.as_path.iter().map(|x| x.0).collect::<Vec<_>>(),
+        "prefix: {}, AsPath: {}, next hop: {}",
+        route.prefix,
+        as_path(route),
         net.get_router_name(route.next_hop)?
     );
     if let Some(local_pref) = route.local_pref {
@@ -85,12 +85,46 @@ pub fn bgp_route(net: &Network, route: &BgpRoute) -> Result<String, NetworkError
     if let Some(med) = route.med {
         result.push_str(&format!(", MED: {}", med))
     }
-    if let Some(community) = route.community {
-        result.push_str(&format!(", community: {}", community))
+    if route.origin != Origin::Igp {
+        result.push_str(&format!(", origin: {}", route.origin))
+    }
+    if !route.communities.is_empty() {
+        result.push_str(&format!(", communities: {:?}", route.communities))
+    }
+    if !route.large_communities.is_empty() {
+        result.push_str(&format!(
+            ", large communities: [{}]",
+            route.large_communities.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(", ")
+        ))
+    }
+    if let Some(originator_id) = route.originator_id {
+        result.push_str(&format!(", originator: {}", net.get_router_name(originator_id)?))
+    }
+    if !route.cluster_list.is_empty() {
+        result.push_str(&format!(
+            ", cluster list: [{}]",
+            route
+                .cluster_list
+                .iter()
+                .map(|c| net.get_router_name(*c))
+                .collect::<Result<Vec<_>, _>>()?
+                .join(", ")
+        ))
     }
     Ok(result)
 }
 
+/// Returns the AS path of the route, followed by the AS_SET in curly braces (if it is not empty).
+fn as_path(route: &BgpRoute) -> String {
+    let path = format!("{:?}", route.as_path.iter().map(|x| x.0).collect::<Vec<u32>>());
+    if route.as_set.is_empty() {
+        path
+    } else {
+        let set = route.as_set.iter().map(|x| x.0.to_string()).collect::<Vec<_>>();
+        format!("{} {{{}}}", path, set.join(", "))
+    }
+}
+
 /// Return a formatted string for a given event
 pub fn event(net: &Network, event: &Event) -> Result<String, NetworkError> {
     Ok(match event {
@@ -104,15 +138,23 @@ pub fn event(net: &Network, event: &Event) -> Result<String, NetworkError> {
             "BGP Event: {} -> {}: Withdraw prefix {}",
             net.get_router_name(*from)?,
             net.get_router_name(*to)?,
-            prefix.0
+            prefix
         ),
         Event::Config(modifier) => format!("Apply Config: {}", config_modifier(net, modifier)?,),
         Event::AdvertiseExternalRoute(r, route) => {
             format!("{} advertisees route [{}]", net.get_router_name(*r)?, bgp_route(net, route)?)
         }
         Event::WithdrawExternalRoute(r, prefix) => {
-            format!("{} withdraws route for prefix {}", net.get_router_name(*r)?, prefix.0)
+            format!("{} withdraws route for prefix {}", net.get_router_name(*r)?, prefix)
+        }
+        Event::LinkDown(a, b) => {
+            format!("Link down: {} -- {}", net.get_router_name(*a)?, net.get_router_name(*b)?)
+        }
+        Event::LinkUp(a, b) => {
+            format!("Link up: {} -- {}", net.get_router_name(*a)?, net.get_router_name(*b)?)
         }
+        Event::RouterDown(r) => format!("Router down: {}", net.get_router_name(*r)?),
+        Event::RouterUp(r) => format!("Router up: {}", net.get_router_name(*r)?),
     })
 }
 
@@ -157,13 +199,44 @@ pub fn config_expr(net: &Network, expr: &ConfigExpr) -> Result<String, NetworkEr
         ConfigExpr::StaticRoute { router, prefix, target } => format!(
             "Static Route: {}: Prefix {} via {}",
             net.get_router_name(*router)?,
-            prefix.0,
+            prefix,
             net.get_router_name(*target)?,
         ),
         &ConfigExpr::AccessControl { .. } => {
             // TODO
             String::from("testing")
         }
+        ConfigExpr::IgpLinkArea { source, target, area } => format!(
+            "IGP Link Area: {} -- {}: area {}",
+            net.get_router_name(*source)?,
+            net.get_router_name(*target)?,
+            area
+        ),
+        ConfigExpr::OspfStubArea { area } => format!("OSPF Stub Area: area {}", area),
+        ConfigExpr::BgpNetwork { router, prefix } => {
+            format!("BGP Network: {}: Prefix {}", net.get_router_name(*router)?, prefix)
+        }
+        ConfigExpr::BgpRedistribute { router, source, route_maps } => format!(
+            "BGP Redistribute {}: {}: [{}]",
+            source,
+            net.get_router_name(*router)?,
+            route_maps.iter().map(|m| route_map(net, m)).collect::<Result<Vec<_>, _>>()?.join(", ")
+        ),
+        ConfigExpr::BgpAggregate { router, prefix, summary_only, as_set } => format!(
+            "BGP Aggregate: {}: Prefix {}{}{}",
+            net.get_router_name(*router)?,
+            prefix,
+            if *summary_only { " summary-only" } else { "" },
+            if *as_set { " as-set" } else { "" },
+        ),
+        ConfigExpr::BgpDecision { router, config } => {
+            format!("BGP Decision: {}: {:?}", net.get_router_name(*router)?, config)
+        }
+        ConfigExpr::BgpClusterId { router, cluster_id } => format!(
+            "BGP Cluster ID: {}: {}",
+            net.get_router_name(*router)?,
+            net.get_router_name(*cluster_id)?
+        ),
     })
 }
 
@@ -221,11 +294,14 @@ pub fn print_config_patch(net: &Network, patch: &ConfigPatch) -> Result<(), Netw
 fn route_map_match(net: &Network, map_match: &RouteMapMatch) -> Result<String, NetworkError> {
     Ok(match map_match {
         RouteMapMatch::Neighbor(n) => format!("Neighbor {}", net.get_router_name(*n)?),
-        RouteMapMatch::Prefix(c) => format!("Prefix == {}", c),
+        RouteMapMatch::Prefix(c) => format!("Prefix {}", c),
         RouteMapMatch::AsPath(c) => format!("{}", c),
         RouteMapMatch::NextHop(nh) => format!("NextHop == {}", net.get_router_name(*nh)?),
         RouteMapMatch::Community(Some(c)) => format!("Community {}", c),
         RouteMapMatch::Community(None) => "Community empty".to_string(),
+        RouteMapMatch::LargeCommunity(Some(c)) => format!("LargeCommunity == {}", c),
+        RouteMapMatch::LargeCommunity(None) => "LargeCommunity empty".to_string(),
+        RouteMapMatch::Origin(o) => format!("Origin == {}", o),
     })
 }
 
@@ -239,5 +315,13 @@ fn route_map_set(net: &Network, map_set: &RouteMapSet) -> Result<String, Network
         RouteMapSet::IgpCost(w) => format!("IgpCost = {:.2}", w),
         RouteMapSet::Community(Some(c)) => format!("Community = {}", c),
         RouteMapSet::Community(None) => "clear Community".to_string(),
+        RouteMapSet::AddCommunity(c) => format!("Community += {}", c),
+        RouteMapSet::DelCommunity(c) => format!("Community -= {}", c),
+        RouteMapSet::LargeCommunity(Some(c)) => format!("LargeCommunity = {}", c),
+        RouteMapSet::LargeCommunity(None) => "clear LargeCommunity".to_string(),
+        RouteMapSet::AddLargeCommunity(c) => format!("LargeCommunity += {}", c),
+        RouteMapSet::DelLargeCommunity(c) => format!("LargeCommunity -= {}", c),
+        RouteMapSet::AsPathPrepend(as_id, n) => format!("AsPath prepend {} x{}", as_id.0, n),
+        RouteMapSet::Origin(o) => format!("Origin = {}", o),
     })
 }
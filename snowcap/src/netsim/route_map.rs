@@ -19,8 +19,10 @@
 //!
 //! This module contains the necessary structures to build route maps for internal BGP routers.
 
-use crate::netsim::bgp::BgpRibEntry;
+use crate::netsim::bgp::{BgpRibEntry, BgpRoute, LargeCommunity, Origin};
 use crate::netsim::{AsId, LinkWeight, Prefix, RouterId};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// # Main RouteMap structure
@@ -31,7 +33,7 @@ use std::fmt;
 /// # use snowcap::netsim::route_map::*;
 /// # use snowcap::netsim::{RouterId, Prefix};
 /// # let neighbor: RouterId = 0.into();
-/// # let prefix: Prefix = Prefix(0);
+/// # let prefix: Prefix = Prefix::from(0);
 /// let map = RouteMapBuilder::new()
 ///     .order(10)
 ///     .allow()
@@ -42,6 +44,7 @@ use std::fmt;
 ///     .build();
 /// ```
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RouteMap {
     /// In which order should the route maps be checked. Lower values mean that they are checked
     /// earlier.
@@ -129,7 +132,7 @@ impl RouteMap {
 /// # use snowcap::netsim::route_map::*;
 /// # use snowcap::netsim::{RouterId, Prefix};
 /// # let neighbor: RouterId = 0.into();
-/// # let prefix: Prefix = Prefix(0);
+/// # let prefix: Prefix = Prefix::from(0);
 /// let map = RouteMapBuilder::new()
 ///     .order(10)
 ///     .allow()
@@ -193,13 +196,14 @@ impl RouteMapBuilder {
 
     /// Add a match condition to the Route-Map, matching on the prefix with exact value
     pub fn match_prefix(&mut self, prefix: Prefix) -> &mut Self {
-        self.conds.push(RouteMapMatch::Prefix(RouteMapMatchClause::Equal(prefix)));
+        self.conds.push(RouteMapMatch::Prefix(RouteMapMatchPrefix::Equal(prefix)));
         self
     }
 
-    /// Add a match condition to the Route-Map, matching on the prefix with an inclusive range
-    pub fn match_prefix_range(&mut self, from: Prefix, to: Prefix) -> &mut Self {
-        self.conds.push(RouteMapMatch::Prefix(RouteMapMatchClause::Range(from, to)));
+    /// Add a match condition to the Route-Map, matching on all prefixes contained in `range`
+    /// (including `range` itself).
+    pub fn match_prefix_range(&mut self, range: Prefix) -> &mut Self {
+        self.conds.push(RouteMapMatch::Prefix(RouteMapMatchPrefix::Range(range)));
         self
     }
 
@@ -232,7 +236,7 @@ impl RouteMapBuilder {
         self
     }
 
-    /// Add a match condition to the Route-Map, matching on the community with exact value
+    /// Add a match condition to the Route-Map, matching on routes carrying the given community
     pub fn match_community(&mut self, community: u32) -> &mut Self {
         self.conds.push(RouteMapMatch::Community(Some(RouteMapMatchClause::Equal(community))));
         self
@@ -244,12 +248,32 @@ impl RouteMapBuilder {
         self
     }
 
-    /// Add a match condition to the Route-Map, matching on the community with an inclusive range
+    /// Add a match condition to the Route-Map, matching on routes carrying any community within an
+    /// inclusive range
     pub fn match_community_range(&mut self, from: u32, to: u32) -> &mut Self {
         self.conds.push(RouteMapMatch::Community(Some(RouteMapMatchClause::Range(from, to))));
         self
     }
 
+    /// Add a match condition to the Route-Map, matching on routes carrying the given large
+    /// community
+    pub fn match_large_community(&mut self, community: LargeCommunity) -> &mut Self {
+        self.conds.push(RouteMapMatch::LargeCommunity(Some(community)));
+        self
+    }
+
+    /// Add a match condition to the Route-Map, matching on routes without large communities
+    pub fn match_large_community_empty(&mut self) -> &mut Self {
+        self.conds.push(RouteMapMatch::LargeCommunity(None));
+        self
+    }
+
+    /// Add a match condition to the Route-Map, matching on the origin
+    pub fn match_origin(&mut self, origin: Origin) -> &mut Self {
+        self.conds.push(RouteMapMatch::Origin(origin));
+        self
+    }
+
     /// Add a set expression to the Route-Map.
     pub fn add_set(&mut self, set: RouteMapSet) -> &mut Self {
         self.set.push(set);
@@ -292,18 +316,66 @@ impl RouteMapBuilder {
         self
     }
 
-    /// Add a set expression, overwriting the Community
+    /// Add a set expression, replacing all communities by the given one
     pub fn set_community(&mut self, community: u32) -> &mut Self {
         self.set.push(RouteMapSet::Community(Some(community)));
         self
     }
 
-    /// Add a set expression, resetting the Community
+    /// Add a set expression, removing all communities
     pub fn reset_community(&mut self) -> &mut Self {
         self.set.push(RouteMapSet::Community(None));
         self
     }
 
+    /// Add a set expression, adding a community to the existing ones
+    pub fn add_community(&mut self, community: u32) -> &mut Self {
+        self.set.push(RouteMapSet::AddCommunity(community));
+        self
+    }
+
+    /// Add a set expression, deleting a single community
+    pub fn del_community(&mut self, community: u32) -> &mut Self {
+        self.set.push(RouteMapSet::DelCommunity(community));
+        self
+    }
+
+    /// Add a set expression, replacing all large communities by the given one
+    pub fn set_large_community(&mut self, community: LargeCommunity) -> &mut Self {
+        self.set.push(RouteMapSet::LargeCommunity(Some(community)));
+        self
+    }
+
+    /// Add a set expression, removing all large communities
+    pub fn reset_large_community(&mut self) -> &mut Self {
+        self.set.push(RouteMapSet::LargeCommunity(None));
+        self
+    }
+
+    /// Add a set expression, adding a large community to the existing ones
+    pub fn add_large_community(&mut self, community: LargeCommunity) -> &mut Self {
+        self.set.push(RouteMapSet::AddLargeCommunity(community));
+        self
+    }
+
+    /// Add a set expression, deleting a single large community
+    pub fn del_large_community(&mut self, community: LargeCommunity) -> &mut Self {
+        self.set.push(RouteMapSet::DelLargeCommunity(community));
+        self
+    }
+
+    /// Add a set expression, prepending `as_id` `count` times to the AS path
+    pub fn prepend_as_path(&mut self, as_id: AsId, count: usize) -> &mut Self {
+        self.set.push(RouteMapSet::AsPathPrepend(as_id, count));
+        self
+    }
+
+    /// Add a set expression, overwriting the origin
+    pub fn set_origin(&mut self, origin: Origin) -> &mut Self {
+        self.set.push(RouteMapSet::Origin(origin));
+        self
+    }
+
     /// Build the route-map.
     ///
     /// # Panics
@@ -327,6 +399,7 @@ impl RouteMapBuilder {
 
 /// State of a route map, which can either be allow or deny
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteMapState {
     /// Set the state to allow
     Allow,
@@ -348,17 +421,24 @@ impl RouteMapState {
 
 /// Match statement of the route map. Can be combined to generate complex match statements
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteMapMatch {
     /// Matches on the neighbor (exact value only)
     Neighbor(RouterId),
-    /// Matches on the Prefix (exact value or a range)
-    Prefix(RouteMapMatchClause<Prefix>),
+    /// Matches on the Prefix (exact value or all prefixes contained in a range)
+    Prefix(RouteMapMatchPrefix),
     /// Matches on the As Path (either if it contains an as, or on the length of the path)
     AsPath(RouteMapMatchAsPath),
     /// Matches on the Next Hop (exact value)
     NextHop(RouterId),
-    /// Matches on the community (either not set, or set and matches a value or a range)
+    /// Matches on the communities (either no community is set, or any community matches a value
+    /// or a range)
     Community(Option<RouteMapMatchClause<u32>>),
+    /// Matches on the large communities (either no large community is set, or the route carries
+    /// the given large community)
+    LargeCommunity(Option<LargeCommunity>),
+    /// Matches on the origin (exact value)
+    Origin(Origin),
 }
 
 impl RouteMapMatch {
@@ -367,18 +447,22 @@ impl RouteMapMatch {
         match self {
             Self::Neighbor(r) => entry.to_id.unwrap_or(entry.from_id) == *r,
             Self::Prefix(clause) => clause.matches(&entry.route.prefix),
-            Self::AsPath(clause) => clause.matches(&entry.route.as_path),
+            Self::AsPath(clause) => clause.matches(&entry.route),
             Self::NextHop(nh) => entry.route.next_hop == *nh,
             Self::Community(Some(clause)) => {
-                entry.route.community.as_ref().map(|c| clause.matches(c)).unwrap_or(false)
+                entry.route.communities.iter().any(|c| clause.matches(c))
             }
-            Self::Community(None) => entry.route.community.is_none(),
+            Self::Community(None) => entry.route.communities.is_empty(),
+            Self::LargeCommunity(Some(c)) => entry.route.large_communities.contains(c),
+            Self::LargeCommunity(None) => entry.route.large_communities.is_empty(),
+            Self::Origin(o) => entry.route.origin == *o,
         }
     }
 }
 
 /// Generic RouteMapMatchClause to match on all, a range or on a specific element
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteMapMatchClause<T> {
     /// Matches a range of values (inclusive)
     Range(T, T),
@@ -417,7 +501,7 @@ where
     }
 }
 
-impl fmt::Display for RouteMapMatchClause<Prefix> {
+impl fmt::Display for RouteMapMatchClause<AsId> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             RouteMapMatchClause::Range(a, b) => f.write_fmt(format_args!("in ({}..{})", a.0, b.0)),
@@ -429,20 +513,39 @@ impl fmt::Display for RouteMapMatchClause<Prefix> {
     }
 }
 
-impl fmt::Display for RouteMapMatchClause<AsId> {
+/// Clause to match on the prefix
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RouteMapMatchPrefix {
+    /// Matches the exact prefix
+    Equal(Prefix),
+    /// Matches every prefix that is contained in the given prefix (e.g., `10.0.0.0/8` matches
+    /// `10.0.0.0/8` and `10.1.0.0/16`, but not `0.0.0.0/0`)
+    Range(Prefix),
+}
+
+impl RouteMapMatchPrefix {
+    /// Returns true if the prefix matches the clause
+    pub fn matches(&self, prefix: &Prefix) -> bool {
+        match self {
+            Self::Equal(p) => p == prefix,
+            Self::Range(range) => range.contains(prefix),
+        }
+    }
+}
+
+impl fmt::Display for RouteMapMatchPrefix {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            RouteMapMatchClause::Range(a, b) => f.write_fmt(format_args!("in ({}..{})", a.0, b.0)),
-            RouteMapMatchClause::RangeExclusive(a, b) => {
-                f.write_fmt(format_args!("in ({}..{}])", a.0, b.0))
-            }
-            RouteMapMatchClause::Equal(a) => f.write_fmt(format_args!("== {}", a.0)),
+            RouteMapMatchPrefix::Equal(p) => f.write_fmt(format_args!("== {}", p)),
+            RouteMapMatchPrefix::Range(p) => f.write_fmt(format_args!("in {}", p)),
         }
     }
 }
 
 /// Clause to match on the as path
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteMapMatchAsPath {
     /// Contains a specific AsId
     Contains(AsId),
@@ -451,11 +554,12 @@ pub enum RouteMapMatchAsPath {
 }
 
 impl RouteMapMatchAsPath {
-    /// Returns true if the value matches the clause
-    pub fn matches(&self, path: &[AsId]) -> bool {
+    /// Returns true if the AS path of the route matches the clause. Both the AS sequence and the
+    /// AS_SET are searched for the AS, and the AS_SET counts as a single AS for the length.
+    pub fn matches(&self, route: &BgpRoute) -> bool {
         match self {
-            Self::Contains(as_id) => path.contains(&as_id),
-            Self::Length(clause) => clause.matches(&path.len()),
+            Self::Contains(as_id) => route.as_path.contains(as_id) || route.as_set.contains(as_id),
+            Self::Length(clause) => clause.matches(&route.as_path_len()),
         }
     }
 }
@@ -473,6 +577,7 @@ impl fmt::Display for RouteMapMatchAsPath {
 
 /// Set action, if a route map matches
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteMapSet {
     /// overwrite the next hop
     NextHop(RouterId),
@@ -482,8 +587,22 @@ pub enum RouteMapSet {
     Med(Option<u32>),
     /// overwrite the distance attribute (IGP weight). This does not affect peers.
     IgpCost(LinkWeight),
-    /// overwrite the community, (None means remove the field from the route)
+    /// replace all communities by the given one (None means remove all communities)
     Community(Option<u32>),
+    /// add a community to the set of communities
+    AddCommunity(u32),
+    /// remove a community from the set of communities
+    DelCommunity(u32),
+    /// replace all large communities by the given one (None means remove all large communities)
+    LargeCommunity(Option<LargeCommunity>),
+    /// add a large community to the set of large communities
+    AddLargeCommunity(LargeCommunity),
+    /// remove a large community from the set of large communities
+    DelLargeCommunity(LargeCommunity),
+    /// prepend the AS the given number of times to the AS path
+    AsPathPrepend(AsId, usize),
+    /// overwrite the origin
+    Origin(Origin),
 }
 
 impl RouteMapSet {
@@ -498,13 +617,31 @@ impl RouteMapSet {
             Self::LocalPref(lp) => entry.route.local_pref = Some(lp.unwrap_or(100)),
             Self::Med(med) => entry.route.med = Some(med.unwrap_or(0)),
             Self::IgpCost(w) => entry.igp_cost = Some(*w),
-            Self::Community(c) => entry.route.community = *c,
+            Self::Community(c) => entry.route.communities = c.iter().copied().collect(),
+            Self::AddCommunity(c) => {
+                entry.route.communities.insert(*c);
+            }
+            Self::DelCommunity(c) => {
+                entry.route.communities.remove(c);
+            }
+            Self::LargeCommunity(c) => entry.route.large_communities = c.iter().copied().collect(),
+            Self::AddLargeCommunity(c) => {
+                entry.route.large_communities.insert(*c);
+            }
+            Self::DelLargeCommunity(c) => {
+                entry.route.large_communities.remove(c);
+            }
+            Self::AsPathPrepend(as_id, count) => {
+                entry.route.as_path.splice(0..0, vec![*as_id; *count]);
+            }
+            Self::Origin(o) => entry.route.origin = *o,
         }
     }
 }
 
 /// Direction of the Route Map
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RouteMapDirection {
     /// Incoming Route Map
     Incoming,
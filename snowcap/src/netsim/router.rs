@@ -17,15 +17,19 @@
 
 //! Module defining an internal router with BGP functionality.
 
-use crate::netsim::bgp::{BgpEvent, BgpRibEntry, BgpRoute, BgpSessionType};
+use crate::netsim::bgp::{
+    BgpDecisionConfig, BgpEvent, BgpRibEntry, BgpRoute, BgpSessionType, Origin,
+    RedistributionSource,
+};
+use crate::netsim::acl::{AccessList, AclDirection};
 use crate::netsim::route_map::RouteMap;
-use crate::netsim::types::{IgpNetwork, ACL, Destination};
+use crate::netsim::snapshot::{CowMap, SharedVec, SnapshotClone};
+use crate::netsim::types::{IgpNetwork, Destination};
 use crate::netsim::{AsId, DeviceError, LinkWeight, Prefix, RouterId};
+use crate::netsim::ospf::{shortest_paths, IgpForwardingTable};
 use crate::netsim::{Event, EventQueue};
 use log::*;
-use petgraph::algo::bellman_ford;
-use std::collections::{hash_map::Iter, HashMap, HashSet};
-use std::iter::FromIterator;
+use std::collections::{hash_map::Iter, BTreeSet, HashMap, HashSet};
 use std::ops::RangeBounds;
 
 /// Bgp Router
@@ -37,37 +41,44 @@ pub struct Router {
     router_id: RouterId,
     /// AS Id of the router
     as_id: AsId,
-    /// forwarding table for IGP messages
-    pub(crate) igp_forwarding_table: HashMap<RouterId, Option<(RouterId, LinkWeight)>>,
+    /// forwarding table for IGP messages, containing all equal-cost next hops
+    pub(crate) igp_forwarding_table: HashMap<RouterId, Option<(Vec<RouterId>, LinkWeight)>>,
     /// Static Routes for Prefixes
     pub(crate) static_routes: HashMap<Prefix, RouterId>,
     /// hashmap of all bgp sessions
     bgp_sessions: HashMap<RouterId, BgpSessionType>,
     /// Table containing all received entries. It is represented as a hashmap, mapping the prefixes
     /// to another hashmap, which maps the received router id to the entry. This way, we can store
-    /// one entry for every prefix and every session.
-    bgp_rib_in: HashMap<Prefix, HashMap<RouterId, BgpRibEntry>>,
+    /// one entry for every prefix and every session. The entries of each prefix are shared with
+    /// the snapshots of the network (see [`CowMap`]).
+    bgp_rib_in: CowMap<Prefix, HashMap<RouterId, BgpRibEntry>>,
     /// Table containing all selected best routes. It is represented as a hashmap, mapping the
     /// prefixes to the table entry
-    bgp_rib: HashMap<Prefix, BgpRibEntry>,
+    bgp_rib: CowMap<Prefix, BgpRibEntry>,
     /// Table containing all exported routes, represented as a hashmap mapping the neighboring
     /// RouterId (of a BGP session) to the table entries.
-    bgp_rib_out: HashMap<Prefix, HashMap<RouterId, BgpRibEntry>>,
+    bgp_rib_out: CowMap<Prefix, HashMap<RouterId, BgpRibEntry>>,
     /// Set of known bgp prefixes
     bgp_known_prefixes: HashSet<Prefix>,
     /// BGP Route-Maps for Input
     bgp_route_maps_in: Vec<RouteMap>,
     /// BGP Route-Maps for Output
     bgp_route_maps_out: Vec<RouteMap>,
+    /// Configuration of the BGP decision process
+    bgp_decision: BgpDecisionConfig,
+    /// Cluster ID used for route reflection. If not set, the router ID is used.
+    bgp_cluster_id: Option<RouterId>,
+    /// Prefixes announced with a network statement
+    bgp_networks: HashSet<Prefix>,
+    /// Sources of redistributed routes, together with the route-maps (sorted by their order)
+    bgp_redistribution: HashMap<RedistributionSource, Vec<RouteMap>>,
+    /// Aggregates created by the router
+    bgp_aggregates: HashMap<Prefix, AggregateConfig>,
     /// Stack to undo action from event mesages. Each event processed will push a new vector onto
     /// the stack, containing all actions to perform in order to undo this event.
-    undo_stack: Vec<Vec<UndoAction>>,
-    /// Devices accepted access (default *)
-    // probably should use a data structure like (ACL, Vec<>)
-    // default on ACL::Deny
-    acl_accept: Vec<RouterId>,
-    /// Device denied access (default None)
-    acl_deny: Vec<RouterId>,
+    undo_stack: SharedVec<Vec<UndoAction>>,
+    /// Access lists applied on the interfaces, identified by the neighbor and the direction
+    acls: HashMap<(RouterId, AclDirection), AccessList>,
 }
 
 impl Clone for Router {
@@ -85,13 +96,29 @@ impl Clone for Router {
             bgp_known_prefixes: self.bgp_known_prefixes.clone(),
             bgp_route_maps_in: self.bgp_route_maps_in.clone(),
             bgp_route_maps_out: self.bgp_route_maps_out.clone(),
-            undo_stack: Vec::new(),
-            acl_accept: self.acl_accept.clone(),
-            acl_deny: self.acl_deny.clone(),
+            bgp_decision: self.bgp_decision,
+            bgp_cluster_id: self.bgp_cluster_id,
+            bgp_networks: self.bgp_networks.clone(),
+            bgp_redistribution: self.bgp_redistribution.clone(),
+            bgp_aggregates: self.bgp_aggregates.clone(),
+            undo_stack: SharedVec::new(),
+            acls: self.acls.clone(),
         }
     }
 }
 
+impl SnapshotClone for Router {
+    fn snapshot_clone(&self) -> Self {
+        Self { undo_stack: self.undo_stack.clone(), ..self.clone() }
+    }
+}
+
+impl SnapshotClone for BgpRibEntry {
+    fn snapshot_clone(&self) -> Self {
+        self.clone()
+    }
+}
+
 impl Router {
 	// creates a new router object
     pub(crate) fn new(name: String, router_id: RouterId, as_id: AsId) -> Router {
@@ -102,15 +129,19 @@ impl Router {
             igp_forwarding_table: HashMap::new(),
             static_routes: HashMap::new(),
             bgp_sessions: HashMap::new(),
-            bgp_rib_in: HashMap::new(),
-            bgp_rib: HashMap::new(),
-            bgp_rib_out: HashMap::new(),
+            bgp_rib_in: CowMap::new(),
+            bgp_rib: CowMap::new(),
+            bgp_rib_out: CowMap::new(),
             bgp_known_prefixes: HashSet::new(),
             bgp_route_maps_in: Vec::new(),
             bgp_route_maps_out: Vec::new(),
-            undo_stack: Vec::new(),
-            acl_accept: Vec::new(),
-            acl_deny: Vec::new(),
+            bgp_decision: BgpDecisionConfig::default(),
+            bgp_cluster_id: None,
+            bgp_networks: HashSet::new(),
+            bgp_redistribution: HashMap::new(),
+            bgp_aggregates: HashMap::new(),
+            undo_stack: SharedVec::new(),
+            acls: HashMap::new(),
         }
     }
 
@@ -130,21 +161,21 @@ impl Router {
     }
 
     /// Returns the IGP Forwarding table. The table maps the ID of every router in the network to
-    /// a tuple `(next_hop, cost)` of the next hop on the path and the cost to reach the
-    /// destination.
-    pub fn get_igp_fw_table(&self) -> &HashMap<RouterId, Option<(RouterId, LinkWeight)>> {
+    /// a tuple `(next_hops, cost)` of all equal-cost next hops on the shortest paths and the cost
+    /// to reach the destination. The first next hop is the one used when only a single path is
+    /// considered, all others are sorted by their ID.
+    pub fn get_igp_fw_table(&self) -> &HashMap<RouterId, Option<(Vec<RouterId>, LinkWeight)>> {
         &self.igp_forwarding_table
     }
 
-    /// Get ACL rule of the router based on the input (either accept or deny)
-    pub fn get_acl(&self) -> Option<(ACL, HashSet<RouterId>)> {
-        if self.acl_accept.len() > 0 {
-            // router uses accept mode
-            Some((ACL::Accept, HashSet::from_iter(self.acl_accept.iter().cloned())))
-        } else {
-            // router uses deny mode
-            Some((ACL::Deny, HashSet::from_iter(self.acl_deny.iter().cloned())))
-        }
+    /// Returns the access list applied on the interface towards `neighbor` in the given direction.
+    pub fn get_acl(&self, neighbor: RouterId, direction: AclDirection) -> Option<&AccessList> {
+        self.acls.get(&(neighbor, direction))
+    }
+
+    /// Returns all access lists, indexed by the neighbor of the interface and the direction.
+    pub fn get_acls(&self) -> &HashMap<(RouterId, AclDirection), AccessList> {
+        &self.acls
     }
 
     /// handle an `Event`, and enqueue several resulting events. Returns Ok(true) if the forwarding
@@ -173,14 +204,24 @@ impl Router {
                     // value was not present. Add to the stack
                     self.undo_stack.last_mut().unwrap().push(UndoAction::RemoveKnownPrefix(prefix));
                 };
-                // phase 2
-                let previous_next_hop = self.get_next_hop(Destination::BGP(prefix));
+                // phase 2. A change of the route also changes the forwarding of all more-specific
+                // prefixes without their own route.
+                let previous_next_hop = self.get_covered_next_hops(prefix);
                 self.run_bgp_decision_process_for_prefix(prefix)?;
-                let new_next_hop = self.get_next_hop(Destination::BGP(prefix));
+                let new_next_hop = self.get_covered_next_hops(prefix);
                 // phase 3
                 self.run_bgp_route_dissemination_for_prefix(prefix, queue, parent_event_id)?;
+                let mut fw_state_changed = previous_next_hop != new_next_hop;
+                // the route may contribute to aggregates, which need to be updated as well (the
+                // most specific ones first).
+                for aggregate in self.get_covering_aggregates(prefix) {
+                    let previous_next_hop = self.get_covered_next_hops(aggregate);
+                    self.run_bgp_decision_process_for_prefix(aggregate)?;
+                    fw_state_changed |= previous_next_hop != self.get_covered_next_hops(aggregate);
+                    self.run_bgp_route_dissemination_for_prefix(aggregate, queue, parent_event_id)?;
+                }
                 // return wether the forwarding state has changed
-                Ok(previous_next_hop != new_next_hop)
+                Ok(fw_state_changed)
                 // alternative version: check if the last undo stack frame is not empty
                 // Ok(!self.undo_stack.last().unwrap().is_empty())
             }
@@ -239,7 +280,7 @@ impl Router {
                     (Some(_), Some(o), Some(b)) if o.from_id == b.from_id => Ok(true),
                     // If the new route is better than the best route (and everything above does not
                     // hold), then something will change
-                    (Some(n), _, Some(b)) if &n > b => Ok(true),
+                    (Some(n), _, Some(b)) if n.compare(b, &self.bgp_decision).is_gt() => Ok(true),
                     // in the final case, nothing will change
                     _ => Ok(false),
                 }
@@ -280,14 +321,9 @@ impl Router {
         for action in self.undo_stack.pop().ok_or(DeviceError::UndoStackEmpty)? {
             match action {
                 UndoAction::UpdateBgpRibIn(prefix, neighbor, entry) => {
-                    match self.bgp_rib_in.get_mut(&prefix) {
-                        Some(table) => table,
-                        None => {
-                            self.bgp_rib_in.insert(prefix, HashMap::new());
-                            self.bgp_rib_in.get_mut(&prefix).unwrap()
-                        }
-                    }
-                    .insert(neighbor, entry);
+                    self.bgp_rib_in
+                        .get_or_insert_with(prefix, HashMap::new)
+                        .insert(neighbor, entry);
                 }
                 UndoAction::RemoveBgpRibIn(prefix, neighbor) => {
                     self.bgp_rib_in
@@ -305,14 +341,9 @@ impl Router {
                         .ok_or(DeviceError::UndoStackError("Entry in BGP RIB does not exist"))?;
                 }
                 UndoAction::UpdateBgpRibOut(prefix, neighbor, entry) => {
-                    match self.bgp_rib_out.get_mut(&prefix) {
-                        Some(table) => table,
-                        None => {
-                            self.bgp_rib_out.insert(prefix, HashMap::new());
-                            self.bgp_rib_out.get_mut(&prefix).unwrap()
-                        }
-                    }
-                    .insert(neighbor, entry);
+                    self.bgp_rib_out
+                        .get_or_insert_with(prefix, HashMap::new)
+                        .insert(neighbor, entry);
                 }
                 UndoAction::RemoveBgpRibOut(prefix, neighbor) => {
                     self.bgp_rib_out
@@ -344,43 +375,86 @@ impl Router {
     // could use an abstract data type here
     pub fn get_next_hop_legacy(&self, prefix: Prefix) -> Option<RouterId> {
         // need to implement the same function for IGP communication
+        let prefix = self.longest_prefix_match(prefix)?;
         // first, check the static routes
         if let Some(target) = self.static_routes.get(&prefix) {
             return Some(*target);
         };
         // then, check the bgp table
+        if self.is_local_aggregate(prefix) {
+            return None;
+        }
         match self.bgp_rib.get(&prefix) {
-            Some(entry) => {
-                self.igp_forwarding_table.get(&entry.route.next_hop).unwrap().map(|e| e.0)
-            }
+            Some(entry) if entry.route.next_hop == self.router_id => Some(self.router_id),
+            Some(entry) => self.get_igp_next_hops(entry.route.next_hop).first().copied(),
             None => None,
         }
     }
     
-    /// New function that supports IGP next hop
+    /// New function that supports IGP next hop. If there exist multiple equal-cost next hops,
+    /// the first one of [`Router::get_next_hops`] is returned.
     pub fn get_next_hop(&self, dest: Destination) -> Option<RouterId> {
-        // check whether it is of type RouterId or Prefix
-        // first, check the static routes
-        // first check accessibility
+        self.get_next_hops(dest).first().copied()
+    }
+
+    /// Returns all next hops towards `dest`, over which traffic is forwarded (ECMP). For IGP
+    /// destinations, these are all next hops on equal-cost shortest paths. For prefixes, the IGP
+    /// next hops of all BGP next hops are returned, which includes the routes used for BGP
+    /// multipath (if enabled). The first next hop is always the one of the best route. If the
+    /// router is the destination itself (for connected prefixes, or prefixes that it originates
+    /// with a network statement), the only next hop is the router itself. The returned vector is
+    /// empty if the router knows no route towards `dest`, or if the most specific route is an
+    /// aggregate created by the router itself (traffic is dropped, like with a route to `Null0`).
+    pub fn get_next_hops(&self, dest: Destination) -> Vec<RouterId> {
         match dest {
             Destination::BGP(prefix) => {
-                // handle Prefix
+                // handle Prefix, using the most specific route that covers it
+                let prefix = match self.longest_prefix_match(prefix) {
+                    Some(prefix) => prefix,
+                    None => return Vec::new(),
+                };
+                // first, check the static routes
                 if let Some(target) = self.static_routes.get(&prefix) {
-                    return Some(*target);
+                    return vec![*target];
                 };
                 // then, check the bgp table
-                match self.bgp_rib.get(&prefix) {
-                    Some(entry) => {
-                        self.igp_forwarding_table.get(&entry.route.next_hop).unwrap().map(|e| e.0)
+                if self.is_local_aggregate(prefix) {
+                    return Vec::new();
+                }
+                let mut next_hops: Vec<RouterId> = Vec::new();
+                for bgp_next_hop in self.get_bgp_next_hops(prefix) {
+                    if bgp_next_hop == self.router_id {
+                        // the route is originated by this router, deliver the packets locally
+                        if !next_hops.contains(&bgp_next_hop) {
+                            next_hops.push(bgp_next_hop);
+                        }
+                        continue;
+                    }
+                    for next_hop in self.get_igp_next_hops(bgp_next_hop) {
+                        if !next_hops.contains(next_hop) {
+                            next_hops.push(*next_hop);
+                        }
                     }
-                    None => None,
                 }
+                next_hops
             }
-            Destination::IGP(router) => {
-                // need to implement static route here
-                self.igp_forwarding_table.get(&router).unwrap().map(|e| e.0)
-            }
+            Destination::IGP(router) => self.get_igp_next_hops(router).to_vec(),
+        }
+    }
+
+    /// Returns the most specific prefix (of all static routes and selected BGP routes) that
+    /// contains `prefix`, or `None` if no route covers `prefix`. Static routes and BGP routes for
+    /// the same prefix are treated equally here, the static route is preferred afterwards.
+    pub fn longest_prefix_match(&self, prefix: Prefix) -> Option<Prefix> {
+        if self.static_routes.contains_key(&prefix) || self.bgp_rib.contains_key(&prefix) {
+            return Some(prefix);
         }
+        self.static_routes
+            .keys()
+            .chain(self.bgp_rib.keys())
+            .filter(|p| p.contains(&prefix))
+            .max_by_key(|p| p.prefix_len())
+            .copied()
     }
 
     /// Return a list of all known bgp routes for a given origin
@@ -401,127 +475,267 @@ impl Router {
         self.bgp_rib.get(&prefix).cloned()
     }
 
-    /// Add a static route. Note that the router must be a neighbor. This is not checked in this
-    /// funciton.
+    /// Add a static route, and update the BGP tables (since static routes may be redistributed).
+    /// Note that the router must be a neighbor (or the router itself). This is not checked in this
+    /// funciton. If `undo` is set, undo from the undo_stack instead of updating the bgp tables.
     pub(crate) fn add_static_route(
         &mut self,
         prefix: Prefix,
         target: RouterId,
+        queue: &mut EventQueue,
+        parent_event_id: usize,
+        undo: bool,
     ) -> Result<(), DeviceError> {
-        match self.static_routes.insert(prefix, target) {
-            None => Ok(()),
-            Some(_) => Err(DeviceError::StaticRouteAlreadyExists(prefix)),
+        if self.static_routes.insert(prefix, target).is_some() {
+            return Err(DeviceError::StaticRouteAlreadyExists(prefix));
+        }
+        if undo {
+            self.undo_last_event()
+        } else {
+            self.update_bgp_tables(queue, parent_event_id)
         }
     }
 
-    /// Remove an existing static route
-    pub(crate) fn remove_static_route(&mut self, prefix: Prefix) -> Result<(), DeviceError> {
-        match self.static_routes.remove(&prefix) {
-            Some(_) => Ok(()),
-            None => Err(DeviceError::NoStaticRoute(prefix)),
+    /// Remove an existing static route, and update the BGP tables. If `undo` is set, undo from the
+    /// undo_stack instead of updating the bgp tables.
+    pub(crate) fn remove_static_route(
+        &mut self,
+        prefix: Prefix,
+        queue: &mut EventQueue,
+        parent_event_id: usize,
+        undo: bool,
+    ) -> Result<(), DeviceError> {
+        if self.static_routes.remove(&prefix).is_none() {
+            return Err(DeviceError::NoStaticRoute(prefix));
+        }
+        if undo {
+            self.undo_last_event()
+        } else {
+            self.update_bgp_tables(queue, parent_event_id)
         }
     }
 
-    /// Modify a static route
+    /// Modify a static route, and update the BGP tables. If `undo` is set, undo from the
+    /// undo_stack instead of updating the bgp tables.
     pub(crate) fn modify_static_route(
         &mut self,
         prefix: Prefix,
         target: RouterId,
+        queue: &mut EventQueue,
+        parent_event_id: usize,
+        undo: bool,
     ) -> Result<(), DeviceError> {
-        match self.static_routes.insert(prefix, target) {
-            Some(_) => Ok(()),
-            None => Err(DeviceError::NoStaticRoute(prefix)),
+        if self.static_routes.insert(prefix, target).is_none() {
+            return Err(DeviceError::NoStaticRoute(prefix));
+        }
+        if undo {
+            self.undo_last_event()
+        } else {
+            self.update_bgp_tables(queue, parent_event_id)
         }
     }
 
-    /// Append new routers to ACL rules
-    pub(crate) fn add_acl_rules(
-        &mut self, 
-        accept: &Vec<RouterId>, 
-        deny: &Vec<RouterId>
+    /// Returns all prefixes that the router announces into BGP itself, either with a network
+    /// statement, as an aggregate, or by redistributing static or connected routes. Redistributed
+    /// routes that are denied by a route-map, and aggregates without any contributing route are
+    /// included as well.
+    pub fn get_originated_prefixes(&self) -> HashSet<Prefix> {
+        self.bgp_networks
+            .iter()
+            .chain(self.bgp_aggregates.keys())
+            .copied()
+            .chain(
+                self.static_routes
+                    .iter()
+                    .filter(|(_, target)| {
+                        self.bgp_redistribution.contains_key(&self.redistribution_source(**target))
+                    })
+                    .map(|(prefix, _)| *prefix),
+            )
+            .collect()
+    }
+
+    /// Returns all routes that the router originates itself (see
+    /// [`Router::get_originated_prefixes`]). Aggregates without any contributing route, and
+    /// redistributed routes that are denied by a route-map are not included.
+    pub fn get_originated_routes(&self) -> Vec<BgpRoute> {
+        self.get_originated_prefixes()
+            .into_iter()
+            .filter_map(|prefix| self.get_local_bgp_route(prefix))
+            .map(|entry| entry.route)
+            .collect()
+    }
+
+    /// Announce a prefix with a network statement, and update the BGP tables. If `undo` is set,
+    /// undo from the undo_stack instead of updating the bgp tables.
+    pub(crate) fn add_bgp_network(
+        &mut self,
+        prefix: Prefix,
+        queue: &mut EventQueue,
+        parent_event_id: usize,
+        undo: bool,
     ) -> Result<(), DeviceError> {
-        // TODO
-        if accept.len() > 0 || deny.len() > 0 {
-            for a in accept.iter() {
-                self.acl_accept.push(*a);
-            }
-            for d in deny.iter() {
-                self.acl_deny.push(*d);
-            }
-            Ok(())
+        if !self.bgp_networks.insert(prefix) {
+            return Err(DeviceError::BgpNetworkAlreadyExists(prefix));
+        }
+        if undo {
+            self.undo_last_event()
         } else {
-            Err(DeviceError::AclDoesNotExist(self.router_id()))
+            self.update_bgp_tables(queue, parent_event_id)
         }
     }
 
-    /// Remove routers from ACL rules
-    pub(crate) fn remove_acl_rules(
-        &mut self, 
-        accept: &Vec<RouterId>, 
-        deny: &Vec<RouterId>,
+    /// Remove a network statement, and update the BGP tables. If `undo` is set, undo from the
+    /// undo_stack instead of updating the bgp tables.
+    pub(crate) fn remove_bgp_network(
+        &mut self,
+        prefix: Prefix,
+        queue: &mut EventQueue,
+        parent_event_id: usize,
+        undo: bool,
     ) -> Result<(), DeviceError> {
-        // TODO
-        if accept.len() > 0 || deny.len() > 0 {
-            for a in accept.iter() {
-                self.acl_accept.push(*a)
+        if !self.bgp_networks.remove(&prefix) {
+            return Err(DeviceError::NoBgpNetwork(prefix));
+        }
+        if undo {
+            self.undo_last_event()
+        } else {
+            self.update_bgp_tables(queue, parent_event_id)
+        }
+    }
+
+    /// Enable (with the given route-maps) or disable (by passing `None`) the redistribution of
+    /// routes from `source` into BGP, and update the BGP tables. If `undo` is set, undo from the
+    /// undo_stack instead of updating the bgp tables.
+    pub(crate) fn set_bgp_redistribution(
+        &mut self,
+        source: RedistributionSource,
+        route_maps: Option<Vec<RouteMap>>,
+        queue: &mut EventQueue,
+        parent_event_id: usize,
+        undo: bool,
+    ) -> Result<(), DeviceError> {
+        match route_maps {
+            Some(mut route_maps) => {
+                route_maps.sort_by_key(|map| map.order);
+                self.bgp_redistribution.insert(source, route_maps);
             }
-            for d in deny.iter() {
-                self.acl_deny.push(*d);
+            None => {
+                self.bgp_redistribution.remove(&source);
             }
-            Ok(())
+        }
+        if undo {
+            self.undo_last_event()
         } else {
-            Err(DeviceError::AclDoesNotExist(self.router_id()))
+            self.update_bgp_tables(queue, parent_event_id)
         }
     }
 
-    /// Modify existing ACL rules
-    pub(crate) fn modify_acl_rules(
-        &mut self, 
-        accept1: &Vec<RouterId>, 
-        deny1: &Vec<RouterId>,
-        accept2: &Vec<RouterId>,
-        deny2: &Vec<RouterId>,
+    /// Create an aggregate for `prefix`, and update the BGP tables. If `undo` is set, undo from
+    /// the undo_stack instead of updating the bgp tables.
+    pub(crate) fn add_bgp_aggregate(
+        &mut self,
+        prefix: Prefix,
+        summary_only: bool,
+        as_set: bool,
+        queue: &mut EventQueue,
+        parent_event_id: usize,
+        undo: bool,
     ) -> Result<(), DeviceError> {
-        // TODO
-        // compute the set that are in the original vector not in the new vector, remove them
-        // compute the set that are not in the original vector in the new vector, append them
-        self.acl_append_remove(accept1, accept2, ACL::Accept)?;
-        self.acl_append_remove(deny1, deny2, ACL::Deny)?;
-        
-        Ok(())
+        let config = AggregateConfig { summary_only, as_set };
+        if self.bgp_aggregates.insert(prefix, config).is_some() {
+            return Err(DeviceError::BgpAggregateAlreadyExists(prefix));
+        }
+        if undo {
+            self.undo_last_event()
+        } else {
+            self.update_bgp_tables(queue, parent_event_id)
+        }
     }
-    
-    // Given the type of ACL, append the new rules, remove the old rules
-    fn acl_append_remove(&mut self, vec1: &Vec<RouterId>, vec2: &Vec<RouterId>, config_type: ACL) -> Result<(), DeviceError> {
-        let set1: HashSet<RouterId> = HashSet::from_iter(vec1.clone());
-        let set2: HashSet<RouterId> = HashSet::from_iter(vec2.clone());
 
-        if set1.len() == 0 && set2.len() == 0 {
-            return Err(DeviceError::AclInvalid);
+    /// Remove the aggregate for `prefix`, and update the BGP tables. If `undo` is set, undo from
+    /// the undo_stack instead of updating the bgp tables.
+    pub(crate) fn remove_bgp_aggregate(
+        &mut self,
+        prefix: Prefix,
+        queue: &mut EventQueue,
+        parent_event_id: usize,
+        undo: bool,
+    ) -> Result<(), DeviceError> {
+        if self.bgp_aggregates.remove(&prefix).is_none() {
+            return Err(DeviceError::NoBgpAggregate(prefix));
         }
-
-        let set_rmv = set1.difference(&set2);
-        let set_apd = set2.difference(&set1);
-
-        let acl_ref: &mut Vec<RouterId>;
-        match config_type {
-            ACL::Accept => acl_ref = self.acl_accept.as_mut(),
-            ACL::Deny => acl_ref = self.acl_deny.as_mut(),
+        if undo {
+            self.undo_last_event()
+        } else {
+            self.update_bgp_tables(queue, parent_event_id)
         }
-        // remove elements in set_rmv
-        for r in set_rmv {
-            // index elements
-            let index = acl_ref.clone().iter().position(|x| *x == *r).unwrap();
-            acl_ref.remove(index);
+    }
+
+    /// Change the options of the aggregate for `prefix`, and update the BGP tables. If `undo` is
+    /// set, undo from the undo_stack instead of updating the bgp tables.
+    pub(crate) fn modify_bgp_aggregate(
+        &mut self,
+        prefix: Prefix,
+        summary_only: bool,
+        as_set: bool,
+        queue: &mut EventQueue,
+        parent_event_id: usize,
+        undo: bool,
+    ) -> Result<(), DeviceError> {
+        let config = AggregateConfig { summary_only, as_set };
+        if self.bgp_aggregates.insert(prefix, config).is_none() {
+            return Err(DeviceError::NoBgpAggregate(prefix));
         }
-        // append elements in set_apd
-        for a in set_apd {
-            acl_ref.push(*a);
+        if undo {
+            self.undo_last_event()
+        } else {
+            self.update_bgp_tables(queue, parent_event_id)
         }
+    }
 
+    /// Apply a new access list on the interface towards `neighbor`.
+    pub(crate) fn add_acl(
+        &mut self,
+        neighbor: RouterId,
+        direction: AclDirection,
+        acl: AccessList,
+    ) -> Result<(), DeviceError> {
+        if self.acls.contains_key(&(neighbor, direction)) {
+            return Err(DeviceError::AclAlreadyExists(self.router_id));
+        }
+        self.acls.insert((neighbor, direction), acl);
         Ok(())
     }
 
+    /// Replace the existing access list on the interface towards `neighbor`.
+    pub(crate) fn modify_acl(
+        &mut self,
+        neighbor: RouterId,
+        direction: AclDirection,
+        acl: AccessList,
+    ) -> Result<(), DeviceError> {
+        match self.acls.get_mut(&(neighbor, direction)) {
+            Some(old) => {
+                *old = acl;
+                Ok(())
+            }
+            None => Err(DeviceError::AclDoesNotExist(self.router_id)),
+        }
+    }
+
+    /// Remove the access list from the interface towards `neighbor`.
+    pub(crate) fn remove_acl(
+        &mut self,
+        neighbor: RouterId,
+        direction: AclDirection,
+    ) -> Result<(), DeviceError> {
+        match self.acls.remove(&(neighbor, direction)) {
+            Some(_) => Ok(()),
+            None => Err(DeviceError::AclDoesNotExist(self.router_id)),
+        }
+    }
+
     /// establish a bgp session with a peer
     /// `session_type` tells that `target` is in relation to `self`. If `session_type` is
     /// `BgpSessionType::IbgpClient`, then the `target` is added as client to `self`. Update the
@@ -598,14 +812,10 @@ impl Router {
             let mut stack: Vec<UndoAction> = Vec::new();
             for prefix in self.bgp_known_prefixes.iter() {
                 // remove the entry in the rib tables, and add it to the stack
-                if let Some(entry) =
-                    self.bgp_rib_in.get_mut(&prefix).and_then(|rib| rib.remove(&target))
-                {
+                if let Some(entry) = remove_rib_entry(&mut self.bgp_rib_in, *prefix, target) {
                     stack.push(UndoAction::UpdateBgpRibIn(*prefix, target, entry));
                 }
-                if let Some(entry) =
-                    self.bgp_rib_out.get_mut(&prefix).and_then(|rib| rib.remove(&target))
-                {
+                if let Some(entry) = remove_rib_entry(&mut self.bgp_rib_out, *prefix, target) {
                     stack.push(UndoAction::UpdateBgpRibOut(*prefix, target, entry));
                 }
             }
@@ -616,6 +826,50 @@ impl Router {
         }
     }
 
+    /// Returns the configuration of the BGP decision process
+    pub fn get_bgp_decision_config(&self) -> &BgpDecisionConfig {
+        &self.bgp_decision
+    }
+
+    /// Change the configuration of the BGP decision process and update the BGP tables. If `undo` is
+    /// set, undo from the undo_stack instead of updating the bgp tables.
+    pub(crate) fn set_bgp_decision_config(
+        &mut self,
+        config: BgpDecisionConfig,
+        queue: &mut EventQueue,
+        parent_event_id: usize,
+        undo: bool,
+    ) -> Result<(), DeviceError> {
+        self.bgp_decision = config;
+        if undo {
+            self.undo_last_event()
+        } else {
+            self.update_bgp_tables(queue, parent_event_id)
+        }
+    }
+
+    /// Returns the configured cluster ID, or `None` if the router ID is used as cluster ID.
+    pub fn get_bgp_cluster_id(&self) -> Option<RouterId> {
+        self.bgp_cluster_id
+    }
+
+    /// Change the cluster ID (or reset it to the router ID by passing `None`), and update the BGP
+    /// tables. If `undo` is set, undo from the undo_stack instead of updating the bgp tables.
+    pub(crate) fn set_bgp_cluster_id(
+        &mut self,
+        cluster_id: Option<RouterId>,
+        queue: &mut EventQueue,
+        parent_event_id: usize,
+        undo: bool,
+    ) -> Result<(), DeviceError> {
+        self.bgp_cluster_id = cluster_id;
+        if undo {
+            self.undo_last_event()
+        } else {
+            self.update_bgp_tables(queue, parent_event_id)
+        }
+    }
+
     /// Returns an interator over all BGP sessions
     pub fn get_bgp_sessions(&self) -> Iter<'_, RouterId, BgpSessionType> {
         self.bgp_sessions.iter()
@@ -785,50 +1039,36 @@ impl Router {
 
     /// write forawrding table based on graph
     /// This function requres that all RouterIds are set to the GraphId, and update the BGP tables
+    /// Returns `true` if the table is different from the previous one.
     pub(crate) fn write_igp_forwarding_table(
         &mut self,
         graph: &IgpNetwork,
         queue: &mut EventQueue,
         parent_event_id: usize,
         undo: bool,
-    ) -> Result<(), DeviceError> {
-        // clear the forwarding table
-        self.igp_forwarding_table = HashMap::new();
-        // compute shortest path to all other nodes in the graph
-        let (path_weights, predecessors) = bellman_ford(graph, self.router_id).unwrap();
-        let mut paths: Vec<(RouterId, LinkWeight, Option<RouterId>)> = path_weights
-            .into_iter()
-            .zip(predecessors.into_iter())
-            .enumerate()
-            .map(|(i, (w, p))| ((i as u32).into(), w, p))
-            .collect();
-        paths.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-        for (router, cost, predecessor) in paths {
-            if cost.is_infinite() {
-                self.igp_forwarding_table.insert(router, None);
-                continue;
-            }
-            let next_hop = if let Some(predecessor) = predecessor {
-                // the predecessor must already be inserted into the forwarding table, because we sorted the table
-                if predecessor == self.router_id {
-                    router
-                } else {
-                    self.igp_forwarding_table
-                        .get(&predecessor)
-                        .unwrap() // first unwrap for get, which returns an option
-                        .unwrap() // second unwrap to unwrap wether the route exists (it must!)
-                        .0
-                }
-            } else {
-                router
-            };
-            self.igp_forwarding_table.insert(router, Some((next_hop, cost)));
-        }
+    ) -> Result<bool, DeviceError> {
+        let table = shortest_paths(graph, self.router_id);
+        self.set_igp_forwarding_table(table, queue, parent_event_id, undo)
+    }
+
+    /// Replace the IGP forwarding table with a table that was computed for the entire network
+    /// (see `OspfAreas::igp_forwarding_tables`), and update the BGP tables. Returns `true` if the
+    /// table is different from the previous one.
+    pub(crate) fn set_igp_forwarding_table(
+        &mut self,
+        table: IgpForwardingTable,
+        queue: &mut EventQueue,
+        parent_event_id: usize,
+        undo: bool,
+    ) -> Result<bool, DeviceError> {
+        let changed = self.igp_forwarding_table != table;
+        self.igp_forwarding_table = table;
         if undo {
-            self.undo_last_event()
+            self.undo_last_event()?;
         } else {
-            self.update_bgp_tables(queue, parent_event_id)
+            self.update_bgp_tables(queue, parent_event_id)?;
         }
+        Ok(changed)
     }
 
     /// Update the bgp tables only, This funciton also causes the undo stack to be created.
@@ -839,8 +1079,17 @@ impl Router {
     ) -> Result<(), DeviceError> {
         // first, push an element to the stack
         self.undo_stack.push(Vec::new());
-        // run the decision process
-        for prefix in self.bgp_known_prefixes.clone() {
+        // learn all prefixes originated by this router
+        for prefix in self.get_originated_prefixes() {
+            if self.bgp_known_prefixes.insert(prefix) {
+                self.undo_stack.last_mut().unwrap().push(UndoAction::RemoveKnownPrefix(prefix));
+            }
+        }
+        // run the decision process. More-specific prefixes are processed first, such that all
+        // contributing routes are known when computing an aggregate.
+        let mut prefixes: Vec<Prefix> = self.bgp_known_prefixes.iter().copied().collect();
+        prefixes.sort_by_key(|p| std::cmp::Reverse(p.prefix_len()));
+        for prefix in prefixes {
             self.run_bgp_decision_process_for_prefix(prefix)?
         }
         // run the route dissemination
@@ -872,32 +1121,46 @@ impl Router {
         true
     }
 
+    /// Returns `true` if the BGP tables of both routers share the entries for the prefix.
+    #[cfg(test)]
+    pub(crate) fn shares_bgp_tables(&self, other: &Self, prefix: Prefix) -> bool {
+        self.bgp_rib_in.shares_value(&other.bgp_rib_in, &prefix)
+            && self.bgp_rib.shares_value(&other.bgp_rib, &prefix)
+            && self.bgp_rib_out.shares_value(&other.bgp_rib_out, &prefix)
+    }
+
     // -----------------
     // Private Functions
     // -----------------
 
+    /// Returns the next hop of all known prefixes that are contained in `prefix` (including
+    /// `prefix` itself), sorted by the prefix.
+    fn get_covered_next_hops(&self, prefix: Prefix) -> Vec<(Prefix, Option<RouterId>)> {
+        let mut result: Vec<(Prefix, Option<RouterId>)> = self
+            .bgp_known_prefixes
+            .iter()
+            .filter(|p| prefix.contains(p))
+            .map(|p| (*p, self.get_next_hop(Destination::BGP(*p))))
+            .collect();
+        result.sort();
+        result
+    }
+
     /// only run bgp decision process (phase 2)
     fn run_bgp_decision_process_for_prefix(&mut self, prefix: Prefix) -> Result<(), DeviceError> {
         // search the best route and compare
         let old_entry = self.bgp_rib.get(&prefix);
-        let mut new_entry = None;
-
         // find the new best route
+        let mut candidates: Vec<BgpRibEntry> =
+            self.get_local_bgp_route(prefix).into_iter().collect();
         if let Some(rib_in) = self.bgp_rib_in.get(&prefix) {
             for entry_unprocessed in rib_in.values() {
-                let entry = match self.process_bgp_rib_in_route(entry_unprocessed.clone())? {
-                    Some(e) => e,
-                    None => continue,
-                };
-                let mut better = true;
-                if let Some(current_best) = new_entry.as_ref() {
-                    better = &entry > current_best;
-                }
-                if better {
-                    new_entry = Some(entry)
+                if let Some(entry) = self.process_bgp_rib_in_route(entry_unprocessed.clone())? {
+                    candidates.push(entry);
                 }
             }
         }
+        let new_entry = self.bgp_decision.best_route(candidates);
 
         // check if the entry will get changed
         if new_entry.as_ref() != old_entry {
@@ -931,19 +1194,22 @@ impl Router {
         queue: &mut EventQueue,
         parent_event_id: usize,
     ) -> Result<(), DeviceError> {
-        self.bgp_rib_out.entry(prefix).or_default();
+        if !self.bgp_rib_out.contains_key(&prefix) {
+            self.bgp_rib_out.insert(prefix, HashMap::new());
+        }
 
         for (peer, peer_type) in self.bgp_sessions.iter() {
-            // apply the route for the specific peer
+            // apply the route for the specific peer (unless it is suppressed by an aggregate)
             let best_route: Option<BgpRibEntry> = self
                 .bgp_rib
                 .get(&prefix)
+                .filter(|_| !self.is_suppressed(prefix))
                 .map(|e| self.process_bgp_rib_out_route(e.clone(), *peer))
                 .transpose()?
                 .flatten();
             // check if the current information is the same
             let current_route: Option<&BgpRibEntry> =
-                self.bgp_rib_out.get_mut(&prefix).and_then(|rib| rib.get(peer));
+                self.bgp_rib_out.get(&prefix).and_then(|rib| rib.get(peer));
             let event = match (best_route, current_route) {
                 (Some(best_r), Some(current_r)) if best_r.route == current_r.route => {
                     // Nothing to do, no new route received
@@ -1038,12 +1304,7 @@ impl Router {
 
         let prefix = new_entry.route.prefix;
 
-        let rib_in = if self.bgp_rib_in.contains_key(&prefix) {
-            self.bgp_rib_in.get_mut(&prefix).unwrap()
-        } else {
-            self.bgp_rib_in.insert(new_entry.route.prefix, HashMap::new());
-            self.bgp_rib_in.get_mut(&prefix).unwrap()
-        };
+        let rib_in = self.bgp_rib_in.get_or_insert_with(prefix, HashMap::new);
 
         // insert the new route. Also, update the undo action to be able to go back.
         match rib_in.insert(from, new_entry) {
@@ -1065,8 +1326,7 @@ impl Router {
     fn remove_bgp_route(&mut self, prefix: Prefix, from: RouterId) -> Prefix {
         // check if the prefix does exist in the table. if there was an entry, then also add it to
         // the undo action.
-        if let Some(old_entry) = self.bgp_rib_in.get_mut(&prefix).and_then(|rib| rib.remove(&from))
-        {
+        if let Some(old_entry) = remove_rib_entry(&mut self.bgp_rib_in, prefix, from) {
             self.undo_stack
                 .last_mut()
                 .unwrap()
@@ -1080,6 +1340,15 @@ impl Router {
         &self,
         mut entry: BgpRibEntry,
     ) -> Result<Option<BgpRibEntry>, DeviceError> {
+        // ignore routes that were announced by this router, or that already passed the cluster of
+        // this router (RFC 4456)
+        if entry.from_type.is_ibgp()
+            && (entry.route.originator_id == Some(self.router_id)
+                || entry.route.cluster_list.contains(&self.cluster_id()))
+        {
+            return Ok(None);
+        }
+
         // apply bgp_route_map_in
         let mut maps = self.bgp_route_maps_in.iter();
         let mut entry = loop {
@@ -1110,13 +1379,18 @@ impl Router {
             ),
         );
 
-        // set the next hop to the egress from router if the message came from externally
+        // set the next hop to the egress from router if the message came from externally. Also,
+        // remove the attributes that are only valid inside an AS.
         if entry.from_type.is_ebgp() {
             entry.route.next_hop = entry.from_id;
+            entry.route.originator_id = None;
+            entry.route.cluster_list.clear();
         }
 
-        // set the default values
-        entry.route.apply_default();
+        // set the default local preference. The MED is kept as received, such that a missing MED
+        // is also missing when the route is advertised to other peers. Its default value depends
+        // on the decision process (see `BgpDecisionConfig::med_missing_as_worst`).
+        entry.route.local_pref.get_or_insert(100);
 
         // set the to_id to None
         entry.to_id = None;
@@ -1151,14 +1425,21 @@ impl Router {
         };
 
         // get the peer type
-        entry.from_type =
+        let to_type =
             *self.bgp_sessions.get(&target_peer).ok_or(DeviceError::NoBgpSession(target_peer))?;
 
         // if the peer type is external, overwrite values of the route accordingly.
-        if entry.from_type.is_ebgp() {
+        if to_type.is_ebgp() {
             entry.route.next_hop = self.router_id;
             entry.route.local_pref = None;
+            entry.route.originator_id = None;
+            entry.route.cluster_list.clear();
+        } else if entry.from_type.is_ibgp() && entry.from_id != self.router_id {
+            // the route is reflected. Set the originator and prepend the cluster ID (RFC 4456)
+            entry.route.originator_id.get_or_insert(entry.from_id);
+            entry.route.cluster_list.insert(0, self.cluster_id());
         }
+        entry.from_type = to_type;
 
         Ok(Some(entry))
     }
@@ -1175,6 +1456,10 @@ impl Router {
         if from == to {
             return Ok(false);
         }
+        // routes originated by this router are advertised to all peers
+        if from == self.router_id {
+            return Ok(true);
+        }
         // check the types
         let from_type = self.bgp_sessions.get(&from).ok_or(DeviceError::NoBgpSession(from))?;
 
@@ -1187,22 +1472,189 @@ impl Router {
         })
     }
 
-    /// Checks if the router accepts traffic from this sender
-    fn check_access(&self, sender: &RouterId) -> bool {
-        if self.acl_accept.len() > 0 {
-            // white list mode
-            self.acl_accept.contains(sender)
+    /// Returns all IGP next hops towards `router`. The slice is empty if `router` is unreachable.
+    fn get_igp_next_hops(&self, router: RouterId) -> &[RouterId] {
+        match self.igp_forwarding_table.get(&router).unwrap() {
+            Some((next_hops, _)) => next_hops,
+            None => &[],
+        }
+    }
+
+    /// Returns the BGP next hops used for forwarding traffic towards `prefix`. The first one is
+    /// the next hop of the selected route. If multipath is enabled, the next hops of all other
+    /// routes that are equally good as the selected route follow, sorted by their ID.
+    fn get_bgp_next_hops(&self, prefix: Prefix) -> Vec<RouterId> {
+        let best = match self.bgp_rib.get(&prefix) {
+            Some(best) => best,
+            None => return Vec::new(),
+        };
+        let mut next_hops = vec![best.route.next_hop];
+        if self.bgp_decision.multipath {
+            let mut multipath_next_hops: Vec<RouterId> = self
+                .bgp_rib_in
+                .get(&prefix)
+                .into_iter()
+                .flat_map(|rib_in| rib_in.values())
+                .filter_map(|entry| self.process_bgp_rib_in_route(entry.clone()).ok().flatten())
+                .filter(|entry| self.bgp_decision.is_multipath(entry, best))
+                .map(|entry| entry.route.next_hop)
+                .filter(|next_hop| *next_hop != best.route.next_hop)
+                .collect();
+            multipath_next_hops.sort();
+            multipath_next_hops.dedup();
+            next_hops.append(&mut multipath_next_hops);
+        }
+        next_hops
+    }
+
+    /// Returns the route that the router originates itself for `prefix`, or `None` if it does not
+    /// originate the prefix. A network statement takes precedence over an aggregate, which takes
+    /// precedence over redistribution. The route has an empty AS path (aggregates with `as_set`
+    /// only have an AS_SET), and the router itself as next hop (with an IGP cost of 0). Hence, it is usually
+    /// preferred over all routes learned from other routers, unless they have a higher local
+    /// preference.
+    fn get_local_bgp_route(&self, prefix: Prefix) -> Option<BgpRibEntry> {
+        let (origin, as_set, route_maps) = if self.bgp_networks.contains(&prefix) {
+            (Origin::Igp, BTreeSet::new(), &[][..])
+        } else if let Some(config) = self.bgp_aggregates.get(&prefix) {
+            let (origin, as_set) = self.get_aggregate_attributes(prefix, *config)?;
+            (origin, as_set, &[][..])
         } else {
-            // black list mode
-            // if the sender exists in deny
-            // then return false
-            self.acl_deny.contains(sender)^true
+            let source = self.redistribution_source(*self.static_routes.get(&prefix)?);
+            (Origin::Incomplete, BTreeSet::new(), &self.bgp_redistribution.get(&source)?[..])
+        };
+        let mut entry = BgpRibEntry {
+            route: BgpRoute {
+                prefix,
+                as_path: Vec::new(),
+                as_set,
+                next_hop: self.router_id,
+                local_pref: None,
+                med: None,
+                origin,
+                communities: BTreeSet::new(),
+                large_communities: BTreeSet::new(),
+                originator_id: None,
+                cluster_list: Vec::new(),
+            },
+            from_type: BgpSessionType::IBgpPeer,
+            from_id: self.router_id,
+            to_id: None,
+            igp_cost: Some(0.0),
+        };
+
+        // apply the route-maps of the redistribution
+        let mut maps = route_maps.iter();
+        let mut entry = loop {
+            match maps.next() {
+                Some(map) => {
+                    entry = match map.apply(entry) {
+                        (true, Some(e)) => break e,
+                        (true, None) => return None,
+                        (false, Some(e)) => e,
+                        (false, None) => unreachable!(),
+                    }
+                }
+                None => break entry,
+            }
+        };
+        entry.route.apply_default();
+        Some(entry)
+    }
+
+    /// Returns the origin and the AS_SET of the aggregate for `prefix`, computed from all selected
+    /// routes for more-specific prefixes (the contributing routes). The origin is the worst origin
+    /// of all contributing routes. Returns `None` if there is no contributing route.
+    fn get_aggregate_attributes(
+        &self,
+        prefix: Prefix,
+        config: AggregateConfig,
+    ) -> Option<(Origin, BTreeSet<AsId>)> {
+        let contributors: Vec<&BgpRibEntry> = self
+            .bgp_rib
+            .iter()
+            .filter(|(p, _)| **p != prefix && prefix.contains(p))
+            .map(|(_, entry)| entry)
+            .collect();
+        let origin = contributors.iter().map(|e| e.route.origin).max()?;
+        let as_set = if config.as_set {
+            contributors
+                .iter()
+                .flat_map(|e| e.route.as_path.iter().chain(e.route.as_set.iter()).copied())
+                .collect()
+        } else {
+            BTreeSet::new()
+        };
+        Some((origin, as_set))
+    }
+
+    /// Returns all aggregates that contain `prefix` (but are not equal to it), sorted such that the
+    /// most specific aggregate comes first.
+    fn get_covering_aggregates(&self, prefix: Prefix) -> Vec<Prefix> {
+        let mut aggregates: Vec<Prefix> = self
+            .bgp_aggregates
+            .keys()
+            .filter(|p| **p != prefix && p.contains(&prefix))
+            .copied()
+            .collect();
+        aggregates.sort_by_key(|p| std::cmp::Reverse(p.prefix_len()));
+        aggregates
+    }
+
+    /// Returns `true` if the advertisement of `prefix` is suppressed, because it is covered by an
+    /// aggregate with `summary_only`.
+    fn is_suppressed(&self, prefix: Prefix) -> bool {
+        self.bgp_aggregates
+            .iter()
+            .any(|(p, config)| config.summary_only && *p != prefix && p.contains(&prefix))
+    }
+
+    /// Returns `true` if the selected route for `prefix` is the aggregate created by this router.
+    fn is_local_aggregate(&self, prefix: Prefix) -> bool {
+        self.bgp_aggregates.contains_key(&prefix)
+            && !self.bgp_networks.contains(&prefix)
+            && self.bgp_rib.get(&prefix).map(|e| e.from_id == self.router_id).unwrap_or(false)
+    }
+
+    /// Returns the redistribution source of a static route towards `target`.
+    fn redistribution_source(&self, target: RouterId) -> RedistributionSource {
+        if target == self.router_id {
+            RedistributionSource::Connected
+        } else {
+            RedistributionSource::Static
         }
-        
+    }
+
+    /// Returns the cluster ID used for route reflection
+    fn cluster_id(&self) -> RouterId {
+        self.bgp_cluster_id.unwrap_or(self.router_id)
     }
 }
 
-#[derive(Debug)]
+/// Remove the entry of the neighbor from the table of the prefix. The entries of the prefix are
+/// only copied if they are shared and contain the neighbor.
+fn remove_rib_entry(
+    rib: &mut CowMap<Prefix, HashMap<RouterId, BgpRibEntry>>,
+    prefix: Prefix,
+    neighbor: RouterId,
+) -> Option<BgpRibEntry> {
+    if rib.get(&prefix).map(|table| table.contains_key(&neighbor)).unwrap_or(false) {
+        rib.get_mut(&prefix).and_then(|table| table.remove(&neighbor))
+    } else {
+        None
+    }
+}
+
+/// Options of an aggregate created by the router
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AggregateConfig {
+    /// Suppress the advertisement of all more-specific prefixes
+    summary_only: bool,
+    /// Include the AS numbers of all contributing routes in the AS path
+    as_set: bool,
+}
+
+#[derive(Debug, Clone)]
 enum UndoAction {
     /// Undo by updating (or inserting) a BGP RIB entry in the BGP RIB IN table
     UpdateBgpRibIn(Prefix, RouterId, BgpRibEntry),
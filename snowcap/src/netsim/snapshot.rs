@@ -0,0 +1,433 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! # Copy-on-Write Device Storage
+//!
+//! The network stores all devices in a [`CowMap`], which shares the devices between all clones of
+//! the network and all of its snapshots. Cloning the map only increments a reference counter. A
+//! device is copied only when it is modified while it is still shared.
+//!
+//! The same structure stores the BGP tables of each router, mapping every prefix to its entries.
+//! Hence, copying a router only copies the pointers to these entries, and processing a route for a
+//! single prefix only copies the entries of this prefix.
+//!
+//! The undo stacks of the devices and the event history of the network are stored in a
+//! [`SharedVec`], such that copying a shared device does not copy its entire history. The same
+//! structure stores the next hops of a forwarding state, which allows the timed simulation to
+//! record a forwarding state after every event without copying it.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::{Index, IndexMut};
+use std::sync::Arc;
+
+/// Clone a device together with its undo stack. This is used when a shared device is modified,
+/// such that the events processed before taking the snapshot can still be undone. The undo stack
+/// is a [`SharedVec`], so this does not copy the history of the device.
+pub(crate) trait SnapshotClone {
+    /// Clone the device, including all undo information.
+    fn snapshot_clone(&self) -> Self;
+}
+
+/// The entries of a BGP table (for a single prefix) carry no undo information.
+impl<K: Clone, V: Clone> SnapshotClone for HashMap<K, V> {
+    fn snapshot_clone(&self) -> Self {
+        self.clone()
+    }
+}
+
+/// Hash map with structural sharing. Both the map itself and every value are reference counted,
+/// and they are only copied when they are modified while being shared.
+#[derive(Debug)]
+pub(crate) struct CowMap<K, V> {
+    inner: Arc<HashMap<K, Arc<V>>>,
+}
+
+impl<K, V> Clone for CowMap<K, V> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<K, V> Default for CowMap<K, V> {
+    fn default() -> Self {
+        Self { inner: Arc::new(HashMap::new()) }
+    }
+}
+
+impl<K, V> CowMap<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: SnapshotClone,
+{
+    /// Create an empty map.
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements in the map.
+    pub(crate) fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if the map contains a value for the key.
+    pub(crate) fn contains_key(&self, key: &K) -> bool {
+        self.inner.contains_key(key)
+    }
+
+    /// Returns a reference to the value of the key.
+    pub(crate) fn get(&self, key: &K) -> Option<&V> {
+        self.inner.get(key).map(|v| v.as_ref())
+    }
+
+    /// Returns a mutable reference to the value of the key. If the value is shared, it is copied
+    /// first.
+    pub(crate) fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        Arc::make_mut(&mut self.inner).get_mut(key).map(make_unique)
+    }
+
+    /// Returns a mutable reference to the value of the key. If the key is not present, the value
+    /// returned by `default` is inserted. If the value is shared, it is copied first.
+    pub(crate) fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, default: F) -> &mut V {
+        make_unique(
+            Arc::make_mut(&mut self.inner).entry(key).or_insert_with(|| Arc::new(default())),
+        )
+    }
+
+    /// Insert a new value into the map, returning the old value of the key.
+    pub(crate) fn insert(&mut self, key: K, value: V) -> Option<V> {
+        Arc::make_mut(&mut self.inner).insert(key, Arc::new(value)).map(into_inner)
+    }
+
+    /// Remove the key from the map, returning its value.
+    pub(crate) fn remove(&mut self, key: &K) -> Option<V> {
+        if !self.inner.contains_key(key) {
+            return None;
+        }
+        Arc::make_mut(&mut self.inner).remove(key).map(into_inner)
+    }
+
+    /// Iterate over all key-value pairs.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.inner.iter().map(|(k, v)| (k, v.as_ref()))
+    }
+
+    /// Iterate over all keys.
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &K> {
+        self.inner.keys()
+    }
+
+    /// Iterate over all values.
+    pub(crate) fn values(&self) -> impl Iterator<Item = &V> {
+        self.inner.values().map(|v| v.as_ref())
+    }
+
+    /// Iterate mutably over all values. Every value that is shared is copied.
+    pub(crate) fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        Arc::make_mut(&mut self.inner).values_mut().map(make_unique)
+    }
+
+    /// Iterate mutably over all key-value pairs. Every value that is shared is copied.
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        Arc::make_mut(&mut self.inner).iter_mut().map(|(k, v)| (k, make_unique(v)))
+    }
+
+    /// Returns all keys whose value differs between both maps, or which are only present in one
+    /// of them. Values are compared by their address, so values that are shared between both maps
+    /// are never compared.
+    pub(crate) fn changed_keys(&self, other: &Self) -> Vec<K> {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            return Vec::new();
+        }
+        let mut keys: Vec<K> = self
+            .inner
+            .iter()
+            .filter(|(k, v)| other.inner.get(k).map(|o| !Arc::ptr_eq(v, o)).unwrap_or(true))
+            .map(|(k, _)| k.clone())
+            .collect();
+        keys.extend(other.inner.keys().filter(|k| !self.inner.contains_key(k)).cloned());
+        keys
+    }
+
+    /// Returns `true` if the value of the key is shared with another map.
+    #[cfg(test)]
+    pub(crate) fn is_shared(&self, key: &K) -> bool {
+        Arc::strong_count(&self.inner) > 1
+            || self.inner.get(key).map(|v| Arc::strong_count(v) > 1).unwrap_or(false)
+    }
+
+    /// Returns `true` if both maps share the same value for the key.
+    #[cfg(test)]
+    pub(crate) fn shares_value(&self, other: &Self, key: &K) -> bool {
+        match (self.inner.get(key), other.inner.get(key)) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl<K, V> PartialEq for CowMap<K, V>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+            || (self.inner.len() == other.inner.len()
+                && self.inner.iter().all(|(k, v)| {
+                    other.inner.get(k).map(|o| Arc::ptr_eq(v, o) || v == o).unwrap_or(false)
+                }))
+    }
+}
+
+impl<K, V> Index<&K> for CowMap<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: SnapshotClone,
+{
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("key not present in the map")
+    }
+}
+
+/// Number of elements stored in a single chunk of a [`SharedVec`].
+const CHUNK_SIZE: usize = 64;
+
+/// Vector with structural sharing, split into reference-counted chunks of [`CHUNK_SIZE`]
+/// elements. Cloning the vector only copies the pointers to the chunks, and modifying the vector
+/// at the end copies at most a single chunk. All chunks except the last one are always full.
+#[derive(Debug)]
+pub(crate) struct SharedVec<T> {
+    chunks: Vec<Arc<Vec<T>>>,
+}
+
+impl<T> Clone for SharedVec<T> {
+    fn clone(&self) -> Self {
+        Self { chunks: self.chunks.clone() }
+    }
+}
+
+impl<T> Default for SharedVec<T> {
+    fn default() -> Self {
+        Self { chunks: Vec::new() }
+    }
+}
+
+impl<T: Clone> SharedVec<T> {
+    /// Create an empty vector.
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements in the vector.
+    pub(crate) fn len(&self) -> usize {
+        match self.chunks.last() {
+            Some(last) => (self.chunks.len() - 1) * CHUNK_SIZE + last.len(),
+            None => 0,
+        }
+    }
+
+    /// Returns `true` if the vector contains no elements.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Returns a reference to the element at position `idx`.
+    pub(crate) fn get(&self, idx: usize) -> Option<&T> {
+        self.chunks.get(idx / CHUNK_SIZE).and_then(|c| c.get(idx % CHUNK_SIZE))
+    }
+
+    /// Returns a mutable reference to the last element. If the last chunk is shared, it is copied
+    /// first.
+    pub(crate) fn last_mut(&mut self) -> Option<&mut T> {
+        self.chunks.last_mut().and_then(|c| Arc::make_mut(c).last_mut())
+    }
+
+    /// Append an element to the back of the vector.
+    pub(crate) fn push(&mut self, value: T) {
+        match self.chunks.last_mut() {
+            Some(last) if last.len() < CHUNK_SIZE => Arc::make_mut(last).push(value),
+            _ => {
+                let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+                chunk.push(value);
+                self.chunks.push(Arc::new(chunk));
+            }
+        }
+    }
+
+    /// Remove the last element and return it, or `None` if the vector is empty.
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        let last = self.chunks.last_mut()?;
+        let value = Arc::make_mut(last).pop();
+        if last.is_empty() {
+            self.chunks.pop();
+        }
+        value
+    }
+
+    /// Remove all elements.
+    pub(crate) fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Iterate over all elements, from the first to the last.
+    #[cfg(test)]
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &T> {
+        self.chunks.iter().flat_map(|c| c.iter())
+    }
+}
+
+impl<T: Clone> From<Vec<T>> for SharedVec<T> {
+    fn from(values: Vec<T>) -> Self {
+        let mut chunks = Vec::with_capacity(values.len().div_ceil(CHUNK_SIZE));
+        let mut values = values.into_iter().peekable();
+        while values.peek().is_some() {
+            chunks.push(Arc::new(values.by_ref().take(CHUNK_SIZE).collect()));
+        }
+        Self { chunks }
+    }
+}
+
+impl<T: Clone> Index<usize> for SharedVec<T> {
+    type Output = T;
+
+    fn index(&self, idx: usize) -> &T {
+        &self.chunks[idx / CHUNK_SIZE][idx % CHUNK_SIZE]
+    }
+}
+
+impl<T: Clone> IndexMut<usize> for SharedVec<T> {
+    /// Returns a mutable reference to the element at position `idx`. If its chunk is shared, it is
+    /// copied first.
+    fn index_mut(&mut self, idx: usize) -> &mut T {
+        &mut Arc::make_mut(&mut self.chunks[idx / CHUNK_SIZE])[idx % CHUNK_SIZE]
+    }
+}
+
+/// Take the value out of the pointer, copying it if it is still shared.
+fn into_inner<V: SnapshotClone>(value: Arc<V>) -> V {
+    Arc::try_unwrap(value).unwrap_or_else(|value| value.snapshot_clone())
+}
+
+/// Make sure that the value is not shared, by copying it if necessary.
+fn make_unique<V: SnapshotClone>(value: &mut Arc<V>) -> &mut V {
+    if Arc::get_mut(value).is_none() {
+        *value = Arc::new(value.snapshot_clone());
+    }
+    Arc::get_mut(value).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Device {
+        value: u32,
+        undo: Vec<u32>,
+    }
+
+    impl SnapshotClone for Device {
+        fn snapshot_clone(&self) -> Self {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let mut map: CowMap<u32, Device> = CowMap::new();
+        map.insert(0, Device { value: 0, undo: vec![] });
+        map.insert(1, Device { value: 1, undo: vec![] });
+        assert!(!map.is_shared(&0));
+
+        let snapshot = map.clone();
+        assert!(map.is_shared(&0));
+        assert!(map.is_shared(&1));
+
+        // modifying a value only copies this value
+        let d = map.get_mut(&0).unwrap();
+        d.undo.push(d.value);
+        d.value = 10;
+        assert!(!map.is_shared(&0));
+        assert!(map.is_shared(&1));
+        assert_eq!(map[&0], Device { value: 10, undo: vec![0] });
+        assert_eq!(snapshot[&0], Device { value: 0, undo: vec![] });
+        assert_eq!(map.get(&1), snapshot.get(&1));
+        assert_eq!(map.changed_keys(&snapshot), vec![0]);
+
+        // iterating mutably copies all shared values
+        map.values_mut().for_each(|d| d.value += 1);
+        assert!(!map.is_shared(&1));
+        assert_eq!(map[&1].value, 2);
+        assert_eq!(snapshot[&1].value, 1);
+        assert_eq!(map.len(), 2);
+        assert_eq!(snapshot.len(), 2);
+
+        // removing and inserting values does not affect the snapshot
+        let mut map = snapshot.clone();
+        assert_eq!(map, snapshot);
+        assert_eq!(map.remove(&0), Some(Device { value: 0, undo: vec![] }));
+        assert_eq!(map.remove(&0), None);
+        assert!(map != snapshot);
+        map.get_or_insert_with(2, || Device { value: 2, undo: vec![] }).value += 1;
+        assert_eq!(map.insert(2, Device { value: 5, undo: vec![] }).map(|d| d.value), Some(3));
+        assert!(map.shares_value(&snapshot, &1));
+        assert_eq!(snapshot.iter().map(|(k, _)| *k).max(), Some(1));
+        assert_eq!(map.iter().map(|(k, _)| *k).max(), Some(2));
+    }
+
+    #[test]
+    fn shared_vec() {
+        let mut v: SharedVec<usize> = SharedVec::new();
+        assert!(v.is_empty());
+        (0..150).for_each(|i| v.push(i));
+        assert_eq!(v.len(), 150);
+        assert_eq!(v.get(70), Some(&70));
+        assert_eq!(v.get(150), None);
+
+        let snapshot = v.clone();
+        // only the last chunk is copied, all others are still shared
+        *v.last_mut().unwrap() = 1000;
+        assert!(Arc::ptr_eq(&v.chunks[0], &snapshot.chunks[0]));
+        assert!(!Arc::ptr_eq(&v.chunks[2], &snapshot.chunks[2]));
+        assert_eq!(v.get(149), Some(&1000));
+        assert_eq!(snapshot.get(149), Some(&149));
+
+        // popping over a chunk boundary
+        (0..30).for_each(|_| {
+            v.pop();
+        });
+        assert_eq!(v.len(), 120);
+        assert_eq!(v.pop(), Some(119));
+        assert_eq!(snapshot.len(), 150);
+        assert_eq!(snapshot.iter().copied().collect::<Vec<_>>(), (0..150).collect::<Vec<_>>());
+        v.clear();
+        assert!(v.is_empty());
+        assert_eq!(v.pop(), None);
+
+        // indexing only copies the chunk that is modified
+        let mut v: SharedVec<usize> = (0..150).collect::<Vec<_>>().into();
+        assert_eq!(v.len(), 150);
+        let snapshot = v.clone();
+        v[70] += 1000;
+        assert_eq!((v[70], snapshot[70]), (1070, 70));
+        assert!(Arc::ptr_eq(&v.chunks[0], &snapshot.chunks[0]));
+        assert!(!Arc::ptr_eq(&v.chunks[1], &snapshot.chunks[1]));
+    }
+}
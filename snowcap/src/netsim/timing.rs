@@ -0,0 +1,315 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! # Timed Simulation
+//!
+//! By default, the network processes all events in FIFO order, without any notion of time. If a
+//! [`TimingModel`] is set with
+//! [`Network::set_timing_model`](crate::netsim::Network::set_timing_model), the network becomes a
+//! discrete-event simulator instead. Every BGP message is assigned the time at which it is
+//! received, and messages are processed in the order of their reception time:
+//!
+//! - A router sends a message after its processing delay, counted from the time at which it has
+//!   processed the event causing the message.
+//! - BGP updates from one router to the same peer are sent at most once per MRAI (Minimum Route
+//!   Advertisement Interval). Later updates are delayed (but not merged). Withdraws are not
+//!   affected by the MRAI.
+//! - A message is received after the propagation delay of all links along the IGP path between
+//!   the two routers. Messages of the same session are always received in the order in which they
+//!   are sent (like with TCP).
+//! - IGP changes (link weights, areas or failures) are applied to all routers immediately. However,
+//!   each router sends the BGP messages that are caused by an IGP change only after its SPF delay.
+//!
+//! While converging, the network records a [`TimedTrace`] containing the forwarding state at the
+//! start, and every time it changes. It can be used to estimate how long a transient violation of
+//! a [`Condition`] actually lasts.
+
+use crate::hard_policies::Condition;
+use crate::netsim::bgp::BgpEvent;
+use crate::netsim::ospf::link_key;
+use crate::netsim::{Event, ForwardingState, NetworkError, RouterId};
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+
+/// # Timing Model
+///
+/// Delays used for the timed simulation. All delays are given in seconds. Each kind of delay has a
+/// default value, which can be overwritten for individual links or routers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimingModel {
+    /// Propagation delay of a link, if not set for the specific link. The default is 1ms.
+    pub default_link_delay: f64,
+    /// Processing delay of a router, if not set for the specific router. The default is 0.
+    pub default_processing_delay: f64,
+    /// MRAI of a router, if not set for the specific router. The default is 0 (disabled).
+    pub default_mrai: f64,
+    /// SPF delay of a router, if not set for the specific router. The default is 0.
+    pub default_spf_delay: f64,
+    link_delays: HashMap<(RouterId, RouterId), f64>,
+    processing_delays: HashMap<RouterId, f64>,
+    mrai: HashMap<RouterId, f64>,
+    spf_delays: HashMap<RouterId, f64>,
+}
+
+impl Default for TimingModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimingModel {
+    /// Create a new timing model with only the default delays.
+    pub fn new() -> Self {
+        Self {
+            default_link_delay: 0.001,
+            default_processing_delay: 0.0,
+            default_mrai: 0.0,
+            default_spf_delay: 0.0,
+            link_delays: HashMap::new(),
+            processing_delays: HashMap::new(),
+            mrai: HashMap::new(),
+            spf_delays: HashMap::new(),
+        }
+    }
+
+    /// Set the propagation delay of the link between `a` and `b` (in both directions).
+    pub fn set_link_delay(&mut self, a: RouterId, b: RouterId, delay: f64) -> &mut Self {
+        self.link_delays.insert(link_key(a, b), delay);
+        self
+    }
+
+    /// Set the processing delay of a router.
+    pub fn set_processing_delay(&mut self, router: RouterId, delay: f64) -> &mut Self {
+        self.processing_delays.insert(router, delay);
+        self
+    }
+
+    /// Set the MRAI of a router, which is used for all of its BGP sessions.
+    pub fn set_mrai(&mut self, router: RouterId, mrai: f64) -> &mut Self {
+        self.mrai.insert(router, mrai);
+        self
+    }
+
+    /// Set the SPF delay of a router.
+    pub fn set_spf_delay(&mut self, router: RouterId, delay: f64) -> &mut Self {
+        self.spf_delays.insert(router, delay);
+        self
+    }
+
+    /// Returns the propagation delay of the link between `a` and `b`.
+    pub fn link_delay(&self, a: RouterId, b: RouterId) -> f64 {
+        *self.link_delays.get(&link_key(a, b)).unwrap_or(&self.default_link_delay)
+    }
+
+    /// Returns the processing delay of a router.
+    pub fn processing_delay(&self, router: RouterId) -> f64 {
+        *self.processing_delays.get(&router).unwrap_or(&self.default_processing_delay)
+    }
+
+    /// Returns the MRAI of a router.
+    pub fn mrai(&self, router: RouterId) -> f64 {
+        *self.mrai.get(&router).unwrap_or(&self.default_mrai)
+    }
+
+    /// Returns the SPF delay of a router.
+    pub fn spf_delay(&self, router: RouterId) -> f64 {
+        *self.spf_delays.get(&router).unwrap_or(&self.default_spf_delay)
+    }
+
+    /// Check that all delays are finite and not negative. Otherwise, `NetworkError::InvalidDelay`
+    /// is returned, containing the first invalid delay.
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        let defaults = [
+            self.default_link_delay,
+            self.default_processing_delay,
+            self.default_mrai,
+            self.default_spf_delay,
+        ];
+        let delays = defaults
+            .iter()
+            .chain(self.link_delays.values())
+            .chain(self.processing_delays.values())
+            .chain(self.mrai.values())
+            .chain(self.spf_delays.values());
+        for delay in delays {
+            if !delay.is_finite() || *delay < 0.0 {
+                return Err(NetworkError::InvalidDelay(*delay));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// # Timed Trace
+///
+/// Sequence of forwarding states recorded during a timed simulation, together with the time at
+/// which they were reached. The first entry is the state at the start of the convergence process,
+/// and the last one is the converged state.
+#[derive(Debug, Clone, Default)]
+pub struct TimedTrace {
+    states: Vec<(f64, ForwardingState)>,
+}
+
+impl TimedTrace {
+    /// Add a new forwarding state, reached at time `time`.
+    pub(crate) fn push(&mut self, time: f64, state: ForwardingState) {
+        self.states.push((time, state))
+    }
+
+    /// Returns the number of recorded forwarding states.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Returns `true` if no forwarding state was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Iterate over all recorded forwarding states, together with the time at which they were
+    /// reached.
+    pub fn iter(&self) -> impl Iterator<Item = &(f64, ForwardingState)> {
+        self.states.iter()
+    }
+
+    /// Returns the time of the last change in the forwarding state, i.e., the time at which the
+    /// network has converged.
+    pub fn convergence_time(&self) -> f64 {
+        self.states.last().map(|(t, _)| *t).unwrap_or(0.0)
+    }
+
+    /// Returns the total amount of time during which `condition` is violated before the network
+    /// has converged. The converged state (the last one in the trace) is not considered, since it
+    /// lasts forever.
+    pub fn violation_time(&self, condition: &Condition) -> f64 {
+        self.states
+            .windows(2)
+            .filter(|w| condition.check(&mut w[0].1.clone()).is_err())
+            .map(|w| w[1].0 - w[0].0)
+            .sum()
+    }
+}
+
+/// Reception time of an event, which is totally ordered. All delays of a valid [`TimingModel`] are
+/// finite, and so are the reception times.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OrderedTime(f64);
+
+impl Eq for OrderedTime {}
+
+impl PartialOrd for OrderedTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Event in the [`EventScheduler`], ordered by the reception time and the sequence number.
+#[derive(Debug)]
+struct ScheduledEvent {
+    time: OrderedTime,
+    seq: usize,
+    event: Event,
+    parent_event_id: usize,
+}
+
+impl PartialEq for ScheduledEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ScheduledEvent {}
+
+impl PartialOrd for ScheduledEvent {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScheduledEvent {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.time, self.seq).cmp(&(other.time, other.seq))
+    }
+}
+
+/// Queue of events that are processed in the order of their reception time. Events with the same
+/// reception time are processed in the order in which they were scheduled.
+#[derive(Debug, Default)]
+pub(crate) struct EventScheduler {
+    /// Events ordered by the reception time (earliest first), and a sequence number.
+    events: BinaryHeap<Reverse<ScheduledEvent>>,
+    /// Number of events scheduled so far.
+    num_scheduled: usize,
+    /// Time of the last update sent over each session (for the MRAI).
+    last_update: HashMap<(RouterId, RouterId), f64>,
+    /// Reception time of the last message of each session (to keep TCP ordering).
+    last_reception: HashMap<(RouterId, RouterId), f64>,
+}
+
+impl EventScheduler {
+    /// Schedule an event that was created at time `now`. `session_delay` is the propagation delay
+    /// between the sender and the receiver of the message.
+    pub(crate) fn schedule(
+        &mut self,
+        timing: &TimingModel,
+        now: f64,
+        event: Event,
+        parent_event_id: usize,
+        session_delay: f64,
+    ) {
+        let time = match &event {
+            Event::Bgp(from, to, bgp_event) => {
+                let session = (*from, *to);
+                let mut departure = now + timing.processing_delay(*from);
+                if let BgpEvent::Update(_) = bgp_event {
+                    if let Some(last) = self.last_update.get(&session) {
+                        departure = departure.max(last + timing.mrai(*from));
+                    }
+                    self.last_update.insert(session, departure);
+                }
+                let mut reception = departure + session_delay;
+                if let Some(last) = self.last_reception.get(&session) {
+                    reception = reception.max(*last);
+                }
+                self.last_reception.insert(session, reception);
+                reception
+            }
+            _ => now,
+        };
+        let seq = self.num_scheduled;
+        self.events.push(Reverse(ScheduledEvent {
+            time: OrderedTime(time),
+            seq,
+            event,
+            parent_event_id,
+        }));
+        self.num_scheduled += 1;
+    }
+
+    /// Remove the event with the earliest reception time, and return it together with its time.
+    pub(crate) fn pop(&mut self) -> Option<(f64, Event, usize)> {
+        let Reverse(e) = self.events.pop()?;
+        Some((e.time.0, e.event, e.parent_event_id))
+    }
+}
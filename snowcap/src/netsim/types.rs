@@ -26,29 +26,202 @@ use crate::netsim::router::Router;
 use petgraph::prelude::*;
 // uses rust's built-in graph structure library
 use petgraph::stable_graph::StableGraph;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
 use thiserror::Error;
 
 type IndexType = u32;
 /// Router Identification (and index into the graph)
 pub type RouterId = NodeIndex<IndexType>;
-/// IP Prefix (simple representation)
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
-pub struct Prefix(pub u32);
+/// # IP Prefix
+///
+/// An IPv4 or IPv6 prefix, given by its network address and its length. The host bits of the
+/// address are always cleared, such that two prefixes are equal if and only if they describe the
+/// same range of addresses. Prefixes can be parsed from the usual notation:
+///
+/// ```
+/// # use snowcap::netsim::Prefix;
+/// let p: Prefix = "10.0.0.0/8".parse().unwrap();
+/// let q: Prefix = "10.1.0.0/16".parse().unwrap();
+/// assert!(p.contains(&q));
+/// assert!(!q.contains(&p));
+/// assert_eq!(p.to_string(), "10.0.0.0/8");
+/// ```
+///
+/// For networks in which prefixes are only used as identifiers, a prefix can also be created from a
+/// number using `Prefix::from(x)`. This results in the IPv4 prefix of length 24, whose network
+/// address holds `x` in the upper 24 bits (e.g., `Prefix::from(1)` is `0.0.1.0/24`). Prefixes
+/// created from different numbers never overlap. The number must be smaller than `2^24`; use
+/// [`Prefix::try_from_id`] to handle larger numbers without panicking.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Prefix {
+    addr: IpAddr,
+    len: u8,
+}
+
+impl Prefix {
+    /// Create a new prefix. All bits of the address after the prefix length are cleared.
+    ///
+    /// # Panics
+    /// This function panics if `len` is larger than 32 for IPv4, or larger than 128 for IPv6.
+    pub fn new(addr: impl Into<IpAddr>, len: u8) -> Self {
+        Self::try_new(addr.into(), len).expect("Invalid prefix length")
+    }
+
+    fn try_new(addr: IpAddr, len: u8) -> Result<Self, PrefixError> {
+        let addr = match addr {
+            IpAddr::V4(a) if len <= 32 => {
+                let mask = u32::MAX.checked_shl(32 - len as u32).unwrap_or(0);
+                IpAddr::V4((u32::from(a) & mask).into())
+            }
+            IpAddr::V6(a) if len <= 128 => {
+                let mask = u128::MAX.checked_shl(128 - len as u32).unwrap_or(0);
+                IpAddr::V6((u128::from(a) & mask).into())
+            }
+            _ => return Err(PrefixError::InvalidLength(len)),
+        };
+        Ok(Self { addr, len })
+    }
+
+    /// Create the IPv4 prefix of length 24 from a number, as `Prefix::from(x)` does. This function
+    /// returns an error if `x` does not fit into the upper 24 bits of the address.
+    pub fn try_from_id(x: u32) -> Result<Self, PrefixError> {
+        if x < 1 << 24 {
+            Ok(Self { addr: IpAddr::V4(Ipv4Addr::from(x << 8)), len: 24 })
+        } else {
+            Err(PrefixError::IdOutOfRange(x))
+        }
+    }
+
+    /// Returns the network address of the prefix.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Returns the length of the prefix (number of fixed bits).
+    pub fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    /// Returns `true` if this is an IPv4 prefix.
+    pub fn is_ipv4(&self) -> bool {
+        self.addr.is_ipv4()
+    }
+
+    /// Returns `true` if this is an IPv6 prefix.
+    pub fn is_ipv6(&self) -> bool {
+        self.addr.is_ipv6()
+    }
+
+    /// Returns `true` if `other` is contained in `self`, i.e., if `other` is equal to `self` or a
+    /// more-specific prefix of it. Prefixes of different address families never contain each
+    /// other.
+    pub fn contains(&self, other: &Prefix) -> bool {
+        self.is_ipv4() == other.is_ipv4()
+            && self.len <= other.len
+            && Self::new(other.addr, self.len).addr == self.addr
+    }
+}
+
+impl From<u32> for Prefix {
+    /// Create the IPv4 prefix of length 24 from a number (see [`Prefix::try_from_id`]).
+    ///
+    /// # Panics
+    /// This function panics if `x` is not smaller than `2^24`.
+    fn from(x: u32) -> Self {
+        Self::try_from_id(x).expect("Prefix number out of range")
+    }
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.len)
+    }
+}
+
+impl fmt::Debug for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl FromStr for Prefix {
+    type Err = PrefixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '/');
+        let addr = parts.next().unwrap_or_default();
+        let addr: IpAddr = addr.parse().map_err(|_| PrefixError::InvalidAddress(s.to_string()))?;
+        let len = match parts.next() {
+            Some(len) => len.parse().map_err(|_| PrefixError::InvalidAddress(s.to_string()))?,
+            None if addr.is_ipv4() => 32,
+            None => 128,
+        };
+        Self::try_new(addr, len)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Prefix {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Prefix {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// Prefixes are stored either as string, or as a number (see `Prefix::from`).
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum PrefixRepr {
+            Id(u32),
+            Str(String),
+        }
+        match PrefixRepr::deserialize(deserializer)? {
+            PrefixRepr::Id(x) => Prefix::try_from_id(x).map_err(serde::de::Error::custom),
+            PrefixRepr::Str(s) => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
 /// AS Number
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AsId(pub u32);
+/// # OSPF Area
+///
+/// Identifier of an OSPF area (or, equivalently, an IS-IS level-1 area with the backbone as the
+/// level-2 domain). Area `0` is the backbone, through which all inter-area traffic is routed.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OspfArea(pub u32);
+
+impl OspfArea {
+    /// The backbone area (area `0`)
+    pub const BACKBONE: Self = Self(0);
+
+    /// Returns `true` if the area is the backbone area.
+    pub fn is_backbone(&self) -> bool {
+        *self == Self::BACKBONE
+    }
+}
+
+impl fmt::Display for OspfArea {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Link Weight for the IGP graph
 pub type LinkWeight = f32;
 /// IGP Network graph
 pub type IgpNetwork = StableGraph<(), LinkWeight, Directed, IndexType>;
-#[derive(Debug, Clone)]
-/// Define ACL behavior here
-pub enum ACL {
-    /// Traffic is accepted
-    Accept,
-    /// Traffic is denied
-    Deny,
-}
+/// Identifier of a snapshot of the network, created by [`crate::netsim::Network::snapshot`].
+pub type SnapshotId = usize;
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
 /// Define destination here
 pub enum Destination {
@@ -183,6 +356,20 @@ impl<'a> NetworkDevice<'a> {
     }
 }
 
+/// Errors when creating or parsing a prefix
+#[derive(Error, Debug, PartialEq)]
+pub enum PrefixError {
+    /// The prefix length is larger than the address
+    #[error("Invalid prefix length: {0}")]
+    InvalidLength(u8),
+    /// The string is not a valid prefix
+    #[error("Invalid prefix: {0}")]
+    InvalidAddress(String),
+    /// The number does not fit into the upper 24 bits of an IPv4 address
+    #[error("Prefix number out of range: {0}")]
+    IdOutOfRange(u32),
+}
+
 /// Router Errors
 #[derive(Error, Debug, PartialEq)]
 pub enum DeviceError {
@@ -204,6 +391,18 @@ pub enum DeviceError {
     /// Static Route doesn't exists
     #[error("Static route for {0:?} does not yet exist")]
     NoStaticRoute(Prefix),
+    /// The prefix is already announced with a network statement
+    #[error("BGP network statement for {0:?} does already exist")]
+    BgpNetworkAlreadyExists(Prefix),
+    /// The prefix is not announced with a network statement
+    #[error("BGP network statement for {0:?} does not yet exist")]
+    NoBgpNetwork(Prefix),
+    /// The router already creates an aggregate for the prefix
+    #[error("BGP aggregate for {0:?} does already exist")]
+    BgpAggregateAlreadyExists(Prefix),
+    /// The router does not create an aggregate for the prefix
+    #[error("BGP aggregate for {0:?} does not yet exist")]
+    NoBgpAggregate(Prefix),
     /// Bgp Route Map with the same order already exists
     #[error("Bgp Route Map at order {0} already exists")]
     BgpRouteMapAlreadyExists(usize),
@@ -213,9 +412,6 @@ pub enum DeviceError {
     /// This ACL rule does not exist!
     #[error("Router {0:?} does not have this ACL rule")]
     AclDoesNotExist(RouterId),
-    /// Unknow error for ACL rules
-    #[error("Invalid ACL rules")]
-    AclInvalid,
     /// Bgp Route Map with the chosen order does not exist
     #[error("Bgp Route Map at order {0} doesn't exists")]
     NoBgpRouteMap(usize),
@@ -286,4 +482,10 @@ pub enum NetworkError {
     /// probability in [`Network::apply_modifier_check_transient`])
     #[error("No events to reorder")]
     NoEventsToReorder,
+    /// The snapshot does not exist
+    #[error("Snapshot was not found: {0}")]
+    SnapshotNotFound(SnapshotId),
+    /// A delay of the timing model is negative, infinite or NaN
+    #[error("Invalid delay in the timing model: {0}")]
+    InvalidDelay(f64),
 }
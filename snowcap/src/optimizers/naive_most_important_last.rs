@@ -168,13 +168,31 @@ fn modifier_cost(m: &ConfigModifier, flows: &[Vec<RouterId>]) -> (ModifierGroup,
             ModifierGroup::Pair,
             flows.iter().filter(|f| f.contains(&speaker_a) || f.contains(&speaker_b)).count(),
         ),
-        ConfigExprKey::BgpRouteMap { router, .. } | ConfigExprKey::StaticRoute { router, .. } => {
+        ConfigExprKey::BgpRouteMap { router, .. }
+        | ConfigExprKey::StaticRoute { router, .. }
+        | ConfigExprKey::BgpNetwork { router, .. }
+        | ConfigExprKey::BgpRedistribute { router, .. }
+        | ConfigExprKey::BgpAggregate { router, .. }
+        | ConfigExprKey::BgpDecision { router }
+        | ConfigExprKey::BgpClusterId { router } => {
             (ModifierGroup::Node, flows.iter().filter(|f| f.contains(&router)).count())
         }
         ConfigExprKey::AccessControl { .. } => {
             // TODO
             (ModifierGroup::Node, 0)
         }
+        ConfigExprKey::IgpLinkArea { router_a, router_b } => (
+            ModifierGroup::Link,
+            flows
+                .iter()
+                .filter(|f| {
+                    path_contains_edge(router_a, router_b, f).is_some()
+                        || path_contains_edge(router_b, router_a, f).is_some()
+                })
+                .count(),
+        ),
+        // changing the area type may change the paths of all flows leaving the network.
+        ConfigExprKey::OspfStubArea { .. } => (ModifierGroup::Link, flows.len()),
     }
 }
 
@@ -18,7 +18,9 @@
 //! Soft Policy to minimize traffic shit
 
 use super::SoftPolicy;
-use crate::netsim::{ForwardingState, Network, Prefix, RouterId};
+use crate::netsim::types::Destination;
+use crate::netsim::{ForwardingChanges, ForwardingState, Network, NetworkDevice, Prefix, RouterId};
+use std::collections::HashMap;
 
 /// # Soft Policy: Minimize Traffic Shift
 ///
@@ -28,7 +30,7 @@ use crate::netsim::{ForwardingState, Network, Prefix, RouterId};
 #[derive(Clone, Debug)]
 pub struct MinimizeTrafficShift {
     current_next_hops: Vec<Option<RouterId>>,
-    prefix_lookup: Vec<(Prefix, usize)>,
+    prefix_lookup: HashMap<Prefix, usize>,
     num_prefixes: usize,
     num_routers: usize,
     num_different: usize,
@@ -36,7 +38,7 @@ pub struct MinimizeTrafficShift {
 
 impl SoftPolicy for MinimizeTrafficShift {
     fn new(state: &mut ForwardingState, net: &Network) -> Self {
-        let prefix_lookup: Vec<(Prefix, usize)> =
+        let prefix_lookup: HashMap<Prefix, usize> =
             net.get_known_prefixes().iter().cloned().enumerate().map(|(i, p)| (p, i)).collect();
         let num_prefixes = prefix_lookup.len();
         let num_routers = net.get_routers().len();
@@ -49,7 +51,7 @@ impl SoftPolicy for MinimizeTrafficShift {
         for r in net.get_routers() {
             for (p, pid) in prefix_lookup.iter() {
                 let idx = get_idx(r.index(), *pid, num_prefixes);
-                current_next_hops[idx] = state.get_next_hop(r, *p).unwrap();
+                current_next_hops[idx] = next_hop(state, r, *p);
             }
         }
 
@@ -61,7 +63,7 @@ impl SoftPolicy for MinimizeTrafficShift {
         for router in net.get_routers() {
             for (p, pid) in self.prefix_lookup.iter() {
                 let idx = get_idx(router.index(), *pid, self.num_prefixes);
-                let new_next_hop = state.get_next_hop(router, *p).unwrap();
+                let new_next_hop = next_hop(state, router, *p);
                 let old_next_hop = self.current_next_hops[idx];
                 if new_next_hop.is_some() && old_next_hop.is_some() && new_next_hop != old_next_hop
                 {
@@ -73,6 +75,39 @@ impl SoftPolicy for MinimizeTrafficShift {
         self.num_different = count;
     }
 
+    fn update_incremental(
+        &mut self,
+        state: &mut ForwardingState,
+        net: &Network,
+        changes: &ForwardingChanges,
+    ) {
+        if changes.is_all() {
+            return self.update(state, net);
+        }
+        // only the changed entries can be different from the previous state
+        let mut count: usize = 0;
+        for (router, dest) in changes.iter() {
+            let (p, pid) = match dest {
+                Destination::BGP(p) => match self.prefix_lookup.get(p) {
+                    Some(pid) => (*p, *pid),
+                    None => continue,
+                },
+                Destination::IGP(_) => continue,
+            };
+            if !matches!(net.get_device(*router), NetworkDevice::InternalRouter(_)) {
+                continue;
+            }
+            let idx = get_idx(router.index(), pid, self.num_prefixes);
+            let new_next_hop = next_hop(state, *router, p);
+            let old_next_hop = self.current_next_hops[idx];
+            if new_next_hop.is_some() && old_next_hop.is_some() && new_next_hop != old_next_hop {
+                count += 1;
+            }
+            self.current_next_hops[idx] = new_next_hop;
+        }
+        self.num_different = count;
+    }
+
     fn cost(&self) -> f64 {
         let total_next_hops = self.num_routers * self.num_prefixes;
         (self.num_different as f64) / (total_next_hops as f64)
@@ -82,3 +117,8 @@ impl SoftPolicy for MinimizeTrafficShift {
 fn get_idx(rid: usize, pid: usize, n_prefixes: usize) -> usize {
     rid * n_prefixes + pid
 }
+
+/// Returns the next hop of a router towards a prefix.
+fn next_hop(state: &ForwardingState, router: RouterId, prefix: Prefix) -> Option<RouterId> {
+    state.get_next_hops_new(router, Destination::BGP(prefix)).unwrap().first().copied()
+}
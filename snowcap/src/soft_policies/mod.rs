@@ -21,7 +21,7 @@
 //! better is the solution which is found.
 
 use crate::netsim::config::ConfigModifier;
-use crate::netsim::{ForwardingState, Network, NetworkError};
+use crate::netsim::{ForwardingChanges, ForwardingState, Network, NetworkError};
 
 mod minimize_traffic_shift;
 pub use minimize_traffic_shift::MinimizeTrafficShift;
@@ -35,6 +35,19 @@ pub trait SoftPolicy {
     /// is applied.
     fn update(&mut self, state: &mut ForwardingState, net: &Network);
 
+    /// Update the information in the SoftPolicy, given the set of entries that have changed since
+    /// the last update (see [`ForwardingState::update`]). Policies that can use the changes to
+    /// avoid checking the entire forwarding state should overwrite this function. By default, it
+    /// calls [`SoftPolicy::update`].
+    fn update_incremental(
+        &mut self,
+        state: &mut ForwardingState,
+        net: &Network,
+        _changes: &ForwardingChanges,
+    ) {
+        self.update(state, net)
+    }
+
     /// Compute the score based on the information gathered by several calls to update. The output
     /// of this funciton is between 0 and 1, and lower is better.
     fn cost(&self) -> f64;
@@ -48,11 +61,14 @@ pub fn compute_cost<P: SoftPolicy>(
 ) -> Result<f64, NetworkError> {
     let mut net = net.clone();
     let mut cost: f64 = 0.0;
-    let mut p = P::new(&mut net.get_forwarding_state(), &net);
+    let mut state = net.get_forwarding_state();
+    net.clear_forwarding_changes();
+    let mut p = P::new(&mut state, &net);
 
     for m in modifiers.iter() {
         net.apply_modifier(m)?;
-        p.update(&mut net.get_forwarding_state(), &net);
+        let changes = state.update(&mut net);
+        p.update_incremental(&mut state, &net, &changes);
         cost += p.cost();
     }
 
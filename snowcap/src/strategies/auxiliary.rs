@@ -0,0 +1,512 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! # The Auxiliary Strategy
+
+use super::{Strategy, StrategyTRTA};
+use crate::hard_policies::{Condition, HardPolicy};
+use crate::netsim::bgp::BgpEvent;
+use crate::netsim::config::{ConfigExpr, ConfigExprKey, ConfigModifier};
+use crate::netsim::route_map::{RouteMapBuilder, RouteMapDirection, RouteMapMatch};
+use crate::netsim::types::Destination;
+use crate::netsim::{
+    BgpSessionType, Event, ForwardingState, Network, NetworkDevice, NetworkError, Prefix, RouterId,
+};
+use crate::{Error, Stopper};
+
+use log::*;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::time::{Duration, SystemTime};
+
+/// Order of the route-maps in the built-in catalogue. They are applied before any other route-map.
+const AUX_ROUTE_MAP_ORDER: usize = 0;
+/// Local preference set by the route-maps in the built-in catalogue.
+const AUX_LOCAL_PREF: u32 = 1000;
+
+/// Routers involved in a violated condition, together with its prefix (`None` for IGP conditions).
+type Violation = (Vec<RouterId>, Option<Prefix>);
+
+/// # The Auxiliary Strategy
+///
+/// Some reconfigurations have no safe ordering at all, since every ordering of the modifiers
+/// violates the hard policy in some intermediate state (see e.g.,
+/// [`CarouselGadget`](crate::example_networks::CarouselGadget)). Operators solve such problems by
+/// adding temporary configuration, which is removed again at the end. This strategy does the same:
+/// If the strategy `S` finds no valid ordering of the modifiers, it tries to insert auxiliary
+/// configuration from a catalogue. The resulting sequence looks as follows:
+///
+/// 1. Insert the auxiliary configuration, i.e., go from `start` to `start + aux`.
+/// 2. Apply all modifiers, together with the removal of the auxiliary configuration, in the
+///    ordering found by the strategy `S`.
+///
+/// Every entry of the catalogue is a set of configuration expressions, which are inserted
+/// together. Entries of which any expression collides with the start configuration or with the
+/// modifiers (i.e., it has the same [`ConfigExprKey`]) are ignored. The remaining entries are
+/// ranked by their relevance for the failure without auxiliary configuration, and entries which are
+/// not relevant at all are removed. Then, the entries are tried one after the other, until a valid
+/// sequence is found, sharing the remaining time budget equally.
+///
+/// To rank the entries, the strategy collects the conditions violated in the intermediate states
+/// where the problem gets stuck. To find them, the modifiers are applied greedily, until no
+/// modifier can be applied anymore without violating the hard policy. Then, all states reached by
+/// applying one of the remaining modifiers are considered, together with the state described by
+/// the [`UnsatExplanation`](crate::UnsatExplanation), if `S` returns one. Every router on the
+/// forwarding path of a violated condition is weighted by the number of such paths it is part of.
+/// If one of the remaining modifiers causes BGP to oscillate, the next hops of the routes
+/// advertised in the oscillation are weighted in the same way. An entry is relevant if one of its
+/// expressions is applied on such a router (or a route-map matches on it as a neighbor), and does
+/// not only match on prefixes of other conditions. Entries are then sorted by the sum of the weights. If no
+/// violated condition can be found, the catalogue is tried in its original order.
+///
+/// When created with [`Strategy::new`], the built-in catalogue (see [`builtin_catalogue`]) is used.
+/// A custom catalogue can be supplied using [`AuxiliaryStrategy::with_catalogue`].
+///
+/// *Note*: The strategy `S` checks the hard policy starting at the state `start + aux`. Temporal
+/// properties spanning both the insertion of the auxiliary configuration and the remaining
+/// sequence are therefore only checked partially.
+///
+/// ## Type Arguments
+/// - `S` represents the chosen [`Strategy`], used to order the modifiers. By default, this is
+///   [`StrategyTRTA`].
+pub struct AuxiliaryStrategy<S = StrategyTRTA> {
+    net: Network,
+    modifiers: Vec<ConfigModifier>,
+    hard_policy: HardPolicy,
+    catalogue: Vec<Vec<ConfigExpr>>,
+    stop_time: Option<SystemTime>,
+    phantom: PhantomData<S>,
+    #[cfg(feature = "count-states")]
+    num_states: usize,
+}
+
+impl<S> Strategy for AuxiliaryStrategy<S>
+where
+    S: Strategy,
+{
+    fn new(
+        net: Network,
+        modifiers: Vec<ConfigModifier>,
+        hard_policy: HardPolicy,
+        time_budget: Option<Duration>,
+    ) -> Result<Box<Self>, Error> {
+        let catalogue = builtin_catalogue(&net, &modifiers);
+        Self::with_catalogue(net, modifiers, hard_policy, time_budget, catalogue)
+    }
+
+    fn work(&mut self, abort: Stopper) -> Result<Vec<ConfigModifier>, Error> {
+        // first, try to solve the problem without any auxiliary configuration
+        let error = match self.solve(&[], self.remaining_time(1), abort.clone()) {
+            Ok(sequence) => return Ok(sequence),
+            Err(e @ Error::NoSafeOrdering)
+            | Err(e @ Error::ProbablyNoSafeOrdering)
+            | Err(e @ Error::Unsat(_)) => e,
+            Err(e) => return Err(e),
+        };
+        info!("No safe ordering without auxiliary configuration. Trying the catalogue...");
+
+        let catalogue = self.rank_catalogue(&error)?;
+        info!("{} of {} catalogue entries are relevant", catalogue.len(), self.catalogue.len());
+
+        for (i, aux) in catalogue.iter().enumerate() {
+            if self.stop_time.as_ref().map(|time| time.elapsed().is_ok()).unwrap_or(false) {
+                error!("Time budget is used up! No solution was found yet!");
+                return Err(Error::Timeout);
+            }
+            // share the remaining time among all remaining entries
+            let time_budget = self.remaining_time(catalogue.len() - i);
+            match self.solve(aux, time_budget, abort.clone()) {
+                Ok(sequence) => {
+                    info!("Found a valid solution using {} auxiliary expressions", aux.len());
+                    return Ok(sequence);
+                }
+                Err(Error::Abort) => return Err(Error::Abort),
+                Err(e) => debug!("Auxiliary configuration {:?} does not help: {}", aux, e),
+            }
+        }
+
+        error!("Could not find any valid ordering, even with auxiliary configuration!");
+        Err(error)
+    }
+
+    #[cfg(feature = "count-states")]
+    fn num_states(&self) -> usize {
+        self.num_states
+    }
+}
+
+impl<S> AuxiliaryStrategy<S>
+where
+    S: Strategy,
+{
+    /// Create the strategy with a custom catalogue of auxiliary configuration. Each entry of the
+    /// catalogue is a set of expressions, which are inserted before and removed after applying the
+    /// modifiers.
+    pub fn with_catalogue(
+        mut net: Network,
+        modifiers: Vec<ConfigModifier>,
+        hard_policy: HardPolicy,
+        time_budget: Option<Duration>,
+        catalogue: Vec<Vec<ConfigExpr>>,
+    ) -> Result<Box<Self>, Error> {
+        // clear the undo stack
+        net.clear_undo_stack();
+
+        // check the initial state
+        let mut initial_policy = hard_policy.clone();
+        initial_policy.set_num_mods_if_none(modifiers.len());
+        let mut fw_state = net.get_forwarding_state();
+        initial_policy.step(&mut net, &mut fw_state)?;
+        if !initial_policy.check() {
+            error!("Initial state is invalid!");
+            return Err(Error::InvalidInitialState);
+        }
+
+        // only keep the entries that do not collide with the existing configuration
+        let mut used_keys: HashSet<ConfigExprKey> =
+            net.current_config().iter().map(|e| e.key()).collect();
+        used_keys.extend(modifiers.iter().map(|m| m.key()));
+        let catalogue = catalogue
+            .into_iter()
+            .filter(|aux| {
+                let keys: HashSet<ConfigExprKey> = aux.iter().map(|e| e.key()).collect();
+                !aux.is_empty() && keys.len() == aux.len() && keys.is_disjoint(&used_keys)
+            })
+            .collect();
+
+        Ok(Box::new(Self {
+            net,
+            modifiers,
+            hard_policy,
+            catalogue,
+            stop_time: time_budget.map(|dur| SystemTime::now() + dur),
+            phantom: PhantomData,
+            #[cfg(feature = "count-states")]
+            num_states: 0,
+        }))
+    }
+
+    /// Insert the auxiliary configuration, and use the strategy `S` to find a valid ordering of the
+    /// modifiers, together with the removal of the auxiliary configuration.
+    fn solve(
+        &mut self,
+        aux: &[ConfigExpr],
+        time_budget: Option<Duration>,
+        abort: Stopper,
+    ) -> Result<Vec<ConfigModifier>, Error> {
+        let mut net = self.net.clone();
+        let mut hard_policy = self.hard_policy.clone();
+        hard_policy.set_num_mods_if_none(self.modifiers.len() + 2 * aux.len());
+        let mut fw_state = net.get_forwarding_state();
+        hard_policy.step(&mut net, &mut fw_state)?;
+
+        // insert the auxiliary configuration
+        let mut sequence: Vec<ConfigModifier> = Vec::with_capacity(aux.len());
+        for expr in aux {
+            let modifier = ConfigModifier::Insert(expr.clone());
+            net.apply_modifier(&modifier)?;
+            let mut fw_state = net.get_forwarding_state();
+            hard_policy.step(&mut net, &mut fw_state)?;
+            if !hard_policy.check() {
+                return Err(Error::NoSafeOrdering);
+            }
+            sequence.push(modifier);
+        }
+
+        // solve the remaining problem
+        let mut modifiers = self.modifiers.clone();
+        modifiers.extend(aux.iter().cloned().map(ConfigModifier::Remove));
+        let mut strategy = S::new(net, modifiers, self.hard_policy.clone(), time_budget)?;
+        let result = strategy.work(abort);
+        #[cfg(feature = "count-states")]
+        {
+            self.num_states += strategy.num_states();
+        }
+        sequence.extend(result?);
+        Ok(sequence)
+    }
+
+    /// Rank the entries of the catalogue by their relevance for the failure without auxiliary
+    /// configuration, described by `error`, and remove the irrelevant ones (see
+    /// [`AuxiliaryStrategy`]).
+    fn rank_catalogue(&self, error: &Error) -> Result<Vec<Vec<ConfigExpr>>, Error> {
+        let mut violations = self.probe_violations()?;
+        if let Error::Unsat(explanation) = error {
+            let mut net = self.net.clone();
+            if explanation.sequence.iter().all(|m| net.apply_modifier(m).is_ok()) {
+                let mut fw_state = net.get_forwarding_state();
+                violations.extend(violated_paths(&mut fw_state, &explanation.conditions));
+            }
+        }
+
+        let mut weights: HashMap<RouterId, usize> = HashMap::new();
+        let mut prefixes: HashSet<Prefix> = HashSet::new();
+        for (path, prefix) in violations {
+            prefixes.extend(prefix);
+            for r in path.into_iter().collect::<HashSet<RouterId>>() {
+                *weights.entry(r).or_default() += 1;
+            }
+        }
+
+        if weights.is_empty() {
+            warn!("No violated condition found! Trying all entries of the catalogue.");
+            return Ok(self.catalogue.clone());
+        }
+        let mut ranked: Vec<(usize, &Vec<ConfigExpr>)> = self
+            .catalogue
+            .iter()
+            .map(|aux| (relevance(aux, &weights, &prefixes), aux))
+            .filter(|(r, _)| *r > 0)
+            .collect();
+        // the sort is stable, keeping the order of the catalogue for equally relevant entries
+        ranked.sort_by_key(|(r, _)| Reverse(*r));
+        Ok(ranked.into_iter().map(|(_, aux)| aux.clone()).collect())
+    }
+
+    /// Apply the modifiers greedily, always taking the first one after which the hard policy is
+    /// still satisfied, until no modifier can be applied anymore. Returns the violated conditions
+    /// (see [`violated_paths`]) of all states reached by applying one of the remaining modifiers
+    /// in this last state, and the oscillating routes (see [`oscillating_routes`]) of those which
+    /// do not converge.
+    fn probe_violations(&self) -> Result<Vec<Violation>, Error> {
+        let mut net = self.net.clone();
+        let mut hard_policy = self.hard_policy.clone();
+        hard_policy.set_num_mods_if_none(self.modifiers.len());
+        let mut fw_state = net.get_forwarding_state();
+        hard_policy.step(&mut net, &mut fw_state)?;
+
+        let mut remaining = self.modifiers.clone();
+        let mut violations = Vec::new();
+        while !remaining.is_empty() {
+            violations.clear();
+            let mut next: Option<usize> = None;
+            for (i, modifier) in remaining.iter().enumerate() {
+                match net.apply_modifier(modifier) {
+                    Ok(()) => {}
+                    Err(NetworkError::ConvergenceLoop(events, _)) => {
+                        violations.extend(oscillating_routes(&events));
+                        net.undo_action()?;
+                        continue;
+                    }
+                    Err(_) => {
+                        net.undo_action()?;
+                        continue;
+                    }
+                }
+                let mut fw_state = net.get_forwarding_state();
+                hard_policy.step(&mut net, &mut fw_state)?;
+                if hard_policy.check() {
+                    next = Some(i);
+                    break;
+                }
+                violations.extend(violated_paths(&mut fw_state, &hard_policy.prop_vars));
+                net.undo_action()?;
+                hard_policy.undo();
+            }
+            match next {
+                Some(i) => {
+                    remaining.remove(i);
+                }
+                None => break,
+            }
+        }
+        Ok(violations)
+    }
+
+    /// Returns the time budget for the next `n` calls to the strategy `S`, when sharing the
+    /// remaining time equally.
+    fn remaining_time(&self, n: usize) -> Option<Duration> {
+        self.stop_time.map(|time| {
+            time.duration_since(SystemTime::now()).unwrap_or_else(|_| Duration::from_secs(0))
+                / n as u32
+        })
+    }
+}
+
+/// Returns the forwarding path (including the router where the traffic is dropped or loops) and the
+/// prefix of every condition which is violated in the forwarding state.
+fn violated_paths(fw_state: &mut ForwardingState, conditions: &[Condition]) -> Vec<Violation> {
+    let violated: Vec<&Condition> =
+        conditions.iter().filter(|c| c.check(fw_state).is_err()).collect();
+    violated
+        .into_iter()
+        .map(|c| match c {
+            Condition::ReachableIGP(..) | Condition::NotReachableIGP(..) => {
+                (vec![c.router_id()], None)
+            }
+            c => {
+                let path = match fw_state.get_route(c.router_id(), c.prefix()) {
+                    Ok(path)
+                    | Err(NetworkError::ForwardingLoop(path))
+                    | Err(NetworkError::ForwardingBlackHole(path)) => path,
+                    Err(_) => vec![c.router_id()],
+                };
+                (path, Some(c.prefix()))
+            }
+        })
+        .collect()
+}
+
+/// Returns the next hop and the prefix of every route which is advertised in the convergence loop
+/// described by `events`. Such a persistent oscillation is resolved by fixing the preference of
+/// one of these routes.
+fn oscillating_routes(events: &[Event]) -> Vec<Violation> {
+    let routes: HashSet<(RouterId, Prefix)> = events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Bgp(_, _, BgpEvent::Update(route)) => Some((route.next_hop, route.prefix)),
+            _ => None,
+        })
+        .collect();
+    routes.into_iter().map(|(next_hop, prefix)| (vec![next_hop], Some(prefix))).collect()
+}
+
+/// Relevance of the catalogue entry, given the weight of every router and the prefixes of the
+/// violated conditions. Each expression contributes the largest weight of its routers (including
+/// the neighbor matched by a route-map), unless it only matches on other prefixes.
+fn relevance(
+    aux: &[ConfigExpr],
+    weights: &HashMap<RouterId, usize>,
+    prefixes: &HashSet<Prefix>,
+) -> usize {
+    aux.iter()
+        .filter(|expr| match expr {
+            ConfigExpr::StaticRoute { prefix, .. } => prefixes.contains(prefix),
+            ConfigExpr::BgpRouteMap { map, .. } => map.conds().iter().all(|c| match c {
+                RouteMapMatch::Prefix(clause) => prefixes.iter().any(|p| clause.matches(p)),
+                _ => true,
+            }),
+            _ => true,
+        })
+        .map(|expr| {
+            let mut routers = expr.routers();
+            if let ConfigExpr::BgpRouteMap { map, .. } = expr {
+                routers.extend(map.conds().iter().filter_map(|c| match c {
+                    RouteMapMatch::Neighbor(n) => Some(*n),
+                    _ => None,
+                }));
+            }
+            routers.iter().filter_map(|r| weights.get(r)).max().copied().unwrap_or(0)
+        })
+        .sum()
+}
+
+/// Generate the built-in catalogue of auxiliary configuration. Each entry contains a single
+/// expression, which is one of the following:
+///
+/// 1. A temporary static route for a known prefix, pinning the next hop of an internal router to
+///    its next hop in either the initial or the final state.
+/// 2. A temporary route-map on an internal router, setting a high local preference for the routes
+///    of a known prefix learned from one of its eBGP neighbors (in either the initial or the final
+///    configuration).
+/// 3. A temporary iBGP peer session between two internal routers, which have no session in either
+///    the initial or the final configuration.
+///
+/// Entries colliding with the start configuration or with the modifiers are removed by the
+/// strategy.
+pub fn builtin_catalogue(net: &Network, modifiers: &[ConfigModifier]) -> Vec<Vec<ConfigExpr>> {
+    let mut routers = net.get_routers();
+    routers.sort();
+    let mut prefixes: Vec<Prefix> = net.get_known_prefixes().iter().cloned().collect();
+    prefixes.sort();
+
+    // compute the final state
+    let mut end_net = net.clone();
+    let end_net = if modifiers.iter().all(|m| end_net.apply_modifier(m).is_ok()) {
+        Some(end_net)
+    } else {
+        warn!("Cannot compute the final state of the network!");
+        None
+    };
+    let mut states: Vec<(&Network, ForwardingState)> = vec![(net, net.get_forwarding_state())];
+    if let Some(end_net) = end_net.as_ref() {
+        states.push((end_net, end_net.get_forwarding_state()));
+    }
+
+    // The same expression is found in both states. Duplicates are detected using the routers and
+    // the prefix from which the expression is built.
+    let mut catalogue: Vec<Vec<ConfigExpr>> = Vec::new();
+
+    // static routes
+    let mut seen: HashSet<(RouterId, Prefix, RouterId)> = HashSet::new();
+    for router in routers.iter() {
+        for prefix in prefixes.iter() {
+            for (_, state) in states.iter() {
+                if let Ok(next_hops) = state.get_next_hops_new(*router, Destination::BGP(*prefix)) {
+                    for target in next_hops.iter().filter(|nh| *nh != router) {
+                        if !seen.insert((*router, *prefix, *target)) {
+                            continue;
+                        }
+                        catalogue.push(vec![ConfigExpr::StaticRoute {
+                            router: *router,
+                            prefix: *prefix,
+                            target: *target,
+                        }]);
+                    }
+                }
+            }
+        }
+    }
+
+    // local preference
+    let mut seen: HashSet<(RouterId, RouterId, Prefix)> = HashSet::new();
+    for router in routers.iter() {
+        for (state_net, _) in states.iter() {
+            let mut neighbors: Vec<RouterId> = match state_net.get_device(*router) {
+                NetworkDevice::InternalRouter(r) => r
+                    .get_bgp_sessions()
+                    .filter(|(_, t)| **t == BgpSessionType::EBgp)
+                    .map(|(n, _)| *n)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            neighbors.sort();
+            for neighbor in neighbors {
+                for prefix in prefixes.iter() {
+                    if !seen.insert((*router, neighbor, *prefix)) {
+                        continue;
+                    }
+                    catalogue.push(vec![ConfigExpr::BgpRouteMap {
+                        router: *router,
+                        direction: RouteMapDirection::Incoming,
+                        map: RouteMapBuilder::new()
+                            .order(AUX_ROUTE_MAP_ORDER)
+                            .allow()
+                            .match_neighbor(neighbor)
+                            .match_prefix(*prefix)
+                            .set_local_pref(AUX_LOCAL_PREF)
+                            .build(),
+                    }]);
+                }
+            }
+        }
+    }
+
+    // iBGP sessions
+    for (i, source) in routers.iter().enumerate() {
+        for target in routers.iter().skip(i + 1) {
+            catalogue.push(vec![ConfigExpr::BgpSession {
+                source: *source,
+                target: *target,
+                session_type: BgpSessionType::IBgpPeer,
+            }]);
+        }
+    }
+
+    catalogue
+}
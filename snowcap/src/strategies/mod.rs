@@ -68,10 +68,19 @@
 //!   exploring the search space similar to the tree strategy. But as soon as we would need to
 //!   backtrace, we try to find a single dependency. If it succeeds, we repeat the tree traversal
 //!   using the new dependency. Using this approach, we only search for the dependencies, which are
-//!   not solvable by using the naive Tree strategy.
+//!   not solvable by using the naive Tree strategy. The `StrategyTRTA` implements
+//!   [`GroupStrategy`], such that already known dependency groups can be reused.
 //!
 //!   *Type Arguments*: None, this algorithm is as good as it gets (using this approach)
 //!
+//! - **[`StagedStrategy`]**: This strategy uses [`StrategyTRTA`] to find a valid sequence, and
+//!   then groups the modifiers into stages, which can be pushed to the network at the same time.
+//!   The hard policy is checked for every order of the modifiers in each stage, and the
+//!   concurrent application of each stage is checked for a sample of event orderings. Starting
+//!   from a greedy solution, the strategy searches for the plan with the fewest stages, until a
+//!   search limit or the time budget is reached. This strategy does not implement [`Strategy`],
+//!   since it returns a sequence of stages.
+//!
 //! - **[`PermutationStrategy`]**: This is the simplest strategy, naively checking every single
 //!   permutation one after the other. It does benefit from dependencies, which have an *immediate*
 //!   *effect* (only if the permutator makes use of the feedback mechanism, when the function
@@ -118,6 +127,14 @@
 //!   [`Permutator<usize>`](crate::permutators), used to generate all permutations of the groups.
 //!   As soon as a new group is formed, the permutator is reset.
 //!
+//! - **[`AuxiliaryStrategy`]**: This strategy wraps another strategy (by default
+//!   [`StrategyTRTA`]). If no valid ordering exists, it tries to insert temporary auxiliary
+//!   configuration from a catalogue, like a static route or a high local preference, which is
+//!   removed again before the end of the sequence.
+//!
+//!   *Type Arguments*: The first type argument `S` is the [`Strategy`] used to order the
+//!   modifiers.
+//!
 //! - **[`NaiveRandomStrategy`]**: This strategy just exists for evaluation purpose. It simply
 //!   shuffles the sequence and checks if this sequence is correct.
 //!
@@ -139,12 +156,16 @@ pub use naive_random::NaiveRandomStrategy;
 mod naive_random_ibr;
 pub use naive_random_ibr::NaiveRandomIBRStrategy;
 
+mod auxiliary;
+pub use auxiliary::{builtin_catalogue, AuxiliaryStrategy};
+
 // dep_pairs_builder is very bad! Therefore, we do not re-export the name!
 //mod dep_pairs_builder;
 //pub use dep_pairs_builder::DepPairsBuilder;
 
 // the DepGroupsStrategy is in a different module. Just re-export it from here
 pub use crate::dep_groups::strategy::DepGroupsStrategy;
+pub use crate::dep_groups::staged::StagedStrategy;
 pub use crate::dep_groups::strategy_trta::StrategyTRTA;
 
 use crate::hard_policies::HardPolicy;
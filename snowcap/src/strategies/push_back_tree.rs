@@ -21,7 +21,7 @@ use super::{ExhaustiveStrategy, GroupStrategy, Strategy};
 use crate::hard_policies::HardPolicy;
 use crate::modifier_ordering::ModifierOrdering;
 use crate::netsim::config::ConfigModifier;
-use crate::netsim::{printer, Network};
+use crate::netsim::{printer, Network, SnapshotId};
 use crate::{Error, Stopper};
 
 use log::*;
@@ -123,14 +123,18 @@ where
     }
 
     fn work(&mut self, mut abort: Stopper) -> Result<Vec<ConfigModifier>, Error> {
-        // initialize the stack
-        let mut stack: Vec<Stack> = vec![Stack::from_vec((0..self.groups.len()).collect(), 0)];
-        // points into the groups vector
-        let mut group_sequence: Vec<usize> = Vec::new();
-        // number of modifiers applied on the network
-
         let mut net = self.net.clone();
         let mut hard_policy = self.hard_policy.clone();
+        // forwarding state, updated incrementally after every change of the network
+        let mut fw_state = net.get_forwarding_state();
+        net.clear_forwarding_changes();
+
+        // initialize the stack. Every level stores a snapshot of the network, from which all of its
+        // branches start.
+        let mut stack: Vec<Stack> =
+            vec![Stack::from_vec((0..self.groups.len()).collect(), 0, net.snapshot())];
+        // points into the groups vector
+        let mut group_sequence: Vec<usize> = Vec::new();
 
         // backtrack level checker
         let mut num_backtrack: usize = 0;
@@ -156,7 +160,6 @@ where
 
                     // perform the modification group
                     let mut mod_ok: bool = true;
-                    let mut num_undo: usize = 0;
                     let mut num_undo_policy: usize = 0;
                     'apply_group: for m_idx in self.groups[current_group].iter() {
                         #[cfg(feature = "count-states")]
@@ -164,11 +167,10 @@ where
                             self.num_states += 1;
                         }
 
-                        num_undo += 1;
                         if net.apply_modifier(self.modifiers.get(*m_idx).unwrap()).is_ok() {
                             num_undo_policy += 1;
-                            let mut fw_state = net.get_forwarding_state();
-                            hard_policy.step(&mut net, &mut fw_state)?;
+                            let changes = fw_state.update(&mut net);
+                            hard_policy.step_incremental(&mut net, &mut fw_state, &changes)?;
                             if !hard_policy.check() {
                                 mod_ok = false;
                                 break 'apply_group;
@@ -181,14 +183,17 @@ where
 
                     if mod_ok {
                         // this single modification works! continue with it
-                        push_stack =
-                            Some(Stack { num_undo, rem_group: s.rem_group.clone(), cur_idx: 0 });
+                        push_stack = Some(Stack {
+                            num_undo: num_undo_policy,
+                            rem_group: s.rem_group.clone(),
+                            cur_idx: 0,
+                            snapshot: net.snapshot(),
+                        });
                         group_sequence.push(current_group);
                     } else {
                         // undo the changes
-                        for _ in 0..num_undo {
-                            net.undo_action()?;
-                        }
+                        net.restore(s.snapshot)?;
+                        fw_state.update(&mut net);
                         for _ in 0..num_undo_policy {
                             hard_policy.undo();
                         }
@@ -225,7 +230,9 @@ where
                     // set the group to the sorted modifiers.
                     self.groups = (0..self.modifiers.len()).map(|i| vec![i]).collect();
                     // re-initialize the stack
-                    stack = vec![Stack::from_vec((0..self.groups.len()).collect(), 0)];
+                    net.truncate_snapshots(0);
+                    stack =
+                        vec![Stack::from_vec((0..self.groups.len()).collect(), 0, net.snapshot())];
                     // clear the current sequence
                     group_sequence = Vec::new();
                     // continue with the loop
@@ -238,10 +245,16 @@ where
 
             if pop_stack {
                 let stack_frame = stack.pop();
-                // undo the network
+                // go back to the snapshot of the parent
+                if let Some(s) = stack_frame.as_ref() {
+                    net.truncate_snapshots(s.snapshot);
+                }
+                if let Some(s) = stack.last() {
+                    net.restore(s.snapshot)?;
+                    fw_state.update(&mut net);
+                }
                 let num_undo = stack_frame.map(|s| s.num_undo).unwrap_or(0);
                 for _ in 0..num_undo {
-                    net.undo_action()?;
                     hard_policy.undo();
                 }
 
@@ -372,14 +385,15 @@ struct Stack {
     pub num_undo: usize,
     pub rem_group: VecDeque<usize>,
     pub cur_idx: usize,
+    pub snapshot: SnapshotId,
 }
 
 impl Stack {
-    pub fn from_vec(rem_mod: Vec<usize>, cur_idx: usize) -> Self {
+    pub fn from_vec(rem_mod: Vec<usize>, cur_idx: usize, snapshot: SnapshotId) -> Self {
         let mut rb: VecDeque<usize> = VecDeque::with_capacity(rem_mod.len());
         for m in rem_mod {
             rb.push_back(m);
         }
-        Self { num_undo: 0, rem_group: rb, cur_idx }
+        Self { num_undo: 0, rem_group: rb, cur_idx, snapshot }
     }
 }
@@ -21,7 +21,7 @@ use super::{ExhaustiveStrategy, Strategy};
 use crate::hard_policies::HardPolicy;
 use crate::modifier_ordering::ModifierOrdering;
 use crate::netsim::config::ConfigModifier;
-use crate::netsim::{printer, Network};
+use crate::netsim::{printer, Network, SnapshotId};
 use crate::{Error, Stopper};
 
 use log::*;
@@ -111,12 +111,17 @@ where
     }
 
     fn work(&mut self, mut abort: Stopper) -> Result<Vec<ConfigModifier>, Error> {
-        // initialize the stack
-        let mut stack: Vec<Stack> = vec![Stack { rem_mod: self.modifiers.clone(), cur_idx: 0 }];
-        let mut mod_sequence: Vec<ConfigModifier> = Vec::new();
-
         let mut net = self.net.clone();
         let mut hard_policy = self.hard_policy.clone();
+        // forwarding state, updated incrementally after every change of the network
+        let mut fw_state = net.get_forwarding_state();
+        net.clear_forwarding_changes();
+
+        // initialize the stack. Every level stores a snapshot of the network, from which all of its
+        // branches start.
+        let mut stack: Vec<Stack> =
+            vec![Stack { rem_mod: self.modifiers.clone(), cur_idx: 0, snapshot: net.snapshot() }];
+        let mut mod_sequence: Vec<ConfigModifier> = Vec::new();
 
         loop {
             let mut pop_stack: bool = false;
@@ -156,8 +161,8 @@ where
                     }
 
                     let (mod_ok, undo_policy) = if net.apply_modifier(current_mod).is_ok() {
-                        let mut fw_state = net.get_forwarding_state();
-                        hard_policy.step(&mut net, &mut fw_state)?;
+                        let changes = fw_state.update(&mut net);
+                        hard_policy.step_incremental(&mut net, &mut fw_state, &changes)?;
                         if hard_policy.check() {
                             (true, false)
                         } else {
@@ -171,10 +176,12 @@ where
                         // this single modification works! continue with it
                         let mut new_mod = s.rem_mod.clone();
                         new_mod.remove(cur_idx);
-                        push_stack = Some(Stack { rem_mod: new_mod, cur_idx: 0 });
+                        push_stack =
+                            Some(Stack { rem_mod: new_mod, cur_idx: 0, snapshot: net.snapshot() });
                         mod_sequence.push(current_mod.clone());
                     } else {
-                        net.undo_action()?;
+                        net.restore(s.snapshot)?;
+                        fw_state.update(&mut net);
                         if undo_policy {
                             hard_policy.undo();
                         }
@@ -186,11 +193,15 @@ where
             }
 
             if pop_stack {
-                // undo the network
-                net.undo_action()?;
+                // pop the stack, and go back to the snapshot of the parent
+                if let Some(s) = stack.pop() {
+                    net.truncate_snapshots(s.snapshot);
+                }
+                if let Some(s) = stack.last() {
+                    net.restore(s.snapshot)?;
+                    fw_state.update(&mut net);
+                }
                 hard_policy.undo();
-                // pop the stack
-                stack.pop();
                 mod_sequence.pop();
                 debug!("Backtrack from tree, current levels: {}", stack.len());
 
@@ -225,4 +236,5 @@ impl<O> ExhaustiveStrategy for TreeStrategy<O> where O: ModifierOrdering<ConfigM
 struct Stack {
     pub rem_mod: Vec<ConfigModifier>,
     pub cur_idx: usize,
+    pub snapshot: SnapshotId,
 }
@@ -23,7 +23,7 @@ use crate::netsim::config::{Config, ConfigModifier};
 use crate::netsim::Network;
 use crate::optimizers::{Optimizer, OptimizerTRTA};
 use crate::soft_policies::SoftPolicy;
-use crate::strategies::{Strategy, StrategyTRTA};
+use crate::strategies::{GroupStrategy, StagedStrategy, Strategy, StrategyTRTA};
 use crate::{Error, Stopper};
 
 use log::*;
@@ -33,7 +33,9 @@ use std::time::Duration;
 /// # Synthesize Configuration Updates
 ///
 /// This is the main function to interact with the system. It uses the
-/// [`StrategyTRTA`](crate::strategies::StrategyTRTA).
+/// [`StrategyTRTA`](crate::strategies::StrategyTRTA). If no valid ordering exists, the returned
+/// [`Error::Unsat`](crate::Error::Unsat) explains why (if an explanation is found within a limited
+/// number of states).
 ///
 /// ## Usage
 ///
@@ -99,11 +101,249 @@ pub fn synthesize(
     }
 }
 
+/// # Synthesize Staged Configuration Updates
+///
+/// This function synthesizes a migration plan made of stages, using the
+/// [`StagedStrategy`](crate::strategies::StagedStrategy). All modifiers of a single stage can be
+/// pushed to the network at the same time. The hard policy holds for every order in which they are
+/// applied, and their concurrent application is checked for a sample of event orderings. Each stage
+/// contains at most `max_stage_size` modifiers.
+///
+/// ## Usage
+///
+/// ```
+/// use snowcap::hard_policies::*;
+/// use snowcap::synthesize_staged;
+/// use snowcap::Error;
+/// use snowcap::netsim::Network;
+/// use snowcap::netsim::config::Config;
+/// # use snowcap::example_networks::*;
+///
+/// fn main() -> Result<(), Error> {
+///     // prepare the network
+///     // let net = ...
+///     // let initial_config = ...
+///     // let final_config = ...
+/// # let net = SimpleNet::net(0);
+/// # let initial_config = net.current_config().clone();
+/// # let final_config = SimpleNet::final_config(&net, 0);
+///
+///     // prepare the policies
+///     // let hard_policy = ...
+/// # let hard_policy = HardPolicy::reachability(net.get_routers().iter(), net.get_known_prefixes().iter());
+///
+///     // synthesize the reconfiguration, with at most 4 modifiers per stage
+///     let stages = synthesize_staged(net, initial_config, final_config, hard_policy, 4, None)?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn synthesize_staged(
+    mut net: Network,
+    config_a: Config,
+    config_b: Config,
+    hard_policy: HardPolicy,
+    max_stage_size: usize,
+    time_limit: Option<Duration>,
+) -> Result<Vec<Vec<ConfigModifier>>, Error> {
+    // setup the network and reset the undo tracker
+    net.set_config(&config_a)?;
+    net.clear_undo_stack();
+
+    // compute the set of modifiers
+    let patch = config_a.get_diff(&config_b);
+    let modifiers: Vec<ConfigModifier> = patch.modifiers;
+
+    info!("Solving the problem...");
+
+    let mut strategy =
+        StagedStrategy::new(net, modifiers, hard_policy, time_limit, max_stage_size)?;
+
+    match strategy.work(Stopper::new()) {
+        Ok(stages) => {
+            info!("Found a valid solution with {} stages!", stages.len());
+            Ok(stages)
+        }
+        Err(e) => {
+            error!("Could not solve the problem: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// # Result of a Re-Synthesis
+///
+/// This structure is returned by [`resynthesize`], and contains the remaining sequence, the
+/// dependency groups known after synthesis, and the already applied modifiers which are no longer
+/// compatible with the (possibly revised) target configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resynthesis {
+    /// Sequence of the remaining modifiers, which must be applied after the already applied prefix.
+    pub sequence: Vec<ConfigModifier>,
+    /// Modifiers of the already applied prefix, whose configuration is changed again by the
+    /// remaining sequence, because the target configuration was revised.
+    pub incompatible: Vec<ConfigModifier>,
+    /// All dependency groups (including those learned during synthesis), which can be passed to
+    /// the next call to [`resynthesize`].
+    pub groups: Vec<Vec<ConfigModifier>>,
+}
+
+impl Resynthesis {
+    /// Returns `true` if all modifiers of the already applied prefix are still part of the path
+    /// towards the target configuration.
+    pub fn is_compatible(&self) -> bool {
+        self.incompatible.is_empty()
+    }
+}
+
+/// # Resume the Synthesis of Configuration Updates
+///
+/// This function continues a migration, of which the modifiers in `applied` were already applied
+/// to the network, starting from `config_a`. It synthesizes the remaining sequence towards
+/// `config_b`, which may differ from the target used to compute `applied` in the first place. The
+/// hard policy is checked from the very beginning of the migration, such that the prefix is
+/// considered when checking the LTL formulas. The dependency groups in `groups` (usually taken from
+/// a previous [`Resynthesis`]) are reused by [`StrategyTRTA`](crate::strategies::StrategyTRTA).
+/// Modifiers in `groups` which are no longer required are dropped.
+///
+/// The returned [`Resynthesis`] also reports which of the applied modifiers are no longer
+/// compatible with `config_b`, i.e., are changed again by the remaining sequence.
+///
+/// ## Usage
+///
+/// ```
+/// use snowcap::hard_policies::*;
+/// use snowcap::resynthesize;
+/// use snowcap::Error;
+/// use snowcap::netsim::Network;
+/// use snowcap::netsim::config::Config;
+/// # use snowcap::example_networks::*;
+///
+/// fn main() -> Result<(), Error> {
+///     // prepare the network
+///     // let net = ...
+///     // let initial_config = ...
+///     // let final_config = ...
+/// # let net = SimpleNet::net(0);
+/// # let initial_config = net.current_config().clone();
+/// # let final_config = SimpleNet::final_config(&net, 0);
+///
+///     // prepare the policies
+///     // let hard_policy = ...
+/// # let hard_policy = HardPolicy::reachability(net.get_routers().iter(), net.get_known_prefixes().iter());
+///
+///     // synthesize the entire reconfiguration
+///     let initial = resynthesize(
+///         net.clone(),
+///         initial_config.clone(),
+///         &[],
+///         final_config.clone(),
+///         hard_policy.clone(),
+///         &[],
+///         None,
+///     )?;
+///
+///     // the migration was halted after the first modifier. Continue from there.
+///     let resumed = resynthesize(
+///         net,
+///         initial_config,
+///         &initial.sequence[..1],
+///         final_config,
+///         hard_policy,
+///         &initial.groups,
+///         None,
+///     )?;
+///     assert!(resumed.is_compatible());
+///
+///     Ok(())
+/// }
+/// ```
+pub fn resynthesize(
+    mut net: Network,
+    config_a: Config,
+    applied: &[ConfigModifier],
+    config_b: Config,
+    mut hard_policy: HardPolicy,
+    groups: &[Vec<ConfigModifier>],
+    time_limit: Option<Duration>,
+) -> Result<Resynthesis, Error> {
+    // setup the network and reset the undo tracker
+    net.set_config(&config_a)?;
+    net.clear_undo_stack();
+
+    // compute the current configuration, and the set of remaining modifiers
+    let mut current_config = config_a;
+    for modifier in applied {
+        current_config.apply_modifier(modifier)?;
+    }
+    let patch = current_config.get_diff(&config_b);
+    let remaining: Vec<ConfigModifier> = patch.modifiers;
+
+    // find all applied modifiers, which are changed again by the remaining sequence
+    let incompatible: Vec<ConfigModifier> =
+        applied.iter().filter(|m| remaining.iter().any(|r| r.key() == m.key())).cloned().collect();
+    if !incompatible.is_empty() {
+        warn!("{} applied modifiers are incompatible with the new target", incompatible.len());
+    }
+
+    // replay the applied prefix on the network and the hard policy. The last state is not checked
+    // here, since the strategy checks it as its initial state.
+    hard_policy.set_num_mods_if_none(applied.len() + remaining.len());
+    if let Some((last, prefix)) = applied.split_last() {
+        let mut fw_state = net.get_forwarding_state();
+        hard_policy.step(&mut net, &mut fw_state)?;
+        if !hard_policy.check() {
+            error!("Invalid initial state");
+            return Err(Error::InvalidInitialState);
+        }
+        for modifier in prefix {
+            net.apply_modifier(modifier)?;
+            let mut fw_state = net.get_forwarding_state();
+            hard_policy.step(&mut net, &mut fw_state)?;
+            if !hard_policy.check() {
+                error!("The applied prefix violates the hard policy");
+                return Err(Error::InvalidInitialState);
+            }
+        }
+        net.apply_modifier(last)?;
+    }
+
+    // reuse the known groups, but only with the modifiers that still need to be applied
+    let mut unused: Vec<Option<ConfigModifier>> = remaining.into_iter().map(Some).collect();
+    let mut new_groups: Vec<Vec<ConfigModifier>> = groups
+        .iter()
+        .map(|group| {
+            group
+                .iter()
+                .filter_map(|m| unused.iter_mut().find(|u| u.as_ref() == Some(m))?.take())
+                .collect::<Vec<_>>()
+        })
+        .filter(|group| !group.is_empty())
+        .collect();
+    new_groups.extend(unused.into_iter().flatten().map(|m| vec![m]));
+
+    info!("Solving the remaining problem...");
+
+    let mut strategy = StrategyTRTA::from_groups(net, new_groups, hard_policy, time_limit)?;
+
+    match strategy.work(Stopper::new()) {
+        Ok(sequence) => {
+            info!("Found a valid solution!");
+            Ok(Resynthesis { sequence, incompatible, groups: strategy.groups().to_vec() })
+        }
+        Err(e) => {
+            error!("Could not solve the problem: {}", e);
+            Err(e)
+        }
+    }
+}
+
 /// # Synthesize Configuration Updates using multiple parallel threads
 ///
 /// This funciton spawns `N` [`StrategyTRTA`](crate::strategies::StrategyTRTA) threads, that search
 /// for a solution in parallel, using different random seeds.. The first solution found will be
-/// used, and all other threads will be killed.
+/// used, and all other threads will be killed. If no thread finds a solution, an
+/// [`Error::Unsat`](crate::Error::Unsat) found by any of the threads is returned.
 ///
 /// ## Usage
 ///
@@ -190,7 +430,10 @@ pub fn synthesize_parallel(
             Ok(valid) => correct_result = Some(valid),
             Err(e) => {
                 warn!("Thread had a problem solving the problem: {}", e);
-                some_error = Some(e)
+                // keep the explanation, if any thread has found one
+                if !matches!(some_error, Some(Error::Unsat(_))) {
+                    some_error = Some(e)
+                }
             }
         }
     }
@@ -15,20 +15,44 @@
 // with this program; if not, write to the Free Software Foundation, Inc.,
 // 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
 
+#[cfg(test)]
+mod test_acl;
+#[cfg(test)]
+mod test_auxiliary;
 #[cfg(test)]
 mod test_config;
 #[cfg(test)]
+mod test_constraints;
+#[cfg(test)]
+mod test_dot;
+#[cfg(test)]
+mod test_explanation;
+#[cfg(test)]
 mod test_forwarding_state;
 #[cfg(test)]
 mod test_network;
 #[cfg(test)]
 mod test_network_complete;
 #[cfg(test)]
+mod test_policy_parser;
+#[cfg(test)]
+mod test_prefix;
+#[cfg(test)]
+mod test_resynthesize;
+#[cfg(test)]
 mod test_route_map;
 #[cfg(test)]
 mod test_router;
+#[cfg(all(test, feature = "serde"))]
+mod test_serde;
+#[cfg(test)]
+mod test_snapshot;
 #[cfg(test)]
 mod test_solve_network;
+#[cfg(test)]
+mod test_staged;
+#[cfg(test)]
+mod test_timing;
 // NOTE These tests are deactivated, since this feature is temporarily disabled.
 //#[cfg(test)]
 //mod test_transient_behavior;
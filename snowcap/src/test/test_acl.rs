@@ -0,0 +1,250 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! Test the access control lists.
+
+use crate::hard_policies::{Condition, PolicyError};
+use crate::netsim::acl::*;
+use crate::netsim::config::{Config, ConfigExpr, ConfigExpr::*, ConfigModifier::*};
+use crate::netsim::types::Destination;
+use crate::netsim::{AsId, BgpSessionType::*, Network, NetworkError, Prefix, RouterId};
+
+fn ssh() -> TrafficClass {
+    TrafficClass { protocol: Some(Protocol::Tcp), dst_port: Some(22), ..Default::default() }
+}
+
+fn web() -> TrafficClass {
+    TrafficClass { protocol: Some(Protocol::Tcp), dst_port: Some(443), ..Default::default() }
+}
+
+/// Access list denying SSH, and permitting all other traffic
+fn deny_ssh() -> AccessList {
+    AccessList::new(vec![
+        AclEntryBuilder::new().order(20).permit().build(),
+        AclEntryBuilder::new()
+            .order(10)
+            .deny()
+            .match_protocol(Protocol::Tcp)
+            .match_dst_port(22, 22)
+            .build(),
+    ])
+}
+
+#[test]
+fn access_list_matching() {
+    let admin: Prefix = "10.0.0.0/8".parse().unwrap();
+    let acl = AccessList::new(vec![
+        AclEntryBuilder::new()
+            .order(10)
+            .permit()
+            .match_src(admin)
+            .match_protocol(Protocol::Tcp)
+            .match_dst_port(22, 22)
+            .build(),
+        AclEntryBuilder::new()
+            .order(20)
+            .permit()
+            .match_protocol(Protocol::Tcp)
+            .match_dst_port(0, 1023)
+            .build(),
+    ]);
+    assert_eq!(acl.entries().iter().map(|e| e.order()).collect::<Vec<_>>(), vec![10, 20]);
+
+    // the first matching entry decides
+    let admin_ssh = TrafficClass { src: Some("10.1.0.0/16".parse().unwrap()), ..ssh() };
+    assert_eq!(acl.action(&admin_ssh), Some(AclAction::Permit));
+    assert!(acl.permits(&ssh()));
+    assert!(acl.permits(&web()));
+
+    // packets matching no entry are denied
+    let udp =
+        TrafficClass { protocol: Some(Protocol::Udp), dst_port: Some(53), ..Default::default() };
+    assert_eq!(acl.action(&udp), Some(AclAction::Deny));
+    let high_port = TrafficClass { dst_port: Some(8080), ..web() };
+    assert!(!acl.permits(&high_port));
+
+    // entries matching only some packets of the class make the action depend on the packet
+    let any_tcp = TrafficClass { protocol: Some(Protocol::Tcp), ..Default::default() };
+    assert_eq!(acl.action(&any_tcp), None);
+    assert!(!acl.permits(&any_tcp));
+    assert!(acl.permits_any(&any_tcp));
+    assert_eq!(deny_ssh().action(&any_tcp), None);
+    assert!(!deny_ssh().permits(&any_tcp));
+    assert!(deny_ssh().permits_any(&any_tcp));
+    assert!(!deny_ssh().permits_any(&ssh()));
+    assert!(!AccessList::default().permits_any(&TrafficClass::default()));
+
+    // partial entries with the same action as the first full match do not change the outcome
+    let web_only = AccessList::new(vec![
+        AclEntryBuilder::new().order(10).deny().match_protocol(Protocol::Udp).build(),
+        AclEntryBuilder::new().order(20).permit().match_dst_port(443, 443).build(),
+    ]);
+    let any_udp = TrafficClass { protocol: Some(Protocol::Udp), ..Default::default() };
+    assert_eq!(web_only.action(&any_udp), Some(AclAction::Deny));
+    assert_eq!(web_only.action(&any_tcp), None);
+    assert_eq!(web_only.action(&TrafficClass::default()), None);
+    assert_eq!(web_only.action(&web()), Some(AclAction::Permit));
+}
+
+/// # Test network
+///
+/// ```text
+/// E1 ---- R1 ---- R2 ---- E2
+/// ```
+///
+/// Only E2 advertises the prefix.
+fn get_test_net() -> (Network, RouterId, RouterId, RouterId, RouterId) {
+    let mut net = Network::new();
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let e1 = net.add_external_router("E1", AsId(65101));
+    let e2 = net.add_external_router("E2", AsId(65102));
+
+    let mut c = Config::new();
+    for (a, b) in [(e1, r1), (r1, r2), (r2, e2)] {
+        net.add_link(a, b);
+        c.add(IgpLinkWeight { source: a, target: b, weight: 1.0 }).unwrap();
+        c.add(IgpLinkWeight { source: b, target: a, weight: 1.0 }).unwrap();
+    }
+    c.add(BgpSession { source: r1, target: r2, session_type: IBgpPeer }).unwrap();
+    c.add(BgpSession { source: r1, target: e1, session_type: EBgp }).unwrap();
+    c.add(BgpSession { source: r2, target: e2, session_type: EBgp }).unwrap();
+    net.set_config(&c).unwrap();
+
+    net.advertise_external_route(e2, Prefix::from(0), vec![AsId(65102)], None, None).unwrap();
+
+    (net, r1, r2, e1, e2)
+}
+
+fn acl_expr(router: RouterId, interface: RouterId, acl: AccessList) -> ConfigExpr {
+    AccessControl { router, interface, direction: AclDirection::Outgoing, acl }
+}
+
+#[test]
+fn filter_migration() {
+    let (mut net, r1, r2, e1, e2) = get_test_net();
+    let p = Prefix::from(0);
+    let reach_ssh = Condition::ReachableTraffic(r1, p, ssh());
+    let isolate_ssh = Condition::NotReachableTraffic(r1, p, ssh());
+    let reach_web = Condition::ReachableTraffic(r1, p, web());
+
+    let mut state = net.get_forwarding_state();
+    net.clear_forwarding_changes();
+    assert!(reach_ssh.check(&mut state).is_ok());
+    assert!(reach_web.check(&mut state).is_ok());
+
+    // block SSH on the link towards E2
+    net.apply_modifier(&Insert(acl_expr(r2, e2, deny_ssh()))).unwrap();
+    assert!(state.update(&mut net).is_all());
+    assert_eq!(
+        state.get_route_of_class(r1, Destination::BGP(p), &ssh()),
+        Err(NetworkError::AccessDenied(r2))
+    );
+    assert_eq!(state.get_route_of_class(r1, Destination::BGP(p), &web()), Ok(vec![r1, r2, e2]));
+    assert_eq!(
+        reach_ssh.check(&mut state),
+        Err(PolicyError::AccessDenied { router1: r2, router2: r1 })
+    );
+    assert!(isolate_ssh.check(&mut state).is_ok());
+    assert!(reach_web.check(&mut state).is_ok());
+
+    // only part of the TCP traffic reaches E2, so it is neither reachable nor isolated
+    let any_tcp = TrafficClass { protocol: Some(Protocol::Tcp), ..Default::default() };
+    assert_eq!(
+        Condition::ReachableTraffic(r1, p, any_tcp).check(&mut state),
+        Err(PolicyError::AccessDenied { router1: r2, router2: r1 })
+    );
+    assert_eq!(
+        Condition::NotReachableTraffic(r1, p, any_tcp).check(&mut state),
+        Err(PolicyError::UnallowedPathExists {
+            router: r1,
+            dest: Destination::BGP(p),
+            path: vec![r1, r2, e2]
+        })
+    );
+
+    // migrate the filter to allow SSH from the admin network
+    let admin: Prefix = "10.0.0.0/8".parse().unwrap();
+    let allow_admin = AccessList::new(vec![
+        AclEntryBuilder::new().order(5).permit().match_src(admin).build(),
+        AclEntryBuilder::new()
+            .order(10)
+            .deny()
+            .match_protocol(Protocol::Tcp)
+            .match_dst_port(22, 22)
+            .build(),
+        AclEntryBuilder::new().order(20).permit().build(),
+    ]);
+    let admin_ssh = TrafficClass { src: Some(admin), ..ssh() };
+    net.apply_modifier(&Update {
+        from: acl_expr(r2, e2, deny_ssh()),
+        to: acl_expr(r2, e2, allow_admin.clone()),
+    })
+    .unwrap();
+    let mut state = net.get_forwarding_state();
+    assert!(Condition::ReachableTraffic(r1, p, admin_ssh).check(&mut state).is_ok());
+    // SSH from the admin network is permitted, so SSH traffic in general is no longer isolated
+    assert!(isolate_ssh.check(&mut state).is_err());
+    assert!(reach_ssh.check(&mut state).is_err());
+    assert_eq!(
+        net.get_device(r2).unwrap_internal().get_acl(e2, AclDirection::Outgoing),
+        Some(&allow_admin)
+    );
+
+    // undo the migration
+    net.undo_action().unwrap();
+    let mut state = net.get_forwarding_state();
+    assert!(Condition::NotReachableTraffic(r1, p, admin_ssh).check(&mut state).is_ok());
+
+    // access lists can only be applied on existing links
+    assert_eq!(
+        net.apply_modifier(&Insert(acl_expr(r2, e1, deny_ssh()))),
+        Err(NetworkError::RoutersNotConnected(r2, e1))
+    );
+}
+
+#[test]
+fn incoming_filter() {
+    let (mut net, r1, r2, _, e2) = get_test_net();
+    let p = Prefix::from(0);
+
+    // R2 only accepts web traffic from R1
+    let acl = AccessList::new(vec![AclEntryBuilder::new()
+        .order(10)
+        .permit()
+        .match_protocol(Protocol::Tcp)
+        .match_dst_port(443, 443)
+        .build()]);
+    net.apply_modifier(&Insert(AccessControl {
+        router: r2,
+        interface: r1,
+        direction: AclDirection::Incoming,
+        acl,
+    }))
+    .unwrap();
+
+    let mut state = net.get_forwarding_state();
+    assert_eq!(state.get_route_of_class(r1, Destination::BGP(p), &web()), Ok(vec![r1, r2, e2]));
+    assert_eq!(
+        state.get_all_routes_of_class(r1, Destination::BGP(p), &ssh()),
+        vec![Err(NetworkError::AccessDenied(r2))]
+    );
+    // traffic originating at R2 is not filtered
+    assert_eq!(state.get_route_of_class(r2, Destination::BGP(p), &ssh()), Ok(vec![r2, e2]));
+    // without a traffic class, the traffic is dropped as soon as some packet is denied
+    assert_eq!(state.get_route_new(r1, Destination::BGP(p)), Err(NetworkError::AccessDenied(r2)));
+}
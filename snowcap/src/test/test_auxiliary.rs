@@ -0,0 +1,210 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! Test the auxiliary strategy.
+
+use crate::example_networks::*;
+use crate::hard_policies::{HardPolicy, OrderingConstraint, PolicyError};
+use crate::netsim::config::{ConfigExpr, ConfigModifier};
+use crate::netsim::route_map::{RouteMapBuilder, RouteMapDirection};
+use crate::netsim::Network;
+use crate::strategies::*;
+use crate::{Error, Stopper, UnsatExplanation};
+
+use std::time::Duration;
+
+fn setup<N: ExampleNetwork>(variant: usize) -> (Network, Vec<ConfigModifier>, HardPolicy) {
+    let net = N::net(variant);
+    let cf = N::final_config(&net, variant);
+    let modifiers = net.current_config().get_diff(&cf).modifiers;
+    let hard_policy =
+        HardPolicy::reachability(net.get_routers().iter(), net.get_known_prefixes().iter());
+    (net, modifiers, hard_policy)
+}
+
+/// Check that the sequence contains every modifier exactly once, that all other modifiers insert
+/// and later remove auxiliary configuration, and that the hard policy holds at every step. The
+/// auxiliary configuration is returned.
+fn check_sequence(
+    mut net: Network,
+    mut modifiers: Vec<ConfigModifier>,
+    mut hard_policy: HardPolicy,
+    sequence: &[ConfigModifier],
+) -> Vec<ConfigExpr> {
+    let mut aux: Vec<ConfigExpr> = Vec::new();
+    let mut removed: Vec<ConfigExpr> = Vec::new();
+    for m in sequence {
+        match (modifiers.iter().position(|x| m == x), m) {
+            (Some(pos), _) => {
+                modifiers.remove(pos);
+            }
+            (None, ConfigModifier::Insert(e)) => aux.push(e.clone()),
+            (None, ConfigModifier::Remove(e)) if aux.contains(e) => removed.push(e.clone()),
+            (None, m) => panic!("Unexpected modifier: {:?}", m),
+        }
+    }
+    assert!(modifiers.is_empty());
+    assert_eq!(aux, removed);
+
+    hard_policy.set_num_mods_if_none(sequence.len());
+    let mut fw_state = net.get_forwarding_state();
+    hard_policy.step(&mut net, &mut fw_state).unwrap();
+    for m in sequence {
+        net.apply_modifier(m).unwrap();
+        let mut fw_state = net.get_forwarding_state();
+        hard_policy.step(&mut net, &mut fw_state).unwrap();
+        assert!(hard_policy.check());
+    }
+    aux
+}
+
+/// Check that the explanation is a minimal subset of the modifiers, and that the policy is violated
+/// after applying its sequence.
+fn check_explanation(modifiers: &[ConfigModifier], explanation: &UnsatExplanation) {
+    assert!(explanation.minimal);
+    assert!(!explanation.modifiers.is_empty());
+    assert!(explanation.modifiers.iter().all(|m| modifiers.contains(m)));
+    assert!(!explanation.sequence.is_empty());
+    assert!(explanation.sequence.iter().all(|m| explanation.modifiers.contains(m)));
+    assert!(!explanation.errors.is_empty());
+}
+
+#[test]
+fn carousel_gadget() {
+    let (net, modifiers, hard_policy) = setup::<CarouselGadget>(0);
+
+    // there exists no valid ordering without auxiliary configuration
+    let mut strategy = AuxiliaryStrategy::<StrategyTRTA>::with_catalogue(
+        net.clone(),
+        modifiers.clone(),
+        hard_policy.clone(),
+        Some(Duration::from_secs(60)),
+        vec![],
+    )
+    .unwrap();
+    match strategy.work(Stopper::new()) {
+        Err(Error::Unsat(explanation)) => {
+            check_explanation(&modifiers, &explanation);
+            assert!(!explanation.conditions.is_empty());
+        }
+        r => panic!("Unexpected result: {:?}", r),
+    }
+
+    // with the built-in catalogue, a single temporary expression solves the problem
+    let sequence = AuxiliaryStrategy::<StrategyTRTA>::synthesize(
+        net.clone(),
+        CarouselGadget::final_config(&net, 0),
+        hard_policy.clone(),
+        Some(Duration::from_secs(60)),
+        Stopper::new(),
+    )
+    .unwrap();
+    let aux = check_sequence(net, modifiers.clone(), hard_policy, &sequence);
+    assert_eq!(aux.len(), 1);
+    assert_eq!(sequence.len(), modifiers.len() + 2);
+    assert_eq!(sequence[0], ConfigModifier::Insert(aux[0].clone()));
+}
+
+#[test]
+fn difficult_gadget_with_constraint() {
+    let (net, modifiers, hard_policy) = setup::<DifficultGadgetMinimal>(0);
+    let find = |source: &str, target: &str| {
+        let routers = vec![net.get_router_id(source).unwrap(), net.get_router_id(target).unwrap()];
+        modifiers.iter().find(|m| m.routers() == routers).unwrap().clone()
+    };
+    // establishing the session ry -> t2 before rx -> t1 leaves no valid ordering
+    let hard_policy = hard_policy
+        .with_constraints(vec![OrderingConstraint::Before(find("ry", "t2"), find("rx", "t1"))]);
+
+    let mut strategy = AuxiliaryStrategy::<StrategyTRTA>::with_catalogue(
+        net.clone(),
+        modifiers.clone(),
+        hard_policy.clone(),
+        Some(Duration::from_secs(60)),
+        vec![],
+    )
+    .unwrap();
+    match strategy.work(Stopper::new()) {
+        Err(Error::Unsat(explanation)) => {
+            check_explanation(&modifiers, &explanation);
+            assert!(explanation.conditions.is_empty());
+            assert_eq!(explanation.errors, vec![PolicyError::OrderingConstraint { constraint: 0 }]);
+        }
+        r => panic!("Unexpected result: {:?}", r),
+    }
+
+    let mut strategy: Box<AuxiliaryStrategy> = AuxiliaryStrategy::new(
+        net.clone(),
+        modifiers.clone(),
+        hard_policy.clone(),
+        Some(Duration::from_secs(60)),
+    )
+    .unwrap();
+    let sequence = strategy.work(Stopper::new()).unwrap();
+    let aux = check_sequence(net, modifiers, hard_policy, &sequence);
+    assert!(!aux.is_empty());
+}
+
+#[test]
+fn custom_catalogue() {
+    let (net, modifiers, hard_policy) = setup::<CarouselGadget>(0);
+    let b2 = net.get_router_id("b2").unwrap();
+    let e2 = net.get_router_id("e2").unwrap();
+
+    // collides with the route-map of the initial configuration, and is therefore ignored
+    let colliding = ConfigExpr::BgpRouteMap {
+        router: b2,
+        direction: RouteMapDirection::Incoming,
+        map: RouteMapBuilder::new().order(10).allow().match_neighbor(e2).build(),
+    };
+    let mut strategy = AuxiliaryStrategy::<StrategyTRTA>::with_catalogue(
+        net.clone(),
+        modifiers.clone(),
+        hard_policy.clone(),
+        Some(Duration::from_secs(60)),
+        vec![vec![colliding]],
+    )
+    .unwrap();
+    assert!(strategy.work(Stopper::new()).is_err());
+
+    // the built-in catalogue contains a solution
+    let catalogue = builtin_catalogue(&net, &modifiers);
+    let mut strategy = AuxiliaryStrategy::<StrategyTRTA>::with_catalogue(
+        net.clone(),
+        modifiers.clone(),
+        hard_policy.clone(),
+        Some(Duration::from_secs(60)),
+        catalogue.into_iter().rev().collect(),
+    )
+    .unwrap();
+    let sequence = strategy.work(Stopper::new()).unwrap();
+    check_sequence(net, modifiers, hard_policy, &sequence);
+}
+
+#[test]
+fn no_auxiliary_needed() {
+    let (net, modifiers, hard_policy) = setup::<SimpleNet>(0);
+    let mut strategy: Box<AuxiliaryStrategy> = AuxiliaryStrategy::new(
+        net.clone(),
+        modifiers.clone(),
+        hard_policy.clone(),
+        Some(Duration::from_secs(60)),
+    )
+    .unwrap();
+    let sequence = strategy.work(Stopper::new()).unwrap();
+    assert!(check_sequence(net, modifiers, hard_policy, &sequence).is_empty());
+}
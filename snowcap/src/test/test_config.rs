@@ -65,8 +65,8 @@ fn config_unique() {
     let r0: RouterId = 0.into();
     let r1: RouterId = 1.into();
     let r2: RouterId = 2.into();
-    let p0: Prefix = Prefix(0);
-    let p1: Prefix = Prefix(1);
+    let p0: Prefix = Prefix::from(0);
+    let p1: Prefix = Prefix::from(1);
 
     // unique static route
     c.add(StaticRoute { router: r0, prefix: p0, target: r1 }).unwrap();
@@ -137,8 +137,8 @@ fn config_add_remove() {
     let r0: RouterId = 0.into();
     let r1: RouterId = 1.into();
     let r2: RouterId = 2.into();
-    let p0: Prefix = Prefix(0);
-    let p1: Prefix = Prefix(1);
+    let p0: Prefix = Prefix::from(0);
+    let p1: Prefix = Prefix::from(1);
 
     {
         // unique static route
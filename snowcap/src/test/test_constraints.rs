@@ -0,0 +1,221 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! Test the ordering constraints of the hard policy.
+
+use crate::example_networks::*;
+use crate::hard_policies::{HardPolicy, OrderingConstraint, PolicyError};
+use crate::modifier_ordering::SimpleOrdering;
+use crate::netsim::config::ConfigModifier;
+use crate::netsim::Network;
+use crate::optimizers::{Optimizer, OptimizerTRTA};
+use crate::soft_policies::{MinimizeTrafficShift, SoftPolicy};
+use crate::strategies::{PushBackTreeStrategy, Strategy, StrategyTRTA, TreeStrategy};
+use crate::Stopper;
+
+use std::time::Duration;
+
+type Net = MediumNet;
+
+/// Prepare the problem, and compute a set of ordering constraints, which are satisfied by a valid
+/// ordering, but not by the ordering found by `StrategyTRTA` without any constraints.
+fn prepare() -> (Network, Vec<ConfigModifier>, HardPolicy, Vec<OrderingConstraint>) {
+    let net = Net::net(0);
+    let cf = Net::final_config(&net, 0);
+    let modifiers = net.current_config().get_diff(&cf).modifiers;
+    let hard_policy =
+        HardPolicy::reachability(net.get_routers().iter(), net.get_known_prefixes().iter());
+
+    let unconstrained =
+        StrategyTRTA::new(net.clone(), modifiers.clone(), hard_policy.clone(), None)
+            .unwrap()
+            .work(Stopper::new())
+            .unwrap();
+    let n = unconstrained.len();
+
+    // find a different valid ordering, by moving a single modifier to the end
+    let target = (0..n - 1)
+        .map(|i| {
+            let mut o = unconstrained.clone();
+            let m = o.remove(i);
+            o.push(m);
+            o
+        })
+        .find(|o| is_valid(&net, &hard_policy, o))
+        .unwrap();
+    let pos = |m: &ConfigModifier| unconstrained.iter().position(|x| x == m).unwrap();
+    let (a, b) = (0..n)
+        .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+        .find(|(i, j)| pos(&target[*i]) > pos(&target[*j]))
+        .unwrap();
+
+    let constraints = vec![
+        OrderingConstraint::Before(target[a].clone(), target[b].clone()),
+        OrderingConstraint::AtEnd(vec![target[n - 2].clone(), target[n - 1].clone()]),
+        OrderingConstraint::Atomic(vec![target[1].clone(), target[2].clone()]),
+    ];
+    (net, modifiers, hard_policy, constraints)
+}
+
+/// Check if the ordering is valid, according to the hard policy.
+fn is_valid(net: &Network, hard_policy: &HardPolicy, ordering: &[ConfigModifier]) -> bool {
+    let mut net = net.clone();
+    let mut hard_policy = hard_policy.clone();
+    hard_policy.set_num_mods_if_none(ordering.len());
+    let mut fw_state = net.get_forwarding_state();
+    hard_policy.step(&mut net, &mut fw_state).unwrap();
+    for modifier in ordering {
+        if net.apply_modifier(modifier).is_err() {
+            return false;
+        }
+        let mut fw_state = net.get_forwarding_state();
+        hard_policy.step(&mut net, &mut fw_state).unwrap();
+        if !hard_policy.check() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Check that the sequence contains every modifier once, and that it satisfies the constraints.
+fn check_sequence(
+    net: &Network,
+    modifiers: &[ConfigModifier],
+    hard_policy: &HardPolicy,
+    constraints: &[OrderingConstraint],
+    sequence: &[ConfigModifier],
+) {
+    assert_eq!(sequence.len(), modifiers.len());
+    assert!(modifiers.iter().all(|m| sequence.contains(m)));
+    let pos = |m: &ConfigModifier| sequence.iter().position(|x| x == m).unwrap();
+    for c in constraints {
+        match c {
+            OrderingConstraint::Before(a, b) => assert!(pos(a) < pos(b)),
+            OrderingConstraint::AtEnd(mods) => {
+                assert!(mods.iter().all(|m| pos(m) >= sequence.len() - mods.len()))
+            }
+            OrderingConstraint::Atomic(mods) => {
+                let first = mods.iter().map(pos).min().unwrap();
+                assert!(mods.iter().all(|m| pos(m) < first + mods.len()))
+            }
+        }
+    }
+    let hard_policy = hard_policy.clone().with_constraints(constraints.to_vec());
+    assert!(is_valid(net, &hard_policy, sequence));
+}
+
+#[test]
+fn constraints_strategy_trta() {
+    let (net, modifiers, hard_policy, constraints) = prepare();
+    let policy = hard_policy.clone().with_constraints(constraints.clone());
+    let sequence = StrategyTRTA::new(net.clone(), modifiers.clone(), policy, None)
+        .unwrap()
+        .work(Stopper::new())
+        .unwrap();
+    check_sequence(&net, &modifiers, &hard_policy, &constraints, &sequence);
+}
+
+#[test]
+fn constraints_tree_strategy() {
+    let (net, modifiers, hard_policy, constraints) = prepare();
+    let policy = hard_policy.clone().with_constraints(constraints.clone());
+    let sequence =
+        TreeStrategy::<SimpleOrdering>::new(net.clone(), modifiers.clone(), policy, None)
+            .unwrap()
+            .work(Stopper::new())
+            .unwrap();
+    check_sequence(&net, &modifiers, &hard_policy, &constraints, &sequence);
+}
+
+#[test]
+fn constraints_push_back_tree_strategy() {
+    let (net, modifiers, hard_policy, constraints) = prepare();
+    let policy = hard_policy.clone().with_constraints(constraints.clone());
+    let sequence =
+        PushBackTreeStrategy::<SimpleOrdering>::new(net.clone(), modifiers.clone(), policy, None)
+            .unwrap()
+            .work(Stopper::new())
+            .unwrap();
+    check_sequence(&net, &modifiers, &hard_policy, &constraints, &sequence);
+}
+
+#[test]
+fn constraints_optimizer_trta() {
+    let (net, modifiers, hard_policy, constraints) = prepare();
+    let policy = hard_policy.clone().with_constraints(constraints.clone());
+    let mut fw_state = net.get_forwarding_state();
+    let soft_policy = MinimizeTrafficShift::new(&mut fw_state, &net);
+    let (sequence, _) = OptimizerTRTA::new(
+        net.clone(),
+        modifiers.clone(),
+        policy,
+        soft_policy,
+        Some(Duration::from_secs(60)),
+    )
+    .unwrap()
+    .work(Stopper::new())
+    .unwrap();
+    check_sequence(&net, &modifiers, &hard_policy, &constraints, &sequence);
+}
+
+#[test]
+fn constraints_violation() {
+    let (net, modifiers, hard_policy, _) = prepare();
+    let sequence = StrategyTRTA::new(net.clone(), modifiers, hard_policy.clone(), None)
+        .unwrap()
+        .work(Stopper::new())
+        .unwrap();
+    assert!(is_valid(&net, &hard_policy, &sequence));
+
+    // require the second modifier to be applied before the first one
+    let constraints = vec![OrderingConstraint::Before(sequence[1].clone(), sequence[0].clone())];
+    let mut net = net;
+    let mut hard_policy = hard_policy.with_constraints(constraints);
+    let mut fw_state = net.get_forwarding_state();
+    hard_policy.step(&mut net, &mut fw_state).unwrap();
+    assert!(hard_policy.check());
+    net.apply_modifier(&sequence[0]).unwrap();
+    let mut fw_state = net.get_forwarding_state();
+    hard_policy.step(&mut net, &mut fw_state).unwrap();
+    assert!(!hard_policy.check());
+    assert!(hard_policy.last_errors().contains(&PolicyError::OrderingConstraint { constraint: 0 }));
+
+    // after undoing the step, the policy is satisfied again
+    hard_policy.undo();
+    assert!(hard_policy.check());
+}
+
+#[test]
+fn constraints_atomic_hides_intermediate_states() {
+    // the carousel gadget has no valid ordering. If all modifiers are applied in one single
+    // commit, only the initial and the final state are observable.
+    let net = CarouselGadget::net(0);
+    let cf = CarouselGadget::final_config(&net, 0);
+    let modifiers = net.current_config().get_diff(&cf).modifiers;
+    let hard_policy =
+        HardPolicy::reachability(net.get_routers().iter(), net.get_known_prefixes().iter());
+    assert!(!is_valid(&net, &hard_policy, &modifiers));
+
+    let constraints = vec![OrderingConstraint::Atomic(modifiers.clone())];
+    let hard_policy = hard_policy.with_constraints(constraints);
+    assert!(is_valid(&net, &hard_policy, &modifiers));
+    let sequence = StrategyTRTA::new(net, modifiers.clone(), hard_policy, None)
+        .unwrap()
+        .work(Stopper::new())
+        .unwrap();
+    assert_eq!(sequence.len(), modifiers.len());
+}
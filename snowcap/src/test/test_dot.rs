@@ -0,0 +1,131 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! Test the Graphviz export.
+
+use crate::netsim::config::{Config, ConfigExpr::*, ConfigModifier::*};
+use crate::netsim::dot;
+use crate::netsim::{AsId, BgpSessionType::*, Network, Prefix, RouterId};
+
+/// # Test network
+///
+/// ```text
+/// E1 ---- R1 ---- R2 ---- R3 ---- E3
+/// ```
+///
+/// R2 is a route reflector with clients R1 and R3. The link from R2 to R1 has weight 5, all other
+/// links have weight 1. Only E1 advertises the prefix.
+fn get_test_net() -> (Network, RouterId, RouterId, RouterId, RouterId, RouterId) {
+    let mut net = Network::new();
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let r3 = net.add_router("R3");
+    let e1 = net.add_external_router("E1", AsId(65101));
+    let e3 = net.add_external_router("E3", AsId(65103));
+
+    let mut c = Config::new();
+    for (a, b) in [(e1, r1), (r1, r2), (r2, r3), (r3, e3)] {
+        net.add_link(a, b);
+        c.add(IgpLinkWeight { source: a, target: b, weight: 1.0 }).unwrap();
+        let weight = if (a, b) == (r1, r2) { 5.0 } else { 1.0 };
+        c.add(IgpLinkWeight { source: b, target: a, weight }).unwrap();
+    }
+    c.add(BgpSession { source: r2, target: r1, session_type: IBgpClient }).unwrap();
+    c.add(BgpSession { source: r2, target: r3, session_type: IBgpClient }).unwrap();
+    c.add(BgpSession { source: r1, target: e1, session_type: EBgp }).unwrap();
+    c.add(BgpSession { source: r3, target: e3, session_type: EBgp }).unwrap();
+    net.set_config(&c).unwrap();
+
+    net.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101)], None, None).unwrap();
+
+    (net, r1, r2, r3, e1, e3)
+}
+
+fn n(router: RouterId) -> String {
+    format!("r{}", router.index())
+}
+
+#[test]
+fn topology() {
+    let (net, r1, r2, r3, e1, e3) = get_test_net();
+    let graph = dot::topology(&net).unwrap();
+    let lines: Vec<&str> = graph.lines().collect();
+
+    assert_eq!(lines.first(), Some(&"graph {"));
+    assert_eq!(lines.last(), Some(&"}"));
+    assert!(lines.contains(&format!("    {} [label=\"R1\", shape=ellipse];", n(r1)).as_str()));
+    assert!(lines.contains(&format!("    {} [label=\"E3\", shape=box];", n(e3)).as_str()));
+
+    // physical links with their weights
+    assert!(lines.contains(&format!("    {} -- {} [label=\"1\"];", n(e1), n(r1)).as_str()));
+    assert!(lines.contains(&format!("    {} -- {} [label=\"1 / 5\"];", n(r1), n(r2)).as_str()));
+
+    // route reflector sessions are only drawn once, pointing towards the client
+    let rr = |client| {
+        format!("    {} -- {} [style=dashed, color=darkgreen, dir=forward];", n(r2), n(client))
+    };
+    assert!(lines.contains(&rr(r1).as_str()));
+    assert!(lines.contains(&rr(r3).as_str()));
+    assert_eq!(lines.iter().filter(|l| l.contains("style=dashed")).count(), 2);
+    let ebgp = format!("    {} -- {} [style=bold, color=blue];", n(r3), n(e3));
+    assert!(lines.contains(&ebgp.as_str()));
+}
+
+#[test]
+fn forwarding_state_and_diff() {
+    let (mut net, r1, r2, r3, e1, _) = get_test_net();
+    let p = Prefix::from(0);
+
+    let state = net.get_forwarding_state();
+    let graph = dot::forwarding_state(&net, &state, p).unwrap();
+    let lines: Vec<&str> = graph.lines().collect();
+    assert!(lines.contains(&format!("    {} -> {};", n(r3), n(r2)).as_str()));
+    assert!(lines.contains(&format!("    {} -> {};", n(r1), n(e1)).as_str()));
+    assert!(lines
+        .contains(&format!("    {} [label=\"E1\", shape=box, peripheries=2];", n(e1)).as_str()));
+    assert!(!graph.contains("red"));
+
+    // a static route on R2 towards R3 creates a forwarding loop
+    let static_route = StaticRoute { router: r2, prefix: p, target: r3 };
+    let sequence = vec![Insert(static_route.clone()), Remove(static_route)];
+    let graphs = dot::sequence_diffs(&net, &sequence, p).unwrap();
+    assert_eq!(graphs.len(), 2);
+    let lines: Vec<&str> = graphs[0].lines().collect();
+    assert!(lines[1].starts_with(&format!("    label=\"{}: INSERT Static Route: R2", p)));
+    assert!(
+        lines.contains(&format!("    {} -> {} [color=red, style=bold];", n(r2), n(r3)).as_str())
+    );
+    assert!(lines.contains(&format!("    {} -> {} [color=red];", n(r3), n(r2)).as_str()));
+    assert!(
+        lines.contains(&format!("    {} -> {} [color=gray, style=dashed];", n(r2), n(r1)).as_str())
+    );
+    let lines: Vec<&str> = graphs[1].lines().collect();
+    assert!(lines
+        .contains(&format!("    {} -> {} [color=darkgreen, style=bold];", n(r2), n(r1)).as_str()));
+    // the network itself is not modified
+    assert_eq!(net.get_route(r3, p), Ok(vec![r3, r2, r1, e1]));
+
+    // without any advertisement, traffic is dropped at every internal router
+    net.retract_external_route(e1, p).unwrap();
+    let after = net.get_forwarding_state();
+    let graph = dot::forwarding_diff(&net, &state, &after, p).unwrap();
+    assert!(graph.contains(&format!(
+        "    {} [label=\"R2\", shape=ellipse, style=filled, fillcolor=red];",
+        n(r2)
+    )));
+    assert!(graph.contains(&format!("    {} -> {} [color=gray, style=dashed];", n(r1), n(e1))));
+}
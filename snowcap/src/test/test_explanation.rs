@@ -0,0 +1,122 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! Test the explanation of unsolvable problems.
+
+use crate::example_networks::*;
+use crate::hard_policies::HardPolicy;
+use crate::modifier_ordering::NoOrdering;
+use crate::netsim::config::ConfigModifier;
+use crate::netsim::{printer, Network};
+use crate::permutators::{HeapsPermutator, Permutator};
+use crate::strategies::{Strategy, StrategyTRTA};
+use crate::{Error, Stopper};
+
+use std::time::Duration;
+
+/// Number of states the explanation may explore.
+const MAX_STATES: usize = 1_000;
+
+/// Returns `true` if there exists an ordering of the modifiers, for which the hard policy is
+/// satisfied in every state.
+fn has_valid_ordering(
+    net: &Network,
+    hard_policy: &HardPolicy,
+    num_mods: usize,
+    modifiers: &[ConfigModifier],
+) -> bool {
+    HeapsPermutator::<NoOrdering, _>::new(modifiers.to_vec()).any(|ordering| {
+        let mut net = net.clone();
+        let mut hard_policy = hard_policy.clone();
+        hard_policy.set_num_mods_if_none(num_mods);
+        let mut fw_state = net.get_forwarding_state();
+        hard_policy.step(&mut net, &mut fw_state).unwrap();
+        ordering.iter().all(|m| {
+            net.apply_modifier(m).is_ok() && {
+                let mut fw_state = net.get_forwarding_state();
+                hard_policy.step(&mut net, &mut fw_state).unwrap();
+                hard_policy.check()
+            }
+        })
+    })
+}
+
+#[test]
+fn carousel_gadget() {
+    let net = CarouselGadget::net(0);
+    let cf = CarouselGadget::final_config(&net, 0);
+    let modifiers = net.current_config().get_diff(&cf).modifiers;
+    let hard_policy =
+        HardPolicy::reachability(net.get_routers().iter(), net.get_known_prefixes().iter());
+
+    let strategy = |max_states: Option<usize>| {
+        let mut strategy = StrategyTRTA::new(
+            net.clone(),
+            modifiers.clone(),
+            hard_policy.clone(),
+            Some(Duration::from_secs(60)),
+        )
+        .unwrap();
+        if let Some(max_states) = max_states {
+            strategy.set_explain_unsat(max_states);
+        }
+        strategy.work(Stopper::new())
+    };
+
+    // the explanation is searched by default
+    assert!(matches!(strategy(None), Err(Error::Unsat(_))));
+
+    // if disabled, or with too few states, no explanation is returned
+    assert!(matches!(strategy(Some(0)), Err(Error::ProbablyNoSafeOrdering)));
+    assert!(matches!(strategy(Some(1)), Err(Error::ProbablyNoSafeOrdering)));
+
+    let explanation = match strategy(Some(MAX_STATES)) {
+        Err(Error::Unsat(explanation)) => explanation,
+        r => panic!("Unexpected result: {:?}", r),
+    };
+
+    // every ordering of the explanation violates the policy, but no modifier can be removed
+    assert!(explanation.minimal);
+    assert!(!explanation.modifiers.is_empty());
+    assert!(explanation.modifiers.iter().all(|m| modifiers.contains(m)));
+    assert!(!has_valid_ordering(&net, &hard_policy, modifiers.len(), &explanation.modifiers));
+    for i in 0..explanation.modifiers.len() {
+        let mut reduced = explanation.modifiers.clone();
+        reduced.remove(i);
+        assert!(has_valid_ordering(&net, &hard_policy, modifiers.len(), &reduced));
+    }
+
+    // the policy is violated after applying the sequence
+    assert!(!explanation.sequence.is_empty());
+    assert!(explanation.sequence.iter().all(|m| explanation.modifiers.contains(m)));
+    assert!(!explanation.errors.is_empty());
+    assert!(!explanation.conditions.is_empty());
+
+    // the description names the modifiers, the sequence, the conditions and the errors
+    let description = &explanation.description;
+    assert!(description.starts_with("Every ordering of the following modifiers violates"));
+    for m in explanation.modifiers.iter().chain(explanation.sequence.iter()) {
+        assert!(description.contains(&printer::config_modifier(&net, m).unwrap()));
+    }
+    for c in explanation.conditions.iter() {
+        assert!(description.contains(&c.repr_with_name(&net)));
+    }
+    for e in explanation.errors.iter() {
+        assert!(description.contains(&e.repr_with_name(&net)));
+    }
+    assert_eq!(explanation.to_string(), *description);
+}
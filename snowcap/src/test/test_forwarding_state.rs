@@ -15,9 +15,13 @@
 // with this program; if not, write to the Free Software Foundation, Inc.,
 // 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
 
-use crate::netsim::config::{Config, ConfigExpr::*};
+use crate::hard_policies::{Condition, HardPolicy, PathCondition::*, PolicyError, Waypoint::*};
+use crate::netsim::config::{Config, ConfigExpr::*, ConfigModifier::*};
+use crate::netsim::acl::{AccessList, AclDirection, TrafficClass};
 use crate::netsim::route_map::*;
-use crate::netsim::{AsId, BgpSessionType::*, Network, Prefix};
+use crate::netsim::{
+    AsId, BgpSessionType::*, ForwardingState, Network, NetworkError, Prefix, RouterId,
+};
 use crate::netsim::types::Destination;
 
 #[test]
@@ -158,21 +162,21 @@ fn test_forwarding_state_carousel_gadget() {
         .unwrap();
         net.set_config(&c).unwrap();
 
-        net.advertise_external_route(er, Prefix(1), vec![AsId(65100), AsId(65201)], None, None)
+        net.advertise_external_route(er, Prefix::from(1), vec![AsId(65100), AsId(65201)], None, None)
             .unwrap();
-        net.advertise_external_route(er, Prefix(2), vec![AsId(65100), AsId(65202)], None, None)
+        net.advertise_external_route(er, Prefix::from(2), vec![AsId(65100), AsId(65202)], None, None)
             .unwrap();
-        net.advertise_external_route(e1, Prefix(1), vec![AsId(65101), AsId(65201)], None, None)
+        net.advertise_external_route(e1, Prefix::from(1), vec![AsId(65101), AsId(65201)], None, None)
             .unwrap();
-        net.advertise_external_route(e2, Prefix(1), vec![AsId(65102), AsId(65201)], None, None)
+        net.advertise_external_route(e2, Prefix::from(1), vec![AsId(65102), AsId(65201)], None, None)
             .unwrap();
-        net.advertise_external_route(e2, Prefix(2), vec![AsId(65102), AsId(65202)], None, None)
+        net.advertise_external_route(e2, Prefix::from(2), vec![AsId(65102), AsId(65202)], None, None)
             .unwrap(); //
-        net.advertise_external_route(e3, Prefix(1), vec![AsId(65103), AsId(65201)], None, None)
+        net.advertise_external_route(e3, Prefix::from(1), vec![AsId(65103), AsId(65201)], None, None)
             .unwrap();
-        net.advertise_external_route(e3, Prefix(2), vec![AsId(65103), AsId(65202)], None, None)
+        net.advertise_external_route(e3, Prefix::from(2), vec![AsId(65103), AsId(65202)], None, None)
             .unwrap();
-        net.advertise_external_route(e4, Prefix(2), vec![AsId(65104), AsId(65202)], None, None)
+        net.advertise_external_route(e4, Prefix::from(2), vec![AsId(65104), AsId(65202)], None, None)
             .unwrap();
 
         let mut routers = net.get_routers();
@@ -188,7 +192,7 @@ fn test_forwarding_state_carousel_gadget() {
                     state.get_next_hop(*router, *prefix).unwrap(),
                     "Invalid next-hop at {} for prefix {}",
                     net.get_router_name(*router).unwrap(),
-                    prefix.0
+                    prefix
                 );
             }
         }
@@ -209,3 +213,342 @@ fn test_forwarding_state_carousel_gadget() {
         }
     }
 }
+
+/// Test network `E1 ---- R1 ---- R2 ---- E2`, where both external routers advertise the same
+/// prefix, but E2 with a longer AS path.
+fn get_chain_net() -> (Network, RouterId, RouterId, RouterId, RouterId) {
+    let mut net = Network::new();
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let e1 = net.add_external_router("E1", AsId(65101));
+    let e2 = net.add_external_router("E2", AsId(65102));
+
+    let mut c = Config::new();
+    for (a, b) in [(e1, r1), (r1, r2), (r2, e2)] {
+        net.add_link(a, b);
+        c.add(IgpLinkWeight { source: a, target: b, weight: 1.0 }).unwrap();
+        c.add(IgpLinkWeight { source: b, target: a, weight: 1.0 }).unwrap();
+    }
+    c.add(BgpSession { source: r1, target: r2, session_type: IBgpPeer }).unwrap();
+    c.add(BgpSession { source: r1, target: e1, session_type: EBgp }).unwrap();
+    c.add(BgpSession { source: r2, target: e2, session_type: EBgp }).unwrap();
+    net.set_config(&c).unwrap();
+
+    let p = Prefix::from(0);
+    net.advertise_external_route(e1, p, vec![AsId(65101)], None, None).unwrap();
+    net.advertise_external_route(e2, p, vec![AsId(65102), AsId(1)], None, None).unwrap();
+
+    (net, r1, r2, e1, e2)
+}
+
+/// Check that both forwarding states have the same next hops for every router and destination.
+fn assert_same_next_hops(net: &Network, a: &ForwardingState, b: &ForwardingState) {
+    let routers = net.get_routers();
+    for router in net.get_routers().into_iter().chain(net.get_external_routers()) {
+        let dests = routers
+            .iter()
+            .map(|r| Destination::IGP(*r))
+            .chain(net.get_known_prefixes().iter().map(|p| Destination::BGP(*p)));
+        for dest in dests {
+            assert_eq!(
+                a.get_next_hops_new(router, dest.clone()),
+                b.get_next_hops_new(router, dest.clone()),
+                "different next hops of {:?} towards {:?}",
+                router,
+                dest
+            );
+        }
+    }
+}
+
+#[test]
+fn test_forwarding_state_incremental_update() {
+    let (mut net, r1, r2, e1, e2) = get_chain_net();
+    let p = Prefix::from(0);
+    let prefer_e2 = BgpRouteMap {
+        router: r2,
+        direction: RouteMapDirection::Incoming,
+        map: RouteMapBuilder::new()
+            .order(10)
+            .allow()
+            .match_neighbor(e2)
+            .set_local_pref(200)
+            .build(),
+    };
+
+    let mut state = net.get_forwarding_state();
+    net.clear_forwarding_changes();
+    assert_eq!(state.get_route_new(r1, Destination::BGP(p)), Ok(vec![r1, e1]));
+    assert!(state.update(&mut net).is_empty());
+
+    // only the entries of R1 and R2 towards the prefix change.
+    net.apply_modifier(&Insert(prefer_e2)).unwrap();
+    let changes = state.update(&mut net);
+    assert!(!changes.is_all());
+    assert!(changes.contains(r1, &Destination::BGP(p)));
+    assert!(changes.contains(r2, &Destination::BGP(p)));
+    assert!(!changes.contains(r1, &Destination::IGP(r2)));
+    assert!(changes.affects(&Destination::BGP(p)));
+    assert!(!changes.affects(&Destination::IGP(r1)));
+    assert_same_next_hops(&net, &state, &net.get_forwarding_state());
+    // the cached path was invalidated
+    assert_eq!(state.get_route_new(r1, Destination::BGP(p)), Ok(vec![r1, r2, e2]));
+
+    // undoing the modifier is tracked as well
+    net.undo_action().unwrap();
+    let changes = state.update(&mut net);
+    assert!(changes.contains(r1, &Destination::BGP(p)));
+    assert_same_next_hops(&net, &state, &net.get_forwarding_state());
+    assert_eq!(state.get_route_new(r1, Destination::BGP(p)), Ok(vec![r1, e1]));
+
+    // changing the IGP without changing any IGP table does not change anything
+    net.apply_modifier(&Update {
+        from: IgpLinkWeight { source: r1, target: r2, weight: 1.0 },
+        to: IgpLinkWeight { source: r1, target: r2, weight: 5.0 },
+    })
+    .unwrap();
+    assert!(state.update(&mut net).is_empty());
+    assert_same_next_hops(&net, &state, &net.get_forwarding_state());
+}
+
+#[test]
+fn test_forwarding_state_incremental_igp_update() {
+    // E2 ---- R2 ---- R1 ---- R3 ---- E3, where R1 prefers the route from E2, since R2 is closer.
+    let mut net = Network::new();
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let r3 = net.add_router("R3");
+    let e2 = net.add_external_router("E2", AsId(65102));
+    let e3 = net.add_external_router("E3", AsId(65103));
+
+    let mut c = Config::new();
+    for (a, b, w) in [(e2, r2, 1.0), (r2, r1, 1.0), (r1, r3, 2.0), (r3, e3, 1.0)] {
+        net.add_link(a, b);
+        c.add(IgpLinkWeight { source: a, target: b, weight: w }).unwrap();
+        c.add(IgpLinkWeight { source: b, target: a, weight: w }).unwrap();
+    }
+    for (a, b) in [(r1, r2), (r1, r3), (r2, r3)] {
+        c.add(BgpSession { source: a, target: b, session_type: IBgpPeer }).unwrap();
+    }
+    c.add(BgpSession { source: r2, target: e2, session_type: EBgp }).unwrap();
+    c.add(BgpSession { source: r3, target: e3, session_type: EBgp }).unwrap();
+    net.set_config(&c).unwrap();
+    let p = Prefix::from(0);
+    net.advertise_external_route(e2, p, vec![AsId(65102)], None, None).unwrap();
+    net.advertise_external_route(e3, p, vec![AsId(65103)], None, None).unwrap();
+
+    let mut state = net.get_forwarding_state();
+    net.clear_forwarding_changes();
+    assert_eq!(state.get_route_new(r1, Destination::BGP(p)), Ok(vec![r1, r2, e2]));
+
+    // only the IGP table of R1 changes, which now prefers the route from E3
+    net.apply_modifier(&Update {
+        from: IgpLinkWeight { source: r1, target: r2, weight: 1.0 },
+        to: IgpLinkWeight { source: r1, target: r2, weight: 5.0 },
+    })
+    .unwrap();
+    let changes = state.update(&mut net);
+    assert!(!changes.is_all());
+    assert!(changes.contains(r1, &Destination::BGP(p)));
+    assert!(!changes.contains(r2, &Destination::BGP(p)));
+    assert_same_next_hops(&net, &state, &net.get_forwarding_state());
+    assert_eq!(state.get_route_new(r1, Destination::BGP(p)), Ok(vec![r1, r3, e3]));
+
+    // undoing the change is tracked as well
+    net.undo_action().unwrap();
+    let changes = state.update(&mut net);
+    assert!(!changes.is_all());
+    assert_same_next_hops(&net, &state, &net.get_forwarding_state());
+    assert_eq!(state.get_route_new(r1, Destination::BGP(p)), Ok(vec![r1, r2, e2]));
+}
+
+#[test]
+fn test_hard_policy_step_incremental() {
+    let (mut net, r1, r2, e1, e2) = get_chain_net();
+    let p = Prefix::from(0);
+    let conditions = vec![
+        Condition::Reachable(r1, p, None),
+        Condition::Reachable(r2, p, None),
+        Condition::ReachableIGP(r1, r2, None),
+    ];
+    let mut full = HardPolicy::globally(conditions.clone());
+    let mut incremental = HardPolicy::globally(conditions);
+
+    let mut state = net.get_forwarding_state();
+    net.clear_forwarding_changes();
+    full.step(&mut net, &mut state.clone()).unwrap();
+    incremental.step(&mut net, &mut state).unwrap();
+
+    // without E1, traffic is forwarded to E2. Without E2 as well, all traffic is dropped.
+    for (e, reachable) in [(e1, true), (e2, false)] {
+        net.retract_external_route(e, p).unwrap();
+        let changes = state.update(&mut net);
+        full.step(&mut net.clone(), &mut net.get_forwarding_state()).unwrap();
+        incremental.step_incremental(&mut net, &mut state, &changes).unwrap();
+        assert_eq!(full.check(), reachable);
+        assert_eq!(incremental.check(), reachable);
+        assert_eq!(full.last_errors(), incremental.last_errors());
+    }
+
+    // nothing changes
+    let changes = state.update(&mut net);
+    assert!(changes.is_empty());
+    full.step(&mut net.clone(), &mut net.get_forwarding_state()).unwrap();
+    incremental.step_incremental(&mut net, &mut state, &changes).unwrap();
+    assert_eq!(full.last_errors(), incremental.last_errors());
+    assert_eq!(full.get_watch(), incremental.get_watch());
+}
+
+#[test]
+fn test_forwarding_state_ecmp_chain() {
+    // chain of 30 diamonds, such that there are 2^30 equal-cost paths from the first to the last
+    // router.
+    let mut net = Network::new();
+    let mut c = Config::new();
+    let first = net.add_router("v0");
+    let mut last = first;
+    let mut lower = Vec::new();
+    for i in 0..30 {
+        let a = net.add_router(format!("a{}", i));
+        let b = net.add_router(format!("b{}", i));
+        let next = net.add_router(format!("v{}", i + 1));
+        for (x, y) in [(last, a), (last, b), (a, next), (b, next)] {
+            net.add_link(x, y);
+            c.add(IgpLinkWeight { source: x, target: y, weight: 1.0 }).unwrap();
+            c.add(IgpLinkWeight { source: y, target: x, weight: 1.0 }).unwrap();
+        }
+        lower.push(b);
+        last = next;
+    }
+    net.set_config(&c).unwrap();
+
+    let any = TrafficClass::default();
+    let mut state = net.get_forwarding_state();
+    assert_eq!(state.check_all_routes(first, Destination::IGP(last)), Ok(()));
+    let path = state.find_valid_route_of_class(first, Destination::IGP(last), &any).unwrap();
+    assert_eq!(path.len(), 61);
+    assert_eq!(path.last(), Some(&last));
+
+    // the last router drops all traffic from the lower router of the last diamond
+    let b = *lower.last().unwrap();
+    net.apply_modifier(&Insert(AccessControl {
+        router: last,
+        interface: b,
+        direction: AclDirection::Incoming,
+        acl: AccessList::default(),
+    }))
+    .unwrap();
+    let mut state = net.get_forwarding_state();
+    assert_eq!(
+        state.check_all_routes(first, Destination::IGP(last)),
+        Err(NetworkError::AccessDenied(last))
+    );
+    let path = state.find_valid_route_of_class(first, Destination::IGP(last), &any).unwrap();
+    assert_eq!(path.len(), 61);
+    assert_ne!(path[59], b);
+
+    // traffic from the lower router itself cannot reach the destination
+    assert_eq!(state.find_valid_route_of_class(b, Destination::IGP(last), &any), None);
+}
+
+/// Chain of `n` diamonds, such that there are `2^n` equal-cost paths from the first router to the
+/// external router, which advertises the prefix 0 to the last router. Returns the network, the
+/// first and the last router, the upper and the lower router of every diamond, and the external
+/// router.
+fn ecmp_chain_bgp(
+    n: usize,
+) -> (Network, RouterId, RouterId, Vec<RouterId>, Vec<RouterId>, RouterId) {
+    let mut net = Network::new();
+    let mut c = Config::new();
+    let first = net.add_router("v0");
+    let mut last = first;
+    let mut clients = Vec::new();
+    let mut upper = Vec::new();
+    let mut lower = Vec::new();
+    for i in 0..n {
+        let a = net.add_router(format!("a{}", i));
+        let b = net.add_router(format!("b{}", i));
+        let next = net.add_router(format!("v{}", i + 1));
+        for (x, y) in [(last, a), (last, b), (a, next), (b, next)] {
+            net.add_link(x, y);
+            c.add(IgpLinkWeight { source: x, target: y, weight: 1.0 }).unwrap();
+            c.add(IgpLinkWeight { source: y, target: x, weight: 1.0 }).unwrap();
+        }
+        upper.push(a);
+        lower.push(b);
+        clients.extend([last, a, b]);
+        last = next;
+    }
+    let e = net.add_external_router("e", AsId(65100));
+    net.add_link(last, e);
+    c.add(IgpLinkWeight { source: last, target: e, weight: 1.0 }).unwrap();
+    c.add(IgpLinkWeight { source: e, target: last, weight: 1.0 }).unwrap();
+    c.add(BgpSession { source: last, target: e, session_type: EBgp }).unwrap();
+    for r in clients {
+        c.add(BgpSession { source: last, target: r, session_type: IBgpClient }).unwrap();
+    }
+    net.set_config(&c).unwrap();
+    net.advertise_external_route(e, Prefix::from(0), vec![AsId(65100)], None, None).unwrap();
+    (net, first, last, upper, lower, e)
+}
+
+#[test]
+fn test_path_condition_ecmp_chain() {
+    let (net, first, last, upper, lower, e) = ecmp_chain_bgp(30);
+    let p = Prefix::from(0);
+    let reach = |c| Condition::Reachable(first, p, Some(c));
+    let mut state = net.get_forwarding_state();
+
+    // every path traverses one router of every diamond, and leaves the network at the last router
+    let diamonds = upper.iter().zip(lower.iter()).map(|(a, b)| Or(vec![Node(*a), Node(*b)]));
+    assert_eq!(reach(And(diamonds.collect())).check(&mut state), Ok(()));
+    let waypoints = vec![Fix(first), Star, Fix(last), Fix(e)];
+    assert_eq!(reach(Positional(waypoints)).check(&mut state), Ok(()));
+    assert_eq!(reach(Not(Box::new(Edge(upper[0], lower[0])))).check(&mut state), Ok(()));
+
+    // some path avoids the lower router of the last diamond
+    let b = *lower.last().unwrap();
+    match reach(Node(b)).check(&mut state) {
+        Err(PolicyError::PathCondition { path, condition: Node(x), .. }) => {
+            assert_eq!(x, b);
+            assert_eq!(path.len(), 62);
+            assert_eq!(path[..1], [first]);
+            assert_eq!(path[60..], [last, e]);
+            assert!(!path.contains(&b));
+        }
+        x => panic!("Unexpected result: {:?}", x),
+    }
+}
+
+#[test]
+fn test_path_condition_all_paths() {
+    let (net, first, last, upper, lower, e) = ecmp_chain_bgp(3);
+    let p = Prefix::from(0);
+    let state = net.get_forwarding_state();
+    let paths = state
+        .get_all_routes(first, Destination::BGP(p))
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(paths.len(), 8);
+
+    let conditions = vec![
+        Node(upper[1]),
+        Edge(upper[0], upper[1]),
+        Edge(first, lower[0]),
+        Not(Box::new(Node(lower[2]))),
+        Or(vec![Node(upper[1]), Node(lower[1])]),
+        And(vec![Node(upper[0]), Not(Box::new(Node(lower[2])))]),
+        Positional(vec![Star, Fix(last), Fix(e)]),
+        Positional(vec![Any, Fix(upper[0]), Star]),
+        Positional(vec![Star, Fix(lower[1]), Any, Star, Fix(e)]),
+        Positional(vec![Star, Fix(upper[1]), Star, Any]),
+        Positional(vec![Fix(first), Star, Any, Fix(lower[2]), Star]),
+        Positional(vec![Star, Star, Fix(upper[2]), Any, Any]),
+    ];
+    for c in conditions {
+        // the first violating path is found, like when enumerating all paths
+        let expected = paths.iter().map(|path| c.check(path, p)).find(Result::is_err);
+        assert_eq!(c.check_all_paths(&state, first, p), expected.unwrap_or(Ok(())), "{}", c);
+    }
+}
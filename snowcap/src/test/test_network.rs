@@ -17,14 +17,25 @@
 
 //! Test the simple functionality of the network, without running it entirely.
 
+use crate::hard_policies::{Condition, PathCondition, PolicyError};
+use crate::netsim::bgp::{
+    BgpDecisionConfig, BgpRoute, LargeCommunity, Origin::*, RedistributionSource::*,
+};
 use crate::netsim::config::{Config, ConfigExpr::*, ConfigModifier::*};
+use crate::netsim::event::Event;
 use crate::netsim::network::Network;
 use crate::netsim::route_map::{
-    RouteMap, RouteMapDirection::*, RouteMapMatch as Match, RouteMapSet as Set, RouteMapState::*,
+    RouteMap, RouteMapBuilder, RouteMapDirection::*, RouteMapMatch as Match, RouteMapSet as Set,
+    RouteMapState::*,
+};
+use crate::netsim::types::Destination;
+use crate::netsim::{
+    AsId, BgpSessionType::*, LinkWeight, NetworkError, OspfArea, Prefix, RouterId,
 };
-use crate::netsim::{AsId, BgpSessionType::*, LinkWeight, NetworkError, Prefix, RouterId};
 use lazy_static::lazy_static;
+use maplit::btreeset;
 use petgraph::algo::FloatMeasure;
+use std::collections::BTreeSet;
 
 lazy_static! {
     static ref R1: RouterId = 0.into();
@@ -165,7 +176,7 @@ fn test_igp_table() {
         assert_eq!(fw_table.len(), 6);
         for (target, entry) in fw_table.iter() {
             if *router == *target {
-                assert_eq!(entry, &Some((*router, 0.0)));
+                assert_eq!(entry, &Some((vec![*router], 0.0)));
             } else {
                 assert_eq!(entry, &None);
             }
@@ -182,9 +193,9 @@ fn test_igp_table() {
         assert_eq!(fw_table.len(), 6);
         for (to, entry) in fw_table.iter() {
             if *from == *R1 && *to == *R2 {
-                assert_eq!(entry, &Some((*to, 5.0)));
+                assert_eq!(entry, &Some((vec![*to], 5.0)));
             } else if *from == *to {
-                assert_eq!(entry, &Some((*to, 0.0)));
+                assert_eq!(entry, &Some((vec![*to], 0.0)));
             } else {
                 assert_eq!(entry, &None);
             }
@@ -201,9 +212,9 @@ fn test_igp_table() {
         assert_eq!(fw_table.len(), 6);
         for (to, entry) in fw_table.iter() {
             if (*from == *R1 && *to == *R2) || (*from == *R2 && *to == *R1) {
-                assert_eq!(entry, &Some((*to, 5.0)));
+                assert_eq!(entry, &Some((vec![*to], 5.0)));
             } else if *from == *to {
-                assert_eq!(entry, &Some((*to, 0.0)));
+                assert_eq!(entry, &Some((vec![*to], 0.0)));
             } else {
                 assert_eq!(entry, &None);
             }
@@ -219,7 +230,7 @@ fn test_igp_table() {
 fn test_bgp_connectivity() {
     let mut net = get_test_net_bgp();
 
-    let p = Prefix(0);
+    let p = Prefix::from(0);
 
     // check that all routes have a black hole
     for router in net.get_routers().iter() {
@@ -261,7 +272,7 @@ fn test_bgp_connectivity() {
 fn test_static_route() {
     let mut net = get_test_net_bgp().clone();
 
-    let p = Prefix(0);
+    let p = Prefix::from(0);
 
     // check that all routes have a black hole
     for router in net.get_routers().iter() {
@@ -322,7 +333,7 @@ fn test_static_route() {
 fn test_bgp_decision() {
     let mut net = get_test_net_bgp().clone();
 
-    let p = Prefix(0);
+    let p = Prefix::from(0);
 
     // advertise both prefixes
     let save_1 = net.clone();
@@ -351,15 +362,15 @@ fn test_bgp_decision() {
     assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R1, *E1]));
     assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *E4]));
 
-    // change the MED
+    // change the MED. Since both routes are learned from different neighboring ASes, the MED is
+    // not compared, and nothing changes.
     let save_5 = net.clone();
     net.advertise_external_route(*E4, p, vec![AsId(65104), AsId(65201)], Some(20), None).unwrap();
 
-    // we now expect all routers to choose R1 as an egress
     assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *E1]));
-    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R3, *R1, *E1]),);
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R4, *E4]),);
     assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R1, *E1]));
-    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *R3, *R1, *E1]));
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *E4]));
 
     // change back
     let save_6 = net.clone();
@@ -386,10 +397,553 @@ fn test_bgp_decision() {
     assert_eq!(net.undo_action(), Ok(false));
 }
 
+/// Configure the BGP decision process of `router`, by inserting or updating its configuration.
+fn set_bgp_decision(net: &mut Network, router: RouterId, config: BgpDecisionConfig) {
+    let to = BgpDecision { router, config };
+    let modifier = match net.current_config().iter().find(|e| e.key() == to.key()) {
+        Some(from) => Update { from: from.clone(), to },
+        None => Insert(to),
+    };
+    net.apply_modifier(&modifier).unwrap();
+}
+
+#[test]
+fn test_bgp_decision_undo() {
+    let mut net = get_test_net_bgp().clone();
+
+    let p = Prefix::from(0);
+    net.advertise_external_route(*E1, p, vec![AsId(65101), AsId(65201)], Some(20), None).unwrap();
+    net.advertise_external_route(*E4, p, vec![AsId(65104), AsId(65201)], None, None).unwrap();
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R4, *E4]));
+    let saved = net.clone();
+
+    // with missing-as-worst and always-compare-med, R2 prefers the route of E1
+    let config = BgpDecisionConfig {
+        always_compare_med: true,
+        med_missing_as_worst: true,
+        multipath: false,
+    };
+    for r in [*R1, *R2, *R3, *R4] {
+        net.apply_modifier(&Insert(BgpDecision { router: r, config })).unwrap();
+    }
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R3, *R1, *E1]));
+
+    // the changes of the decision process can be undone
+    for _ in 0..4 {
+        assert_eq!(net.undo_action(), Ok(true));
+    }
+    assert!(net == saved);
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R4, *E4]));
+}
+
+#[test]
+fn test_bgp_decision_med() {
+    let mut net = get_test_net_bgp().clone();
+
+    let p = Prefix::from(0);
+
+    // E1 advertises a route with MED 20, and E4 a route without any MED.
+    net.advertise_external_route(*E1, p, vec![AsId(65101), AsId(65201)], Some(20), None).unwrap();
+    net.advertise_external_route(*E4, p, vec![AsId(65104), AsId(65201)], None, None).unwrap();
+
+    // MED is not compared between different neighboring ASes
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *E1]));
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R4, *E4]));
+    assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R1, *E1]));
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *E4]));
+
+    // with missing-as-worst and always-compare-med, the route of E1 is preferred
+    let config = BgpDecisionConfig {
+        always_compare_med: true,
+        med_missing_as_worst: true,
+        multipath: false,
+    };
+    for r in [*R1, *R2, *R3, *R4] {
+        set_bgp_decision(&mut net, r, config);
+        assert_eq!(net.get_device(r).unwrap_internal().get_bgp_decision_config(), &config);
+    }
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *E1]));
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R3, *R1, *E1]));
+    assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R1, *E1]));
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *R3, *R1, *E1]));
+
+    // the missing MED is only treated as the worst value in the decision process, and is not
+    // stored in the route.
+    let known = net.get_device(*R4).unwrap_internal().get_known_bgp_routes(p).unwrap();
+    assert_eq!(known.iter().find(|e| e.from_id == *E4).unwrap().route.med, None);
+
+    // with always-compare-med, the missing MED of E4 is treated as 0, and E4 is preferred
+    let config = BgpDecisionConfig {
+        always_compare_med: true,
+        med_missing_as_worst: false,
+        multipath: false,
+    };
+    for r in [*R1, *R2, *R3, *R4] {
+        set_bgp_decision(&mut net, r, config);
+    }
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *R3, *R4, *E4]));
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R4, *E4]));
+    assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R4, *E4]));
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *E4]));
+
+    // go back to the default configuration. If both routes are learned from the same
+    // neighboring AS, the MED is always compared.
+    for r in [*R1, *R2, *R3, *R4] {
+        net.apply_modifier(&Remove(BgpDecision { router: r, config })).unwrap();
+        assert_eq!(
+            net.get_device(r).unwrap_internal().get_bgp_decision_config(),
+            &BgpDecisionConfig::default()
+        );
+    }
+    net.advertise_external_route(*E4, p, vec![AsId(65101), AsId(65201)], Some(30), None).unwrap();
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *E1]));
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R3, *R1, *E1]));
+    assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R1, *E1]));
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *R3, *R1, *E1]));
+}
+
+#[test]
+fn test_bgp_redundant_route_reflectors() {
+    let mut net = get_test_net();
+    let mut c = Config::new();
+    for (a, b) in net.links_symmetric().cloned().collect::<Vec<_>>() {
+        c.add(IgpLinkWeight { source: a, target: b, weight: 1.0 }).unwrap();
+        c.add(IgpLinkWeight { source: b, target: a, weight: 1.0 }).unwrap();
+    }
+    // R2 and R3 are redundant route reflectors for R1 and R4
+    c.add(BgpSession { source: *R2, target: *R3, session_type: IBgpPeer }).unwrap();
+    c.add(BgpSession { source: *R2, target: *R1, session_type: IBgpClient }).unwrap();
+    c.add(BgpSession { source: *R2, target: *R4, session_type: IBgpClient }).unwrap();
+    c.add(BgpSession { source: *R3, target: *R1, session_type: IBgpClient }).unwrap();
+    c.add(BgpSession { source: *R3, target: *R4, session_type: IBgpClient }).unwrap();
+    c.add(BgpSession { source: *R1, target: *E1, session_type: EBgp }).unwrap();
+    c.add(BgpSession { source: *R4, target: *E4, session_type: EBgp }).unwrap();
+    net.set_config(&c).unwrap();
+
+    let p = Prefix::from(0);
+    net.advertise_external_route(*E1, p, vec![AsId(65101), AsId(65201)], None, None).unwrap();
+
+    // R3 learns the route from R1 and the reflected route from R2
+    let known = |net: &Network, r: RouterId| {
+        let mut routes = net.get_device(r).unwrap_internal().get_known_bgp_routes(p).unwrap();
+        routes.sort_by_key(|e| e.from_id);
+        routes
+    };
+    let routes = known(&net, *R3);
+    assert_eq!(routes.iter().map(|e| e.from_id).collect::<Vec<_>>(), vec![*R1, *R2]);
+    assert_eq!(routes[1].route.originator_id, Some(*R1));
+    assert_eq!(routes[1].route.cluster_list, vec![*R2]);
+
+    // R4 prefers the route reflected by R2, since R2 has the lower router-id.
+    let selected = net.get_device(*R4).unwrap_internal().get_selected_bgp_route(p).unwrap();
+    assert_eq!(selected.from_id, *R2);
+    assert_eq!(selected.route.originator_id, Some(*R1));
+    assert_eq!(selected.route.cluster_list, vec![*R2]);
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *R2, *R1, *E1]));
+
+    // R1 does not accept its own route, which is reflected back by R3 and R2.
+    assert_eq!(known(&net, *R1).iter().map(|e| e.from_id).collect::<Vec<_>>(), vec![*E1]);
+
+    // Put both route reflectors into the same cluster
+    let saved = net.clone();
+    net.apply_modifier(&Insert(BgpClusterId { router: *R2, cluster_id: *R2 })).unwrap();
+    net.apply_modifier(&Insert(BgpClusterId { router: *R3, cluster_id: *R2 })).unwrap();
+    let routes = known(&net, *R3);
+    assert_eq!(routes.iter().map(|e| e.from_id).collect::<Vec<_>>(), vec![*R1]);
+    let routes = known(&net, *R2);
+    assert_eq!(routes.iter().map(|e| e.from_id).collect::<Vec<_>>(), vec![*R1]);
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *R2, *R1, *E1]));
+
+    // undoing both modifiers restores the previous state
+    assert_eq!(net.undo_action(), Ok(true));
+    assert_eq!(net.undo_action(), Ok(true));
+    assert_eq!(known(&net, *R3).iter().map(|e| e.from_id).collect::<Vec<_>>(), vec![*R1, *R2]);
+    assert!(net == saved);
+}
+
+#[test]
+fn test_ecmp_multipath() {
+    let mut net = get_test_net();
+    let mut c = Config::new();
+    for (a, b) in net.links_symmetric().cloned().collect::<Vec<_>>() {
+        c.add(IgpLinkWeight { source: a, target: b, weight: 1.0 }).unwrap();
+        c.add(IgpLinkWeight { source: b, target: a, weight: 1.0 }).unwrap();
+    }
+    for (a, b) in [(*R1, *R2), (*R1, *R3), (*R1, *R4), (*R2, *R3), (*R2, *R4), (*R3, *R4)] {
+        c.add(BgpSession { source: a, target: b, session_type: IBgpPeer }).unwrap();
+    }
+    c.add(BgpSession { source: *R1, target: *E1, session_type: EBgp }).unwrap();
+    c.add(BgpSession { source: *R4, target: *E4, session_type: EBgp }).unwrap();
+    net.set_config(&c).unwrap();
+
+    // R1 reaches R4 over two equal-cost paths
+    let fw_table = net.get_device(*R1).unwrap_internal().get_igp_fw_table();
+    let (next_hops, cost) = fw_table.get(&*R4).cloned().flatten().unwrap();
+    assert_eq!(next_hops.into_iter().collect::<BTreeSet<_>>(), btreeset! {*R2, *R3});
+    assert_eq!(cost, 2.0);
+    let fw = net.get_forwarding_state();
+    let mut routes = fw
+        .get_all_routes(*R1, Destination::IGP(*R4))
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    routes.sort();
+    assert_eq!(routes, vec![vec![*R1, *R2, *R4], vec![*R1, *R3, *R4]]);
+
+    // E1 and E4 advertise equally good routes from the same neighboring AS
+    let p = Prefix::from(0);
+    net.advertise_external_route(*E1, p, vec![AsId(65101), AsId(65201)], None, None).unwrap();
+    net.advertise_external_route(*E4, p, vec![AsId(65101), AsId(65201)], None, None).unwrap();
+
+    // without multipath, R2 only uses the route from R1, which has the lower router-id
+    let waypoint = Condition::Reachable(*R2, p, Some(PathCondition::Node(*R1)));
+    let r2 = net.get_device(*R2).unwrap_internal();
+    assert_eq!(r2.get_next_hops(Destination::BGP(p)), vec![*R1]);
+    let mut fw = net.get_forwarding_state();
+    assert_eq!(fw.get_all_routes(*R2, Destination::BGP(p)), vec![Ok(vec![*R2, *R1, *E1])]);
+    assert_eq!(waypoint.check(&mut fw), Ok(()));
+
+    // with multipath, R2 and R3 forward traffic over both routes
+    let config = BgpDecisionConfig { multipath: true, ..Default::default() };
+    for r in [*R1, *R2, *R3, *R4] {
+        set_bgp_decision(&mut net, r, config);
+    }
+    for r in [*R2, *R3] {
+        let router = net.get_device(r).unwrap_internal();
+        assert_eq!(router.get_next_hops(Destination::BGP(p)), vec![*R1, *R4]);
+    }
+    // routes learned via eBGP are still preferred
+    assert_eq!(net.get_device(*R1).unwrap_internal().get_next_hops(Destination::BGP(p)), vec![*E1]);
+    assert_eq!(net.get_device(*R4).unwrap_internal().get_next_hops(Destination::BGP(p)), vec![*E4]);
+    // the single path still follows the selected route
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R1, *E1]));
+
+    // every path of the forwarding DAG is checked
+    let mut fw = net.get_forwarding_state();
+    assert_eq!(
+        fw.get_all_routes(*R2, Destination::BGP(p)),
+        vec![Ok(vec![*R2, *R1, *E1]), Ok(vec![*R2, *R4, *E4])]
+    );
+    assert_eq!(Condition::Reachable(*R2, p, None).check(&mut fw), Ok(()));
+    assert_eq!(
+        waypoint.check(&mut fw),
+        Err(PolicyError::PathCondition {
+            path: vec![*R2, *R4, *E4],
+            condition: PathCondition::Node(*R1),
+            dest: Destination::BGP(p),
+        })
+    );
+}
+
+#[test]
+fn test_ospf_areas() {
+    let mut net = get_test_net_bgp();
+    let p = Prefix::from(0);
+    net.advertise_external_route(*E4, p, vec![AsId(65104), AsId(65200)], None, None).unwrap();
+    let igp_route = |net: &Network, src: RouterId, dst: RouterId| {
+        let fw_table = net.get_device(src).unwrap_internal().get_igp_fw_table();
+        fw_table.get(&dst).cloned().flatten().map(|(nh, c)| (nh.into_iter().collect(), c))
+    };
+
+    // a single flat area
+    assert_eq!(igp_route(&net, *R1, *R2), Some((btreeset! {*R3}, 2.0)));
+    assert_eq!(igp_route(&net, *R1, *R4), Some((btreeset! {*R3}, 3.0)));
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *R3, *R4, *E4]));
+
+    // move all links of R1 into area 1. R2 and R3 become area border routers.
+    let area = OspfArea(1);
+    let area_exprs = vec![
+        IgpLinkArea { source: *R1, target: *R2, area },
+        IgpLinkArea { source: *R3, target: *R1, area },
+        IgpLinkArea { source: *R1, target: *E1, area },
+    ];
+    for expr in area_exprs.iter() {
+        net.apply_modifier(&Insert(expr.clone())).unwrap();
+    }
+
+    // intra-area routes are preferred, even though the path through the backbone is shorter
+    assert_eq!(igp_route(&net, *R1, *R2), Some((btreeset! {*R2}, 5.0)));
+    assert_eq!(igp_route(&net, *R2, *R1), Some((btreeset! {*R1}, 5.0)));
+    // inter-area routes use the ABR with the smallest total cost
+    assert_eq!(igp_route(&net, *R1, *R4), Some((btreeset! {*R3}, 3.0)));
+    assert_eq!(igp_route(&net, *R4, *R1), Some((btreeset! {*R2, *R3}, 3.0)));
+    assert_eq!(igp_route(&net, *R4, *E1), Some((btreeset! {*R2, *R3}, 4.0)));
+    assert_eq!(igp_route(&net, *R1, *E4), Some((btreeset! {*R3}, 4.0)));
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *R3, *R4, *E4]));
+
+    // in a stub area, external routers outside of the area are reached with the default route
+    net.apply_modifier(&Insert(OspfStubArea { area })).unwrap();
+    assert_eq!(igp_route(&net, *R1, *E4), Some((btreeset! {*R3}, 2.0)));
+    assert_eq!(igp_route(&net, *R1, *E1), Some((btreeset! {*E1}, 1.0)));
+    assert_eq!(igp_route(&net, *R1, *R4), Some((btreeset! {*R3}, 3.0)));
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *R3, *R4, *E4]));
+
+    // the backbone cannot be a stub area
+    let backbone_stub = Insert(OspfStubArea { area: OspfArea::BACKBONE });
+    assert!(net.clone().apply_modifier(&backbone_stub).is_err());
+
+    // undo the stub area
+    net.undo_action().unwrap();
+    assert_eq!(igp_route(&net, *R1, *E4), Some((btreeset! {*R3}, 4.0)));
+
+    // remove the areas again
+    for expr in area_exprs {
+        net.apply_modifier(&Remove(expr)).unwrap();
+    }
+    assert_eq!(igp_route(&net, *R1, *R2), Some((btreeset! {*R3}, 2.0)));
+    assert_eq!(igp_route(&net, *R4, *R1), Some((btreeset! {*R2, *R3}, 3.0)));
+}
+
+#[test]
+fn test_link_and_router_failures() {
+    let mut net = get_test_net_bgp();
+    let p = Prefix::from(0);
+    net.advertise_external_route(*E1, p, vec![AsId(65101), AsId(65201)], None, None).unwrap();
+    net.advertise_external_route(*E4, p, vec![AsId(65104), AsId(65201)], None, None).unwrap();
+    let egress = |net: &Network, router: RouterId| net.get_route(router, p).unwrap().pop();
+    let net_before = net.clone();
+
+    // failing the external link tears down the eBGP session
+    net.fail_link(*R1, *E1).unwrap();
+    assert!(net.is_link_down(*E1, *R1));
+    for router in net.get_routers() {
+        assert_eq!(egress(&net, router), Some(*E4));
+    }
+    assert_eq!(net.fail_link(*E1, *R1), Err(NetworkError::InvalidEvent(Event::LinkDown(*E1, *R1))));
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(!net.is_link_down(*R1, *E1));
+    assert!(net == net_before);
+
+    net.fail_link(*R1, *E1).unwrap();
+    net.restore_link(*R1, *E1).unwrap();
+    assert!(net == net_before);
+
+    // failing an internal link only changes the IGP, iBGP sessions stay up
+    net.fail_link(*R1, *R3).unwrap();
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *E1]));
+    assert_eq!(egress(&net, *R3), Some(*E4));
+    let net_link_down = net.clone();
+
+    // failing a router closes all of its sessions and removes all of its links
+    net.fail_router(*R4).unwrap();
+    assert!(net.is_router_down(*R4));
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R1, *E1]));
+    assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R2, *R1, *E1]));
+    assert_eq!(net.fail_router(*R4), Err(NetworkError::InvalidEvent(Event::RouterDown(*R4))));
+
+    // reconfigure the failed router, and restore it afterwards
+    let remove_session = Remove(BgpSession { source: *R4, target: *E4, session_type: EBgp });
+    net.apply_modifier(&remove_session).unwrap();
+    net.restore_router(*R4).unwrap();
+    for router in net.get_routers() {
+        assert_eq!(egress(&net, router), Some(*E1));
+    }
+
+    // undo everything step by step
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net.is_router_down(*R4));
+    assert_eq!(net.undo_action(), Ok(true));
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net == net_link_down);
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net == net_before);
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net.is_link_down(*R1, *E1));
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net == net_before);
+
+    // restoring a link or router that is not down is not possible
+    assert_eq!(
+        net.restore_link(*R1, *R3),
+        Err(NetworkError::InvalidEvent(Event::LinkUp(*R1, *R3)))
+    );
+    assert_eq!(net.restore_router(*R4), Err(NetworkError::InvalidEvent(Event::RouterUp(*R4))));
+    assert_eq!(net.fail_link(*R1, *R4), Err(NetworkError::RoutersNotConnected(*R1, *R4)));
+}
+
+#[test]
+fn test_igp_partition_closes_ibgp_sessions() {
+    let mut net = get_test_net_bgp();
+    let p = Prefix::from(0);
+    net.advertise_external_route(*E1, p, vec![AsId(65101), AsId(65201)], None, None).unwrap();
+    net.advertise_external_route(*E4, p, vec![AsId(65104), AsId(65201)], None, None).unwrap();
+    let peers = |net: &Network, router: RouterId| -> Vec<RouterId> {
+        let mut peers: Vec<RouterId> =
+            net.get_device(router).unwrap_internal().get_bgp_sessions().map(|(r, _)| *r).collect();
+        peers.sort();
+        peers
+    };
+    let net_before = net.clone();
+
+    // R1 is still reachable over R3, so all iBGP sessions stay up
+    net.fail_link(*R1, *R2).unwrap();
+    assert_eq!(peers(&net, *R1), vec![*R2, *R3, *R4, *E1]);
+    let net_one_link_down = net.clone();
+
+    // now, R1 is cut off from all other internal routers, closing its iBGP sessions
+    net.fail_link(*R1, *R3).unwrap();
+    assert_eq!(peers(&net, *R1), vec![*E1]);
+    assert_eq!(peers(&net, *R2), vec![*R3, *R4]);
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *E1]));
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R4, *E4]));
+    assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R4, *E4]));
+
+    // undo the partition, which re-establishes the sessions
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net == net_one_link_down);
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net == net_before);
+
+    // restoring the link also re-establishes the sessions
+    net.fail_link(*R1, *R2).unwrap();
+    net.fail_link(*R1, *R3).unwrap();
+    net.restore_link(*R1, *R3).unwrap();
+    assert!(net == net_one_link_down);
+    net.restore_link(*R1, *R2).unwrap();
+    assert!(net == net_before);
+
+    // failing R2 and R3 also partitions the network between R1 and R4
+    net.fail_router(*R2).unwrap();
+    assert_eq!(peers(&net, *R1), vec![*R3, *R4, *E1]);
+    net.fail_router(*R3).unwrap();
+    assert_eq!(peers(&net, *R1), vec![*E1]);
+    assert_eq!(peers(&net, *R4), vec![*E4]);
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *E1]));
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *E4]));
+    assert_eq!(net.undo_action(), Ok(true));
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net == net_before);
+}
+
+#[test]
+fn test_bgp_network_and_redistribution() {
+    let mut net = get_test_net_bgp();
+    let p = Prefix::from(0);
+    let net_before = net.clone();
+
+    // R2 originates the prefix using a network statement
+    net.apply_modifier(&Insert(BgpNetwork { router: *R2, prefix: p })).unwrap();
+    assert!(net.get_known_prefixes().contains(&p));
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *R3, *R2]));
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2]));
+    assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R2]));
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *R2]));
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net == net_before);
+
+    // a connected prefix on R4 is only known to R4, until it is redistributed
+    net.apply_modifier(&Insert(StaticRoute { router: *R4, prefix: p, target: *R4 })).unwrap();
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4]));
+    assert_eq!(net.get_route(*R1, p), Err(NetworkError::ForwardingBlackHole(vec![*R1])));
+    let net_connected = net.clone();
+    let redistribute_connected =
+        BgpRedistribute { router: *R4, source: Connected, route_maps: vec![] };
+    net.apply_modifier(&Insert(redistribute_connected.clone())).unwrap();
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *R3, *R4]));
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R4]));
+    assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R4]));
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4]));
+    let net_redistributed = net.clone();
+
+    // redistribute a static route on R1 with a higher local preference
+    net.apply_modifier(&Insert(StaticRoute { router: *R1, prefix: p, target: *E1 })).unwrap();
+    let prefer = RouteMap::new(10, Allow, vec![], vec![Set::LocalPref(Some(200))]);
+    let redistribute_static =
+        BgpRedistribute { router: *R1, source: Static, route_maps: vec![prefer] };
+    net.apply_modifier(&Insert(redistribute_static.clone())).unwrap();
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *E1]));
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R3, *R1, *E1]));
+    assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R1, *E1]));
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4]));
+
+    // redistributed routes have origin incomplete, and can be filtered with route-maps
+    let deny = RouteMap::new(10, Deny, vec![Match::Origin(Incomplete)], vec![]);
+    let redistribute_deny = BgpRedistribute { router: *R1, source: Static, route_maps: vec![deny] };
+    net.apply_modifier(&Update { from: redistribute_static, to: redistribute_deny }).unwrap();
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *E1]));
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R4]));
+    assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R4]));
+
+    // undo everything step by step
+    assert_eq!(net.undo_action(), Ok(true));
+    assert_eq!(net.undo_action(), Ok(true));
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net == net_redistributed);
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net == net_connected);
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net == net_before);
+    assert!(!net.get_known_prefixes().contains(&p));
+
+    // the config diff generates the modifiers for network statements and redistribution
+    let mut c = net.current_config().clone();
+    c.add(BgpNetwork { router: *R2, prefix: p }).unwrap();
+    c.add(redistribute_connected).unwrap();
+    let patch = net.current_config().get_diff(&c);
+    assert_eq!(patch.modifiers.len(), 2);
+    net.apply_patch(&patch).unwrap();
+    assert_eq!(net.current_config(), &c);
+    assert_eq!(net.get_route(*R3, p), Ok(vec![*R3, *R2]));
+}
+
+#[test]
+fn test_bgp_decision_origin_and_prepend() {
+    let mut net = get_test_net_bgp().clone();
+
+    let p = Prefix::from(0);
+    let route = |next_hop, as_path, origin| BgpRoute {
+        prefix: p,
+        as_path,
+        as_set: BTreeSet::new(),
+        next_hop,
+        local_pref: None,
+        med: None,
+        origin,
+        communities: btreeset! {10, 20},
+        large_communities: btreeset! {LargeCommunity::new(65201, 1, 1)},
+        originator_id: None,
+        cluster_list: Vec::new(),
+    };
+
+    // advertise both prefixes
+    net.advertise_external_bgp_route(*E1, route(*E1, vec![AsId(65101), AsId(65201)], Igp)).unwrap();
+    net.advertise_external_bgp_route(*E4, route(*E4, vec![AsId(65104), AsId(65201)], Igp)).unwrap();
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R4, *E4]));
+
+    // a worse origin is less preferred, even before comparing the MED
+    let save = net.clone();
+    net.advertise_external_bgp_route(*E4, route(*E4, vec![AsId(65104), AsId(65201)], Incomplete))
+        .unwrap();
+    assert_eq!(net.get_route(*R1, p), Ok(vec![*R1, *E1]));
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R3, *R1, *E1]));
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *R3, *R1, *E1]));
+    assert_eq!(net.undo_action(), Ok(true));
+    assert!(net == save);
+
+    // prepending the AS path on R4 makes the route via E4 less preferred
+    net.apply_modifier(&Insert(BgpRouteMap {
+        router: *R4,
+        direction: Incoming,
+        map: RouteMapBuilder::new()
+            .order(10)
+            .allow()
+            .match_neighbor(*E4)
+            .prepend_as_path(AsId(65104), 2)
+            .build(),
+    }))
+    .unwrap();
+    assert_eq!(net.get_route(*R2, p), Ok(vec![*R2, *R3, *R1, *E1]));
+    assert_eq!(net.get_route(*R4, p), Ok(vec![*R4, *R3, *R1, *E1]));
+    let prepended = net.get_device(*R4).unwrap_internal().get_known_bgp_routes(p).unwrap();
+    let prepended = prepended.iter().find(|e| e.from_id == *E4).unwrap();
+    assert_eq!(prepended.route.as_path.len(), 4);
+    assert_eq!(prepended.route.communities, btreeset! {10, 20});
+}
+
 #[test]
 fn test_route_maps() {
     let mut original_net = get_test_net_bgp().clone();
-    let p = Prefix(0);
+    let p = Prefix::from(0);
 
     // advertise both prefixes
     let save_1 = original_net.clone();
@@ -37,7 +37,7 @@ fn test_simple() {
     // e0       e1
     let mut t = Network::new();
 
-    let prefix = Prefix(0);
+    let prefix = Prefix::from(0);
 
     let e0 = t.add_external_router("E0", AsId(1));
     let b0 = t.add_router("B0");
@@ -104,7 +104,7 @@ fn test_external_router() {
     //          r3 ---- r4 ---- e4
 
     let mut n = Network::new();
-    let prefix = Prefix(0);
+    let prefix = Prefix::from(0);
 
     // add routers
     let r1 = n.add_router("r1");
@@ -238,7 +238,7 @@ fn test_route_order1() {
     // e1       e0
     let mut t = Network::new();
 
-    let prefix = Prefix(0);
+    let prefix = Prefix::from(0);
 
     let e0 = t.add_external_router("E0", AsId(1));
     let b0 = t.add_router("B0");
@@ -297,7 +297,7 @@ fn test_route_order2() {
     // e1       e0
     let mut t = Network::new();
 
-    let prefix = Prefix(0);
+    let prefix = Prefix::from(0);
 
     let e0 = t.add_external_router("E0", AsId(1));
     let b0 = t.add_router("B0");
@@ -358,7 +358,7 @@ fn test_bad_gadget() {
     //    e0       e1       e2
     let mut t = Network::new();
 
-    let prefix = Prefix(0);
+    let prefix = Prefix::from(0);
 
     let e0 = t.add_external_router("E0", AsId(65100));
     let e1 = t.add_external_router("E1", AsId(65101));
@@ -465,7 +465,7 @@ fn change_ibgp_topology_1() {
 
     let mut n = Network::new();
 
-    let prefix = Prefix(0);
+    let prefix = Prefix::from(0);
 
     let rr = n.add_router("rr");
     let r1 = n.add_router("r1");
@@ -606,7 +606,7 @@ fn change_ibgp_topology_2() {
 
     let mut n = Network::new();
 
-    let prefix = Prefix(0);
+    let prefix = Prefix::from(0);
 
     let rr = n.add_router("rr");
     let r1 = n.add_router("r1");
@@ -817,8 +817,8 @@ fn change_ibgp_topology_2() {
 fn test_twicebad_gadget() {
     // Example from L. Vanbever bgpmig_ton, figure 4
     let mut n = Network::new();
-    let prefix1 = Prefix(1);
-    let prefix2 = Prefix(2);
+    let prefix1 = Prefix::from(1);
+    let prefix2 = Prefix::from(2);
 
     let r1 = n.add_router("r1");
     let r2 = n.add_router("r2");
@@ -965,7 +965,7 @@ fn test_twicebad_gadget() {
 fn test_pylon_gadget() {
     // Example from L. Vanbever bgpmig_ton, figure 5
     let mut n = Network::new();
-    let prefix = Prefix(0);
+    let prefix = Prefix::from(0);
 
     let s = n.add_router("s");
     let rr1 = n.add_router("rr1");
@@ -1088,8 +1088,8 @@ fn test_pylon_gadget() {
 fn carousel_gadget() {
     // Example from L. Vanbever bgpmig_ton, figure 6
     let mut n = Network::new();
-    let prefix1 = Prefix(1);
-    let prefix2 = Prefix(2);
+    let prefix1 = Prefix::from(1);
+    let prefix2 = Prefix::from(2);
 
     let rr = n.add_router("rr");
     let r1 = n.add_router("r1");
@@ -1335,7 +1335,7 @@ fn assert_route_bad(n: &Network, source: RouterId, prefix: Prefix, exp: Vec<Rout
             acq, exp,
             "Bad route expected on path on {} for prefix {}, but got a correct path:\n        acq: {:?}, exp: {:?}",
             n.get_router_name(source).unwrap(),
-            prefix.0,
+            prefix,
             acq,
             exp
         );
@@ -1354,7 +1354,7 @@ fn assert_route_bad(n: &Network, source: RouterId, prefix: Prefix, exp: Vec<Rout
             &exp,
             "Unexpected path on {} for prefix {}:\n        acq: {:?}, exp: {:?}",
             n.get_router_name(source).unwrap(),
-            prefix.0,
+            prefix,
             &acq,
             &exp
         )
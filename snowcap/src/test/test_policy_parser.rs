@@ -0,0 +1,154 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! Test the runtime parser of hard policies.
+
+use crate::hard_policies::Waypoint::*;
+use crate::hard_policies::{parse_policy, Condition, LTLOperator, ParseError, PathCondition};
+use crate::netsim::{AsId, Network, Prefix, RouterId};
+
+fn get_test_net() -> (Network, RouterId, RouterId, RouterId, RouterId) {
+    let mut net = Network::new();
+    let zurich = net.add_router("Zurich");
+    let basel = net.add_router("Basel");
+    let geneva = net.add_router("Geneva");
+    let ext = net.add_external_router("Upstream Provider", AsId(65100));
+    (net, zurich, basel, geneva, ext)
+}
+
+#[test]
+fn parse_example() {
+    let (net, zurich, basel, geneva, _) = get_test_net();
+    let p = Prefix::from(10);
+    let policy =
+        parse_policy(&net, "G(reach(Zurich, 10) & !via(Zurich, Basel, 10)) U reach(Geneva, 10)")
+            .unwrap();
+    assert_eq!(
+        policy.prop_vars,
+        vec![
+            Condition::Reachable(zurich, p, None),
+            Condition::Reachable(zurich, p, Some(PathCondition::Node(basel))),
+            Condition::Reachable(geneva, p, None),
+        ]
+    );
+    assert_eq!(policy.expr.repr(), "((G (x00 && !x01)) U x02)");
+}
+
+#[test]
+fn parse_precedence() {
+    let (net, ..) = get_test_net();
+    let parse = |s| parse_policy(&net, s).unwrap().expr.repr();
+
+    // equal conditions share the same propositional variable
+    assert_eq!(parse("reach(Zurich, 1) && reach(Zurich, 1)"), "(x00 && x00)");
+    assert_eq!(parse("isolated(Basel, 1) | reach(Zurich, 1) & true"), "(x00 || (x01 && true))");
+    assert_eq!(
+        parse("!reach(Zurich, 1) ^ false -> F X reach(Basel, 1)"),
+        "((!x00 ^^ false) => (F (N x01)))"
+    );
+    assert_eq!(parse("reach(Zurich, 1) <-> reach(Basel, 1) -> false"), "(x00 <=> (x01 => false))");
+    assert_eq!(
+        parse("reach(Zurich, 1) & reach(Basel, 1) W G reach(Geneva, 1)"),
+        "(x00 && (x01 W (G x02)))"
+    );
+    assert_eq!(parse("true U false R true M (false)"), "(true U (false R (true M false)))");
+}
+
+#[test]
+fn parse_conditions() {
+    let (net, zurich, basel, geneva, ext) = get_test_net();
+    let p: Prefix = "10.0.0.0/8".parse().unwrap();
+    let policy = parse_policy(
+        &net,
+        "reach(Zurich, 10.0.0.0/8, [Zurich, *, Basel, ?]) & reliable(Basel, 10.0.0.0/8) \
+         & transient(Geneva, 10.0.0.0/8, !edge(Geneva, Basel) & (Zurich | \"Upstream Provider\")) \
+         & reach_igp(Zurich, Geneva) & isolated_igp(Basel, Zurich)",
+    )
+    .unwrap();
+    assert_eq!(
+        policy.prop_vars,
+        vec![
+            Condition::Reachable(
+                zurich,
+                p,
+                Some(PathCondition::Positional(vec![Fix(zurich), Star, Fix(basel), Any]))
+            ),
+            Condition::Reliable(basel, p, None),
+            Condition::TransientPath(
+                geneva,
+                p,
+                PathCondition::And(vec![
+                    PathCondition::Not(Box::new(PathCondition::Edge(geneva, basel))),
+                    PathCondition::Or(vec![PathCondition::Node(zurich), PathCondition::Node(ext)])
+                ])
+            ),
+            Condition::ReachableIGP(zurich, geneva, None),
+            Condition::NotReachableIGP(basel, zurich),
+        ]
+    );
+
+    // waypoint is equal to reach with a positional path condition
+    let policy = parse_policy(&net, "waypoint(Zurich, 1, [*, Basel, *])").unwrap();
+    let path = PathCondition::Positional(vec![Star, Fix(basel), Star]);
+    assert_eq!(policy.prop_vars, vec![Condition::Reachable(zurich, Prefix::from(1), Some(path))]);
+    assert_eq!(
+        parse_policy(&net, "waypoint(Zurich, 1, Basel)").map(|_| ()),
+        Err(ParseError::InvalidArguments { pos: 0, name: String::from("waypoint") })
+    );
+}
+
+#[test]
+fn parse_errors() {
+    let (net, ..) = get_test_net();
+    let parse = |s| parse_policy(&net, s).map(|_| ());
+
+    assert_eq!(
+        parse("reach(Bern, 1)"),
+        Err(ParseError::UnknownRouter { pos: 6, name: String::from("Bern") })
+    );
+    assert_eq!(
+        parse("reachable(Zurich, 1)"),
+        Err(ParseError::UnknownCondition { pos: 0, name: String::from("reachable") })
+    );
+    assert_eq!(
+        parse("G isolated(Zurich)"),
+        Err(ParseError::InvalidArguments { pos: 2, name: String::from("isolated") })
+    );
+    assert_eq!(
+        parse("isolated(Zurich, 1, 2)"),
+        Err(ParseError::InvalidArguments { pos: 0, name: String::from("isolated") })
+    );
+    assert_eq!(
+        parse("reach(Zurich, 1.2)"),
+        Err(ParseError::InvalidPrefix { pos: 14, name: String::from("1.2") })
+    );
+    assert_eq!(
+        parse("reach(Zurich, 16777216)"),
+        Err(ParseError::InvalidPrefix { pos: 14, name: String::from("16777216") })
+    );
+    assert_eq!(
+        parse("reach(Zurich, 1) reach(Basel, 1)"),
+        Err(ParseError::UnexpectedToken {
+            pos: 17,
+            expected: String::from("end of input"),
+            found: String::from("\"reach\"")
+        })
+    );
+    assert_eq!(parse("G (reach(Zurich, 1)"), Err(ParseError::UnexpectedEnd(String::from("\")\""))));
+    assert_eq!(parse("reach(Zurich, 1) $"), Err(ParseError::InvalidCharacter(17, '$')));
+    assert_eq!(parse("reach(\"Zurich, 1)"), Err(ParseError::UnterminatedString(6)));
+}
@@ -0,0 +1,227 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! Test IPv4 / IPv6 prefixes and longest prefix matching.
+
+use crate::hard_policies::{Condition, PathCondition};
+use crate::netsim::config::{Config, ConfigExpr::*, ConfigModifier::*};
+use crate::netsim::types::Destination;
+use crate::netsim::{
+    AsId, BgpSessionType::*, ForwardingState, Network, NetworkError, Prefix, PrefixError, RouterId,
+};
+
+fn p(s: &str) -> Prefix {
+    s.parse().unwrap()
+}
+
+#[test]
+fn parse_prefix() {
+    let prefix = p("10.1.2.3/16");
+    assert_eq!(prefix, Prefix::new([10, 1, 0, 0], 16));
+    assert_eq!(prefix.to_string(), "10.1.0.0/16");
+    assert_eq!(prefix.prefix_len(), 16);
+    assert!(prefix.is_ipv4());
+
+    assert_eq!(p("10.1.2.3"), Prefix::new([10, 1, 2, 3], 32));
+    assert_eq!(p("2001:db8::1/32").to_string(), "2001:db8::/32");
+    assert_eq!(p("2001:db8::1").prefix_len(), 128);
+    assert!(p("::/0").is_ipv6());
+
+    assert_eq!("10.0.0.0/33".parse::<Prefix>(), Err(PrefixError::InvalidLength(33)));
+    assert_eq!("::/129".parse::<Prefix>(), Err(PrefixError::InvalidLength(129)));
+    assert!(matches!("10.0.0/8".parse::<Prefix>(), Err(PrefixError::InvalidAddress(_))));
+    assert!(matches!("10.0.0.0/x".parse::<Prefix>(), Err(PrefixError::InvalidAddress(_))));
+}
+
+#[test]
+fn prefix_from_id() {
+    assert_eq!(Prefix::from(1), p("0.0.1.0/24"));
+    assert_eq!(Prefix::try_from_id((1 << 24) - 1), Ok(p("255.255.255.0/24")));
+    assert_eq!(Prefix::try_from_id(1 << 24), Err(PrefixError::IdOutOfRange(1 << 24)));
+}
+
+#[test]
+fn prefix_contains() {
+    assert!(p("10.0.0.0/8").contains(&p("10.0.0.0/8")));
+    assert!(p("10.0.0.0/8").contains(&p("10.1.0.0/16")));
+    assert!(p("10.0.0.0/8").contains(&p("10.255.255.255/32")));
+    assert!(!p("10.0.0.0/8").contains(&p("11.0.0.0/16")));
+    assert!(!p("10.1.0.0/16").contains(&p("10.0.0.0/8")));
+    assert!(p("0.0.0.0/0").contains(&p("192.168.0.0/16")));
+    assert!(!p("0.0.0.0/0").contains(&p("::/0")));
+    assert!(p("2001:db8::/32").contains(&p("2001:db8:1::/48")));
+    assert!(!p("2001:db8::/32").contains(&p("2001:db9::/48")));
+}
+
+/// # Test network
+///
+/// ```text
+/// E1 ---- R1 ---- R2 ---- E2
+/// ```
+///
+/// E1 advertises `10.0.0.0/8` and E2 advertises the more specific prefix `10.1.0.0/16`.
+fn get_test_net() -> (Network, RouterId, RouterId, RouterId, RouterId) {
+    let mut net = Network::new();
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let e1 = net.add_external_router("E1", AsId(65101));
+    let e2 = net.add_external_router("E2", AsId(65102));
+
+    net.add_link(r1, r2);
+    net.add_link(r1, e1);
+    net.add_link(r2, e2);
+
+    let mut c = Config::new();
+    for (a, b) in [(r1, r2), (r1, e1), (r2, e2)] {
+        c.add(IgpLinkWeight { source: a, target: b, weight: 1.0 }).unwrap();
+        c.add(IgpLinkWeight { source: b, target: a, weight: 1.0 }).unwrap();
+    }
+    c.add(BgpSession { source: r1, target: r2, session_type: IBgpPeer }).unwrap();
+    c.add(BgpSession { source: r1, target: e1, session_type: EBgp }).unwrap();
+    c.add(BgpSession { source: r2, target: e2, session_type: EBgp }).unwrap();
+    net.set_config(&c).unwrap();
+
+    net.advertise_external_route(e1, p("10.0.0.0/8"), vec![AsId(65101)], None, None).unwrap();
+    net.advertise_external_route(e2, p("10.1.0.0/16"), vec![AsId(65102)], None, None).unwrap();
+
+    (net, r1, r2, e1, e2)
+}
+
+#[test]
+fn longest_prefix_match_network() {
+    let (mut net, r1, r2, e1, e2) = get_test_net();
+
+    // exact matches
+    assert_eq!(net.get_route(r1, p("10.0.0.0/8")), Ok(vec![r1, e1]));
+    assert_eq!(net.get_route(r1, p("10.1.0.0/16")), Ok(vec![r1, r2, e2]));
+    // more specific destinations use the longest matching prefix
+    assert_eq!(net.get_route(r1, p("10.1.2.0/24")), Ok(vec![r1, r2, e2]));
+    assert_eq!(net.get_route(r2, p("10.1.2.3/32")), Ok(vec![r2, e2]));
+    assert_eq!(net.get_route(r2, p("10.2.0.0/16")), Ok(vec![r2, r1, e1]));
+    // no prefix contains the destination
+    assert_eq!(
+        net.get_route(r1, p("11.0.0.0/8")),
+        Err(NetworkError::ForwardingBlackHole(vec![r1]))
+    );
+    assert_eq!(net.get_route(r1, p("::/0")), Err(NetworkError::ForwardingBlackHole(vec![r1])));
+
+    // after withdrawing the more specific prefix, the less specific one is used
+    net.retract_external_route(e2, p("10.1.0.0/16")).unwrap();
+    assert_eq!(net.get_route(r2, p("10.1.2.0/24")), Ok(vec![r2, r1, e1]));
+    assert_eq!(net.get_route(r2, p("10.1.0.0/16")), Ok(vec![r2, r1, e1]));
+}
+
+#[test]
+fn longest_prefix_match_static_route() {
+    let (mut net, r1, r2, e1, e2) = get_test_net();
+
+    // more specific static routes on r2 and r1 override the BGP route
+    net.apply_modifier(&Insert(StaticRoute { router: r1, prefix: p("10.1.2.0/24"), target: e1 }))
+        .unwrap();
+    net.apply_modifier(&Insert(StaticRoute { router: r2, prefix: p("10.1.2.0/24"), target: r1 }))
+        .unwrap();
+    assert_eq!(net.get_route(r2, p("10.1.2.3/32")), Ok(vec![r2, r1, e1]));
+    assert_eq!(net.get_route(r2, p("10.1.3.0/24")), Ok(vec![r2, e2]));
+}
+
+#[test]
+fn longest_prefix_match_forwarding_state() {
+    let (net, r1, r2, e1, e2) = get_test_net();
+    let mut fw = ForwardingState::from_net(&net);
+
+    assert_eq!(fw.get_route(r1, p("10.0.0.0/8")), Ok(vec![r1, e1]));
+    assert_eq!(fw.get_route(r1, p("10.1.2.0/24")), Ok(vec![r1, r2, e2]));
+    assert_eq!(fw.get_route(r2, p("10.2.0.0/16")), Ok(vec![r2, r1, e1]));
+    assert_eq!(fw.get_route(r1, p("11.0.0.0/8")), Err(NetworkError::ForwardingBlackHole(vec![r1])));
+    assert_eq!(fw.get_next_hop(r1, p("10.1.2.3/32")), Ok(Some(r2)));
+    assert_eq!(fw.get_next_hop(r1, p("11.0.0.0/8")), Ok(None));
+
+    let mut fw = net.get_forwarding_state();
+    assert_eq!(fw.get_longest_known_prefix(p("10.1.2.0/24")), Some(p("10.1.0.0/16")));
+    assert_eq!(fw.get_longest_known_prefix(p("10.2.0.0/16")), Some(p("10.0.0.0/8")));
+    assert_eq!(fw.get_longest_known_prefix(p("11.0.0.0/8")), None);
+    assert_eq!(fw.get_route_new(r1, Destination::BGP(p("10.1.2.0/24"))), Ok(vec![r1, r2, e2]));
+    assert_eq!(
+        fw.get_route_new(r1, Destination::BGP(p("11.0.0.0/8"))),
+        Err(NetworkError::ForwardingBlackHole(vec![r1]))
+    );
+}
+
+#[test]
+fn bgp_aggregate() {
+    let (mut net, r1, r2, e1, e2) = get_test_net();
+    net.advertise_external_route(e2, p("10.2.1.0/24"), vec![AsId(65102), AsId(65201)], None, None)
+        .unwrap();
+    net.advertise_external_route(e2, p("10.2.2.0/24"), vec![AsId(65102), AsId(65202)], None, None)
+        .unwrap();
+    let net_before = net.clone();
+    let selected = |net: &Network, prefix: &str| {
+        net.get_device(r1).unwrap_internal().get_selected_bgp_route(p(prefix)).map(|e| e.route)
+    };
+    assert_eq!(net.get_route(r1, p("10.2.3.0/24")), Ok(vec![r1, e1]));
+
+    // r2 aggregates both more-specific prefixes, and drops traffic for the remaining addresses
+    let aggregate =
+        BgpAggregate { router: r2, prefix: p("10.2.0.0/16"), summary_only: false, as_set: false };
+    net.apply_modifier(&Insert(aggregate.clone())).unwrap();
+    let route = selected(&net, "10.2.0.0/16").unwrap();
+    assert_eq!(route.next_hop, r2);
+    assert!(route.as_path.is_empty());
+    assert!(selected(&net, "10.2.1.0/24").is_some());
+    assert_eq!(net.get_route(r1, p("10.2.1.0/24")), Ok(vec![r1, r2, e2]));
+    assert_eq!(
+        net.get_route(r1, p("10.2.3.0/24")),
+        Err(NetworkError::ForwardingBlackHole(vec![r1, r2]))
+    );
+
+    // suppress the more-specifics, and carry the AS set
+    let summary =
+        BgpAggregate { router: r2, prefix: p("10.2.0.0/16"), summary_only: true, as_set: true };
+    net.apply_modifier(&Update { from: aggregate, to: summary }).unwrap();
+    let route = selected(&net, "10.2.0.0/16").unwrap();
+    assert!(route.as_path.is_empty());
+    assert_eq!(route.as_set, vec![AsId(65102), AsId(65201), AsId(65202)].into_iter().collect());
+    assert_eq!(route.as_path_len(), 1);
+    assert!(selected(&net, "10.2.1.0/24").is_none());
+    assert_eq!(net.get_route(r1, p("10.2.1.0/24")), Ok(vec![r1, r2, e2]));
+
+    // the AS_SET counts as a single AS, so the aggregate is preferred over a longer AS path
+    net.advertise_external_route(e1, p("10.2.0.0/16"), vec![AsId(65101), AsId(65300)], None, None)
+        .unwrap();
+    assert_eq!(selected(&net, "10.2.0.0/16").unwrap().next_hop, r2);
+    net.retract_external_route(e1, p("10.2.0.0/16")).unwrap();
+
+    // policies are checked on the forwarding state using the aggregate
+    let mut fw = net.get_forwarding_state();
+    assert!(Condition::Reachable(r1, p("10.2.2.0/24"), None).check(&mut fw).is_ok());
+    let via_r2 = Some(PathCondition::Node(r2));
+    assert!(Condition::Reachable(r1, p("10.2.2.128/25"), via_r2).check(&mut fw).is_ok());
+    assert!(Condition::NotReachable(r1, p("10.2.3.0/24")).check(&mut fw).is_ok());
+    assert!(Condition::Reachable(r1, p("10.2.0.0/16"), None).check(&mut fw).is_err());
+
+    // without contributing routes, the aggregate is withdrawn
+    net.retract_external_route(e2, p("10.2.1.0/24")).unwrap();
+    assert!(selected(&net, "10.2.0.0/16").is_some());
+    net.retract_external_route(e2, p("10.2.2.0/24")).unwrap();
+    assert!(selected(&net, "10.2.0.0/16").is_none());
+    assert_eq!(net.get_route(r1, p("10.2.3.0/24")), Ok(vec![r1, e1]));
+
+    for _ in 0..6 {
+        assert_eq!(net.undo_action(), Ok(true));
+    }
+    assert!(net == net_before);
+}
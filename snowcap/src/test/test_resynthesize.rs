@@ -0,0 +1,144 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! Test resuming the synthesis from an already applied prefix.
+
+use crate::example_networks::repetitions::*;
+use crate::example_networks::*;
+use crate::hard_policies::HardPolicy;
+use crate::netsim::config::{Config, ConfigModifier};
+use crate::netsim::Network;
+use crate::resynthesize;
+
+/// Check that the sequence is valid according to the hard policy, and that it reaches `config_b`.
+fn check_sequence(
+    net: &Network,
+    hard_policy: &HardPolicy,
+    sequence: &[ConfigModifier],
+    config_b: &Config,
+) {
+    let mut net = net.clone();
+    let mut hard_policy = hard_policy.clone();
+    hard_policy.set_num_mods_if_none(sequence.len());
+    let mut fw_state = net.get_forwarding_state();
+    hard_policy.step(&mut net, &mut fw_state).unwrap();
+    assert!(hard_policy.check());
+    for modifier in sequence {
+        net.apply_modifier(modifier).unwrap();
+        let mut fw_state = net.get_forwarding_state();
+        hard_policy.step(&mut net, &mut fw_state).unwrap();
+        assert!(hard_policy.check());
+    }
+    assert_eq!(net.current_config().get_diff(config_b).modifiers, vec![]);
+}
+
+fn prepare<N: ExampleNetwork>() -> (Network, Config, Config, HardPolicy) {
+    let net = N::net(0);
+    let config_a = net.current_config().clone();
+    let config_b = N::final_config(&net, 0);
+    let hard_policy =
+        HardPolicy::reachability(net.get_routers().iter(), net.get_known_prefixes().iter());
+    (net, config_a, config_b, hard_policy)
+}
+
+fn test_resume<N: ExampleNetwork>(k: usize) {
+    let (net, config_a, config_b, hard_policy) = prepare::<N>();
+
+    let initial = resynthesize(
+        net.clone(),
+        config_a.clone(),
+        &[],
+        config_b.clone(),
+        hard_policy.clone(),
+        &[],
+        None,
+    )
+    .unwrap();
+    assert!(initial.is_compatible());
+    check_sequence(&net, &hard_policy, &initial.sequence, &config_b);
+
+    // resume after k modifiers, with the same target
+    let applied = &initial.sequence[..k];
+    let resumed = resynthesize(
+        net.clone(),
+        config_a,
+        applied,
+        config_b.clone(),
+        hard_policy.clone(),
+        &initial.groups,
+        None,
+    )
+    .unwrap();
+    assert!(resumed.is_compatible());
+    assert_eq!(resumed.sequence.len(), initial.sequence.len() - k);
+    assert!(resumed.groups.iter().flatten().all(|m| resumed.sequence.contains(m)));
+    let full: Vec<ConfigModifier> =
+        applied.iter().chain(resumed.sequence.iter()).cloned().collect();
+    check_sequence(&net, &hard_policy, &full, &config_b);
+}
+
+#[test]
+fn resynthesize_resume_simple_net() {
+    test_resume::<SimpleNet>(1);
+}
+
+#[test]
+fn resynthesize_resume_chain_gadget() {
+    test_resume::<ChainGadget<Repetition5>>(3);
+}
+
+#[test]
+fn resynthesize_resume_medium_net() {
+    test_resume::<MediumNet>(2);
+}
+
+#[test]
+fn resynthesize_revised_target() {
+    let (net, config_a, config_b, hard_policy) = prepare::<MediumNet>();
+
+    let initial = resynthesize(
+        net.clone(),
+        config_a.clone(),
+        &[],
+        config_b.clone(),
+        hard_policy.clone(),
+        &[],
+        None,
+    )
+    .unwrap();
+
+    // apply two modifiers, and then revise the target to revert the first one
+    let applied = &initial.sequence[..2];
+    let mut revised_b = config_b;
+    revised_b.apply_modifier(&applied[0].clone().reverse()).unwrap();
+
+    let resumed = resynthesize(
+        net.clone(),
+        config_a,
+        applied,
+        revised_b.clone(),
+        hard_policy.clone(),
+        &initial.groups,
+        None,
+    )
+    .unwrap();
+    assert!(!resumed.is_compatible());
+    assert_eq!(resumed.incompatible, vec![applied[0].clone()]);
+    let full: Vec<ConfigModifier> =
+        applied.iter().chain(resumed.sequence.iter()).cloned().collect();
+    check_sequence(&net, &hard_policy, &full, &revised_b);
+}
@@ -16,25 +16,37 @@
 // 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
 
 use crate::netsim::bgp::BgpSessionType::*;
-use crate::netsim::bgp::{BgpRibEntry, BgpRoute};
+use crate::netsim::bgp::{BgpRibEntry, BgpRoute, LargeCommunity, Origin};
 use crate::netsim::route_map::RouteMapMatch as Match;
 use crate::netsim::route_map::RouteMapMatchAsPath as AClause;
 use crate::netsim::route_map::RouteMapMatchClause as Clause;
+use crate::netsim::route_map::RouteMapMatchPrefix as PClause;
 use crate::netsim::route_map::RouteMapSet as Set;
 use crate::netsim::route_map::RouteMapState::*;
 use crate::netsim::route_map::*;
 use crate::netsim::{AsId, Prefix};
+use maplit::btreeset;
+use std::collections::BTreeSet;
+
+fn p(s: &str) -> Prefix {
+    s.parse().unwrap()
+}
 
 #[test]
 fn simple_matches() {
     let default_entry = BgpRibEntry {
         route: BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(0)],
+            as_set: BTreeSet::new(),
             next_hop: 0.into(),
             local_pref: None,
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         },
         from_type: IBgpClient,
         from_id: 0.into(),
@@ -51,37 +63,38 @@ fn simple_matches() {
     assert_eq!(map.apply(entry.clone()).0, false);
 
     // Match on Prefix, exact
-    let map = RouteMap::new(10, Deny, vec![Match::Prefix(Clause::Equal(Prefix(0)))], vec![]);
+    let map = RouteMap::new(10, Deny, vec![Match::Prefix(PClause::Equal(Prefix::from(0)))], vec![]);
     let mut entry = default_entry.clone();
-    entry.route.prefix = Prefix(0);
+    entry.route.prefix = Prefix::from(0);
     assert_eq!(map.apply(entry.clone()).0, true);
-    entry.route.prefix = Prefix(1);
+    entry.route.prefix = Prefix::from(1);
     assert_eq!(map.apply(entry.clone()).0, false);
 
-    // Match on Prefix with range
-    let map =
-        RouteMap::new(10, Deny, vec![Match::Prefix(Clause::Range(Prefix(0), Prefix(9)))], vec![]);
+    // Match on Prefix with range (CIDR containment)
+    let map = RouteMap::new(10, Deny, vec![Match::Prefix(PClause::Range(p("10.0.0.0/8")))], vec![]);
     let mut entry = default_entry.clone();
-    entry.route.prefix = Prefix(0);
+    entry.route.prefix = p("10.0.0.0/8");
     assert_eq!(map.apply(entry.clone()).0, true);
-    entry.route.prefix = Prefix(9);
+    entry.route.prefix = p("10.1.0.0/16");
     assert_eq!(map.apply(entry.clone()).0, true);
-    entry.route.prefix = Prefix(10);
+    entry.route.prefix = p("10.1.2.3/32");
+    assert_eq!(map.apply(entry.clone()).0, true);
+    entry.route.prefix = p("0.0.0.0/0");
+    assert_eq!(map.apply(entry.clone()).0, false);
+    entry.route.prefix = p("11.0.0.0/8");
+    assert_eq!(map.apply(entry.clone()).0, false);
+    entry.route.prefix = p("::/0");
     assert_eq!(map.apply(entry.clone()).0, false);
 
-    // Match on Prefix with exclusive_range
-    let map = RouteMap::new(
-        10,
-        Deny,
-        vec![Match::Prefix(Clause::RangeExclusive(Prefix(0), Prefix(10)))],
-        vec![],
-    );
+    // Match on IPv6 Prefix with range
+    let map =
+        RouteMap::new(10, Deny, vec![Match::Prefix(PClause::Range(p("2001:db8::/32")))], vec![]);
     let mut entry = default_entry.clone();
-    entry.route.prefix = Prefix(0);
-    assert_eq!(map.apply(entry.clone()).0, true);
-    entry.route.prefix = Prefix(9);
+    entry.route.prefix = p("2001:db8:1::/48");
     assert_eq!(map.apply(entry.clone()).0, true);
-    entry.route.prefix = Prefix(10);
+    entry.route.prefix = p("2001:db9::/32");
+    assert_eq!(map.apply(entry.clone()).0, false);
+    entry.route.prefix = p("10.0.0.0/8");
     assert_eq!(map.apply(entry.clone()).0, false);
 
     // Match on AsPath to contain 0
@@ -130,31 +143,31 @@ fn simple_matches() {
     // Match on communits, not set
     let map = RouteMap::new(10, Deny, vec![Match::Community(None)], vec![]);
     let mut entry = default_entry.clone();
-    entry.route.community = None;
+    entry.route.communities = btreeset! {};
     assert_eq!(map.apply(entry.clone()).0, true);
-    entry.route.community = Some(0);
+    entry.route.communities = btreeset! {0};
     assert_eq!(map.apply(entry.clone()).0, false);
 
     // Match on Community, exact
     let map = RouteMap::new(10, Deny, vec![Match::Community(Some(Clause::Equal(0)))], vec![]);
     let mut entry = default_entry.clone();
-    entry.route.community = Some(0);
+    entry.route.communities = btreeset! {0};
     assert_eq!(map.apply(entry.clone()).0, true);
-    entry.route.community = Some(1);
+    entry.route.communities = btreeset! {1};
     assert_eq!(map.apply(entry.clone()).0, false);
-    entry.route.community = None;
+    entry.route.communities = btreeset! {};
     assert_eq!(map.apply(entry.clone()).0, false);
 
     // Match on Community with range
     let map = RouteMap::new(10, Deny, vec![Match::Community(Some(Clause::Range(0, 9)))], vec![]);
     let mut entry = default_entry.clone();
-    entry.route.community = Some(0);
+    entry.route.communities = btreeset! {0};
     assert_eq!(map.apply(entry.clone()).0, true);
-    entry.route.community = Some(9);
+    entry.route.communities = btreeset! {9};
     assert_eq!(map.apply(entry.clone()).0, true);
-    entry.route.community = Some(10);
+    entry.route.communities = btreeset! {10};
     assert_eq!(map.apply(entry.clone()).0, false);
-    entry.route.community = None;
+    entry.route.communities = btreeset! {};
     assert_eq!(map.apply(entry.clone()).0, false);
 
     // Match on Community with exclusive_range
@@ -165,13 +178,49 @@ fn simple_matches() {
         vec![],
     );
     let mut entry = default_entry.clone();
-    entry.route.community = Some(0);
+    entry.route.communities = btreeset! {0};
     assert_eq!(map.apply(entry.clone()).0, true);
-    entry.route.community = Some(9);
+    entry.route.communities = btreeset! {9};
     assert_eq!(map.apply(entry.clone()).0, true);
-    entry.route.community = Some(10);
+    entry.route.communities = btreeset! {10};
     assert_eq!(map.apply(entry.clone()).0, false);
-    entry.route.community = None;
+    entry.route.communities = btreeset! {};
+    assert_eq!(map.apply(entry.clone()).0, false);
+
+    // Match on Community, with multiple communities set
+    let map = RouteMap::new(10, Deny, vec![Match::Community(Some(Clause::Equal(5)))], vec![]);
+    let mut entry = default_entry.clone();
+    entry.route.communities = btreeset! {1, 5, 10};
+    assert_eq!(map.apply(entry.clone()).0, true);
+    entry.route.communities = btreeset! {1, 10};
+    assert_eq!(map.apply(entry.clone()).0, false);
+
+    // Match on large communities, not set
+    let map = RouteMap::new(10, Deny, vec![Match::LargeCommunity(None)], vec![]);
+    let mut entry = default_entry.clone();
+    assert_eq!(map.apply(entry.clone()).0, true);
+    entry.route.large_communities = btreeset! {LargeCommunity::new(65000, 1, 2)};
+    assert_eq!(map.apply(entry.clone()).0, false);
+
+    // Match on large community
+    let lc = LargeCommunity::new(65000, 1, 2);
+    let map = RouteMap::new(10, Deny, vec![Match::LargeCommunity(Some(lc))], vec![]);
+    let mut entry = default_entry.clone();
+    entry.route.large_communities = btreeset! {lc, LargeCommunity::new(65000, 3, 4)};
+    assert_eq!(map.apply(entry.clone()).0, true);
+    entry.route.large_communities = btreeset! {LargeCommunity::new(65000, 1, 3)};
+    assert_eq!(map.apply(entry.clone()).0, false);
+    entry.route.large_communities = btreeset! {};
+    assert_eq!(map.apply(entry.clone()).0, false);
+
+    // Match on origin
+    let map = RouteMap::new(10, Deny, vec![Match::Origin(Origin::Egp)], vec![]);
+    let mut entry = default_entry.clone();
+    entry.route.origin = Origin::Egp;
+    assert_eq!(map.apply(entry.clone()).0, true);
+    entry.route.origin = Origin::Igp;
+    assert_eq!(map.apply(entry.clone()).0, false);
+    entry.route.origin = Origin::Incomplete;
     assert_eq!(map.apply(entry.clone()).0, false);
 }
 
@@ -179,12 +228,17 @@ fn simple_matches() {
 fn complex_matches() {
     let default_entry = BgpRibEntry {
         route: BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(0)],
+            as_set: BTreeSet::new(),
             next_hop: 0.into(),
             local_pref: None,
             med: None,
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         },
         from_type: IBgpClient,
         from_id: 0.into(),
@@ -221,12 +275,17 @@ fn complex_matches() {
 fn overwrite() {
     let default_entry = BgpRibEntry {
         route: BgpRoute {
-            prefix: Prefix(0),
+            prefix: Prefix::from(0),
             as_path: vec![AsId(0)],
+            as_set: BTreeSet::new(),
             next_hop: 0.into(),
             local_pref: Some(1),
             med: Some(10),
-            community: None,
+            origin: Origin::Igp,
+            communities: BTreeSet::new(),
+            large_communities: BTreeSet::new(),
+            originator_id: None,
+            cluster_list: Vec::new(),
         },
         from_type: IBgpClient,
         from_id: 0.into(),
@@ -259,6 +318,48 @@ fn overwrite() {
     let map = RouteMap::new(10, Allow, vec![], vec![Set::IgpCost(20.0)]);
     assert_eq!(map.apply(default_entry.clone()).1.unwrap().igp_cost, Some(20.0));
 
+    // Community (replace and reset)
+    let mut entry = default_entry.clone();
+    entry.route.communities = btreeset! {1, 2};
+    let map = RouteMap::new(10, Allow, vec![], vec![Set::Community(Some(3))]);
+    assert_eq!(map.apply(entry.clone()).1.unwrap().route.communities, btreeset! {3});
+    let map = RouteMap::new(10, Allow, vec![], vec![Set::Community(None)]);
+    assert_eq!(map.apply(entry.clone()).1.unwrap().route.communities, btreeset! {});
+
+    // Community (add and delete)
+    let map = RouteMap::new(10, Allow, vec![], vec![Set::AddCommunity(3), Set::DelCommunity(1)]);
+    assert_eq!(map.apply(entry.clone()).1.unwrap().route.communities, btreeset! {2, 3});
+
+    // Large community
+    let lc1 = LargeCommunity::new(65000, 1, 1);
+    let lc2 = LargeCommunity::new(65000, 2, 2);
+    let map = RouteMap::new(10, Allow, vec![], vec![Set::LargeCommunity(Some(lc1))]);
+    let entry = map.apply(default_entry.clone()).1.unwrap();
+    assert_eq!(entry.route.large_communities, btreeset! {lc1});
+    let map = RouteMap::new(
+        10,
+        Allow,
+        vec![],
+        vec![Set::AddLargeCommunity(lc1), Set::AddLargeCommunity(lc2), Set::DelLargeCommunity(lc1)],
+    );
+    let entry = map.apply(default_entry.clone()).1.unwrap();
+    assert_eq!(entry.route.large_communities, btreeset! {lc2});
+    let map = RouteMap::new(10, Allow, vec![], vec![Set::LargeCommunity(None)]);
+    let mut entry = default_entry.clone();
+    entry.route.large_communities = btreeset! {lc1, lc2};
+    assert_eq!(map.apply(entry).1.unwrap().route.large_communities, btreeset! {});
+
+    // AS path prepend
+    let map = RouteMap::new(10, Allow, vec![], vec![Set::AsPathPrepend(AsId(10), 2)]);
+    assert_eq!(
+        map.apply(default_entry.clone()).1.unwrap().route.as_path,
+        vec![AsId(10), AsId(10), AsId(0)]
+    );
+
+    // Origin
+    let map = RouteMap::new(10, Allow, vec![], vec![Set::Origin(Origin::Incomplete)]);
+    assert_eq!(map.apply(default_entry.clone()).1.unwrap().route.origin, Origin::Incomplete);
+
     // set everything together
     let map = RouteMap::new(
         10,
@@ -293,20 +394,20 @@ fn route_map_builder() {
         RouteMap::new(
             100,
             Allow,
-            vec![Match::Prefix(Clause::Equal(Prefix(0)))],
+            vec![Match::Prefix(PClause::Equal(Prefix::from(0)))],
             vec![Set::LocalPref(Some(10))]
         ),
         RouteMapBuilder::new()
             .order(100)
             .allow()
-            .match_prefix(Prefix(0))
+            .match_prefix(Prefix::from(0))
             .set_local_pref(10)
             .build()
     );
 
     assert_eq!(
-        RouteMap::new(10, Deny, vec![Match::Prefix(Clause::Range(Prefix(0), Prefix(9)))], vec![]),
-        RouteMapBuilder::new().order(10).deny().match_prefix_range(Prefix(0), Prefix(9)).build()
+        RouteMap::new(10, Deny, vec![Match::Prefix(PClause::Range(p("10.0.0.0/8")))], vec![]),
+        RouteMapBuilder::new().order(10).deny().match_prefix_range(p("10.0.0.0/8")).build()
     );
 
     assert_eq!(
@@ -388,4 +489,49 @@ fn route_map_builder() {
         RouteMap::new(10, Allow, vec![], vec![Set::Community(None)]),
         RouteMapBuilder::new().order(10).allow().reset_community().build()
     );
+
+    assert_eq!(
+        RouteMap::new(10, Allow, vec![], vec![Set::AddCommunity(10), Set::DelCommunity(20)]),
+        RouteMapBuilder::new().order(10).allow().add_community(10).del_community(20).build()
+    );
+
+    let lc = LargeCommunity::new(65000, 1, 2);
+    assert_eq!(
+        RouteMap::new(
+            10,
+            Allow,
+            vec![Match::LargeCommunity(Some(lc)), Match::Origin(Origin::Egp)],
+            vec![
+                Set::LargeCommunity(Some(lc)),
+                Set::LargeCommunity(None),
+                Set::AddLargeCommunity(lc),
+                Set::DelLargeCommunity(lc)
+            ]
+        ),
+        RouteMapBuilder::new()
+            .order(10)
+            .allow()
+            .match_large_community(lc)
+            .match_origin(Origin::Egp)
+            .set_large_community(lc)
+            .reset_large_community()
+            .add_large_community(lc)
+            .del_large_community(lc)
+            .build()
+    );
+
+    assert_eq!(
+        RouteMap::new(
+            10,
+            Allow,
+            vec![],
+            vec![Set::AsPathPrepend(AsId(65000), 3), Set::Origin(Origin::Incomplete)]
+        ),
+        RouteMapBuilder::new()
+            .order(10)
+            .allow()
+            .prepend_as_path(AsId(65000), 3)
+            .set_origin(Origin::Incomplete)
+            .build()
+    );
 }
@@ -16,13 +16,14 @@
 // 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
 
 use crate::netsim::bgp::BgpSessionType::{EBgp, IBgpClient, IBgpPeer};
-use crate::netsim::bgp::{BgpEvent, BgpRoute};
+use crate::netsim::bgp::{BgpEvent, BgpRoute, Origin};
 use crate::netsim::event::{Event, EventQueue};
 use crate::netsim::external_router::*;
 use crate::netsim::router::*;
-use crate::netsim::types::IgpNetwork;
+use crate::netsim::types::{Destination, IgpNetwork};
 use crate::netsim::{AsId, DeviceError, Prefix};
 use maplit::{hashmap, hashset};
+use std::collections::BTreeSet;
 
 #[test]
 fn test_bgp_single() {
@@ -36,15 +37,15 @@ fn test_bgp_single() {
     r.establish_bgp_session(5.into(), IBgpClient, &mut queue, 0, false).unwrap();
     r.establish_bgp_session(6.into(), IBgpClient, &mut queue, 0, false).unwrap();
     r.igp_forwarding_table = hashmap! {
-        100.into() => Some((100.into(), 0.0)),
-        1.into()   => Some((1.into(), 1.0)),
-        2.into()   => Some((2.into(), 1.0)),
-        3.into()   => Some((2.into(), 4.0)),
-        4.into()   => Some((4.into(), 2.0)),
-        5.into()   => Some((4.into(), 6.0)),
-        6.into()   => Some((1.into(), 13.0)),
-        10.into()  => Some((1.into(), 6.0)),
-        11.into()  => Some((1.into(), 15.0)),
+        100.into() => Some((vec![100.into()], 0.0)),
+        1.into()   => Some((vec![1.into()], 1.0)),
+        2.into()   => Some((vec![2.into()], 1.0)),
+        3.into()   => Some((vec![2.into()], 4.0)),
+        4.into()   => Some((vec![4.into()], 2.0)),
+        5.into()   => Some((vec![4.into()], 6.0)),
+        6.into()   => Some((vec![1.into()], 13.0)),
+        10.into()  => Some((vec![1.into()], 6.0)),
+        11.into()  => Some((vec![1.into()], 15.0)),
     };
 
     let mut queue: EventQueue = EventQueue::new();
@@ -58,12 +59,17 @@ fn test_bgp_single() {
             100.into(),
             0.into(),
             BgpEvent::Update(BgpRoute {
-                prefix: Prefix(200),
+                prefix: Prefix::from(200),
                 as_path: vec![AsId(1), AsId(2), AsId(3), AsId(4), AsId(5)],
+                as_set: BTreeSet::new(),
                 next_hop: 100.into(),
                 local_pref: None,
                 med: None,
-                community: None,
+                origin: Origin::Igp,
+                communities: BTreeSet::new(),
+                large_communities: BTreeSet::new(),
+                originator_id: None,
+                cluster_list: Vec::new(),
             }),
         ),
         &mut queue,
@@ -72,7 +78,7 @@ fn test_bgp_single() {
     .unwrap();
 
     // check that the router now has a route selected for 100 with the correct data
-    let entry = r.get_selected_bgp_route(Prefix(200)).unwrap();
+    let entry = r.get_selected_bgp_route(Prefix::from(200)).unwrap();
     assert_eq!(entry.from_type, EBgp);
     assert_eq!(entry.route.next_hop, 100.into());
     assert_eq!(entry.route.local_pref, Some(100));
@@ -100,12 +106,17 @@ fn test_bgp_single() {
             1.into(),
             0.into(),
             BgpEvent::Update(BgpRoute {
-                prefix: Prefix(201),
+                prefix: Prefix::from(201),
                 as_path: vec![AsId(1), AsId(2), AsId(3)],
+                as_set: BTreeSet::new(),
                 next_hop: 11.into(),
                 local_pref: Some(50),
                 med: None,
-                community: None,
+                origin: Origin::Igp,
+                communities: BTreeSet::new(),
+                large_communities: BTreeSet::new(),
+                originator_id: None,
+                cluster_list: Vec::new(),
             }),
         ),
         &mut queue,
@@ -114,7 +125,7 @@ fn test_bgp_single() {
     .unwrap();
 
     // check that the router now has a route selected for 100 with the correct data
-    let entry = r.get_selected_bgp_route(Prefix(201)).unwrap();
+    let entry = r.get_selected_bgp_route(Prefix::from(201)).unwrap();
     assert_eq!(entry.from_type, IBgpPeer);
     assert_eq!(entry.route.next_hop, 11.into());
     assert_eq!(entry.route.local_pref, Some(50));
@@ -145,12 +156,17 @@ fn test_bgp_single() {
             2.into(),
             0.into(),
             BgpEvent::Update(BgpRoute {
-                prefix: Prefix(200),
+                prefix: Prefix::from(200),
                 as_path: vec![AsId(1), AsId(2), AsId(3), AsId(4), AsId(5)],
+                as_set: BTreeSet::new(),
                 next_hop: 10.into(),
                 local_pref: None,
                 med: None,
-                community: None,
+                origin: Origin::Igp,
+                communities: BTreeSet::new(),
+                large_communities: BTreeSet::new(),
+                originator_id: None,
+                cluster_list: Vec::new(),
             }),
         ),
         &mut queue,
@@ -159,7 +175,7 @@ fn test_bgp_single() {
     .unwrap();
 
     // check that
-    let entry = r.get_selected_bgp_route(Prefix(200)).unwrap();
+    let entry = r.get_selected_bgp_route(Prefix::from(200)).unwrap();
     assert_eq!(entry.from_type, EBgp);
     assert_eq!(entry.route.next_hop, 100.into());
     assert_eq!(queue.len(), 0);
@@ -175,7 +191,7 @@ fn test_bgp_single() {
             5.into(),
             0.into(),
             BgpEvent::Update(BgpRoute {
-                prefix: Prefix(200),
+                prefix: Prefix::from(200),
                 as_path: vec![
                     AsId(1),
                     AsId(2),
@@ -188,10 +204,15 @@ fn test_bgp_single() {
                     AsId(9),
                     AsId(10),
                 ],
+                as_set: BTreeSet::new(),
                 next_hop: 5.into(),
                 local_pref: Some(150),
                 med: None,
-                community: None,
+                origin: Origin::Igp,
+                communities: BTreeSet::new(),
+                large_communities: BTreeSet::new(),
+                originator_id: None,
+                cluster_list: Vec::new(),
             }),
         ),
         &mut queue,
@@ -200,7 +221,7 @@ fn test_bgp_single() {
     .unwrap();
 
     // check that the router now has a route selected for 100 with the correct data
-    let entry = r.get_selected_bgp_route(Prefix(200)).unwrap().clone();
+    let entry = r.get_selected_bgp_route(Prefix::from(200)).unwrap().clone();
     assert_eq!(entry.from_type, IBgpClient);
     assert_eq!(entry.route.next_hop, 5.into());
     assert_eq!(entry.route.local_pref, Some(150));
@@ -221,7 +242,7 @@ fn test_bgp_single() {
             Event::Bgp(from, to, BgpEvent::Withdraw(prefix)) => {
                 assert_eq!(from, 0.into());
                 assert_eq!(to, 5.into());
-                assert_eq!(prefix, Prefix(200));
+                assert_eq!(prefix, Prefix::from(200));
             }
             e => panic!("Invalid event: {:?}", e),
         }
@@ -231,11 +252,11 @@ fn test_bgp_single() {
     // retract bad route //
     ///////////////////////
 
-    r.handle_event(Event::Bgp(2.into(), 0.into(), BgpEvent::Withdraw(Prefix(200))), &mut queue, 0)
+    r.handle_event(Event::Bgp(2.into(), 0.into(), BgpEvent::Withdraw(Prefix::from(200))), &mut queue, 0)
         .unwrap();
 
     // check that the router now has a route selected for 100 with the correct data
-    let new_entry = r.get_selected_bgp_route(Prefix(200)).unwrap();
+    let new_entry = r.get_selected_bgp_route(Prefix::from(200)).unwrap();
     assert_eq!(new_entry, entry);
     assert_eq!(queue.len(), 0);
 
@@ -243,12 +264,12 @@ fn test_bgp_single() {
     // retract good route //
     ////////////////////////
 
-    r.handle_event(Event::Bgp(5.into(), 0.into(), BgpEvent::Withdraw(Prefix(200))), &mut queue, 0)
+    r.handle_event(Event::Bgp(5.into(), 0.into(), BgpEvent::Withdraw(Prefix::from(200))), &mut queue, 0)
         .unwrap();
 
     // check that the router now has a route selected for 100 with the correct data
     //eprintln!("{:#?}", r);
-    let new_entry = r.get_selected_bgp_route(Prefix(200)).unwrap();
+    let new_entry = r.get_selected_bgp_route(Prefix::from(200)).unwrap();
     assert_eq!(new_entry, original_entry);
     assert_eq!(queue.len(), 7);
     while let Some((job, _)) = queue.pop_front() {
@@ -262,7 +283,7 @@ fn test_bgp_single() {
             Event::Bgp(from, to, BgpEvent::Withdraw(prefix)) => {
                 assert_eq!(from, 0.into());
                 assert_eq!(to, 100.into());
-                assert_eq!(prefix, Prefix(200));
+                assert_eq!(prefix, Prefix::from(200));
             }
             e => panic!("Invalid event: {:?}", e),
         }
@@ -273,18 +294,18 @@ fn test_bgp_single() {
     ////////////////////////
 
     r.handle_event(
-        Event::Bgp(100.into(), 0.into(), BgpEvent::Withdraw(Prefix(200))),
+        Event::Bgp(100.into(), 0.into(), BgpEvent::Withdraw(Prefix::from(200))),
         &mut queue,
         0,
     )
     .unwrap();
 
     // check that the router now has a route selected for 100 with the correct data
-    assert!(r.get_selected_bgp_route(Prefix(200)).is_none());
+    assert!(r.get_selected_bgp_route(Prefix::from(200)).is_none());
     assert_eq!(queue.len(), 6);
     while let Some((job, _)) = queue.pop_front() {
         match job {
-            Event::Bgp(from, to, BgpEvent::Withdraw(Prefix(200))) => {
+            Event::Bgp(from, to, BgpEvent::Withdraw(p)) if p == Prefix::from(200) => {
                 assert_eq!(from, 0.into());
                 assert!(hashset![1, 2, 3, 4, 5, 6].contains(&(to.index() as usize)));
             }
@@ -293,6 +314,109 @@ fn test_bgp_single() {
     }
 }
 
+#[test]
+fn test_bgp_route_reflection() {
+    let mut r = Router::new("test".to_string(), 0.into(), AsId(65001));
+    let mut queue: EventQueue = EventQueue::new();
+    r.establish_bgp_session(100.into(), EBgp, &mut queue, 0, false).unwrap();
+    r.establish_bgp_session(1.into(), IBgpPeer, &mut queue, 0, false).unwrap();
+    r.establish_bgp_session(4.into(), IBgpClient, &mut queue, 0, false).unwrap();
+    r.establish_bgp_session(5.into(), IBgpClient, &mut queue, 0, false).unwrap();
+    r.igp_forwarding_table = hashmap! {
+        100.into() => Some((vec![100.into()], 0.0)),
+        1.into()   => Some((vec![1.into()], 1.0)),
+        4.into()   => Some((vec![4.into()], 1.0)),
+        5.into()   => Some((vec![5.into()], 1.0)),
+        9.into()   => Some((vec![1.into()], 2.0)),
+    };
+
+    let mut queue: EventQueue = EventQueue::new();
+    let route = |next_hop: u32, originator_id: Option<u32>, cluster_list: Vec<u32>| BgpRoute {
+        prefix: Prefix::from(0),
+        as_path: vec![AsId(1)],
+        as_set: BTreeSet::new(),
+        next_hop: next_hop.into(),
+        local_pref: None,
+        med: None,
+        origin: Origin::Igp,
+        communities: BTreeSet::new(),
+        large_communities: BTreeSet::new(),
+        originator_id: originator_id.map(|x| x.into()),
+        cluster_list: cluster_list.into_iter().map(|x| x.into()).collect(),
+    };
+
+    // a route from a client is reflected with the originator and the cluster list set
+    r.handle_event(
+        Event::Bgp(4.into(), 0.into(), BgpEvent::Update(route(4, None, vec![]))),
+        &mut queue,
+        0,
+    )
+    .unwrap();
+    assert_eq!(queue.len(), 3);
+    while let Some((job, _)) = queue.pop_front() {
+        match job {
+            Event::Bgp(_, to, BgpEvent::Update(r)) if to == 100.into() => {
+                assert_eq!(r.originator_id, None);
+                assert!(r.cluster_list.is_empty());
+            }
+            Event::Bgp(_, to, BgpEvent::Update(r)) => {
+                assert!(to == 1.into() || to == 5.into());
+                assert_eq!(r.originator_id, Some(4.into()));
+                assert_eq!(r.cluster_list, vec![0.into()]);
+            }
+            e => panic!("Invalid event: {:?}", e),
+        }
+    }
+    r.handle_event(
+        Event::Bgp(4.into(), 0.into(), BgpEvent::Withdraw(Prefix::from(0))),
+        &mut queue,
+        0,
+    )
+    .unwrap();
+    queue.clear();
+
+    // routes originated by the router itself are ignored
+    r.handle_event(
+        Event::Bgp(1.into(), 0.into(), BgpEvent::Update(route(9, Some(0), vec![]))),
+        &mut queue,
+        0,
+    )
+    .unwrap();
+    assert!(r.get_selected_bgp_route(Prefix::from(0)).is_none());
+    assert_eq!(queue.len(), 0);
+
+    // routes that already passed the own cluster are ignored
+    r.handle_event(
+        Event::Bgp(1.into(), 0.into(), BgpEvent::Update(route(9, Some(9), vec![8, 0]))),
+        &mut queue,
+        0,
+    )
+    .unwrap();
+    assert!(r.get_selected_bgp_route(Prefix::from(0)).is_none());
+    assert_eq!(queue.len(), 0);
+
+    // after changing the cluster ID, the route is accepted and reflected to the clients
+    r.set_bgp_cluster_id(Some(7.into()), &mut queue, 0, false).unwrap();
+    assert_eq!(r.get_bgp_cluster_id(), Some(7.into()));
+    let entry = r.get_selected_bgp_route(Prefix::from(0)).unwrap();
+    assert_eq!(entry.from_id, 1.into());
+    assert_eq!(queue.len(), 3);
+    while let Some((job, _)) = queue.pop_front() {
+        match job {
+            Event::Bgp(_, to, BgpEvent::Update(r)) if to == 100.into() => {
+                assert_eq!(r.originator_id, None);
+                assert!(r.cluster_list.is_empty());
+            }
+            Event::Bgp(_, to, BgpEvent::Update(r)) => {
+                assert!(to == 4.into() || to == 5.into());
+                assert_eq!(r.originator_id, Some(9.into()));
+                assert_eq!(r.cluster_list, vec![7.into(), 8.into(), 0.into()]);
+            }
+            e => panic!("Invalid event: {:?}", e),
+        }
+    }
+}
+
 #[test]
 fn test_fw_table_simple() {
     let mut net: IgpNetwork = IgpNetwork::new();
@@ -325,11 +449,11 @@ fn test_fw_table_simple() {
     a.write_igp_forwarding_table(&net, &mut EventQueue::new(), 0, false).unwrap();
 
     let expected_forwarding_table = hashmap! {
-        a.router_id() => Some((a.router_id(), 0.0)),
-        b.router_id() => Some((b.router_id(), 1.0)),
-        c.router_id() => Some((b.router_id(), 2.0)),
-        d.router_id() => Some((b.router_id(), 3.0)),
-        e.router_id() => Some((b.router_id(), 4.0)),
+        a.router_id() => Some((vec![a.router_id()], 0.0)),
+        b.router_id() => Some((vec![b.router_id()], 1.0)),
+        c.router_id() => Some((vec![b.router_id()], 2.0)),
+        d.router_id() => Some((vec![b.router_id()], 3.0)),
+        e.router_id() => Some((vec![b.router_id()], 4.0)),
     };
 
     let exp = &expected_forwarding_table;
@@ -342,11 +466,11 @@ fn test_fw_table_simple() {
     b.write_igp_forwarding_table(&net, &mut EventQueue::new(), 0, false).unwrap();
 
     let expected_forwarding_table = hashmap! {
-        a.router_id() => Some((a.router_id(), 1.0)),
-        b.router_id() => Some((b.router_id(), 0.0)),
-        c.router_id() => Some((c.router_id(), 1.0)),
-        d.router_id() => Some((c.router_id(), 2.0)),
-        e.router_id() => Some((c.router_id(), 3.0)),
+        a.router_id() => Some((vec![a.router_id()], 1.0)),
+        b.router_id() => Some((vec![b.router_id()], 0.0)),
+        c.router_id() => Some((vec![c.router_id()], 1.0)),
+        d.router_id() => Some((vec![c.router_id()], 2.0)),
+        e.router_id() => Some((vec![c.router_id()], 3.0)),
     };
 
     let exp = &expected_forwarding_table;
@@ -359,11 +483,11 @@ fn test_fw_table_simple() {
     c.write_igp_forwarding_table(&net, &mut EventQueue::new(), 0, false).unwrap();
 
     let expected_forwarding_table = hashmap! {
-        a.router_id() => Some((b.router_id(), 2.0)),
-        b.router_id() => Some((b.router_id(), 1.0)),
-        c.router_id() => Some((c.router_id(), 0.0)),
-        d.router_id() => Some((d.router_id(), 1.0)),
-        e.router_id() => Some((d.router_id(), 2.0)),
+        a.router_id() => Some((vec![b.router_id()], 2.0)),
+        b.router_id() => Some((vec![b.router_id()], 1.0)),
+        c.router_id() => Some((vec![c.router_id()], 0.0)),
+        d.router_id() => Some((vec![d.router_id()], 1.0)),
+        e.router_id() => Some((vec![d.router_id()], 2.0)),
     };
 
     let exp = &expected_forwarding_table;
@@ -374,6 +498,49 @@ fn test_fw_table_simple() {
     }
 }
 
+#[test]
+fn test_igp_fw_table_ecmp() {
+    let mut net: IgpNetwork = IgpNetwork::new();
+    let mut a = Router::new("A".to_string(), net.add_node(()), AsId(65001));
+    let b = Router::new("B".to_string(), net.add_node(()), AsId(65001));
+    let c = Router::new("C".to_string(), net.add_node(()), AsId(65001));
+    let d = Router::new("D".to_string(), net.add_node(()), AsId(65001));
+    let e = Router::new("E".to_string(), net.add_node(()), AsId(65001));
+
+    for (x, y, w) in [(&a, &b, 1.0), (&a, &c, 1.0), (&b, &d, 1.0), (&c, &d, 1.0), (&d, &e, 1.0)] {
+        net.add_edge(x.router_id(), y.router_id(), w);
+        net.add_edge(y.router_id(), x.router_id(), w);
+    }
+
+    /*
+     * all weights = 1
+     *   b
+     *  / \
+     * a   d --- e
+     *  \ /
+     *   c
+     */
+
+    a.write_igp_forwarding_table(&net, &mut EventQueue::new(), 0, false).unwrap();
+
+    let acq = &a.igp_forwarding_table;
+    assert_eq!(acq.get(&a.router_id()), Some(&Some((vec![a.router_id()], 0.0))));
+    assert_eq!(acq.get(&b.router_id()), Some(&Some((vec![b.router_id()], 1.0))));
+    assert_eq!(acq.get(&c.router_id()), Some(&Some((vec![c.router_id()], 1.0))));
+    for target in [&d, &e] {
+        let (next_hops, _) = acq.get(&target.router_id()).cloned().flatten().unwrap();
+        assert_eq!(
+            next_hops.iter().copied().collect::<BTreeSet<_>>(),
+            [b.router_id(), c.router_id()].iter().copied().collect::<BTreeSet<_>>()
+        );
+        // the first next hop is used if only a single path is considered
+        assert_eq!(a.get_next_hop(Destination::IGP(target.router_id())), Some(next_hops[0]));
+        assert_eq!(a.get_next_hops(Destination::IGP(target.router_id())), next_hops);
+    }
+    assert_eq!(acq.get(&d.router_id()).cloned().flatten().unwrap().1, 2.0);
+    assert_eq!(acq.get(&e.router_id()).cloned().flatten().unwrap().1, 3.0);
+}
+
 #[test]
 fn test_igp_fw_table_complex() {
     let mut net: IgpNetwork = IgpNetwork::new();
@@ -422,14 +589,14 @@ fn test_igp_fw_table_complex() {
     a.write_igp_forwarding_table(&net, &mut EventQueue::new(), 0, false).unwrap();
 
     let expected_forwarding_table = hashmap! {
-        a.router_id() => Some((a.router_id(), 0.0)),
-        b.router_id() => Some((b.router_id(), 3.0)),
-        c.router_id() => Some((e.router_id(), 3.0)),
-        d.router_id() => Some((e.router_id(), 6.0)),
-        e.router_id() => Some((e.router_id(), 1.0)),
-        f.router_id() => Some((e.router_id(), 2.0)),
-        g.router_id() => Some((e.router_id(), 4.0)),
-        h.router_id() => Some((e.router_id(), 5.0)),
+        a.router_id() => Some((vec![a.router_id()], 0.0)),
+        b.router_id() => Some((vec![b.router_id()], 3.0)),
+        c.router_id() => Some((vec![e.router_id()], 3.0)),
+        d.router_id() => Some((vec![e.router_id()], 6.0)),
+        e.router_id() => Some((vec![e.router_id()], 1.0)),
+        f.router_id() => Some((vec![e.router_id()], 2.0)),
+        g.router_id() => Some((vec![e.router_id()], 4.0)),
+        h.router_id() => Some((vec![e.router_id()], 5.0)),
     };
 
     let exp = &expected_forwarding_table;
@@ -442,14 +609,14 @@ fn test_igp_fw_table_complex() {
     c.write_igp_forwarding_table(&net, &mut EventQueue::new(), 0, false).unwrap();
 
     let expected_forwarding_table = hashmap! {
-        a.router_id() => Some((f.router_id(), 3.0)),
-        b.router_id() => Some((f.router_id(), 3.0)),
-        c.router_id() => Some((c.router_id(), 0.0)),
-        d.router_id() => Some((g.router_id(), 3.0)),
-        e.router_id() => Some((f.router_id(), 2.0)),
-        f.router_id() => Some((f.router_id(), 1.0)),
-        g.router_id() => Some((g.router_id(), 1.0)),
-        h.router_id() => Some((g.router_id(), 2.0)),
+        a.router_id() => Some((vec![f.router_id()], 3.0)),
+        b.router_id() => Some((vec![f.router_id()], 3.0)),
+        c.router_id() => Some((vec![c.router_id()], 0.0)),
+        d.router_id() => Some((vec![g.router_id()], 3.0)),
+        e.router_id() => Some((vec![f.router_id()], 2.0)),
+        f.router_id() => Some((vec![f.router_id()], 1.0)),
+        g.router_id() => Some((vec![g.router_id()], 1.0)),
+        h.router_id() => Some((vec![g.router_id()], 2.0)),
     };
 
     let exp = &expected_forwarding_table;
@@ -477,7 +644,20 @@ fn external_router_advertise_to_neighbors() {
     );
 
     // advertise route
-    r.advertise_prefix(Prefix(0), vec![AsId(0)], None, None, &mut queue, 0);
+    let route = BgpRoute {
+        prefix: Prefix::from(0),
+        as_path: vec![AsId(0)],
+        as_set: BTreeSet::new(),
+        next_hop: 0.into(),
+        local_pref: None,
+        med: None,
+        origin: Origin::Igp,
+        communities: BTreeSet::new(),
+        large_communities: BTreeSet::new(),
+        originator_id: None,
+        cluster_list: Vec::new(),
+    };
+    r.advertise_route(route, &mut queue, 0);
 
     // check that one event was created
     assert_eq!(queue.len(), 1);
@@ -487,24 +667,29 @@ fn external_router_advertise_to_neighbors() {
             0.into(),
             1.into(),
             BgpEvent::Update(BgpRoute {
-                prefix: Prefix(0),
+                prefix: Prefix::from(0),
                 as_path: vec![AsId(0)],
+                as_set: BTreeSet::new(),
                 next_hop: 0.into(),
                 local_pref: None,
                 med: None,
-                community: None,
+                origin: Origin::Igp,
+                communities: BTreeSet::new(),
+                large_communities: BTreeSet::new(),
+                originator_id: None,
+                cluster_list: Vec::new(),
             }),
         )
     );
 
     // emove the route
-    r.widthdraw_prefix(Prefix(0), &mut queue, 0);
+    r.widthdraw_prefix(Prefix::from(0), &mut queue, 0);
 
     // check that one event was created
     assert_eq!(queue.len(), 1);
     assert_eq!(
         queue.pop_front().unwrap().0,
-        Event::Bgp(0.into(), 1.into(), BgpEvent::Withdraw(Prefix(0)))
+        Event::Bgp(0.into(), 1.into(), BgpEvent::Withdraw(Prefix::from(0)))
     )
 }
 
@@ -515,7 +700,20 @@ fn external_router_new_neighbor() {
     let mut queue = EventQueue::new();
 
     // advertise route
-    r.advertise_prefix(Prefix(0), vec![AsId(0)], None, None, &mut queue, 0);
+    let route = BgpRoute {
+        prefix: Prefix::from(0),
+        as_path: vec![AsId(0)],
+        as_set: BTreeSet::new(),
+        next_hop: 0.into(),
+        local_pref: None,
+        med: None,
+        origin: Origin::Igp,
+        communities: BTreeSet::new(),
+        large_communities: BTreeSet::new(),
+        originator_id: None,
+        cluster_list: Vec::new(),
+    };
+    r.advertise_route(route, &mut queue, 0);
 
     // check that no event was created
     assert_eq!(queue.len(), 0);
@@ -531,12 +729,17 @@ fn external_router_new_neighbor() {
             0.into(),
             1.into(),
             BgpEvent::Update(BgpRoute {
-                prefix: Prefix(0),
+                prefix: Prefix::from(0),
                 as_path: vec![AsId(0)],
+                as_set: BTreeSet::new(),
                 next_hop: 0.into(),
                 local_pref: None,
                 med: None,
-                community: None,
+                origin: Origin::Igp,
+                communities: BTreeSet::new(),
+                large_communities: BTreeSet::new(),
+                originator_id: None,
+                cluster_list: Vec::new(),
             }),
         )
     );
@@ -546,6 +749,6 @@ fn external_router_new_neighbor() {
     assert_eq!(r.close_ebgp_session(1.into()), Err(DeviceError::NoBgpSession(1.into())));
 
     // then, withdraw the session
-    r.widthdraw_prefix(Prefix(0), &mut queue, 0);
+    r.widthdraw_prefix(Prefix::from(0), &mut queue, 0);
     assert_eq!(queue.len(), 0);
 }
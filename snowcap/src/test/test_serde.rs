@@ -0,0 +1,137 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+use crate as snowcap;
+use crate::example_networks::{CarouselGadget, ExampleNetwork};
+use crate::hard_policies::{
+    Condition, HardPolicy, LTLModal, LTLOperator, OrderingConstraint, PathCondition,
+};
+use crate::netsim::bgp::BgpDecisionConfig;
+use crate::netsim::config::{Config, ConfigExpr, ConfigModifier, ConfigPatch};
+use crate::netsim::{Network, Prefix};
+use snowcap_ltl_parser::ltl;
+
+#[test]
+fn test_serde_network() {
+    let mut net = CarouselGadget::net(0);
+    let decision =
+        BgpDecisionConfig { always_compare_med: true, med_missing_as_worst: true, multipath: true };
+    net.apply_modifier(&ConfigModifier::Insert(ConfigExpr::BgpDecision {
+        router: net.get_routers()[0],
+        config: decision,
+    }))
+    .unwrap();
+    net.apply_modifier(&ConfigModifier::Insert(ConfigExpr::BgpClusterId {
+        router: net.get_routers()[1],
+        cluster_id: net.get_routers()[0],
+    }))
+    .unwrap();
+    let (a, b) = *net.links_symmetric().next().unwrap();
+    net.fail_link(a, b).unwrap();
+    let json = serde_json::to_string(&net).unwrap();
+    let restored: Network = serde_json::from_str(&json).unwrap();
+    assert_eq!(net.current_config(), restored.current_config());
+    assert!(restored.is_link_down(a, b));
+    assert_eq!(net.get_known_prefixes(), restored.get_known_prefixes());
+    assert_eq!(net.num_devices(), restored.num_devices());
+    for r in net.get_routers() {
+        assert_eq!(net.get_router_name(r).unwrap(), restored.get_router_name(r).unwrap());
+        assert_eq!(
+            net.get_device(r).unwrap_internal().get_bgp_decision_config(),
+            restored.get_device(r).unwrap_internal().get_bgp_decision_config()
+        );
+        assert_eq!(
+            net.get_device(r).unwrap_internal().get_bgp_cluster_id(),
+            restored.get_device(r).unwrap_internal().get_bgp_cluster_id()
+        );
+        for p in net.get_known_prefixes() {
+            assert_eq!(net.get_route(r, *p), restored.get_route(r, *p));
+        }
+    }
+    for r in net.get_external_routers() {
+        assert_eq!(net.get_router_name(r).unwrap(), restored.get_router_name(r).unwrap());
+        assert!(net
+            .get_device(r)
+            .unwrap_external()
+            .advertises_same_routes(restored.get_device(r).unwrap_external()));
+    }
+}
+
+#[test]
+fn test_serde_config() {
+    let net = CarouselGadget::net(0);
+    let config = CarouselGadget::final_config(&net, 0);
+    let json = serde_json::to_string(&config).unwrap();
+    let restored: Config = serde_json::from_str(&json).unwrap();
+    assert_eq!(config, restored);
+    assert_eq!(config.len(), restored.len());
+
+    let patch = net.current_config().get_diff(&config);
+    let json = serde_json::to_string(&patch).unwrap();
+    let restored: ConfigPatch = serde_json::from_str(&json).unwrap();
+    assert_eq!(patch, restored);
+}
+
+#[test]
+fn test_serde_config_duplicate_expr() {
+    let net = CarouselGadget::net(0);
+    let config = CarouselGadget::final_config(&net, 0);
+    let mut exprs: Vec<serde_json::Value> =
+        serde_json::from_str(&serde_json::to_string(&config).unwrap()).unwrap();
+    exprs.push(exprs[0].clone());
+    assert!(serde_json::from_value::<Config>(serde_json::Value::Array(exprs)).is_err());
+}
+
+#[test]
+fn test_serde_hard_policy() {
+    let net = CarouselGadget::net(0);
+    let r = net.get_routers()[0];
+    let prop_vars = vec![
+        Condition::Reachable(r, Prefix::from(0), None),
+        Condition::Reachable(
+            r,
+            Prefix::from(1),
+            Some(PathCondition::Not(Box::new(PathCondition::Node(r)))),
+        ),
+        Condition::NotReachable(r, Prefix::from(2)),
+    ];
+    let expr = ltl!(Until(And(0, Not(2)), Globally(Or(1, true))));
+    let policy = HardPolicy::new(prop_vars, LTLModal::Now(Box::new(expr)));
+
+    let json = serde_json::to_string(&policy).unwrap();
+    let restored: HardPolicy = serde_json::from_str(&json).unwrap();
+    assert_eq!(policy.prop_vars, restored.prop_vars);
+    assert_eq!(policy.expr.repr(), restored.expr.repr());
+}
+
+#[test]
+fn test_serde_hard_policy_constraints() {
+    let net = CarouselGadget::net(0);
+    let cf = CarouselGadget::final_config(&net, 0);
+    let modifiers = net.current_config().get_diff(&cf).modifiers;
+    let constraints = vec![
+        OrderingConstraint::Before(modifiers[0].clone(), modifiers[1].clone()),
+        OrderingConstraint::AtEnd(vec![modifiers[1].clone()]),
+        OrderingConstraint::Atomic(modifiers),
+    ];
+    let policy =
+        HardPolicy::reachability(net.get_routers().iter(), net.get_known_prefixes().iter())
+            .with_constraints(constraints);
+
+    let json = serde_json::to_string(&policy).unwrap();
+    let restored: HardPolicy = serde_json::from_str(&json).unwrap();
+    assert_eq!(policy.constraints(), restored.constraints());
+}
@@ -0,0 +1,179 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! Test snapshots of the network.
+
+use crate::netsim::config::{Config, ConfigExpr, ConfigExpr::*, ConfigModifier::*};
+use crate::netsim::route_map::{RouteMapBuilder, RouteMapDirection::*};
+use crate::netsim::types::Destination;
+use crate::netsim::{AsId, BgpSessionType::*, Network, NetworkError, Prefix, RouterId};
+
+/// # Test network
+///
+/// ```text
+/// E1 ---- R1 ---- R2 ---- E2
+/// ```
+///
+/// Both external routers advertise the same prefix, but E2 with a longer AS path.
+fn get_test_net() -> (Network, RouterId, RouterId, RouterId, RouterId) {
+    let mut net = Network::new();
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let e1 = net.add_external_router("E1", AsId(65101));
+    let e2 = net.add_external_router("E2", AsId(65102));
+
+    let mut c = Config::new();
+    for (a, b) in [(e1, r1), (r1, r2), (r2, e2)] {
+        net.add_link(a, b);
+        c.add(IgpLinkWeight { source: a, target: b, weight: 1.0 }).unwrap();
+        c.add(IgpLinkWeight { source: b, target: a, weight: 1.0 }).unwrap();
+    }
+    c.add(BgpSession { source: r1, target: r2, session_type: IBgpPeer }).unwrap();
+    c.add(BgpSession { source: r1, target: e1, session_type: EBgp }).unwrap();
+    c.add(BgpSession { source: r2, target: e2, session_type: EBgp }).unwrap();
+    net.set_config(&c).unwrap();
+
+    let p = Prefix::from(0);
+    net.advertise_external_route(e1, p, vec![AsId(65101)], None, None).unwrap();
+    net.advertise_external_route(e2, p, vec![AsId(65102), AsId(1)], None, None).unwrap();
+
+    (net, r1, r2, e1, e2)
+}
+
+/// Route map on `router`, preferring all routes from `neighbor`.
+fn prefer(router: RouterId, neighbor: RouterId) -> ConfigExpr {
+    BgpRouteMap {
+        router,
+        direction: Incoming,
+        map: RouteMapBuilder::new()
+            .order(10)
+            .allow()
+            .match_neighbor(neighbor)
+            .set_local_pref(200)
+            .build(),
+    }
+}
+
+#[test]
+fn snapshot_restore() {
+    let (mut net, r1, r2, e1, e2) = get_test_net();
+    let p = Prefix::from(0);
+    let initial = net.clone();
+    assert_eq!(net.get_route(r2, p), Ok(vec![r2, r1, e1]));
+
+    let s0 = net.snapshot();
+    let prefer_e2 = prefer(r2, e2);
+    net.apply_modifier(&Insert(prefer_e2.clone())).unwrap();
+    assert_eq!(net.get_route(r1, p), Ok(vec![r1, r2, e2]));
+    let modified = net.clone();
+    let s1 = net.snapshot();
+
+    // jump back and forth between both snapshots
+    net.restore(s0).unwrap();
+    assert_eq!(net, initial);
+    assert_eq!(net.get_route(r1, p), Ok(vec![r1, e1]));
+    net.restore(s1).unwrap();
+    assert_eq!(net, modified);
+    assert_eq!(net.get_route(r1, p), Ok(vec![r1, r2, e2]));
+
+    // branch off from the first snapshot, without affecting the second one
+    net.restore(s0).unwrap();
+    net.retract_external_route(e1, p).unwrap();
+    assert_eq!(net.get_route(r1, p), Ok(vec![r1, r2, e2]));
+    net.restore(s1).unwrap();
+    assert_eq!(net, modified);
+
+    // actions performed before taking the snapshot can still be undone
+    net.undo_action().unwrap();
+    assert_eq!(net, initial);
+    assert_eq!(net.get_route(r1, p), Ok(vec![r1, e1]));
+
+    // the removed config can be applied again
+    net.apply_modifier(&Insert(prefer_e2)).unwrap();
+    assert_eq!(net, modified);
+
+    assert_eq!(net.restore(2), Err(NetworkError::SnapshotNotFound(2)));
+    net.clear_snapshots();
+    assert_eq!(net.restore(s0), Err(NetworkError::SnapshotNotFound(s0)));
+}
+
+#[test]
+fn clone_is_independent() {
+    let (mut net, r1, r2, e1, e2) = get_test_net();
+    let p = Prefix::from(0);
+
+    let copy = net.clone();
+    net.apply_modifier(&Insert(prefer(r2, e2))).unwrap();
+    assert_eq!(net.get_route(r1, p), Ok(vec![r1, r2, e2]));
+    assert_eq!(copy.get_route(r1, p), Ok(vec![r1, e1]));
+    assert!(net != copy);
+
+    // the original network can still undo its own actions
+    net.undo_action().unwrap();
+    assert_eq!(net, copy);
+}
+
+#[test]
+fn restore_updates_forwarding_state() {
+    let (mut net, r1, r2, e1, e2) = get_test_net();
+    let p = Prefix::from(0);
+    let mut fw_state = net.get_forwarding_state();
+    net.clear_forwarding_changes();
+
+    let s0 = net.snapshot();
+    net.apply_modifier(&Insert(prefer(r2, e2))).unwrap();
+    let changes = fw_state.update(&mut net);
+    assert!(!changes.is_all());
+    assert!(changes.contains(r1, &Destination::BGP(p)));
+    assert_eq!(fw_state, net.get_forwarding_state());
+
+    // restoring the snapshot only marks the routers that differ from the snapshot
+    net.restore(s0).unwrap();
+    let changes = fw_state.update(&mut net);
+    assert!(!changes.is_all());
+    assert!(changes.contains(r1, &Destination::BGP(p)));
+    assert!(!changes.contains(e1, &Destination::BGP(p)));
+    assert_eq!(fw_state, net.get_forwarding_state());
+    assert_eq!(net.get_route(r1, p), Ok(vec![r1, e1]));
+}
+
+#[test]
+fn snapshot_shares_bgp_tables() {
+    let (mut net, r1, r2, e1, _) = get_test_net();
+    let p0 = Prefix::from(0);
+    let p1 = Prefix::from(1);
+    net.advertise_external_route(e1, p1, vec![AsId(65101)], None, None).unwrap();
+
+    let copy = net.clone();
+    net.retract_external_route(e1, p1).unwrap();
+    assert_eq!(net.get_route(r2, p1), Err(NetworkError::ForwardingBlackHole(vec![r2])));
+    assert_eq!(copy.get_route(r2, p1), Ok(vec![r2, r1, e1]));
+
+    // both routers are copied, but only the BGP tables of the withdrawn prefix
+    for r in [r1, r2] {
+        let router = net.get_device(r).unwrap_internal();
+        let original = copy.get_device(r).unwrap_internal();
+        assert!(router.shares_bgp_tables(original, p0));
+        assert!(!router.shares_bgp_tables(original, p1));
+    }
+
+    // undoing the action restores the table, without affecting the entries of the other prefix
+    net.undo_action().unwrap();
+    assert_eq!(net, copy);
+    let router = net.get_device(r1).unwrap_internal();
+    assert!(router.shares_bgp_tables(copy.get_device(r1).unwrap_internal(), p0));
+}
@@ -86,7 +86,9 @@ where
     }
 }
 
-fn test_net_bad_policy<S>()
+/// Synthesize with policies that cannot be satisfied, and return the error of the policy which is
+/// satisfied in the initial state.
+fn test_net_bad_policy<S>() -> Error
 where
     S: Strategy,
 {
@@ -128,7 +130,9 @@ where
             "Solution was found!\n{:#?}",
             r.iter().map(|m| printer::config_modifier(&net, m).unwrap()).collect::<Vec<String>>()
         ),
-        Err(Error::NoSafeOrdering) | Err(Error::ProbablyNoSafeOrdering) => {}
+        Err(e @ Error::NoSafeOrdering)
+        | Err(e @ Error::ProbablyNoSafeOrdering)
+        | Err(e @ Error::Unsat(_)) => e,
         Err(e) => panic!("Unexpected error: {}", e),
     }
 }
@@ -378,5 +382,17 @@ fn impossible_constraint() {
     eprintln!("DepGroups");
     test_net_bad_policy::<DepGroupsStrategy>();
     eprintln!("StrategyTRTA");
-    test_net_bad_policy::<StrategyTRTA>();
+    match test_net_bad_policy::<StrategyTRTA>() {
+        Error::Unsat(explanation) => {
+            let net = SimpleNet::net(0);
+            let modifiers =
+                net.current_config().get_diff(&SimpleNet::final_config(&net, 0)).modifiers;
+            assert!(explanation.minimal);
+            assert!(!explanation.modifiers.is_empty());
+            assert!(explanation.modifiers.iter().all(|m| modifiers.contains(m)));
+            assert!(explanation.sequence.iter().all(|m| explanation.modifiers.contains(m)));
+            assert!(explanation.description.starts_with("Every ordering"));
+        }
+        e => panic!("Unexpected error: {}", e),
+    }
 }
@@ -0,0 +1,149 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! Test the staged strategy.
+
+use crate::dep_groups::staged::same_next_hops;
+use crate::example_networks::repetitions::*;
+use crate::example_networks::*;
+use crate::hard_policies::HardPolicy;
+use crate::modifier_ordering::NoOrdering;
+use crate::netsim::config::ConfigModifier;
+use crate::netsim::Network;
+use crate::permutators::{HeapsPermutator, Permutator};
+use crate::strategies::StagedStrategy;
+use crate::Stopper;
+
+use rand::prelude::*;
+use std::time::Duration;
+
+/// Synthesize the stages, and check that every modifier is present exactly once, that the hard
+/// policy holds for every interleaving of each stage, and that each stage can be applied
+/// concurrently.
+fn test_staged<N: ExampleNetwork>(
+    initial_variant: usize,
+    final_variant: usize,
+    max_stage_size: usize,
+) -> Vec<Vec<ConfigModifier>> {
+    test_staged_with::<N, _>(initial_variant, final_variant, max_stage_size, |_| {})
+}
+
+/// Same as [`test_staged`], but `configure` is called on the strategy before synthesizing.
+fn test_staged_with<N: ExampleNetwork, F: FnOnce(&mut StagedStrategy)>(
+    initial_variant: usize,
+    final_variant: usize,
+    max_stage_size: usize,
+    configure: F,
+) -> Vec<Vec<ConfigModifier>> {
+    let net = N::net(initial_variant);
+    let cf = N::final_config(&net, final_variant);
+    let mut modifiers = net.current_config().get_diff(&cf).modifiers;
+    let hard_policy =
+        HardPolicy::reachability(net.get_routers().iter(), net.get_known_prefixes().iter());
+
+    let mut strategy = StagedStrategy::new(
+        net.clone(),
+        modifiers.clone(),
+        hard_policy.clone(),
+        Some(Duration::from_secs(60)),
+        max_stage_size,
+    )
+    .unwrap();
+    configure(&mut strategy);
+    let stages = strategy.work(Stopper::new()).unwrap();
+
+    // check that every modifier is present exactly once
+    for m in stages.iter().flatten() {
+        let pos = modifiers.iter().position(|x| m == x).unwrap();
+        modifiers.remove(pos);
+    }
+    assert!(modifiers.is_empty());
+    assert!(stages.iter().all(|s| !s.is_empty() && s.len() <= max_stage_size));
+
+    // check every interleaving of each stage
+    let mut rng = thread_rng();
+    let num_mods = stages.iter().map(|s| s.len()).sum();
+    let mut net = net;
+    let mut hard_policy = hard_policy;
+    hard_policy.set_num_mods_if_none(num_mods);
+    step(&mut net, &mut hard_policy);
+    for stage in stages.iter() {
+        for ordering in HeapsPermutator::<NoOrdering, _>::new(stage.clone()) {
+            let mut n = net.clone();
+            let mut p = hard_policy.clone();
+            for modifier in ordering.iter() {
+                n.apply_modifier(modifier).unwrap();
+                step(&mut n, &mut p);
+            }
+        }
+        // applying the stage concurrently leads to the same forwarding state
+        let mut concurrent = net.clone();
+        assert!(concurrent.apply_modifiers_concurrently(stage, &mut rng, |_| true).unwrap());
+        for modifier in stage.iter() {
+            net.apply_modifier(modifier).unwrap();
+            step(&mut net, &mut hard_policy);
+        }
+        let fw_state = net.get_forwarding_state();
+        assert!(same_next_hops(&net, &concurrent.get_forwarding_state(), &fw_state));
+    }
+
+    stages
+}
+
+fn step(net: &mut Network, hard_policy: &mut HardPolicy) {
+    let mut fw_state = net.get_forwarding_state();
+    hard_policy.step(net, &mut fw_state).unwrap();
+    assert!(hard_policy.check());
+}
+
+#[test]
+fn staged_example_networks() {
+    test_staged::<SimpleNet>(0, 0, 4);
+    test_staged::<SmallNet>(0, 1, 4);
+    test_staged::<MediumNet>(0, 0, 4);
+    test_staged::<MediumNet>(1, 3, 4);
+    test_staged::<DifficultGadgetRepeated<Repetition2>>(0, 0, 4);
+}
+
+#[test]
+fn staged_single_ordering() {
+    // the chain gadget has only one single valid ordering, so every stage has size one.
+    let stages = test_staged::<ChainGadget<Repetition5>>(0, 0, 4);
+    assert!(stages.iter().all(|s| s.len() == 1));
+}
+
+#[test]
+fn staged_max_stage_size() {
+    let stages = test_staged::<MediumNet>(0, 0, 1);
+    assert!(stages.iter().all(|s| s.len() == 1));
+}
+
+#[test]
+fn staged_independent_modifiers() {
+    // after the first modifier, all remaining modifiers of the bipartite gadget are independent.
+    let stages = test_staged::<BipartiteGadget<Repetition2>>(3, 3, 8);
+    let num_mods: usize = stages.iter().map(|s| s.len()).sum();
+    assert!(stages.len() < num_mods);
+}
+
+#[test]
+fn staged_minimal_stages() {
+    // 14 modifiers need at least 4 stages of size 4. Depending on the sequence, the greedy solution
+    // may need 5 stages.
+    let stages = test_staged_with::<MediumNet, _>(0, 0, 4, |s| s.set_search_limit(None));
+    assert_eq!(stages.len(), 4);
+}
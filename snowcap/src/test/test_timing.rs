@@ -0,0 +1,243 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! Test the timed simulation.
+
+use crate::hard_policies::Condition;
+use crate::netsim::bgp::{BgpEvent, BgpRoute};
+use crate::netsim::config::{Config, ConfigExpr::*, ConfigModifier::*};
+use crate::netsim::timing::{EventScheduler, TimingModel};
+use crate::netsim::types::Destination;
+use crate::netsim::{AsId, BgpSessionType::*, Event, Network, NetworkError, Prefix, RouterId};
+
+use assert_approx_eq::assert_approx_eq;
+
+/// # Test network
+///
+/// ```text
+/// E1 ---- R1 ---- R2 ---- R3 ---- E3
+/// ```
+///
+/// All internal routers are connected with a full mesh of iBGP sessions. E1 advertises the prefix
+/// with a long AS path, such that initially, all routers prefer the route of E3.
+fn get_test_net() -> (Network, RouterId, RouterId, RouterId, RouterId, RouterId) {
+    let mut net = Network::new();
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let r3 = net.add_router("R3");
+    let e1 = net.add_external_router("E1", AsId(65101));
+    let e3 = net.add_external_router("E3", AsId(65103));
+
+    net.add_link(e1, r1);
+    net.add_link(r1, r2);
+    net.add_link(r2, r3);
+    net.add_link(r3, e3);
+
+    let mut c = Config::new();
+    for (a, b) in [(e1, r1), (r1, r2), (r2, r3), (r3, e3)] {
+        c.add(IgpLinkWeight { source: a, target: b, weight: 1.0 }).unwrap();
+        c.add(IgpLinkWeight { source: b, target: a, weight: 1.0 }).unwrap();
+    }
+    c.add(BgpSession { source: r1, target: r2, session_type: IBgpPeer }).unwrap();
+    c.add(BgpSession { source: r1, target: r3, session_type: IBgpPeer }).unwrap();
+    c.add(BgpSession { source: r2, target: r3, session_type: IBgpPeer }).unwrap();
+    c.add(BgpSession { source: r1, target: e1, session_type: EBgp }).unwrap();
+    c.add(BgpSession { source: r3, target: e3, session_type: EBgp }).unwrap();
+    net.set_config(&c).unwrap();
+
+    (net, r1, r2, r3, e1, e3)
+}
+
+#[test]
+fn timed_advertisement() {
+    let (mut net, r1, r2, r3, e1, _) = get_test_net();
+    let p = Prefix::from(0);
+
+    let mut timing = TimingModel::new();
+    timing.set_link_delay(e1, r1, 0.01).set_link_delay(r1, r2, 0.1);
+    net.set_timing_model(Some(timing.clone())).unwrap();
+
+    net.advertise_external_route(e1, p, vec![AsId(65101)], None, None).unwrap();
+    assert_eq!(net.get_route(r3, p), Ok(vec![r3, r2, r1, e1]));
+
+    // R1 learns the route after 10ms, R2 after 110ms, and R3 after 111ms.
+    let times: Vec<f64> = net.get_timed_trace().iter().map(|(t, _)| *t).collect();
+    assert_eq!(times.len(), 4);
+    for (t, exp) in times.iter().zip([0.0, 0.01, 0.11, 0.111]) {
+        assert_approx_eq!(t, exp);
+    }
+    assert_approx_eq!(net.get_timed_trace().convergence_time(), 0.111);
+
+    // the trace ends in the converged forwarding state
+    let (_, last) = net.get_timed_trace().iter().last().unwrap();
+    assert_eq!(last.clone().get_route_new(r3, Destination::BGP(p)), Ok(vec![r3, r2, r1, e1]));
+
+    // a processing delay at R1 delays the propagation to R2 and R3
+    net.retract_external_route(e1, p).unwrap();
+    timing.set_processing_delay(r1, 0.5);
+    net.set_timing_model(Some(timing)).unwrap();
+    net.advertise_external_route(e1, p, vec![AsId(65101)], None, None).unwrap();
+    assert_approx_eq!(net.get_timed_trace().convergence_time(), 0.611);
+}
+
+#[test]
+fn timed_violation() {
+    let (mut net, r1, r2, r3, e1, e3) = get_test_net();
+    let p = Prefix::from(0);
+
+    net.advertise_external_route(e1, p, vec![AsId(65101), AsId(1), AsId(2)], None, None).unwrap();
+    net.advertise_external_route(e3, p, vec![AsId(65103)], None, None).unwrap();
+    assert_eq!(net.get_route(r1, p), Ok(vec![r1, r2, r3, e3]));
+
+    let mut timing = TimingModel::new();
+    timing.set_link_delay(r1, r2, 0.1);
+    net.set_timing_model(Some(timing)).unwrap();
+
+    // R3 withdraws the route from R2 (after 1ms) and from R1 (after 101ms). Only then, R1 falls
+    // back to the route of E1, and advertises it to R2 (after 100ms) and R3 (after 101ms).
+    let before = net.clone();
+    net.retract_external_route(e3, p).unwrap();
+    assert_eq!(net.get_route(r3, p), Ok(vec![r3, r2, r1, e1]));
+    let trace = net.get_timed_trace();
+    assert_approx_eq!(trace.convergence_time(), 0.203);
+
+    // traffic is dropped at E3 from the start
+    assert_approx_eq!(trace.violation_time(&Condition::Reachable(r1, p, None)), 0.102);
+    assert_approx_eq!(trace.violation_time(&Condition::Reachable(r2, p, None)), 0.202);
+    assert_approx_eq!(trace.violation_time(&Condition::Reachable(r3, p, None)), 0.203);
+
+    // the timed simulation can be undone just like the untimed one.
+    net.undo_action().unwrap();
+    assert_eq!(net, before);
+}
+
+/// # Test network for the SPF delay
+///
+/// ```text
+/// E1 ---- R1 ---- R2 ---- R3 ---- E3
+///          \              /
+///           '---- R4 ----'
+/// ```
+///
+/// R2 is a route reflector, and R1, R3 and R4 are its clients. Both E1 and E3 advertise the same
+/// route, and R2 chooses between them based on the IGP cost.
+#[test]
+fn timed_spf_delay() {
+    let mut net = Network::new();
+    let r1 = net.add_router("R1");
+    let r2 = net.add_router("R2");
+    let r3 = net.add_router("R3");
+    let r4 = net.add_router("R4");
+    let e1 = net.add_external_router("E1", AsId(65101));
+    let e3 = net.add_external_router("E3", AsId(65103));
+    let p = Prefix::from(0);
+
+    let mut c = Config::new();
+    for (a, b) in [(e1, r1), (r1, r2), (r2, r3), (r3, e3), (r1, r4), (r3, r4)] {
+        net.add_link(a, b);
+        c.add(IgpLinkWeight { source: a, target: b, weight: 1.0 }).unwrap();
+        c.add(IgpLinkWeight { source: b, target: a, weight: 1.0 }).unwrap();
+    }
+    for client in [r1, r3, r4] {
+        c.add(BgpSession { source: r2, target: client, session_type: IBgpClient }).unwrap();
+    }
+    c.add(BgpSession { source: r1, target: e1, session_type: EBgp }).unwrap();
+    c.add(BgpSession { source: r3, target: e3, session_type: EBgp }).unwrap();
+    net.set_config(&c).unwrap();
+
+    net.advertise_external_route(e1, p, vec![AsId(65101)], None, None).unwrap();
+    net.advertise_external_route(e3, p, vec![AsId(65103)], None, None).unwrap();
+    assert_eq!(net.get_route(r4, p), Ok(vec![r4, r1, e1]));
+
+    let mut timing = TimingModel::new();
+    net.set_timing_model(Some(timing.clone())).unwrap();
+    let increase_weight = Update {
+        from: IgpLinkWeight { source: r2, target: r1, weight: 1.0 },
+        to: IgpLinkWeight { source: r2, target: r1, weight: 10.0 },
+    };
+
+    // R2 now selects the route of E3, and reflects it to R4 (over R3)
+    net.apply_modifier(&increase_weight).unwrap();
+    assert_eq!(net.get_route(r4, p), Ok(vec![r4, r3, e3]));
+    assert_approx_eq!(net.get_timed_trace().convergence_time(), 0.002);
+    net.undo_action().unwrap();
+
+    // R2 reacts to the changed IGP cost only after its SPF delay
+    timing.set_spf_delay(r2, 0.5);
+    net.set_timing_model(Some(timing)).unwrap();
+    net.apply_modifier(&increase_weight).unwrap();
+    assert_eq!(net.get_route(r4, p), Ok(vec![r4, r3, e3]));
+    assert_approx_eq!(net.get_timed_trace().convergence_time(), 0.502);
+}
+
+#[test]
+fn scheduler_mrai_and_ordering() {
+    let route = |next_hop| BgpRoute {
+        prefix: Prefix::from(0),
+        as_path: vec![AsId(65101)],
+        as_set: Default::default(),
+        next_hop,
+        local_pref: None,
+        med: None,
+        origin: Default::default(),
+        communities: Default::default(),
+        large_communities: Default::default(),
+        originator_id: None,
+        cluster_list: vec![],
+    };
+    let (a, b, c) = (0.into(), 1.into(), 2.into());
+
+    let mut timing = TimingModel::new();
+    timing.set_mrai(a, 1.0);
+    let mut scheduler = EventScheduler::default();
+    scheduler.schedule(&timing, 0.0, Event::Bgp(a, b, BgpEvent::Update(route(a))), 0, 0.1);
+    scheduler.schedule(&timing, 0.0, Event::Bgp(a, b, BgpEvent::Update(route(c))), 1, 0.1);
+    scheduler.schedule(&timing, 0.0, Event::Bgp(a, c, BgpEvent::Update(route(a))), 2, 0.1);
+    let withdraw = BgpEvent::Withdraw(Prefix::from(0));
+    scheduler.schedule(&timing, 0.0, Event::Bgp(a, b, withdraw), 3, 0.1);
+
+    // The second update to b is delayed by the MRAI, and the withdraw is received after it.
+    let order: Vec<(f64, usize)> =
+        std::iter::from_fn(|| scheduler.pop()).map(|(t, _, parent)| (t, parent)).collect();
+    assert_eq!(order.iter().map(|(_, parent)| *parent).collect::<Vec<_>>(), vec![0, 2, 1, 3]);
+    for ((t, _), exp) in order.iter().zip([0.1, 0.1, 1.1, 1.1]) {
+        assert_approx_eq!(t, exp);
+    }
+}
+
+#[test]
+fn timing_model_invalid_delays() {
+    let (mut net, r1, r2, ..) = get_test_net();
+
+    let mut timing = TimingModel::new();
+    timing.set_spf_delay(r1, f64::NAN);
+    assert!(
+        matches!(net.set_timing_model(Some(timing)), Err(NetworkError::InvalidDelay(d)) if d.is_nan())
+    );
+    let mut timing = TimingModel::new();
+    timing.set_link_delay(r1, r2, -1.0);
+    assert_eq!(net.set_timing_model(Some(timing)), Err(NetworkError::InvalidDelay(-1.0)));
+    let mut timing = TimingModel::new();
+    timing.default_mrai = f64::INFINITY;
+    assert_eq!(net.set_timing_model(Some(timing)), Err(NetworkError::InvalidDelay(f64::INFINITY)));
+    assert!(net.get_timing_model().is_none());
+
+    let mut timing = TimingModel::new();
+    timing.set_processing_delay(r2, 0.5);
+    assert_eq!(net.set_timing_model(Some(timing.clone())), Ok(()));
+    assert_eq!(net.get_timing_model(), Some(&timing));
+}
@@ -67,8 +67,8 @@ fn test_simple_case_correct() {
     n.set_config(&c).unwrap();
 
     // advertise the same prefix everywhere
-    n.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None).unwrap();
-    n.advertise_external_route(e3, Prefix(0), vec![AsId(65103), AsId(65200)], None, None).unwrap();
+    n.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None).unwrap();
+    n.advertise_external_route(e3, Prefix::from(0), vec![AsId(65103), AsId(65200)], None, None).unwrap();
 
     // check constraints
     let constraints = Constraints::reachability()
@@ -121,8 +121,8 @@ fn simple_case_incorrect() {
     n.set_config(&c).unwrap();
 
     // advertise the same prefix everywhere
-    n.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None).unwrap();
-    n.advertise_external_route(e3, Prefix(0), vec![AsId(65103), AsId(65200)], None, None).unwrap();
+    n.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None).unwrap();
+    n.advertise_external_route(e3, Prefix::from(0), vec![AsId(65103), AsId(65200)], None, None).unwrap();
 
     // check constraints
     let constraints = Constraints::reachability()
@@ -208,9 +208,9 @@ fn multiple_options_incorrect() {
     n.set_config(&c).unwrap();
 
     // advertise the same prefix everywhere
-    n.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None).unwrap();
-    n.advertise_external_route(e2, Prefix(0), vec![AsId(65102), AsId(65200)], None, None).unwrap();
-    n.advertise_external_route(e3, Prefix(0), vec![AsId(65103), AsId(65200)], None, None).unwrap();
+    n.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None).unwrap();
+    n.advertise_external_route(e2, Prefix::from(0), vec![AsId(65102), AsId(65200)], None, None).unwrap();
+    n.advertise_external_route(e3, Prefix::from(0), vec![AsId(65103), AsId(65200)], None, None).unwrap();
 
     // check constraints
     let constraints = Constraints::reachability()
@@ -307,10 +307,10 @@ fn multiple_options_correct() {
     n.set_config(&c).unwrap();
 
     // advertise the same prefix everywhere
-    n.advertise_external_route(e1, Prefix(0), vec![AsId(65101), AsId(65200)], None, None).unwrap();
-    n.advertise_external_route(e2, Prefix(0), vec![AsId(65102), AsId(65200)], None, None).unwrap();
-    n.advertise_external_route(e3, Prefix(0), vec![AsId(65103), AsId(65200)], None, None).unwrap();
-    n.advertise_external_route(e4, Prefix(0), vec![AsId(65104), AsId(65200)], None, None).unwrap();
+    n.advertise_external_route(e1, Prefix::from(0), vec![AsId(65101), AsId(65200)], None, None).unwrap();
+    n.advertise_external_route(e2, Prefix::from(0), vec![AsId(65102), AsId(65200)], None, None).unwrap();
+    n.advertise_external_route(e3, Prefix::from(0), vec![AsId(65103), AsId(65200)], None, None).unwrap();
+    n.advertise_external_route(e4, Prefix::from(0), vec![AsId(65104), AsId(65200)], None, None).unwrap();
 
     // check constraints
     let constraints = Constraints::reachability()
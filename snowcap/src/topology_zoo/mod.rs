@@ -449,21 +449,21 @@ impl ZooTopology {
 
         net.advertise_external_route(
             self.graph.node_weight(ext_lp100).unwrap().net_idx.unwrap(),
-            Prefix(0),
+            Prefix::from(0),
             vec![AsId(65101), AsId(65200)],
             None,
             None,
         )?;
         net.advertise_external_route(
             self.graph.node_weight(ext_lp50).unwrap().net_idx.unwrap(),
-            Prefix(0),
+            Prefix::from(0),
             vec![AsId(65102), AsId(65200)],
             None,
             None,
         )?;
         net.advertise_external_route(
             self.graph.node_weight(ext_new).unwrap().net_idx.unwrap(),
-            Prefix(0),
+            Prefix::from(0),
             vec![AsId(65103), AsId(65200)],
             None,
             None,
@@ -1048,7 +1048,7 @@ impl ZooTopology {
 
         let mut prefixes: Vec<(Prefix, AsId)> = Vec::with_capacity(num_prefixes);
         for i in 0..num_prefixes {
-            prefixes.push((Prefix(i as u32), AsId(65300 + i as u32)));
+            prefixes.push((Prefix::from(i as u32), AsId(65300 + i as u32)));
         }
 
         let mut component_known_prefixes = [HashSet::new(), HashSet::new()];
@@ -1062,7 +1062,7 @@ impl ZooTopology {
                     trace!(
                         "{} advertises {}",
                         self.graph.node_weight(node).unwrap().name,
-                        prefix.0
+                        prefix
                     );
                     net.advertise_external_route(
                         node,
@@ -1122,7 +1122,7 @@ impl ZooTopology {
                 // compute the difference between the components
                 let mut differences: Vec<Prefix> =
                     must_known_prefixes.difference(&known_prefixes).cloned().collect();
-                differences.sort_by_key(|x| x.0);
+                differences.sort();
 
                 // for each of the missing prefixes, we need to advertise at a single router
                 for prefix in differences {
@@ -1137,7 +1137,7 @@ impl ZooTopology {
                     trace!(
                         "{} advertises {}",
                         self.graph.node_weight(*node).unwrap().name,
-                        prefix.0
+                        prefix
                     );
                     net.advertise_external_route(
                         *node,
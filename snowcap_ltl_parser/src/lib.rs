@@ -17,23 +17,27 @@
 
 //! # LTL Parser
 //!
-//! This module provides macros to generate LTL expressions more easily.
+//! This module provides macros to generate LTL expressions and hard policies more easily.
 //!
 
 use proc_macro::TokenStream;
-use proc_macro2::TokenStream as TokenStream2;
+use proc_macro2::{Delimiter, Group, Ident, Literal, TokenStream as TokenStream2, TokenTree};
 use quote::quote;
 use syn::{
-    parse_macro_input, BinOp, Error, Expr, ExprBinary, ExprCall, ExprLit, ExprParen, ExprPath,
-    ExprUnary, Lit, Result, UnOp,
+    BinOp, Error, Expr, ExprArray, ExprBinary, ExprCall, ExprLit, ExprParen, ExprPath, ExprUnary,
+    Lit, LitInt, Result, UnOp,
 };
 
 /// # Generate LTL Expressions from the provided tokens
 ///
-/// The result will be wrapped into a `LTLModal::Now` structure.
+/// The result will be wrapped into a `LTLModal::Now` structure. If the propositional variables are
+/// written as conditions (see [`hard_policy!`]), they are collected into the vector of
+/// propositional variables, and the result is a `HardPolicy`. Numbers and conditions cannot be
+/// mixed in the same expression.
 ///
 /// ## Allowed Tokens
 /// - Literals, like `true`, `false`, and numbers t index propositional variables
+/// - Conditions, like `reach(r, p)` or `isolated(r, p)`, as listed in [`hard_policy!`]
 /// - `!`, `-`: `LTLBoolean::Not`
 /// - `+`, `||`, `|`: `LTLBoolean::Or`
 /// - `*`, `&&`, `&`: `LTLBoolean::And`
@@ -56,19 +60,211 @@ use syn::{
 /// - `M(_, _)`, `m(_, _)`, `StrongRelease(_, _)`: `LTLModal::StrongRelease`
 #[proc_macro]
 pub fn ltl(input: TokenStream) -> TokenStream {
-    let e = parse_macro_input!(input as Expr);
+    let (result, vars) = match parse_input(input) {
+        Ok(x) => x,
+        Err(e) => return e.to_compile_error().into(),
+    };
 
-    match parse_recursive(e) {
-        Ok(result) => TokenStream::from(quote! {snowcap::hard_policies::LTLModal::Now(#result)}),
-        Err(e) => e.to_compile_error().into(),
+    if vars.conds.is_empty() {
+        return TokenStream::from(quote! {snowcap::hard_policies::LTLModal::Now(#result)});
     }
+    match vars.index {
+        Some(i) => Error::new_spanned(
+            i,
+            "Propositional variables cannot be given both as numbers and as conditions",
+        )
+        .to_compile_error()
+        .into(),
+        None => generate_hard_policy(result, vars.conds),
+    }
+}
+
+/// # Generate a Hard Policy from the provided tokens
+///
+/// The syntax is equal to [`ltl!`], but the propositional variables must be written as conditions.
+/// All conditions are collected into the vector of propositional variables (where equal conditions
+/// are only added once), and the result is always a `HardPolicy`, even if it has no conditions.
+///
+/// ## Conditions
+/// - `reach(r, p)`, `reach(r, p, c)`: `Condition::Reachable`, where `c` is a `PathCondition`
+/// - `via(r, w, p)`: `Condition::Reachable`, where the path must traverse `w`
+/// - `waypoint(r, p, [a, *, ?, b])`: `Condition::Reachable` with a positional path condition, where
+///   `*` is `Waypoint::Star`, `?` is `Waypoint::Any`, and all other elements are routers.
+/// - `isolated(r, p)`: `Condition::NotReachable`
+/// - `reach_igp(r, t)`, `reach_igp(r, t, c)`: `Condition::ReachableIGP`
+/// - `isolated_igp(r, t)`: `Condition::NotReachableIGP`
+/// - `reliable(r, p)`, `reliable(r, p, c)`: `Condition::Reliable`
+/// - `transient(r, p, c)`: `Condition::TransientPath`
+///
+/// All arguments are arbitrary Rust expressions.
+#[proc_macro]
+pub fn hard_policy(input: TokenStream) -> TokenStream {
+    let (result, vars) = match parse_input(input) {
+        Ok(x) => x,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    match vars.index {
+        Some(i) => Error::new_spanned(
+            i,
+            "Propositional variables in hard_policy! must be given as conditions",
+        )
+        .to_compile_error()
+        .into(),
+        None => generate_hard_policy(result, vars.conds),
+    }
+}
+
+/// Propositional variables found while parsing the expression.
+#[derive(Default)]
+struct PropVars {
+    /// Conditions given inline, where the position is the index of the propositional variable.
+    conds: Vec<TokenStream2>,
+    /// First propositional variable given as an integer literal.
+    index: Option<LitInt>,
+}
+
+/// Parse the input of the macro, and collect all propositional variables.
+fn parse_input(input: TokenStream) -> Result<(TokenStream2, PropVars)> {
+    let input = replace_wildcards(TokenStream2::from(input));
+    let e = syn::parse2::<Expr>(input)?;
+    let mut vars = PropVars::default();
+    let result = parse_recursive(e, &mut vars)?;
+    Ok((result, vars))
+}
+
+/// Generate the `HardPolicy` from the parsed expression and the collected conditions.
+fn generate_hard_policy(result: TokenStream2, conds: Vec<TokenStream2>) -> TokenStream {
+    TokenStream::from(quote! {
+        snowcap::hard_policies::HardPolicy::new(
+            vec![#(#conds),*],
+            snowcap::hard_policies::LTLModal::Now(#result),
+        )
+    })
+}
+
+/// Names of all conditions that can be used in `ltl!` and `hard_policy!`
+const CONDITIONS: [&str; 8] = [
+    "reach",
+    "via",
+    "waypoint",
+    "isolated",
+    "reach_igp",
+    "isolated_igp",
+    "reliable",
+    "transient",
+];
+
+/// Identifiers which replace the wildcards `*` and `?` in positional path conditions, such that
+/// the input can be parsed as an expression.
+const STAR: &str = "__snowcap_waypoint_star";
+const ANY: &str = "__snowcap_waypoint_any";
+
+/// Replace the wildcards `*` and `?` inside brackets (`[a, *, ?, b]`) by identifiers.
+fn replace_wildcards(input: TokenStream2) -> TokenStream2 {
+    input
+        .into_iter()
+        .map(|t| match t {
+            TokenTree::Group(g) => {
+                let mut stream = replace_wildcards(g.stream());
+                if g.delimiter() == Delimiter::Bracket {
+                    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+                    let is_comma = |t: Option<&TokenTree>| match t {
+                        None => true,
+                        Some(TokenTree::Punct(p)) => p.as_char() == ',',
+                        _ => false,
+                    };
+                    stream = (0..tokens.len())
+                        .map(|i| match &tokens[i] {
+                            TokenTree::Punct(p)
+                                if matches!(p.as_char(), '*' | '?')
+                                    && (i == 0 || is_comma(tokens.get(i - 1)))
+                                    && is_comma(tokens.get(i + 1)) =>
+                            {
+                                let name = if p.as_char() == '*' { STAR } else { ANY };
+                                TokenTree::Ident(Ident::new(name, p.span()))
+                            }
+                            t => t.clone(),
+                        })
+                        .collect();
+                }
+                let mut group = Group::new(g.delimiter(), stream);
+                group.set_span(g.span());
+                TokenTree::Group(group)
+            }
+            t => t,
+        })
+        .collect()
+}
+
+/// Generate the condition `name` with the given arguments.
+fn parse_condition(name: &str, func: &Expr, args: Vec<Expr>) -> Result<TokenStream2> {
+    let c = quote! {snowcap::hard_policies::Condition};
+    let invalid = || {
+        Error::new_spanned(
+            func.clone(),
+            format!("Invalid number of arguments for \"{}\"", name),
+        )
+    };
+    match (name, args.as_slice()) {
+        ("reach", [r, p]) => Ok(quote! {#c::Reachable(#r, #p, None)}),
+        ("reach", [r, p, cond]) => Ok(quote! {#c::Reachable(#r, #p, Some(#cond))}),
+        ("via", [r, w, p]) => Ok(quote! {
+            #c::Reachable(#r, #p, Some(snowcap::hard_policies::PathCondition::Node(#w)))
+        }),
+        ("waypoint", [r, p, waypoints]) => {
+            let waypoints = parse_waypoints(waypoints)?;
+            Ok(quote! {
+                #c::Reachable(
+                    #r,
+                    #p,
+                    Some(snowcap::hard_policies::PathCondition::Positional(vec![#(#waypoints),*])),
+                )
+            })
+        }
+        ("isolated", [r, p]) => Ok(quote! {#c::NotReachable(#r, #p)}),
+        ("reach_igp", [r, t]) => Ok(quote! {#c::ReachableIGP(#r, #t, None)}),
+        ("reach_igp", [r, t, cond]) => Ok(quote! {#c::ReachableIGP(#r, #t, Some(#cond))}),
+        ("isolated_igp", [r, t]) => Ok(quote! {#c::NotReachableIGP(#r, #t)}),
+        ("reliable", [r, p]) => Ok(quote! {#c::Reliable(#r, #p, None)}),
+        ("reliable", [r, p, cond]) => Ok(quote! {#c::Reliable(#r, #p, Some(#cond))}),
+        ("transient", [r, p, cond]) => Ok(quote! {#c::TransientPath(#r, #p, #cond)}),
+        _ => Err(invalid()),
+    }
+}
+
+/// Generate the waypoints of a positional path condition, given as an array.
+fn parse_waypoints(e: &Expr) -> Result<Vec<TokenStream2>> {
+    let elems = match e {
+        Expr::Array(ExprArray { elems, .. }) => elems,
+        e => {
+            return Err(Error::new_spanned(
+                e.clone(),
+                "Expected an array of waypoints, like [a, *, ?, b]",
+            ))
+        }
+    };
+    let w = quote! {snowcap::hard_policies::Waypoint};
+    Ok(elems
+        .iter()
+        .map(|e| match e {
+            Expr::Path(ExprPath { path, .. }) if path.is_ident(STAR) => quote! {#w::Star},
+            Expr::Path(ExprPath { path, .. }) if path.is_ident(ANY) => quote! {#w::Any},
+            e => quote! {#w::Fix(#e)},
+        })
+        .collect())
 }
 
-fn parse_recursive(e: Expr) -> Result<TokenStream2> {
+fn parse_recursive(e: Expr, vars: &mut PropVars) -> Result<TokenStream2> {
     match e {
         Expr::Lit(ExprLit {
             lit: Lit::Int(i), ..
-        }) => Ok(quote! {Box::new(#i)}.into()),
+        }) => {
+            if vars.index.is_none() {
+                vars.index = Some(i.clone());
+            }
+            Ok(quote! {Box::new(#i)}.into())
+        }
         Expr::Lit(ExprLit {
             lit: Lit::Bool(b), ..
         }) => Ok(quote! {Box::new(#b)}.into()),
@@ -82,7 +278,7 @@ fn parse_recursive(e: Expr) -> Result<TokenStream2> {
             expr,
             ..
         }) => {
-            let content = parse_recursive(*expr)?;
+            let content = parse_recursive(*expr, vars)?;
             Ok(quote! {Box::new(snowcap::hard_policies::LTLBoolean::Not(#content))})
         }
         Expr::Binary(ExprBinary {
@@ -91,8 +287,8 @@ fn parse_recursive(e: Expr) -> Result<TokenStream2> {
             right,
             attrs,
         }) => {
-            let l = parse_recursive(*left.clone())?;
-            let r = parse_recursive(*right.clone())?;
+            let l = parse_recursive(*left.clone(), vars)?;
+            let r = parse_recursive(*right.clone(), vars)?;
             match op {
                 BinOp::Add(_) | BinOp::Or(_) | BinOp::BitOr(_) => Ok(quote! {
                     Box::new(snowcap::hard_policies::LTLBoolean::Or(vec![#l, #r]))
@@ -123,7 +319,7 @@ fn parse_recursive(e: Expr) -> Result<TokenStream2> {
                 )),
             }
         }
-        Expr::Paren(ExprParen { expr, .. }) => parse_recursive(*expr),
+        Expr::Paren(ExprParen { expr, .. }) => parse_recursive(*expr, vars),
         Expr::Call(ExprCall { func, args, .. }) => {
             // check the function name
             let func_ident = if let Expr::Path(ExprPath { path, .. }) = *func.clone() {
@@ -141,9 +337,24 @@ fn parse_recursive(e: Expr) -> Result<TokenStream2> {
                     format!("Invalid function: {:?}", func),
                 ));
             };
+            if CONDITIONS.contains(&func_ident.as_str()) {
+                let cond = parse_condition(&func_ident, &func, args.into_iter().collect())?;
+                // equal conditions use the same propositional variable
+                let key = cond.to_string();
+                let idx = match vars.conds.iter().position(|c| c.to_string() == key) {
+                    Some(idx) => idx,
+                    None => {
+                        vars.conds.push(cond);
+                        vars.conds.len() - 1
+                    }
+                };
+                let idx = Literal::usize_suffixed(idx);
+                return Ok(quote! {Box::new(#idx)});
+            }
+
             let args = args
                 .iter()
-                .map(|e| parse_recursive(e.clone()))
+                .map(|e| parse_recursive(e.clone(), vars))
                 .collect::<Result<Vec<_>>>()?;
 
             let args_len = args.len();
@@ -0,0 +1,90 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+#![cfg(test)]
+
+#[macro_use]
+extern crate snowcap_ltl_parser;
+use snowcap::hard_policies::*;
+use snowcap::netsim::{Prefix, RouterId};
+
+#[test]
+fn collect_conditions() {
+    let (a, b, c): (RouterId, RouterId, RouterId) = (0.into(), 1.into(), 2.into());
+    let p = Prefix::from(0);
+
+    let policy = hard_policy!(U(reach(a, p) & !via(a, b, p), G(isolated(c, p))));
+    assert_eq!(
+        policy.prop_vars,
+        vec![
+            Condition::Reachable(a, p, None),
+            Condition::Reachable(a, p, Some(PathCondition::Node(b))),
+            Condition::NotReachable(c, p),
+        ]
+    );
+    assert_eq!(policy.expr.repr(), "((x00 && !x01) U (G x02))");
+}
+
+#[test]
+fn equal_conditions() {
+    let a: RouterId = 0.into();
+    let p = Prefix::from(0);
+
+    let policy = hard_policy!(reach(a, p) || F(reach(a, p) && reliable(a, p)));
+    assert_eq!(
+        policy.prop_vars,
+        vec![
+            Condition::Reachable(a, p, None),
+            Condition::Reliable(a, p, None)
+        ]
+    );
+    assert_eq!(policy.expr.repr(), "(x00 || (F (x00 && x01)))");
+}
+
+#[test]
+fn waypoints_and_path_conditions() {
+    let (a, b, c): (RouterId, RouterId, RouterId) = (0.into(), 1.into(), 2.into());
+    let p = Prefix::from(0);
+
+    let policy = hard_policy!(G(waypoint(a, p, [a, *, b, ?])
+        && transient(c, p, PathCondition::Edge(c, b))
+        && reach_igp(a, c, PathCondition::Not(Box::new(PathCondition::Node(b))))
+        && isolated_igp(b, c)));
+    assert_eq!(
+        policy.prop_vars,
+        vec![
+            Condition::Reachable(
+                a,
+                p,
+                Some(PathCondition::Positional(vec![
+                    Waypoint::Fix(a),
+                    Waypoint::Star,
+                    Waypoint::Fix(b),
+                    Waypoint::Any
+                ]))
+            ),
+            Condition::TransientPath(c, p, PathCondition::Edge(c, b)),
+            Condition::ReachableIGP(
+                a,
+                c,
+                Some(PathCondition::Not(Box::new(PathCondition::Node(b))))
+            ),
+            Condition::NotReachableIGP(b, c),
+        ]
+    );
+    assert_eq!(policy.expr.repr(), "(G (((x00 && x01) && x02) && x03))");
+}
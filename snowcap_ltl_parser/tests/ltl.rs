@@ -20,6 +20,7 @@
 #[macro_use]
 extern crate snowcap_ltl_parser;
 use snowcap::hard_policies::*;
+use snowcap::netsim::{Prefix, RouterId};
 
 #[test]
 fn now_bool() {
@@ -116,3 +117,32 @@ fn modal_complex() {
         "(((F x01) || (x00 R !x01)) U (G (x02 <=> x03)))"
     );
 }
+
+#[test]
+fn inline_conditions() {
+    let (a, b): (RouterId, RouterId) = (0.into(), 1.into());
+    let p = Prefix::from(0);
+
+    let policy = ltl!(G(reach(a, p) && !isolated(b, p)) || waypoint(a, p, [a, *, b]));
+    assert_eq!(
+        policy.prop_vars,
+        vec![
+            Condition::Reachable(a, p, None),
+            Condition::NotReachable(b, p),
+            Condition::Reachable(
+                a,
+                p,
+                Some(PathCondition::Positional(vec![
+                    Waypoint::Fix(a),
+                    Waypoint::Star,
+                    Waypoint::Fix(b)
+                ]))
+            ),
+        ]
+    );
+    assert_eq!(policy.expr.repr(), "((G (x00 && !x01)) || x02)");
+
+    let expected = hard_policy!(G(reach(a, p) && !isolated(b, p)) || waypoint(a, p, [a, *, b]));
+    assert_eq!(policy.prop_vars, expected.prop_vars);
+    assert_eq!(policy.expr.repr(), expected.expr.repr());
+}
@@ -34,6 +34,8 @@ use std::fmt;
 
 mod example_topologies;
 use example_topologies::*;
+mod problem_file;
+use problem_file::*;
 mod transient_violation;
 use transient_violation::*;
 
@@ -54,7 +56,12 @@ fn main() -> Result<(), Box<dyn Error>> {
             transient_violation_topologyzoo(gml_file, seed, n_seeds, n_iter, num_threads, reverse)?
         }
         MainCommand::CustomOperation { n_iter, variant } => transient_violation(n_iter, variant)?,
-        MainCommand::Optimize { network, use_tree } => {
+        MainCommand::Optimize {
+            network,
+            use_tree,
+            output,
+            format,
+        } => {
             // initialize the env logger
             pretty_env_logger::init();
             // get the network
@@ -100,8 +107,15 @@ fn main() -> Result<(), Box<dyn Error>> {
                     .collect::<Vec<_>>()
                     .join("\n    "),
             );
+
+            write_sequence(&net, &sequence, output.as_deref(), format)?;
         }
-        MainCommand::Synthesize { network, use_tree } => {
+        MainCommand::Synthesize {
+            network,
+            use_tree,
+            output,
+            format,
+        } => {
             // initialize the env logger
             pretty_env_logger::init();
             // get the network
@@ -142,6 +156,8 @@ fn main() -> Result<(), Box<dyn Error>> {
                     .collect::<Vec<_>>()
                     .join("\n    "),
             );
+
+            write_sequence(&net, &sequence, output.as_deref(), format)?;
         }
         MainCommand::Runtime {
             network,
@@ -222,6 +238,14 @@ fn get_topo(args: NetworkSelection) -> Result<(Network, Config, HardPolicy), Box
             final_variant,
             repetitions,
         } => example_networks_scenario(topology, initial_variant, final_variant, repetitions),
+        NetworkSelection::File { problem, policy } => {
+            let (net, final_config, hard_policy) = load_problem(&problem)?;
+            let hard_policy = match policy {
+                Some(filename) => load_policy(&net, &filename)?,
+                None => hard_policy,
+            };
+            Ok((net, final_config, hard_policy))
+        }
     }
 }
 
@@ -273,6 +297,12 @@ enum MainCommand {
         /// Use the tree strategy instead of the more complex one
         #[clap(short = 't', long)]
         use_tree: bool,
+        /// Write the update sequence to this file, instead of stdout
+        #[clap(short = 'o', long)]
+        output: Option<String>,
+        /// Format of the update sequence
+        #[clap(arg_enum, long, default_value = "json")]
+        format: OutputFormat,
         /// Type of measurement to perform
         #[clap(subcommand)]
         network: NetworkSelection,
@@ -283,6 +313,12 @@ enum MainCommand {
         /// Use the tree strategy instead of the more complex one
         #[clap(short = 't', long)]
         use_tree: bool,
+        /// Write the update sequence to this file, instead of stdout
+        #[clap(short = 'o', long)]
+        output: Option<String>,
+        /// Format of the update sequence
+        #[clap(arg_enum, long, default_value = "json")]
+        format: OutputFormat,
         /// Type of measurement to perform
         #[clap(subcommand)]
         network: NetworkSelection,
@@ -394,6 +430,18 @@ enum NetworkSelection {
         #[clap(arg_enum)]
         topology: Topology,
     },
+    /// Load the network, the initial and final configuration, and the hard policy from a JSON
+    /// problem file.
+    #[clap(name = "file")]
+    File {
+        /// Problem file to load
+        #[clap(short = 'p', long)]
+        problem: String,
+        /// Text file containing the hard policy, which replaces the hard policy of the problem
+        /// file. See `snowcap::hard_policies::parse_policy` for the syntax.
+        #[clap(long)]
+        policy: Option<String>,
+    },
 }
 
 impl NetworkSelection {
@@ -401,6 +449,7 @@ impl NetworkSelection {
     pub fn repr(&self) -> String {
         match self {
             NetworkSelection::CustomNetwork => "Custom Network".to_string(),
+            NetworkSelection::File { problem, .. } => problem.clone(),
             NetworkSelection::TopologyZoo {
                 gml_file,
                 many_prefixes,
@@ -0,0 +1,183 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+//! # Problem Files
+//!
+//! This module reads problem instances from JSON files, and writes the synthesized update sequence
+//! either as JSON or as text (see [`OutputFormat`]). A problem file contains the network (topology, external routers, advertised routes
+//! and the initial configuration), the final configuration, and the hard policy. The hard policy
+//! can also be read from a separate text file.
+
+use snowcap::hard_policies::{parse_policy, HardPolicy};
+use snowcap::netsim::config::{Config, ConfigModifier};
+use snowcap::netsim::{printer, Network};
+
+use clap::Clap;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+
+/// Problem instance stored in a problem file. The initial configuration is the current
+/// configuration of the network.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Problem {
+    /// Network, including the initial configuration and all advertised routes
+    pub net: Network,
+    /// Configuration to reach after the reconfiguration
+    pub final_config: Config,
+    /// Hard policy which must be satisfied during the entire reconfiguration
+    pub hard_policy: HardPolicy,
+}
+
+/// Read the problem from a JSON file.
+pub fn load_problem(filename: &str) -> Result<(Network, Config, HardPolicy), Box<dyn Error>> {
+    let reader = BufReader::new(File::open(filename)?);
+    let problem: Problem = serde_json::from_reader(reader)?;
+    Ok((problem.net, problem.final_config, problem.hard_policy))
+}
+
+/// Read the hard policy from a text file, resolving the router names in `net`. Lines starting with
+/// `#` are ignored.
+pub fn load_policy(net: &Network, filename: &str) -> Result<HardPolicy, Box<dyn Error>> {
+    let content = fs::read_to_string(filename)?;
+    let policy = content
+        .lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(parse_policy(net, &policy)?)
+}
+
+/// Format in which the update sequence is written.
+#[derive(Clap, Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// JSON array of all modifiers, which can be read back using serde.
+    #[clap(name = "json")]
+    Json,
+    /// One modifier per line, with all router names inserted.
+    #[clap(name = "text")]
+    Text,
+}
+
+/// Write the update sequence in the given `format`, either to the file `output`, or to stdout if
+/// `output` is `None`.
+pub fn write_sequence(
+    net: &Network,
+    sequence: &[ConfigModifier],
+    output: Option<&str>,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    match output {
+        Some(filename) => {
+            let mut writer = BufWriter::new(File::create(filename)?);
+            write_sequence_to(net, sequence, format, &mut writer)?;
+            writer.flush()?;
+        }
+        None => write_sequence_to(net, sequence, format, &mut io::stdout().lock())?,
+    }
+    Ok(())
+}
+
+/// Write the update sequence in the given `format` to `writer`.
+fn write_sequence_to<W: Write>(
+    net: &Network,
+    sequence: &[ConfigModifier],
+    format: OutputFormat,
+    writer: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *writer, sequence)?;
+            writeln!(writer)?;
+        }
+        OutputFormat::Text => {
+            for modifier in sequence {
+                writeln!(writer, "{}", printer::config_modifier(net, modifier)?)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use snowcap::example_networks::{ExampleNetwork, SimpleNet};
+    use snowcap::netsim::types::Destination;
+    use snowcap::synthesize;
+
+    #[test]
+    fn problem_round_trip() {
+        let net = SimpleNet::net(0);
+        let final_config = SimpleNet::final_config(&net, 0);
+        let hard_policy =
+            HardPolicy::reachability(net.get_routers().iter(), net.get_known_prefixes().iter());
+        let problem = Problem { net, final_config, hard_policy };
+
+        let dir = std::env::temp_dir().join("snowcap_main_problem_round_trip");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let problem_file = dir.join("problem.json");
+        serde_json::to_writer(File::create(&problem_file).unwrap(), &problem).unwrap();
+
+        // the loaded problem is the same as the stored one
+        let (net, final_config, hard_policy) =
+            load_problem(problem_file.to_str().unwrap()).unwrap();
+        let names = |net: &Network| {
+            let mut ids = net.get_routers();
+            ids.extend(net.get_external_routers());
+            let mut names: Vec<String> =
+                ids.into_iter().map(|r| net.get_router_name(r).unwrap().to_string()).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(&net), names(&problem.net));
+        assert_eq!(net.links_symmetric().count(), problem.net.links_symmetric().count());
+        let (fw_a, fw_b) = (net.get_forwarding_state(), problem.net.get_forwarding_state());
+        for router in net.get_routers() {
+            for prefix in net.get_known_prefixes() {
+                assert_eq!(
+                    fw_a.get_next_hops_new(router, Destination::BGP(*prefix)).unwrap(),
+                    fw_b.get_next_hops_new(router, Destination::BGP(*prefix)).unwrap()
+                );
+            }
+        }
+        assert_eq!(net.current_config(), problem.net.current_config());
+        assert_eq!(final_config, problem.final_config);
+        assert_eq!(hard_policy.prop_vars, problem.hard_policy.prop_vars);
+
+        // the synthesized sequence can be written and read back
+        let initial_config = net.current_config().clone();
+        let sequence =
+            synthesize(net.clone(), initial_config, final_config, hard_policy, None).unwrap();
+        let json_file = dir.join("sequence.json");
+        write_sequence(&net, &sequence, json_file.to_str(), OutputFormat::Json).unwrap();
+        let read: Vec<ConfigModifier> =
+            serde_json::from_reader(File::open(&json_file).unwrap()).unwrap();
+        assert_eq!(read, sequence);
+
+        let text_file = dir.join("sequence.txt");
+        write_sequence(&net, &sequence, text_file.to_str(), OutputFormat::Text).unwrap();
+        let text = fs::read_to_string(&text_file).unwrap();
+        assert_eq!(text.lines().count(), sequence.len());
+        for (line, modifier) in text.lines().zip(sequence.iter()) {
+            assert_eq!(line, printer::config_modifier(&net, modifier).unwrap());
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -47,7 +47,7 @@ pub fn transient_violation(n_iter: usize, variant: usize) -> Result<(), Box<dyn
     let ny = net.get_router_id("New York").unwrap();
     let ch = net.get_router_id("Chicago").unwrap();
 
-    let p = Prefix(0);
+    let p = Prefix::from(0);
 
     let commands = [ConfigModifier::Update {
         from: ConfigExpr::IgpLinkWeight {
@@ -192,7 +192,7 @@ fn transient_violation_topologyzoo_thread(
             let final_fws = final_net.get_forwarding_state();
             let external_routers = net.get_external_routers();
 
-            let p = Prefix(0);
+            let p = Prefix::from(0);
             let commands = net.current_config().get_diff(&final_config).modifiers;
 
             let mut result = SeedResult::default();
@@ -39,13 +39,13 @@ pub fn check(
             Condition::Reachable(router, prefix, None) => {
                 let client: RouterId = (router.index() as u32 + CLIENT_ID_BASE).into();
                 let router_name = phys_net.router_name(*router);
-                info!("Checking condition: {} can reach prefix {}", router_name, prefix.0);
+                info!("Checking condition: {} can reach prefix {}", router_name, prefix);
                 let p = match paths.get(&(client, *prefix)) {
                     Some(p) => p,
                     None => {
                         warn!(
                             "No packets from router {} to prefix {} were found!",
-                            router_name, prefix.0
+                            router_name, prefix
                         );
                         conds_ok = false;
                         continue;
@@ -70,14 +70,14 @@ pub fn check(
                 let router_name = phys_net.router_name(*router);
                 info!(
                     "Checking condition: {} can reach prefix {} with path condition: {}",
-                    router_name, prefix.0, cond
+                    router_name, prefix, cond
                 );
                 let p = match paths.get(&(client, *prefix)) {
                     Some(p) => p,
                     None => {
                         warn!(
                             "No packets from router {} to prefix {} were found!",
-                            router_name, prefix.0
+                            router_name, prefix
                         );
                         conds_ok = false;
                         continue;
@@ -112,13 +112,13 @@ pub fn check(
             Condition::NotReachable(router, prefix) => {
                 let client: RouterId = (router.index() as u32 + CLIENT_ID_BASE).into();
                 let router_name = phys_net.router_name(*router);
-                info!("Checking condition: {} cannot reach prefix {}", router_name, prefix.0);
+                info!("Checking condition: {} cannot reach prefix {}", router_name, prefix);
                 let p = match paths.get(&(client, *prefix)) {
                     Some(p) => p,
                     None => {
                         warn!(
                             "No packets from router {} to prefix {} were found!",
-                            router_name, prefix.0
+                            router_name, prefix
                         );
                         conds_ok = false;
                         continue;
@@ -140,6 +140,9 @@ pub fn check(
             }
             Condition::NotReachableIGP(router1, router2) => {
                 
+            }
+            Condition::ReachableTraffic(_, _, _) | Condition::NotReachableTraffic(_, _, _) => {
+                info!("Skipping traffic class condition")
             }
             Condition::Reliable(_, _, _) => info!("Skipping reliability condition"),
             Condition::TransientPath(_, _, _) => info!("Skipping transient path condition"),
@@ -155,7 +158,7 @@ pub fn print_paths(
     phys_net: &PhysicalNetwork,
 ) {
     for ((router, prefix), paths) in flows {
-        info!("Paths from {} for prefix {}", phys_net.router_name(*router), prefix.0);
+        info!("Paths from {} for prefix {}", phys_net.router_name(*router), prefix);
         for (path, num) in paths {
             if let Some(path) = path {
                 info!("    {} packets: [{}]", num, path_str(phys_net, path));
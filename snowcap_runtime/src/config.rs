@@ -18,49 +18,215 @@
 //! This module is responsible for parsing the config correctly
 
 use crate::physical_network::*;
+use snowcap::netsim::acl::{AccessList, AclDirection, AclEntry};
+use snowcap::netsim::bgp::{BgpDecisionConfig, RedistributionSource};
 use snowcap::netsim::config::{
-    Config,
-    ConfigExpr::{BgpRouteMap, BgpSession, IgpLinkWeight, StaticRoute},
+    Config, ConfigExpr,
+    ConfigExpr::{
+        AccessControl, BgpAggregate, BgpClusterId, BgpDecision, BgpNetwork, BgpRedistribute,
+        BgpRouteMap, BgpSession, IgpLinkArea, IgpLinkWeight, OspfStubArea, StaticRoute,
+    },
     ConfigModifier::{self, Insert, Remove, Update},
 };
-use snowcap::netsim::route_map::RouteMapDirection;
-use snowcap::netsim::{BgpSessionType, RouterId};
+use snowcap::netsim::route_map::{RouteMap, RouteMapDirection};
+use snowcap::netsim::{BgpSessionType, OspfArea, Prefix, RouterId};
+
+use std::collections::HashMap;
+use std::error::Error;
+
+/// Commands to execute on each router, generated from a single modifier.
+pub type RouterCommands = Vec<(RouterId, Vec<String>)>;
 
 /// Apply an entire configuration on the physical network. This funciton generates no commands to be
 /// executed. If this is needed, use [`parse_modifier`].
-pub fn apply_config(phys_net: &mut PhysicalNetwork, config: &Config) {
+pub fn apply_config(phys_net: &mut PhysicalNetwork, config: &Config) -> Result<(), Box<dyn Error>> {
     // parse configuration, and update the physical network
-    for expr in config.iter() {
-        parse_modifier(phys_net, &Insert(expr.clone()));
+    for expr in sorted_exprs(config) {
+        parse_modifier(phys_net, &Insert(expr.clone()))?;
     }
+    Ok(())
+}
+
+/// Returns all expressions of the configuration, sorted by their key. Applying them in this order
+/// always results in the same names of route-maps and the same order of all commands.
+pub(crate) fn sorted_exprs(config: &Config) -> Vec<&ConfigExpr> {
+    let mut exprs: Vec<&ConfigExpr> = config.iter().collect();
+    exprs.sort_by_key(|expr| expr.key());
+    exprs
+}
+
+/// Generate all commands, which configure the router from scratch. The commands must be executed
+/// in the configuration mode of `vtysh`.
+pub fn router_config(router: &PhysicalRouter) -> Vec<String> {
+    let mut cmds = Vec::new();
+
+    // set hostname
+    cmds.push(format!("hostname {}", router.name));
+
+    // configure loopback interface
+    cmds.push("interface lo".to_string());
+    cmds.push(format!("ip address {}/32", router.loopback_addr.addr));
+    cmds.push("exit".to_string());
+
+    // confgure ospf
+    if router.ifaces.iter().any(|i| i.cost.is_some()) {
+        cmds.push("router ospf 1".to_string());
+        cmds.push(format!("router-id {}", router.loopback_addr.addr));
+        cmds.push("redistribute connected".to_string());
+        for area in router.stub_areas.iter() {
+            cmds.push(format!("area {} stub", area));
+        }
+        cmds.push("exit".to_string());
+    }
+
+    // configure the access lists, before they are applied on the interfaces
+    for acl in &router.acls {
+        cmds.extend(acl.entries.iter().map(|e| format!("access-list {} {}", acl.name, e)));
+    }
+
+    // configure every interface
+    for iface in router.ifaces.iter().filter(|i| i.enabled) {
+        cmds.push(format!("interface {}", iface.gns_interface.short_name));
+        cmds.push(format!("ip address {}", iface.iface_addr));
+        if let Some(cost) = iface.cost.as_ref() {
+            cmds.push(format!("ip ospf 1 area {}", iface.area));
+            cmds.push(format!("ip ospf cost {}", cost));
+        }
+        for acl in router.acls.iter().filter(|acl| acl.neighbor == iface.neighbor) {
+            cmds.push(format!("ip access-group {} {}", acl.name, acl.direction));
+        }
+        cmds.push("exit".to_string());
+    }
+
+    // configure route maps
+    for rm in &router.route_maps {
+        cmds.extend(route_map_entry_cmds(rm));
+
+        // configure to allow per default
+        cmds.push(format!("route-map {} permit 65535", rm.name));
+        cmds.push("exit".to_string());
+    }
+
+    // configure the route maps of the redistributions
+    for rd in &router.redistributions {
+        cmds.extend(redistribution_route_map_cmds(rd));
+    }
+
+    // configure BGP
+    cmds.push(format!("router bgp {}", router.as_id.0));
+    cmds.push(format!("bgp router-id {}", router.loopback_addr.addr));
+    cmds.push("bgp log-neighbor-changes".to_string());
+    // configure the decision process such that it matches the simulation
+    cmds.push("bgp deterministic-med".to_string());
+    cmds.push("bgp bestpath compare-routerid".to_string());
+    if router.bgp_decision.always_compare_med {
+        cmds.push("bgp always-compare-med".to_string());
+    }
+    if router.bgp_decision.med_missing_as_worst {
+        cmds.push("bgp bestpath med missing-as-worst".to_string());
+    }
+    if let Some(cluster_id) = router.cluster_id.as_ref() {
+        cmds.push(format!("bgp cluster-id {}", cluster_id.addr));
+    }
+    cmds.push("bgp route-reflector allow-outbound-policy".to_string());
+    cmds.push("neighbor internal peer-group".to_string());
+    cmds.push("neighbor external peer-group".to_string());
+    for session in &router.bgp_sessions {
+        let n_addr = session.neighbor_addr.addr.as_str();
+        cmds.push(format!("neighbor {} remote-as {}", n_addr, session.neighbor_as_id.0));
+        cmds.push(format!(
+            "neighbor {} update-source {}",
+            n_addr,
+            if session.internal_session {
+                "lo"
+            } else {
+                router
+                    .ifaces
+                    .iter()
+                    .filter(|i| i.neighbor == session.neighbor)
+                    .map(|i| i.gns_interface.short_name.as_str())
+                    .next()
+                    .expect("No direct connection with the neighbor")
+            }
+        ));
+        cmds.push(format!(
+            "neighbor {} peer-group {}",
+            n_addr,
+            if session.internal_session { "internal" } else { "external" }
+        ));
+        if session.is_rr_client {
+            cmds.push(format!("neighbor {} route-reflector-client", n_addr));
+        }
+    }
+    // enable ipv4 communication
+    cmds.push("address-family ipv4".to_string());
+    if let Some(prefix) = router.advertise_route.as_ref() {
+        cmds.push(format!("network {}", prefix));
+    }
+    for prefix in &router.bgp_networks {
+        cmds.push(format!("network {}", prefix));
+    }
+    for rd in &router.redistributions {
+        cmds.push(format!("redistribute {} route-map {}", rd.source, rd.route_map));
+    }
+    for aggregate in &router.aggregates {
+        cmds.push(aggregate_cmd(aggregate));
+    }
+    for rm in &router.route_maps {
+        cmds.push(format!("neighbor internal route-map {} {}", rm.name, rm.direction));
+        cmds.push(format!("neighbor external route-map {} {}", rm.name, rm.direction));
+    }
+    // FRR uses all equally good paths by default
+    if !router.bgp_decision.multipath {
+        cmds.push("maximum-paths 1".to_string());
+        cmds.push("maximum-paths ibgp 1".to_string());
+    }
+    cmds.push("exit".to_string()); // exit address-family
+    cmds.push("exit".to_string()); // exit router bgp
+
+    // configure static routes
+    for sr in &router.static_routes {
+        cmds.push(format!("ip route {} {}", sr.addr, sr.next_hop));
+    }
+
+    cmds
 }
 
 /// Apply the modifier to the settings in physnet, and generate a set of commands that can be
-/// executed
+/// executed. An error is returned if the modifier cannot be translated into FRR commands.
 pub fn parse_modifier(
     phys_net: &mut PhysicalNetwork,
     modifier: &ConfigModifier,
-) -> Vec<(RouterId, Vec<String>)> {
-    match modifier {
+) -> Result<RouterCommands, Box<dyn Error>> {
+    parse_router_modifier(&mut phys_net.routers, &phys_net.prefix_router_lookup, modifier)
+}
+
+/// Apply the modifier to the routers, and generate a set of commands that can be executed. This
+/// function does not require a running network. `prefix_routers` maps each prefix to the origin
+/// router which advertises it.
+pub(crate) fn parse_router_modifier(
+    routers: &mut [PhysicalRouter],
+    prefix_routers: &HashMap<Prefix, RouterId>,
+    modifier: &ConfigModifier,
+) -> Result<RouterCommands, Box<dyn Error>> {
+    Ok(match modifier {
         // create a new bgp session!
         Insert(BgpSession { source, target, session_type }) => {
             // get the source and target address and as
             let (source_addr, target_addr) =
-                get_bgp_peering_addr(phys_net, *source, *target, *session_type);
-            let (source_as, target_as) = (
-                phys_net.routers[source.index()].as_id.clone(),
-                phys_net.routers[target.index()].as_id.clone(),
-            );
+                get_bgp_peering_addr(routers, *source, *target, *session_type)?;
+            let (source_as, target_as) =
+                (routers[source.index()].as_id.clone(), routers[target.index()].as_id.clone());
 
             // write into the datastructure
-            phys_net.routers[source.index()].bgp_sessions.push(BgpSessionInfo {
+            routers[source.index()].bgp_sessions.push(BgpSessionInfo {
                 neighbor: *target,
                 neighbor_addr: target_addr.clone(),
                 neighbor_as_id: target_as,
                 is_rr_client: *session_type == BgpSessionType::IBgpClient,
                 internal_session: session_type.is_ibgp(),
             });
-            phys_net.routers[target.index()].bgp_sessions.push(BgpSessionInfo {
+            routers[target.index()].bgp_sessions.push(BgpSessionInfo {
                 neighbor: *source,
                 neighbor_addr: source_addr.clone(),
                 neighbor_as_id: source_as,
@@ -72,8 +238,8 @@ pub fn parse_modifier(
                 ("lo".to_string(), "lo".to_string(), "internal")
             } else {
                 (
-                    get_interface_from_to(phys_net, *source, *target),
-                    get_interface_from_to(phys_net, *target, *source),
+                    get_interface_from_to(routers, *source, *target)?,
+                    get_interface_from_to(routers, *target, *source)?,
                     "external",
                 )
             };
@@ -112,41 +278,40 @@ pub fn parse_modifier(
 
         // enable the interface and set the cost if necessary, but only if the cost is not infinity.
         Insert(IgpLinkWeight { source, target, weight }) => {
-            let link_idx = get_iface_idx(phys_net, *source, *target);
-            let iface_name = get_interface_from_to(phys_net, *source, *target);
+            let link_idx = get_iface_idx(routers, *source, *target)?;
+            let iface_name = get_interface_from_to(routers, *source, *target)?;
 
             // check if the weight is set. If not, then consider the interface as disabled
             if weight.is_infinite() {
-                // assert that the interface was already disabled!
-                assert!(!phys_net.routers[source.index()].ifaces[link_idx].enabled);
+                // the interface must already be disabled
+                if routers[source.index()].ifaces[link_idx].enabled {
+                    return Err("Cannot disable an interface by inserting a link weight".into());
+                }
                 vec![]
             } else {
                 // enable the interface
-                phys_net.routers[source.index()].ifaces[link_idx].enabled = true;
+                routers[source.index()].ifaces[link_idx].enabled = true;
 
                 // get the address
-                let iface_addr =
-                    phys_net.routers[source.index()].ifaces[link_idx].iface_addr.clone();
+                let iface_addr = routers[source.index()].ifaces[link_idx].iface_addr.clone();
 
                 // check if both routers are internal, in which case we set the cost
-                if phys_net.routers[source.index()].is_internal
-                    && phys_net.routers[target.index()].is_internal
-                {
+                if routers[source.index()].is_internal && routers[target.index()].is_internal {
                     // set the cost
-                    phys_net.routers[source.index()].ifaces[link_idx].cost =
-                        Some(weight.round() as u32);
+                    routers[source.index()].ifaces[link_idx].cost = Some(weight.round() as u32);
+                    let area = routers[source.index()].ifaces[link_idx].area;
                     vec![(
                         *source,
                         vec![
                             format!("interface {}", iface_name),
                             format!("ip address {}", iface_addr),
-                            format!("ip ospf 1 area 0"),
+                            format!("ip ospf 1 area {}", area),
                             format!("ip ospf cost {}", weight.round() as u32),
                         ],
                     )]
                 } else {
                     // enable the interface wihtout setting the cost
-                    phys_net.routers[source.index()].ifaces[link_idx].cost = None;
+                    routers[source.index()].ifaces[link_idx].cost = None;
                     vec![(
                         *source,
                         vec![
@@ -161,23 +326,15 @@ pub fn parse_modifier(
         // Create a new route map
         Insert(BgpRouteMap { router, direction, map }) => {
             // create the new route map info and add it to the datastructure
-            let rm_name = get_route_map_name(phys_net, *router);
-            let rm =
-                RouteMapInfo::from_route_map(rm_name, *router, *direction, map, &phys_net.routers);
-            phys_net.routers[router.index()].route_maps.push(rm.clone());
+            let rm_name = get_route_map_name(routers, *router);
+            let rm = RouteMapInfo::from_route_map(rm_name, *router, *direction, map, routers)?;
+            routers[router.index()].route_maps.push(rm.clone());
 
-            // create the updates
-            let mut cmds = vec![format!("route-map {} {} {}", rm.name, rm.state, rm.order)];
-            for (key, value) in rm.match_statements {
-                cmds.push(format!("match {} {}", key, value));
-            }
-            for (key, value) in rm.set_statements {
-                cmds.push(format!("set {} {}", key, value));
-            }
-            cmds.push(format!("exit"));
+            // create the updates, starting with the community lists used by the route map
+            let mut cmds = route_map_entry_cmds(&rm);
 
             // also create the update for enabling them in the bgp configuration
-            cmds.push(format!("router bgp {}", phys_net.routers[router.index()].as_id.0));
+            cmds.push(format!("router bgp {}", routers[router.index()].as_id.0));
             cmds.push(format!("address-family ipv4"));
             cmds.push(format!("neighbor internal route-map {} {}", rm.name, rm.direction));
             cmds.push(format!("neighbor external route-map {} {}", rm.name, rm.direction));
@@ -189,15 +346,10 @@ pub fn parse_modifier(
 
         // insert the static route
         Insert(StaticRoute { router, prefix, target }) => {
-            let link_idx = get_iface_idx(phys_net, *router, *target);
-            let next_hop_addr =
-                phys_net.routers[router.index()].ifaces[link_idx].neighbor_addr.addr.clone();
-            let addr = phys_net.routers[phys_net.prefix_router_id(*prefix).index()]
-                .advertise_route
-                .as_ref()
-                .unwrap()
-                .clone();
-            phys_net.routers[router.index()]
+            let link_idx = get_iface_idx(routers, *router, *target)?;
+            let next_hop_addr = routers[router.index()].ifaces[link_idx].neighbor_addr.addr.clone();
+            let addr = get_prefix_addr(routers, prefix_routers, *prefix)?;
+            routers[router.index()]
                 .static_routes
                 .push(StaticRouteInfo { addr: addr.clone(), next_hop: next_hop_addr.clone() });
             vec![(*router, vec![format!("ip route {} {}", addr, next_hop_addr)])]
@@ -205,19 +357,19 @@ pub fn parse_modifier(
 
         // remove the existing bgp session!
         Remove(BgpSession { source, target, .. }) => {
-            let source_idx = phys_net.routers[source.index()]
+            let source_idx = routers[source.index()]
                 .bgp_sessions
                 .iter()
                 .position(|s| s.neighbor == *target)
-                .expect("Session does not already exist!");
-            let target_idx = phys_net.routers[target.index()]
+                .ok_or("Session does not exist")?;
+            let target_idx = routers[target.index()]
                 .bgp_sessions
                 .iter()
                 .position(|s| s.neighbor == *source)
-                .expect("Session does not already exist!");
+                .ok_or("Session does not exist")?;
 
-            let ss = phys_net.routers[source.index()].bgp_sessions.remove(source_idx);
-            let ts = phys_net.routers[target.index()].bgp_sessions.remove(target_idx);
+            let ss = routers[source.index()].bgp_sessions.remove(source_idx);
+            let ts = routers[target.index()].bgp_sessions.remove(target_idx);
 
             vec![
                 (
@@ -231,7 +383,7 @@ pub fn parse_modifier(
                     ],
                 ),
                 (
-                    *source,
+                    *target,
                     vec![
                         format!("router bgp {}", ss.neighbor_as_id.0),
                         format!(
@@ -245,15 +397,16 @@ pub fn parse_modifier(
 
         // remove the interface and the ospf cost, but only if the interface is enabled!
         Remove(IgpLinkWeight { source, target, .. }) => {
-            let link_idx = get_iface_idx(phys_net, *source, *target);
-            let iface_name = get_interface_from_to(phys_net, *source, *target);
+            let link_idx = get_iface_idx(routers, *source, *target)?;
+            let iface_name = get_interface_from_to(routers, *source, *target)?;
 
             // check if the weight is set. If not, then consider the interface as disabled
-            if phys_net.routers[source.index()].ifaces[link_idx].enabled {
-                let old_cost = phys_net.routers[source.index()].ifaces[link_idx].cost;
-                let old_addr = phys_net.routers[source.index()].ifaces[link_idx].iface_addr.clone();
-                phys_net.routers[source.index()].ifaces[link_idx].enabled = false;
-                phys_net.routers[source.index()].ifaces[link_idx].cost = None;
+            if routers[source.index()].ifaces[link_idx].enabled {
+                let old_cost = routers[source.index()].ifaces[link_idx].cost;
+                let old_addr = routers[source.index()].ifaces[link_idx].iface_addr.clone();
+                let area = routers[source.index()].ifaces[link_idx].area;
+                routers[source.index()].ifaces[link_idx].enabled = false;
+                routers[source.index()].ifaces[link_idx].cost = None;
 
                 if let Some(old_cost) = old_cost {
                     vec![(
@@ -261,7 +414,7 @@ pub fn parse_modifier(
                         vec![
                             format!("interface {}", iface_name),
                             format!("no ip ospf cost {}", old_cost),
-                            format!("no ip ospf 1 area 0"),
+                            format!("no ip ospf 1 area {}", area),
                             format!("no ip address {}", old_addr),
                         ],
                     )]
@@ -287,18 +440,18 @@ pub fn parse_modifier(
                 RouteMapDirection::Incoming => "in",
                 RouteMapDirection::Outgoing => "out",
             };
-            let rm_pos = phys_net.routers[router.index()]
+            let rm_pos = routers[router.index()]
                 .route_maps
                 .iter()
                 .position(|rm| rm.direction == direction && rm.order == map.order() as u32)
-                .expect("RouteMap does not exist!");
+                .ok_or("Route-map does not exist")?;
             // remove the route map
-            let rm = phys_net.routers[router.index()].route_maps.remove(rm_pos);
+            let rm = routers[router.index()].route_maps.remove(rm_pos);
 
             // create the updates
             let mut cmds = Vec::with_capacity(7);
             // first disable the route map in bgp
-            cmds.push(format!("router bgp {}", phys_net.routers[router.index()].as_id.0));
+            cmds.push(format!("router bgp {}", routers[router.index()].as_id.0));
             cmds.push(format!("address-family ipv4"));
             cmds.push(format!("no neighbor internal route-map {} {}", rm.name, rm.direction));
             cmds.push(format!("no neighbor external route-map {} {}", rm.name, rm.direction));
@@ -307,23 +460,21 @@ pub fn parse_modifier(
 
             // then, delete the route map
             cmds.push(format!("no route-map {} {} {}", rm.name, rm.state, rm.order));
+            cmds.extend(rm.community_lists.iter().map(|l| format!("no {}", l)));
 
             vec![(*router, cmds)]
         }
 
         // remove the static route of the prefix, no matter where it points.
         Remove(StaticRoute { router, prefix, .. }) => {
-            let addr = phys_net.routers[phys_net.prefix_router_id(*prefix).index()]
-                .advertise_route
-                .as_ref()
-                .unwrap();
+            let addr = get_prefix_addr(routers, prefix_routers, *prefix)?;
             // search this entry and remove it from the static routes
-            let pos = phys_net.routers[router.index()]
+            let pos = routers[router.index()]
                 .static_routes
                 .iter()
-                .position(|sr| sr.addr == *addr)
-                .expect("Static route to remove does not exist!");
-            let old_sr = phys_net.routers[router.index()].static_routes.remove(pos);
+                .position(|sr| sr.addr == addr)
+                .ok_or("Static route does not exist")?;
+            let old_sr = routers[router.index()].static_routes.remove(pos);
             vec![(*router, vec![format!("no ip route {} {}", old_sr.addr, old_sr.next_hop)])]
         }
 
@@ -331,26 +482,27 @@ pub fn parse_modifier(
         // check this here!
         Update { from: BgpSession { .. }, to: BgpSession { source, target, session_type } } => {
             // only internal sessions can be updated
-            assert!(phys_net.routers[source.index()].is_internal);
-            assert!(phys_net.routers[target.index()].is_internal);
+            if !(routers[source.index()].is_internal && routers[target.index()].is_internal) {
+                return Err("Only sessions between internal routers can be updated".into());
+            }
 
-            let source_idx = phys_net.routers[source.index()]
+            let source_idx = routers[source.index()]
                 .bgp_sessions
                 .iter()
                 .position(|s| s.neighbor == *target)
-                .expect("Session does not already exist!");
-            let target_idx = phys_net.routers[target.index()]
+                .ok_or("Session does not exist")?;
+            let target_idx = routers[target.index()]
                 .bgp_sessions
                 .iter()
                 .position(|s| s.neighbor == *source)
-                .expect("Session does not already exist!");
+                .ok_or("Session does not exist")?;
 
-            let ss = phys_net.routers[source.index()].bgp_sessions[source_idx].clone();
-            let ts = phys_net.routers[target.index()].bgp_sessions[target_idx].clone();
+            let ss = routers[source.index()].bgp_sessions[source_idx].clone();
+            let ts = routers[target.index()].bgp_sessions[target_idx].clone();
 
             if *session_type == BgpSessionType::IBgpClient {
                 // update from peer to client
-                phys_net.routers[source.index()].bgp_sessions[source_idx].is_rr_client = true;
+                routers[source.index()].bgp_sessions[source_idx].is_rr_client = true;
                 vec![(
                     *source,
                     vec![
@@ -359,7 +511,7 @@ pub fn parse_modifier(
                     ],
                 )]
             } else if ss.is_rr_client == true {
-                phys_net.routers[source.index()].bgp_sessions[source_idx].is_rr_client = false;
+                routers[source.index()].bgp_sessions[source_idx].is_rr_client = false;
                 vec![(
                     *source,
                     vec![
@@ -368,7 +520,7 @@ pub fn parse_modifier(
                     ],
                 )]
             } else {
-                phys_net.routers[target.index()].bgp_sessions[target_idx].is_rr_client = false;
+                routers[target.index()].bgp_sessions[target_idx].is_rr_client = false;
                 vec![(
                     *source,
                     vec![
@@ -382,34 +534,35 @@ pub fn parse_modifier(
         // Change the link weight. For this, source and target must be the same. If the link was
         // disabled previously, then just generate it!
         Update { from: IgpLinkWeight { source, target, .. }, to: IgpLinkWeight { weight, .. } } => {
-            let link_idx = get_iface_idx(phys_net, *source, *target);
+            let link_idx = get_iface_idx(routers, *source, *target)?;
 
             // check if the link is already enabled
-            if phys_net.routers[source.index()].ifaces[link_idx].enabled {
+            if routers[source.index()].ifaces[link_idx].enabled {
                 // if the new weight is infinite, then just remove the old weight
                 if weight.is_infinite() {
                     // just remove the old weight
-                    parse_modifier(
-                        phys_net,
+                    parse_router_modifier(
+                        routers,
+                        prefix_routers,
                         &Remove(IgpLinkWeight {
                             source: *source,
                             target: *target,
                             weight: *weight,
                         }),
-                    )
+                    )?
                 } else {
                     // ospf weight needs to change. But first, check if both routers are internal.
                     // If not, then this operation is not permitted
-                    if !(phys_net.routers[source.index()].is_internal
-                        && phys_net.routers[target.index()].is_internal)
+                    if !(routers[source.index()].is_internal && routers[target.index()].is_internal)
                     {
-                        panic!("Link weight can only be changed in between internal routers");
+                        return Err(
+                            "Link weight can only be changed between internal routers".into()
+                        );
                     }
 
                     // update the interface
-                    let iface_name = get_interface_from_to(phys_net, *source, *target);
-                    phys_net.routers[source.index()].ifaces[link_idx].cost =
-                        Some(weight.round() as u32);
+                    let iface_name = get_interface_from_to(routers, *source, *target)?;
+                    routers[source.index()].ifaces[link_idx].cost = Some(weight.round() as u32);
                     vec![(
                         *source,
                         vec![
@@ -421,10 +574,11 @@ pub fn parse_modifier(
             } else {
                 // link is not enabled! This is the same as inserting a new modifier with the new
                 // weight
-                parse_modifier(
-                    phys_net,
+                parse_router_modifier(
+                    routers,
+                    prefix_routers,
                     &Insert(IgpLinkWeight { source: *source, target: *target, weight: *weight }),
-                )
+                )?
             }
         }
         // router, direction and map.order() must be the same
@@ -434,13 +588,13 @@ pub fn parse_modifier(
                 RouteMapDirection::Incoming => "in",
                 RouteMapDirection::Outgoing => "out",
             };
-            let rm_pos = phys_net.routers[router.index()]
+            let rm_pos = routers[router.index()]
                 .route_maps
                 .iter()
                 .position(|rm| rm.direction == dir && rm.order == map.order() as u32)
-                .expect("RouteMap does not exist!");
+                .ok_or("Route-map does not exist")?;
             // remove the route map
-            let old_rm = phys_net.routers[router.index()].route_maps.remove(rm_pos);
+            let old_rm = routers[router.index()].route_maps.remove(rm_pos);
 
             // create the new route map with the same name as the old one.
             let new_rm = RouteMapInfo::from_route_map(
@@ -448,14 +602,19 @@ pub fn parse_modifier(
                 *router,
                 *direction,
                 map,
-                &phys_net.routers,
-            );
+                routers,
+            )?;
             // add the new rm to the datastructure
-            phys_net.routers[router.index()].route_maps.push(new_rm.clone());
+            routers[router.index()].route_maps.push(new_rm.clone());
 
-            // create the updates
-            let mut cmds =
-                vec![format!("route-map {} {} {}", new_rm.name, new_rm.state, new_rm.order)];
+            // create the updates, starting with the new community lists
+            let mut cmds: Vec<String> = new_rm
+                .community_lists
+                .iter()
+                .filter(|l| !old_rm.community_lists.contains(l))
+                .cloned()
+                .collect();
+            cmds.push(format!("route-map {} {} {}", new_rm.name, new_rm.state, new_rm.order));
 
             // modify the match statements
 
@@ -485,7 +644,7 @@ pub fn parse_modifier(
 
             // update existing entries and add new entries
             for (key, new_val) in new_rm.set_statements.iter() {
-                if let Some(old_val) = old_rm.set_statements.get(key) {
+                if let Some((_, old_val)) = old_rm.set_statements.iter().find(|(k, _)| k == key) {
                     // either unmodified, or it changes
                     if new_val == old_val {
                         // nothing to do
@@ -500,33 +659,38 @@ pub fn parse_modifier(
 
             // delete old entries
             for (key, old_val) in old_rm.set_statements.iter() {
-                if !new_rm.set_statements.contains_key(key) {
+                if !new_rm.set_statements.iter().any(|(k, _)| k == key) {
                     cmds.push(format!("no set {} {}", key, old_val));
                 }
             }
 
+            // delete the community lists, which are no longer used
+            cmds.extend(
+                old_rm
+                    .community_lists
+                    .iter()
+                    .filter(|l| !new_rm.community_lists.contains(l))
+                    .map(|l| format!("no {}", l)),
+            );
+
             vec![(*router, cmds)]
         }
 
         // Change the static route to a different location. For this, the router and the prefix must
         // be the same
         Update { from: StaticRoute { router, prefix, .. }, to: StaticRoute { target, .. } } => {
-            let link_idx = get_iface_idx(phys_net, *router, *target);
+            let link_idx = get_iface_idx(routers, *router, *target)?;
             let new_next_hop_addr =
-                phys_net.routers[router.index()].ifaces[link_idx].neighbor_addr.addr.clone();
-            let addr = phys_net.routers[phys_net.prefix_router_id(*prefix).index()]
-                .advertise_route
-                .as_ref()
-                .unwrap()
-                .clone();
+                routers[router.index()].ifaces[link_idx].neighbor_addr.addr.clone();
+            let addr = get_prefix_addr(routers, prefix_routers, *prefix)?;
             // search this entry and remove it from the static routes
-            let pos = phys_net.routers[router.index()]
+            let pos = routers[router.index()]
                 .static_routes
                 .iter()
                 .position(|sr| sr.addr == addr)
-                .expect("Static route to remove does not exist!");
-            let old_sr = phys_net.routers[router.index()].static_routes.remove(pos);
-            phys_net.routers[router.index()]
+                .ok_or("Static route does not exist")?;
+            let old_sr = routers[router.index()].static_routes.remove(pos);
+            routers[router.index()]
                 .static_routes
                 .push(StaticRouteInfo { addr: addr.clone(), next_hop: new_next_hop_addr.clone() });
             vec![(
@@ -537,53 +701,518 @@ pub fn parse_modifier(
                 ],
             )]
         }
-        modifier => panic!("Invalid Modifier: {:?}", modifier),
+        // move the link into the area. Link areas are configured on both ends at once.
+        Insert(IgpLinkArea { source, target, area })
+        | Update { to: IgpLinkArea { source, target, area }, .. } => {
+            set_link_area(routers, *source, *target, *area)?
+        }
+        Remove(IgpLinkArea { source, target, .. }) => {
+            set_link_area(routers, *source, *target, OspfArea::BACKBONE)?
+        }
+
+        // stub areas are configured on all internal routers
+        Insert(OspfStubArea { area }) => routers
+            .iter_mut()
+            .filter(|r| r.is_internal)
+            .map(|r| {
+                r.stub_areas.push(*area);
+                (r.router_id, vec![format!("router ospf 1"), format!("area {} stub", area)])
+            })
+            .collect(),
+        Remove(OspfStubArea { area }) => routers
+            .iter_mut()
+            .filter(|r| r.is_internal)
+            .map(|r| {
+                r.stub_areas.retain(|a| a != area);
+                (r.router_id, vec![format!("router ospf 1"), format!("no area {} stub", area)])
+            })
+            .collect(),
+        // a stub area has no other properties, updating it does not change anything
+        Update { from: OspfStubArea { .. }, to: OspfStubArea { .. } } => Vec::new(),
+
+        // configure the decision process of BGP
+        Insert(BgpDecision { router, config })
+        | Update { to: BgpDecision { router, config }, .. } => {
+            set_bgp_decision(routers, *router, *config)
+        }
+        Remove(BgpDecision { router, .. }) => {
+            set_bgp_decision(routers, *router, BgpDecisionConfig::default())
+        }
+
+        // configure the cluster ID, given by the loopback address of the router
+        Insert(BgpClusterId { router, cluster_id })
+        | Update { to: BgpClusterId { router, cluster_id }, .. } => {
+            let addr = routers
+                .get(cluster_id.index())
+                .ok_or("Cluster ID is not a router")?
+                .loopback_addr
+                .clone();
+            let r = &mut routers[router.index()];
+            let cmds = vec![
+                format!("router bgp {}", r.as_id.0),
+                format!("bgp cluster-id {}", addr.addr),
+                String::from("exit"),
+            ];
+            r.cluster_id = Some(addr);
+            vec![(*router, cmds)]
+        }
+        Remove(BgpClusterId { router, .. }) => {
+            let r = &mut routers[router.index()];
+            let cmds = vec![
+                format!("router bgp {}", r.as_id.0),
+                String::from("no bgp cluster-id"),
+                String::from("exit"),
+            ];
+            r.cluster_id = None;
+            vec![(*router, cmds)]
+        }
+
+        // announce a prefix with a network statement
+        Insert(BgpNetwork { router, prefix }) => {
+            let addr = get_network_addr(*prefix)?;
+            let r = &mut routers[router.index()];
+            r.bgp_networks.push(addr.clone());
+            vec![(*router, address_family_cmds(r, vec![format!("network {}", addr)]))]
+        }
+        Remove(BgpNetwork { router, prefix }) => {
+            let addr = get_network_addr(*prefix)?;
+            let r = &mut routers[router.index()];
+            let pos = r
+                .bgp_networks
+                .iter()
+                .position(|a| *a == addr)
+                .ok_or("Network statement does not exist")?;
+            r.bgp_networks.remove(pos);
+            vec![(*router, address_family_cmds(r, vec![format!("no network {}", addr)]))]
+        }
+        // a network statement has no other properties, updating it does not change anything
+        Update { from: BgpNetwork { .. }, to: BgpNetwork { .. } } => Vec::new(),
+
+        // redistribute routes through a new route map
+        Insert(BgpRedistribute { router, source, route_maps }) => {
+            let name = get_redistribution_name(routers, *router, *source, 0);
+            let rd = get_redistribution(routers, *router, *source, route_maps, name)?;
+            let r = &mut routers[router.index()];
+            let mut cmds = redistribution_route_map_cmds(&rd);
+            cmds.extend(address_family_cmds(
+                r,
+                vec![format!("redistribute {} route-map {}", rd.source, rd.route_map)],
+            ));
+            r.redistributions.push(rd);
+            vec![(*router, cmds)]
+        }
+        Remove(BgpRedistribute { router, source, .. }) => {
+            let source = get_redistribution_source(*source);
+            let r = &mut routers[router.index()];
+            let pos = r
+                .redistributions
+                .iter()
+                .position(|rd| rd.source == source)
+                .ok_or("Redistribution does not exist")?;
+            let rd = r.redistributions.remove(pos);
+            // first stop the redistribution, then delete the route map
+            let mut cmds = address_family_cmds(r, vec![format!("no redistribute {}", rd.source)]);
+            cmds.push(format!("no route-map {}", rd.route_map));
+            cmds.extend(
+                rd.route_maps
+                    .iter()
+                    .flat_map(|rm| rm.community_lists.iter())
+                    .map(|l| format!("no {}", l)),
+            );
+            vec![(*router, cmds)]
+        }
+        // Create the new route map with a different name, and switch the redistribution to it in a
+        // single command. This way, the routes are never redistributed through a partial route map.
+        Update {
+            from: BgpRedistribute { router, source, .. },
+            to: BgpRedistribute { route_maps, .. },
+        } => {
+            let src = get_redistribution_source(*source);
+            let pos = routers[router.index()]
+                .redistributions
+                .iter()
+                .position(|rd| rd.source == src)
+                .ok_or("Redistribution does not exist")?;
+            let version = if routers[router.index()].redistributions[pos].route_map.ends_with("_0")
+            {
+                1
+            } else {
+                0
+            };
+            let name = get_redistribution_name(routers, *router, *source, version);
+            let rd = get_redistribution(routers, *router, *source, route_maps, name)?;
+            let r = &mut routers[router.index()];
+            let mut cmds = redistribution_route_map_cmds(&rd);
+            cmds.extend(address_family_cmds(
+                r,
+                vec![format!("redistribute {} route-map {}", rd.source, rd.route_map)],
+            ));
+            let old_rd = std::mem::replace(&mut r.redistributions[pos], rd);
+            cmds.push(format!("no route-map {}", old_rd.route_map));
+            cmds.extend(
+                old_rd
+                    .route_maps
+                    .iter()
+                    .flat_map(|rm| rm.community_lists.iter())
+                    .map(|l| format!("no {}", l)),
+            );
+            vec![(*router, cmds)]
+        }
+
+        // create the aggregate. Configuring an existing aggregate again replaces its options.
+        Insert(BgpAggregate { router, prefix, summary_only, as_set })
+        | Update { to: BgpAggregate { router, prefix, summary_only, as_set }, .. } => {
+            let addr = get_network_addr(*prefix)?;
+            let aggregate = AggregateInfo { addr, summary_only: *summary_only, as_set: *as_set };
+            let r = &mut routers[router.index()];
+            let cmds = address_family_cmds(r, vec![aggregate_cmd(&aggregate)]);
+            match r.aggregates.iter_mut().find(|a| a.addr == aggregate.addr) {
+                Some(old) => *old = aggregate,
+                None => r.aggregates.push(aggregate),
+            }
+            vec![(*router, cmds)]
+        }
+        Remove(BgpAggregate { router, prefix, .. }) => {
+            let addr = get_network_addr(*prefix)?;
+            let r = &mut routers[router.index()];
+            let pos = r
+                .aggregates
+                .iter()
+                .position(|a| a.addr == addr)
+                .ok_or("Aggregate does not exist")?;
+            r.aggregates.remove(pos);
+            vec![(*router, address_family_cmds(r, vec![format!("no aggregate-address {}", addr)]))]
+        }
+
+        // create the access list, and apply it on the interface
+        Insert(AccessControl { router, interface, direction, acl }) => {
+            let iface_name = get_interface_from_to(routers, *router, *interface)?;
+            let acl = get_acl(routers, *router, *interface, *direction, acl, 0)?;
+            let mut cmds: Vec<String> =
+                acl.entries.iter().map(|e| format!("access-list {} {}", acl.name, e)).collect();
+            cmds.push(format!("interface {}", iface_name));
+            cmds.push(format!("ip access-group {} {}", acl.name, acl.direction));
+            cmds.push("exit".to_string());
+            routers[router.index()].acls.push(acl);
+            vec![(*router, cmds)]
+        }
+        Remove(AccessControl { router, interface, direction, .. }) => {
+            let iface_name = get_interface_from_to(routers, *router, *interface)?;
+            let pos = get_acl_pos(routers, *router, *interface, *direction)?;
+            let acl = routers[router.index()].acls.remove(pos);
+            vec![(
+                *router,
+                vec![
+                    format!("interface {}", iface_name),
+                    format!("no ip access-group {} {}", acl.name, acl.direction),
+                    "exit".to_string(),
+                    format!("no access-list {}", acl.name),
+                ],
+            )]
+        }
+        // Create the new access list with a different name, and apply it on the interface before
+        // the old one is deleted. This way, the packets are never filtered by a partial list.
+        Update {
+            from: AccessControl { router, interface, direction, .. },
+            to: AccessControl { acl, .. },
+        } => {
+            let iface_name = get_interface_from_to(routers, *router, *interface)?;
+            let pos = get_acl_pos(routers, *router, *interface, *direction)?;
+            let version =
+                if routers[router.index()].acls[pos].name.ends_with("_0") { 1 } else { 0 };
+            let acl = get_acl(routers, *router, *interface, *direction, acl, version)?;
+            let mut cmds: Vec<String> =
+                acl.entries.iter().map(|e| format!("access-list {} {}", acl.name, e)).collect();
+            cmds.push(format!("interface {}", iface_name));
+            cmds.push(format!("ip access-group {} {}", acl.name, acl.direction));
+            cmds.push("exit".to_string());
+            let old_acl = std::mem::replace(&mut routers[router.index()].acls[pos], acl);
+            cmds.push(format!("no access-list {}", old_acl.name));
+            vec![(*router, cmds)]
+        }
+
+        modifier => return Err(format!("Cannot export the modifier {:?}", modifier).into()),
+    })
+}
+
+/// Change the configuration of the BGP decision process, and generate the commands for all options
+/// that have changed. FRR uses all equally good paths, unless `maximum-paths` is set to 1.
+fn set_bgp_decision(
+    routers: &mut [PhysicalRouter],
+    router: RouterId,
+    config: BgpDecisionConfig,
+) -> RouterCommands {
+    let r = &mut routers[router.index()];
+    let old = std::mem::replace(&mut r.bgp_decision, config);
+    let no = |enabled: bool| if enabled { "" } else { "no " };
+    let mut cmds = vec![format!("router bgp {}", r.as_id.0)];
+    if old.always_compare_med != config.always_compare_med {
+        cmds.push(format!("{}bgp always-compare-med", no(config.always_compare_med)));
+    }
+    if old.med_missing_as_worst != config.med_missing_as_worst {
+        cmds.push(format!("{}bgp bestpath med missing-as-worst", no(config.med_missing_as_worst)));
+    }
+    if old.multipath != config.multipath {
+        cmds.push(String::from("address-family ipv4"));
+        cmds.push(format!("{}maximum-paths 1", no(!config.multipath)));
+        cmds.push(format!("{}maximum-paths ibgp 1", no(!config.multipath)));
+        cmds.push(String::from("exit"));
+    }
+    cmds.push(String::from("exit"));
+    vec![(router, cmds)]
+}
+
+/// Change the OSPF area of the link on both ends. Commands are only generated for interfaces on
+/// which OSPF is enabled. FRR requires the old area to be removed before the new one is set.
+fn set_link_area(
+    routers: &mut [PhysicalRouter],
+    source: RouterId,
+    target: RouterId,
+    area: OspfArea,
+) -> Result<RouterCommands, Box<dyn Error>> {
+    let mut result = Vec::new();
+    for (a, b) in [(source, target), (target, source)].iter().copied() {
+        let iface_name = get_interface_from_to(routers, a, b)?;
+        let link_idx = get_iface_idx(routers, a, b)?;
+        let iface = &mut routers[a.index()].ifaces[link_idx];
+        let old_area = std::mem::replace(&mut iface.area, area);
+        if iface.enabled && iface.cost.is_some() && old_area != area {
+            result.push((
+                a,
+                vec![
+                    format!("interface {}", iface_name),
+                    format!("no ip ospf 1 area {}", old_area),
+                    format!("ip ospf 1 area {}", area),
+                ],
+            ));
+        }
     }
+    Ok(result)
 }
 
 fn get_interface_from_to(
-    phys_net: &mut PhysicalNetwork,
+    routers: &[PhysicalRouter],
     source: RouterId,
     target: RouterId,
-) -> String {
-    let iface_idx = get_iface_idx(phys_net, source, target);
-    phys_net.routers[source.index()].ifaces[iface_idx].gns_interface.name.clone()
+) -> Result<String, Box<dyn Error>> {
+    let iface_idx = get_iface_idx(routers, source, target)?;
+    Ok(routers[source.index()].ifaces[iface_idx].gns_interface.name.clone())
 }
 
-fn get_iface_idx(phys_net: &mut PhysicalNetwork, source: RouterId, target: RouterId) -> usize {
-    phys_net.routers[source.index()]
-        .ifaces
-        .iter()
-        .position(|x| x.neighbor == target)
-        .expect("Link does not exist")
+fn get_iface_idx(
+    routers: &[PhysicalRouter],
+    source: RouterId,
+    target: RouterId,
+) -> Result<usize, Box<dyn Error>> {
+    routers
+        .get(source.index())
+        .and_then(|r| r.ifaces.iter().position(|x| x.neighbor == target))
+        .ok_or_else(|| format!("Link {:?} -> {:?} does not exist", source, target).into())
 }
 
 fn get_bgp_peering_addr(
-    phys_net: &mut PhysicalNetwork,
+    routers: &[PhysicalRouter],
     source: RouterId,
     target: RouterId,
     session_type: BgpSessionType,
-) -> (IpAddr, IpAddr) {
+) -> Result<(IpAddr, IpAddr), Box<dyn Error>> {
     if session_type.is_ebgp() {
-        phys_net.routers[source.index()]
-            .ifaces
-            .iter()
-            .filter(|i| i.neighbor == target)
-            .map(|i| (i.iface_addr.clone(), i.neighbor_addr.clone()))
-            .next()
-            .expect("eBGP peers must be directly connected")
+        let idx = get_iface_idx(routers, source, target)
+            .map_err(|_| "eBGP peers must be directly connected")?;
+        let iface = &routers[source.index()].ifaces[idx];
+        Ok((iface.iface_addr.clone(), iface.neighbor_addr.clone()))
     } else {
-        (
-            phys_net.routers[source.index()].loopback_addr.clone(),
-            phys_net.routers[target.index()].loopback_addr.clone(),
-        )
+        match (routers.get(source.index()), routers.get(target.index())) {
+            (Some(s), Some(t)) => Ok((s.loopback_addr.clone(), t.loopback_addr.clone())),
+            _ => Err("BGP peers must be routers of the network".into()),
+        }
+    }
+}
+
+/// Returns the address of the prefix, as advertised by its origin router.
+fn get_prefix_addr(
+    routers: &[PhysicalRouter],
+    prefix_routers: &HashMap<Prefix, RouterId>,
+    prefix: Prefix,
+) -> Result<IpAddr, Box<dyn Error>> {
+    prefix_routers
+        .get(&prefix)
+        .and_then(|r| routers[r.index()].advertise_route.clone())
+        .ok_or_else(|| format!("Prefix {} is not advertised by any router", prefix).into())
+}
+
+fn get_route_map_name(routers: &[PhysicalRouter], router: RouterId) -> String {
+    format!("{}_RM_{}", routers[router.index()].name, routers[router.index()].route_maps.len())
+}
+
+/// Generate the commands for a single route map entry, including the community lists it uses.
+fn route_map_entry_cmds(rm: &RouteMapInfo) -> Vec<String> {
+    let mut cmds = rm.community_lists.clone();
+    cmds.push(format!("route-map {} {} {}", rm.name, rm.state, rm.order));
+    // sort the match statements, such that the configuration is always the same. The set
+    // statements keep the order of the actions.
+    let mut match_statements: Vec<_> = rm.match_statements.iter().collect();
+    match_statements.sort();
+    for (key, value) in match_statements {
+        cmds.push(format!("match {} {}", key, value));
+    }
+    for (key, value) in rm.set_statements.iter() {
+        cmds.push(format!("set {} {}", key, value));
+    }
+    cmds.push("exit".to_string());
+    cmds
+}
+
+/// Wrap the commands, such that they are executed in the ipv4 address family of BGP.
+fn address_family_cmds(router: &PhysicalRouter, cmds: Vec<String>) -> Vec<String> {
+    let mut result = vec![format!("router bgp {}", router.as_id.0), "address-family ipv4".into()];
+    result.extend(cmds);
+    result.push("exit".to_string()); // exit address-family
+    result.push("exit".to_string()); // exit router bgp
+    result
+}
+
+/// Returns the address of a prefix that is announced or aggregated by an internal router. Only IPv4
+/// is supported.
+fn get_network_addr(prefix: Prefix) -> Result<IpAddr, Box<dyn Error>> {
+    if !prefix.is_ipv4() {
+        return Err(format!("Cannot announce the IPv6 prefix {}", prefix).into());
+    }
+    Ok(IpAddr::new(prefix.addr().to_string(), prefix.prefix_len() as u32))
+}
+
+/// Build the access list applied on the interface of `router` towards `neighbor`. FRR access lists
+/// only match on the source and destination address, and deny all packets that match no entry.
+fn get_acl(
+    routers: &[PhysicalRouter],
+    router: RouterId,
+    neighbor: RouterId,
+    direction: AclDirection,
+    acl: &AccessList,
+    version: usize,
+) -> Result<AclInfo, Box<dyn Error>> {
+    let direction = match direction {
+        AclDirection::Incoming => "in",
+        AclDirection::Outgoing => "out",
+    };
+    let name = format!(
+        "{}_ACL_{}_{}_{}",
+        routers[router.index()].name,
+        routers[neighbor.index()].name,
+        direction,
+        version
+    );
+    let entries = acl.entries().iter().map(get_acl_entry).collect::<Result<_, _>>()?;
+    Ok(AclInfo { name, neighbor, direction, entries })
+}
+
+fn get_acl_entry(entry: &AclEntry) -> Result<String, Box<dyn Error>> {
+    if entry.protocol().is_some() || entry.src_port().is_some() || entry.dst_port().is_some() {
+        return Err(format!("ACL entry {} cannot match on the protocol or ports", entry).into());
+    }
+    Ok(format!(
+        "seq {} {} ip {} {}",
+        entry.order(),
+        entry.action(),
+        get_acl_addr(entry.src())?,
+        get_acl_addr(entry.dst())?
+    ))
+}
+
+/// Returns the address and the wildcard mask of the prefix, as used in an access list entry.
+fn get_acl_addr(prefix: Option<Prefix>) -> Result<String, Box<dyn Error>> {
+    let prefix = match prefix {
+        Some(prefix) => prefix,
+        None => return Ok("any".to_string()),
+    };
+    match prefix.addr() {
+        std::net::IpAddr::V4(addr) if prefix.prefix_len() == 32 => Ok(format!("host {}", addr)),
+        std::net::IpAddr::V4(addr) => {
+            let wildcard = std::net::Ipv4Addr::from(u32::MAX >> prefix.prefix_len());
+            Ok(format!("{} {}", addr, wildcard))
+        }
+        std::net::IpAddr::V6(_) => {
+            Err(format!("ACL entry cannot match on the IPv6 prefix {}", prefix).into())
+        }
     }
 }
 
-fn get_route_map_name(phys_net: &mut PhysicalNetwork, router: RouterId) -> String {
+fn get_acl_pos(
+    routers: &[PhysicalRouter],
+    router: RouterId,
+    neighbor: RouterId,
+    direction: AclDirection,
+) -> Result<usize, Box<dyn Error>> {
+    let direction = match direction {
+        AclDirection::Incoming => "in",
+        AclDirection::Outgoing => "out",
+    };
+    Ok(routers[router.index()]
+        .acls
+        .iter()
+        .position(|acl| acl.neighbor == neighbor && acl.direction == direction)
+        .ok_or("Access list does not exist")?)
+}
+
+fn aggregate_cmd(aggregate: &AggregateInfo) -> String {
     format!(
-        "{}_RM_{}",
-        &phys_net.routers[router.index()].name,
-        phys_net.routers[router.index()].route_maps.len()
+        "aggregate-address {}{}{}",
+        aggregate.addr,
+        if aggregate.as_set { " as-set" } else { "" },
+        if aggregate.summary_only { " summary-only" } else { "" }
     )
 }
+
+fn get_redistribution_source(source: RedistributionSource) -> &'static str {
+    match source {
+        RedistributionSource::Static => "static",
+        RedistributionSource::Connected => "connected",
+    }
+}
+
+fn get_redistribution_name(
+    routers: &[PhysicalRouter],
+    router: RouterId,
+    source: RedistributionSource,
+    version: usize,
+) -> String {
+    format!("{}_RD_{}_{}", routers[router.index()].name, source, version)
+}
+
+/// Build the redistribution of `source` into BGP. All route maps become entries of a single route
+/// map named `name`, since FRR only allows one route map per redistribution.
+fn get_redistribution(
+    routers: &[PhysicalRouter],
+    router: RouterId,
+    source: RedistributionSource,
+    route_maps: &[RouteMap],
+    name: String,
+) -> Result<RedistributionInfo, Box<dyn Error>> {
+    let mut maps: Vec<&RouteMap> = route_maps.iter().collect();
+    maps.sort_by_key(|map| map.order());
+    let route_maps = maps
+        .into_iter()
+        .map(|map| {
+            // every entry gets its own community lists, but all entries share the same name.
+            let entry_name = format!("{}_{}", name, map.order());
+            let direction = RouteMapDirection::Outgoing;
+            let mut rm = RouteMapInfo::from_route_map(entry_name, router, direction, map, routers)?;
+            rm.name = name.clone();
+            Ok(rm)
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+    Ok(RedistributionInfo {
+        source: get_redistribution_source(source),
+        route_map: name,
+        route_maps,
+    })
+}
+
+/// Generate the commands for all entries of the route map of a redistribution. Routes that are not
+/// matched by any entry are redistributed unchanged.
+fn redistribution_route_map_cmds(rd: &RedistributionInfo) -> Vec<String> {
+    let mut cmds: Vec<String> = rd.route_maps.iter().flat_map(route_map_entry_cmds).collect();
+    cmds.push(format!("route-map {} permit 65535", rd.route_map));
+    cmds.push("exit".to_string());
+    cmds
+}
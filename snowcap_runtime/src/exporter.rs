@@ -0,0 +1,904 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! # Offline FRR Exporter
+//!
+//! This module generates the FRRouting configuration of every router, without the need of GNS3 or
+//! a telnet connection. It uses the same addressing scheme as
+//! [`PhysicalNetwork`](crate::physical_network::PhysicalNetwork), and the same commands as
+//! [`parse_modifier`](crate::config::parse_modifier). Use [`FrrExporter`] to generate the
+//! configuration of a network step by step, or [`export_migration`] to write all configuration
+//! files and all reconfiguration commands into a directory.
+//!
+//! The generated directory has the following structure:
+//!
+//! ```text
+//! <dir>/initial/<router>.conf   configuration of each router before the migration
+//! <dir>/step_<i>/<router>.vtysh commands of step i (starting at 1) for each affected router
+//! <dir>/final/<router>.conf     configuration of each router after the migration
+//! ```
+//!
+//! Configuration files contain the commands of the configuration mode of `vtysh`, in the same order
+//! in which they are applied on a new router. Command snippets can be passed to `vtysh` directly,
+//! since they enter and leave the configuration mode.
+
+use crate::config::{parse_router_modifier, router_config, sorted_exprs, RouterCommands};
+use crate::physical_network::{BgpSessionInfo, IfaceInfo, IpAddr, PhysicalNetwork, PhysicalRouter};
+use gns3::{GNS3Interface, GNS3Node, GNS3NodeStatus};
+use snowcap::netsim::bgp::BgpDecisionConfig;
+use snowcap::netsim::config::{Config, ConfigModifier};
+use snowcap::netsim::{AsId, Network, NetworkDevice, OspfArea, Prefix, RouterId};
+
+use log::*;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// Generates the FRR configuration of all routers in the network, including one origin router for
+/// every prefix. The state of all routers is kept up to date while applying modifiers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrrExporter {
+    routers: Vec<PhysicalRouter>,
+    prefix_router_lookup: HashMap<Prefix, RouterId>,
+}
+
+impl FrrExporter {
+    /// Create all routers, interfaces and origin routers of the network, and apply the
+    /// configuration. The topology and the advertised routes are taken from `net`, while its
+    /// configuration is ignored. The expressions are applied in the order of their keys, such that
+    /// the result is always the same. This function returns an error if a prefix cannot be
+    /// originated with its own address (see [`PhysicalNetwork`]), or if the configuration cannot
+    /// be expressed in FRR.
+    pub fn new(net: &Network, config: &Config) -> Result<Self, Box<dyn Error>> {
+        let mut exporter = Self { routers: Vec::new(), prefix_router_lookup: HashMap::new() };
+        exporter.create_routers(net);
+        exporter.create_origin_routers(net)?;
+        exporter.create_all_links(net);
+        exporter.create_links_to_origin(net)?;
+        for expr in sorted_exprs(config) {
+            exporter.apply_modifier(&ConfigModifier::Insert(expr.clone()))?;
+        }
+        Ok(exporter)
+    }
+
+    /// Returns the state of all routers, including the origin routers.
+    pub fn routers(&self) -> &[PhysicalRouter] {
+        &self.routers
+    }
+
+    /// Returns the name of a router
+    pub fn router_name(&self, router: RouterId) -> &str {
+        self.routers[router.index()].name.as_str()
+    }
+
+    /// Generate the entire configuration of every router. The result contains the name of the
+    /// router and its configuration file.
+    pub fn router_configs(&self) -> Vec<(String, String)> {
+        self.routers.iter().map(|r| (r.name.clone(), render_config(&router_config(r)))).collect()
+    }
+
+    /// Apply the modifier, and return the commands to execute on each affected router. Every
+    /// router appears at most once, in the order in which it is first reconfigured. An error is
+    /// returned if the modifier cannot be expressed in FRR.
+    pub fn apply_modifier(
+        &mut self,
+        modifier: &ConfigModifier,
+    ) -> Result<RouterCommands, Box<dyn Error>> {
+        let mut result: RouterCommands = Vec::new();
+        for (router, cmds) in
+            parse_router_modifier(&mut self.routers, &self.prefix_router_lookup, modifier)?
+        {
+            match result.iter_mut().find(|(r, _)| *r == router) {
+                Some((_, old_cmds)) => old_cmds.extend(cmds),
+                None => result.push((router, cmds)),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Write the configuration file of every router (named `<router>.conf`) into the directory.
+    /// The directory is created if it does not yet exist.
+    pub fn write_configs(&self, dir: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(dir.as_ref())?;
+        for (name, config) in self.router_configs() {
+            fs::write(dir.as_ref().join(format!("{}.conf", name)), config)?;
+        }
+        Ok(())
+    }
+
+    /// Apply the modifier, and write the commands for every affected router (named
+    /// `<router>.vtysh`) into the directory. The directory is created if it does not yet exist.
+    pub fn write_modifier(
+        &mut self,
+        modifier: &ConfigModifier,
+        dir: impl AsRef<Path>,
+    ) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(dir.as_ref())?;
+        for (router, cmds) in self.apply_modifier(modifier)? {
+            let filename = dir.as_ref().join(format!("{}.vtysh", self.router_name(router)));
+            fs::write(filename, render_snippet(&cmds))?;
+        }
+        Ok(())
+    }
+
+    /// Create all internal and external routers
+    fn create_routers(&mut self, net: &Network) {
+        for i in 0..net.num_devices() {
+            let router_id: RouterId = (i as u32).into();
+            match net.get_device(router_id) {
+                NetworkDevice::InternalRouter(r) => self.routers.push(PhysicalRouter {
+                    stub_areas: Vec::new(),
+                    ..new_router(
+                        router_id,
+                        r.name(),
+                        r.as_id(),
+                        IpAddr::new(format!("10.0.{}.1", router_id.index()), 24),
+                        IpAddr::new("10.0.0.0", 8),
+                        true,
+                    )
+                }),
+                NetworkDevice::ExternalRouter(r) => self.routers.push(new_router(
+                    router_id,
+                    r.name(),
+                    r.as_id(),
+                    IpAddr::new(format!("{}.0.0.1", router_id.index() + 100), 24),
+                    IpAddr::new(format!("{}.0.0.0", router_id.index() + 100), 8),
+                    false,
+                )),
+                _ => unreachable!("Could not find device!"),
+            }
+        }
+    }
+
+    /// Create all routers that originate a specific prefix
+    fn create_origin_routers(&mut self, net: &Network) -> Result<(), Box<dyn Error>> {
+        let loopbacks = PhysicalNetwork::origin_loopbacks(net)?;
+        for (prefix_idx, prefix) in PhysicalNetwork::sorted_prefixes(net).iter().enumerate() {
+            // get the as id of the origin, which must be the same for all advertisements
+            let as_id = match PhysicalNetwork::get_external_routers_with_prefix(net, *prefix)
+                .iter()
+                .flat_map(|r| r.get_advertised_routes().iter())
+                .find(|r| r.prefix == *prefix)
+                .and_then(|r| r.as_path.last())
+            {
+                Some(as_id) => *as_id,
+                None => {
+                    warn!("No external router actually advertises a prefix!");
+                    continue;
+                }
+            };
+
+            let router_id: RouterId = (self.routers.len() as u32).into();
+            self.routers.push(new_router(
+                router_id,
+                &format!("origin{}", prefix_idx),
+                as_id,
+                loopbacks[prefix].clone(),
+                IpAddr::new(prefix.addr().to_string(), prefix.prefix_len() as u32),
+                false,
+            ));
+            self.prefix_router_lookup.insert(*prefix, router_id);
+        }
+        Ok(())
+    }
+
+    /// Create all links that are present in the network. The interfaces are disabled until they
+    /// are configured.
+    fn create_all_links(&mut self, net: &Network) {
+        for (link_id, (a, b)) in net.links_symmetric().enumerate() {
+            let a_addr = IpAddr::new(format!("10.1.{}.1", link_id), 24);
+            let b_addr = IpAddr::new(format!("10.1.{}.2", link_id), 24);
+            self.add_iface(*a, *b, a_addr.clone(), b_addr.clone(), false, link_id + 1);
+            self.add_iface(*b, *a, b_addr, a_addr, false, link_id + 1);
+        }
+    }
+
+    /// Create the links and sessions between the external routers and the origin routers.
+    fn create_links_to_origin(&mut self, net: &Network) -> Result<(), Box<dyn Error>> {
+        let num_links = net.links_symmetric().count();
+        let mut link_id = num_links;
+        for prefix in PhysicalNetwork::sorted_prefixes(net) {
+            let origin = match self.prefix_router_lookup.get(&prefix) {
+                Some(origin) => *origin,
+                None => continue,
+            };
+            for ext in PhysicalNetwork::get_external_routers_with_prefix(net, prefix)
+                .iter()
+                .map(|r| r.router_id())
+            {
+                let (origin_addr, ext_addr) =
+                    PhysicalNetwork::origin_link_addrs(link_id - num_links)?;
+                link_id += 1;
+                self.add_iface(origin, ext, origin_addr.clone(), ext_addr.clone(), true, link_id);
+                self.add_iface(ext, origin, ext_addr.clone(), origin_addr.clone(), true, link_id);
+
+                // setup a session from the origin to the external router and viceversa
+                let origin_as = self.routers[origin.index()].as_id;
+                let ext_as = self.routers[ext.index()].as_id;
+                self.routers[origin.index()].bgp_sessions.push(BgpSessionInfo {
+                    neighbor: ext,
+                    neighbor_addr: ext_addr,
+                    neighbor_as_id: ext_as,
+                    is_rr_client: false,
+                    internal_session: false,
+                });
+                self.routers[ext.index()].bgp_sessions.push(BgpSessionInfo {
+                    neighbor: origin,
+                    neighbor_addr: origin_addr,
+                    neighbor_as_id: origin_as,
+                    is_rr_client: false,
+                    internal_session: false,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a new interface to the router `source`, connected to `target`.
+    fn add_iface(
+        &mut self,
+        source: RouterId,
+        target: RouterId,
+        iface_addr: IpAddr,
+        neighbor_addr: IpAddr,
+        enabled: bool,
+        link_id: usize,
+    ) {
+        let router = &mut self.routers[source.index()];
+        let name = format!("eth{}", router.ifaces.len());
+        let gns_interface = GNS3Interface {
+            adapter_number: router.ifaces.len() as u32,
+            port_number: 0,
+            name: name.clone(),
+            short_name: name,
+            link_type: "ethernet".to_string(),
+        };
+        router.gns_node.interfaces.push(gns_interface.clone());
+        router.ifaces.push(IfaceInfo {
+            neighbor: target,
+            neighbor_addr,
+            iface_addr,
+            gns_interface,
+            enabled,
+            cost: None,
+            area: OspfArea::BACKBONE,
+            link_id,
+        });
+    }
+}
+
+/// Export the entire migration into a directory. This writes the configuration of every router
+/// before and after the migration, and the commands of every step. See the
+/// [module documentation](self) for the structure of the directory.
+pub fn export_migration(
+    net: &Network,
+    config: &Config,
+    sequence: &[ConfigModifier],
+    dir: impl AsRef<Path>,
+) -> Result<(), Box<dyn Error>> {
+    let dir = dir.as_ref();
+    let mut exporter = FrrExporter::new(net, config)?;
+    exporter.write_configs(dir.join("initial"))?;
+    for (i, modifier) in sequence.iter().enumerate() {
+        exporter.write_modifier(modifier, dir.join(format!("step_{}", i + 1)))?;
+    }
+    exporter.write_configs(dir.join("final"))?;
+    Ok(())
+}
+
+/// Create a new router without any interfaces. Since the router is never started, the GNS3 node
+/// only carries the name and the interfaces.
+fn new_router(
+    router_id: RouterId,
+    name: &str,
+    as_id: AsId,
+    loopback_addr: IpAddr,
+    advertise_route: IpAddr,
+    is_internal: bool,
+) -> PhysicalRouter {
+    PhysicalRouter {
+        router_id,
+        name: name.to_string(),
+        gns_node: GNS3Node {
+            id: String::new(),
+            name: name.to_string(),
+            node_type: String::new(),
+            port: 0,
+            status: GNS3NodeStatus::Stopped,
+            interfaces: Vec::new(),
+        },
+        loopback_addr,
+        as_id,
+        ifaces: Vec::new(),
+        bgp_sessions: Vec::new(),
+        route_maps: Vec::new(),
+        static_routes: Vec::new(),
+        advertise_route: Some(advertise_route),
+        is_internal,
+        bgp_decision: BgpDecisionConfig::default(),
+        stub_areas: Vec::new(),
+        cluster_id: None,
+        bgp_networks: Vec::new(),
+        redistributions: Vec::new(),
+        aggregates: Vec::new(),
+        acls: Vec::new(),
+    }
+}
+
+/// Render the configuration file, with one command per line.
+fn render_config(cmds: &[String]) -> String {
+    cmds.iter().map(|cmd| format!("{}\n", cmd)).collect()
+}
+
+/// Render the commands such that they can be passed to `vtysh`.
+fn render_snippet(cmds: &[String]) -> String {
+    let mut result = String::from("configure terminal\n");
+    for cmd in cmds {
+        result.push_str(cmd);
+        result.push('\n');
+    }
+    result.push_str("end\n");
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use snowcap::example_networks::{ExampleNetwork, SimpleNet};
+    use snowcap::netsim::acl::{AccessList, AclDirection, AclEntryBuilder};
+    use snowcap::netsim::bgp::{LargeCommunity, RedistributionSource};
+    use snowcap::netsim::config::ConfigExpr;
+    use snowcap::netsim::route_map::{RouteMapBuilder, RouteMapDirection};
+    use snowcap::netsim::BgpSessionType;
+
+    #[test]
+    fn export_config() {
+        let net = SimpleNet::net(0);
+        let exporter = FrrExporter::new(&net, net.current_config()).unwrap();
+        let configs = exporter.router_configs();
+        let names: Vec<&str> = configs.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["r1", "r2", "r3", "r4", "e1", "e4", "origin0"]);
+
+        let r1 = &configs[0].1;
+        assert!(r1.starts_with("hostname r1\ninterface lo\nip address 10.0.0.1/32\n"));
+        assert!(r1.contains("interface eth0\nip address 10.1.0.1/24\nip ospf 1 area 0\n"));
+        assert!(r1.contains("neighbor 10.0.1.1 remote-as 65001\n"));
+        assert!(r1.contains("neighbor 10.0.2.1 peer-group internal\n"));
+        assert!(r1.contains("neighbor 10.1.5.2 peer-group external\n"));
+        assert!(!r1.contains("route-reflector-client"));
+        assert!(r1.contains("bgp deterministic-med\nbgp bestpath compare-routerid\n"));
+        assert!(!r1.contains("bgp always-compare-med"));
+        assert!(r1.contains("maximum-paths 1\nmaximum-paths ibgp 1\n"));
+    }
+
+    #[test]
+    fn export_bgp_decision() {
+        let mut net = SimpleNet::net(0);
+        let r1 = net.get_router_id("r1").unwrap();
+        let decision = BgpDecisionConfig {
+            always_compare_med: true,
+            med_missing_as_worst: true,
+            multipath: true,
+        };
+        net.apply_modifier(&ConfigModifier::Insert(ConfigExpr::BgpDecision {
+            router: r1,
+            config: decision,
+        }))
+        .unwrap();
+        let mut exporter = FrrExporter::new(&net, net.current_config()).unwrap();
+        let configs = exporter.router_configs();
+        assert!(configs[0].1.contains("bgp always-compare-med\n"));
+        assert!(configs[0].1.contains("bgp bestpath med missing-as-worst\n"));
+        assert!(!configs[0].1.contains("maximum-paths"));
+        assert!(!configs[1].1.contains("bgp always-compare-med"));
+        assert!(configs[1].1.contains("maximum-paths 1\n"));
+
+        // going back to the default configuration only changes the options that differ
+        let cmds = exporter
+            .apply_modifier(&ConfigModifier::Remove(ConfigExpr::BgpDecision {
+                router: r1,
+                config: decision,
+            }))
+            .unwrap();
+        assert_eq!(
+            cmds,
+            vec![(
+                r1,
+                vec![
+                    "router bgp 65001".to_string(),
+                    "no bgp always-compare-med".to_string(),
+                    "no bgp bestpath med missing-as-worst".to_string(),
+                    "address-family ipv4".to_string(),
+                    "maximum-paths 1".to_string(),
+                    "maximum-paths ibgp 1".to_string(),
+                    "exit".to_string(),
+                    "exit".to_string(),
+                ]
+            )]
+        );
+        assert!(!exporter.router_configs()[0].1.contains("bgp always-compare-med"));
+    }
+
+    #[test]
+    fn export_cluster_id() {
+        let mut net = SimpleNet::net(0);
+        let r1 = net.get_router_id("r1").unwrap();
+        let r2 = net.get_router_id("r2").unwrap();
+        net.apply_modifier(&ConfigModifier::Insert(ConfigExpr::BgpClusterId {
+            router: r2,
+            cluster_id: r1,
+        }))
+        .unwrap();
+        let exporter = FrrExporter::new(&net, net.current_config()).unwrap();
+        let configs = exporter.router_configs();
+        assert!(!configs[0].1.contains("bgp cluster-id"));
+        assert!(configs[1].1.contains("bgp cluster-id 10.0.0.1\n"));
+    }
+
+    #[test]
+    fn export_ospf_areas() {
+        let net = SimpleNet::net(0);
+        let mut exporter = FrrExporter::new(&net, net.current_config()).unwrap();
+        let routers = net.get_routers();
+        let (a, b) = *net
+            .links_symmetric()
+            .find(|(a, b)| routers.contains(a) && routers.contains(b))
+            .unwrap();
+
+        let stub_area = ConfigExpr::OspfStubArea { area: OspfArea(1) };
+        let cmds = exporter.apply_modifier(&ConfigModifier::Insert(stub_area.clone())).unwrap();
+        assert_eq!(cmds.len(), routers.len());
+        let cmds = exporter
+            .apply_modifier(&ConfigModifier::Insert(ConfigExpr::IgpLinkArea {
+                source: a,
+                target: b,
+                area: OspfArea(1),
+            }))
+            .unwrap();
+        assert_eq!(cmds.iter().map(|(r, _)| *r).collect::<Vec<_>>(), vec![a, b]);
+        assert_eq!(cmds[0].1[1..], ["no ip ospf 1 area 0", "ip ospf 1 area 1"]);
+
+        let configs = exporter.router_configs();
+        let config_a = &configs[a.index()].1;
+        assert!(config_a.contains("redistribute connected\narea 1 stub\nexit\n"));
+        assert!(config_a.contains("ip ospf 1 area 1\n"));
+        assert!(config_a.contains("ip ospf 1 area 0\n"));
+
+        let cmds = exporter
+            .apply_modifier(&ConfigModifier::Update {
+                from: stub_area.clone(),
+                to: stub_area.clone(),
+            })
+            .unwrap();
+        assert!(cmds.is_empty());
+        let cmds = exporter
+            .apply_modifier(&ConfigModifier::Remove(ConfigExpr::IgpLinkArea {
+                source: a,
+                target: b,
+                area: OspfArea(1),
+            }))
+            .unwrap();
+        assert_eq!(cmds[1].1[1..], ["no ip ospf 1 area 1", "ip ospf 1 area 0"]);
+        let cmds = exporter.apply_modifier(&ConfigModifier::Remove(stub_area)).unwrap();
+        assert_eq!(cmds.len(), routers.len());
+        assert!(cmds.iter().all(|(_, c)| c == &["router ospf 1", "no area 1 stub"]));
+        let configs = exporter.router_configs();
+        assert!(!configs[a.index()].1.contains("area 1 stub"));
+        assert!(!configs[b.index()].1.contains("ip ospf 1 area 1\n"));
+    }
+
+    #[test]
+    fn export_networks() {
+        let mut net = SimpleNet::net(0);
+        let r1 = net.get_router_id("r1").unwrap();
+        let p: Prefix = "200.0.0.0/24".parse().unwrap();
+        let network = ConfigExpr::BgpNetwork { router: r1, prefix: p };
+        net.apply_modifier(&ConfigModifier::Insert(network.clone())).unwrap();
+        let mut exporter = FrrExporter::new(&net, net.current_config()).unwrap();
+        assert!(exporter.router_configs()[0]
+            .1
+            .contains("network 10.0.0.0/8\nnetwork 200.0.0.0/24\n"));
+
+        let cmds = exporter.apply_modifier(&ConfigModifier::Remove(network.clone())).unwrap();
+        assert_eq!(cmds.len(), 1);
+        assert_eq!(cmds[0].0, r1);
+        assert_eq!(
+            cmds[0].1,
+            ["router bgp 65001", "address-family ipv4", "no network 200.0.0.0/24", "exit", "exit"]
+        );
+        assert!(!exporter.router_configs()[0].1.contains("network 200.0.0.0/24"));
+        let cmds = exporter.apply_modifier(&ConfigModifier::Insert(network)).unwrap();
+        assert_eq!(cmds[0].1[2], "network 200.0.0.0/24");
+
+        // IPv6 prefixes cannot be announced
+        let network =
+            ConfigExpr::BgpNetwork { router: r1, prefix: "2001:db8::/32".parse().unwrap() };
+        assert!(exporter.apply_modifier(&ConfigModifier::Insert(network)).is_err());
+    }
+
+    #[test]
+    fn export_redistribution() {
+        let mut net = SimpleNet::net(0);
+        let r1 = net.get_router_id("r1").unwrap();
+        let map = |order, prefix| {
+            RouteMapBuilder::new().order(order).deny().match_prefix(Prefix::from(prefix)).build()
+        };
+        let redistribute = |route_maps| ConfigExpr::BgpRedistribute {
+            router: r1,
+            source: RedistributionSource::Static,
+            route_maps,
+        };
+        net.apply_modifier(&ConfigModifier::Insert(redistribute(vec![map(20, 1), map(10, 0)])))
+            .unwrap();
+        let mut exporter = FrrExporter::new(&net, net.current_config()).unwrap();
+        let config = &exporter.router_configs()[0].1;
+        assert!(config.contains(
+            "ip prefix-list r1_RD_static_0_10-pl permit 0.0.0.0/24\n\
+             route-map r1_RD_static_0 deny 10\n\
+             match ip address prefix-list r1_RD_static_0_10-pl\n\
+             exit\n\
+             ip prefix-list r1_RD_static_0_20-pl permit 0.0.1.0/24\n\
+             route-map r1_RD_static_0 deny 20\n\
+             match ip address prefix-list r1_RD_static_0_20-pl\n\
+             exit\n\
+             route-map r1_RD_static_0 permit 65535\n"
+        ));
+        assert!(config.contains("redistribute static route-map r1_RD_static_0\n"));
+
+        // the update switches to a new route map, before the old one is removed.
+        let cmds = exporter
+            .apply_modifier(&ConfigModifier::Update {
+                from: redistribute(vec![map(20, 1), map(10, 0)]),
+                to: redistribute(vec![map(10, 1)]),
+            })
+            .unwrap();
+        assert_eq!(
+            cmds[0].1,
+            vec![
+                "ip prefix-list r1_RD_static_1_10-pl permit 0.0.1.0/24",
+                "route-map r1_RD_static_1 deny 10",
+                "match ip address prefix-list r1_RD_static_1_10-pl",
+                "exit",
+                "route-map r1_RD_static_1 permit 65535",
+                "exit",
+                "router bgp 65001",
+                "address-family ipv4",
+                "redistribute static route-map r1_RD_static_1",
+                "exit",
+                "exit",
+                "no route-map r1_RD_static_0",
+                "no ip prefix-list r1_RD_static_0_10-pl permit 0.0.0.0/24",
+                "no ip prefix-list r1_RD_static_0_20-pl permit 0.0.1.0/24",
+            ]
+        );
+
+        let cmds =
+            exporter.apply_modifier(&ConfigModifier::Remove(redistribute(Vec::new()))).unwrap();
+        assert_eq!(cmds[0].1[2..5], ["no redistribute static", "exit", "exit"]);
+        assert_eq!(cmds[0].1[5], "no route-map r1_RD_static_1");
+        assert!(!exporter.router_configs()[0].1.contains("redistribute static"));
+    }
+
+    #[test]
+    fn export_aggregates() {
+        let mut net = SimpleNet::net(0);
+        let r1 = net.get_router_id("r1").unwrap();
+        let aggregate = |summary_only, as_set| ConfigExpr::BgpAggregate {
+            router: r1,
+            prefix: "0.0.0.0/16".parse().unwrap(),
+            summary_only,
+            as_set,
+        };
+        net.apply_modifier(&ConfigModifier::Insert(aggregate(true, false))).unwrap();
+        let mut exporter = FrrExporter::new(&net, net.current_config()).unwrap();
+        let configs = exporter.router_configs();
+        assert!(configs[0].1.contains("aggregate-address 0.0.0.0/16 summary-only\n"));
+        assert!(!configs[1].1.contains("aggregate-address"));
+
+        // updating the options configures the aggregate again
+        let cmds = exporter
+            .apply_modifier(&ConfigModifier::Update {
+                from: aggregate(true, false),
+                to: aggregate(false, true),
+            })
+            .unwrap();
+        assert_eq!(cmds.len(), 1);
+        assert_eq!(cmds[0].0, r1);
+        assert_eq!(
+            cmds[0].1,
+            [
+                "router bgp 65001",
+                "address-family ipv4",
+                "aggregate-address 0.0.0.0/16 as-set",
+                "exit",
+                "exit"
+            ]
+        );
+        let config = &exporter.router_configs()[0].1;
+        assert!(config.contains("aggregate-address 0.0.0.0/16 as-set\n"));
+        assert!(!config.contains("summary-only"));
+
+        let cmds =
+            exporter.apply_modifier(&ConfigModifier::Remove(aggregate(false, true))).unwrap();
+        assert_eq!(cmds[0].1[2], "no aggregate-address 0.0.0.0/16");
+        assert!(!exporter.router_configs()[0].1.contains("aggregate-address"));
+        assert!(exporter.apply_modifier(&ConfigModifier::Remove(aggregate(false, true))).is_err());
+    }
+
+    #[test]
+    fn export_access_lists() {
+        let mut net = SimpleNet::net(0);
+        let r1 = net.get_router_id("r1").unwrap();
+        let e1 = net.get_router_id("e1").unwrap();
+        let acl = |entries| ConfigExpr::AccessControl {
+            router: r1,
+            interface: e1,
+            direction: AclDirection::Incoming,
+            acl: AccessList::new(entries),
+        };
+        let entries = vec![
+            AclEntryBuilder::new().order(20).permit().build(),
+            AclEntryBuilder::new()
+                .order(10)
+                .deny()
+                .match_src("10.0.0.0/8".parse().unwrap())
+                .match_dst("0.0.0.1/32".parse().unwrap())
+                .build(),
+        ];
+        net.apply_modifier(&ConfigModifier::Insert(acl(entries.clone()))).unwrap();
+        let mut exporter = FrrExporter::new(&net, net.current_config()).unwrap();
+        let config = &exporter.router_configs()[0].1;
+        assert!(config.contains(
+            "access-list r1_ACL_e1_in_0 seq 10 deny ip 10.0.0.0 0.255.255.255 host 0.0.0.1\n\
+             access-list r1_ACL_e1_in_0 seq 20 permit ip any any\n"
+        ));
+        assert!(config.contains("ip access-group r1_ACL_e1_in_0 in\n"));
+
+        // the update applies the new access list before the old one is removed
+        let cmds = exporter
+            .apply_modifier(&ConfigModifier::Update {
+                from: acl(entries),
+                to: acl(vec![AclEntryBuilder::new().order(10).deny().build()]),
+            })
+            .unwrap();
+        assert_eq!(cmds.len(), 1);
+        assert_eq!(cmds[0].0, r1);
+        assert_eq!(
+            cmds[0].1,
+            [
+                "access-list r1_ACL_e1_in_1 seq 10 deny ip any any",
+                "interface eth2",
+                "ip access-group r1_ACL_e1_in_1 in",
+                "exit",
+                "no access-list r1_ACL_e1_in_0",
+            ]
+        );
+
+        let cmds = exporter.apply_modifier(&ConfigModifier::Remove(acl(Vec::new()))).unwrap();
+        assert_eq!(
+            cmds[0].1[1..],
+            ["no ip access-group r1_ACL_e1_in_1 in", "exit", "no access-list r1_ACL_e1_in_1"]
+        );
+        assert!(!exporter.router_configs()[0].1.contains("access-"));
+
+        // FRR cannot filter on the protocol or the ports
+        let entries =
+            vec![AclEntryBuilder::new().order(10).permit().match_dst_port(53, 53).build()];
+        assert!(exporter.apply_modifier(&ConfigModifier::Insert(acl(entries))).is_err());
+    }
+
+    #[test]
+    fn export_prefixes() {
+        let mut net = SimpleNet::net(0);
+        let e1 = net.get_router_id("e1").unwrap();
+        let p0 = Prefix::from(0);
+        let p1: Prefix = "0.0.0.0/16".parse().unwrap();
+        net.advertise_external_route(e1, p1, vec![AsId(65101), AsId(65300)], None, None).unwrap();
+        let exporter = FrrExporter::new(&net, net.current_config()).unwrap();
+
+        // the origin of the less-specific prefix must not use the addresses of the other one
+        let loopback = |prefix: Prefix| {
+            let origin = exporter.prefix_router_lookup[&prefix];
+            let r = &exporter.routers()[origin.index()];
+            assert_eq!(r.advertise_route.as_ref().unwrap().to_string(), prefix.to_string());
+            r.loopback_addr.addr.clone()
+        };
+        assert_eq!(loopback(p0), "0.0.0.1");
+        assert_eq!(loopback(p1), "0.0.1.1");
+        let configs = exporter.router_configs();
+        assert!(configs.iter().any(|(_, c)| c.contains("network 0.0.0.0/16\n")));
+        assert!(configs.iter().any(|(_, c)| c.contains("network 0.0.0.0/24\n")));
+
+        // prefixes within the addresses of the routers cannot be exported
+        let p2: Prefix = "10.5.0.0/16".parse().unwrap();
+        net.advertise_external_route(e1, p2, vec![AsId(65101), AsId(65400)], None, None).unwrap();
+        assert!(FrrExporter::new(&net, net.current_config()).is_err());
+    }
+
+    #[test]
+    fn export_route_maps() {
+        let build = || {
+            let mut net = SimpleNet::net(0);
+            let r1 = net.get_router_id("r1").unwrap();
+            let r2 = net.get_router_id("r2").unwrap();
+            let e1 = net.get_router_id("e1").unwrap();
+            let maps = vec![
+                RouteMapBuilder::new()
+                    .order(10)
+                    .allow()
+                    .match_prefix_range(Prefix::from(0))
+                    .match_as_path_contains(AsId(65200))
+                    .match_community((65001 << 16) | 10)
+                    .set_local_pref(200)
+                    .build(),
+                RouteMapBuilder::new()
+                    .order(20)
+                    .deny()
+                    .match_next_hop(e1)
+                    .match_neighbor(r2)
+                    .build(),
+            ];
+            for map in maps {
+                let direction = RouteMapDirection::Incoming;
+                let expr = ConfigExpr::BgpRouteMap { router: r1, direction, map };
+                net.apply_modifier(&ConfigModifier::Insert(expr)).unwrap();
+            }
+            net
+        };
+        let net = build();
+        let mut exporter = FrrExporter::new(&net, net.current_config()).unwrap();
+        let configs = exporter.router_configs();
+        let r1 = &configs[0].1;
+        assert!(r1.contains("ip prefix-list r1_RM_0-pl permit 0.0.0.0/24 le 32\n"));
+        assert!(r1.contains("bgp as-path access-list r1_RM_0-as permit _65200_\n"));
+        let community: u32 = (65001 << 16) | 10;
+        let c_list = format!("bgp community-list standard r1_RM_0-c-match permit {}\n", community);
+        assert!(r1.contains(&c_list));
+        assert!(r1.contains(
+            "route-map r1_RM_0 permit 10\nmatch as-path r1_RM_0-as\n\
+             match community r1_RM_0-c-match\nmatch ip address prefix-list r1_RM_0-pl\n\
+             set local-preference 200\nexit\n"
+        ));
+        assert!(r1.contains(
+            "route-map r1_RM_1 deny 20\nmatch ip next-hop address 10.1.5.2\n\
+             match peer 10.0.1.1\nexit\n"
+        ));
+
+        // the result does not depend on the order in which the configuration is stored
+        let other = build();
+        assert_eq!(FrrExporter::new(&other, other.current_config()).unwrap(), exporter);
+
+        // route-maps that cannot be expressed in FRR are rejected
+        let r1 = net.get_router_id("r1").unwrap();
+        for map in [
+            RouteMapBuilder::new().order(30).allow().set_igp_cost(5.0).build(),
+            RouteMapBuilder::new().order(30).allow().match_as_path_length(3).build(),
+            RouteMapBuilder::new().order(30).allow().match_community_range(1, 5).build(),
+        ]
+        .iter()
+        {
+            let direction = RouteMapDirection::Outgoing;
+            let expr = ConfigExpr::BgpRouteMap { router: r1, direction, map: map.clone() };
+            assert!(exporter.apply_modifier(&ConfigModifier::Insert(expr)).is_err());
+        }
+    }
+
+    #[test]
+    fn export_route_map_actions() {
+        let net = SimpleNet::net(0);
+        let mut exporter = FrrExporter::new(&net, net.current_config()).unwrap();
+        let r1 = net.get_router_id("r1").unwrap();
+        let map = RouteMapBuilder::new()
+            .order(10)
+            .allow()
+            .set_med(10)
+            .add_community(1)
+            .add_community(2)
+            .prepend_as_path(AsId(1), 2)
+            .prepend_as_path(AsId(2), 1)
+            .set_large_community(LargeCommunity::new(1, 2, 3))
+            .add_large_community(LargeCommunity::new(4, 5, 6))
+            .set_med(20)
+            .build();
+        let direction = RouteMapDirection::Outgoing;
+        let expr = ConfigExpr::BgpRouteMap { router: r1, direction, map };
+        let cmds = exporter.apply_modifier(&ConfigModifier::Insert(expr)).unwrap();
+
+        // every kind of statement appears once, in the order of the first action of that kind
+        assert_eq!(
+            cmds[0].1[1..6],
+            [
+                "set metric 20",
+                "set community 1 2 additive",
+                "set as-path prepend 2 1 1",
+                "set large-community 1:2:3 4:5:6",
+                "exit"
+            ]
+        );
+    }
+
+    #[test]
+    fn export_modifier() {
+        let net = SimpleNet::net(0);
+        let mut exporter = FrrExporter::new(&net, net.current_config()).unwrap();
+        let r1 = net.get_router_id("r1").unwrap();
+        let r2 = net.get_router_id("r2").unwrap();
+
+        let cmds = exporter
+            .apply_modifier(&ConfigModifier::Update {
+                from: ConfigExpr::BgpSession {
+                    source: r1,
+                    target: r2,
+                    session_type: BgpSessionType::IBgpPeer,
+                },
+                to: ConfigExpr::BgpSession {
+                    source: r1,
+                    target: r2,
+                    session_type: BgpSessionType::IBgpClient,
+                },
+            })
+            .unwrap();
+        assert_eq!(
+            cmds,
+            vec![(
+                r1,
+                vec![
+                    "router bgp 65001".to_string(),
+                    "neighbor 10.0.1.1 route-reflector-client".to_string()
+                ]
+            )]
+        );
+        assert!(exporter.router_configs()[0]
+            .1
+            .contains("neighbor 10.0.1.1 route-reflector-client"));
+
+        // removing a session reconfigures both routers
+        let cmds = exporter
+            .apply_modifier(&ConfigModifier::Remove(ConfigExpr::BgpSession {
+                source: r1,
+                target: r2,
+                session_type: BgpSessionType::IBgpClient,
+            }))
+            .unwrap();
+        assert_eq!(cmds.iter().map(|(r, _)| *r).collect::<Vec<_>>(), vec![r1, r2]);
+        assert!(!exporter.router_configs()[1].1.contains("neighbor 10.0.0.1"));
+    }
+
+    #[test]
+    fn export_migration_dir() {
+        let net = SimpleNet::net(0);
+        let final_config = SimpleNet::final_config(&net, 0);
+        let patch = net.current_config().get_diff(&final_config);
+        let dir = std::env::temp_dir().join("snowcap_runtime_export_migration");
+        let _ = fs::remove_dir_all(&dir);
+
+        export_migration(&net, net.current_config(), &patch.modifiers, &dir).unwrap();
+
+        assert!(dir.join("initial").join("r1.conf").exists());
+        assert!(dir.join("final").join("origin0.conf").exists());
+        for i in 1..=patch.modifiers.len() {
+            assert!(fs::read_dir(dir.join(format!("step_{}", i))).unwrap().count() > 0);
+        }
+        let snippet = fs::read_dir(dir.join("step_1")).unwrap().next().unwrap().unwrap().path();
+        let snippet = fs::read_to_string(snippet).unwrap();
+        assert!(snippet.starts_with("configure terminal\n"));
+        assert!(snippet.ends_with("end\n"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -17,6 +17,7 @@
 
 //! Utilities for telnet interactions
 
+use crate::config::router_config;
 use crate::physical_network::{IpAddr, PhysicalRouter};
 
 use log::*;
@@ -226,111 +227,9 @@ impl FrrConnection {
         // switch into config mode
         self.config_expr("config\n")?;
 
-        // set hostname
-        self.config_expr(format!("hostname {}\n", router.name))?;
-
-        // configure loopback interface
-        self.config_expr("interface lo\n")?;
-        self.config_expr(format!("ip address {}/32\n", router.loopback_addr.addr))?;
-        self.config_expr("exit\n")?;
-
-        // confgure ospf
-        if router.ifaces.iter().any(|i| i.cost.is_some()) {
-            self.config_expr("router ospf 1\n")?;
-            self.config_expr(format!("router-id {}\n", router.loopback_addr.addr))?;
-            self.config_expr("redistribute connected\n")?;
-            self.config_expr("exit\n")?;
-        }
-
-        // configure every interface
-        for iface in &router.ifaces {
-            // extract the cost from the configuration
-            if iface.enabled {
-                self.config_expr(format!("interface {}\n", iface.gns_interface.short_name))?;
-                self.config_expr(format!("ip address {}\n", iface.iface_addr))?;
-                if let Some(cost) = iface.cost.as_ref() {
-                    self.config_expr("ip ospf 1 area 0\n")?;
-                    self.config_expr(format!("ip ospf cost {}\n", cost))?;
-                }
-                self.config_expr("exit\n")?;
-            }
-        }
-
-        // configure route maps
-        for rm in &router.route_maps {
-            self.config_expr(format!("route-map {} {} {}\n", rm.name, rm.state, rm.order))?;
-            for (key, value) in rm.match_statements.iter() {
-                self.config_expr(format!("match {} {}\n", key, value))?; // exit router bgp
-            }
-            for (key, value) in rm.set_statements.iter() {
-                self.config_expr(format!("set {} {}\n", key, value))?; // exit router bgp
-            }
-            self.config_expr("exit\n")?; // exit router bgp
-
-            // configure to allow per default
-            self.config_expr(format!("route-map {} permit 65535\n", rm.name))?;
-            self.config_expr("exit\n")?;
-        }
-
-        // configure BGP
-        self.config_expr(format!("router bgp {}\n", router.as_id.0))?;
-        self.config_expr(format!("bgp router-id {}\n", router.loopback_addr.addr))?;
-        self.config_expr("bgp log-neighbor-changes\n")?;
-        self.config_expr("bgp bestpath compare-routerid\n")?;
-        self.config_expr("bgp route-reflector allow-outbound-policy\n")?;
-        self.config_expr("neighbor internal peer-group\n")?;
-        self.config_expr("neighbor external peer-group\n")?;
-        for session in &router.bgp_sessions {
-            let n_addr = session.neighbor_addr.addr.as_str();
-            self.config_expr(format!(
-                "neighbor {} remote-as {}\n",
-                n_addr, session.neighbor_as_id.0
-            ))?;
-            self.config_expr(format!(
-                "neighbor {} update-source {}\n",
-                n_addr,
-                if session.internal_session {
-                    "lo"
-                } else {
-                    router
-                        .ifaces
-                        .iter()
-                        .filter(|i| i.neighbor == session.neighbor)
-                        .map(|i| i.gns_interface.short_name.as_str())
-                        .next()
-                        .expect("No direct connection with the neighbor")
-                }
-            ))?;
-            self.config_expr(format!(
-                "neighbor {} peer-group {}\n",
-                n_addr,
-                if session.internal_session { "internal" } else { "external" }
-            ))?;
-            if session.is_rr_client {
-                self.config_expr(format!("neighbor {} route-reflector-client\n", n_addr))?;
-            }
-        }
-        // enable ipv4 communication
-        self.config_expr("address-family ipv4\n")?;
-        if let Some(prefix) = router.advertise_route.as_ref() {
-            self.config_expr(format!("network {}\n", prefix))?;
-        }
-        for rm in &router.route_maps {
-            self.config_expr(format!(
-                "neighbor internal route-map {} {}\n",
-                rm.name, rm.direction
-            ))?;
-            self.config_expr(format!(
-                "neighbor external route-map {} {}\n",
-                rm.name, rm.direction
-            ))?;
-        }
-        self.config_expr("exit\n")?; // exit address-family
-        self.config_expr("exit\n")?; // exit router bgp
-
-        // configure static routes
-        for sr in &router.static_routes {
-            self.config_expr(format!("ip route {} {}\n", sr.addr, sr.next_hop))?;
+        // apply the entire configuration
+        for cmd in router_config(router) {
+            self.config_expr(format!("{}\n", cmd))?;
         }
 
         self.config_expr("exit\n")?; // exit config mode
@@ -710,6 +609,7 @@ mod test {
     use super::*;
     use crate::physical_network::{BgpSessionInfo, IfaceInfo, IpAddr, StaticRouteInfo};
     use gns3::*;
+    use snowcap::netsim::bgp::BgpDecisionConfig;
     use snowcap::netsim::*;
 
     const TEST_PROJECT_NAME: &str = "FrrConnTestProject";
@@ -899,6 +799,13 @@ B   109.0.0.0/8 [20/0] via 10.1.12.2, eth1, 00:01:42
             loopback_addr: IpAddr::new("10.0.0.1", 24),
             as_id: AsId(65001),
             is_internal: true,
+            bgp_decision: BgpDecisionConfig::default(),
+            stub_areas: Vec::new(),
+            cluster_id: None,
+            bgp_networks: Vec::new(),
+            redistributions: Vec::new(),
+            aggregates: Vec::new(),
+            acls: Vec::new(),
             ifaces: vec![
                 IfaceInfo {
                     neighbor: 1.into(),
@@ -913,6 +820,7 @@ B   109.0.0.0/8 [20/0] via 10.1.12.2, eth1, 00:01:42
                     },
                     enabled: true,
                     cost: Some(10),
+                    area: OspfArea::BACKBONE,
                     link_id: 0,
                 },
                 IfaceInfo {
@@ -928,6 +836,7 @@ B   109.0.0.0/8 [20/0] via 10.1.12.2, eth1, 00:01:42
                     },
                     enabled: true,
                     cost: None,
+                    area: OspfArea::BACKBONE,
                     link_id: 0,
                 },
                 IfaceInfo {
@@ -943,6 +852,7 @@ B   109.0.0.0/8 [20/0] via 10.1.12.2, eth1, 00:01:42
                     },
                     enabled: false,
                     cost: None,
+                    area: OspfArea::BACKBONE,
                     link_id: 0,
                 },
             ],
@@ -1000,6 +910,7 @@ interface lo
 router bgp 65001
  bgp router-id 10.0.0.1
  bgp log-neighbor-changes
+ bgp deterministic-med
  bgp route-reflector allow-outbound-policy
  bgp bestpath compare-routerid
  neighbor external peer-group
@@ -1014,6 +925,8 @@ router bgp 65001
  address-family ipv4 unicast
   network 10.0.0.0/24
   neighbor 10.0.0.2 route-reflector-client
+  maximum-paths 1
+  maximum-paths ibgp 1
  exit-address-family
 !
 router ospf 1
@@ -0,0 +1,1286 @@
+// Snowcap: Synthesizing Network-Wide Configuration Updates
+// Copyright (C) 2021  Tibor Schneider
+//
+// This program is free software; you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation; either version 2 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along
+// with this program; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+
+//! # FRR Configuration Importer
+//!
+//! This module is the reverse of [`crate::config`]: It parses the `running-config` of several
+//! FRRouting routers, and builds a [`Network`] together with its [`Config`]. Every configuration
+//! passed to [`import_frr_configs`] describes one internal router. The following parts of the
+//! configuration are understood:
+//!
+//! - `hostname`, used as the name of the router.
+//! - `interface` blocks with `ip address`, `ip ospf cost` and `ip ospf area`. OSPF can also be
+//!   enabled with `network ... area ...` inside `router ospf`. Two interfaces in the same subnet
+//!   form a link, and the OSPF cost of the interface becomes the link weight in this direction.
+//!   Both interfaces of a link must be in the same area. Areas can be written as a number or as an
+//!   address, and `area ... stub` inside `router ospf` declares a stub area.
+//! - `router bgp` with `neighbor ... remote-as`, `neighbor ... peer-group`,
+//!   `neighbor ... route-reflector-client` and `neighbor ... route-map`. eBGP neighbors which are
+//!   not part of the configured routers become external routers. The decision process is
+//!   configured with `bgp always-compare-med` and `bgp bestpath med missing-as-worst`. The
+//!   `bgp cluster-id` must be the address of one of the routers. Like in FRR, BGP multipath is
+//!   enabled unless both `maximum-paths 1` and `maximum-paths ibgp 1` are configured.
+//! - `route-map` entries with the match clauses `peer`, `ip address prefix-list`,
+//!   `ip next-hop address` and `community`, and the set clauses `local-preference`, `metric`,
+//!   `ip next-hop`, `community`, `large-community` (both optionally `additive`),
+//!   `as-path prepend` and `origin`.
+//! - `ip route`, `ip prefix-list` and `bgp community-list`. A prefix-list entry either matches
+//!   the network exactly, or all networks contained in it (`le 32`). Other `ge` and `le` bounds
+//!   are rejected.
+//!
+//! The route-map entries of a router are renumbered in steps of 10, with one block of entries for
+//! each neighbor on which the route-map is applied. Like in FRR, each block ends by denying all
+//! routes that matched no entry.
+//!
+//! All other lines are ignored. Every IP network that appears in a prefix-list or a static route is
+//! translated into the [`Prefix`] with the same address and length. All of them are returned in
+//! [`ImportedNetwork::prefixes`].
+
+use crate::physical_network::IpAddr;
+use snowcap::netsim::bgp::{BgpDecisionConfig, LargeCommunity, Origin};
+use snowcap::netsim::config::{Config, ConfigExpr};
+use snowcap::netsim::route_map::{RouteMapBuilder, RouteMapDirection};
+use snowcap::netsim::{AsId, BgpSessionType, LinkWeight, Network, OspfArea, Prefix, RouterId};
+
+use log::*;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::error::Error;
+
+/// OSPF cost of an interface on which OSPF is enabled, but no cost is configured.
+pub const DEFAULT_OSPF_COST: u32 = 10;
+/// Link weight of links from internal routers towards external routers.
+const EXTERNAL_LINK_WEIGHT: LinkWeight = 1.0;
+
+/// Network, configuration and prefixes, imported from the router configurations.
+#[derive(Debug)]
+pub struct ImportedNetwork {
+    /// Network with all internal and external routers and links. The imported configuration is
+    /// already applied. No routes are advertised.
+    pub net: Network,
+    /// Configuration of the network, parsed from the running-config of all routers.
+    pub config: Config,
+    /// All prefixes found in the configuration.
+    pub prefixes: HashSet<Prefix>,
+}
+
+/// Parse the `running-config` of all internal routers (one string per router), and build the
+/// network and its configuration. The routers get their `RouterId` in the order in which they are
+/// passed to this function. External routers are added afterwards, and they are named by the
+/// address of their BGP session.
+pub fn import_frr_configs<S: AsRef<str>>(
+    running_configs: &[S],
+) -> Result<ImportedNetwork, Box<dyn Error>> {
+    let routers = running_configs
+        .iter()
+        .map(|c| FrrRouter::parse(c.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Importer::new(routers).import()
+}
+
+/// Builds the network and the configuration from the parsed routers.
+struct Importer {
+    routers: Vec<FrrRouter>,
+    ids: Vec<RouterId>,
+    net: Network,
+    config: Config,
+    prefixes: HashSet<Prefix>,
+    addresses: HashMap<String, RouterId>,
+}
+
+impl Importer {
+    fn new(routers: Vec<FrrRouter>) -> Self {
+        Self {
+            routers,
+            ids: Vec::new(),
+            net: Network::new(),
+            config: Config::new(),
+            prefixes: HashSet::new(),
+            addresses: HashMap::new(),
+        }
+    }
+
+    fn import(mut self) -> Result<ImportedNetwork, Box<dyn Error>> {
+        self.add_routers()?;
+        self.add_internal_links()?;
+        self.add_bgp_sessions()?;
+        self.add_route_maps()?;
+        self.add_static_routes()?;
+        self.set_bgp_decision_configs()?;
+        self.add_stub_areas()?;
+
+        self.net.set_config(&self.config)?;
+        Ok(ImportedNetwork { net: self.net, config: self.config, prefixes: self.prefixes })
+    }
+
+    /// Create all internal routers, and remember their addresses
+    fn add_routers(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut as_id = None;
+        for router in self.routers.iter() {
+            if self.net.get_router_id(&router.name).is_ok() {
+                return Err(format!("Hostname {} is used twice", router.name).into());
+            }
+            match (as_id, router.as_id) {
+                (Some(a), Some(b)) if a != b => {
+                    return Err(format!("Router {} is in a different AS", router.name).into())
+                }
+                (None, Some(b)) => as_id = Some(b),
+                _ => {}
+            }
+            let id = self.net.add_router(router.name.clone());
+            for addr in router.ifaces.iter().filter_map(|i| i.addr.as_ref()) {
+                self.addresses.insert(addr.addr.clone(), id);
+            }
+            self.ids.push(id);
+        }
+        Ok(())
+    }
+
+    /// Configure the BGP decision process and the cluster ID of all routers
+    fn set_bgp_decision_configs(&mut self) -> Result<(), Box<dyn Error>> {
+        for (router, id) in self.routers.iter().zip(self.ids.iter()) {
+            // FRR uses all equally good paths, unless `maximum-paths` is set to 1.
+            let mut decision = router.bgp_decision;
+            decision.multipath = match (router.max_paths_ebgp, router.max_paths_ibgp) {
+                (Some(1), Some(1)) => false,
+                (Some(1), _) | (_, Some(1)) => {
+                    return Err(format!(
+                        "{} uses multipath only for either eBGP or iBGP, which is not supported",
+                        router.name
+                    )
+                    .into())
+                }
+                _ => true,
+            };
+            if decision != BgpDecisionConfig::default() {
+                self.config.add(ConfigExpr::BgpDecision { router: *id, config: decision })?;
+            }
+            if let Some(cluster_id) = router.cluster_id.as_ref() {
+                let cluster_id = *self.addresses.get(cluster_id).ok_or_else(|| {
+                    format!("Cluster ID {} of {} is not a router", cluster_id, router.name)
+                })?;
+                self.config.add(ConfigExpr::BgpClusterId { router: *id, cluster_id })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the links between all interfaces in the same subnet, and set the link weight based on
+    /// the OSPF configuration.
+    fn add_internal_links(&mut self) -> Result<(), Box<dyn Error>> {
+        for a in 0..self.routers.len() {
+            for b in (a + 1)..self.routers.len() {
+                let mut connected = false;
+                for iface_a in self.routers[a].ifaces.iter() {
+                    for iface_b in self.routers[b].ifaces.iter() {
+                        if !iface_a.same_subnet(iface_b) {
+                            continue;
+                        }
+                        if connected {
+                            return Err(format!(
+                                "Routers {} and {} are connected twice",
+                                self.routers[a].name, self.routers[b].name
+                            )
+                            .into());
+                        }
+                        connected = true;
+                        self.net.add_link(self.ids[a], self.ids[b]);
+                        let area_a = self.routers[a].ospf_area(iface_a);
+                        let area_b = self.routers[b].ospf_area(iface_b);
+                        if area_a != area_b {
+                            return Err(format!(
+                                "Link between {} and {} is in area {} and {}",
+                                self.routers[a].name, self.routers[b].name, area_a, area_b
+                            )
+                            .into());
+                        }
+                        if !area_a.is_backbone() {
+                            self.config.add(ConfigExpr::IgpLinkArea {
+                                source: self.ids[a],
+                                target: self.ids[b],
+                                area: area_a,
+                            })?;
+                        }
+                        for (src, iface, dst) in
+                            &[(a, iface_a, self.ids[b]), (b, iface_b, self.ids[a])]
+                        {
+                            if let Some(cost) = self.routers[*src].ospf_cost(iface) {
+                                self.config.add(ConfigExpr::IgpLinkWeight {
+                                    source: self.ids[*src],
+                                    target: *dst,
+                                    weight: cost as LinkWeight,
+                                })?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Configure all areas that are a stub area on any router
+    fn add_stub_areas(&mut self) -> Result<(), Box<dyn Error>> {
+        let stub_areas: BTreeSet<OspfArea> =
+            self.routers.iter().flat_map(|r| r.stub_areas.iter().copied()).collect();
+        for area in stub_areas {
+            self.config.add(ConfigExpr::OspfStubArea { area })?;
+        }
+        Ok(())
+    }
+
+    /// Create all BGP sessions, and generate all external routers
+    fn add_bgp_sessions(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut sessions: HashMap<(RouterId, RouterId), ConfigExpr> = HashMap::new();
+        for i in 0..self.routers.len() {
+            let id = self.ids[i];
+            let local_as = self.routers[i].as_id;
+            for neighbor in self.routers[i].neighbors.iter().filter(|n| n.is_address()) {
+                let remote_as = self.routers[i].remote_as(neighbor).ok_or_else(|| {
+                    format!(
+                        "No remote-as for neighbor {} on {}",
+                        neighbor.name, self.routers[i].name
+                    )
+                })?;
+                let is_ebgp = Some(remote_as) != local_as;
+
+                let peer = match self.addresses.get(&neighbor.name) {
+                    Some(peer) => *peer,
+                    None if is_ebgp => {
+                        let ext = self.net.add_external_router(&neighbor.name, AsId(remote_as));
+                        if !self.routers[i]
+                            .ifaces
+                            .iter()
+                            .any(|iface| iface.contains(&neighbor.name))
+                        {
+                            return Err(format!(
+                                "eBGP neighbor {} of {} is not directly connected",
+                                neighbor.name, self.routers[i].name
+                            )
+                            .into());
+                        }
+                        self.net.add_link(id, ext);
+                        self.config.add(ConfigExpr::IgpLinkWeight {
+                            source: id,
+                            target: ext,
+                            weight: EXTERNAL_LINK_WEIGHT,
+                        })?;
+                        self.addresses.insert(neighbor.name.clone(), ext);
+                        ext
+                    }
+                    None => {
+                        return Err(format!(
+                            "iBGP neighbor {} of {} is unknown",
+                            neighbor.name, self.routers[i].name
+                        )
+                        .into())
+                    }
+                };
+
+                let expr = if is_ebgp {
+                    if self.ids.contains(&peer) {
+                        return Err(format!(
+                            "eBGP session between {} and internal router {} is not supported",
+                            self.routers[i].name, neighbor.name
+                        )
+                        .into());
+                    }
+                    ConfigExpr::BgpSession {
+                        source: id,
+                        target: peer,
+                        session_type: BgpSessionType::EBgp,
+                    }
+                } else if self.routers[i].is_rr_client(neighbor) {
+                    ConfigExpr::BgpSession {
+                        source: id,
+                        target: peer,
+                        session_type: BgpSessionType::IBgpClient,
+                    }
+                } else {
+                    ConfigExpr::BgpSession {
+                        source: id,
+                        target: peer,
+                        session_type: BgpSessionType::IBgpPeer,
+                    }
+                };
+
+                // both sides of an iBGP session are configured. Only the route reflector knows
+                // that it is a client session.
+                let key = if id < peer { (id, peer) } else { (peer, id) };
+                match (sessions.get(&key), &expr) {
+                    (
+                        Some(ConfigExpr::BgpSession {
+                            session_type: BgpSessionType::IBgpClient,
+                            ..
+                        }),
+                        ConfigExpr::BgpSession { session_type: BgpSessionType::IBgpClient, .. },
+                    ) => {
+                        return Err(format!(
+                            "Routers {} and {} are both route reflectors of each other",
+                            self.routers[i].name, neighbor.name
+                        )
+                        .into())
+                    }
+                    (
+                        Some(ConfigExpr::BgpSession {
+                            session_type: BgpSessionType::IBgpClient,
+                            ..
+                        }),
+                        _,
+                    ) => {}
+                    _ => {
+                        sessions.insert(key, expr);
+                    }
+                }
+            }
+        }
+
+        let mut sessions: Vec<_> = sessions.into_iter().collect();
+        sessions.sort_by_key(|(k, _)| *k);
+        for (_, expr) in sessions {
+            self.config.add(expr)?;
+        }
+        Ok(())
+    }
+
+    /// Translate all route-maps which are applied on a neighbor or peer-group
+    fn add_route_maps(&mut self) -> Result<(), Box<dyn Error>> {
+        // the routers are moved out, such that prefixes can be assigned while building the maps.
+        let routers = std::mem::take(&mut self.routers);
+        let ids = self.ids.clone();
+        let result =
+            routers.iter().zip(ids).try_for_each(|(router, id)| self.add_route_maps_of(router, id));
+        self.routers = routers;
+        result
+    }
+
+    /// Translate all route-maps of a single router
+    fn add_route_maps_of(
+        &mut self,
+        router: &FrrRouter,
+        id: RouterId,
+    ) -> Result<(), Box<dyn Error>> {
+        let all_neighbors: HashSet<&str> =
+            router.neighbors.iter().filter(|n| n.is_address()).map(|n| n.name.as_str()).collect();
+
+        // collect the neighbors on which each route-map is applied
+        let mut applied: Vec<(&str, RouteMapDirection, HashSet<&str>)> = Vec::new();
+        for neighbor in router.neighbors.iter() {
+            for (name, direction) in neighbor.route_maps.iter() {
+                let members: Vec<&str> = if neighbor.is_address() {
+                    vec![neighbor.name.as_str()]
+                } else {
+                    router
+                        .neighbors
+                        .iter()
+                        .filter(|n| n.peer_group.as_ref() == Some(&neighbor.name))
+                        .map(|n| n.name.as_str())
+                        .collect()
+                };
+                match applied.iter_mut().find(|(n, d, _)| n == name && d == direction) {
+                    Some((_, _, set)) => set.extend(members),
+                    None => applied.push((name, *direction, members.into_iter().collect())),
+                }
+            }
+        }
+
+        // snowcap has a single sequence of route-maps per direction. Hence, the entries are
+        // renumbered, with one block of entries for each neighbor on which the route-map applies.
+        let mut exprs = Vec::new();
+        let mut next_order = HashMap::new();
+        for (name, direction, neighbors) in applied {
+            let blocks: Vec<Option<RouterId>> = if neighbors.is_empty() {
+                continue;
+            } else if neighbors == all_neighbors {
+                vec![None]
+            } else {
+                let mut addrs: Vec<&str> = neighbors.into_iter().collect();
+                addrs.sort_unstable();
+                addrs
+                    .into_iter()
+                    .map(|addr| {
+                        self.addresses
+                            .get(addr)
+                            .copied()
+                            .map(Some)
+                            .ok_or_else(|| unknown_address(addr))
+                    })
+                    .collect::<Result<_, _>>()?
+            };
+
+            let mut entries: Vec<&FrrRouteMapEntry> =
+                router.route_maps.iter().filter(|rm| rm.name == name).collect();
+            if entries.is_empty() {
+                return Err(format!("Route-map {} on {} is not defined", name, router.name).into());
+            }
+            entries.sort_by_key(|entry| entry.order);
+            // FRR denies all routes that match no entry, while snowcap accepts them. Hence, a last
+            // entry that permits everything is the same as no entry at all, and otherwise, the
+            // block is terminated by an entry that denies everything.
+            let last = entries.last().unwrap();
+            let permit_all = last.permit && last.matches.is_empty() && last.sets.is_empty();
+            if permit_all {
+                entries.pop();
+            }
+
+            for neighbor in blocks {
+                let order = next_order.entry(direction).or_insert(0);
+                for entry in entries.iter() {
+                    *order += 10;
+                    let map = self.build_route_map(router, entry, *order, neighbor)?;
+                    exprs.push(ConfigExpr::BgpRouteMap { router: id, direction, map });
+                }
+                if !permit_all {
+                    *order += 10;
+                    let mut builder = RouteMapBuilder::new();
+                    builder.order(*order).deny();
+                    if let Some(n) = neighbor {
+                        builder.match_neighbor(n);
+                    }
+                    exprs.push(ConfigExpr::BgpRouteMap {
+                        router: id,
+                        direction,
+                        map: builder.build(),
+                    });
+                }
+            }
+        }
+
+        // the exporter terminates each sequence with an entry of order 65535
+        if next_order.values().any(|order| *order >= 65535) {
+            return Err(format!("Too many route-map entries on {}", router.name).into());
+        }
+        for expr in exprs {
+            self.config.add(expr)?;
+        }
+        Ok(())
+    }
+
+    /// Translate a single route-map entry of the router.
+    fn build_route_map(
+        &mut self,
+        router: &FrrRouter,
+        entry: &FrrRouteMapEntry,
+        order: usize,
+        neighbor: Option<RouterId>,
+    ) -> Result<snowcap::netsim::route_map::RouteMap, Box<dyn Error>> {
+        let mut builder = RouteMapBuilder::new();
+        builder.order(order);
+        if entry.permit {
+            builder.allow();
+        } else {
+            builder.deny();
+        }
+        if let Some(n) = neighbor {
+            builder.match_neighbor(n);
+        }
+
+        for clause in entry.matches.iter() {
+            let tokens: Vec<&str> = clause.split_whitespace().collect();
+            match tokens.as_slice() {
+                ["peer", addr] => {
+                    builder.match_neighbor(self.router_by_addr(addr)?);
+                }
+                ["ip", "address", "prefix-list", name] => {
+                    let entry = match router.prefix_lists.get(*name).map(|l| l.as_slice()) {
+                        Some([entry]) if entry.permit => entry.clone(),
+                        Some(_) => {
+                            return Err(format!(
+                                "Prefix-list {} must contain exactly one permit entry",
+                                name
+                            )
+                            .into())
+                        }
+                        None => return Err(format!("Prefix-list {} is not defined", name).into()),
+                    };
+                    // snowcap matches either the exact prefix, or all prefixes contained in it
+                    let prefix = self.prefix(entry.net.clone())?;
+                    let len = prefix.prefix_len() as u32;
+                    let max_len = if prefix.is_ipv4() { 32 } else { 128 };
+                    match entry.lengths(max_len) {
+                        (ge, le) if ge == len && le == len => builder.match_prefix(prefix),
+                        (ge, le) if ge == len && le == max_len => {
+                            builder.match_prefix_range(prefix)
+                        }
+                        (ge, le) => {
+                            return Err(format!(
+                                "Prefix-list {} with ge {} le {} is not supported",
+                                name, ge, le
+                            )
+                            .into())
+                        }
+                    };
+                }
+                ["ip", "next-hop", "address", addr] => {
+                    builder.match_next_hop(self.router_by_addr(addr)?);
+                }
+                ["community", name] => {
+                    match router.community_lists.get(*name).map(|l| l.as_slice()) {
+                        Some([c]) => builder.match_community(*c),
+                        Some(_) => {
+                            return Err(format!(
+                                "Community-list {} must contain exactly one community",
+                                name
+                            )
+                            .into())
+                        }
+                        None => {
+                            return Err(format!("Community-list {} is not defined", name).into())
+                        }
+                    };
+                }
+                _ => return Err(format!("Unsupported match clause: match {}", clause).into()),
+            }
+        }
+
+        for clause in entry.sets.iter() {
+            let tokens: Vec<&str> = clause.split_whitespace().collect();
+            match tokens.as_slice() {
+                ["local-preference", lp] => {
+                    builder.set_local_pref(parse_num(lp)?);
+                }
+                ["metric", med] => {
+                    builder.set_med(parse_num(med)?);
+                }
+                ["ip", "next-hop", addr] => {
+                    builder.set_next_hop(self.router_by_addr(addr)?);
+                }
+                ["community", "none"] => {
+                    builder.reset_community();
+                }
+                ["community", communities @ ..] => {
+                    let (communities, additive) = split_additive(communities);
+                    for (i, c) in communities.iter().enumerate() {
+                        if i == 0 && !additive {
+                            builder.set_community(parse_community(c)?);
+                        } else {
+                            builder.add_community(parse_community(c)?);
+                        }
+                    }
+                }
+                ["large-community", "none"] => {
+                    builder.reset_large_community();
+                }
+                ["large-community", communities @ ..] => {
+                    let (communities, additive) = split_additive(communities);
+                    for (i, c) in communities.iter().enumerate() {
+                        if i == 0 && !additive {
+                            builder.set_large_community(parse_large_community(c)?);
+                        } else {
+                            builder.add_large_community(parse_large_community(c)?);
+                        }
+                    }
+                }
+                ["as-path", "prepend", path @ ..] if !path.is_empty() => {
+                    // prepend the last AS first, such that the path ends up in the given order
+                    for as_id in path.iter().rev() {
+                        builder.prepend_as_path(AsId(parse_num(as_id)?), 1);
+                    }
+                }
+                ["origin", "igp"] => {
+                    builder.set_origin(Origin::Igp);
+                }
+                ["origin", "egp"] => {
+                    builder.set_origin(Origin::Egp);
+                }
+                ["origin", "incomplete"] => {
+                    builder.set_origin(Origin::Incomplete);
+                }
+                _ => return Err(format!("Unsupported set clause: set {}", clause).into()),
+            }
+        }
+
+        Ok(builder.build())
+    }
+
+    /// Translate all static routes
+    fn add_static_routes(&mut self) -> Result<(), Box<dyn Error>> {
+        for i in 0..self.routers.len() {
+            for (net, next_hop) in self.routers[i].static_routes.clone() {
+                let prefix = self.prefix(net)?;
+                let target = self.router_by_addr(&next_hop)?;
+                self.config.add(ConfigExpr::StaticRoute { router: self.ids[i], prefix, target })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the router which owns the address
+    fn router_by_addr(&self, addr: &str) -> Result<RouterId, Box<dyn Error>> {
+        self.addresses.get(addr).copied().ok_or_else(|| unknown_address(addr))
+    }
+
+    /// Get the prefix of an IP network, and remember it.
+    fn prefix(&mut self, net: IpAddr) -> Result<Prefix, Box<dyn Error>> {
+        let prefix: Prefix = net.to_string().parse()?;
+        self.prefixes.insert(prefix);
+        Ok(prefix)
+    }
+}
+
+fn unknown_address(addr: &str) -> Box<dyn Error> {
+    format!("Address {} does not belong to any router", addr).into()
+}
+
+fn parse_num(s: &str) -> Result<u32, Box<dyn Error>> {
+    s.parse().map_err(|_| format!("Invalid number: {}", s).into())
+}
+
+/// Split the optional trailing `additive` keyword from a list of communities.
+fn split_additive<'a, 'b>(communities: &'a [&'b str]) -> (&'a [&'b str], bool) {
+    match communities.split_last() {
+        Some((&"additive", rest)) => (rest, true),
+        _ => (communities, false),
+    }
+}
+
+/// Parse a large community in the form `GLOBAL:LOCAL1:LOCAL2`.
+fn parse_large_community(s: &str) -> Result<LargeCommunity, Box<dyn Error>> {
+    match s.split(':').collect::<Vec<_>>().as_slice() {
+        [a, b, c] => Ok(LargeCommunity::new(parse_num(a)?, parse_num(b)?, parse_num(c)?)),
+        _ => Err(format!("Invalid large community: {}", s).into()),
+    }
+}
+
+/// Parse a community, either as a single number, or in the form `AS:VAL`.
+fn parse_community(s: &str) -> Result<u32, Box<dyn Error>> {
+    match s.split(':').collect::<Vec<_>>().as_slice() {
+        [c] => parse_num(c),
+        [a, b] => Ok((parse_num(a)? << 16) | (parse_num(b)? & 0xffff)),
+        _ => Err(format!("Invalid community: {}", s).into()),
+    }
+}
+
+/// Parse an OSPF area, either as a number, or in the form of an address.
+fn parse_area(s: &str) -> Result<OspfArea, Box<dyn Error>> {
+    match s.parse::<std::net::Ipv4Addr>() {
+        Ok(addr) => Ok(OspfArea(u32::from(addr))),
+        Err(_) => Ok(OspfArea(parse_num(s)?)),
+    }
+}
+
+/// Convert the address to a number
+fn addr_to_u32(addr: &IpAddr) -> u32 {
+    addr.addr_parts().iter().fold(0, |acc, x| (acc << 8) | *x as u32)
+}
+
+/// Returns `true` if the address (without mask) is inside the network
+fn net_contains(net: &IpAddr, addr: &str) -> bool {
+    let mask = if net.mask == 0 { 0 } else { u32::MAX << (32 - net.mask.min(32)) };
+    addr_to_u32(&IpAddr::new(addr, 32)) & mask == addr_to_u32(net) & mask
+}
+
+/// Parsed running-config of a single FRR router
+#[derive(Debug, Default)]
+struct FrrRouter {
+    name: String,
+    as_id: Option<u32>,
+    ifaces: Vec<FrrIface>,
+    ospf_networks: Vec<(IpAddr, OspfArea)>,
+    stub_areas: Vec<OspfArea>,
+    neighbors: Vec<FrrNeighbor>,
+    route_maps: Vec<FrrRouteMapEntry>,
+    prefix_lists: HashMap<String, Vec<FrrPrefixListEntry>>,
+    community_lists: HashMap<String, Vec<u32>>,
+    static_routes: Vec<(IpAddr, String)>,
+    bgp_decision: BgpDecisionConfig,
+    cluster_id: Option<String>,
+    max_paths_ebgp: Option<u32>,
+    max_paths_ibgp: Option<u32>,
+}
+
+/// Section of the configuration which is currently parsed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    Global,
+    Interface,
+    Ospf,
+    Bgp,
+    RouteMap,
+}
+
+impl FrrRouter {
+    /// Parse the running-config of a router
+    fn parse(running_config: &str) -> Result<Self, Box<dyn Error>> {
+        let mut router = Self::default();
+        let mut section = Section::Global;
+
+        for line in running_config.lines() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let error = || -> Box<dyn Error> { format!("Invalid line: {}", line.trim()).into() };
+            match (section, tokens.as_slice()) {
+                // empty lines and comments. Only unindented comments terminate the section.
+                (_, []) => {}
+                (_, _) if line.starts_with('!') => section = Section::Global,
+                (_, [c, ..]) if c.starts_with('!') => {}
+                (_, ["end"]) => section = Section::Global,
+                (Section::Bgp, ["exit-address-family"])
+                | (Section::Bgp, ["address-family", ..]) => {}
+                (_, ["exit"]) => section = Section::Global,
+
+                // global commands, which also terminate the current section
+                (_, ["hostname", name]) => {
+                    router.name = name.to_string();
+                    section = Section::Global;
+                }
+                (_, ["interface", ..]) => {
+                    router.ifaces.push(FrrIface::default());
+                    section = Section::Interface;
+                }
+                (_, ["router", "ospf", ..]) => section = Section::Ospf,
+                (_, ["router", "bgp", as_id, ..]) => {
+                    router.as_id = Some(parse_num(as_id)?);
+                    section = Section::Bgp;
+                }
+                (_, ["route-map", name, state, order]) => {
+                    let permit = match *state {
+                        "permit" => true,
+                        "deny" => false,
+                        _ => return Err(error()),
+                    };
+                    router.route_maps.push(FrrRouteMapEntry {
+                        name: name.to_string(),
+                        permit,
+                        order: parse_num(order)? as usize,
+                        matches: Vec::new(),
+                        sets: Vec::new(),
+                    });
+                    section = Section::RouteMap;
+                }
+                (_, ["ip", "route", net, next_hop, ..]) => {
+                    router
+                        .static_routes
+                        .push((IpAddr::try_from_str(net)?.get_network(), next_hop.to_string()));
+                    section = Section::Global;
+                }
+                (_, ["ip", "prefix-list", name, "seq", _, action, net, bounds @ ..])
+                | (_, ["ip", "prefix-list", name, action, net, bounds @ ..]) => {
+                    let permit = match *action {
+                        "permit" => true,
+                        "deny" => false,
+                        _ => return Err(error()),
+                    };
+                    let mut entry = FrrPrefixListEntry {
+                        permit,
+                        net: IpAddr::try_from_str(net)?.get_network(),
+                        ge: None,
+                        le: None,
+                    };
+                    for bound in bounds.chunks(2) {
+                        match bound {
+                            ["ge", len] => entry.ge = Some(parse_num(len)?),
+                            ["le", len] => entry.le = Some(parse_num(len)?),
+                            _ => return Err(error()),
+                        }
+                    }
+                    router.prefix_lists.entry(name.to_string()).or_default().push(entry);
+                    section = Section::Global;
+                }
+                (_, ["bgp", "community-list", "standard", name, "permit", c])
+                | (_, ["bgp", "community-list", name, "permit", c])
+                | (_, ["ip", "community-list", "standard", name, "permit", c])
+                | (_, ["ip", "community-list", name, "permit", c]) => {
+                    router
+                        .community_lists
+                        .entry(name.to_string())
+                        .or_default()
+                        .push(parse_community(c)?);
+                    section = Section::Global;
+                }
+
+                // interface
+                (Section::Interface, ["ip", "address", addr]) => {
+                    router.ifaces.last_mut().unwrap().addr = Some(IpAddr::try_from_str(addr)?);
+                }
+                (Section::Interface, ["ip", "ospf", "cost", cost]) => {
+                    router.ifaces.last_mut().unwrap().cost = Some(parse_num(cost)?);
+                }
+                (Section::Interface, ["ip", "ospf", "area", area])
+                | (Section::Interface, ["ip", "ospf", _, "area", area]) => {
+                    let iface = router.ifaces.last_mut().unwrap();
+                    iface.ospf = true;
+                    iface.area = Some(parse_area(area)?);
+                }
+                (Section::Interface, ["shutdown"]) => {
+                    router.ifaces.last_mut().unwrap().shutdown = true;
+                }
+
+                // router ospf
+                (Section::Ospf, ["network", net, "area", area]) => {
+                    router.ospf_networks.push((IpAddr::try_from_str(net)?, parse_area(area)?));
+                }
+                (Section::Ospf, ["area", area, "stub"]) => {
+                    router.stub_areas.push(parse_area(area)?);
+                }
+
+                // router bgp
+                (Section::Bgp, ["neighbor", name, "remote-as", remote_as]) => {
+                    router.neighbor(name).remote_as = match *remote_as {
+                        "internal" => router.as_id,
+                        remote_as => Some(parse_num(remote_as)?),
+                    };
+                }
+                (Section::Bgp, ["neighbor", name, "peer-group"]) => {
+                    router.neighbor(name);
+                }
+                (Section::Bgp, ["neighbor", name, "peer-group", group]) => {
+                    router.neighbor(name).peer_group = Some(group.to_string());
+                }
+                (Section::Bgp, ["bgp", "cluster-id", cluster_id]) => {
+                    router.cluster_id = Some(cluster_id.to_string());
+                }
+                (Section::Bgp, ["bgp", "always-compare-med"]) => {
+                    router.bgp_decision.always_compare_med = true;
+                }
+                (Section::Bgp, ["bgp", "bestpath", "med", options @ ..])
+                    if options.contains(&"missing-as-worst") =>
+                {
+                    router.bgp_decision.med_missing_as_worst = true;
+                }
+                (Section::Bgp, ["maximum-paths", "ibgp", max_paths, ..]) => {
+                    router.max_paths_ibgp = Some(parse_num(max_paths)?);
+                }
+                (Section::Bgp, ["maximum-paths", max_paths]) => {
+                    router.max_paths_ebgp = Some(parse_num(max_paths)?);
+                }
+                (Section::Bgp, ["neighbor", name, "route-reflector-client"]) => {
+                    router.neighbor(name).rr_client = true;
+                }
+                (Section::Bgp, ["neighbor", name, "route-map", map, direction]) => {
+                    let direction = match *direction {
+                        "in" => RouteMapDirection::Incoming,
+                        "out" => RouteMapDirection::Outgoing,
+                        _ => return Err(error()),
+                    };
+                    router.neighbor(name).route_maps.push((map.to_string(), direction));
+                }
+
+                // route-map
+                (Section::RouteMap, ["match", ..]) => {
+                    router.route_maps.last_mut().unwrap().matches.push(tokens[1..].join(" "));
+                }
+                (Section::RouteMap, ["set", ..]) => {
+                    router.route_maps.last_mut().unwrap().sets.push(tokens[1..].join(" "));
+                }
+
+                _ => debug!("Ignoring line: {}", line.trim()),
+            }
+        }
+
+        if router.name.is_empty() {
+            return Err("Running-config does not contain a hostname".into());
+        }
+        Ok(router)
+    }
+
+    /// Get the neighbor (or peer-group) with the given name, or create it.
+    fn neighbor(&mut self, name: &str) -> &mut FrrNeighbor {
+        match self.neighbors.iter().position(|n| n.name == name) {
+            Some(pos) => &mut self.neighbors[pos],
+            None => {
+                self.neighbors.push(FrrNeighbor { name: name.to_string(), ..Default::default() });
+                self.neighbors.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// Get the peer-group of a neighbor
+    fn peer_group(&self, neighbor: &FrrNeighbor) -> Option<&FrrNeighbor> {
+        neighbor
+            .peer_group
+            .as_ref()
+            .and_then(|group| self.neighbors.iter().find(|n| &n.name == group))
+    }
+
+    /// Get the remote AS of a neighbor, inheriting it from the peer-group.
+    fn remote_as(&self, neighbor: &FrrNeighbor) -> Option<u32> {
+        neighbor.remote_as.or_else(|| self.peer_group(neighbor).and_then(|g| g.remote_as))
+    }
+
+    /// Returns `true` if the neighbor (or its peer-group) is a route-reflector client.
+    fn is_rr_client(&self, neighbor: &FrrNeighbor) -> bool {
+        neighbor.rr_client || self.peer_group(neighbor).map(|g| g.rr_client).unwrap_or(false)
+    }
+
+    /// Returns the OSPF cost of the interface, or `None` if OSPF is not enabled on it.
+    fn ospf_cost(&self, iface: &FrrIface) -> Option<u32> {
+        let addr = iface.addr.as_ref()?;
+        let enabled =
+            iface.ospf || self.ospf_networks.iter().any(|(n, _)| net_contains(n, &addr.addr));
+        if enabled && !iface.shutdown {
+            Some(iface.cost.unwrap_or(DEFAULT_OSPF_COST))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the OSPF area of the interface. Interfaces without OSPF are in the backbone.
+    fn ospf_area(&self, iface: &FrrIface) -> OspfArea {
+        iface
+            .area
+            .or_else(|| {
+                let addr = iface.addr.as_ref()?;
+                self.ospf_networks.iter().find(|(n, _)| net_contains(n, &addr.addr)).map(|x| x.1)
+            })
+            .unwrap_or(OspfArea::BACKBONE)
+    }
+}
+
+/// Parsed interface block
+#[derive(Debug, Default)]
+struct FrrIface {
+    addr: Option<IpAddr>,
+    ospf: bool,
+    area: Option<OspfArea>,
+    cost: Option<u32>,
+    shutdown: bool,
+}
+
+impl FrrIface {
+    /// Returns `true` if both interfaces are in the same subnet
+    fn same_subnet(&self, other: &Self) -> bool {
+        match (self.addr.as_ref(), other.addr.as_ref()) {
+            (Some(a), Some(b)) => {
+                a.mask < 32 && a.mask == b.mask && a.addr != b.addr && net_contains(a, &b.addr)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the address is in the subnet of the interface
+    fn contains(&self, addr: &str) -> bool {
+        self.addr.as_ref().map(|a| a.mask < 32 && net_contains(a, addr)).unwrap_or(false)
+    }
+}
+
+/// Parsed BGP neighbor or peer-group
+#[derive(Debug, Default)]
+struct FrrNeighbor {
+    name: String,
+    remote_as: Option<u32>,
+    peer_group: Option<String>,
+    rr_client: bool,
+    route_maps: Vec<(String, RouteMapDirection)>,
+}
+
+impl FrrNeighbor {
+    /// Returns `true` if the neighbor is an address, and not a peer-group
+    fn is_address(&self) -> bool {
+        self.name.parse::<std::net::Ipv4Addr>().is_ok()
+    }
+}
+
+/// Parsed entry of a prefix-list
+#[derive(Debug, Clone)]
+struct FrrPrefixListEntry {
+    permit: bool,
+    net: IpAddr,
+    ge: Option<u32>,
+    le: Option<u32>,
+}
+
+impl FrrPrefixListEntry {
+    /// Range of prefix lengths matched by the entry. Like in FRR, `ge` without `le` matches up to
+    /// the full length, and `le` without `ge` matches from the length of the network.
+    fn lengths(&self, max_len: u32) -> (u32, u32) {
+        match (self.ge, self.le) {
+            (None, None) => (self.net.mask, self.net.mask),
+            (ge, le) => (ge.unwrap_or(self.net.mask), le.unwrap_or(max_len)),
+        }
+    }
+}
+
+/// Parsed entry of a route-map
+#[derive(Debug)]
+struct FrrRouteMapEntry {
+    name: String,
+    permit: bool,
+    order: usize,
+    matches: Vec<String>,
+    sets: Vec<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use snowcap::netsim::route_map::RouteMapBuilder;
+
+    const R1: &str = "
+hostname r1
+!
+interface lo
+ ip address 10.0.1.1/32
+!
+interface eth0
+ ip address 10.1.0.1/24
+ ip ospf 1 area 0
+ ip ospf cost 5
+!
+interface eth1
+ ip address 10.1.1.1/24
+!
+interface eth2
+ ip address 172.16.0.1/24
+!
+router ospf 1
+ network 10.1.1.0/24 area 0
+!
+router bgp 65001
+ bgp cluster-id 10.0.2.1
+ bgp always-compare-med
+ bgp deterministic-med
+ bgp bestpath med missing-as-worst
+ neighbor internal peer-group
+ neighbor internal remote-as 65001
+ neighbor internal route-reflector-client
+ neighbor 10.0.2.1 peer-group internal
+ neighbor 10.0.3.1 peer-group internal
+ neighbor 172.16.0.2 remote-as 65100
+ !
+ address-family ipv4 unicast
+  neighbor 172.16.0.2 route-map ext_in in
+  maximum-paths 1
+  maximum-paths ibgp 1
+ exit-address-family
+!
+ip prefix-list p0 seq 5 permit 200.0.0.0/8
+!
+route-map ext_in permit 10
+ match ip address prefix-list p0
+ set local-preference 200
+ set community 65001:10 65001:20 additive
+ set large-community 65001:1:2
+ set as-path prepend 65001 65002
+ set origin egp
+!
+route-map ext_in permit 65535
+!
+";
+
+    const R2: &str = "
+hostname r2
+interface lo
+ ip address 10.0.2.1/32
+interface eth0
+ ip address 10.1.0.2/24
+ ip ospf 1 area 0
+ ip ospf cost 7
+interface eth1
+ ip address 10.1.2.1/24
+ ip ospf 1 area 0.0.0.1
+router ospf 1
+ area 0.0.0.1 stub
+router bgp 65001
+ neighbor 10.0.1.1 remote-as 65001
+ip route 201.0.0.0/8 10.1.2.2
+";
+
+    const R3: &str = "
+hostname r3
+interface lo
+ ip address 10.0.3.1/32
+interface eth0
+ ip address 10.1.1.2/24
+ ip ospf area 0
+interface eth1
+ ip address 10.1.2.2/24
+ ip ospf area 1
+ ip ospf cost 3
+router bgp 65001
+ neighbor 10.0.1.1 remote-as internal
+";
+
+    #[test]
+    fn import_network() {
+        let imported = import_frr_configs(&[R1, R2, R3]).unwrap();
+        let net = &imported.net;
+        let r1 = net.get_router_id("r1").unwrap();
+        let r2 = net.get_router_id("r2").unwrap();
+        let r3 = net.get_router_id("r3").unwrap();
+        let e1 = net.get_router_id("172.16.0.2").unwrap();
+        assert_eq!(net.get_external_routers(), vec![e1]);
+        assert_eq!(net.get_device(e1).unwrap_external().as_id(), AsId(65100));
+        assert_eq!(
+            net.get_device(r1).unwrap_internal().get_bgp_decision_config(),
+            &BgpDecisionConfig {
+                always_compare_med: true,
+                med_missing_as_worst: true,
+                multipath: false
+            }
+        );
+        assert_eq!(
+            net.get_device(r2).unwrap_internal().get_bgp_decision_config(),
+            &BgpDecisionConfig { multipath: true, ..Default::default() }
+        );
+        assert_eq!(net.get_device(r1).unwrap_internal().get_bgp_cluster_id(), Some(r2));
+        assert_eq!(net.get_device(r2).unwrap_internal().get_bgp_cluster_id(), None);
+
+        let p0: Prefix = "200.0.0.0/8".parse().unwrap();
+        let p1: Prefix = "201.0.0.0/8".parse().unwrap();
+        assert_eq!(imported.prefixes, vec![p0, p1].into_iter().collect());
+
+        let mut c = Config::new();
+        let w = |source, target, weight| ConfigExpr::IgpLinkWeight { source, target, weight };
+        c.add(w(r1, r2, 5.0)).unwrap();
+        c.add(w(r2, r1, 7.0)).unwrap();
+        c.add(w(r1, r3, 10.0)).unwrap();
+        c.add(w(r3, r1, 10.0)).unwrap();
+        c.add(w(r2, r3, 10.0)).unwrap();
+        c.add(w(r3, r2, 3.0)).unwrap();
+        c.add(w(r1, e1, 1.0)).unwrap();
+        c.add(ConfigExpr::IgpLinkArea { source: r2, target: r3, area: OspfArea(1) }).unwrap();
+        c.add(ConfigExpr::OspfStubArea { area: OspfArea(1) }).unwrap();
+        let s =
+            |source, target, session_type| ConfigExpr::BgpSession { source, target, session_type };
+        c.add(s(r1, r2, BgpSessionType::IBgpClient)).unwrap();
+        c.add(s(r1, r3, BgpSessionType::IBgpClient)).unwrap();
+        c.add(s(r1, e1, BgpSessionType::EBgp)).unwrap();
+        c.add(ConfigExpr::BgpRouteMap {
+            router: r1,
+            direction: RouteMapDirection::Incoming,
+            map: RouteMapBuilder::new()
+                .order(10)
+                .allow()
+                .match_neighbor(e1)
+                .match_prefix(p0)
+                .set_local_pref(200)
+                .add_community((65001 << 16) | 10)
+                .add_community((65001 << 16) | 20)
+                .set_large_community(LargeCommunity::new(65001, 1, 2))
+                .prepend_as_path(AsId(65002), 1)
+                .prepend_as_path(AsId(65001), 1)
+                .set_origin(Origin::Egp)
+                .build(),
+        })
+        .unwrap();
+        c.add(ConfigExpr::StaticRoute { router: r2, prefix: p1, target: r3 }).unwrap();
+        let decision = |router, config| ConfigExpr::BgpDecision { router, config };
+        c.add(decision(
+            r1,
+            BgpDecisionConfig {
+                always_compare_med: true,
+                med_missing_as_worst: true,
+                multipath: false,
+            },
+        ))
+        .unwrap();
+        let multipath = BgpDecisionConfig { multipath: true, ..Default::default() };
+        c.add(decision(r2, multipath)).unwrap();
+        c.add(decision(r3, multipath)).unwrap();
+        c.add(ConfigExpr::BgpClusterId { router: r1, cluster_id: r2 }).unwrap();
+
+        assert_eq!(imported.config, c);
+        assert_eq!(imported.config.len(), c.len());
+        assert_eq!(net.current_config(), &c);
+    }
+
+    #[test]
+    fn import_route_maps() {
+        // no trailing permit-all, a route-map shared by two neighbors, and a prefix-list range
+        let r1 = R1
+            .replace("route-map ext_in permit 65535\n", "")
+            .replace("permit 200.0.0.0/8", "permit 200.0.0.0/8 le 32")
+            .replace(
+                "  maximum-paths 1\n",
+                "  neighbor internal route-map int_out out\n  maximum-paths 1\n",
+            )
+            + "route-map int_out permit 10\n set metric 10\n!\n";
+        let imported = import_frr_configs(&[r1.as_str(), R2, R3]).unwrap();
+        let net = &imported.net;
+        let r1 = net.get_router_id("r1").unwrap();
+        let r2 = net.get_router_id("r2").unwrap();
+        let r3 = net.get_router_id("r3").unwrap();
+        let e1 = net.get_router_id("172.16.0.2").unwrap();
+        let p0: Prefix = "200.0.0.0/8".parse().unwrap();
+
+        let mut maps: Vec<_> = imported
+            .config
+            .iter()
+            .filter_map(|expr| match expr {
+                ConfigExpr::BgpRouteMap { router, direction, map } if *router == r1 => {
+                    Some((*direction, map.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        maps.sort_by_key(|(direction, map)| {
+            (*direction == RouteMapDirection::Outgoing, map.order())
+        });
+
+        let incoming = RouteMapDirection::Incoming;
+        let outgoing = RouteMapDirection::Outgoing;
+        assert_eq!(
+            maps,
+            vec![
+                (
+                    incoming,
+                    RouteMapBuilder::new()
+                        .order(10)
+                        .allow()
+                        .match_neighbor(e1)
+                        .match_prefix_range(p0)
+                        .set_local_pref(200)
+                        .add_community((65001 << 16) | 10)
+                        .add_community((65001 << 16) | 20)
+                        .set_large_community(LargeCommunity::new(65001, 1, 2))
+                        .prepend_as_path(AsId(65002), 1)
+                        .prepend_as_path(AsId(65001), 1)
+                        .set_origin(Origin::Egp)
+                        .build()
+                ),
+                (incoming, RouteMapBuilder::new().order(20).deny().match_neighbor(e1).build()),
+                (
+                    outgoing,
+                    RouteMapBuilder::new().order(10).allow().match_neighbor(r2).set_med(10).build()
+                ),
+                (outgoing, RouteMapBuilder::new().order(20).deny().match_neighbor(r2).build()),
+                (
+                    outgoing,
+                    RouteMapBuilder::new().order(30).allow().match_neighbor(r3).set_med(10).build()
+                ),
+                (outgoing, RouteMapBuilder::new().order(40).deny().match_neighbor(r3).build()),
+            ]
+        );
+    }
+
+    #[test]
+    fn import_errors() {
+        // missing hostname
+        assert!(import_frr_configs(&["router bgp 65001"]).is_err());
+        // unknown iBGP neighbor
+        assert!(import_frr_configs(&[R2]).is_err());
+        // unsupported match clause
+        let r1 = R1.replace("match ip address prefix-list p0", "match as-path 1");
+        assert!(import_frr_configs(&[r1.as_str(), R2, R3]).is_err());
+        // cluster-id which is not a router address
+        let r1 = R1.replace("bgp cluster-id 10.0.2.1", "bgp cluster-id 1.2.3.4");
+        assert!(import_frr_configs(&[r1.as_str(), R2, R3]).is_err());
+        // multipath only for iBGP
+        let r1 = R1.replace("  maximum-paths 1\n", "");
+        assert!(import_frr_configs(&[r1.as_str(), R2, R3]).is_err());
+        // link in two different areas
+        let r3 = R3.replace("ip ospf area 1", "ip ospf area 2");
+        assert!(import_frr_configs(&[R1, R2, r3.as_str()]).is_err());
+        // invalid large community
+        let r1 = R1.replace("65001:1:2", "65001:1");
+        assert!(import_frr_configs(&[r1.as_str(), R2, R3]).is_err());
+        // prefix-list bounds that cannot be represented
+        let r1 = R1.replace("permit 200.0.0.0/8", "permit 200.0.0.0/8 le 24");
+        assert!(import_frr_configs(&[r1.as_str(), R2, R3]).is_err());
+        let r1 = R1.replace("permit 200.0.0.0/8", "permit 200.0.0.0/8 ge 16");
+        assert!(import_frr_configs(&[r1.as_str(), R2, R3]).is_err());
+    }
+}
@@ -20,12 +20,17 @@
 //! This system generates a virtual network inside GNS3, configures all nodes and performs the
 //! migration scenario, while monitoring the forwarding state. For simplified usage, check the
 //! function [`perform_migration`].
+//!
+//! To generate the FRR configuration files and the reconfiguration commands without GNS3, use the
+//! [`exporter`] module.
 
 #![deny(missing_docs, missing_debug_implementations)]
 
 pub mod checker;
 pub mod config;
+pub mod exporter;
 pub mod frr_conn;
+pub mod importer;
 pub mod pcap_reader;
 pub mod physical_network;
 pub mod python_conn;
@@ -130,7 +135,7 @@ pub fn perform_migration(
             .into_iter()
             .map(|((router, prefix), paths)| FlowInformation {
                 router: phys_net.router_name(router).to_string(),
-                prefix: prefix.0,
+                prefix: prefix.to_string(),
                 paths: paths
                     .into_iter()
                     .map(|v| {
@@ -159,7 +164,7 @@ pub fn perform_migration(
 #[derive(Debug, Clone, Serialize)]
 struct FlowInformation {
     router: String,
-    prefix: u32,
+    prefix: String,
     paths: Vec<Vec<PathInformation>>,
 }
 
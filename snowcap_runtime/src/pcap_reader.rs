@@ -187,7 +187,7 @@ mod test {
         let n5: RouterId = 5.into();
         let n6: RouterId = 1000006.into();
 
-        let flows = hashmap![(n1, Prefix(0)) => 0, (n1, Prefix(1)) => 1];
+        let flows = hashmap![(n1, Prefix::from(0)) => 0, (n1, Prefix::from(1)) => 1];
 
         let captures = vec![
             (
@@ -241,8 +241,8 @@ mod test {
         assert_eq!(
             result,
             hashmap![
-                (n1, Prefix(0)) => hashmap![Some(vec![n1, n2, n3, n5, n6]) => 8],
-                (n1, Prefix(1)) => hashmap![Some(vec![n1, n2, n4, n5, n6]) => 8]
+                (n1, Prefix::from(0)) => hashmap![Some(vec![n1, n2, n3, n5, n6]) => 8],
+                (n1, Prefix::from(1)) => hashmap![Some(vec![n1, n2, n4, n5, n6]) => 8]
             ]
         );
     }
@@ -256,7 +256,7 @@ mod test {
         let n5: RouterId = 5.into();
         let n6: RouterId = 1000006.into();
 
-        let flows = hashmap![(n1, Prefix(0)) => 0, (n1, Prefix(1)) => 1];
+        let flows = hashmap![(n1, Prefix::from(0)) => 0, (n1, Prefix::from(1)) => 1];
 
         let captures = vec![
             (
@@ -312,11 +312,11 @@ mod test {
         assert_eq!(
             result,
             hashmap![
-                (n1, Prefix(0)) => hashmap![
+                (n1, Prefix::from(0)) => hashmap![
                     Some(vec![n1, n2, n3, n5, n6]) => 6,
                     Some(vec![n1, n2, n4, n5, n6]) => 2
                 ],
-                (n1, Prefix(1)) => hashmap![
+                (n1, Prefix::from(1)) => hashmap![
                     Some(vec![n1, n2, n4, n5, n6]) => 6,
                     None => 2,
                 ]
@@ -20,6 +20,7 @@
 //! This module uses GNS3 to simulate the network, provided by `snowcap`.
 
 use gns3::*;
+use snowcap::netsim::bgp::BgpDecisionConfig;
 use snowcap::netsim::config::*;
 use snowcap::netsim::external_router::ExternalRouter;
 use snowcap::netsim::route_map::RouteMap;
@@ -33,6 +34,7 @@ use crate::python_conn::PythonConnection;
 
 use log::*;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::error::Error;
 use std::fmt;
 use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
@@ -86,16 +88,20 @@ while True:
 ///   - Loopback Address: `(100 + x).0.0.1/32`
 ///   - Address of the interface towards the client: `(100 + x).0.0.2/24`
 ///   - Address of the client: `(100 + x).0.0.2/24`
-/// - **Origin routers**, with router id `x`, advertising prefix `p`
-///   - Loopback Address: `(200 + x).0.0.1/32`
-///   - Address of the interface towards the client: `(200 + x).0.0.2/24`
-///   - Address of the client: `(200 + x).0.0.2/24`
+/// - **Origin routers**, advertising prefix `p` (the prefix itself), using the first block `b` of
+///   (at least) 256 addresses in `p` that is not part of a more-specific prefix
+///   - Loopback Address: `b + 1`
+///   - Address of the interface towards the client: `b + 2`
+///   - Address of the client: `b + 3`
 /// - **Links: Internal --- Internal/External**, with link i 'x', from router `a` to router `b`
 ///   - Address of router a: `10.1.x.1/32`
 ///   - Address of router b: `10.1.x.2/32`
-/// - **Links: External --- Origin**, from external router `x` to origin router `o` with orefix `p`
-///   - Address of origin router o: `(200 + o).1.x.1/32`
-///   - Address of external router b: `(200 + o).1.x.2/32`
+/// - **Links: External --- Origin**, the `k`-th link between an external and an origin router
+///   - Address of origin router: `10.2.0.0 + 4k + 1/30`
+///   - Address of external router: `10.2.0.0 + 4k + 2/30`
+///
+/// Prefixes that overlap with the addresses of the internal or external routers cannot be
+/// originated, and creating the network fails.
 #[derive(Debug, Clone, PartialEq)]
 pub struct PhysicalNetwork {
     server: GNS3Server,
@@ -110,7 +116,7 @@ pub struct PhysicalNetwork {
     /// Vector of all clients in the network
     pub clients: Vec<PhysicalClient>,
     ip_lookup: HashMap<RouterId, [u8; 4]>,
-    prefix_router_lookup: HashMap<Prefix, RouterId>,
+    pub(crate) prefix_router_lookup: HashMap<Prefix, RouterId>,
     reverse_ip_lookup: HashMap<[u8; 4], RouterId>,
     flow_lookup: HashMap<(RouterId, Prefix), u32>,
     frr_template_id: String,
@@ -156,7 +162,7 @@ impl PhysicalNetwork {
             project_id,
             num_explicit_routers,
             num_origin_routers,
-            prefixes: net.get_known_prefixes().iter().cloned().collect(),
+            prefixes: Self::sorted_prefixes(net),
             routers: Vec::with_capacity(num_devices),
             links: Vec::with_capacity(net.links_symmetric().count()),
             clients: Vec::with_capacity(num_devices),
@@ -174,7 +180,7 @@ impl PhysicalNetwork {
         phys_net.create_all_links(net)?;
         phys_net.create_links_to_origin(net)?;
         phys_net.create_clients_on_all_routers();
-        apply_config(&mut phys_net, config);
+        apply_config(&mut phys_net, config)?;
         phys_net.setup_ip_lookup();
         phys_net.prepare_flows();
 
@@ -224,6 +230,13 @@ impl PhysicalNetwork {
                         static_routes: Vec::new(),
                         advertise_route: Some(IpAddr::new("10.0.0.0", 8)),
                         is_internal: true,
+                        bgp_decision: BgpDecisionConfig::default(),
+                        stub_areas: Vec::new(),
+                        cluster_id: None,
+                        bgp_networks: Vec::new(),
+                        redistributions: Vec::new(),
+                        aggregates: Vec::new(),
+                        acls: Vec::new(),
                     });
                 }
                 NetworkDevice::ExternalRouter(r) => {
@@ -246,6 +259,13 @@ impl PhysicalNetwork {
                             8,
                         )),
                         is_internal: false,
+                        bgp_decision: BgpDecisionConfig::default(),
+                        stub_areas: Vec::new(),
+                        cluster_id: None,
+                        bgp_networks: Vec::new(),
+                        redistributions: Vec::new(),
+                        aggregates: Vec::new(),
+                        acls: Vec::new(),
                     });
                 }
                 _ => unreachable!("Could not find device!"),
@@ -262,7 +282,8 @@ impl PhysicalNetwork {
 
     /// Create all routers that originate a specific prefix
     fn create_origin_routers(&mut self, net: &Network) -> Result<(), Box<dyn Error>> {
-        for prefix in self.prefixes.iter() {
+        let loopbacks = Self::origin_loopbacks(net)?;
+        for (prefix_idx, prefix) in self.prefixes.iter().enumerate() {
             // get advertising routers
             let advertising_routers = Self::get_external_routers_with_prefix(net, *prefix);
 
@@ -283,7 +304,7 @@ impl PhysicalNetwork {
             let as_id: AsId = *as_id_iter.next().unwrap();
             assert!(as_id_iter.all(|x| as_id == *x));
 
-            let name = format!("origin{}", prefix.0);
+            let name = format!("origin{}", prefix_idx);
             let gns_node = self.server.create_node(&name, &self.frr_template_id)?;
             let origin_router_id = self.routers.len();
             self.routers.push(PhysicalRouter {
@@ -291,13 +312,23 @@ impl PhysicalNetwork {
                 name,
                 as_id,
                 gns_node,
-                loopback_addr: IpAddr::new(format!("{}.0.0.1", prefix.0 + 200), 24),
+                loopback_addr: loopbacks[prefix].clone(),
                 ifaces: Vec::new(),
                 bgp_sessions: Vec::new(),
                 route_maps: Vec::new(),
                 static_routes: Vec::new(),
-                advertise_route: Some(IpAddr::new(format!("{}.0.0.0", prefix.0 + 200), 8)),
+                advertise_route: Some(IpAddr::new(
+                    prefix.addr().to_string(),
+                    prefix.prefix_len() as u32,
+                )),
                 is_internal: false,
+                bgp_decision: BgpDecisionConfig::default(),
+                stub_areas: Vec::new(),
+                cluster_id: None,
+                bgp_networks: Vec::new(),
+                redistributions: Vec::new(),
+                aggregates: Vec::new(),
+                acls: Vec::new(),
             });
 
             self.prefix_router_lookup.insert(*prefix, (origin_router_id as u32).into());
@@ -343,6 +374,7 @@ impl PhysicalNetwork {
                 gns_interface: gns_iface_a,
                 enabled: false,
                 cost: None,
+                area: OspfArea::BACKBONE,
                 link_id: self.links.len(),
             });
 
@@ -353,6 +385,7 @@ impl PhysicalNetwork {
                 gns_interface: gns_iface_b,
                 enabled: false,
                 cost: None,
+                area: OspfArea::BACKBONE,
                 link_id: self.links.len(),
             });
 
@@ -370,8 +403,12 @@ impl PhysicalNetwork {
 
     // Create the links to the origin routers
     fn create_links_to_origin(&mut self, net: &Network) -> Result<(), Box<dyn Error>> {
+        let mut origin_links = 0;
         for prefix in self.prefixes.iter() {
-            let origin_router_index = self.get_origin_router_index(*prefix);
+            let origin_router_index = match self.prefix_router_lookup.get(prefix) {
+                Some(origin) => origin.index(),
+                None => continue,
+            };
             for ext_router_id in
                 Self::get_external_routers_with_prefix(net, *prefix).iter().map(|r| r.router_id())
             {
@@ -392,10 +429,8 @@ impl PhysicalNetwork {
                     iface_ext,
                 )?;
 
-                let origin_addr =
-                    IpAddr::new(format!("{}.1.{}.1", 200 + prefix.0, ext_router_id.index()), 24);
-                let ext_addr =
-                    IpAddr::new(format!("{}.1.{}.2", 200 + prefix.0, ext_router_id.index()), 24);
+                let (origin_addr, ext_addr) = Self::origin_link_addrs(origin_links)?;
+                origin_links += 1;
 
                 self.links.push(PhysicalLink {
                     gns_link,
@@ -411,6 +446,7 @@ impl PhysicalNetwork {
                     gns_interface: gns_iface_origin,
                     enabled: true,
                     cost: None,
+                    area: OspfArea::BACKBONE,
                     link_id,
                 });
 
@@ -421,6 +457,7 @@ impl PhysicalNetwork {
                     gns_interface: gns_iface_ext,
                     enabled: true,
                     cost: None,
+                    area: OspfArea::BACKBONE,
                     link_id,
                 });
 
@@ -502,6 +539,7 @@ impl PhysicalNetwork {
                 gns_interface: gns_iface,
                 enabled: true,
                 cost: None,
+                area: OspfArea::BACKBONE,
                 link_id,
             });
             info!("Created client: {}, ip: {}", self.clients.last().unwrap().name, client_ip);
@@ -648,18 +686,94 @@ impl PhysicalNetwork {
     }
 
     /// Extract the routers that advertise a specific prefix
-    fn get_external_routers_with_prefix(net: &Network, prefix: Prefix) -> Vec<&ExternalRouter> {
+    pub(crate) fn get_external_routers_with_prefix(
+        net: &Network,
+        prefix: Prefix,
+    ) -> Vec<&ExternalRouter> {
         // extract the as id of this prefix
-        net.get_external_routers()
+        let mut routers: Vec<&ExternalRouter> = net
+            .get_external_routers()
             .into_iter()
             .map(|r| net.get_device(r).unwrap_external())
             .filter(|r| r.advertised_prefixes().contains(&prefix))
-            .collect()
+            .collect();
+        routers.sort_by_key(|r| r.router_id());
+        routers
+    }
+
+    /// Returns all known prefixes of the network in sorted order. The position of a prefix in
+    /// this list determines the name of its origin router.
+    pub(crate) fn sorted_prefixes(net: &Network) -> Vec<Prefix> {
+        let mut prefixes: Vec<Prefix> = net.get_known_prefixes().iter().cloned().collect();
+        prefixes.sort();
+        prefixes
+    }
+
+    /// Returns the loopback address of the origin router of every known prefix. The origin router
+    /// takes the first block of (at least) 256 addresses in its prefix that does not overlap with
+    /// any more-specific prefix, such that the longest prefix match still leads to the origin of
+    /// the most specific prefix. This function returns an error if a prefix cannot be originated
+    /// in the physical network, i.e., if it is an IPv6 prefix, if it contains less than 8
+    /// addresses, or if it overlaps with the addresses of the internal or external routers.
+    pub(crate) fn origin_loopbacks(
+        net: &Network,
+    ) -> Result<HashMap<Prefix, IpAddr>, Box<dyn Error>> {
+        let prefixes = Self::sorted_prefixes(net);
+        let overlaps = |a: &Prefix, b: &Prefix| a.contains(b) || b.contains(a);
+        let reserved: Vec<Prefix> = std::iter::once(Prefix::new([10, 0, 0, 0], 8))
+            .chain(net.get_external_routers().into_iter().filter_map(|r| {
+                Some(Prefix::new([u8::try_from(r.index() + 100).ok()?, 0, 0, 0], 8))
+            }))
+            .collect();
+
+        let mut result = HashMap::new();
+        for prefix in prefixes.iter() {
+            let addr = match prefix.addr() {
+                std::net::IpAddr::V4(addr) if prefix.prefix_len() <= 29 => u32::from(addr),
+                _ => {
+                    return Err(format!("Prefix {} cannot be originated by a router", prefix).into())
+                }
+            };
+            if let Some(r) = reserved.iter().find(|r| overlaps(prefix, r)) {
+                return Err(
+                    format!("Prefix {} overlaps with the addresses in {}", prefix, r).into()
+                );
+            }
+            // search for the first block that is not part of any more-specific prefix
+            let len = prefix.prefix_len().max(24);
+            let block = (0..1u32 << (len - prefix.prefix_len()))
+                .map(|i| Prefix::new(std::net::Ipv4Addr::from(addr + (i << (32 - len))), len))
+                .find(|block| {
+                    !prefixes
+                        .iter()
+                        .any(|p| p != prefix && prefix.contains(p) && overlaps(p, block))
+                })
+                .ok_or_else(|| format!("Prefix {} has no addresses left for its origin", prefix))?;
+            let loopback = match block.addr() {
+                std::net::IpAddr::V4(addr) => std::net::Ipv4Addr::from(u32::from(addr) + 1),
+                std::net::IpAddr::V6(_) => unreachable!(),
+            };
+            result.insert(*prefix, IpAddr::new(loopback.to_string(), len as u32));
+        }
+        Ok(result)
+    }
+
+    /// Returns the addresses of the origin router and of the external router of the `k`-th link
+    /// towards an origin router. Each link uses a block of 4 addresses in `10.2.0.0/16`.
+    pub(crate) fn origin_link_addrs(k: usize) -> Result<(IpAddr, IpAddr), Box<dyn Error>> {
+        if k >= 1 << 14 {
+            return Err("Too many links towards origin routers".into());
+        }
+        let (x, y) = (4 * k / 256, 4 * k % 256);
+        Ok((
+            IpAddr::new(format!("10.2.{}.{}", x, y + 1), 30),
+            IpAddr::new(format!("10.2.{}.{}", x, y + 2), 30),
+        ))
     }
 
     /// Returns the index of an origin router in the structure
     fn get_origin_router_index(&self, prefix: Prefix) -> usize {
-        self.num_explicit_routers + prefix.0 as usize
+        self.prefix_router_lookup[&prefix].index()
     }
 
     /// Wait until the network has converged. We call a network to be converged, if after 10
@@ -820,7 +934,7 @@ impl PhysicalNetwork {
 
     /// Apply a modifier without monitoring the network
     fn apply_modifier(&mut self, modifier: &ConfigModifier) -> Result<(), Box<dyn Error>> {
-        let commands = parse_modifier(self, modifier);
+        let commands = parse_modifier(self, modifier)?;
         for (target, commands) in commands {
             let mut term = FrrConnection::new(self.routers[target.index()].gns_node.port)?;
             term.reconfigure(commands)?;
@@ -1012,6 +1126,20 @@ pub struct PhysicalRouter {
     pub advertise_route: Option<IpAddr>,
     /// Flag, if the router is internal or not
     pub is_internal: bool,
+    /// Configuration of the BGP decision process
+    pub bgp_decision: BgpDecisionConfig,
+    /// Stub areas, configured on every router
+    pub stub_areas: Vec<OspfArea>,
+    /// Cluster ID for route reflection, if it is different from the router ID.
+    pub cluster_id: Option<IpAddr>,
+    /// Prefixes announced by a BGP `network` statement (in addition to `advertise_route`)
+    pub bgp_networks: Vec<IpAddr>,
+    /// Routes redistributed into BGP
+    pub redistributions: Vec<RedistributionInfo>,
+    /// Aggregate routes created by BGP
+    pub aggregates: Vec<AggregateInfo>,
+    /// Access lists applied on the interfaces
+    pub acls: Vec<AclInfo>,
 }
 
 /// BGP Session Information
@@ -1030,6 +1158,41 @@ pub struct BgpSessionInfo {
     pub internal_session: bool,
 }
 
+/// Redistribution of routes into BGP
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedistributionInfo {
+    /// Source of the redistributed routes (either `static` or `connected`)
+    pub source: &'static str,
+    /// Name of the route map through which the routes are redistributed
+    pub route_map: String,
+    /// Entries of the route map, sorted by their order. All entries have the name `route_map`.
+    pub route_maps: Vec<RouteMapInfo>,
+}
+
+/// BGP Aggregate Information
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateInfo {
+    /// Prefix of the aggregate
+    pub addr: IpAddr,
+    /// Flag, if the more-specific prefixes are no longer advertised
+    pub summary_only: bool,
+    /// Flag, if the aggregate carries the AS numbers of the contributing routes as an `AS_SET`
+    pub as_set: bool,
+}
+
+/// Access List Information
+#[derive(Debug, Clone, PartialEq)]
+pub struct AclInfo {
+    /// Name of the access list
+    pub name: String,
+    /// Neighbor at the other end of the interface, on which the access list is applied
+    pub neighbor: RouterId,
+    /// Direction (either `in` or `out`)
+    pub direction: &'static str,
+    /// Entries of the access list, in the form `seq <order> <permit|deny> ip <src> <dst>`
+    pub entries: Vec<String>,
+}
+
 /// Route Map Information
 #[derive(Debug, Clone, PartialEq)]
 pub struct RouteMapInfo {
@@ -1043,64 +1206,237 @@ pub struct RouteMapInfo {
     pub direction: &'static str,
     /// Match statements, allowed by [FRR](https://docs.frrouting.org/en/latest/routemap.html)
     pub match_statements: HashMap<&'static str, String>,
-    /// Set statements, allowed by [FRR](https://docs.frrouting.org/en/latest/routemap.html)
-    pub set_statements: HashMap<&'static str, String>,
+    /// Set statements, allowed by [FRR](https://docs.frrouting.org/en/latest/routemap.html), in
+    /// the order of the actions of the route map. FRR keeps only one statement of each kind, so
+    /// every kind appears at most once, and contains the combined effect of all its actions.
+    pub set_statements: Vec<(&'static str, String)>,
+    /// Global configuration commands, which define the (large) community lists, prefix-lists and
+    /// AS path access-lists used by the match and set statements. These must be configured before
+    /// the route map.
+    pub community_lists: Vec<String>,
 }
 
 impl RouteMapInfo {
-    /// Build a RouteMapInfo from a [`RouteMap`](snowcap::netsim::route_map::RouteMap)
+    /// Build a RouteMapInfo from a [`RouteMap`](snowcap::netsim::route_map::RouteMap). An error is
+    /// returned if the route map cannot be expressed in FRR.
     pub fn from_route_map(
         name: String,
         router_id: RouterId,
         direction: RouteMapDirection,
         map: &RouteMap,
         routers: &[PhysicalRouter],
-    ) -> Self {
-        Self {
-            name,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut community_lists = Vec::new();
+        let unsupported = |what: &str| format!("Route-map {} cannot match on {}", name, what);
+
+        // large communities to match on. FRR matches a community list entry if all communities
+        // of that entry are present. Matching on the empty set is done using a regular expression.
+        let match_lc_list = format!("{}-lc-match", name);
+        let match_lc: Vec<String> = map
+            .conds()
+            .iter()
+            .filter_map(|cond| match cond {
+                RouteMapMatch::LargeCommunity(Some(c)) => Some(c.to_string()),
+                _ => None,
+            })
+            .collect();
+        if !match_lc.is_empty() {
+            community_lists.push(format!(
+                "bgp large-community-list standard {} permit {}",
+                match_lc_list,
+                match_lc.join(" ")
+            ));
+        } else if map.conds().contains(&RouteMapMatch::LargeCommunity(None)) {
+            community_lists
+                .push(format!("bgp large-community-list expanded {} permit ^$", match_lc_list));
+        }
+
+        // communities to match on, in the same way as the large communities. Only exact values
+        // can be expressed in a standard community list.
+        let match_c_list = format!("{}-c-match", name);
+        let mut match_c: Vec<String> = Vec::new();
+        for cond in map.conds() {
+            match cond {
+                RouteMapMatch::Community(Some(RouteMapMatchClause::Equal(c))) => {
+                    match_c.push(c.to_string())
+                }
+                RouteMapMatch::Community(Some(_)) => {
+                    return Err(unsupported("community ranges").into())
+                }
+                _ => {}
+            }
+        }
+        if !match_c.is_empty() {
+            community_lists.push(format!(
+                "bgp community-list standard {} permit {}",
+                match_c_list,
+                match_c.join(" ")
+            ));
+        } else if map.conds().contains(&RouteMapMatch::Community(None)) {
+            community_lists.push(format!("bgp community-list expanded {} permit ^$", match_c_list));
+        }
+
+        // communities to delete. Every community gets its own entry, such that each of them is
+        // deleted individually.
+        let del_c_list = format!("{}-c-del", name);
+        let del_lc_list = format!("{}-lc-del", name);
+        for action in map.actions() {
+            match action {
+                RouteMapSet::DelCommunity(c) => community_lists
+                    .push(format!("bgp community-list standard {} permit {}", del_c_list, c)),
+                RouteMapSet::DelLargeCommunity(c) => community_lists.push(format!(
+                    "bgp large-community-list standard {} permit {}",
+                    del_lc_list, c
+                )),
+                _ => {}
+            }
+        }
+
+        // FRR allows only a single match statement of each kind. Multiple conditions on the
+        // (large) communities are combined in the same list.
+        let mut match_statements: HashMap<&'static str, String> = HashMap::new();
+        for cond in map.conds() {
+            let (key, value) = match cond {
+                RouteMapMatch::Neighbor(neighbor) => {
+                    ("peer", peer_addr(routers, router_id, *neighbor)?.addr)
+                }
+                RouteMapMatch::Prefix(clause) => {
+                    let list = format!("{}-pl", name);
+                    let entry = match clause {
+                        RouteMapMatchPrefix::Equal(p) if p.is_ipv4() => p.to_string(),
+                        RouteMapMatchPrefix::Range(p) if p.is_ipv4() => format!("{} le 32", p),
+                        _ => return Err(unsupported("IPv6 prefixes").into()),
+                    };
+                    community_lists.push(format!("ip prefix-list {} permit {}", list, entry));
+                    ("ip address prefix-list", list)
+                }
+                RouteMapMatch::AsPath(RouteMapMatchAsPath::Contains(as_id)) => {
+                    let list = format!("{}-as", name);
+                    community_lists
+                        .push(format!("bgp as-path access-list {} permit _{}_", list, as_id.0));
+                    ("as-path", list)
+                }
+                RouteMapMatch::AsPath(RouteMapMatchAsPath::Length(_)) => {
+                    return Err(unsupported("the length of the AS path").into())
+                }
+                RouteMapMatch::NextHop(r) => ("ip next-hop address", next_hop_addr(routers, *r)?),
+                RouteMapMatch::Community(_) => ("community", match_c_list.clone()),
+                RouteMapMatch::LargeCommunity(_) => ("large-community", match_lc_list.clone()),
+                RouteMapMatch::Origin(o) => ("origin", o.to_string()),
+            };
+            match match_statements.insert(key, value.clone()) {
+                Some(old) if old != value || !key.ends_with("community") => {
+                    return Err(format!("Route-map {} has multiple `match {}`", name, key).into())
+                }
+                _ => {}
+            }
+        }
+
+        // communities that are set (`false`) or added (`true`), and the prepended AS path
+        let mut communities: Option<(Vec<String>, bool)> = None;
+        let mut large_communities: Option<(Vec<String>, bool)> = None;
+        let mut prepend: Vec<String> = Vec::new();
+        let mut set_statements: Vec<(&'static str, String)> = Vec::new();
+        for action in map.actions() {
+            let (key, value) = match action {
+                RouteMapSet::NextHop(r) => {
+                    ("ip next-hop", routers[r.index()].loopback_addr.addr.clone())
+                }
+                RouteMapSet::LocalPref(Some(lp)) => ("local-preference", format!("{}", lp)),
+                RouteMapSet::LocalPref(None) => ("local-preference", String::from("+0")),
+                RouteMapSet::Med(Some(med)) => ("metric", format!("{}", med)),
+                RouteMapSet::Med(None) => ("metric", String::from("+0")),
+                RouteMapSet::IgpCost(_) => {
+                    return Err(format!("Route-map {} cannot change the IGP cost", name).into())
+                }
+                RouteMapSet::Community(c) => {
+                    communities = Some((c.iter().map(|c| c.to_string()).collect(), false));
+                    ("community", render_communities(&communities))
+                }
+                RouteMapSet::AddCommunity(c) => {
+                    communities.get_or_insert((Vec::new(), true)).0.push(c.to_string());
+                    ("community", render_communities(&communities))
+                }
+                RouteMapSet::DelCommunity(_) => ("comm-list", format!("{} delete", del_c_list)),
+                RouteMapSet::LargeCommunity(c) => {
+                    large_communities = Some((c.iter().map(|c| c.to_string()).collect(), false));
+                    ("large-community", render_communities(&large_communities))
+                }
+                RouteMapSet::AddLargeCommunity(c) => {
+                    large_communities.get_or_insert((Vec::new(), true)).0.push(c.to_string());
+                    ("large-community", render_communities(&large_communities))
+                }
+                RouteMapSet::DelLargeCommunity(_) => {
+                    ("large-comm-list", format!("{} delete", del_lc_list))
+                }
+                RouteMapSet::AsPathPrepend(as_id, count) => {
+                    // later actions prepend in front of the earlier ones
+                    prepend.splice(0..0, vec![as_id.0.to_string(); *count]);
+                    ("as-path prepend", prepend.join(" "))
+                }
+                RouteMapSet::Origin(o) => ("origin", o.to_string()),
+            };
+            match set_statements.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, old)) => *old = value,
+                None => set_statements.push((key, value)),
+            }
+        }
+
+        Ok(Self {
             state: if map.state().is_allow() { "permit" } else { "deny" },
             order: map.order() as u32,
             direction: if direction == RouteMapDirection::Incoming { "in" } else { "out" },
-            match_statements: map
-                .conds()
-                .iter()
-                .map(|cond| match cond {
-                    RouteMapMatch::Neighbor(neighbor_id) => (
-                        "peer",
-                        routers[neighbor_id.index()]
-                            .ifaces
-                            .iter()
-                            .find(|i| i.neighbor == router_id)
-                            .unwrap()
-                            .iface_addr
-                            .to_string(),
-                    ),
-                    RouteMapMatch::Prefix(_) => todo!(),
-                    RouteMapMatch::AsPath(_) => todo!(),
-                    RouteMapMatch::NextHop(_) => todo!(),
-                    RouteMapMatch::Community(_) => todo!(),
-                })
-                .collect(),
-            set_statements: map
-                .actions()
-                .iter()
-                .filter_map(|action| match action {
-                    RouteMapSet::NextHop(r) => {
-                        Some(("ip next-hop", routers[r.index()].loopback_addr.addr.clone()))
-                    }
-                    RouteMapSet::LocalPref(Some(lp)) => {
-                        Some(("local-preference", format!("{}", lp)))
-                    }
-                    RouteMapSet::LocalPref(None) => Some(("local-preference", String::from("+0"))),
-                    RouteMapSet::Med(Some(med)) => Some(("metric", format!("{}", med))),
-                    RouteMapSet::Med(None) => Some(("metric", String::from("+0"))),
-                    RouteMapSet::IgpCost(_) => panic!("IGP const cannot be changed on FRR"),
-                    RouteMapSet::Community(Some(c)) => Some(("community", format!("{}", c))),
-                    RouteMapSet::Community(None) => panic!("Communities are not yet supported"),
-                })
-                .collect(),
-        }
+            match_statements,
+            set_statements,
+            community_lists,
+            name,
+        })
+    }
+}
+
+/// Render the value of `set community` or `set large-community`, given the communities and whether
+/// they are added to the existing ones.
+fn render_communities(communities: &Option<(Vec<String>, bool)>) -> String {
+    match communities {
+        Some((c, false)) if c.is_empty() => String::from("none"),
+        Some((c, false)) => c.join(" "),
+        Some((c, true)) => format!("{} additive", c.join(" ")),
+        None => String::new(),
+    }
+}
+
+/// Returns the address with which `router` peers with `neighbor`. eBGP sessions use the address
+/// of the interface of the neighbor, and iBGP sessions use its loopback address.
+fn peer_addr(
+    routers: &[PhysicalRouter],
+    router: RouterId,
+    neighbor: RouterId,
+) -> Result<IpAddr, Box<dyn Error>> {
+    let r = routers.get(neighbor.index()).ok_or("Neighbor is not a router of the network")?;
+    if r.as_id == routers[router.index()].as_id {
+        Ok(r.loopback_addr.clone())
+    } else {
+        r.ifaces
+            .iter()
+            .find(|i| i.neighbor == router)
+            .map(|i| i.iface_addr.clone())
+            .ok_or_else(|| format!("eBGP neighbor {} is not directly connected", r.name).into())
+    }
+}
+
+/// Returns the address that FRR uses as next hop for routes of the router. Internal routers use
+/// their loopback address, and external routers the address of their interface towards the
+/// network.
+fn next_hop_addr(routers: &[PhysicalRouter], router: RouterId) -> Result<String, Box<dyn Error>> {
+    let r = routers.get(router.index()).ok_or("Next hop is not a router of the network")?;
+    if r.is_internal {
+        return Ok(r.loopback_addr.addr.clone());
     }
+    r.ifaces
+        .iter()
+        .find(|i| routers.get(i.neighbor.index()).map(|n| n.is_internal).unwrap_or(false))
+        .map(|i| i.iface_addr.addr.clone())
+        .ok_or_else(|| format!("External router {} is not connected to the network", r.name).into())
 }
 
 /// Information about the static route
@@ -1127,6 +1463,8 @@ pub struct IfaceInfo {
     pub enabled: bool,
     /// OSPF cost of this link
     pub cost: Option<u32>,
+    /// OSPF area of this link
+    pub area: OspfArea,
     pub(crate) link_id: usize,
 }
 